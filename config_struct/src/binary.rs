@@ -0,0 +1,130 @@
+use crate::{
+    error::GenerationError,
+    options::StructOptions,
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Decode the base64 strings at the paths listed in
+/// `options.binary_paths` into bytes.
+pub fn apply_binary_paths(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if options.binary_paths.is_empty() {
+        return Ok(());
+    }
+
+    apply_to_struct(struct_value, "", options)
+}
+
+fn apply_to_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    for (key, value) in &mut struct_value.fields {
+        let path = paths::join(parent_path, key);
+
+        if options.binary_paths.contains(&path) {
+            decode_strings(value, &path)?;
+        } else {
+            apply_to_value(value, &path, options)?;
+        }
+    }
+    Ok(())
+}
+
+fn apply_to_value(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => apply_to_struct(struct_value, path, options),
+        GenericValue::Option(Some(ref mut value)) => apply_to_value(value, path, options),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                apply_to_value(value, path, options)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn decode_strings(value: &mut GenericValue, path: &str) -> Result<(), GenerationError> {
+    let bytes = match *value {
+        GenericValue::String(ref text) => base64::decode(text)
+            .map_err(|error| GenerationError::InvalidBase64(path.into(), error.to_string()))?,
+        GenericValue::Option(None) => return Ok(()),
+        GenericValue::Option(Some(ref mut value)) => return decode_strings(value, path),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                decode_strings(value, path)?;
+            }
+            return Ok(());
+        }
+        _ => return Err(GenerationError::InvalidBinaryField(path.into())),
+    };
+
+    *value = GenericValue::Bytes(bytes);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options_with_binary(path: &str) -> StructOptions {
+        StructOptions {
+            binary_paths: vec![path.to_owned()],
+            ..StructOptions::default()
+        }
+    }
+
+    fn config(value: GenericValue) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![("icon".to_owned(), value)].into_iter().collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    #[test]
+    fn strings_are_decoded() {
+        let mut config = config(GenericValue::Array(vec![
+            GenericValue::String("iVBORw==".to_owned()),
+            GenericValue::String("".to_owned()),
+        ]));
+        apply_binary_paths(&mut config, &options_with_binary("icon")).unwrap();
+
+        assert_eq!(
+            format!("{:?}", config.fields["icon"]),
+            format!(
+                "{:?}",
+                GenericValue::Array(vec![
+                    GenericValue::Bytes(vec![0x89, 0x50, 0x4e, 0x47]),
+                    GenericValue::Bytes(vec![]),
+                ])
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_errors() {
+        let mut invalid = config(GenericValue::String("not base64!".to_owned()));
+        match apply_binary_paths(&mut invalid, &options_with_binary("icon")) {
+            Err(GenerationError::InvalidBase64(path, _)) => assert_eq!(path, "icon"),
+            other => panic!("Expected base64 error, found {:?}", other),
+        }
+
+        let mut number = config(GenericValue::I64(1));
+        match apply_binary_paths(&mut number, &options_with_binary("icon")) {
+            Err(GenerationError::InvalidBinaryField(path)) => assert_eq!(path, "icon"),
+            other => panic!("Expected binary field error, found {:?}", other),
+        }
+    }
+}
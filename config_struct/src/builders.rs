@@ -0,0 +1,175 @@
+use crate::{
+    generation::{declared_structs, type_string},
+    naming::allow_non_camel_case,
+    options::StructOptions,
+    value::{GenericStruct, GenericValue},
+};
+
+/// Generate a builder type for the root struct and each nested struct.
+///
+/// If `root_const` is given, the root builder gets a `from_const`
+/// constructor which starts from a clone of that const.
+pub fn generate_builders(
+    struct_value: &GenericStruct,
+    options: &StructOptions,
+    root_const: Option<&str>,
+) -> String {
+    let mut buffer = String::new();
+
+    for (index, struct_value) in declared_structs(struct_value).iter().enumerate() {
+        let from_const = if index == 0 { root_const } else { None };
+        generate_builder(&mut buffer, struct_value, options, from_const);
+    }
+
+    buffer
+}
+
+fn generate_builder(
+    output: &mut String,
+    struct_value: &GenericStruct,
+    options: &StructOptions,
+    root_const: Option<&str>,
+) {
+    let struct_name = &struct_value.struct_name;
+    let builder_name = format!("{}Builder", struct_name);
+
+    let mut methods = String::new();
+
+    if let Some(const_name) = root_const {
+        methods.push_str(&format!(
+            "
+    pub fn from_const() -> Self {{
+        Self::new({}.clone())
+    }}
+",
+            const_name
+        ));
+    }
+
+    for (name, value) in struct_value.ordered_fields(options.field_order) {
+        let method_name = format!("with_{}", name.trim_start_matches("r#"));
+        let field_type = type_string(value, options);
+
+        // Types with several obvious sources (like `&'static str` or
+        // `String` for a `Cow<'static, str>`, or a builder for a struct)
+        // are accepted through `Into`. Numbers are not, so that literals
+        // still infer the right type.
+        let (generics, argument_type, conversion) = match *value {
+            GenericValue::String(_)
+            | GenericValue::DateTime(_)
+            | GenericValue::Bytes(_)
+            | GenericValue::Array(_)
+            | GenericValue::EmptyArray(_)
+            | GenericValue::Map(_)
+            | GenericValue::Struct(_) => (
+                format!("<T: Into<{}>>", field_type),
+                "T".to_owned(),
+                ".into()",
+            ),
+            _ => (String::new(), field_type, ""),
+        };
+
+        methods.push_str(&format!(
+            "
+    pub fn {method_name}{generics}(mut self, value: {argument_type}) -> Self {{
+        self.value.{name} = value{conversion};
+        self
+    }}
+",
+            method_name = method_name,
+            generics = generics,
+            argument_type = argument_type,
+            name = name,
+            conversion = conversion,
+        ));
+    }
+
+    output.push_str(&format!(
+        "
+{allow_non_camel_case}pub struct {builder_name} {{
+    value: {struct_name},
+}}
+
+impl {builder_name} {{
+    pub fn new(value: {struct_name}) -> Self {{
+        {builder_name} {{ value }}
+    }}
+{methods}
+    pub fn build(self) -> {struct_name} {{
+        self.value
+    }}
+}}
+
+impl From<{builder_name}> for {struct_name} {{
+    fn from(builder: {builder_name}) -> Self {{
+        builder.build()
+    }}
+}}
+",
+        allow_non_camel_case = allow_non_camel_case(&builder_name),
+        builder_name = builder_name,
+        struct_name = struct_name,
+        methods = methods,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_methods() {
+        let nested = GenericStruct {
+            struct_name: "_Config__server".to_owned(),
+            fields: vec![("port".to_owned(), GenericValue::I64(80))]
+                .into_iter()
+                .collect(),
+            original_keys: Default::default(),
+            doc: None,
+            field_docs: Default::default(),
+            field_cfgs: Default::default(),
+            field_spans: Default::default(),
+            flattened_fields: Default::default(),
+            absent_fields: Default::default(),
+            field_order: Vec::new(),
+        };
+        let config = GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![
+                ("type".to_owned(), GenericValue::String("x".into())),
+                ("server".to_owned(), GenericValue::Struct(nested)),
+            ]
+            .into_iter()
+            .map(|(key, value)| {
+                let key = if key == "type" {
+                    "r#type".to_owned()
+                } else {
+                    key
+                };
+                (key, value)
+            })
+            .collect(),
+            original_keys: Default::default(),
+            doc: None,
+            field_docs: Default::default(),
+            field_cfgs: Default::default(),
+            field_spans: Default::default(),
+            flattened_fields: Default::default(),
+            absent_fields: Default::default(),
+            field_order: Vec::new(),
+        };
+
+        let code = generate_builders(&config, &StructOptions::default(), Some("CONFIG"));
+
+        assert!(code.contains("pub struct ConfigBuilder {"));
+        assert!(code.contains("pub struct _Config__serverBuilder {"));
+        assert!(code.contains("Self::new(CONFIG.clone())"));
+        assert!(code.contains(
+            "pub fn with_type<T: Into<Cow<'static, str>>>(mut self, value: T) -> Self {"
+        ));
+        assert!(code.contains("self.value.r#type = value.into();"));
+        assert!(code.contains("pub fn with_server<T: Into<_Config__server>>(mut self, value: T)"));
+        assert!(code.contains("pub fn with_port(mut self, value: i64) -> Self {"));
+        assert_eq!(code.matches("from_const").count(), 1);
+    }
+}
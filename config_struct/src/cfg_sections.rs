@@ -0,0 +1,231 @@
+//! Conditional compilation of fields, with `StructOptions::cfg_sections`.
+//!
+//! Each field listed there gets a `#[cfg(...)]` attribute in its struct's
+//! declaration and in every value of that struct. Nested structs which are
+//! only reachable through fields with the same predicate are gated too,
+//! along with their impls. A struct which is also used elsewhere, or under
+//! a different predicate, is declared unconditionally, so that the
+//! generated code compiles whichever predicates hold.
+use std::collections::BTreeMap;
+
+use crate::{
+    options::StructOptions,
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Record the predicates from `options.cfg_sections` on the structs
+/// containing those fields.
+///
+/// Paths are made of the original keys, so this can run after fields have
+/// been renamed.
+pub fn apply_cfg_sections(struct_value: &mut GenericStruct, options: &StructOptions) {
+    if options.cfg_sections.is_empty() {
+        return;
+    }
+
+    apply_to_struct(struct_value, "", options);
+}
+
+fn apply_to_struct(struct_value: &mut GenericStruct, parent_path: &str, options: &StructOptions) {
+    let mut field_cfgs = BTreeMap::new();
+
+    for (name, value) in &mut struct_value.fields {
+        let key = struct_value
+            .original_keys
+            .get(name)
+            .map(String::as_str)
+            .unwrap_or(name);
+        let path = paths::join(parent_path, key);
+
+        if let Some(cfg) = options.cfg_sections.get(&path) {
+            field_cfgs.insert(name.clone(), cfg.clone());
+        }
+        apply_to_value(value, &path, options);
+    }
+
+    struct_value.field_cfgs.extend(field_cfgs);
+}
+
+fn apply_to_value(value: &mut GenericValue, path: &str, options: &StructOptions) {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => apply_to_struct(struct_value, path, options),
+        GenericValue::Option(Some(ref mut value)) => apply_to_value(value, path, options),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Tuple(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                apply_to_value(value, path, options);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// The predicates of the nested structs which should only be declared
+/// under a `#[cfg]`, indexed by struct name.
+pub fn struct_cfgs(struct_value: &GenericStruct) -> BTreeMap<String, String> {
+    let mut uses = BTreeMap::new();
+    collect_struct_uses(struct_value, None, &mut uses);
+
+    uses.into_iter()
+        .filter_map(|(name, cfg)| Some((name, cfg?)))
+        .collect()
+}
+
+/// Record the predicate each struct is used under, or `None` if it is
+/// used unconditionally or under more than one predicate.
+fn collect_struct_uses(
+    struct_value: &GenericStruct,
+    cfg: Option<&String>,
+    uses: &mut BTreeMap<String, Option<String>>,
+) {
+    for (name, value) in &struct_value.fields {
+        let cfg = struct_value.field_cfgs.get(name).or(cfg);
+        collect_value_uses(value, cfg, uses);
+    }
+}
+
+fn collect_value_uses(
+    value: &GenericValue,
+    cfg: Option<&String>,
+    uses: &mut BTreeMap<String, Option<String>>,
+) {
+    match *value {
+        GenericValue::Struct(ref struct_value) => {
+            let struct_cfg = uses
+                .entry(struct_value.struct_name.clone())
+                .or_insert_with(|| cfg.cloned());
+            if struct_cfg.as_ref() != cfg {
+                *struct_cfg = None;
+            }
+            collect_struct_uses(struct_value, cfg, uses);
+        }
+        GenericValue::Option(Some(ref value)) => collect_value_uses(value, cfg, uses),
+        GenericValue::Array(ref values)
+        | GenericValue::Tuple(ref values)
+        | GenericValue::Map(GenericMap { ref values, .. }) => {
+            for value in values {
+                collect_value_uses(value, cfg, uses);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// The `#[cfg(...)]` line for an item or field, if it has a predicate.
+pub fn cfg_attribute(cfg: Option<&String>, indentation: usize) -> String {
+    match cfg {
+        Some(cfg) => format!("{:indent$}#[cfg({})]\n", "", cfg, indent = indentation),
+        None => String::new(),
+    }
+}
+
+/// Whether a predicate can be placed in `#[cfg(...)]` without closing it
+/// early: it must be non-empty, with balanced parentheses outside of
+/// string literals, and no brackets.
+pub fn valid_cfg_predicate(cfg: &str) -> bool {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in cfg.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '(' => depth += 1,
+            ')' if depth == 0 => return false,
+            ')' => depth -= 1,
+            '[' | ']' | '{' | '}' | ';' => return false,
+            _ => (),
+        }
+    }
+
+    !cfg.trim().is_empty() && depth == 0 && !in_string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn platform(name: &str) -> GenericValue {
+        GenericValue::Struct(GenericStruct {
+            struct_name: name.to_owned(),
+            fields: vec![("shell".to_owned(), GenericValue::String("sh".to_owned()))]
+                .into_iter()
+                .collect(),
+            ..GenericStruct::default()
+        })
+    }
+
+    fn config(fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    fn cfg_options(paths: &[(&str, &str)]) -> StructOptions {
+        StructOptions {
+            cfg_sections: paths
+                .iter()
+                .map(|&(path, cfg)| (path.to_owned(), cfg.to_owned()))
+                .collect(),
+            ..StructOptions::default()
+        }
+    }
+
+    #[test]
+    fn structs_only_used_under_a_cfg_are_gated() {
+        let mut config = config(vec![
+            ("linux", platform("_Config__linux")),
+            ("windows", platform("_Config__windows")),
+        ]);
+        let options = cfg_options(&[("windows", "windows")]);
+        apply_cfg_sections(&mut config, &options);
+
+        assert_eq!(config.field_cfgs["windows"], "windows");
+        assert!(!config.field_cfgs.contains_key("linux"));
+
+        let cfgs = struct_cfgs(&config);
+        assert_eq!(
+            cfgs.get("_Config__windows").map(String::as_str),
+            Some("windows")
+        );
+        assert!(!cfgs.contains_key("_Config__linux"));
+    }
+
+    #[test]
+    fn shared_structs_are_not_gated() {
+        let mut config = config(vec![
+            ("linux", platform("Platform")),
+            ("windows", platform("Platform")),
+        ]);
+        let options = cfg_options(&[("linux", "unix"), ("windows", "windows")]);
+        apply_cfg_sections(&mut config, &options);
+
+        assert!(struct_cfgs(&config).is_empty());
+    }
+
+    #[test]
+    fn predicates_must_stay_inside_the_attribute() {
+        assert!(valid_cfg_predicate("target_os = \"windows\""));
+        assert!(valid_cfg_predicate("any(unix, target_os = \"a)\")"));
+        assert!(!valid_cfg_predicate(""));
+        assert!(!valid_cfg_predicate("unix)] fn evil() {} #[cfg(unix"));
+        assert!(!valid_cfg_predicate("all(unix"));
+    }
+}
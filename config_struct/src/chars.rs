@@ -0,0 +1,137 @@
+use crate::{
+    error::GenerationError,
+    options::StructOptions,
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Convert the single-character strings at the paths listed in
+/// `options.char_paths` into `char`s.
+pub fn apply_char_paths(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if options.char_paths.is_empty() {
+        return Ok(());
+    }
+
+    apply_to_struct(struct_value, "", options)
+}
+
+fn apply_to_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    for (key, value) in &mut struct_value.fields {
+        let path = paths::join(parent_path, key);
+
+        if options.char_paths.contains(&path) {
+            convert_strings(value, &path)?;
+        } else {
+            apply_to_value(value, &path, options)?;
+        }
+    }
+    Ok(())
+}
+
+fn apply_to_value(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => apply_to_struct(struct_value, path, options),
+        GenericValue::Option(Some(ref mut value)) => apply_to_value(value, path, options),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                apply_to_value(value, path, options)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn convert_strings(value: &mut GenericValue, path: &str) -> Result<(), GenerationError> {
+    let c = match *value {
+        GenericValue::String(ref text) => {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => c,
+                _ => return Err(GenerationError::InvalidChar(path.into(), text.clone())),
+            }
+        }
+        GenericValue::Char(_) | GenericValue::Option(None) => return Ok(()),
+        GenericValue::Option(Some(ref mut value)) => return convert_strings(value, path),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                convert_strings(value, path)?;
+            }
+            return Ok(());
+        }
+        _ => return Err(GenerationError::InvalidCharField(path.into())),
+    };
+
+    *value = GenericValue::Char(c);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options_with_char(path: &str) -> StructOptions {
+        StructOptions {
+            char_paths: vec![path.to_owned()],
+            ..StructOptions::default()
+        }
+    }
+
+    fn config(value: GenericValue) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![("separator".to_owned(), value)].into_iter().collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    #[test]
+    fn single_characters_are_converted() {
+        let mut config = config(GenericValue::Array(vec![
+            GenericValue::String(",".to_owned()),
+            GenericValue::String("é".to_owned()),
+        ]));
+        apply_char_paths(&mut config, &options_with_char("separator")).unwrap();
+
+        assert_eq!(
+            format!("{:?}", config.fields["separator"]),
+            format!(
+                "{:?}",
+                GenericValue::Array(vec![GenericValue::Char(','), GenericValue::Char('é')])
+            )
+        );
+    }
+
+    #[test]
+    fn other_strings_are_errors() {
+        for text in &["", "ab"] {
+            let mut invalid = config(GenericValue::String(text.to_string()));
+            match apply_char_paths(&mut invalid, &options_with_char("separator")) {
+                Err(GenerationError::InvalidChar(path, value)) => {
+                    assert_eq!(path, "separator");
+                    assert_eq!(value, *text);
+                }
+                other => panic!("Expected an invalid char error, found {:?}", other),
+            }
+        }
+
+        let mut number = config(GenericValue::I64(1));
+        match apply_char_paths(&mut number, &options_with_char("separator")) {
+            Err(GenerationError::InvalidCharField(path)) => assert_eq!(path, "separator"),
+            other => panic!("Expected an invalid field error, found {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,498 @@
+use std::collections::HashMap;
+
+use crate::{
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Attach the comments in `docs`, indexed by path, to the fields they
+/// describe. A comment on a struct-valued field also documents the
+/// struct.
+pub fn apply_comments(struct_value: &mut GenericStruct, docs: &HashMap<String, String>) {
+    if docs.is_empty() {
+        return;
+    }
+
+    apply_to_struct(struct_value, "", docs)
+}
+
+fn apply_to_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    docs: &HashMap<String, String>,
+) {
+    for (key, value) in &mut struct_value.fields {
+        let path = paths::join(parent_path, key);
+
+        if let Some(doc) = docs.get(&path) {
+            struct_value.field_docs.insert(key.clone(), doc.clone());
+            if let GenericValue::Struct(ref mut nested) = *value {
+                nested.doc = Some(doc.clone());
+            }
+        }
+        apply_to_value(value, &path, docs);
+    }
+}
+
+fn apply_to_value(value: &mut GenericValue, path: &str, docs: &HashMap<String, String>) {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => apply_to_struct(struct_value, path, docs),
+        GenericValue::Option(Some(ref mut value)) => apply_to_value(value, path, docs),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Tuple(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                apply_to_value(value, path, docs);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Comments found so far, by path. Paths given different comments, for
+/// example by different elements of an array, are ambiguous and map to
+/// `None`.
+#[cfg(any(feature = "toml-parsing", feature = "yaml-parsing"))]
+#[derive(Default)]
+struct Comments {
+    docs: HashMap<String, Option<String>>,
+}
+
+#[cfg(any(feature = "toml-parsing", feature = "yaml-parsing"))]
+impl Comments {
+    fn insert(&mut self, path: String, doc: String) {
+        let entry = self.docs.entry(path).or_insert_with(|| Some(doc.clone()));
+        if entry.as_ref() != Some(&doc) {
+            *entry = None;
+        }
+    }
+
+    fn into_docs(self) -> HashMap<String, String> {
+        self.docs
+            .into_iter()
+            .filter_map(|(path, doc)| doc.map(|doc| (path, doc)))
+            .collect()
+    }
+}
+
+/// Take the comment lines collected since the last key, if any.
+#[cfg(any(feature = "toml-parsing", feature = "yaml-parsing"))]
+fn take_doc(pending: &mut Vec<String>) -> Option<String> {
+    if pending.is_empty() {
+        None
+    } else {
+        Some(std::mem::take(pending).join("\n"))
+    }
+}
+
+/// The text of a comment, without the space conventionally following
+/// the `#`.
+#[cfg(any(feature = "toml-parsing", feature = "yaml-parsing"))]
+fn comment_text(comment: &str) -> String {
+    let comment = comment.trim_end();
+    comment.strip_prefix(' ').unwrap_or(comment).to_owned()
+}
+
+/// Find the comments directly above keys and tables in a TOML file.
+#[cfg(feature = "toml-parsing")]
+pub fn toml_comments(source: &str) -> HashMap<String, String> {
+    let mut comments = Comments::default();
+    let mut pending = Vec::new();
+    let mut table = String::new();
+    let mut value = toml_scanner::ValueScanner::default();
+
+    for line in source.lines() {
+        // Lines continuing a multi-line array or string aren't keys.
+        if value.is_open() {
+            value.scan(line);
+            pending.clear();
+            continue;
+        }
+
+        let line = line.trim();
+        if let Some(comment) = line.strip_prefix('#') {
+            pending.push(comment_text(comment));
+            continue;
+        }
+        let doc = take_doc(&mut pending);
+
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header.strip_prefix('[').unwrap_or(header);
+            table = match toml_scanner::parse_key(header) {
+                Some((keys, rest)) if rest.trim_start().starts_with(']') => keys.join("."),
+                // Keys can't be matched to a table which wasn't understood.
+                _ => "\0".to_owned(),
+            };
+            if let Some(doc) = doc {
+                comments.insert(table.clone(), doc);
+            }
+        } else if let Some((keys, rest)) = toml_scanner::parse_key(line) {
+            if let Some(rest) = rest.trim_start().strip_prefix('=') {
+                if let Some(doc) = doc {
+                    comments.insert(paths::join(&table, &keys.join(".")), doc);
+                }
+                value.scan(rest);
+            }
+        }
+    }
+
+    comments.into_docs()
+}
+
+#[cfg(feature = "toml-parsing")]
+pub mod toml_scanner {
+    #[derive(Clone, Copy, PartialEq)]
+    enum StringKind {
+        Basic,
+        Literal,
+        MultiLineBasic,
+        MultiLineLiteral,
+    }
+
+    /// Tracks whether a TOML value continues onto the following lines.
+    #[derive(Default)]
+    pub struct ValueScanner {
+        depth: usize,
+        string: Option<StringKind>,
+    }
+
+    impl ValueScanner {
+        pub fn is_open(&self) -> bool {
+            self.depth > 0
+                || self.string == Some(StringKind::MultiLineBasic)
+                || self.string == Some(StringKind::MultiLineLiteral)
+        }
+
+        pub fn scan(&mut self, line: &str) {
+            let mut rest = line;
+            while let Some(c) = rest.chars().next() {
+                let mut next = c.len_utf8();
+                match self.string {
+                    Some(StringKind::Basic) | Some(StringKind::MultiLineBasic) if c == '\\' => {
+                        next += rest[1..].chars().next().map_or(0, char::len_utf8);
+                    }
+                    Some(StringKind::Basic) if c == '"' => self.string = None,
+                    Some(StringKind::Literal) if c == '\'' => self.string = None,
+                    Some(StringKind::MultiLineBasic) if rest.starts_with("\"\"\"") => {
+                        self.string = None;
+                        next = 3;
+                    }
+                    Some(StringKind::MultiLineLiteral) if rest.starts_with("'''") => {
+                        self.string = None;
+                        next = 3;
+                    }
+                    Some(_) => (),
+                    None => match c {
+                        '"' if rest.starts_with("\"\"\"") => {
+                            self.string = Some(StringKind::MultiLineBasic);
+                            next = 3;
+                        }
+                        '\'' if rest.starts_with("'''") => {
+                            self.string = Some(StringKind::MultiLineLiteral);
+                            next = 3;
+                        }
+                        '"' => self.string = Some(StringKind::Basic),
+                        '\'' => self.string = Some(StringKind::Literal),
+                        '[' | '{' => self.depth += 1,
+                        ']' | '}' => self.depth = self.depth.saturating_sub(1),
+                        '#' => break,
+                        _ => (),
+                    },
+                }
+                rest = &rest[next.min(rest.len())..];
+            }
+
+            // Single-line strings can't continue onto the next line.
+            if let Some(StringKind::Basic) | Some(StringKind::Literal) = self.string {
+                self.string = None;
+            }
+        }
+    }
+
+    /// Parse a possibly dotted and quoted key from the start of `text`,
+    /// returning its parts and the rest of the text.
+    pub fn parse_key(text: &str) -> Option<(Vec<String>, &str)> {
+        let mut keys = Vec::new();
+        let mut rest = text;
+
+        loop {
+            rest = rest.trim_start();
+            let (key, after) = if let Some(quoted) = rest.strip_prefix('"') {
+                let end = quoted.find('"')?;
+                (quoted[..end].to_owned(), &quoted[end + 1..])
+            } else if let Some(quoted) = rest.strip_prefix('\'') {
+                let end = quoted.find('\'')?;
+                (quoted[..end].to_owned(), &quoted[end + 1..])
+            } else {
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                    .unwrap_or(rest.len());
+                if end == 0 {
+                    return None;
+                }
+                (rest[..end].to_owned(), &rest[end..])
+            };
+            keys.push(key);
+
+            rest = after.trim_start();
+            match rest.strip_prefix('.') {
+                Some(after) => rest = after,
+                None => return Some((keys, rest)),
+            }
+        }
+    }
+}
+
+/// Find the comments directly above keys in a YAML file.
+///
+/// Only block mappings are understood. Keys inside sequences share the
+/// path of the sequence, like the elements of an array.
+#[cfg(feature = "yaml-parsing")]
+pub fn yaml_comments(source: &str) -> HashMap<String, String> {
+    let mut comments = Comments::default();
+    let mut pending = Vec::new();
+    // The indentation and path of each mapping containing the current line.
+    let mut parents: Vec<(usize, String)> = Vec::new();
+    let mut block_scalar_indent = None;
+
+    for line in source.lines() {
+        let content = line.trim_start();
+        let mut indent = line.len() - content.len();
+
+        if let Some(block_indent) = block_scalar_indent {
+            if content.is_empty() || indent > block_indent {
+                continue;
+            }
+            block_scalar_indent = None;
+        }
+
+        if let Some(comment) = content.strip_prefix('#') {
+            pending.push(comment_text(comment));
+            continue;
+        }
+        let doc = take_doc(&mut pending);
+
+        if content.starts_with("---") || content.starts_with("...") {
+            parents.clear();
+            continue;
+        }
+
+        // The keys of a mapping in a sequence are indented past the `-`.
+        let mut content = content.trim_end();
+        while let Some(item) = content.strip_prefix('-') {
+            if !(item.is_empty() || item.starts_with(' ')) {
+                break;
+            }
+            let item = item.trim_start();
+            indent += content.len() - item.len();
+            content = item;
+        }
+
+        let (key, value) = match yaml_key(content) {
+            Some(key_value) => key_value,
+            None => continue,
+        };
+
+        while parents
+            .last()
+            .is_some_and(|&(parent_indent, _)| parent_indent >= indent)
+        {
+            parents.pop();
+        }
+        let parent = parents.last().map_or("", |(_, path)| path.as_str());
+        let path = paths::join(parent, &key);
+
+        if let Some(doc) = doc {
+            comments.insert(path.clone(), doc);
+        }
+
+        let value = value.trim();
+        if value.is_empty() || value.starts_with('#') || yaml_anchor_only(value) {
+            parents.push((indent, path));
+        } else if value.starts_with('|') || value.starts_with('>') {
+            block_scalar_indent = Some(indent);
+        }
+    }
+
+    comments.into_docs()
+}
+
+/// Split a `key: value` line into its key and value.
+#[cfg(feature = "yaml-parsing")]
+fn yaml_key(content: &str) -> Option<(String, &str)> {
+    let (key, rest) = if let Some(quoted) = content.strip_prefix('"') {
+        let end = quoted.find('"')?;
+        (quoted[..end].to_owned(), quoted[end + 1..].trim_start())
+    } else if let Some(quoted) = content.strip_prefix('\'') {
+        let end = quoted.find('\'')?;
+        (quoted[..end].to_owned(), quoted[end + 1..].trim_start())
+    } else {
+        if content.starts_with(|c| "[{&*!|>%@`".contains(c)) {
+            return None;
+        }
+        let end = content
+            .match_indices(':')
+            .map(|(index, _)| index)
+            .find(|&index| {
+                content[index + 1..].is_empty() || content[index + 1..].starts_with(' ')
+            })?;
+        (content[..end].trim_end().to_owned(), &content[end..])
+    };
+
+    let value = rest.strip_prefix(':')?;
+    if !(value.is_empty() || value.starts_with(' ')) {
+        return None;
+    }
+    Some((key, value))
+}
+
+/// Whether a value is only an anchor, so that the mapping it names
+/// follows on the next lines.
+#[cfg(feature = "yaml-parsing")]
+fn yaml_anchor_only(value: &str) -> bool {
+    value.starts_with('&') && !value.contains(' ')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "toml-parsing")]
+    #[test]
+    fn toml_comments_above_keys_and_tables() {
+        let docs = toml_comments(
+            r#"
+# The name.
+# Shown in the title.
+name = "x"
+
+# Detached comments are ignored.
+
+number = 1
+words = [
+    # Not a key.
+    "one",
+]
+
+# The server.
+[server]
+# The port.
+port = 80 # Trailing comments are ignored.
+"#,
+        );
+
+        assert_eq!(docs["name"], "The name.\nShown in the title.");
+        assert_eq!(docs["server"], "The server.");
+        assert_eq!(docs["server.port"], "The port.");
+        assert_eq!(docs.len(), 3);
+    }
+
+    #[cfg(feature = "toml-parsing")]
+    #[test]
+    fn toml_conflicting_comments_are_dropped() {
+        let docs = toml_comments(
+            r#"
+[[servers]]
+# The host.
+host = "a"
+# The first port.
+port = 1
+
+[[servers]]
+# The host.
+host = "b"
+# The second port.
+port = 2
+"#,
+        );
+
+        assert_eq!(docs["servers.host"], "The host.");
+        assert!(!docs.contains_key("servers.port"));
+    }
+
+    #[cfg(feature = "toml-parsing")]
+    #[test]
+    fn toml_multi_line_strings_are_skipped() {
+        let docs = toml_comments(
+            r#"
+text = """
+# Not a comment.
+fake = 1
+"""
+# Real.
+real = 2
+"#,
+        );
+
+        assert_eq!(docs["real"], "Real.");
+        assert_eq!(docs.len(), 1);
+    }
+
+    #[cfg(feature = "yaml-parsing")]
+    #[test]
+    fn yaml_comments_in_nested_mappings() {
+        let docs = yaml_comments(
+            r#"
+---
+# The name.
+name: x
+# The server.
+server:
+    # The port.
+    port: 80
+    text: |
+        # Not a comment.
+        fake: 1
+# Entries.
+entries:
+    # The entry name.
+    - name: a
+      # The entry value.
+      value: 1
+"#,
+        );
+
+        assert_eq!(docs["name"], "The name.");
+        assert_eq!(docs["server"], "The server.");
+        assert_eq!(docs["server.port"], "The port.");
+        assert_eq!(docs["entries"], "Entries.");
+        assert_eq!(docs["entries.name"], "The entry name.");
+        assert_eq!(docs["entries.value"], "The entry value.");
+        assert_eq!(docs.len(), 6);
+    }
+
+    #[test]
+    fn comments_are_applied_to_fields_and_structs() {
+        let nested = GenericStruct {
+            struct_name: "_Config__server".to_owned(),
+            fields: vec![("port".to_owned(), GenericValue::I64(80))]
+                .into_iter()
+                .collect(),
+            ..GenericStruct::default()
+        };
+        let mut config = GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![("server".to_owned(), GenericValue::Struct(nested))]
+                .into_iter()
+                .collect(),
+            ..GenericStruct::default()
+        };
+        let docs = vec![
+            ("server".to_owned(), "The server.".to_owned()),
+            ("server.port".to_owned(), "The port.".to_owned()),
+        ]
+        .into_iter()
+        .collect();
+
+        apply_comments(&mut config, &docs);
+
+        assert_eq!(config.field_docs["server"], "The server.");
+        match config.fields["server"] {
+            GenericValue::Struct(ref nested) => {
+                assert_eq!(nested.doc.as_deref(), Some("The server."));
+                assert_eq!(nested.field_docs["port"], "The port.");
+            }
+            ref other => panic!("Expected struct, found {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,439 @@
+use std::cmp::Ordering;
+
+use crate::{
+    error::GenerationError,
+    int_types::{int_size_name, int_size_of, integer_value, resize_integer},
+    options::{Computation, ComputedField, IntSize, StructOptions},
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Add the fields in `options.computed_fields` to the config, computing
+/// each from the values in it, in the order they're listed.
+///
+/// This runs after `value_transforms`, so that byte sizes and durations
+/// can be summed.
+pub fn apply_computed_fields(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    for field in &options.computed_fields {
+        let value = compute(struct_value, field)?;
+        insert_field(struct_value, &field.path, value)?;
+    }
+    Ok(())
+}
+
+/// The type a number computed from some numbers has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumberType {
+    Integer(IntSize),
+    F32,
+    F64,
+}
+
+fn compute(
+    struct_value: &GenericStruct,
+    field: &ComputedField,
+) -> Result<GenericValue, GenerationError> {
+    let read = |path: &str| read_path(struct_value, &field.path, path);
+
+    match field.computation {
+        Computation::Len(ref path) => Ok(GenericValue::Usize(read(path)?.len())),
+        Computation::Sum(ref path) => sum(&numbers(read(path)?, &field.path)?, &field.path),
+        Computation::Min(ref path) => extreme(
+            &numbers(read(path)?, &field.path)?,
+            Ordering::Less,
+            field,
+            path,
+        ),
+        Computation::Max(ref path) => extreme(
+            &numbers(read(path)?, &field.path)?,
+            Ordering::Greater,
+            field,
+            path,
+        ),
+        Computation::Concat(ref paths, ref separator) => {
+            let mut texts = Vec::new();
+            for path in paths {
+                for (value_path, value) in read(path)? {
+                    let text = value_text(value).ok_or_else(|| {
+                        GenerationError::InvalidComputedValue(
+                            field.path.clone(),
+                            value_path,
+                            "strings, numbers or booleans".into(),
+                        )
+                    })?;
+                    texts.push(text);
+                }
+            }
+            Ok(GenericValue::String(texts.join(separator)))
+        }
+    }
+}
+
+/// The values at `path`, with their paths, numbering the elements of
+/// arrays. An array at the end of the path gives its elements, and nulls
+/// are skipped.
+fn read_path<'a>(
+    struct_value: &'a GenericStruct,
+    field: &str,
+    path: &str,
+) -> Result<Vec<(String, &'a GenericValue)>, GenerationError> {
+    let keys = path.split('.').collect::<Vec<_>>();
+    let mut found = Vec::new();
+    read_struct(struct_value, "", &keys, field, &mut found)?;
+    Ok(found)
+}
+
+fn read_struct<'a>(
+    struct_value: &'a GenericStruct,
+    parent_path: &str,
+    keys: &[&str],
+    field: &str,
+    found: &mut Vec<(String, &'a GenericValue)>,
+) -> Result<(), GenerationError> {
+    let (&key, rest) = keys.split_first().expect("paths have at least one key");
+
+    if key == "*" {
+        for (name, value) in &struct_value.fields {
+            read_value(value, &paths::join(parent_path, name), rest, field, found)?;
+        }
+        return Ok(());
+    }
+
+    let path = paths::join(parent_path, key);
+    match struct_value.fields.get(key) {
+        Some(value) => read_value(value, &path, rest, field, found),
+        None => Err(GenerationError::MissingComputedPath(field.into(), path)),
+    }
+}
+
+fn read_value<'a>(
+    value: &'a GenericValue,
+    path: &str,
+    keys: &[&str],
+    field: &str,
+    found: &mut Vec<(String, &'a GenericValue)>,
+) -> Result<(), GenerationError> {
+    let elements = match *value {
+        GenericValue::Option(None) => return Ok(()),
+        GenericValue::Option(Some(ref value)) => {
+            return read_value(value, path, keys, field, found)
+        }
+        GenericValue::Struct(ref struct_value) if !keys.is_empty() => {
+            return read_struct(struct_value, path, keys, field, found)
+        }
+        GenericValue::Array(ref values) | GenericValue::Tuple(ref values) => values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (paths::join(path, &index.to_string()), value))
+            .collect::<Vec<_>>(),
+        GenericValue::Map(GenericMap {
+            ref keys,
+            ref values,
+        }) => keys
+            .iter()
+            .map(|key| paths::join(path, key))
+            .zip(values)
+            .collect::<Vec<_>>(),
+        _ if keys.is_empty() => {
+            found.push((path.to_owned(), value));
+            return Ok(());
+        }
+        _ => {
+            return Err(GenerationError::MissingComputedPath(
+                field.into(),
+                paths::join(path, keys[0]),
+            ))
+        }
+    };
+
+    match keys.split_first() {
+        None => {
+            for (path, value) in elements {
+                if let Some(value) = present_value(value) {
+                    found.push((path, value));
+                }
+            }
+            Ok(())
+        }
+        Some((&"*", rest)) => {
+            for (path, value) in elements {
+                read_value(value, &path, rest, field, found)?;
+            }
+            Ok(())
+        }
+        Some((key, _)) => Err(GenerationError::MissingComputedPath(
+            field.into(),
+            paths::join(path, key),
+        )),
+    }
+}
+
+/// The value inside any `Some`, or `None` for a null.
+fn present_value(value: &GenericValue) -> Option<&GenericValue> {
+    match *value {
+        GenericValue::Option(ref value) => value.as_deref().and_then(present_value),
+        ref value => Some(value),
+    }
+}
+
+/// Check that the values are all numbers, returning them.
+fn numbers<'a>(
+    values: Vec<(String, &'a GenericValue)>,
+    field: &str,
+) -> Result<Vec<&'a GenericValue>, GenerationError> {
+    values
+        .into_iter()
+        .map(|(path, value)| match *value {
+            GenericValue::F32(_) | GenericValue::F64(_) => Ok(value),
+            _ if integer_value(value).is_some() => Ok(value),
+            _ => Err(GenerationError::InvalidComputedValue(
+                field.into(),
+                path,
+                "numbers".into(),
+            )),
+        })
+        .collect()
+}
+
+/// Integers keep their type if they all have the same one, and are
+/// `i64` otherwise. Floats are `f32` if they're all `f32`.
+fn number_type(numbers: &[&GenericValue]) -> NumberType {
+    let mut int_sizes = numbers.iter().map(|value| int_size_of(value));
+
+    if int_sizes.clone().all(|int_size| int_size.is_some()) {
+        let first = int_sizes.next().flatten().unwrap_or(IntSize::I64);
+        let int_size = if int_sizes.all(|int_size| int_size == Some(first)) {
+            first
+        } else {
+            IntSize::I64
+        };
+        NumberType::Integer(int_size)
+    } else if numbers
+        .iter()
+        .all(|value| matches!(**value, GenericValue::F32(_)))
+    {
+        NumberType::F32
+    } else {
+        NumberType::F64
+    }
+}
+
+fn float_value(value: &GenericValue) -> f64 {
+    match *value {
+        GenericValue::F32(value) => f64::from(value),
+        GenericValue::F64(value) => value,
+        _ => integer_value(value).map_or(0.0, |value| value as f64),
+    }
+}
+
+/// An integer computed for `field`, with the type it should have.
+fn sized_integer(
+    value: Option<i128>,
+    int_size: IntSize,
+    field: &str,
+) -> Result<GenericValue, GenerationError> {
+    value
+        .and_then(|value| resize_integer(&GenericValue::I128(value), int_size))
+        .ok_or_else(|| {
+            GenerationError::IntegerOutOfRange(
+                field.into(),
+                value.map_or_else(|| "more than i128".to_owned(), |value| value.to_string()),
+                int_size_name(int_size).into(),
+            )
+        })
+}
+
+fn float(value: f64, number_type: NumberType) -> GenericValue {
+    match number_type {
+        NumberType::F32 => GenericValue::F32(value as f32),
+        _ => GenericValue::F64(value),
+    }
+}
+
+fn sum(numbers: &[&GenericValue], field: &str) -> Result<GenericValue, GenerationError> {
+    match number_type(numbers) {
+        NumberType::Integer(int_size) => {
+            let total = numbers.iter().try_fold(0i128, |total, value| {
+                total.checked_add(integer_value(value).expect("numbers are checked"))
+            });
+            sized_integer(total, int_size, field)
+        }
+        number_type => Ok(float(
+            numbers.iter().map(|value| float_value(value)).sum(),
+            number_type,
+        )),
+    }
+}
+
+/// The smallest number if `ordering` is `Less`, or the largest if it's
+/// `Greater`.
+fn extreme(
+    numbers: &[&GenericValue],
+    ordering: Ordering,
+    field: &ComputedField,
+    path: &str,
+) -> Result<GenericValue, GenerationError> {
+    if numbers.is_empty() {
+        return Err(GenerationError::EmptyComputedValue(
+            field.path.clone(),
+            path.into(),
+        ));
+    }
+
+    match number_type(numbers) {
+        NumberType::Integer(int_size) => {
+            let integers = numbers
+                .iter()
+                .map(|value| integer_value(value).expect("numbers are checked"));
+            let value = match ordering {
+                Ordering::Less => integers.min(),
+                _ => integers.max(),
+            };
+            sized_integer(value, int_size, &field.path)
+        }
+        number_type => {
+            let floats = numbers.iter().map(|value| float_value(value));
+            let value = match ordering {
+                Ordering::Less => floats.fold(f64::INFINITY, f64::min),
+                _ => floats.fold(f64::NEG_INFINITY, f64::max),
+            };
+            Ok(float(value, number_type))
+        }
+    }
+}
+
+/// The value as it's concatenated, if it's a string, number or boolean.
+fn value_text(value: &GenericValue) -> Option<String> {
+    let text = match *value {
+        GenericValue::String(ref text)
+        | GenericValue::DateTime(ref text)
+        | GenericValue::Version(ref text) => text.clone(),
+        GenericValue::Char(value) => value.to_string(),
+        GenericValue::Bool(value) => value.to_string(),
+        GenericValue::F32(value) => value.to_string(),
+        GenericValue::F64(value) => value.to_string(),
+        GenericValue::U128(value) => value.to_string(),
+        _ => integer_value(value)?.to_string(),
+    };
+    Some(text)
+}
+
+/// Add `value` to the config at `path`, whose parent must be a table
+/// without its key.
+fn insert_field(
+    struct_value: &mut GenericStruct,
+    path: &str,
+    value: GenericValue,
+) -> Result<(), GenerationError> {
+    let invalid = || GenerationError::InvalidComputedField(path.into());
+    let mut keys = path.split('.').collect::<Vec<_>>();
+    let key = keys.pop().expect("paths have at least one key");
+
+    let mut parent = struct_value;
+    for parent_key in keys {
+        parent = match parent.fields.get_mut(parent_key) {
+            Some(GenericValue::Struct(struct_value)) => struct_value,
+            _ => return Err(invalid()),
+        };
+    }
+
+    if key.is_empty() || key == "*" || parent.fields.contains_key(key) {
+        return Err(invalid());
+    }
+    parent.fields.insert(key.to_owned(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn struct_with(fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    fn item(cost: GenericValue) -> GenericValue {
+        GenericValue::Struct(struct_with(vec![("cost", cost)]))
+    }
+
+    fn computed(path: &str, computation: Computation) -> StructOptions {
+        StructOptions {
+            computed_fields: vec![ComputedField::new(path, computation)],
+            ..StructOptions::default()
+        }
+    }
+
+    fn apply(config: &mut GenericStruct, computation: Computation) -> Result<(), GenerationError> {
+        apply_computed_fields(config, &computed("result", computation))
+    }
+
+    #[test]
+    fn results_have_inferred_types() {
+        let mut config = struct_with(vec![(
+            "items",
+            GenericValue::Array(vec![
+                item(GenericValue::U32(3)),
+                item(GenericValue::Option(None)),
+                item(GenericValue::U32(4)),
+            ]),
+        )]);
+        apply(&mut config, Computation::Sum("items.*.cost".into())).unwrap();
+        assert!(matches!(config.fields["result"], GenericValue::U32(7)));
+
+        let mut config = struct_with(vec![(
+            "values",
+            GenericValue::Array(vec![GenericValue::I64(2), GenericValue::F64(0.5)]),
+        )]);
+        apply(&mut config, Computation::Min("values".into())).unwrap();
+        assert!(matches!(config.fields["result"], GenericValue::F64(value) if value == 0.5));
+
+        let mut config = struct_with(vec![("values", GenericValue::Array(vec![]))]);
+        apply(&mut config, Computation::Len("values".into())).unwrap();
+        assert!(matches!(config.fields["result"], GenericValue::Usize(0)));
+    }
+
+    #[test]
+    fn sums_which_overflow_are_errors() {
+        let mut config = struct_with(vec![(
+            "values",
+            GenericValue::Array(vec![GenericValue::U8(200), GenericValue::U8(100)]),
+        )]);
+
+        match apply(&mut config, Computation::Sum("values".into())) {
+            Err(GenerationError::IntegerOutOfRange(path, value, int_type)) => {
+                assert_eq!((&*path, &*value, &*int_type), ("result", "300", "u8"))
+            }
+            other => panic!("Expected an out of range error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn fields_are_added_to_tables() {
+        let mut config = struct_with(vec![
+            ("name", GenericValue::String("app".into())),
+            ("stats", GenericValue::Struct(struct_with(vec![]))),
+        ]);
+
+        insert_field(&mut config, "stats.total", GenericValue::I64(1)).unwrap();
+        assert!(insert_field(&mut config, "stats.total", GenericValue::I64(1)).is_err());
+        assert!(insert_field(&mut config, "name.total", GenericValue::I64(1)).is_err());
+        assert!(insert_field(&mut config, "missing.total", GenericValue::I64(1)).is_err());
+
+        match config.fields["stats"] {
+            GenericValue::Struct(ref stats) => {
+                assert!(matches!(stats.fields["total"], GenericValue::I64(1)))
+            }
+            ref other => panic!("Expected a table, found {:?}", other),
+        }
+    }
+}
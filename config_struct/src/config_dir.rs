@@ -0,0 +1,362 @@
+//! Generating one struct for a directory of config files with the same
+//! shape, such as one file per environment, with
+//! [`create_config_from_dir`](fn.create_config_from_dir.html).
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    conversions,
+    descriptions::{self, GeneratedModule},
+    diffs, display,
+    error::{file_error, Error, GenerationError, OptionsError},
+    format::Format,
+    generation, getters, key_constants, lookups,
+    options::{ArrayStyle, DisplayStyle, FieldAccess, MissingFields, RootMode, StructOptions},
+    owned, root_arrays, schema,
+    statics::RootValue,
+    unification::{struct_template, unwrap_option_mut},
+    validation,
+    value::{GenericStruct, GenericValue},
+};
+
+/// Options for generating a config struct from every config file in a
+/// directory.
+#[derive(Debug, Clone)]
+pub struct DirOptions {
+    /// The options for the generated struct, which are shared by every
+    /// file.
+    ///
+    /// The `const_name` is ignored, since each file's const is named
+    /// after its file stem. Options which only make sense for a single
+    /// const, like `generate_load_fns` or `impl_default`, can't be used.
+    ///
+    /// Defaults to `StructOptions::default()`.
+    pub struct_options: StructOptions,
+
+    /// The name of the generated function which looks up a config by
+    /// its file stem, like `for_env("dev")`.
+    ///
+    /// Defaults to `"for_env"`.
+    pub lookup_fn: String,
+}
+
+impl Default for DirOptions {
+    fn default() -> Self {
+        DirOptions {
+            struct_options: StructOptions::default(),
+            lookup_fn: "for_env".to_owned(),
+        }
+    }
+}
+
+impl DirOptions {
+    pub(crate) fn validate(&self) -> Result<(), GenerationError> {
+        let options = &self.struct_options;
+        options.validate()?;
+
+        let conflicting_option = if !options.generate_const {
+            Some("generate_const")
+        } else if options.generate_load_fns {
+            Some("generate_load_fns")
+        } else if options.impl_default {
+            Some("impl_default")
+        } else if options.generate_builder {
+            Some("generate_builder")
+        } else if options.serde_missing_fields == MissingFields::UseConst {
+            Some("serde_missing_fields")
+        } else if options.env_overrides.is_some() {
+            Some("env_overrides")
+        } else if options.max_array_size != 0 {
+            Some("max_array_size")
+        } else if options.array_style != ArrayStyle::AlwaysCow {
+            Some("array_style")
+        } else if options.root_mode != RootMode::Wrapper {
+            Some("root_mode")
+        } else if options.impl_display == DisplayStyle::SourceFormat {
+            Some("impl_display")
+        } else if options.generate_field_consts {
+            Some("generate_field_consts")
+        } else {
+            None
+        };
+        if let Some(option) = conflicting_option {
+            return Err(OptionsError::ConfigDirWith(option).into());
+        }
+
+        if !validation::valid_identifier(&self.lookup_fn) {
+            return Err(GenerationError::InvalidLookupFn(self.lookup_fn.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+/// A config file from the directory, after every pass.
+pub struct DirConfig {
+    pub path: PathBuf,
+    pub options: StructOptions,
+    pub config: GenericStruct,
+}
+
+/// The config files in a directory, with the formats given by their
+/// extensions, sorted by name. Files with other extensions are skipped.
+pub fn config_files(dir: &Path) -> Result<Vec<(PathBuf, Format)>, Error> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(file_error(dir))? {
+        let path = entry.map_err(file_error(dir))?.path();
+        if !path.is_file() {
+            continue;
+        }
+        if let Ok(format) = Format::from_filename(&path) {
+            files.push((path, format));
+        }
+    }
+
+    if files.is_empty() {
+        return Err(GenerationError::NoInputFiles.into());
+    }
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(files)
+}
+
+/// The shared options, with the const named after the file's stem: for
+/// example, `staging.toml` becomes `STAGING`.
+pub fn file_options(path: &Path, options: &DirOptions) -> StructOptions {
+    let const_name = file_stem(path)
+        .to_uppercase()
+        .replace(|c: char| !c.is_alphanumeric(), "_");
+
+    StructOptions {
+        const_name: Some(const_name),
+        ..options.struct_options.clone()
+    }
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Make fields which are null in some configs, and have a value in
+/// others, optional in all of them, as in an array of structs.
+pub fn unify_nulls(configs: &mut [DirConfig]) {
+    let mut structs = configs
+        .iter_mut()
+        .map(|config| &mut config.config)
+        .collect::<Vec<_>>();
+    unify_struct_nulls(&mut structs);
+}
+
+fn unify_struct_nulls(structs: &mut [&mut GenericStruct]) {
+    let keys = match structs.first() {
+        Some(first) => first.fields.keys().cloned().collect::<Vec<_>>(),
+        None => return,
+    };
+
+    let count = structs.len();
+    for key in keys {
+        // Missing keys are left for the shape check to report.
+        let mut values = structs
+            .iter_mut()
+            .filter_map(|struct_value| struct_value.fields.get_mut(&key))
+            .collect::<Vec<_>>();
+        if values.len() != count {
+            continue;
+        }
+
+        let has_null = values
+            .iter()
+            .any(|value| matches!(**value, GenericValue::Option(None)));
+        if has_null {
+            for value in &mut values {
+                if !matches!(**value, GenericValue::Option(_)) {
+                    let inner = std::mem::replace(&mut **value, GenericValue::Unit);
+                    **value = GenericValue::Option(Some(Box::new(inner)));
+                }
+            }
+        }
+
+        let mut nested = values
+            .into_iter()
+            .filter_map(|value| match *unwrap_option_mut(value) {
+                GenericValue::Struct(ref mut struct_value) => Some(struct_value),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if nested.len() == count {
+            unify_struct_nulls(&mut nested);
+        }
+    }
+}
+
+/// Check that every config has the same shape as the first, and that
+/// no two would have consts of the same name.
+pub fn check_configs(configs: &[DirConfig]) -> Result<(), GenerationError> {
+    let mut const_names = BTreeSet::new();
+    for config in configs {
+        let const_name = config.options.real_const_name();
+        if !const_names.insert(const_name.clone()) {
+            return Err(GenerationError::DuplicateModuleName(const_name));
+        }
+    }
+
+    let (reference, others) = configs.split_first().ok_or(GenerationError::NoInputFiles)?;
+    for candidate in others {
+        let mismatches =
+            schema::compare_structs(&candidate.config, &reference.config, &candidate.options);
+        if !mismatches.is_empty() {
+            let details = mismatches
+                .iter()
+                .map(|mismatch| format!("\n  {}", mismatch))
+                .collect::<String>();
+            return Err(GenerationError::MismatchedDirConfig(
+                candidate.path.display().to_string(),
+                reference.path.display().to_string(),
+                details,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Generate the struct declarations, one root value per config, and the
+/// lookup function.
+pub fn generate_dir_items(
+    configs: &[DirConfig],
+    options: &DirOptions,
+) -> Result<GeneratedModule, GenerationError> {
+    let struct_options = &options.struct_options;
+    let mut code = String::new();
+
+    // A value missing from one file may be present in another, so the
+    // declarations are made from all of them.
+    let roots = configs
+        .iter()
+        .map(|config| &config.config)
+        .collect::<Vec<_>>();
+    let template = struct_template(&roots);
+    let root_array = root_arrays::root_elements(&template).is_some();
+    let declared_root = match root_arrays::root_elements(&template) {
+        Some(elements) => root_arrays::element_template(elements, struct_options),
+        None => template.clone(),
+    };
+
+    code.push_str(&generation::generate_structs(
+        &declared_root,
+        struct_options,
+    ));
+
+    let mut lookup_arms = Vec::new();
+    for config in configs {
+        let root_value = RootValue::choose(&config.config, &config.options)?;
+        code.push_str(&root_value.declaration(&config.config, &config.options));
+        code.push('\n');
+        lookup_arms.push(format!(
+            "        {:?} => {},",
+            file_stem(&config.path),
+            root_value.reference(&config.options)
+        ));
+    }
+
+    let borrowed_type = if root_array {
+        format!("[{}]", struct_options.struct_name)
+    } else {
+        struct_options.struct_name.clone()
+    };
+    code.push_str(&format!(
+        "/// The config from the file with the given stem, if there is one.
+pub fn {lookup_fn}(name: &str) -> Option<&'static {borrowed_type}> {{
+    let config: &'static {borrowed_type} = match name {{
+{arms}
+        _ => return None,
+    }};
+    Some(config)
+}}
+
+",
+        lookup_fn = options.lookup_fn,
+        borrowed_type = borrowed_type,
+        arms = lookup_arms.join("\n"),
+    ));
+
+    if struct_options.field_access == FieldAccess::Getters {
+        code.push_str(&getters::generate_getters(&declared_root, struct_options)?);
+    }
+
+    if struct_options.generate_diff {
+        code.push_str(&diffs::generate_diffs(&declared_root, struct_options));
+    }
+
+    if struct_options.generate_owned_variant {
+        code.push_str(&owned::generate_owned_variant(
+            &declared_root,
+            struct_options,
+        ));
+    }
+
+    if struct_options.generate_get_by_path {
+        code.push_str(&lookups::generate_lookups(&declared_root, struct_options));
+    }
+
+    if struct_options.generate_key_constants {
+        code.push_str(&key_constants::generate_key_constants(&declared_root));
+    }
+
+    if let Some(ref target) = struct_options.impl_into {
+        code.push_str(&conversions::generate_into_impl(
+            &declared_root,
+            target,
+            struct_options,
+        ));
+    }
+
+    // Files in a directory may not share a format, so only the pretty
+    // `Debug` output is allowed.
+    code.push_str(&display::generate_display(
+        &declared_root.struct_name,
+        None,
+        struct_options,
+    )?);
+
+    let types = descriptions::describe_types(&declared_root, struct_options);
+    Ok(GeneratedModule { code, types })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consts_are_named_after_file_stems() {
+        let options = DirOptions::default();
+
+        let staging = file_options(Path::new("config/staging.toml"), &options);
+        assert_eq!(staging.real_const_name(), "STAGING");
+
+        let eu_west = file_options(Path::new("config/eu-west.json"), &options);
+        assert_eq!(eu_west.real_const_name(), "EU_WEST");
+    }
+
+    #[test]
+    fn single_const_options_are_rejected() {
+        let options = DirOptions {
+            struct_options: StructOptions {
+                impl_default: true,
+                ..StructOptions::default()
+            },
+            ..DirOptions::default()
+        };
+
+        match options.validate() {
+            Err(GenerationError::StructOptions(OptionsError::ConfigDirWith(option))) => {
+                assert_eq!(option, "impl_default")
+            }
+            other => panic!("Expected an options error, found {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{
+    options::{IntoConversion, StructOptions},
+    value::GenericStruct,
+};
+
+/// Generate a `From` impl converting the root struct into the `impl_into`
+/// target, by converting each field into the target's field of the same
+/// name.
+pub fn generate_into_impl(
+    struct_value: &GenericStruct,
+    target: &str,
+    options: &StructOptions,
+) -> String {
+    let fields = struct_value
+        .ordered_fields(options.field_order)
+        .into_iter()
+        .map(|(name, _)| {
+            format!(
+                "            {}: {},\n",
+                name,
+                conversion(&format!("value.{}", name), name, options)
+            )
+        })
+        .collect::<String>();
+
+    // Fields which already have the target type still go through
+    // `try_into`, so that every field is converted the same way.
+    let allow = match options.into_conversion {
+        IntoConversion::TryInto => "#[allow(clippy::unnecessary_fallible_conversions)]\n",
+        IntoConversion::Move | IntoConversion::Into => "",
+    };
+
+    format!(
+        "{allow}impl From<{struct_name}> for {target} {{
+    fn from(value: {struct_name}) -> Self {{
+        {target} {{
+{fields}        }}
+    }}
+}}
+
+",
+        allow = allow,
+        struct_name = struct_value.struct_name,
+        target = target,
+        fields = fields
+    )
+}
+
+fn conversion(field: &str, name: &str, options: &StructOptions) -> String {
+    match options.into_conversion {
+        IntoConversion::Move => field.to_owned(),
+        IntoConversion::Into => format!("{}.into()", field),
+        IntoConversion::TryInto => format!(
+            "::std::convert::TryInto::try_into({}).expect({:?})",
+            field,
+            format!("Failed to convert `{}`", name.trim_start_matches("r#"))
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::GenericValue;
+
+    fn config() -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![
+                ("name".to_owned(), GenericValue::String("x".to_owned())),
+                ("r#type".to_owned(), GenericValue::I64(1)),
+            ]
+            .into_iter()
+            .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    #[test]
+    fn fields_are_converted_with_into() {
+        let code = generate_into_impl(&config(), "crate::AppSettings", &StructOptions::default());
+
+        assert!(code.contains("impl From<Config> for crate::AppSettings {"));
+        assert!(code.contains("    fn from(value: Config) -> Self {\n        crate::AppSettings {"));
+        assert!(code.contains("            name: value.name.into(),\n"));
+        assert!(code.contains("            r#type: value.r#type.into(),\n"));
+    }
+
+    #[test]
+    fn conversions_follow_the_options() {
+        let options = StructOptions {
+            into_conversion: IntoConversion::TryInto,
+            ..StructOptions::default()
+        };
+        let code = generate_into_impl(&config(), "AppSettings", &options);
+        assert!(code.contains(
+            r#"r#type: ::std::convert::TryInto::try_into(value.r#type).expect("Failed to convert `type`"),"#
+        ));
+
+        let options = StructOptions {
+            into_conversion: IntoConversion::Move,
+            ..StructOptions::default()
+        };
+        let code = generate_into_impl(&config(), "AppSettings", &options);
+        assert!(code.contains("            name: value.name,\n"));
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{
+    generation::{string_type, string_value},
+    options::{DateTimeType, StructOptions},
+};
+
+/// The kinds of date and time value which can appear in a config, as
+/// described by RFC 3339 and TOML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateTimeKind {
+    OffsetDateTime,
+    LocalDateTime,
+    LocalDate,
+    LocalTime,
+}
+
+/// Determine the kind of a date or time from its text, like
+/// `2023-05-01T10:00:00Z` or `07:32:00`.
+pub fn date_time_kind(text: &str) -> DateTimeKind {
+    let has_date = text.len() >= 10 && text.as_bytes()[4] == b'-';
+    let has_time = text.contains(':');
+
+    match (has_date, has_time) {
+        (true, true) => {
+            let time = &text[10..];
+            if time.ends_with(['Z', 'z']) || time.contains(['+', '-']) {
+                DateTimeKind::OffsetDateTime
+            } else {
+                DateTimeKind::LocalDateTime
+            }
+        }
+        (true, false) => DateTimeKind::LocalDate,
+        _ => DateTimeKind::LocalTime,
+    }
+}
+
+#[cfg_attr(not(feature = "chrono"), allow(unused_variables))]
+pub fn date_time_type_string(text: &str, options: &StructOptions) -> String {
+    match options.date_time_type {
+        DateTimeType::String => string_type(options),
+        #[cfg(feature = "chrono")]
+        DateTimeType::ChronoDateTime => {
+            chrono_support::type_string(date_time_kind(text)).to_owned()
+        }
+    }
+}
+
+pub fn date_time_value_string(text: &str, options: &StructOptions) -> String {
+    match options.date_time_type {
+        DateTimeType::String => string_value(text, options),
+        #[cfg(feature = "chrono")]
+        DateTimeType::ChronoDateTime => chrono_support::value_string(text)
+            .expect("date and time values should be checked during validation"),
+    }
+}
+
+/// Whether the date or time can be represented by the chosen
+/// `date_time_type`.
+#[cfg_attr(not(feature = "chrono"), allow(unused_variables))]
+pub fn valid_date_time(text: &str, options: &StructOptions) -> bool {
+    match options.date_time_type {
+        DateTimeType::String => true,
+        #[cfg(feature = "chrono")]
+        DateTimeType::ChronoDateTime => chrono_support::value_string(text).is_some(),
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_support {
+    use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+
+    use super::{date_time_kind, DateTimeKind};
+
+    const TIME_FORMAT: &str = "%H:%M:%S%.f";
+
+    pub fn type_string(kind: DateTimeKind) -> &'static str {
+        match kind {
+            DateTimeKind::OffsetDateTime => "chrono::DateTime<chrono::Utc>",
+            DateTimeKind::LocalDateTime => "chrono::NaiveDateTime",
+            DateTimeKind::LocalDate => "chrono::NaiveDate",
+            DateTimeKind::LocalTime => "chrono::NaiveTime",
+        }
+    }
+
+    /// A const expression constructing the date or time, or `None` if
+    /// it can't be parsed.
+    pub fn value_string(text: &str) -> Option<String> {
+        // TOML allows a space in place of the `T` separator.
+        let text = match text.get(10..11) {
+            Some(" ") => format!("{}T{}", &text[..10], &text[11..]),
+            _ => text.to_owned(),
+        };
+
+        let value = match date_time_kind(&text) {
+            DateTimeKind::OffsetDateTime => {
+                let value = DateTime::parse_from_rfc3339(&text).ok()?;
+                format!(
+                    "chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset({}, chrono::Utc)",
+                    naive_date_time_string(value.with_timezone(&Utc).naive_utc())
+                )
+            }
+            DateTimeKind::LocalDateTime => {
+                let format = format!("%Y-%m-%dT{}", TIME_FORMAT);
+                naive_date_time_string(NaiveDateTime::parse_from_str(&text, &format).ok()?)
+            }
+            DateTimeKind::LocalDate => {
+                naive_date_string(NaiveDate::parse_from_str(&text, "%Y-%m-%d").ok()?)
+            }
+            DateTimeKind::LocalTime => {
+                naive_time_string(NaiveTime::parse_from_str(&text, TIME_FORMAT).ok()?)
+            }
+        };
+        Some(value)
+    }
+
+    fn naive_date_time_string(value: NaiveDateTime) -> String {
+        format!(
+            "chrono::NaiveDateTime::new({}, {})",
+            naive_date_string(value.date()),
+            naive_time_string(value.time())
+        )
+    }
+
+    fn naive_date_string(value: NaiveDate) -> String {
+        format!(
+            "match chrono::NaiveDate::from_ymd_opt({}, {}, {}) {{ Some(date) => date, None => panic!() }}",
+            value.year(),
+            value.month(),
+            value.day()
+        )
+    }
+
+    fn naive_time_string(value: NaiveTime) -> String {
+        format!(
+            "match chrono::NaiveTime::from_hms_nano_opt({}, {}, {}, {}) {{ Some(time) => time, None => panic!() }}",
+            value.hour(),
+            value.minute(),
+            value.second(),
+            value.nanosecond()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_are_detected() {
+        assert_eq!(
+            date_time_kind("2023-05-01T10:00:00Z"),
+            DateTimeKind::OffsetDateTime
+        );
+        assert_eq!(
+            date_time_kind("2023-05-01T10:00:00.5-07:00"),
+            DateTimeKind::OffsetDateTime
+        );
+        assert_eq!(
+            date_time_kind("2023-05-01T10:00:00"),
+            DateTimeKind::LocalDateTime
+        );
+        assert_eq!(date_time_kind("2023-05-01"), DateTimeKind::LocalDate);
+        assert_eq!(date_time_kind("10:00:00"), DateTimeKind::LocalTime);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn offsets_are_converted_to_utc() {
+        let value = chrono_support::value_string("2023-05-01T10:00:00+02:00").unwrap();
+
+        assert!(value.contains("from_ymd_opt(2023, 5, 1)"));
+        assert!(value.contains("from_hms_nano_opt(8, 0, 0, 0)"));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn invalid_dates_are_rejected() {
+        let options = StructOptions {
+            date_time_type: DateTimeType::ChronoDateTime,
+            ..StructOptions::default()
+        };
+
+        assert!(valid_date_time("2023-05-01", &options));
+        assert!(!valid_date_time("2023-02-30", &options));
+    }
+}
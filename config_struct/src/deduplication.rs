@@ -0,0 +1,265 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    generation::type_string,
+    options::StructOptions,
+    unification::{array_structs, array_structs_mut, struct_template, unwrap_option_mut},
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Give structurally identical nested structs the same name, so that only
+/// one struct is declared for them, if `options.deduplicate_structs` is
+/// set.
+///
+/// Two structs are identical if they have the same field names, keys and
+/// types. The shared name is that of the first such struct found.
+pub fn deduplicate_structs(struct_value: &mut GenericStruct, options: &StructOptions) {
+    if !options.deduplicate_structs {
+        return;
+    }
+
+    let mut names_by_shape = HashMap::new();
+    for value in struct_value.fields.values_mut() {
+        deduplicate_value(value, options, &mut names_by_shape);
+    }
+}
+
+fn deduplicate_value(
+    value: &mut GenericValue,
+    options: &StructOptions,
+    names_by_shape: &mut HashMap<String, String>,
+) {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => {
+            deduplicate_structs_in_array(vec![struct_value], options, names_by_shape)
+        }
+        GenericValue::Option(Some(ref mut value)) => {
+            deduplicate_value(value, options, names_by_shape)
+        }
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            let structs = array_structs_mut(values);
+            if !structs.is_empty() {
+                deduplicate_structs_in_array(structs, options, names_by_shape);
+            } else {
+                for value in values {
+                    deduplicate_value(value, options, names_by_shape);
+                }
+            }
+        }
+        GenericValue::Tuple(ref mut values) => {
+            for value in values {
+                deduplicate_value(value, options, names_by_shape);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Rename a group of structs which must share a type, such as the
+/// elements of an array.
+fn deduplicate_structs_in_array(
+    mut structs: Vec<&mut GenericStruct>,
+    options: &StructOptions,
+    names_by_shape: &mut HashMap<String, String>,
+) {
+    // Nested structs are renamed first, so that the types of these
+    // structs' fields use their shared names. Nested structs under the
+    // same key must also share a type.
+    let keys = structs
+        .iter()
+        .flat_map(|struct_value| struct_value.fields.keys().cloned())
+        .collect::<BTreeSet<String>>();
+
+    for key in keys {
+        let mut nested_structs = Vec::new();
+        let mut other_values = Vec::new();
+
+        for struct_value in &mut structs {
+            if let Some(value) = struct_value.fields.get_mut(&key) {
+                let value = unwrap_option_mut(value);
+                if array_structs(std::slice::from_ref(value)).is_empty() {
+                    other_values.push(value);
+                } else {
+                    nested_structs.extend(array_structs_mut(std::slice::from_mut(value)));
+                }
+            }
+        }
+
+        if !nested_structs.is_empty() {
+            deduplicate_structs_in_array(nested_structs, options, names_by_shape);
+        }
+        for value in other_values {
+            deduplicate_value(value, options, names_by_shape);
+        }
+    }
+
+    let shape = {
+        let structs = structs.iter().map(|value| &**value).collect::<Vec<_>>();
+        struct_shape(&struct_template(&structs), options)
+    };
+    let shared_name = names_by_shape
+        .entry(shape)
+        .or_insert_with(|| structs[0].struct_name.clone())
+        .clone();
+
+    for struct_value in structs {
+        struct_value.struct_name = shared_name.clone();
+    }
+}
+
+fn struct_shape(struct_value: &GenericStruct, options: &StructOptions) -> String {
+    struct_value
+        .fields
+        .iter()
+        .map(|(name, value)| {
+            let cfg = match struct_value.field_cfgs.get(name) {
+                Some(cfg) => format!("#[cfg({})] ", cfg),
+                None => String::new(),
+            };
+            let flatten = if struct_value.flattened_fields.contains(name) {
+                "#[serde(flatten)] "
+            } else {
+                ""
+            };
+            let absent = if struct_value.absent_fields.contains(name) {
+                "#[serde(skip_serializing_if)] "
+            } else {
+                ""
+            };
+            format!(
+                "{}{}{}{}({}): {}",
+                cfg,
+                flatten,
+                absent,
+                name,
+                struct_value.original_key(name),
+                type_string(value, options)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dedup_options() -> StructOptions {
+        StructOptions {
+            deduplicate_structs: true,
+            ..StructOptions::default()
+        }
+    }
+
+    fn make_struct(struct_name: &str, fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: struct_name.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            original_keys: Default::default(),
+            doc: None,
+            field_docs: Default::default(),
+            field_cfgs: Default::default(),
+            field_spans: Default::default(),
+            flattened_fields: Default::default(),
+            absent_fields: Default::default(),
+            field_order: Vec::new(),
+        }
+    }
+
+    fn server(struct_name: &str, port: i64) -> GenericValue {
+        GenericValue::Struct(make_struct(
+            struct_name,
+            vec![
+                ("host", GenericValue::String("localhost".into())),
+                ("port", GenericValue::I64(port)),
+            ],
+        ))
+    }
+
+    fn struct_name(value: &GenericValue) -> &str {
+        match *value {
+            GenericValue::Struct(ref value) => &value.struct_name,
+            ref other => panic!("Expected struct, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn identical_structs_share_a_name() {
+        let mut config = make_struct(
+            "Config",
+            vec![
+                ("primary", server("_Config__primary", 1)),
+                ("secondary", server("_Config__secondary", 2)),
+            ],
+        );
+
+        deduplicate_structs(&mut config, &dedup_options());
+
+        assert_eq!(struct_name(&config.fields["primary"]), "_Config__primary");
+        assert_eq!(struct_name(&config.fields["secondary"]), "_Config__primary");
+    }
+
+    #[test]
+    fn different_structs_keep_their_names() {
+        let mut config = make_struct(
+            "Config",
+            vec![
+                ("primary", server("_Config__primary", 1)),
+                (
+                    "secondary",
+                    GenericValue::Struct(make_struct(
+                        "_Config__secondary",
+                        vec![("host", GenericValue::String("localhost".into()))],
+                    )),
+                ),
+            ],
+        );
+
+        deduplicate_structs(&mut config, &dedup_options());
+
+        assert_eq!(
+            struct_name(&config.fields["secondary"]),
+            "_Config__secondary"
+        );
+    }
+
+    #[test]
+    fn structs_containing_identical_structs_share_a_name() {
+        let wrapper = |name: &str| {
+            GenericValue::Struct(make_struct(
+                name,
+                vec![("server", server(&format!("{}__server", name), 1))],
+            ))
+        };
+        let mut config = make_struct(
+            "Config",
+            vec![("a", wrapper("_Config__a")), ("b", wrapper("_Config__b"))],
+        );
+
+        deduplicate_structs(&mut config, &dedup_options());
+
+        assert_eq!(struct_name(&config.fields["b"]), "_Config__a");
+    }
+
+    #[test]
+    fn structs_are_not_deduplicated_by_default() {
+        let mut config = make_struct(
+            "Config",
+            vec![
+                ("primary", server("_Config__primary", 1)),
+                ("secondary", server("_Config__secondary", 2)),
+            ],
+        );
+
+        deduplicate_structs(&mut config, &StructOptions::default());
+
+        assert_eq!(
+            struct_name(&config.fields["secondary"]),
+            "_Config__secondary"
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Descriptions of the types declared for a config, for build scripts
+//! which generate more code from them.
+use std::collections::BTreeMap;
+
+use crate::{
+    generation::{collect_enums, declared_structs, type_string},
+    naming::pascal_case,
+    newtypes::collect_newtypes,
+    options::StructOptions,
+    paths,
+    tagged_unions::{collect_unions, union_template},
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Generated code, along with descriptions of the types it declares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-descriptions",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct GeneratedModule {
+    /// The generated code.
+    pub code: String,
+
+    /// The structs, enums and newtypes declared for the config, in the
+    /// order they're declared.
+    ///
+    /// Types generated alongside them, like builders, owned variants and
+    /// the struct for versions, aren't described.
+    pub types: Vec<TypeDescription>,
+}
+
+/// The kinds of type a [`TypeDescription`](struct.TypeDescription.html)
+/// can describe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-descriptions",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum TypeKind {
+    /// The root struct, or the struct of a nested table.
+    Struct,
+
+    /// An enum from `enum_fields` or `tagged_unions`.
+    Enum,
+
+    /// A newtype from `newtype_paths`.
+    Newtype,
+}
+
+/// A type declared in a [`GeneratedModule`](struct.GeneratedModule.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-descriptions",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct TypeDescription {
+    /// The name of the type, like `_Config__server`.
+    pub name: String,
+
+    /// The dotted path of the keys the type was generated from, like
+    /// `server`, or an empty string for the root struct. The elements of
+    /// an array are at the array's path with a `*` added, like
+    /// `servers.*`. A type used at several paths is at the first of them.
+    pub path: String,
+
+    pub kind: TypeKind,
+
+    /// The fields of a struct, in the order they're declared. A newtype
+    /// has the number it wraps, as a field named `0` with no key.
+    pub fields: Vec<FieldDescription>,
+
+    /// The variants of an enum, in the order they're declared.
+    pub variants: Vec<VariantDescription>,
+}
+
+/// A field of a [`TypeDescription`](struct.TypeDescription.html) or
+/// [`VariantDescription`](struct.VariantDescription.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-descriptions",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FieldDescription {
+    /// The Rust name of the field, like `max_connections`.
+    pub name: String,
+
+    /// The key in the config the field was generated from, like
+    /// `max-connections`.
+    pub key: String,
+
+    /// The Rust type the field is declared with, like `Option<i64>`.
+    pub type_name: String,
+}
+
+/// A variant of an enum in a
+/// [`TypeDescription`](struct.TypeDescription.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-descriptions",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct VariantDescription {
+    /// The name of the variant, like `Debug`.
+    pub name: String,
+
+    /// The string in the config which chooses the variant: the value of
+    /// a field in `enum_fields`, like `debug`, or the tag of a table in
+    /// `tagged_unions`.
+    pub key: String,
+
+    /// The fields of a variant of an enum from `tagged_unions`.
+    pub fields: Vec<FieldDescription>,
+}
+
+/// Describe the types declared for `struct_value`, as
+/// `generation::generate_structs` declares them, finding them the same
+/// way.
+pub fn describe_types(
+    struct_value: &GenericStruct,
+    options: &StructOptions,
+) -> Vec<TypeDescription> {
+    let mut type_paths = BTreeMap::new();
+    type_paths.insert(struct_value.struct_name.clone(), String::new());
+    collect_type_paths(struct_value, "", &mut type_paths);
+    let path = |name: &str| type_paths.get(name).cloned().unwrap_or_default();
+
+    let mut types = Vec::new();
+    for struct_value in declared_structs(struct_value) {
+        types.push(TypeDescription {
+            name: struct_value.struct_name.clone(),
+            path: path(&struct_value.struct_name),
+            kind: TypeKind::Struct,
+            fields: describe_fields(&struct_value, options),
+            variants: Vec::new(),
+        });
+    }
+
+    let mut enums = BTreeMap::new();
+    collect_enums(struct_value, &mut enums);
+    for (name, enum_value) in enums {
+        let variants = enum_value
+            .variants
+            .iter()
+            .map(|variant| VariantDescription {
+                name: pascal_case(variant),
+                key: variant.clone(),
+                fields: Vec::new(),
+            })
+            .collect();
+        types.push(TypeDescription {
+            path: path(&name),
+            name,
+            kind: TypeKind::Enum,
+            fields: Vec::new(),
+            variants,
+        });
+    }
+
+    let mut unions = BTreeMap::new();
+    collect_unions(struct_value, &mut unions);
+    for (name, values) in unions {
+        let template = union_template(&values);
+        let variants = template
+            .variants
+            .iter()
+            .map(|(tag_value, variant)| VariantDescription {
+                name: variant.clone(),
+                key: tag_value.clone(),
+                fields: template
+                    .known_variants
+                    .get(variant)
+                    .map(|body| describe_fields(body, options))
+                    .unwrap_or_default(),
+            })
+            .collect();
+        types.push(TypeDescription {
+            path: path(&name),
+            name,
+            kind: TypeKind::Enum,
+            fields: Vec::new(),
+            variants,
+        });
+    }
+
+    let mut newtypes = BTreeMap::new();
+    collect_newtypes(struct_value, &mut newtypes);
+    for (name, inner) in newtypes {
+        types.push(TypeDescription {
+            path: path(&name),
+            name,
+            kind: TypeKind::Newtype,
+            fields: vec![FieldDescription {
+                name: "0".to_owned(),
+                key: String::new(),
+                type_name: type_string(inner, options),
+            }],
+            variants: Vec::new(),
+        });
+    }
+
+    types
+}
+
+fn describe_fields(struct_value: &GenericStruct, options: &StructOptions) -> Vec<FieldDescription> {
+    struct_value
+        .ordered_fields(options.field_order)
+        .into_iter()
+        .map(|(name, value)| FieldDescription {
+            name: name.clone(),
+            key: struct_value.original_key(name).to_owned(),
+            type_name: type_string(value, options),
+        })
+        .collect()
+}
+
+/// Find the first path each named type is used at.
+fn collect_type_paths(
+    struct_value: &GenericStruct,
+    parent_path: &str,
+    type_paths: &mut BTreeMap<String, String>,
+) {
+    for (name, value) in &struct_value.fields {
+        let path = paths::join(parent_path, struct_value.original_key(name));
+        collect_value_paths(value, &path, type_paths);
+    }
+}
+
+fn collect_value_paths(
+    value: &GenericValue,
+    path: &str,
+    type_paths: &mut BTreeMap<String, String>,
+) {
+    match *value {
+        GenericValue::Struct(ref struct_value)
+            if !type_paths.contains_key(&struct_value.struct_name) =>
+        {
+            type_paths.insert(struct_value.struct_name.clone(), path.to_owned());
+            collect_type_paths(struct_value, path, type_paths);
+        }
+        GenericValue::Enum(ref enum_value) => {
+            type_paths
+                .entry(enum_value.enum_name.clone())
+                .or_insert_with(|| path.to_owned());
+        }
+        GenericValue::Newtype(ref newtype) => {
+            type_paths
+                .entry(newtype.type_name.clone())
+                .or_insert_with(|| path.to_owned());
+        }
+        GenericValue::TaggedUnion(ref union) => {
+            type_paths
+                .entry(union.enum_name.clone())
+                .or_insert_with(|| path.to_owned());
+            collect_type_paths(&union.value, path, type_paths);
+            for variant in union.known_variants.values() {
+                collect_type_paths(variant, path, type_paths);
+            }
+        }
+        GenericValue::Option(Some(ref value)) => collect_value_paths(value, path, type_paths),
+        GenericValue::Array(ref values)
+        | GenericValue::Tuple(ref values)
+        | GenericValue::Map(GenericMap { ref values, .. }) => {
+            let element_path = paths::join(path, "*");
+            for value in values {
+                collect_value_paths(value, &element_path, type_paths);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn struct_with(name: &str, fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: name.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    #[test]
+    fn types_are_at_their_first_path() {
+        let point =
+            || GenericValue::Struct(struct_with("Point", vec![("x", GenericValue::I64(1))]));
+        let config = struct_with(
+            "Config",
+            vec![
+                ("paths", GenericValue::Array(vec![point(), point()])),
+                ("start", point()),
+            ],
+        );
+
+        let mut type_paths = BTreeMap::new();
+        collect_type_paths(&config, "", &mut type_paths);
+
+        assert_eq!(type_paths["Point"], "paths.*");
+        assert_eq!(type_paths.len(), 1);
+    }
+}
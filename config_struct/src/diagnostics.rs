@@ -0,0 +1,84 @@
+use std::fmt;
+
+/// A problem found while generating a config which doesn't stop the
+/// code from being generated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// What kind of problem this is.
+    pub kind: WarningKind,
+
+    /// The dotted path of the key (or pattern) the warning concerns, or
+    /// an empty string for warnings about the whole config.
+    pub path: String,
+
+    /// A description of the problem.
+    pub message: String,
+}
+
+/// The kinds of problem a [`Warning`](struct.Warning.html) can describe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    /// A pattern in `exclude_paths` doesn't match any field.
+    UnusedExcludePath,
+
+    /// An array is empty, so its element type can't be inferred, and no
+    /// type was given for it in `empty_array_types` or
+    /// `default_empty_array_type`. It is generated with `()` elements.
+    EmptyArray,
+
+    /// An option is `None` wherever it appears, so its type can't be
+    /// inferred, and no type was given for it in `option_types` or
+    /// `default_option_type`. It is generated as an `Option<()>`.
+    UntypedOption,
+
+    /// `rustfmt_output` is set, but `rustfmt` couldn't format the code,
+    /// so it was left unformatted.
+    RustfmtFailed,
+
+    /// A key was sanitized into a field name which was already taken,
+    /// so a number was added to it.
+    SanitizedKeyCollision,
+
+    /// A RON struct or tuple was given a name, like `Point(x: 1)`, which
+    /// can't be checked against the generated struct, so it was ignored.
+    IgnoredRonName,
+
+    /// Two paths would give the same name in the `keys` module generated
+    /// by `generate_key_constants`, so a number was added to the later
+    /// one.
+    KeyConstantCollision,
+
+    /// A variant listed in `tagged_unions` isn't the tag of any table at
+    /// its path, so it is declared with no fields.
+    EmptyVariant,
+}
+
+impl Warning {
+    pub(crate) fn new<P: Into<String>, M: Into<String>>(
+        kind: WarningKind,
+        path: P,
+        message: M,
+    ) -> Self {
+        Warning {
+            kind,
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Generated code, along with any warnings from generating it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerationOutput {
+    /// The generated code.
+    pub code: String,
+
+    /// Any problems found which didn't stop the code being generated.
+    pub warnings: Vec<Warning>,
+}
@@ -0,0 +1,170 @@
+use crate::{
+    generation::{declared_structs, type_string},
+    getters::is_copy,
+    naming::allow_non_camel_case,
+    options::StructOptions,
+    value::{GenericStruct, GenericValue},
+};
+
+/// Generate a diff type for the root struct and each nested struct, with
+/// `diff` and `apply` methods on the structs themselves.
+///
+/// A diff has an `Option` per field, which is `Some` if the field
+/// changed. Nested structs are diffed field by field, while everything
+/// else, including arrays, is replaced as a whole.
+pub fn generate_diffs(struct_value: &GenericStruct, options: &StructOptions) -> String {
+    let mut buffer = String::new();
+
+    for struct_value in declared_structs(struct_value) {
+        generate_diff(&mut buffer, &struct_value, options);
+    }
+
+    buffer
+}
+
+/// The name of the diff type for a struct.
+pub fn diff_name(struct_name: &str) -> String {
+    format!("{}Diff", struct_name)
+}
+
+fn generate_diff(output: &mut String, struct_value: &GenericStruct, options: &StructOptions) {
+    let struct_name = &struct_value.struct_name;
+    let diff_name = diff_name(struct_name);
+    let fields = struct_value.ordered_fields(options.field_order);
+
+    let mut declarations = String::new();
+    let mut empty_checks = Vec::new();
+    let mut diffs = String::new();
+    let mut applications = String::new();
+
+    for (name, value) in fields {
+        empty_checks.push(format!("self.{}.is_none()", name));
+
+        match *value {
+            GenericValue::Struct(ref nested) => {
+                declarations.push_str(&format!(
+                    "    pub {}: Option<{}>,\n",
+                    name,
+                    self::diff_name(&nested.struct_name)
+                ));
+                diffs.push_str(&format!(
+                    "            {name}: Some(self.{name}.diff(&other.{name})).filter(|diff| !diff.is_empty()),\n",
+                    name = name
+                ));
+                applications.push_str(&format!(
+                    "        if let Some(ref diff) = diff.{name} {{\n            self.{name}.apply(diff);\n        }}\n",
+                    name = name
+                ));
+            }
+            _ => {
+                let copied = if is_copy(value, options) {
+                    ""
+                } else {
+                    ".clone()"
+                };
+                declarations.push_str(&format!(
+                    "    pub {}: Option<{}>,\n",
+                    name,
+                    type_string(value, options)
+                ));
+                diffs.push_str(&format!(
+                    "            {name}: if self.{name} != other.{name} {{ Some(other.{name}{copied}) }} else {{ None }},\n",
+                    name = name,
+                    copied = copied
+                ));
+                let binding = if copied.is_empty() {
+                    "value"
+                } else {
+                    "ref value"
+                };
+                applications.push_str(&format!(
+                    "        if let Some({binding}) = diff.{name} {{\n            self.{name} = value{copied};\n        }}\n",
+                    binding = binding,
+                    name = name,
+                    copied = copied
+                ));
+            }
+        }
+    }
+
+    let is_empty = if empty_checks.is_empty() {
+        "true".to_owned()
+    } else {
+        empty_checks.join("\n            && ")
+    };
+
+    let debug = if options.derived_traits.iter().any(|name| name == "Debug") {
+        "Debug, "
+    } else {
+        ""
+    };
+
+    output.push_str(&format!(
+        "
+#[derive({debug}Clone, Default, PartialEq)]
+{allow_non_camel_case}pub struct {diff_name} {{
+{declarations}}}
+
+impl {diff_name} {{
+    pub fn is_empty(&self) -> bool {{
+        {is_empty}
+    }}
+}}
+
+impl {struct_name} {{
+    pub fn diff(&self, other: &{struct_name}) -> {diff_name} {{
+        {diff_name} {{
+{diffs}        }}
+    }}
+
+    pub fn apply(&mut self, diff: &{diff_name}) {{
+{applications}    }}
+}}
+",
+        debug = debug,
+        allow_non_camel_case = allow_non_camel_case(&diff_name),
+        diff_name = diff_name,
+        declarations = declarations,
+        is_empty = is_empty,
+        struct_name = struct_name,
+        diffs = diffs,
+        applications = applications
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_structs_have_nested_diffs() {
+        let server = GenericStruct {
+            struct_name: "_Config__server".to_owned(),
+            fields: vec![("port".to_owned(), GenericValue::I64(80))]
+                .into_iter()
+                .collect(),
+            ..GenericStruct::default()
+        };
+        let config = GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![
+                ("name".to_owned(), GenericValue::String("x".to_owned())),
+                ("server".to_owned(), GenericValue::Struct(server)),
+            ]
+            .into_iter()
+            .collect(),
+            ..GenericStruct::default()
+        };
+
+        let code = generate_diffs(&config, &StructOptions::default());
+
+        assert!(code.contains("pub struct ConfigDiff {\n    pub name: Option<Cow<'static, str>>,\n    pub server: Option<_Config__serverDiff>,\n}"));
+        assert!(code.contains("pub struct _Config__serverDiff {\n    pub port: Option<i64>,\n}"));
+        assert!(code.contains(
+            "server: Some(self.server.diff(&other.server)).filter(|diff| !diff.is_empty()),"
+        ));
+        assert!(
+            code.contains("        self.name.is_none()\n            && self.server.is_none()\n")
+        );
+    }
+}
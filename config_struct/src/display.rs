@@ -0,0 +1,76 @@
+use crate::{
+    error::GenerationError,
+    format::Format,
+    options::{DisplayStyle, StructOptions},
+};
+
+/// Generate the `Display` impl for the root struct, in the style given by
+/// `options.impl_display`. The `format` of the config is needed to display
+/// it in its source format.
+pub fn generate_display(
+    struct_name: &str,
+    format: Option<Format>,
+    options: &StructOptions,
+) -> Result<String, GenerationError> {
+    let body = match options.impl_display {
+        DisplayStyle::None => return Ok(String::new()),
+        DisplayStyle::PrettyDebug => "write!(f, \"{:#?}\", self)".to_owned(),
+        DisplayStyle::SourceFormat => {
+            let format = format.ok_or(GenerationError::MissingDisplayFormat)?;
+            let serialize_expression = serialize_expression(format)
+                .ok_or_else(|| GenerationError::UnsupportedDisplay(format.to_string()))?;
+            format!(
+                "let text = {}.map_err(|_| ::core::fmt::Error)?;
+        f.write_str(&text)",
+                serialize_expression
+            )
+        }
+    };
+
+    Ok(format!(
+        "
+impl ::core::fmt::Display for {struct_name} {{
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {{
+        {body}
+    }}
+}}
+",
+        struct_name = struct_name,
+        body = body
+    ))
+}
+
+/// Whether the config can be displayed in the given format.
+pub fn supports_source_display(format: Format) -> bool {
+    serialize_expression(format).is_some()
+}
+
+/// The expression which serializes `self` to a pretty string in the given
+/// format, if there is a serde crate for it.
+fn serialize_expression(format: Format) -> Option<&'static str> {
+    match format {
+        #[cfg(feature = "json-parsing")]
+        Format::Json => Some("::serde_json::to_string_pretty(self)"),
+
+        #[cfg(feature = "json5-parsing")]
+        Format::Json5 => Some("::json5::to_string(self)"),
+
+        #[cfg(feature = "ron-parsing")]
+        Format::Ron => {
+            Some("::ron::ser::to_string_pretty(self, ::ron::ser::PrettyConfig::default())")
+        }
+
+        // Values have to come before tables in TOML, which a `Value`
+        // takes care of, but serializing the struct directly doesn't.
+        #[cfg(feature = "toml-parsing")]
+        Format::Toml => {
+            Some("::toml::Value::try_from(self).and_then(|value| ::toml::to_string_pretty(&value))")
+        }
+
+        #[cfg(feature = "yaml-parsing")]
+        Format::Yaml => Some("::serde_yaml::to_string(self)"),
+
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
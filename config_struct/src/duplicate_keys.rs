@@ -0,0 +1,121 @@
+//! Handling of keys which appear more than once in the same mapping of a
+//! JSON or YAML config. Their parsers silently keep the last value, so
+//! the duplicates are found by scanning the source separately.
+use std::ops::Range;
+
+use crate::{
+    error::GenerationError,
+    options::{DuplicateKeyBehavior, StructOptions},
+};
+
+/// A key which appears more than once in the same mapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKey {
+    /// The dotted path of the key.
+    pub path: String,
+
+    /// The lines of the first occurrence, and of this one.
+    pub lines: (usize, usize),
+
+    /// The bytes of the source to remove to drop this occurrence, along
+    /// with its value.
+    pub removal: Range<usize>,
+}
+
+/// Apply `options.duplicate_keys` to the duplicate keys found in a
+/// config's `source`, returning new source to parse if any were removed.
+///
+/// The parsers already keep the last value, so only `FirstWins` needs
+/// to change the source.
+pub fn resolve_duplicate_keys(
+    source: &str,
+    mut duplicates: Vec<DuplicateKey>,
+    options: &StructOptions,
+) -> Result<Option<String>, GenerationError> {
+    if duplicates.is_empty() {
+        return Ok(None);
+    }
+
+    match options.duplicate_keys {
+        DuplicateKeyBehavior::Error | DuplicateKeyBehavior::Array => {
+            let duplicate = duplicates
+                .into_iter()
+                .min_by_key(|duplicate| duplicate.removal.start)
+                .expect("There are duplicates.");
+            let (first_line, line) = duplicate.lines;
+            Err(GenerationError::DuplicateKeyAt(
+                duplicate.path,
+                first_line,
+                line,
+            ))
+        }
+        DuplicateKeyBehavior::LastWins => Ok(None),
+        DuplicateKeyBehavior::FirstWins => {
+            duplicates.sort_by_key(|duplicate| duplicate.removal.start);
+
+            let mut output = String::with_capacity(source.len());
+            let mut position = 0;
+            for duplicate in duplicates {
+                // Duplicates within a removed value are already gone.
+                if duplicate.removal.start < position {
+                    continue;
+                }
+                output.push_str(&source[position..duplicate.removal.start]);
+                position = duplicate.removal.end;
+            }
+            output.push_str(&source[position..]);
+
+            Ok(Some(output))
+        }
+    }
+}
+
+/// The line, counting from 1, of a byte offset in `source`.
+pub fn line_at(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(duplicate_keys: DuplicateKeyBehavior) -> StructOptions {
+        StructOptions {
+            duplicate_keys,
+            ..StructOptions::default()
+        }
+    }
+
+    #[test]
+    fn nested_removals_are_skipped() {
+        let source = "0123456789";
+        let duplicates = vec![
+            DuplicateKey {
+                path: "a".to_owned(),
+                lines: (1, 1),
+                removal: 2..6,
+            },
+            DuplicateKey {
+                path: "a.b".to_owned(),
+                lines: (1, 1),
+                removal: 3..5,
+            },
+            DuplicateKey {
+                path: "c".to_owned(),
+                lines: (1, 1),
+                removal: 8..9,
+            },
+        ];
+
+        let resolved = resolve_duplicate_keys(
+            source,
+            duplicates.clone(),
+            &options(DuplicateKeyBehavior::FirstWins),
+        );
+        assert_eq!(resolved.unwrap().as_deref(), Some("01679"));
+
+        let resolved =
+            resolve_duplicate_keys(source, duplicates, &options(DuplicateKeyBehavior::LastWins));
+        assert_eq!(resolved.unwrap(), None);
+    }
+}
@@ -0,0 +1,229 @@
+//! Typing empty arrays, with `StructOptions::empty_array_types` and
+//! `StructOptions::default_empty_array_type`.
+//!
+//! An empty array says nothing about the type of its elements. Arrays
+//! which are empty wherever they appear (in every element of an array of
+//! structs, for example) are given the element type from the options, and
+//! the rest are reported, or rejected, according to
+//! `untyped_empty_arrays`.
+use std::collections::BTreeSet;
+
+use crate::{
+    diagnostics::{Warning, WarningKind},
+    error::GenerationError,
+    options::{EmptyArrayBehavior, StructOptions},
+    paths,
+    unification::{array_structs, array_structs_mut, struct_template},
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Give each empty array the element type configured for its path, and
+/// report any which have none.
+pub fn apply_empty_array_types(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), GenerationError> {
+    let mut empty_paths = BTreeSet::new();
+    collect_empty_arrays(struct_value, "", &mut empty_paths);
+
+    let mut typed_paths = BTreeSet::new();
+    for path in empty_paths {
+        match (element_type(&path, options), options.untyped_empty_arrays) {
+            (Some(_), _) => {
+                typed_paths.insert(path);
+            }
+            (None, EmptyArrayBehavior::Warn) => {
+                let message = format!(
+                    "`{}` is an empty array, so its elements are given the type `()`. \
+                     Give it a type in `empty_array_types` to fix.",
+                    path
+                );
+                warnings.push(Warning::new(WarningKind::EmptyArray, path, message));
+            }
+            (None, EmptyArrayBehavior::Error) => {
+                return Err(GenerationError::UntypedEmptyArray(path));
+            }
+        }
+    }
+
+    if !typed_paths.is_empty() {
+        type_struct(struct_value, "", &typed_paths, options);
+    }
+
+    Ok(())
+}
+
+/// The element type given for the empty array at a path, if any.
+fn element_type<'a>(path: &str, options: &'a StructOptions) -> Option<&'a String> {
+    options
+        .empty_array_types
+        .get(path)
+        .or(options.default_empty_array_type.as_ref())
+}
+
+/// Collect the paths of the arrays which are empty wherever they appear.
+///
+/// For arrays of structs, only the fields which are empty in every
+/// element count, since the others get their type from the rest.
+fn collect_empty_arrays(
+    struct_value: &GenericStruct,
+    parent_path: &str,
+    paths: &mut BTreeSet<String>,
+) {
+    for (key, value) in &struct_value.fields {
+        let path = paths::join(parent_path, struct_value.original_key(key));
+        collect_value_empty_arrays(value, &path, paths);
+    }
+}
+
+fn collect_value_empty_arrays(value: &GenericValue, path: &str, paths: &mut BTreeSet<String>) {
+    match *value {
+        GenericValue::Struct(ref struct_value) => collect_empty_arrays(struct_value, path, paths),
+        GenericValue::Option(Some(ref value)) => collect_value_empty_arrays(value, path, paths),
+        GenericValue::Array(ref values) | GenericValue::Map(GenericMap { ref values, .. }) => {
+            let structs = array_structs(values);
+            if !structs.is_empty() {
+                collect_empty_arrays(&struct_template(&structs), path, paths);
+            } else if let Some(value) = values.first() {
+                collect_value_empty_arrays(value, path, paths);
+            } else if let GenericValue::Array(_) = *value {
+                paths.insert(path.to_owned());
+            }
+        }
+        GenericValue::Tuple(ref values) => {
+            for value in values {
+                collect_value_empty_arrays(value, path, paths);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn type_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    typed_paths: &BTreeSet<String>,
+    options: &StructOptions,
+) {
+    let original_keys = struct_value.original_keys.clone();
+    for (key, value) in &mut struct_value.fields {
+        let key = original_keys.get(key).unwrap_or(key);
+        let path = paths::join(parent_path, key);
+        type_value(value, &path, typed_paths, options);
+    }
+}
+
+fn type_value(
+    value: &mut GenericValue,
+    path: &str,
+    typed_paths: &BTreeSet<String>,
+    options: &StructOptions,
+) {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => {
+            type_struct(struct_value, path, typed_paths, options)
+        }
+        GenericValue::Option(Some(ref mut value)) => type_value(value, path, typed_paths, options),
+        GenericValue::Array(ref values) if values.is_empty() && typed_paths.contains(path) => {
+            let element_type = element_type(path, options).expect("typed paths have a type");
+            *value = GenericValue::EmptyArray(element_type.clone());
+        }
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            let mut structs = array_structs_mut(values);
+            if !structs.is_empty() {
+                for struct_value in &mut structs {
+                    type_struct(struct_value, path, typed_paths, options);
+                }
+            } else {
+                for value in values {
+                    type_value(value, path, typed_paths, options);
+                }
+            }
+        }
+        GenericValue::Tuple(ref mut values) => {
+            for value in values {
+                type_value(value, path, typed_paths, options);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(tags: Vec<GenericValue>) -> GenericValue {
+        GenericValue::Struct(GenericStruct {
+            struct_name: "_Config__servers".to_owned(),
+            fields: vec![("tags".to_owned(), GenericValue::Array(tags))]
+                .into_iter()
+                .collect(),
+            ..GenericStruct::default()
+        })
+    }
+
+    fn config() -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![
+                ("plugins".to_owned(), GenericValue::Array(vec![])),
+                (
+                    "servers".to_owned(),
+                    GenericValue::Array(vec![element(vec![]), element(vec![])]),
+                ),
+                (
+                    "tagged".to_owned(),
+                    GenericValue::Array(vec![element(vec![]), element(vec![GenericValue::I64(1)])]),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    #[test]
+    fn empty_arrays_are_reported_by_path() {
+        let mut config = config();
+        let mut warnings = Vec::new();
+        apply_empty_array_types(&mut config, &StructOptions::default(), &mut warnings).unwrap();
+
+        let paths = warnings
+            .iter()
+            .map(|warning| (warning.kind, warning.path.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                (WarningKind::EmptyArray, "plugins"),
+                (WarningKind::EmptyArray, "servers.tags"),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_arrays_empty_everywhere_are_typed() {
+        let mut config = config();
+        let options = StructOptions {
+            default_empty_array_type: Some("u8".to_owned()),
+            ..StructOptions::default()
+        };
+        let mut warnings = Vec::new();
+        apply_empty_array_types(&mut config, &options, &mut warnings).unwrap();
+        assert!(warnings.is_empty());
+
+        let tags = |field: &str, index: usize| match config.fields[field] {
+            GenericValue::Array(ref values) => match values[index] {
+                GenericValue::Struct(ref element) => element.fields["tags"].clone(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        assert!(matches!(config.fields["plugins"], GenericValue::EmptyArray(ref t) if t == "u8"));
+        assert!(matches!(tags("servers", 1), GenericValue::EmptyArray(ref t) if t == "u8"));
+        assert!(matches!(tags("tagged", 0), GenericValue::Array(ref values) if values.is_empty()));
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    error::GenerationError,
+    naming::pascal_case,
+    options::StructOptions,
+    paths,
+    validation::valid_identifier,
+    value::{GenericEnum, GenericMap, GenericStruct, GenericValue},
+};
+
+/// Replace string values at the paths listed in `options.enum_fields`
+/// with enum values, checking that each value is one of those allowed.
+pub fn apply_enum_fields(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if options.enum_fields.is_empty() {
+        return Ok(());
+    }
+
+    let mut enum_names = BTreeMap::new();
+    for (path, variants) in &options.enum_fields {
+        validate_enum_spec(path, variants, &mut enum_names)?;
+    }
+
+    apply_to_struct(struct_value, "", options)
+}
+
+fn validate_enum_spec<'a>(
+    path: &'a str,
+    variants: &'a [String],
+    enum_names: &mut BTreeMap<String, (&'a str, &'a [String])>,
+) -> Result<(), GenerationError> {
+    let enum_name = enum_name_for_path(path);
+    if !valid_identifier(&enum_name) {
+        return Err(GenerationError::InvalidEnumName(path.into()));
+    }
+
+    let mut variant_names = BTreeMap::new();
+    for variant in variants {
+        let variant_name = pascal_case(variant);
+        if !valid_identifier(&variant_name) {
+            return Err(GenerationError::InvalidEnumVariant(
+                path.into(),
+                variant.clone(),
+            ));
+        }
+        if let Some(other) = variant_names.insert(variant_name.clone(), variant) {
+            return Err(GenerationError::DuplicateEnumVariant(
+                path.into(),
+                other.clone(),
+                variant.clone(),
+                variant_name,
+            ));
+        }
+    }
+
+    if let Some((other_path, other_variants)) = enum_names.get(&enum_name) {
+        if *other_variants != variants {
+            return Err(GenerationError::ConflictingEnumNames(
+                enum_name,
+                (*other_path).into(),
+                path.into(),
+            ));
+        }
+    }
+    enum_names.insert(enum_name, (path, variants));
+
+    Ok(())
+}
+
+fn apply_to_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    for (key, value) in &mut struct_value.fields {
+        let path = paths::join(parent_path, key);
+
+        match options.enum_fields.get(&path) {
+            Some(variants) => convert_to_enum(value, &path, variants)?,
+            None => apply_to_value(value, &path, options)?,
+        }
+    }
+    Ok(())
+}
+
+fn apply_to_value(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => apply_to_struct(struct_value, path, options),
+        GenericValue::Option(Some(ref mut value)) => apply_to_value(value, path, options),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                apply_to_value(value, path, options)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn convert_to_enum(
+    value: &mut GenericValue,
+    path: &str,
+    variants: &[String],
+) -> Result<(), GenerationError> {
+    let enum_value = match *value {
+        GenericValue::String(ref string) => {
+            if !variants.contains(string) {
+                return Err(GenerationError::InvalidEnumValue(
+                    path.into(),
+                    string.clone(),
+                ));
+            }
+
+            GenericValue::Enum(GenericEnum {
+                enum_name: enum_name_for_path(path),
+                variants: variants.to_vec(),
+                value: string.clone(),
+            })
+        }
+        GenericValue::Option(None) => return Ok(()),
+        GenericValue::Option(Some(ref mut value)) => return convert_to_enum(value, path, variants),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                convert_to_enum(value, path, variants)?;
+            }
+            return Ok(());
+        }
+        _ => return Err(GenerationError::InvalidEnumField(path.into())),
+    };
+
+    *value = enum_value;
+    Ok(())
+}
+
+pub fn enum_name_for_path(path: &str) -> String {
+    let key = path.rsplit('.').next().unwrap_or(path);
+    pascal_case(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options_with_enum(path: &str, variants: &[&str]) -> StructOptions {
+        let mut options = StructOptions::default();
+        options.enum_fields.insert(
+            path.to_owned(),
+            variants.iter().map(|&v| v.to_owned()).collect(),
+        );
+        options
+    }
+
+    fn config_with(key: &str, value: GenericValue) -> GenericStruct {
+        let mut fields = BTreeMap::new();
+        fields.insert(key.to_owned(), value);
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields,
+            original_keys: BTreeMap::new(),
+            doc: None,
+            field_docs: BTreeMap::new(),
+            field_cfgs: BTreeMap::new(),
+            field_spans: BTreeMap::new(),
+            flattened_fields: Default::default(),
+            absent_fields: Default::default(),
+            field_order: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn string_becomes_enum() {
+        let options = options_with_enum("log_level", &["debug", "info"]);
+        let mut config = config_with("log_level", GenericValue::String("info".into()));
+
+        apply_enum_fields(&mut config, &options).unwrap();
+
+        match config.fields["log_level"] {
+            GenericValue::Enum(ref value) => {
+                assert_eq!(value.enum_name, "LogLevel");
+                assert_eq!(value.value, "info");
+            }
+            ref other => panic!("Expected enum, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn disallowed_value_is_an_error() {
+        let options = options_with_enum("log_level", &["debug", "info"]);
+        let mut config = config_with("log_level", GenericValue::String("trace".into()));
+
+        assert!(apply_enum_fields(&mut config, &options).is_err());
+    }
+
+    #[test]
+    fn non_string_value_is_an_error() {
+        let options = options_with_enum("log_level", &["debug", "info"]);
+        let mut config = config_with("log_level", GenericValue::I64(1));
+
+        match apply_enum_fields(&mut config, &options) {
+            Err(error @ GenerationError::InvalidEnumField(..)) => {
+                assert_eq!(error.path(), Some("log_level"));
+                assert_eq!(
+                    error.to_string(),
+                    "Field `log_level` is listed in `enum_fields`, but is not a string."
+                );
+            }
+            other => panic!("Expected an enum field error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn colliding_variant_names_are_an_error() {
+        let options = options_with_enum("lvl", &["a-b", "debug", "a_b"]);
+        let mut config = config_with("lvl", GenericValue::String("debug".into()));
+
+        match apply_enum_fields(&mut config, &options) {
+            Err(error @ GenerationError::DuplicateEnumVariant(..)) => {
+                assert_eq!(error.path(), Some("lvl"));
+                assert_eq!(
+                    error.to_string(),
+                    "Allowed values `a-b` and `a_b` for field `lvl` would both be enum variant `AB`."
+                );
+            }
+            other => panic!("Expected a duplicate variant error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_variant_names_are_an_error() {
+        let options = options_with_enum("log_level", &["debug", "1st"]);
+        let mut config = config_with("log_level", GenericValue::String("debug".into()));
+
+        assert!(apply_enum_fields(&mut config, &options).is_err());
+    }
+}
@@ -1,7 +1,13 @@
-use std::io::Error as IOError;
+use std::{
+    fmt,
+    io::Error as IOError,
+    path::{Path, PathBuf},
+};
 
 use failure::Fail;
 
+use crate::{options::Rule, value::Span};
+
 /// An error type for errors while generating config struct modules.
 ///
 /// Errors can either occur during IO (when reading or creating files) or during
@@ -13,6 +19,10 @@ pub enum Error {
 
     #[fail(display = "IO error: {}", _0)]
     IO(#[cause] IOError),
+
+    /// An IO error while reading or writing the file at the given path.
+    #[fail(display = "IO error in `{}`: {}", _0, _1)]
+    FileIO(String, #[cause] IOError),
 }
 
 /// An error occurring during code generation.
@@ -26,18 +36,411 @@ pub enum GenerationError {
     )]
     UnknownInputFormat(String),
 
+    /// Occurs when the format of a config could not be detected from its
+    /// contents, because it parsed as none or several of the enabled
+    /// formats.
+    #[fail(display = "Could not detect the format of the config: {}", _0)]
+    UndetectedFormat(String),
+
     /// Occurs when encountering a field in the config which is not a
     /// valid name for a struct field.
     #[fail(display = "Invalid field name: `{}`.", _0)]
     InvalidFieldName(String),
 
+    /// Occurs when `field_renames` would give two fields of the same
+    /// struct the same name.
+    #[fail(display = "Multiple fields would be renamed to `{}`.", _0)]
+    DuplicateFieldName(String),
+
     /// Occurs when an array in the config file contains multiple different types
     /// of data, which cannot be represented in a Rust struct.
+    ///
+    /// Gives the indices of the elements whose type differs from the
+    /// first element. Numbers are widened to a common type where
+    /// possible, so this only occurs for incompatible mixes.
+    #[fail(
+        display = "Array under key `{}` has elements of different types (elements {:?} differ from the first). Arrays must be homogenous, unless `mixed_arrays` is set to `Tuple`.",
+        _0, _1
+    )]
+    HeterogenousArray(String, Vec<usize>),
+
+    /// Occurs when an array in the config is empty and has no element
+    /// type in `empty_array_types` or `default_empty_array_type`, and
+    /// `untyped_empty_arrays` is set to `Error`.
+    #[fail(
+        display = "Array under key `{}` is empty, so its element type is unknown. Give it a type in `empty_array_types`.",
+        _0
+    )]
+    UntypedEmptyArray(String),
+
+    /// Occurs when an option in the config is `None` wherever it appears
+    /// and has no type in `option_types` or `default_option_type`, and
+    /// `untyped_options` is set to `Error`.
+    #[fail(
+        display = "The value under key `{}` is always `None`, so its type is unknown. Give it a type in `option_types`.",
+        _0
+    )]
+    UntypedOption(String),
+
+    /// Occurs when arrays which share a type, like the same field of the
+    /// structs in an array, have different lengths but would be generated
+    /// as fixed-size arrays. The last field is the option setting which
+    /// would use slices for them instead.
+    #[fail(
+        display = "Arrays under key `{}` have different lengths ({:?}), so they can't share a fixed-size array type. Make the arrays the same length, or set `{}` to use slices.",
+        _0, _1, _2
+    )]
+    MismatchedArrayLengths(String, Vec<usize>, &'static str),
+
+    /// Occurs when a fixed-size array has more than 32 elements, but serde
+    /// traits are derived, as serde only implements them for arrays of up
+    /// to 32 elements.
+    #[fail(
+        display = "Array under key `{}` has {} elements, but serde only supports fixed-size arrays of up to 32. Use `ArrayStyle::FixedUpTo(32)` to generate longer arrays as slices.",
+        _0, _1
+    )]
+    FixedArrayTooLong(String, usize),
+
+    /// Occurs when a map in the config has values of different types.
+    #[fail(display = "Map under key `{}` has values of different types.", _0)]
+    HeterogenousMap(String),
+
+    /// Occurs when a field has a different type in one element of an
+    /// array of structs than in the elements before it.
+    #[fail(
+        display = "Field `{}` has a different type in array element {} than in earlier elements.",
+        _0, _1
+    )]
+    ConflictingArrayElement(String, usize),
+
+    /// Occurs when a field listed in `enum_fields` has a value which is
+    /// not one of the allowed values for that enum.
+    #[fail(
+        display = "Invalid value for enum field `{}`: `{}` is not one of the allowed values.",
+        _0, _1
+    )]
+    InvalidEnumValue(String, String),
+
+    /// Occurs when a field listed in `enum_fields` has a value which is
+    /// not a string.
+    #[fail(
+        display = "Field `{}` is listed in `enum_fields`, but is not a string.",
+        _0
+    )]
+    InvalidEnumField(String),
+
+    /// Occurs when a field listed in `enum_fields` would produce an enum
+    /// type name which is not a valid Rust identifier.
+    #[fail(display = "Invalid enum name for field `{}`.", _0)]
+    InvalidEnumName(String),
+
+    /// Occurs when an allowed value listed in `enum_fields` would produce
+    /// an invalid enum variant name, or a variant in `tagged_unions` is
+    /// invalid or repeated.
+    #[fail(display = "Invalid enum variant for field `{}`: `{}`.", _0, _1)]
+    InvalidEnumVariant(String, String),
+
+    /// Occurs when two allowed values listed in `enum_fields` would
+    /// produce the same enum variant name. Gives the field, both values,
+    /// and the variant name.
+    #[fail(
+        display = "Allowed values `{}` and `{}` for field `{}` would both be enum variant `{}`.",
+        _1, _2, _0, _3
+    )]
+    DuplicateEnumVariant(String, String, String, String),
+
+    /// Occurs when two fields listed in `enum_fields` would produce enums
+    /// of the same name, but with different allowed values.
+    #[fail(
+        display = "Enum `{}` would be generated for both `{}` and `{}` with different values.",
+        _0, _1, _2
+    )]
+    ConflictingEnumNames(String, String, String),
+
+    /// Occurs when a name in `newtype_paths` is not a valid Rust type
+    /// name.
+    #[fail(display = "Invalid newtype name for field `{}`: `{}`.", _0, _1)]
+    InvalidNewtypeName(String, String),
+
+    /// Occurs when a field listed in `newtype_paths` is not a number.
+    #[fail(
+        display = "Field `{}` is listed in newtype_paths, but is not a number: `{}`.",
+        _0, _1
+    )]
+    InvalidNewtypeField(String, String),
+
+    /// Occurs when fields which share a newtype in `newtype_paths` hold
+    /// numbers of different types.
+    #[fail(
+        display = "{}.
+(Give the fields the same type with field_int_types, or use different newtypes, to fix.)",
+        _0
+    )]
+    ConflictingNewtypes(Box<NewtypeConflict>),
+
+    /// Occurs when a field listed in `tagged_unions` is not a table.
+    #[fail(
+        display = "Field `{}` is listed in tagged_unions, but is not a table.",
+        _0
+    )]
+    InvalidTaggedUnionField(String),
+
+    /// Occurs when a table listed in `tagged_unions` doesn't have a
+    /// string value for its tag key.
+    #[fail(display = "Table `{}` has no string `{}` tag.", _0, _1)]
+    MissingTag(String, String),
+
+    /// Occurs when the tag of a table listed in `tagged_unions` is not one
+    /// of the tag values listed for it. Holds the path, the tag, and the
+    /// known tag values.
+    #[fail(
+        display = "Table `{}` has the tag `{}`, which is not one of the known tags: {:?}.",
+        _0, _1, _2
+    )]
+    UnknownTag(String, String, Vec<String>),
+
+    /// Occurs when the name chosen for the struct of a nested table is
+    /// not a valid Rust identifier.
+    #[fail(display = "Invalid struct name for table `{}`: `{}`.", _0, _1)]
+    InvalidNestedStructName(String, String),
+
+    /// Occurs when the structs for two different tables, or a table and
+    /// an enum, would have the same name.
+    #[fail(
+        display = "Struct `{}` would be generated for both `{}` and `{}`.",
+        _0, _1, _2
+    )]
+    ConflictingStructNames(String, String, String),
+
+    /// Occurs when a date or time value in the config can't be
+    /// represented by the chosen `date_time_type`.
+    #[fail(display = "Invalid date or time for field `{}`: `{}`.", _0, _1)]
+    InvalidDateTime(String, String),
+
+    /// Occurs when a path listed in `map_paths` refers to a value which
+    /// is not a table.
+    #[fail(
+        display = "Field `{}` is listed in `map_paths`, but is not a table.",
+        _0
+    )]
+    InvalidMapPath(String),
+
+    /// Occurs when a path listed in `index_map_paths` refers to a value
+    /// which is not a table.
+    #[fail(
+        display = "Field `{}` is listed in `index_map_paths`, but is not a table.",
+        _0
+    )]
+    InvalidIndexMapPath(String),
+
+    /// Occurs when a table listed in `index_map_paths` has a key which is
+    /// not a non-negative integer.
+    #[fail(
+        display = "Field `{}` is listed in `index_map_paths`, but has the key `{}`, which is not an index.",
+        _0, _1
+    )]
+    InvalidIndexKey(String, String),
+
+    /// Occurs when the keys of a table listed in `index_map_paths` skip
+    /// an index, and `index_map_gaps` is `IndexGapBehavior::Error`.
+    #[fail(
+        display = "Field `{}` is listed in `index_map_paths`, but has no index {}.",
+        _0, _1
+    )]
+    MissingIndex(String, u64),
+
+    /// Occurs when a path listed in `flatten_paths` refers to a value
+    /// which is not a table.
     #[fail(
-        display = "Array under key `{}` has elements of different types. Arrays must be homogenous.",
+        display = "Field `{}` is listed in `flatten_paths`, but is not a table.",
         _0
     )]
-    HeterogenousArray(String),
+    InvalidFlattenPath(String),
+
+    /// Occurs when flattening a table listed in `flatten_paths` would
+    /// give its parent two fields with the same name, or two fields
+    /// with the same key with `FlattenStyle::SerdeFlatten`.
+    #[fail(
+        display = "Flattening field `{}` into its parent would conflict with `{}`.
+(Use a different flatten_joiner, or rename one of the fields to fix.)",
+        _0, _1
+    )]
+    FlattenConflict(String, String),
+
+    /// Occurs when a field listed in `field_int_types` has a value
+    /// which is not an integer.
+    #[fail(
+        display = "Field `{}` is listed in `field_int_types`, but is not an integer.",
+        _0
+    )]
+    InvalidIntegerField(String),
+
+    /// Occurs when an integer in the config doesn't fit in the type
+    /// given for its field in `field_int_types`, or in
+    /// `default_int_size`.
+    #[fail(display = "Value `{}` of field `{}` does not fit in `{}`.", _1, _0, _2)]
+    IntegerOutOfRange(String, String, String),
+
+    /// Occurs when an integer in the config is too large, or too small, to
+    /// fit in any 128-bit integer type.
+    #[fail(
+        display = "Value `{}` of field `{}` does not fit in any 128-bit integer type.",
+        _1, _0
+    )]
+    IntegerTooLarge(String, String),
+
+    /// Occurs when a value is nested in more structs and arrays than
+    /// `max_depth` allows.
+    #[fail(
+        display = "Value `{}` is nested {} structs and arrays deep, more than max_depth allows.
+(Flatten the config, or raise max_depth to fix.)",
+        path, depth
+    )]
+    MaxDepthExceeded { path: String, depth: usize },
+
+    /// Occurs when a config is larger than one of its `resource_limits`
+    /// allows.
+    #[fail(
+        display = "{}.
+(Raise the limit to fix, if the config can be trusted.)",
+        _0
+    )]
+    LimitExceeded(ExceededLimit),
+
+    /// Occurs when a field with `IntSize::Auto` has both negative values
+    /// and values too large for `i64`, so no type would suit all of them
+    /// without growing to 128 bits.
+    #[fail(
+        display = "Field `{}` has values from `{}` to `{}`, which mix signs beyond the range of `i64`.",
+        _0, _1, _2
+    )]
+    MixedSignIntegers(String, String, String),
+
+    /// Occurs when a field listed in `binary_paths` has a value which is
+    /// not a string.
+    #[fail(
+        display = "Field `{}` is listed in `binary_paths`, but is not a string.",
+        _0
+    )]
+    InvalidBinaryField(String),
+
+    /// Occurs when a string at one of the `binary_paths` is not valid
+    /// base64.
+    #[fail(display = "Field `{}` is not valid base64: {}", _0, _1)]
+    InvalidBase64(String, String),
+
+    /// Occurs when a field listed in `char_paths` has a value which is
+    /// not a string.
+    #[fail(
+        display = "Field `{}` is listed in `char_paths`, but is not a string.",
+        _0
+    )]
+    InvalidCharField(String),
+
+    /// Occurs when a string at one of the `char_paths` is not exactly one
+    /// character long.
+    #[fail(display = "Field `{}` is not a single character: `{}`.", _0, _1)]
+    InvalidChar(String, String),
+
+    /// Occurs when a field listed in `semver_paths` has a value which is
+    /// not a string.
+    #[fail(
+        display = "Field `{}` is listed in `semver_paths`, but is not a string.",
+        _0
+    )]
+    InvalidVersionField(String),
+
+    /// Occurs when a string at one of the `semver_paths` is not a valid
+    /// semantic version.
+    #[fail(display = "Field `{}` is not a valid semantic version: {}", _0, _1)]
+    InvalidVersion(String, String),
+
+    /// Occurs when a string at a path with `ValueTransform::EnvSubstitute`
+    /// refers to an environment variable which isn't set.
+    #[fail(
+        display = "Field `{}` refers to the environment variable `{}`, which is not set.",
+        _0, _1
+    )]
+    MissingEnvVar(String, String),
+
+    /// Occurs when a value at a path with `ValueTransform::ParseByteSize`
+    /// is not a byte size.
+    #[fail(display = "Field `{}` is not a byte size like `10MB`: `{}`.", _0, _1)]
+    InvalidByteSize(String, String),
+
+    /// Occurs when a value at a path with `ValueTransform::ParseDurationMs`
+    /// is not a duration.
+    #[fail(
+        display = "Field `{}` is not a duration like `5s` or `100ms`: `{}`.",
+        _0, _1
+    )]
+    InvalidDuration(String, String),
+
+    /// Occurs when a path read by one of the `computed_fields` is not in
+    /// the config. Holds the computed field's path, and the path which is
+    /// missing, with array elements numbered.
+    #[fail(
+        display = "Computed field `{}` reads `{}`, which is not in the config.",
+        _0, _1
+    )]
+    MissingComputedPath(String, String),
+
+    /// Occurs when one of the `computed_fields` reads a value it can't
+    /// use, like a string to sum. Holds the computed field's path, the
+    /// path of the value, and what the value should have been.
+    #[fail(
+        display = "Computed field `{}` needs {}, but `{}` is not one.",
+        _0, _2, _1
+    )]
+    InvalidComputedValue(String, String, String),
+
+    /// Occurs when one of the `computed_fields` takes the minimum or
+    /// maximum of a path with no values, like an empty array.
+    #[fail(
+        display = "Computed field `{}` takes the minimum or maximum of `{}`, which has no values.",
+        _0, _1
+    )]
+    EmptyComputedValue(String, String),
+
+    /// Occurs when one of the `computed_fields` can't be added to the
+    /// config, because its parent isn't a table or already has its key.
+    #[fail(
+        display = "Computed field `{}` can't be added: its parent must be a table without that key.",
+        _0
+    )]
+    InvalidComputedField(String),
+
+    /// Occurs when values break the rules in `validations`. Holds every
+    /// value which broke a rule.
+    #[fail(display = "Config values failed validation: {:?}", _0)]
+    ValidationFailed(Vec<Violation>),
+
+    /// Occurs when structs derive `clap::Parser`, but fields have values
+    /// which can't be command-line arguments, like arrays of tables, or
+    /// would have the same argument name as another field. Holds the
+    /// paths of those fields.
+    #[fail(
+        display = "Fields can't be command-line arguments: {:?}. Exclude them with exclude_paths. (Arrays need string_type: StringType::String, and can only hold values like numbers and strings.)",
+        _0
+    )]
+    UnsupportedCliFields(Vec<String>),
+
+    /// Occurs when a JSON or YAML config has a `null` value, and
+    /// `null_values` is set to `Error`.
+    #[fail(
+        display = "Field `{}` is null. (Set null_values to NullBehavior::Option or NullBehavior::Unit to allow nulls.)",
+        _0
+    )]
+    NullValue(String),
+
+    /// Occurs when a const would be generated, but a field can't be built
+    /// in a const context and `const_fallback` is `Error`.
+    #[fail(
+        display = "Field `{}` cannot be built in a const.
+(Set const_fallback: ConstFallback::LazyStatic or ConstFallback::RuntimeFn to fix.)",
+        _0
+    )]
+    NonConstField(String),
 
     /// Occurs when generating from source and not a file, if attempting to also
     /// generate dynamic loading functions.
@@ -50,10 +453,236 @@ pub enum GenerationError {
     )]
     MissingFilePath,
 
+    /// Occurs when generating from multiple files, if attempting to also
+    /// generate dynamic loading functions.
+    ///
+    /// The dynamic loading functions can only load a single file, so they
+    /// would not reproduce the merged config.
+    #[fail(
+        display = "Cannot generate dynamic loading functions for a config merged from multiple files.
+(Set generate_load_fns: false, or set dynamic_loading: DynamicLoading::Never to fix.)"
+    )]
+    MultipleFilePaths,
+
+    /// Occurs when writing the generated code to `OUT_DIR`, outside of a
+    /// build script run by cargo.
+    #[fail(display = "The OUT_DIR environment variable is not set.
+(Call create_config_in_out_dir from a build script run by cargo, or use create_config to fix.)")]
+    MissingOutDir,
+
+    /// Occurs when attempting to generate a config from an empty list of files.
+    #[fail(display = "No input files were given.")]
+    NoInputFiles,
+
+    /// Occurs when two configs generated into the same module would
+    /// define a struct or const with the same name.
+    #[fail(display = "Multiple configs in the module would define `{}`.", _0)]
+    DuplicateModuleName(String),
+
+    /// Occurs when a job passed to `create_configs_parallel` would write
+    /// to the same destination as an earlier job.
+    #[fail(display = "Multiple jobs would write to `{}`.", _0)]
+    DuplicateDestination(String),
+
+    /// Occurs when a config in a directory passed to
+    /// `create_config_from_dir` has a different shape than the first
+    /// config in it. Gives both files, and the differences.
+    #[fail(display = "`{}` does not have the same shape as `{}`:{}", _0, _1, _2)]
+    MismatchedDirConfig(String, String, String),
+
+    /// Occurs when two schemas being merged give a field different
+    /// types.
+    #[fail(display = "Field `{}` has a different type in each schema.", _0)]
+    ConflictingSchemas(String),
+
+    /// Occurs when the values passed to `Schema::generate_code` don't
+    /// fit the schema. Gives the differences.
+    #[fail(display = "The config does not fit the schema:{}", _0)]
+    NonConformingConfig(String),
+
+    /// Occurs when the `lookup_fn` of a `DirOptions` is not a valid Rust
+    /// identifier.
+    #[fail(display = "Invalid name for the lookup function: `{}`.", _0)]
+    InvalidLookupFn(String),
+
+    /// Occurs when merging multiple config files, if the same key has
+    /// incompatible types in different files.
+    #[fail(
+        display = "Cannot merge key `{}`: it has incompatible types in different files.",
+        _0
+    )]
+    IncompatibleMerge(String),
+
     /// Occurs when the config file could not be correctly parsed.
     #[fail(display = "Deserialization failed: {}", _0)]
     DeserializationFailed(String),
 
+    /// Occurs when a RON config uses a construct which can't be
+    /// represented in the generated structs, like an enum variant or a
+    /// map with non-string keys. Gives the kind of construct, the path of
+    /// the key it occurs under, and its line and column.
+    #[fail(
+        display = "Unsupported RON {} under key `{}` at line {} column {}.",
+        construct, path, line, column
+    )]
+    UnsupportedRon {
+        construct: String,
+        path: String,
+        line: usize,
+        column: usize,
+    },
+
+    /// Occurs when a RON config contains a tuple, like `(1, "a")`, but
+    /// `mixed_arrays` is not set to `Tuple`, so tuples can't be generated.
+    #[fail(
+        display = "RON tuple under key `{}` at line {} column {} can't be generated, unless `mixed_arrays` is set to `Tuple`.",
+        path, line, column
+    )]
+    RonTupleWithoutTuples {
+        path: String,
+        line: usize,
+        column: usize,
+    },
+
+    /// Occurs when the parser for the config's format rejects it. Gives
+    /// the location of the error, where the parser reports it, and the
+    /// parser's own error as the cause.
+    #[fail(display = "Parsing failed: {}", _0)]
+    ParseFailed(#[cause] ParseError),
+
+    /// Wraps an error concerning a field of a config file with where the
+    /// field's key is in the file. Only errors from files in formats which
+    /// record where their keys are, currently TOML, are located.
+    ///
+    /// `path` and `unlocated` see through this to the wrapped error.
+    #[fail(display = "{}", _0)]
+    Located(Box<GenerationError>, SourceLocation),
+
+    /// Occurs when a key appears more than once in the same mapping or
+    /// section, and `duplicate_keys` is `DuplicateKeyBehavior::Error`, or
+    /// in a TOML file, which never allows duplicate keys.
+    #[fail(display = "Duplicate key: `{}`.", _0)]
+    DuplicateKey(String),
+
+    /// Like `DuplicateKey`, but gives the lines of the first and second
+    /// occurrences of the key.
+    #[fail(display = "Duplicate key: `{}` (lines {} and {}).", _0, _1, _2)]
+    DuplicateKeyAt(String, usize, usize),
+
+    /// Occurs when a file named by an include can't be read, or has an
+    /// unknown format.
+    #[fail(display = "Failed to include `{}`: {}", _0, _1)]
+    IncludeFailed(String, String),
+
+    /// Occurs when config files include each other in a cycle. The chain
+    /// of includes is listed, starting and ending with the same file.
+    #[fail(display = "Config files include each other in a cycle: {}", _0)]
+    IncludeCycle(String),
+
+    /// Occurs when dynamic loading functions are requested for a format
+    /// which can't be deserialized at runtime.
+    #[fail(
+        display = "Dynamic loading is not supported for {} files. (Set dynamic_loading: DynamicLoading::Never, or generate_load_fns: false to fix.)",
+        _0
+    )]
+    UnsupportedDynamicLoading(String),
+
+    /// Occurs when `generate_tests` is set for a format which has no
+    /// serde crate to check the const with.
+    #[fail(
+        display = "Tests can't be generated for {} files. (Set generate_tests: false to fix.)",
+        _0
+    )]
+    UnsupportedTests(String),
+
+    /// Occurs when `generate_tests` is set when generating from source
+    /// and not a file, since there is no file to check the const against.
+    #[fail(display = "Cannot generate tests without a filename.
+(Generate struct from a file, or set generate_tests: false to fix.)")]
+    MissingTestFile,
+
+    /// Occurs when `impl_display` is `DisplayStyle::SourceFormat` for a
+    /// format which has no serde crate to serialize the config with.
+    #[fail(
+        display = "The config can't be displayed as {}. (Use a different impl_display to fix.)",
+        _0
+    )]
+    UnsupportedDisplay(String),
+
+    /// Occurs when `impl_display` is `DisplayStyle::SourceFormat` when
+    /// generating from source and not a file, since the format of the
+    /// file isn't known.
+    #[fail(
+        display = "Cannot display the config in its source format without a filename.
+(Generate struct from a file, or use a different impl_display to fix.)"
+    )]
+    MissingDisplayFormat,
+
+    /// Occurs when `field_access` is `FieldAccess::Getters`, and the getter
+    /// for a field would have the same name as a generated loading
+    /// function.
+    #[fail(
+        display = "The getter for field `{}` would conflict with another generated method.",
+        _0
+    )]
+    ReservedGetterName(String),
+
+    /// Occurs when the root of a config is an array with elements which
+    /// are not tables. Holds the indices of those elements.
+    #[fail(
+        display = "Elements {:?} of the root array are not tables. Every element of a root array must be a table.",
+        _0
+    )]
+    NonTableRootElements(Vec<usize>),
+
+    /// Occurs when the root of a config is an array, and an option or
+    /// operation needing a single root value was requested.
+    #[fail(display = "Configs whose root is an array don't support {}.", _0)]
+    UnsupportedRootArray(String),
+
+    /// Occurs when generating a const per top-level table, and a
+    /// top-level value isn't a table.
+    #[fail(
+        display = "Top-level key `{}` is not a table. Every top-level value must be a table when generating a const per table.",
+        _0
+    )]
+    NonTableTopLevelKey(String),
+
+    /// Occurs when generating a const per top-level table, and a table
+    /// doesn't have the same shape as the first. Gives the path within
+    /// the tables where they differ, then the keys of both tables.
+    #[fail(
+        display = "`{}` differs between top-level tables `{}` and `{}`. Every top-level table must have the same shape when generating a const per table.",
+        _0, _1, _2
+    )]
+    MismatchedTopLevelTables(String, String, String),
+
+    /// Occurs when `format_options.yaml.document` selects a document by
+    /// an index past the end of the file. Gives the index, then how many
+    /// documents there are.
+    #[fail(
+        display = "There is no YAML document {} (counting from 0): the file has {} documents.",
+        _0, _1
+    )]
+    MissingYamlDocument(usize, usize),
+
+    /// Occurs when merging every document of a YAML file, if the same key
+    /// has incompatible types in different documents. Gives the index of
+    /// the document which couldn't be merged, then the key's path.
+    #[fail(
+        display = "Cannot merge YAML document {}: key `{}` has an incompatible type in an earlier document.",
+        _0, _1
+    )]
+    YamlDocumentConflict(usize, String),
+
+    /// Occurs when a YAML mapping has a key which isn't a string, number
+    /// or boolean, like a list. Gives the path of the mapping.
+    #[fail(
+        display = "Mapping under key `{}` has a key which is not a string, number or boolean.",
+        _0
+    )]
+    UnsupportedYamlKey(String),
+
     /// Occurs when invalid options were provided.
     #[fail(display = "Invalid options error: {}", _0)]
     StructOptions(#[cause] OptionsError),
@@ -70,6 +699,725 @@ pub enum OptionsError {
     /// Occurs when the provided `const_name` is not a valid Rust identifier.
     #[fail(display = "Invalid name for a const: `{}`.", _0)]
     InvalidConstName(String),
+
+    /// Occurs when the provided `impl_into` is not a valid path to a
+    /// type.
+    #[fail(display = "Invalid path for the `impl_into` type: `{}`.", _0)]
+    InvalidIntoTarget(String),
+
+    /// Occurs when `impl_default` is set, but `Default` is also listed in
+    /// `derived_traits`.
+    #[fail(display = "Cannot both derive and implement `Default`.")]
+    ConflictingDefault,
+
+    /// Occurs when `generate_diff` is set, but `Clone` or `PartialEq` is
+    /// not listed in `derived_traits`.
+    #[fail(display = "Cannot generate diffs without deriving Clone and PartialEq.
+(Add them to derived_traits, or set generate_diff: false to fix.)")]
+    DiffWithoutTraits,
+
+    /// Occurs when `generate_owned_variant` is set, but `Clone` is not
+    /// listed in `derived_traits`.
+    #[fail(display = "Cannot generate owned structs without deriving Clone.
+(Add it to derived_traits, or set generate_owned_variant: false to fix.)")]
+    OwnedVariantWithoutClone,
+
+    /// Occurs when `generate_owned_variant` is set with a `string_type` of
+    /// `StaticStr`, which owned values can't be converted back into.
+    #[fail(display = "Cannot generate owned structs with &'static str strings.
+(Set string_type: StringType::Cow, or set generate_owned_variant: false to fix.)")]
+    OwnedVariantWithStaticStr,
+
+    /// Occurs when `clap::Parser` is in `derived_traits` with the
+    /// `clap-support` feature, but `string_type` is `StaticStr`, which
+    /// can't be parsed from arguments.
+    #[fail(
+        display = "Cannot parse &'static str strings from command-line arguments.
+(Set string_type: StringType::String, or remove clap::Parser from derived_traits to fix.)"
+    )]
+    ClapWithStaticStr,
+
+    /// Occurs when `serde_attributes` are given, but `serde_support` does
+    /// not derive any serde traits.
+    #[fail(display = "Cannot add serde attributes without deriving serde traits.
+(Enable serde_support, or remove the serde_attributes to fix.)")]
+    SerdeAttributesWithoutSerde,
+
+    /// Occurs when `serde_support` is `SerdeSupport::ManualImpl`, along
+    /// with an option which needs serde's derive attributes.
+    #[fail(
+        display = "Cannot implement the serde traits by hand with {}.
+(Derive the serde traits, or remove the conflicting option, to fix.)",
+        _0
+    )]
+    ManualImplWith(&'static str),
+
+    /// Occurs when `serde_missing_fields` is set, but `serde_support` does
+    /// not derive `Deserialize`.
+    #[fail(display = "Cannot handle missing fields without deriving Deserialize.
+(Enable serde_support, or set serde_missing_fields: MissingFields::Error to fix.)")]
+    MissingFieldsWithoutDeserialize,
+
+    /// Occurs when `string_type` is `StaticStr`, but the load functions
+    /// would load the config dynamically.
+    #[fail(display = "Cannot use static string references with dynamic loading.
+(Set dynamic_loading: DynamicLoading::Never, or use a different string_type to fix.)")]
+    StaticStrWithDynamicLoading,
+
+    /// Occurs when `string_type` is `StaticStr`, but `serde_support` would
+    /// derive `Deserialize`.
+    #[fail(display = "Cannot derive Deserialize for static string references.
+(Disable deserialization in serde_support, or use a different string_type to fix.)")]
+    StaticStrWithDeserialize,
+
+    /// Occurs when `string_type` is `String`, but a const would be
+    /// generated.
+    #[fail(display = "Cannot use owned strings in a const.
+(Set generate_const: false and dynamic_loading: DynamicLoading::Always, or use a different string_type to fix.)")]
+    OwnedStringsInConst,
+
+    /// Occurs when `env_overrides` is set, but neither a const nor
+    /// load functions would be generated to start from.
+    #[fail(
+        display = "Cannot generate environment overrides without a const or load functions.
+(Set generate_const: true or generate_load_fns: true to fix.)"
+    )]
+    EnvOverridesWithoutSource,
+
+    /// Occurs when `env_overrides` is set, but `string_type` is
+    /// `StaticStr`.
+    #[fail(
+        display = "Cannot override static string references from environment variables.
+(Use a different string_type to fix.)"
+    )]
+    StaticStrWithEnvOverrides,
+
+    /// Occurs when a path listed in `include_paths` is also excluded by
+    /// `exclude_paths`.
+    #[fail(
+        display = "Cannot include `{}`, because it is also excluded by `exclude_paths`.",
+        _0
+    )]
+    ConflictingPathFilters(String),
+
+    /// Occurs when `map_paths` is set and `map_type` is `Slice`, but the
+    /// load functions would load the config dynamically.
+    #[fail(display = "Cannot deserialize maps into slices with dynamic loading.
+(Set map_type: MapType::HashMap, or dynamic_loading: DynamicLoading::Never to fix.)")]
+    SliceMapsWithDynamicLoading,
+
+    /// Occurs when `binary_paths` is set, but the load functions would
+    /// load the config dynamically.
+    #[fail(display = "Cannot decode base64 fields with dynamic loading.
+(Set dynamic_loading: DynamicLoading::Never, or remove the binary_paths to fix.)")]
+    BinaryPathsWithDynamicLoading,
+
+    /// Occurs when `index_map_paths` is set, but the load functions would
+    /// load the config dynamically.
+    #[fail(display = "Cannot read tables as arrays with dynamic loading.
+(Set dynamic_loading: DynamicLoading::Never, or remove the index_map_paths to fix.)")]
+    IndexMapsWithDynamicLoading,
+
+    /// Occurs when `value_transforms` is set, but the load functions would
+    /// load the config dynamically.
+    #[fail(display = "Cannot transform values with dynamic loading.
+(Set dynamic_loading: DynamicLoading::Never, or remove the value_transforms to fix.)")]
+    ValueTransformsWithDynamicLoading,
+
+    /// Occurs when `computed_fields` is set, but the load functions would
+    /// load the config dynamically.
+    #[fail(display = "Cannot compute fields with dynamic loading.
+(Set dynamic_loading: DynamicLoading::Never, or remove the computed_fields to fix.)")]
+    ComputedFieldsWithDynamicLoading,
+
+    /// Occurs when `flatten_paths` is set, but the load functions would
+    /// load the config dynamically.
+    #[fail(display = "Cannot flatten tables with dynamic loading.
+(Set dynamic_loading: DynamicLoading::Never, or remove the flatten_paths to fix.)")]
+    FlattenPathsWithDynamicLoading,
+
+    /// Occurs when `flatten_style` is `FlattenStyle::SerdeFlatten`, but
+    /// serde's traits aren't derived.
+    #[fail(
+        display = "Cannot flatten tables with serde without deriving serde's traits.
+(Enable serde_support, or use FlattenStyle::Fields to fix.)"
+    )]
+    SerdeFlattenWithoutSerde,
+
+    /// Occurs when `flatten_joiner` has characters which can't be in a
+    /// field name.
+    #[fail(
+        display = "The flatten_joiner `{}` can only contain letters, digits and underscores.",
+        _0
+    )]
+    InvalidFlattenJoiner(String),
+
+    /// Occurs when a rule in `validations` can never be followed, like a
+    /// range whose minimum is above its maximum, or a regex which doesn't
+    /// compile.
+    #[fail(display = "The validation rule for `{}` is invalid: {}.", _0, _1)]
+    InvalidRule(String, String),
+
+    /// Occurs when a predicate in `cfg_sections` would not fit inside
+    /// `#[cfg(...)]`, such as one with unbalanced parentheses.
+    #[fail(display = "Invalid cfg predicate `{}` for field `{}`.", _1, _0)]
+    InvalidCfgPredicate(String, String),
+
+    /// Occurs when `cfg_sections` is set along with an option which
+    /// generates code for each field, which isn't conditionally compiled.
+    #[fail(
+        display = "Cannot use cfg_sections with {}.
+(Remove the cfg_sections, or the conflicting option, to fix.)",
+        _0
+    )]
+    CfgSectionsWith(&'static str),
+
+    /// Occurs when `resolve_includes` is set, but the load functions would
+    /// load the config dynamically.
+    #[fail(display = "Cannot resolve includes with dynamic loading.
+(Set dynamic_loading: DynamicLoading::Never, or resolve_includes: None to fix.)")]
+    IncludesWithDynamicLoading,
+
+    /// Occurs when `no_std` is set, but the load functions would load the
+    /// config dynamically, which requires `std`.
+    #[fail(display = "Cannot load the config dynamically without std.
+(Set dynamic_loading: DynamicLoading::Never, or generate_load_fns: false to fix.)")]
+    NoStdWithDynamicLoading,
+
+    /// Occurs when `dynamic_path` is set along with
+    /// `DynamicLoading::Embedded`, which never reads from a path.
+    #[fail(display = "Cannot read an embedded config from another path.
+(Set dynamic_path: DynamicPath::BuildTimePath, or use a different dynamic_loading to fix.)")]
+    EmbeddedWithDynamicPath,
+
+    /// Occurs when `env_overrides` is set, but the load functions take
+    /// their path as an argument, so there is no `load()` to start from.
+    #[fail(
+        display = "Cannot generate environment overrides when the load functions take a path.
+(Set env_overrides: None, or use a different dynamic_path to fix.)"
+    )]
+    ArgumentPathWithEnvOverrides,
+
+    /// Occurs when `generate_tests` is set, but the structs can't be
+    /// both serialized and deserialized, or deserialized and loaded
+    /// dynamically.
+    #[fail(
+        display = "Cannot generate tests without deriving Serialize and Deserialize, or Deserialize with dynamic loading.
+(Enable serde_support, or set generate_tests: false to fix.)"
+    )]
+    TestsWithoutSerde,
+
+    /// Occurs when `generate_tests` is set, but there is no const to test.
+    #[fail(display = "Cannot generate tests without a const.
+(Set generate_const: true, or generate_tests: false to fix.)")]
+    TestsWithoutConst,
+
+    /// Occurs when `impl_display` is `DisplayStyle::SourceFormat`, but
+    /// `Serialize` isn't derived.
+    #[fail(
+        display = "Cannot display the config in its source format without deriving Serialize.
+(Enable serde_support, or use a different impl_display to fix.)"
+    )]
+    DisplayWithoutSerialize,
+
+    /// Occurs when `no_std` is set, but `impl_display` is
+    /// `DisplayStyle::SourceFormat`, which serializes to a `String`.
+    #[fail(display = "Cannot display the config in its source format without std.
+(Use a different impl_display, or set no_std: false to fix.)")]
+    NoStdWithSourceDisplay,
+
+    /// Occurs when `no_std` is set, but `env_overrides` is also set.
+    #[fail(display = "Cannot read environment variables without std.
+(Set env_overrides: None, or no_std: false to fix.)")]
+    NoStdWithEnvOverrides,
+
+    /// Occurs when `no_std` is set, but `map_paths` is set and `map_type`
+    /// is `HashMap`.
+    #[fail(display = "Cannot use `HashMap` maps without std.
+(Set map_type: MapType::Slice, or no_std: false to fix.)")]
+    NoStdWithHashMaps,
+
+    /// Occurs when generating a config from a directory with an option
+    /// which only makes sense for a single const, or for arrays of a
+    /// single length.
+    #[fail(
+        display = "Cannot generate a config from a directory with {}.
+(Each file in the directory gets its own const, with arrays of any length.)",
+        _0
+    )]
+    ConfigDirWith(&'static str),
+
+    /// Occurs when an option needs a serde crate for the config's format
+    /// at runtime, and there isn't one, like dynamic loading for INI
+    /// files. Returned by
+    /// [`StructOptions::validate_for`](struct.StructOptions.html#method.validate_for).
+    #[fail(
+        display = "Cannot use {} with {} files.
+(There is no serde crate to load or serialize the format at runtime.)",
+        _1, _0
+    )]
+    FormatWith(String, &'static str),
+
+    /// Occurs when both `max_array_size` and `array_style` are set.
+    #[fail(display = "Cannot set both max_array_size and array_style.
+(Set max_array_size: 0, and use ArrayStyle::FixedUpTo instead, to fix.)")]
+    ConflictingArrayStyle,
+
+    /// Occurs when `root_mode` is `RootMode::ConstPerTopLevelTable`, and
+    /// an option needing a single root value was requested.
+    #[fail(
+        display = "Cannot generate a const per top-level table with {}.
+(These options need a single value for the whole config.)",
+        _0
+    )]
+    RootModeWith(&'static str),
+}
+
+impl OptionsError {
+    /// The names of the options which caused the error, starting with the
+    /// one which is invalid or conflicts with the others. These are also
+    /// the names of the `StructOptionsBuilder` setters.
+    pub fn option_names(&self) -> &'static [&'static str] {
+        match *self {
+            OptionsError::InvalidStructName(_) => &["struct_name"],
+            OptionsError::InvalidConstName(_) => &["const_name"],
+            OptionsError::InvalidIntoTarget(_) => &["impl_into"],
+            OptionsError::ConflictingDefault => &["impl_default", "derived_traits"],
+            OptionsError::DiffWithoutTraits => &["generate_diff", "derived_traits"],
+            OptionsError::OwnedVariantWithoutClone => &["generate_owned_variant", "derived_traits"],
+            OptionsError::OwnedVariantWithStaticStr => &["generate_owned_variant", "string_type"],
+            OptionsError::ClapWithStaticStr => &["derived_traits", "string_type"],
+            OptionsError::SerdeAttributesWithoutSerde => &["serde_attributes", "serde_support"],
+            OptionsError::ManualImplWith(option) => match option {
+                "serde_attributes" => &["serde_support", "serde_attributes"],
+                "tagged_unions" => &["serde_support", "tagged_unions"],
+                _ => &["serde_support", "flatten_style"],
+            },
+            OptionsError::MissingFieldsWithoutDeserialize => {
+                &["serde_missing_fields", "serde_support"]
+            }
+            OptionsError::StaticStrWithDynamicLoading => &["string_type", "dynamic_loading"],
+            OptionsError::StaticStrWithDeserialize => &["string_type", "serde_support"],
+            OptionsError::OwnedStringsInConst => {
+                &["string_type", "generate_const", "dynamic_loading"]
+            }
+            OptionsError::EnvOverridesWithoutSource => {
+                &["env_overrides", "generate_const", "generate_load_fns"]
+            }
+            OptionsError::StaticStrWithEnvOverrides => &["env_overrides", "string_type"],
+            OptionsError::ConflictingPathFilters(_) => &["include_paths", "exclude_paths"],
+            OptionsError::SliceMapsWithDynamicLoading => {
+                &["map_paths", "map_type", "dynamic_loading"]
+            }
+            OptionsError::BinaryPathsWithDynamicLoading => &["binary_paths", "dynamic_loading"],
+            OptionsError::IndexMapsWithDynamicLoading => &["index_map_paths", "dynamic_loading"],
+            OptionsError::ValueTransformsWithDynamicLoading => {
+                &["value_transforms", "dynamic_loading"]
+            }
+            OptionsError::ComputedFieldsWithDynamicLoading => {
+                &["computed_fields", "dynamic_loading"]
+            }
+            OptionsError::FlattenPathsWithDynamicLoading => &["flatten_paths", "dynamic_loading"],
+            OptionsError::SerdeFlattenWithoutSerde => &["flatten_style", "serde_support"],
+            OptionsError::InvalidFlattenJoiner(_) => &["flatten_joiner"],
+            OptionsError::InvalidRule(..) => &["validations"],
+            OptionsError::InvalidCfgPredicate(..) => &["cfg_sections"],
+            OptionsError::CfgSectionsWith(option) => match option {
+                "generate_builder" => &["cfg_sections", "generate_builder"],
+                "field_access" => &["cfg_sections", "field_access"],
+                "generate_diff" => &["cfg_sections", "generate_diff"],
+                "generate_get_by_path" => &["cfg_sections", "generate_get_by_path"],
+                "impl_into" => &["cfg_sections", "impl_into"],
+                _ => &["cfg_sections", "env_overrides"],
+            },
+            OptionsError::IncludesWithDynamicLoading => &["resolve_includes", "dynamic_loading"],
+            OptionsError::NoStdWithDynamicLoading => &["no_std", "dynamic_loading"],
+            OptionsError::EmbeddedWithDynamicPath => &["dynamic_path", "dynamic_loading"],
+            OptionsError::ArgumentPathWithEnvOverrides => &["dynamic_path", "env_overrides"],
+            OptionsError::TestsWithoutSerde => &["generate_tests", "serde_support"],
+            OptionsError::TestsWithoutConst => &["generate_tests", "generate_const"],
+            OptionsError::DisplayWithoutSerialize => &["impl_display", "serde_support"],
+            OptionsError::NoStdWithSourceDisplay => &["no_std", "impl_display"],
+            OptionsError::NoStdWithEnvOverrides => &["no_std", "env_overrides"],
+            OptionsError::NoStdWithHashMaps => &["no_std", "map_type"],
+            OptionsError::ConfigDirWith(option) => match option {
+                "generate_const" => &["generate_const"],
+                "generate_load_fns" => &["generate_load_fns"],
+                "impl_default" => &["impl_default"],
+                "generate_builder" => &["generate_builder"],
+                "serde_missing_fields" => &["serde_missing_fields"],
+                "env_overrides" => &["env_overrides"],
+                "max_array_size" => &["max_array_size"],
+                "array_style" => &["array_style"],
+                "impl_display" => &["impl_display"],
+                "generate_field_consts" => &["generate_field_consts"],
+                _ => &["root_mode"],
+            },
+            OptionsError::FormatWith(_, option) => match option {
+                "dynamic_loading" => &["dynamic_loading", "generate_load_fns"],
+                "impl_display" => &["impl_display"],
+                _ => &["generate_tests"],
+            },
+            OptionsError::ConflictingArrayStyle => &["max_array_size", "array_style"],
+            OptionsError::RootModeWith(option) => match option {
+                "generate_load_fns" => &["root_mode", "generate_load_fns"],
+                "impl_default" => &["root_mode", "impl_default"],
+                "generate_builder" => &["root_mode", "generate_builder"],
+                "generate_field_consts" => &["root_mode", "generate_field_consts"],
+                _ => &["root_mode", "env_overrides"],
+            },
+        }
+    }
+}
+
+impl GenerationError {
+    /// The dotted path of the field the error concerns, like
+    /// `server.port`, if it concerns a single field.
+    ///
+    /// As in `field_int_types` and the other options which take paths,
+    /// the elements of an array of structs share the path of the array.
+    pub fn path(&self) -> Option<&str> {
+        match *self {
+            GenerationError::HeterogenousArray(ref path, _)
+            | GenerationError::UntypedEmptyArray(ref path)
+            | GenerationError::UntypedOption(ref path)
+            | GenerationError::HeterogenousMap(ref path)
+            | GenerationError::MismatchedArrayLengths(ref path, _, _)
+            | GenerationError::FixedArrayTooLong(ref path, _)
+            | GenerationError::ConflictingArrayElement(ref path, _)
+            | GenerationError::InvalidEnumValue(ref path, _)
+            | GenerationError::InvalidEnumField(ref path)
+            | GenerationError::InvalidEnumName(ref path)
+            | GenerationError::InvalidEnumVariant(ref path, _)
+            | GenerationError::DuplicateEnumVariant(ref path, ..)
+            | GenerationError::InvalidNewtypeName(ref path, _)
+            | GenerationError::InvalidNewtypeField(ref path, _)
+            | GenerationError::InvalidTaggedUnionField(ref path)
+            | GenerationError::MissingTag(ref path, _)
+            | GenerationError::UnknownTag(ref path, ..)
+            | GenerationError::InvalidNestedStructName(ref path, _)
+            | GenerationError::InvalidDateTime(ref path, _)
+            | GenerationError::InvalidMapPath(ref path)
+            | GenerationError::InvalidIndexMapPath(ref path)
+            | GenerationError::InvalidIndexKey(ref path, _)
+            | GenerationError::MissingIndex(ref path, _)
+            | GenerationError::InvalidFlattenPath(ref path)
+            | GenerationError::FlattenConflict(ref path, _)
+            | GenerationError::InvalidIntegerField(ref path)
+            | GenerationError::IntegerOutOfRange(ref path, ..)
+            | GenerationError::IntegerTooLarge(ref path, _)
+            | GenerationError::MaxDepthExceeded { ref path, .. }
+            | GenerationError::UnsupportedRon { ref path, .. }
+            | GenerationError::RonTupleWithoutTuples { ref path, .. }
+            | GenerationError::MixedSignIntegers(ref path, ..)
+            | GenerationError::InvalidBinaryField(ref path)
+            | GenerationError::InvalidBase64(ref path, _)
+            | GenerationError::InvalidCharField(ref path)
+            | GenerationError::InvalidChar(ref path, _)
+            | GenerationError::InvalidVersionField(ref path)
+            | GenerationError::InvalidVersion(ref path, _)
+            | GenerationError::MissingEnvVar(ref path, _)
+            | GenerationError::InvalidByteSize(ref path, _)
+            | GenerationError::InvalidDuration(ref path, _)
+            | GenerationError::MissingComputedPath(ref path, _)
+            | GenerationError::InvalidComputedValue(ref path, ..)
+            | GenerationError::EmptyComputedValue(ref path, _)
+            | GenerationError::InvalidComputedField(ref path)
+            | GenerationError::NullValue(ref path)
+            | GenerationError::NonConstField(ref path)
+            | GenerationError::ConflictingSchemas(ref path)
+            | GenerationError::IncompatibleMerge(ref path)
+            | GenerationError::YamlDocumentConflict(_, ref path)
+            | GenerationError::UnsupportedYamlKey(ref path)
+            | GenerationError::DuplicateKey(ref path)
+            | GenerationError::DuplicateKeyAt(ref path, ..)
+            | GenerationError::NonTableTopLevelKey(ref path)
+            | GenerationError::MismatchedTopLevelTables(ref path, ..) => Some(path),
+            GenerationError::LimitExceeded(ExceededLimit { ref path, .. }) if !path.is_empty() => {
+                Some(path)
+            }
+            GenerationError::Located(ref error, _) => error.path(),
+            _ => None,
+        }
+    }
+
+    /// Where the key of the field the error concerns is in the config
+    /// file, if it's known.
+    pub fn location(&self) -> Option<&SourceLocation> {
+        match *self {
+            GenerationError::Located(_, ref location) => Some(location),
+            _ => None,
+        }
+    }
+
+    /// The error without its location, for matching on the kind of error.
+    pub fn unlocated(&self) -> &GenerationError {
+        match *self {
+            GenerationError::Located(ref error, _) => error,
+            ref error => error,
+        }
+    }
+
+    /// Describe the error over several lines, for printing from a build
+    /// script: the error itself, then the field it concerns or, if
+    /// parsing failed, the file and line where it failed, with the text
+    /// of that line. Located errors point at the field's key the same way.
+    ///
+    /// ```text
+    /// Parsing failed: expected `,` or `}` at line 3 column 5
+    ///   --> config.json:3:5
+    ///    |
+    ///  3 |     "port": 80
+    ///    |     ^
+    /// ```
+    pub fn to_diagnostic_string(&self) -> String {
+        let mut text = self.to_string();
+        if let GenerationError::ParseFailed(ref error) = *self {
+            text.push_str(&error.location_snippet());
+        } else if let GenerationError::Located(_, ref location) = *self {
+            text.push_str(&location_snippet(
+                Some(&location.file),
+                Some(location.span.line),
+                Some(location.span.column),
+                Some(&location.source_line),
+            ));
+        } else if let GenerationError::ValidationFailed(ref violations) = *self {
+            text = "Config values failed validation:".to_owned();
+            for violation in violations {
+                text.push_str(&format!("\n  - {}", violation));
+            }
+        } else if let Some(path) = self.path() {
+            text.push_str(&format!("\n  --> field `{}`", path));
+        }
+        text
+    }
+
+    /// Record the file being parsed in a parse error.
+    pub(crate) fn in_file(mut self, path: &Path) -> Self {
+        if let GenerationError::ParseFailed(ref mut error) = self {
+            error.file = Some(path.to_owned());
+        }
+        self
+    }
+}
+
+/// The details of a config which its format's parser rejected.
+#[derive(Debug)]
+pub struct ParseError {
+    /// The parser's description of the error.
+    pub message: String,
+
+    /// The file the config was read from, if it was read from a file.
+    pub file: Option<PathBuf>,
+
+    /// The line of the error, counting from 1, if the parser reported it.
+    pub line: Option<usize>,
+
+    /// The column of the error, counting from 1, if the parser reported
+    /// it.
+    pub column: Option<usize>,
+
+    /// The text of the line of the config containing the error.
+    pub source_line: Option<String>,
+
+    cause: Option<Box<dyn Fail>>,
+}
+
+impl ParseError {
+    /// Wrap an error from a parser, which found it at the given line and
+    /// column of `source`.
+    pub(crate) fn new<E: Fail>(
+        cause: E,
+        source: &str,
+        line: Option<usize>,
+        column: Option<usize>,
+    ) -> ParseError {
+        let mut error = ParseError::from_message(cause.to_string(), source, line, column);
+        error.cause = Some(Box::new(cause));
+        error
+    }
+
+    /// An error found by this crate while parsing, rather than by a
+    /// parser.
+    pub(crate) fn from_message(
+        message: String,
+        source: &str,
+        line: Option<usize>,
+        column: Option<usize>,
+    ) -> ParseError {
+        let source_line = line
+            .and_then(|line| line.checked_sub(1))
+            .and_then(|index| source.lines().nth(index))
+            .map(str::to_owned);
+
+        ParseError {
+            message,
+            file: None,
+            line,
+            column,
+            source_line,
+            cause: None,
+        }
+    }
+
+    /// The file and line of the error, and the text of that line with
+    /// the column marked, for `to_diagnostic_string`.
+    fn location_snippet(&self) -> String {
+        location_snippet(
+            self.file.as_deref(),
+            self.line,
+            self.column,
+            self.source_line.as_deref(),
+        )
+    }
+}
+
+/// A value which broke one of the rules in `validations`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The path of the value, with the indices of array elements, like
+    /// `servers.1.port`.
+    pub path: String,
+
+    /// The rule it broke.
+    pub rule: Rule,
+
+    /// The value, as it would be written in a config.
+    pub value: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is {}, which is not {}",
+            self.path, self.value, self.rule
+        )
+    }
+}
+
+/// One of the `resource_limits` which a config passed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExceededLimit {
+    /// The name of the limit, like `max_nodes`.
+    pub limit: &'static str,
+
+    /// Its value.
+    pub max: usize,
+
+    /// The path of the value the config had been read up to, or an empty
+    /// string for `max_source_bytes`, which is checked first.
+    pub path: String,
+}
+
+impl fmt::Display for ExceededLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The config passed resource_limits.{}", self.limit)?;
+        if !self.path.is_empty() {
+            write!(f, " at `{}`", self.path)?;
+        }
+        write!(f, ", which is {}", self.max)
+    }
+}
+
+/// Two fields which share a newtype in `newtype_paths`, but hold
+/// numbers of different types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewtypeConflict {
+    /// The name of the newtype.
+    pub name: String,
+
+    /// The type it would wrap for the first field found.
+    pub first_type: String,
+
+    /// The path of the first field found.
+    pub first_path: String,
+
+    /// The type it would wrap for the other field.
+    pub second_type: String,
+
+    /// The path of the other field.
+    pub second_path: String,
+}
+
+impl fmt::Display for NewtypeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Newtype `{}` would wrap `{}` for `{}`, but `{}` for `{}`",
+            self.name, self.first_type, self.first_path, self.second_type, self.second_path
+        )
+    }
+}
+
+/// Where the key of a field is in a config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// The config file.
+    pub file: PathBuf,
+
+    /// The line and column of the key.
+    pub span: Span,
+
+    /// The text of the line containing the key.
+    pub source_line: String,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.file.display(), self.span)
+    }
+}
+
+/// A file, line and column, and the text of that line with the column
+/// marked, for `to_diagnostic_string`.
+fn location_snippet(
+    file: Option<&Path>,
+    line: Option<usize>,
+    column: Option<usize>,
+    source_line: Option<&str>,
+) -> String {
+    let file_name = file
+        .map(|file| file.display().to_string())
+        .unwrap_or_else(|| "<config>".to_owned());
+
+    let line = match line {
+        Some(line) => line,
+        None if file.is_some() => return format!("\n  --> {}", file_name),
+        None => return String::new(),
+    };
+    let mut text = match column {
+        Some(column) => format!("\n  --> {}:{}:{}", file_name, line, column),
+        None => format!("\n  --> {}:{}", file_name, line),
+    };
+
+    if let Some(source_line) = source_line {
+        let margin = " ".repeat(line.to_string().len());
+        text.push_str(&format!("\n {} |\n {} | {}", margin, line, source_line));
+        if let Some(column) = column {
+            // Keep tabs, so the marker lines up with the text above.
+            let indent = source_line
+                .chars()
+                .take(column.saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            text.push_str(&format!("\n {} | {}^", margin, indent));
+        }
+    }
+
+    text
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Fail for ParseError {
+    fn cause(&self) -> Option<&dyn Fail> {
+        self.cause.as_deref()
+    }
 }
 
 impl From<GenerationError> for Error {
@@ -84,6 +1432,11 @@ impl From<IOError> for Error {
     }
 }
 
+/// Make an IO error on the file at `path` into an `Error` naming the file.
+pub(crate) fn file_error(path: &Path) -> impl FnOnce(IOError) -> Error + '_ {
+    move |error| Error::FileIO(path.display().to_string(), error)
+}
+
 impl From<OptionsError> for GenerationError {
     fn from(error: OptionsError) -> Self {
         GenerationError::StructOptions(error)
@@ -0,0 +1,217 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    cfg_sections::{cfg_attribute, struct_cfgs},
+    fixed_arrays::ArrayEmitContext,
+    generation::{declared_structs, string_literal, type_string, value_string},
+    int_types::integer_value,
+    naming::upper_snake_case,
+    options::StructOptions,
+    paths, root_arrays,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Generate an `impl` for each struct with an associated const holding
+/// the value of each of its string and integer fields, like
+/// `Config::MODE`, for matching on and using in const contexts.
+///
+/// Only structs with a single value in the config get consts: not the
+/// elements of arrays or maps, or structs shared by several fields after
+/// `deduplicate_structs`. Neither do configs whose root is an array.
+///
+/// The types of the consts come from the structs declared from `schema`,
+/// and the values from `config`.
+pub fn generate_field_consts(
+    schema: &GenericStruct,
+    config: &GenericStruct,
+    options: &StructOptions,
+) -> String {
+    if root_arrays::root_elements(config).is_some() {
+        return String::new();
+    }
+
+    let mut uses = BTreeMap::new();
+    let mut paths = BTreeMap::new();
+    collect_structs(config, "", &mut uses, &mut paths);
+
+    let declared = declared_structs(schema);
+    let cfgs = struct_cfgs(schema);
+
+    let mut code = String::new();
+    for declared_struct in &declared {
+        let name = &declared_struct.struct_name;
+        let (struct_value, path) = match paths.get(name) {
+            Some(&(struct_value, ref path)) if uses[name] == 1 => (struct_value, path),
+            _ => continue,
+        };
+
+        let consts = struct_consts(declared_struct, struct_value, path, options);
+        if consts.is_empty() {
+            continue;
+        }
+        code.push_str(&format!(
+            "{}impl {} {{\n{}}}\n\n",
+            cfg_attribute(cfgs.get(name), 0),
+            name,
+            consts
+        ));
+    }
+    code
+}
+
+/// Count the values of each struct, and record the first value of each
+/// struct which is a field, with the path of its key.
+fn collect_structs<'a>(
+    struct_value: &'a GenericStruct,
+    path: &str,
+    uses: &mut BTreeMap<String, usize>,
+    paths: &mut BTreeMap<String, (&'a GenericStruct, String)>,
+) {
+    *uses.entry(struct_value.struct_name.clone()).or_insert(0) += 1;
+    paths
+        .entry(struct_value.struct_name.clone())
+        .or_insert_with(|| (struct_value, path.to_owned()));
+
+    for (name, value) in &struct_value.fields {
+        let path = paths::join(path, struct_value.original_key(name));
+        collect_value_structs(value, Some(&path), uses, paths);
+    }
+}
+
+/// Like `collect_structs`, for a value which is a field if it has a
+/// `path`, or an element otherwise.
+fn collect_value_structs<'a>(
+    value: &'a GenericValue,
+    path: Option<&str>,
+    uses: &mut BTreeMap<String, usize>,
+    paths: &mut BTreeMap<String, (&'a GenericStruct, String)>,
+) {
+    match *value {
+        GenericValue::Struct(ref struct_value) => match path {
+            Some(path) => collect_structs(struct_value, path, uses, paths),
+            // Elements count as a second use, so they never get consts.
+            None => {
+                *uses.entry(struct_value.struct_name.clone()).or_insert(0) += 2;
+                for value in struct_value.fields.values() {
+                    collect_value_structs(value, None, uses, paths);
+                }
+            }
+        },
+        GenericValue::Option(Some(ref value)) => collect_value_structs(value, path, uses, paths),
+        GenericValue::Array(ref values)
+        | GenericValue::Tuple(ref values)
+        | GenericValue::Map(GenericMap { ref values, .. }) => {
+            for value in values {
+                collect_value_structs(value, None, uses, paths);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// The consts for the fields of a struct, each on its own lines.
+///
+/// Fields whose names give the same const name, like `logLevel` and
+/// `log_level`, are told apart by their whole key path, then numbered.
+fn struct_consts(
+    declared_struct: &GenericStruct,
+    struct_value: &GenericStruct,
+    path: &str,
+    options: &StructOptions,
+) -> String {
+    let mut taken = BTreeSet::new();
+    let mut consts = String::new();
+    for (name, declared_value) in declared_struct.ordered_fields(options.field_order) {
+        let (type_name, literal) = match (declared_value, struct_value.fields.get(name)) {
+            (&GenericValue::String(_), Some(GenericValue::String(value))) => {
+                ("&'static str".to_owned(), string_literal(value))
+            }
+            (declared_value, Some(value)) if is_integer(declared_value) && is_integer(value) => (
+                type_string(declared_value, options),
+                value_string(value, ArrayEmitContext::new(options, 0), options),
+            ),
+            _ => continue,
+        };
+
+        let field_name = name.strip_prefix("r#").unwrap_or(name);
+        let key_path = paths::join(path, struct_value.original_key(name));
+        let path_name = upper_snake_case(&key_path);
+        let const_name = vec![upper_snake_case(field_name), path_name.clone()]
+            .into_iter()
+            .chain((2..).map(|n| format!("{}_{}", path_name, n)))
+            .find(|const_name| !taken.contains(const_name))
+            .expect("some name is free");
+        taken.insert(const_name.clone());
+
+        consts.push_str(&format!(
+            "{}    pub const {}: {} = {};\n",
+            cfg_attribute(struct_value.field_cfgs.get(name), 4),
+            const_name,
+            type_name,
+            literal
+        ));
+    }
+    consts
+}
+
+fn is_integer(value: &GenericValue) -> bool {
+    integer_value(value).is_some() || matches!(*value, GenericValue::U128(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_struct(name: &str, fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: name.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    #[test]
+    fn colliding_names_use_the_key_path() {
+        let server = make_struct(
+            "_Config__server",
+            vec![
+                ("logLevel", GenericValue::String("info".to_owned())),
+                ("log_level", GenericValue::I64(2)),
+            ],
+        );
+        let config = make_struct("Config", vec![("server", GenericValue::Struct(server))]);
+
+        let code = generate_field_consts(&config, &config, &StructOptions::default());
+
+        assert!(code.contains("impl _Config__server {\n"));
+        assert!(code.contains("    pub const LOG_LEVEL: &'static str = \"info\";\n"));
+        assert!(code.contains("    pub const SERVER_LOG_LEVEL: i64 = 2;\n"));
+        assert!(!code.contains("impl Config"));
+    }
+
+    #[test]
+    fn array_elements_get_no_consts() {
+        let element =
+            |port| make_struct("_Config__servers", vec![("port", GenericValue::I64(port))]);
+        let config = make_struct(
+            "Config",
+            vec![
+                (
+                    "servers",
+                    GenericValue::Array(vec![
+                        GenericValue::Struct(element(80)),
+                        GenericValue::Struct(element(81)),
+                    ]),
+                ),
+                ("version", GenericValue::U8(5)),
+            ],
+        );
+
+        let code = generate_field_consts(&config, &config, &StructOptions::default());
+
+        assert_eq!(code, "impl Config {\n    pub const VERSION: u8 = 5;\n}\n\n");
+    }
+}
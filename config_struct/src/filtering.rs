@@ -0,0 +1,204 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    diagnostics::{Warning, WarningKind},
+    options::StructOptions,
+    paths,
+    value::{GenericStruct, GenericValue},
+};
+
+/// Remove the fields matching `options.exclude_paths`, and, if
+/// `options.include_paths` is given, any fields which don't match it.
+///
+/// Fields containing an included path are kept, with only the included
+/// fields inside them. Exclude patterns which don't match any field are
+/// reported as warnings.
+pub fn apply_path_filters(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+    warnings: &mut Vec<Warning>,
+) {
+    if options.exclude_paths.is_empty() && options.include_paths.is_empty() {
+        return;
+    }
+
+    let mut used_excludes = BTreeSet::new();
+    filter_struct(struct_value, "", false, options, &mut used_excludes);
+
+    for pattern in &options.exclude_paths {
+        if !used_excludes.contains(pattern.as_str()) {
+            warnings.push(Warning::new(
+                WarningKind::UnusedExcludePath,
+                pattern.as_str(),
+                format!(
+                    "`{}` is listed in `exclude_paths`, but does not match any field.",
+                    pattern
+                ),
+            ));
+        }
+    }
+}
+
+fn filter_struct<'a>(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    included: bool,
+    options: &'a StructOptions,
+    used_excludes: &mut BTreeSet<&'a str>,
+) {
+    struct_value.fields.retain(|key, value| {
+        let path = paths::join(parent_path, key);
+
+        if let Some(pattern) = options
+            .exclude_paths
+            .iter()
+            .find(|pattern| paths::matches(pattern, &path))
+        {
+            used_excludes.insert(pattern);
+            return false;
+        }
+
+        let included = included
+            || options.include_paths.is_empty()
+            || options
+                .include_paths
+                .iter()
+                .any(|pattern| paths::matches(pattern, &path));
+        let contains_included = options
+            .include_paths
+            .iter()
+            .any(|pattern| paths::matches_within(&path, pattern) && *pattern != path);
+
+        if included || contains_included {
+            filter_value(value, &path, included, options, used_excludes);
+            true
+        } else {
+            false
+        }
+    });
+}
+
+fn filter_value<'a>(
+    value: &mut GenericValue,
+    path: &str,
+    included: bool,
+    options: &'a StructOptions,
+    used_excludes: &mut BTreeSet<&'a str>,
+) {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => {
+            filter_struct(struct_value, path, included, options, used_excludes)
+        }
+        GenericValue::Option(Some(ref mut value)) => {
+            filter_value(value, path, included, options, used_excludes)
+        }
+        GenericValue::Array(ref mut values) | GenericValue::Tuple(ref mut values) => {
+            for value in values {
+                filter_value(value, path, included, options, used_excludes);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_struct(name: &str, fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: name.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    fn config() -> GenericStruct {
+        make_struct(
+            "Config",
+            vec![
+                ("name", GenericValue::String("x".into())),
+                (
+                    "server",
+                    GenericValue::Struct(make_struct(
+                        "_Config__server",
+                        vec![
+                            ("host", GenericValue::String("a".into())),
+                            ("port", GenericValue::I64(80)),
+                        ],
+                    )),
+                ),
+                (
+                    "ui",
+                    GenericValue::Struct(make_struct(
+                        "_Config__ui",
+                        vec![("theme", GenericValue::String("dark".into()))],
+                    )),
+                ),
+            ],
+        )
+    }
+
+    fn field_names(struct_value: &GenericStruct) -> Vec<&str> {
+        struct_value.fields.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn excluded_fields_are_removed() {
+        let options = StructOptions {
+            exclude_paths: vec!["server.host".to_owned(), "ui.*".to_owned()],
+            ..StructOptions::default()
+        };
+        let mut config = config();
+        let mut warnings = Vec::new();
+
+        apply_path_filters(&mut config, &options, &mut warnings);
+
+        assert_eq!(field_names(&config), vec!["name", "server", "ui"]);
+        match config.fields["server"] {
+            GenericValue::Struct(ref server) => assert_eq!(field_names(server), vec!["port"]),
+            ref other => panic!("Expected struct, found {:?}", other),
+        }
+        match config.fields["ui"] {
+            GenericValue::Struct(ref ui) => assert!(ui.fields.is_empty()),
+            ref other => panic!("Expected struct, found {:?}", other),
+        }
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn only_included_fields_are_kept() {
+        let options = StructOptions {
+            include_paths: vec!["name".to_owned(), "server.port".to_owned()],
+            ..StructOptions::default()
+        };
+        let mut config = config();
+
+        apply_path_filters(&mut config, &options, &mut Vec::new());
+
+        assert_eq!(field_names(&config), vec!["name", "server"]);
+        match config.fields["server"] {
+            GenericValue::Struct(ref server) => assert_eq!(field_names(server), vec!["port"]),
+            ref other => panic!("Expected struct, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unmatched_excludes_are_warnings() {
+        let options = StructOptions {
+            exclude_paths: vec!["secrets".to_owned()],
+            ..StructOptions::default()
+        };
+        let mut config = config();
+        let mut warnings = Vec::new();
+
+        apply_path_filters(&mut config, &options, &mut warnings);
+
+        assert_eq!(config.fields.len(), 3);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].path, "secrets");
+    }
+}
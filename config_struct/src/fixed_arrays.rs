@@ -0,0 +1,164 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    error::GenerationError,
+    options::{ArrayStyle, StructOptions},
+    paths,
+    unification::unwrap_option,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Serde only implements its traits for fixed-size arrays of up to this
+/// many elements.
+const SERDE_MAX_ARRAY_LEN: usize = 32;
+
+/// Where a value is being written, and which of its arrays are written as
+/// fixed-size arrays.
+///
+/// The longest fixed-size array is resolved once from `max_array_size` and
+/// `array_style`, where a `0` means there are none, so the writers only
+/// ever see the one answer for each length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArrayEmitContext {
+    /// How many spaces the lines of the value are indented by.
+    pub indentation: usize,
+    max_fixed_len: usize,
+}
+
+impl ArrayEmitContext {
+    pub fn new(options: &StructOptions, indentation: usize) -> Self {
+        let max_fixed_len = match options.array_style {
+            ArrayStyle::AlwaysCow => options.max_array_size,
+            ArrayStyle::FixedUpTo(max_len) => max_len,
+            ArrayStyle::AlwaysFixed => usize::MAX,
+        };
+        ArrayEmitContext {
+            indentation,
+            max_fixed_len,
+        }
+    }
+
+    /// The context for a value indented to `indentation`.
+    pub fn at(self, indentation: usize) -> Self {
+        ArrayEmitContext {
+            indentation,
+            ..self
+        }
+    }
+
+    /// The context for a value one level deeper than this one.
+    pub fn indented(self) -> Self {
+        self.at(self.indentation + 4)
+    }
+
+    /// Whether an array with `len` elements is a fixed-size array, rather
+    /// than a slice. Empty arrays are always slices.
+    pub fn uses_fixed_array(self, len: usize) -> bool {
+        len != 0 && len <= self.max_fixed_len
+    }
+}
+
+/// Check that fixed-size arrays can be generated for the arrays which
+/// `options.array_style` asks for.
+///
+/// Values which share a type, like the same field of every struct in an
+/// array, are checked together: their arrays need the same length for
+/// their fixed-size array types to agree.
+pub fn check_fixed_arrays(
+    struct_value: &GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if !options.uses_fixed_arrays() {
+        return Ok(());
+    }
+
+    check_structs(&[struct_value], "", options)
+}
+
+fn check_structs(
+    structs: &[&GenericStruct],
+    parent_path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let keys = structs
+        .iter()
+        .flat_map(|struct_value| struct_value.fields.keys())
+        .collect::<BTreeSet<_>>();
+
+    for key in keys {
+        let values = structs
+            .iter()
+            .filter_map(|struct_value| struct_value.fields.get(key))
+            .collect::<Vec<_>>();
+        let path = paths::join(parent_path, structs[0].original_key(key));
+        check_values(&values, &path, options)?;
+    }
+    Ok(())
+}
+
+fn check_values(
+    values: &[&GenericValue],
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let values = values
+        .iter()
+        .map(|value| unwrap_option(value))
+        .collect::<Vec<_>>();
+
+    let mut lengths = BTreeSet::new();
+    let mut elements = Vec::new();
+    let mut structs = Vec::new();
+    let mut tuples = Vec::new();
+    for value in &values {
+        match **value {
+            GenericValue::Array(ref values) => {
+                lengths.insert(values.len());
+                elements.extend(values);
+            }
+            GenericValue::EmptyArray(_) => {
+                lengths.insert(0);
+            }
+            GenericValue::Map(GenericMap { ref values, .. }) => elements.extend(values),
+            GenericValue::Struct(ref struct_value) => structs.push(struct_value),
+            GenericValue::Tuple(ref values) => tuples.push(values),
+            _ => (),
+        }
+    }
+
+    if lengths.iter().any(|&len| options.uses_fixed_array(len)) {
+        if lengths.len() > 1 {
+            let slices_setting = match options.array_style {
+                ArrayStyle::AlwaysCow => "max_array_size = 0",
+                _ => "array_style = ArrayStyle::AlwaysCow",
+            };
+            return Err(GenerationError::MismatchedArrayLengths(
+                path.into(),
+                lengths.into_iter().collect(),
+                slices_setting,
+            ));
+        }
+
+        let len = lengths.into_iter().next().expect("some array has a length");
+        if len > SERDE_MAX_ARRAY_LEN && options.serde_support.should_derive_ser_de().is_some() {
+            return Err(GenerationError::FixedArrayTooLong(path.into(), len));
+        }
+    }
+
+    if !elements.is_empty() {
+        check_values(&elements, path, options)?;
+    }
+    if !structs.is_empty() {
+        check_structs(&structs, path, options)?;
+    }
+    if let Some(first) = tuples.first() {
+        for index in 0..first.len() {
+            let values = tuples
+                .iter()
+                .filter_map(|values| values.get(index))
+                .collect::<Vec<_>>();
+            check_values(&values, path, options)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use crate::{
+    error::GenerationError,
+    options::{FlattenStyle, StructOptions},
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Flatten the tables at the paths listed in `options.flatten_paths` into
+/// their parents, as `options.flatten_style` says.
+///
+/// Paths are made of the original keys, so this can run after fields have
+/// been renamed. Tables listed inside other listed tables are flattened
+/// first, so that their fields end up in the outermost parent.
+pub fn apply_flatten_paths(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if options.flatten_paths.is_empty() {
+        return Ok(());
+    }
+
+    apply_to_struct(struct_value, "", options)
+}
+
+fn apply_to_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let mut listed = Vec::new();
+
+    for (name, value) in &mut struct_value.fields {
+        let key = struct_value
+            .original_keys
+            .get(name)
+            .map(String::as_str)
+            .unwrap_or(name);
+        let path = paths::join(parent_path, key);
+
+        apply_to_value(value, &path, options)?;
+        if options.flatten_paths.contains(&path) {
+            listed.push((name.clone(), path));
+        }
+    }
+
+    for (name, path) in listed {
+        match options.flatten_style {
+            FlattenStyle::Fields => {
+                flatten_fields(struct_value, &name, &path, parent_path, options)?
+            }
+            FlattenStyle::SerdeFlatten => mark_flattened(struct_value, &name, &path, parent_path)?,
+        }
+    }
+    Ok(())
+}
+
+fn apply_to_value(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => apply_to_struct(struct_value, path, options),
+        GenericValue::Option(Some(ref mut value)) => apply_to_value(value, path, options),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Tuple(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                apply_to_value(value, path, options)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Replace the table in the field `name` with its fields, renamed with the
+/// name of the table and `options.flatten_joiner`.
+fn flatten_fields(
+    struct_value: &mut GenericStruct,
+    name: &str,
+    path: &str,
+    parent_path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let nested = match struct_value.fields.remove(name) {
+        Some(GenericValue::Struct(nested)) => nested,
+        _ => return Err(GenerationError::InvalidFlattenPath(path.into())),
+    };
+    let key = struct_value
+        .original_keys
+        .remove(name)
+        .unwrap_or_else(|| name.to_owned());
+    struct_value.field_docs.remove(name);
+    struct_value.field_spans.remove(name);
+    let cfg = struct_value.field_cfgs.remove(name);
+
+    let joined_name = |field: &str| format!("{}{}{}", name, options.flatten_joiner, field);
+
+    for (field, value) in &nested.fields {
+        let joined = joined_name(field);
+        if struct_value.fields.contains_key(&joined) {
+            return Err(GenerationError::FlattenConflict(
+                paths::join(path, nested.original_key(field)),
+                paths::join(parent_path, struct_value.original_key(&joined)),
+            ));
+        }
+
+        if let Some(doc) = nested.field_docs.get(field) {
+            struct_value.field_docs.insert(joined.clone(), doc.clone());
+        }
+        if let Some(span) = nested.field_spans.get(field) {
+            struct_value.field_spans.insert(joined.clone(), *span);
+        }
+        let field_cfg = match (cfg.as_ref(), nested.field_cfgs.get(field)) {
+            (Some(outer), Some(inner)) => Some(format!("all({}, {})", outer, inner)),
+            (outer, inner) => outer.or(inner).cloned(),
+        };
+        if let Some(field_cfg) = field_cfg {
+            struct_value.field_cfgs.insert(joined.clone(), field_cfg);
+        }
+        if nested.flattened_fields.contains(field) {
+            struct_value.flattened_fields.insert(joined.clone());
+        }
+        if nested.absent_fields.contains(field) {
+            struct_value.absent_fields.insert(joined.clone());
+        }
+        struct_value.fields.insert(joined, value.clone());
+    }
+
+    // The flattened fields take the place of the table in source order.
+    if let Some(position) = struct_value
+        .field_order
+        .iter()
+        .position(|ordered| *ordered == key)
+    {
+        let keys = nested.field_order.iter().filter_map(|ordered| {
+            nested
+                .fields
+                .keys()
+                .find(|field| nested.original_key(field) == ordered)
+                .map(|field| joined_name(field))
+        });
+        struct_value
+            .field_order
+            .splice(position..=position, keys.collect::<Vec<_>>());
+    }
+
+    Ok(())
+}
+
+/// Mark the table in the field `name` to be flattened by serde, as long as
+/// none of its keys are already keys of its parent.
+fn mark_flattened(
+    struct_value: &mut GenericStruct,
+    name: &str,
+    path: &str,
+    parent_path: &str,
+) -> Result<(), GenerationError> {
+    let nested = match struct_value.fields.get(name) {
+        Some(GenericValue::Struct(nested)) => nested,
+        _ => return Err(GenerationError::InvalidFlattenPath(path.into())),
+    };
+
+    let sibling_keys = struct_value
+        .fields
+        .iter()
+        .filter(|(sibling, _)| *sibling != name)
+        .flat_map(|(sibling, value)| match *value {
+            GenericValue::Struct(ref flattened)
+                if struct_value.flattened_fields.contains(sibling) =>
+            {
+                flattened
+                    .fields
+                    .keys()
+                    .map(|field| flattened.original_key(field))
+                    .collect()
+            }
+            _ => vec![struct_value.original_key(sibling)],
+        })
+        .collect::<Vec<_>>();
+
+    for field in nested.fields.keys() {
+        let key = nested.original_key(field);
+        if sibling_keys.contains(&key) {
+            return Err(GenerationError::FlattenConflict(
+                paths::join(path, key),
+                paths::join(parent_path, key),
+            ));
+        }
+    }
+
+    struct_value.flattened_fields.insert(name.to_owned());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_struct(name: &str, fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: name.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    fn options_with_flatten(path: &str, flatten_style: FlattenStyle) -> StructOptions {
+        StructOptions {
+            flatten_paths: vec![path.to_owned()],
+            flatten_style,
+            ..StructOptions::default()
+        }
+    }
+
+    fn limits(extra: Vec<(&str, GenericValue)>) -> GenericStruct {
+        let mut fields = vec![(
+            "limits",
+            GenericValue::Struct(make_struct(
+                "_Config__limits",
+                vec![
+                    ("max", GenericValue::I64(10)),
+                    ("min", GenericValue::I64(1)),
+                ],
+            )),
+        )];
+        fields.extend(extra);
+        make_struct("Config", fields)
+    }
+
+    #[test]
+    fn tables_become_joined_fields() {
+        let mut config = limits(vec![]);
+        config.field_order = vec!["limits".to_owned()];
+        if let Some(GenericValue::Struct(ref mut limits)) = config.fields.get_mut("limits") {
+            limits.field_order = vec!["min".to_owned(), "max".to_owned()];
+        }
+
+        apply_flatten_paths(
+            &mut config,
+            &options_with_flatten("limits", FlattenStyle::Fields),
+        )
+        .unwrap();
+
+        let names = config.fields.keys().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(names, ["limits_max", "limits_min"]);
+        assert_eq!(config.field_order, ["limits_min", "limits_max"]);
+    }
+
+    #[test]
+    fn joined_names_must_be_free() {
+        let mut config = limits(vec![("limits_max", GenericValue::I64(0))]);
+        match apply_flatten_paths(
+            &mut config,
+            &options_with_flatten("limits", FlattenStyle::Fields),
+        ) {
+            Err(GenerationError::FlattenConflict(flattened, existing)) => {
+                assert_eq!(
+                    (flattened.as_str(), existing.as_str()),
+                    ("limits.max", "limits_max")
+                )
+            }
+            other => panic!("Expected conflict error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn serde_flattened_keys_must_be_free() {
+        let mut config = limits(vec![]);
+        apply_flatten_paths(
+            &mut config,
+            &options_with_flatten("limits", FlattenStyle::SerdeFlatten),
+        )
+        .unwrap();
+        assert!(config.flattened_fields.contains("limits"));
+
+        let mut config = limits(vec![("min", GenericValue::I64(0))]);
+        match apply_flatten_paths(
+            &mut config,
+            &options_with_flatten("limits", FlattenStyle::SerdeFlatten),
+        ) {
+            Err(GenerationError::FlattenConflict(flattened, existing)) => {
+                assert_eq!(
+                    (flattened.as_str(), existing.as_str()),
+                    ("limits.min", "min")
+                )
+            }
+            other => panic!("Expected conflict error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn only_tables_are_flattened() {
+        let mut config = limits(vec![("name", GenericValue::String("x".to_owned()))]);
+        match apply_flatten_paths(
+            &mut config,
+            &options_with_flatten("name", FlattenStyle::Fields),
+        ) {
+            Err(GenerationError::InvalidFlattenPath(path)) => assert_eq!(path, "name"),
+            other => panic!("Expected invalid path error, found {:?}", other),
+        }
+    }
+}
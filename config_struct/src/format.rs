@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::{fmt, path::Path, str::FromStr};
 
 use crate::error::*;
 
@@ -7,10 +7,15 @@ use crate::error::*;
 /// The variants that exist correspond to the features that have been enabled.
 /// For example, if the `json-parsing` feature is not enabled, then the
 /// `Format::Json` variant will not exist.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Format {
+    #[cfg(feature = "ini-parsing")]
+    Ini,
     #[cfg(feature = "json-parsing")]
     Json,
+    /// JSON5, which includes JSON with comments and trailing commas.
+    #[cfg(feature = "json5-parsing")]
+    Json5,
     #[cfg(feature = "ron-parsing")]
     Ron,
     #[cfg(feature = "toml-parsing")]
@@ -20,24 +25,197 @@ pub enum Format {
 }
 
 impl Format {
+    /// All of the formats enabled by features.
+    pub const ENABLED: &'static [Format] = &[
+        #[cfg(feature = "ini-parsing")]
+        Format::Ini,
+        #[cfg(feature = "json-parsing")]
+        Format::Json,
+        #[cfg(feature = "json5-parsing")]
+        Format::Json5,
+        #[cfg(feature = "ron-parsing")]
+        Format::Ron,
+        #[cfg(feature = "toml-parsing")]
+        Format::Toml,
+        #[cfg(feature = "yaml-parsing")]
+        Format::Yaml,
+    ];
+
     pub fn from_filename(filename: &Path) -> Result<Self, GenerationError> {
         match filename.extension() {
-            Some(ext) => match ext.to_string_lossy().as_ref() {
-                #[cfg(feature = "json-parsing")]
-                "json" => Ok(Format::Json),
+            Some(ext) => ext.to_string_lossy().parse(),
+            None => Err(GenerationError::UnknownInputFormat("<none>".into())),
+        }
+    }
+
+    /// Guess the format of a config from its contents.
+    ///
+    /// This only looks at the start of the source, so it is a heuristic
+    /// rather than a guarantee that the source will parse. Only enabled
+    /// formats are returned.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use config_struct::Format;
+    ///
+    /// assert_eq!(Format::detect("name = \"Config\""), Some(Format::Toml));
+    /// ```
+    pub fn detect(source: &str) -> Option<Self> {
+        let line = source
+            .lines()
+            .map(str::trim)
+            .find(|line| !(line.is_empty() || line.starts_with('#') || line.starts_with("//")))?;
 
-                #[cfg(feature = "ron-parsing")]
-                "ron" => Ok(Format::Ron),
+        let guess = if line.starts_with('{') {
+            "json"
+        } else if line.starts_with('(') || line.ends_with('(') {
+            "ron"
+        } else if line.starts_with("---") || line.starts_with("- ") {
+            "yaml"
+        } else if line.starts_with('[') {
+            if is_toml_table_header(line) {
+                "toml"
+            } else {
+                "json"
+            }
+        } else {
+            match (line.find('='), line.find(':')) {
+                (Some(equals), Some(colon)) if equals < colon => "toml",
+                (Some(_), None) => "toml",
+                (_, Some(_)) => "yaml",
+                _ => return None,
+            }
+        };
 
-                #[cfg(feature = "toml-parsing")]
-                "toml" => Ok(Format::Toml),
+        guess.parse().ok()
+    }
+}
 
-                #[cfg(feature = "yaml-parsing")]
-                "yaml" | "yml" => Ok(Format::Yaml),
+fn is_toml_table_header(line: &str) -> bool {
+    let line = match line.find('#') {
+        Some(comment) => line[..comment].trim_end(),
+        None => line,
+    };
 
-                other => Err(GenerationError::UnknownInputFormat(other.into())),
-            },
-            None => Err(GenerationError::UnknownInputFormat("<none>".into())),
+    let name = line.trim_start_matches('[').trim_end_matches(']').trim();
+
+    line.ends_with(']')
+        && !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || "_-.\" ".contains(c))
+}
+
+impl FromStr for Format {
+    type Err = GenerationError;
+
+    /// Parse a format from its name or file extension, such as `"json"`
+    /// or `"yml"`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_ref() {
+            #[cfg(feature = "ini-parsing")]
+            "ini" | "cfg" | "properties" => Ok(Format::Ini),
+
+            #[cfg(feature = "json-parsing")]
+            "json" => Ok(Format::Json),
+
+            #[cfg(feature = "json5-parsing")]
+            "json5" | "jsonc" => Ok(Format::Json5),
+
+            #[cfg(feature = "ron-parsing")]
+            "ron" => Ok(Format::Ron),
+
+            #[cfg(feature = "toml-parsing")]
+            "toml" => Ok(Format::Toml),
+
+            #[cfg(feature = "yaml-parsing")]
+            "yaml" | "yml" => Ok(Format::Yaml),
+
+            _ => Err(GenerationError::UnknownInputFormat(name.into())),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            #[cfg(feature = "ini-parsing")]
+            Format::Ini => "ini",
+
+            #[cfg(feature = "json-parsing")]
+            Format::Json => "json",
+
+            #[cfg(feature = "json5-parsing")]
+            Format::Json5 => "json5",
+
+            #[cfg(feature = "ron-parsing")]
+            Format::Ron => "ron",
+
+            #[cfg(feature = "toml-parsing")]
+            Format::Toml => "toml",
+
+            #[cfg(feature = "yaml-parsing")]
+            Format::Yaml => "yaml",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(all(
+        feature = "json-parsing",
+        feature = "ron-parsing",
+        feature = "toml-parsing",
+        feature = "yaml-parsing"
+    ))]
+    fn parse_format_names() {
+        assert_eq!("json".parse::<Format>().unwrap(), Format::Json);
+        assert_eq!("RON".parse::<Format>().unwrap(), Format::Ron);
+        assert_eq!("toml".parse::<Format>().unwrap(), Format::Toml);
+        assert_eq!("yml".parse::<Format>().unwrap(), Format::Yaml);
+        assert!("xml".parse::<Format>().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "json5-parsing")]
+    fn json5_extensions() {
+        for name in &["settings.json5", "settings.jsonc"] {
+            assert_eq!(
+                Format::from_filename(Path::new(name)).unwrap(),
+                Format::Json5
+            );
         }
     }
+
+    #[test]
+    #[cfg(feature = "ini-parsing")]
+    fn ini_extensions() {
+        for name in &["settings.ini", "setup.cfg", "app.properties"] {
+            assert_eq!(Format::from_filename(Path::new(name)).unwrap(), Format::Ini);
+        }
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "json-parsing",
+        feature = "ron-parsing",
+        feature = "toml-parsing",
+        feature = "yaml-parsing"
+    ))]
+    fn detect_formats() {
+        assert_eq!(Format::detect("{ \"a\": 1 }"), Some(Format::Json));
+        assert_eq!(Format::detect("(a: 1)"), Some(Format::Ron));
+        assert_eq!(Format::detect("Config(\n    a: 1,\n)"), Some(Format::Ron));
+        assert_eq!(Format::detect("# comment\na = 1"), Some(Format::Toml));
+        assert_eq!(Format::detect("[table]\na = 1"), Some(Format::Toml));
+        assert_eq!(Format::detect("url = \"http://x\""), Some(Format::Toml));
+        assert_eq!(Format::detect("---\na: 1"), Some(Format::Yaml));
+        assert_eq!(Format::detect("a: 1"), Some(Format::Yaml));
+        assert_eq!(Format::detect(""), None);
+        assert_eq!(Format::detect("just words"), None);
+    }
 }
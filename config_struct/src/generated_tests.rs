@@ -0,0 +1,129 @@
+use std::path::Path;
+
+use crate::{
+    error::GenerationError,
+    format::Format,
+    generation, load_fns,
+    options::{DynamicLoading, StructOptions},
+};
+
+/// Generate the `generated_tests` module, which checks the root value
+/// against serde, and against the config file if it is loaded
+/// dynamically.
+pub fn generate_tests(
+    format: Format,
+    filepath: &Path,
+    root_reference: &str,
+    root_array: bool,
+    options: &StructOptions,
+) -> Result<String, GenerationError> {
+    let unsupported = || GenerationError::UnsupportedTests(format.to_string());
+    let load_expression = load_fns::load_expression(format).map_err(|_| unsupported())?;
+
+    let struct_name = &options.struct_name;
+    let (owned, borrowed) = if root_array {
+        (
+            format!("Vec<{}>", struct_name),
+            format!("[{}]", struct_name),
+        )
+    } else {
+        (struct_name.clone(), struct_name.clone())
+    };
+
+    let mut tests = Vec::new();
+
+    if options.serde_support.should_derive_ser_de() == Some((true, true)) {
+        let serialize_expression = serialize_expression(format).ok_or_else(unsupported)?;
+        tests.push(format!(
+            "
+    #[test]
+    fn round_trips() {{
+        let expected: &{borrowed} = {root_reference};
+        let file_contents = {serialize_expression}.unwrap();
+        let result: {owned} = {load_expression}.unwrap();
+        assert!(result == *expected, \"{struct_name} changed when serialized and deserialized.\");
+    }}
+",
+            borrowed = borrowed,
+            root_reference = root_reference,
+            serialize_expression = serialize_expression,
+            owned = owned,
+            load_expression = load_expression,
+            struct_name = struct_name,
+        ));
+    }
+
+    let loads_dynamically =
+        options.generate_load_fns && options.dynamic_loading != DynamicLoading::Never;
+    if loads_dynamically {
+        let path = load_fns::absolute_path(filepath).unwrap_or_else(|| filepath.to_owned());
+        let path = path.to_string_lossy();
+        let message = format!(
+            "{} is out of date with `{}`. Generate it again.",
+            struct_name, path
+        );
+        tests.push(format!(
+            "
+    #[test]
+    fn matches_config_file() {{
+        let expected: &{borrowed} = {root_reference};
+        let file_contents: &str = include_str!({path});
+        let result: {owned} = {load_expression}.unwrap();
+        assert!(result == *expected, {message});
+    }}
+",
+            borrowed = borrowed,
+            root_reference = root_reference,
+            path = load_fns::path_literal(&path),
+            owned = owned,
+            load_expression = load_expression,
+            message = generation::string_literal(&message),
+        ));
+    }
+
+    Ok(format!(
+        "
+#[cfg(test)]
+mod generated_tests {{
+    use super::*;
+{}}}
+",
+        tests.join("")
+    ))
+}
+
+/// Whether the tests generated with `options` can be generated for a
+/// config in the given format.
+pub fn supports_tests(format: Format, options: &StructOptions) -> bool {
+    load_fns::supports_dynamic_loading(format)
+        && (options.serde_support.should_derive_ser_de() != Some((true, true))
+            || serialize_expression(format).is_some())
+}
+
+/// The expression which serializes the `expected` value in the given
+/// format, if there is a serde crate for it.
+fn serialize_expression(format: Format) -> Option<&'static str> {
+    match format {
+        #[cfg(feature = "json-parsing")]
+        Format::Json => Some("::serde_json::to_string(expected)"),
+
+        #[cfg(feature = "json5-parsing")]
+        Format::Json5 => Some("::json5::to_string(expected)"),
+
+        #[cfg(feature = "ron-parsing")]
+        Format::Ron => Some("::ron::ser::to_string(expected)"),
+
+        // Values have to come before tables in TOML, which a `Value`
+        // takes care of, but serializing the struct directly doesn't.
+        #[cfg(feature = "toml-parsing")]
+        Format::Toml => {
+            Some("::toml::Value::try_from(expected).and_then(|value| ::toml::to_string(&value))")
+        }
+
+        #[cfg(feature = "yaml-parsing")]
+        Format::Yaml => Some("::serde_yaml::to_string(expected)"),
+
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
@@ -1,88 +1,554 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Write},
+};
+
 use crate::{
-    options::StructOptions,
-    value::{GenericStruct, GenericValue},
+    cfg_sections::{cfg_attribute, struct_cfgs},
+    datetimes,
+    fixed_arrays::ArrayEmitContext,
+    interop::{self, DeriveTarget, StructPath},
+    naming::{allow_non_camel_case, pascal_case},
+    newtypes,
+    options::{
+        DisplayStyle, EmptyRootStyle, FieldAccess, MapType, MissingFields, SerdeSupport,
+        StringType, StructOptions,
+    },
+    renaming, serde_defaults, serde_impls,
+    tagged_unions::{self, variant_templates},
+    unification::{array_structs, array_unions, struct_template},
+    value::{GenericEnum, GenericMap, GenericStruct, GenericTaggedUnion, GenericValue},
+    versions,
 };
 
 pub fn generate_structs(struct_value: &GenericStruct, options: &StructOptions) -> String {
+    let mut buffer = generate_struct_declarations(struct_value, options);
+
+    let mut enums = BTreeMap::new();
+    collect_enums(struct_value, &mut enums);
+    for enum_value in enums.values() {
+        generate_enum_declaration(&mut buffer, enum_value, options);
+    }
+    buffer.push_str(&tagged_unions::generate_tagged_unions(
+        struct_value,
+        options,
+    ));
+    buffer.push_str(&newtypes::generate_newtypes(struct_value, options));
+
+    buffer.push_str(&versions::generate_version_struct(struct_value, options));
+
+    buffer
+}
+
+/// Declare the root struct and every nested struct, without the enums
+/// and other types their fields use.
+pub fn generate_struct_declarations(
+    struct_value: &GenericStruct,
+    options: &StructOptions,
+) -> String {
     let mut buffer = String::new();
-    generate_struct_declarations(&mut buffer, struct_value, options);
+    let cfgs = struct_cfgs(struct_value);
+    let struct_paths = if interop::derives_clap(options) || interop::derives_schemars(options) {
+        interop::struct_paths(struct_value)
+    } else {
+        BTreeMap::new()
+    };
+    let root_name = &struct_value.struct_name;
+    for struct_value in declared_structs(struct_value) {
+        let cfg = cfgs.get(&struct_value.struct_name);
+        let path = struct_paths.get(&struct_value.struct_name);
+        let target = if struct_value.struct_name == *root_name {
+            DeriveTarget::RootStruct
+        } else {
+            DeriveTarget::NestedStruct
+        };
+        generate_struct_declaration(&mut buffer, &struct_value, cfg, path, target, options);
+        if options.serde_support == SerdeSupport::ManualImpl {
+            buffer.push_str(&serde_impls::generate_struct_impls(
+                &struct_value,
+                cfg,
+                options,
+            ));
+        }
+    }
     buffer
 }
 
-fn generate_struct_declarations(
+/// List the root struct and every nested struct which needs declaring,
+/// once each.
+///
+/// For arrays of structs, this is a template with the fields of all of the
+/// elements, since the first element alone may have `None` for optional
+/// fields.
+pub fn declared_structs(struct_value: &GenericStruct) -> Vec<Cow<'_, GenericStruct>> {
+    let mut structs = Vec::new();
+    collect_declared_structs(struct_value, &mut structs, &mut BTreeSet::new());
+    structs
+}
+
+fn collect_declared_structs<'a>(
+    struct_value: &'a GenericStruct,
+    structs: &mut Vec<Cow<'a, GenericStruct>>,
+    declared: &mut BTreeSet<String>,
+) {
+    // Deduplicated structs share a name, and only need declaring once.
+    if !declared.insert(struct_value.struct_name.clone()) {
+        return;
+    }
+    // Structs are borrowed where possible, since the root holds the
+    // whole config.
+    structs.push(Cow::Borrowed(struct_value));
+
+    for value in struct_value.fields.values() {
+        collect_nested_structs(value, structs, declared);
+    }
+}
+
+fn collect_nested_structs<'a>(
+    value: &'a GenericValue,
+    structs: &mut Vec<Cow<'a, GenericStruct>>,
+    declared: &mut BTreeSet<String>,
+) {
+    match *value {
+        GenericValue::Struct(ref value) => collect_declared_structs(value, structs, declared),
+        GenericValue::Option(Some(ref value)) => collect_nested_structs(value, structs, declared),
+        GenericValue::TaggedUnion(ref union) => {
+            collect_variant_structs(&[union], structs, declared)
+        }
+        GenericValue::Array(ref values) | GenericValue::Map(GenericMap { ref values, .. }) => {
+            let elements = array_structs(values);
+            let unions = array_unions(values);
+            if !elements.is_empty() {
+                let template = struct_template(&elements);
+                let mut template_structs = Vec::new();
+                collect_declared_structs(&template, &mut template_structs, declared);
+                structs.extend(
+                    template_structs
+                        .into_iter()
+                        .map(|struct_value| Cow::Owned(struct_value.into_owned())),
+                );
+            } else if !unions.is_empty() {
+                collect_variant_structs(&unions, structs, declared);
+            } else if let Some(value) = GenericValue::typed_element(values) {
+                collect_nested_structs(value, structs, declared);
+            }
+        }
+        GenericValue::Tuple(ref values) => {
+            for value in values {
+                collect_nested_structs(value, structs, declared);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Collect the structs nested in the fields of the variants of an enum in
+/// `tagged_unions`. The variants themselves aren't declared as structs.
+fn collect_variant_structs(
+    unions: &[&GenericTaggedUnion],
+    structs: &mut Vec<Cow<'_, GenericStruct>>,
+    declared: &mut BTreeSet<String>,
+) {
+    for variant in variant_templates(unions).values() {
+        let mut variant_structs = Vec::new();
+        for value in variant.fields.values() {
+            collect_nested_structs(value, &mut variant_structs, declared);
+        }
+        structs.extend(
+            variant_structs
+                .into_iter()
+                .map(|struct_value| Cow::Owned(struct_value.into_owned())),
+        );
+    }
+}
+
+fn generate_struct_declaration(
     output: &mut String,
     struct_value: &GenericStruct,
+    cfg: Option<&String>,
+    path: Option<&StructPath>,
+    target: DeriveTarget,
     options: &StructOptions,
 ) {
+    let serde_derives = options.serde_support.derived_ser_de();
+    let uses_serde = serde_derives.is_some();
+    let derives_serialize = matches!(serde_derives, Some((true, _)));
+    let derives_deserialize = matches!(serde_derives, Some((_, true)));
+    let visibility = match options.field_access {
+        FieldAccess::PublicFields => "pub ",
+        FieldAccess::Getters => "",
+    };
+
     let field_strings = struct_value
-        .fields
-        .iter()
+        .ordered_fields(options.field_order)
+        .into_iter()
         .map(|(name, value)| {
+            let mut serde_args = Vec::new();
+            if uses_serde {
+                if let Some(key) = renaming::serde_rename(struct_value, name, options) {
+                    serde_args.push(format!("rename = {}", string_literal(key)));
+                }
+            }
+            // Fields missing from the config stay missing when it's
+            // serialized again, rather than becoming nulls.
+            let absent = struct_value.absent_fields.contains(name);
+            if derives_serialize && absent {
+                serde_args.push("skip_serializing_if = \"Option::is_none\"".to_owned());
+            }
+            if derives_deserialize {
+                match options.serde_missing_fields {
+                    MissingFields::Error if absent => serde_args.push("default".to_owned()),
+                    MissingFields::Error => (),
+                    MissingFields::UseConst => serde_args.push(format!(
+                        "default = {}",
+                        string_literal(&serde_defaults::default_fn_path(
+                            &struct_value.struct_name,
+                            name
+                        ))
+                    )),
+                    MissingFields::UseTypeDefault => serde_args.push("default".to_owned()),
+                }
+            }
+            // A flattened struct has no key of its own to rename or default.
+            if struct_value.flattened_fields.contains(name) {
+                serde_args = vec!["flatten".to_owned()];
+            }
+            let attribute = if serde_args.is_empty() {
+                String::new()
+            } else {
+                format!("    #[serde({})]\n", serde_args.join(", "))
+            };
+
+            let doc = match struct_value.field_docs.get(name) {
+                Some(doc) => doc_string(doc, "    "),
+                None => String::new(),
+            };
+            let interop_attributes = match path {
+                Some(path) => interop::field_attributes(struct_value, name, value, path, options),
+                None => String::new(),
+            };
+
             format!(
-                "    pub {}: {},",
+                "{}{}{}{}    {}{}: {},",
+                doc,
+                cfg_attribute(struct_value.field_cfgs.get(name), 4),
+                attribute,
+                interop_attributes,
+                visibility,
                 name,
-                type_string_with_options(value, options.max_array_size)
+                type_string_with_options(value, options)
             )
         })
         .collect::<Vec<String>>();
 
-    let derive_string = {
-        let mut derived_traits = options.derived_traits.clone();
-        if let Some((ser, de)) = options.serde_support.should_derive_ser_de() {
-            let prefix = if options.use_serde_derive_crate {
-                "serde_derive::"
-            } else {
-                "serde::"
-            };
+    let mut derived_traits = options.derived_traits.clone();
+    interop::adjust_derives(&mut derived_traits, target, options);
+    // Missing nested tables default to their type's `Default` too, which
+    // `impl_default` would otherwise provide.
+    if target == DeriveTarget::NestedStruct
+        && options.serde_missing_fields == MissingFields::UseTypeDefault
+        && !options.impl_default
+        && !derived_traits.iter().any(|name| name == "Default")
+    {
+        derived_traits.push("Default".to_owned());
+    }
+    let derive_string = derive_string(derived_traits, options);
+    let serde_attributes = options
+        .serde_attributes
+        .for_struct(&struct_value.struct_name);
+    let attribute_string = if serde_attributes.is_empty() {
+        String::new()
+    } else {
+        format!("#[serde({})]\n", serde_attributes.join(", "))
+    };
 
-            if ser {
-                derived_traits.push(format!("{}Serialize", prefix));
-            }
-            if de {
-                derived_traits.push(format!("{}Deserialize", prefix));
-            }
-        }
+    let doc = match struct_value.doc {
+        Some(ref doc) => doc_string(doc, ""),
+        None => String::new(),
+    };
 
-        if derived_traits.is_empty() {
-            "".to_owned()
-        } else {
-            format!("#[derive({})]\n", derived_traits.join(", "))
-        }
+    let body = if is_unit_struct(struct_value, options) {
+        ";".to_owned()
+    } else if field_strings.is_empty() {
+        " {}".to_owned()
+    } else {
+        format!(" {{\n{}\n}}", field_strings.join("\n"))
     };
 
     output.push_str(&format!(
-        "{}#[allow(non_camel_case_types)]
-pub struct {} {{
-{}
-}}
+        "{}{}{}{}{}pub struct {}{}
 
 ",
+        doc,
+        cfg_attribute(cfg, 0),
         derive_string,
+        attribute_string,
+        allow_non_camel_case(&struct_value.struct_name),
         struct_value.struct_name,
-        field_strings.join("\n")
+        body
     ));
+}
+
+/// Whether a struct is declared as a unit struct: only the root of a
+/// config with no fields, with `EmptyRootStyle::Unit`, and not when serde
+/// derives would read and write it as a map.
+pub fn is_unit_struct(struct_value: &GenericStruct, options: &StructOptions) -> bool {
+    struct_value.fields.is_empty()
+        && struct_value.struct_name == options.struct_name
+        && options.empty_root == EmptyRootStyle::Unit
+        && options.serde_support.derived_ser_de().is_none()
+}
+
+/// Generate `Default` impls for the root struct and all nested structs,
+/// returning the values from the config.
+///
+/// If `root_const` is given, the root struct's impl clones that const
+/// rather than spelling out the value again.
+pub fn generate_default_impls(
+    struct_value: &GenericStruct,
+    options: &StructOptions,
+    root_const: Option<&str>,
+) -> String {
+    let mut buffer = String::new();
+
+    let root_value = match root_const {
+        Some(const_name) => format!("{}.clone()", const_name),
+        None => struct_value_string(struct_value, ArrayEmitContext::new(options, 8), options),
+    };
+    push_default_impl(&mut buffer, &struct_value.struct_name, None, &root_value);
 
-    // TODO: is this ... accurate? Does this handle nested arrays/options???
+    let mut context = DefaultImplContext {
+        cfgs: struct_cfgs(struct_value),
+        implemented: BTreeSet::new(),
+    };
     for value in struct_value.fields.values() {
+        generate_nested_default_impls(&mut buffer, value, options, &mut context);
+    }
+
+    buffer
+}
+
+/// The structs which already have `Default` impls, and the predicates of
+/// any which are conditionally compiled.
+struct DefaultImplContext {
+    cfgs: BTreeMap<String, String>,
+    implemented: BTreeSet<String>,
+}
+
+fn generate_nested_default_impls(
+    output: &mut String,
+    value: &GenericValue,
+    options: &StructOptions,
+    context: &mut DefaultImplContext,
+) {
+    match *value {
+        GenericValue::Struct(ref value) => {
+            generate_struct_default_impls(output, value, value, options, context)
+        }
+        GenericValue::Option(Some(ref value)) => {
+            generate_nested_default_impls(output, value, options, context)
+        }
+        GenericValue::TaggedUnion(ref union) => {
+            for value in union.value.fields.values() {
+                generate_nested_default_impls(output, value, options, context);
+            }
+        }
+        GenericValue::Array(ref values) | GenericValue::Map(GenericMap { ref values, .. }) => {
+            // Use the first element's values, but find nested structs in
+            // all of the elements, as the first may not contain them all.
+            let structs = array_structs(values);
+            if !structs.is_empty() {
+                let template = struct_template(&structs);
+                generate_struct_default_impls(output, structs[0], &template, options, context);
+            } else if let Some(value) = GenericValue::typed_element(values) {
+                generate_nested_default_impls(output, value, options, context);
+            }
+        }
+        GenericValue::Tuple(ref values) => {
+            for value in values {
+                generate_nested_default_impls(output, value, options, context);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn generate_struct_default_impls(
+    output: &mut String,
+    value: &GenericStruct,
+    template: &GenericStruct,
+    options: &StructOptions,
+    context: &mut DefaultImplContext,
+) {
+    if !context.implemented.insert(value.struct_name.clone()) {
+        return;
+    }
+
+    let value_string = struct_value_string(value, ArrayEmitContext::new(options, 8), options);
+    let cfg = context.cfgs.get(&value.struct_name);
+    push_default_impl(output, &value.struct_name, cfg, &value_string);
+
+    for value in template.fields.values() {
+        generate_nested_default_impls(output, value, options, context);
+    }
+}
+
+fn push_default_impl(
+    output: &mut String,
+    struct_name: &str,
+    cfg: Option<&String>,
+    value_string: &str,
+) {
+    output.push_str(&format!(
+        "
+{}impl Default for {} {{
+    fn default() -> Self {{
+        {}
+    }}
+}}
+",
+        cfg_attribute(cfg, 0),
+        struct_name,
+        value_string
+    ));
+}
+
+/// Format a doc comment, one `///` line per line of `doc`.
+pub fn doc_string(doc: &str, indentation: &str) -> String {
+    doc.lines()
+        .map(|line| {
+            if line.is_empty() {
+                format!("{}///\n", indentation)
+            } else {
+                format!("{}/// {}\n", indentation, line)
+            }
+        })
+        .collect()
+}
+
+pub fn derive_string(mut derived_traits: Vec<String>, options: &StructOptions) -> String {
+    // The generated tests compare values.
+    if options.generate_tests && !derived_traits.iter().any(|name| name == "PartialEq") {
+        derived_traits.push("PartialEq".to_owned());
+    }
+    // The pretty `Display` is the `Debug` output.
+    if options.impl_display == DisplayStyle::PrettyDebug
+        && !derived_traits.iter().any(|name| name == "Debug")
+    {
+        derived_traits.push("Debug".to_owned());
+    }
+
+    if let Some((ser, de)) = options.serde_support.derived_ser_de() {
+        let prefix = if options.use_serde_derive_crate {
+            "serde_derive::"
+        } else {
+            "serde::"
+        };
+
+        if ser {
+            derived_traits.push(format!("{}Serialize", prefix));
+        }
+        if de {
+            derived_traits.push(format!("{}Deserialize", prefix));
+        }
+    }
+
+    if derived_traits.is_empty() {
+        "".to_owned()
+    } else {
+        format!("#[derive({})]\n", derived_traits.join(", "))
+    }
+}
+
+pub fn collect_enums<'a>(
+    struct_value: &'a GenericStruct,
+    enums: &mut BTreeMap<String, &'a GenericEnum>,
+) {
+    fn collect_from_value<'a>(
+        value: &'a GenericValue,
+        enums: &mut BTreeMap<String, &'a GenericEnum>,
+    ) {
         match *value {
-            GenericValue::Struct(ref value) => generate_struct_declarations(output, value, options),
-            GenericValue::Array(ref values) => {
-                if let Some(&GenericValue::Struct(ref value)) = values.get(0) {
-                    generate_struct_declarations(output, value, options);
+            GenericValue::Enum(ref enum_value) => {
+                enums.insert(enum_value.enum_name.clone(), enum_value);
+            }
+            GenericValue::Struct(ref struct_value) => collect_enums(struct_value, enums),
+            GenericValue::Option(Some(ref value)) => collect_from_value(value, enums),
+            GenericValue::Array(ref values)
+            | GenericValue::Tuple(ref values)
+            | GenericValue::Map(GenericMap { ref values, .. }) => {
+                for value in values {
+                    collect_from_value(value, enums);
                 }
             }
             _ => (),
         }
     }
+
+    for value in struct_value.fields.values() {
+        collect_from_value(value, enums);
+    }
+}
+
+fn generate_enum_declaration(
+    output: &mut String,
+    enum_value: &GenericEnum,
+    options: &StructOptions,
+) {
+    // Enum fields must support everything the containing struct derives.
+    let mut derived_traits: Vec<String> = ["Debug", "Clone", "Copy", "PartialEq", "Eq"]
+        .iter()
+        .map(|&name| name.to_owned())
+        .collect();
+    for name in &options.derived_traits {
+        if !derived_traits.contains(name) {
+            derived_traits.push(name.clone());
+        }
+    }
+    interop::adjust_derives(&mut derived_traits, DeriveTarget::Enum, options);
+
+    let serde_renames = options.serde_support.derived_ser_de().is_some();
+    // Command-line values are written as they are in the config.
+    let clap_names = interop::derives_clap(options);
+    let variant_strings = enum_value
+        .variants
+        .iter()
+        .map(|variant| {
+            let name = pascal_case(variant);
+            let mut attributes = String::new();
+            if serde_renames && name != *variant {
+                attributes.push_str(&format!("    #[serde(rename = {:?})]\n", variant));
+            }
+            if clap_names {
+                attributes.push_str(&format!("    #[value(name = {:?})]\n", variant));
+            }
+            format!("{}    {},", attributes, name)
+        })
+        .collect::<Vec<String>>();
+
+    output.push_str(&format!(
+        "{}pub enum {} {{
+{}
+}}
+
+",
+        derive_string(derived_traits, options),
+        enum_value.enum_name,
+        variant_strings.join("\n")
+    ));
+    if options.serde_support == SerdeSupport::ManualImpl {
+        output.push_str(&serde_impls::generate_enum_impls(enum_value, options));
+    }
 }
 
 // TODO: Shouldn't really need to be public
-pub fn type_string(value: &GenericValue) -> String {
-    type_string_with_options(value, 0)
+pub fn type_string(value: &GenericValue, options: &StructOptions) -> String {
+    type_string_with_options(value, options)
 }
 
 // TODO: So ugly, wow.
-fn type_string_with_options(value: &GenericValue, max_array_size: usize) -> String {
+fn type_string_with_options(value: &GenericValue, options: &StructOptions) -> String {
     match *value {
         GenericValue::Unit => "()".to_owned(),
         GenericValue::Bool(_) => "bool".to_owned(),
@@ -91,115 +557,553 @@ fn type_string_with_options(value: &GenericValue, max_array_size: usize) -> Stri
         GenericValue::I16(_) => "i16".to_owned(),
         GenericValue::I32(_) => "i32".to_owned(),
         GenericValue::I64(_) => "i64".to_owned(),
+        GenericValue::I128(_) => "i128".to_owned(),
         GenericValue::U8(_) => "u8".to_owned(),
         GenericValue::U16(_) => "u16".to_owned(),
         GenericValue::U32(_) => "u32".to_owned(),
         GenericValue::U64(_) => "u64".to_owned(),
+        GenericValue::U128(_) => "u128".to_owned(),
         GenericValue::ISize(_) => "isize".to_owned(),
         GenericValue::Usize(_) => "usize".to_owned(),
         GenericValue::F32(_) => "f32".to_owned(),
         GenericValue::F64(_) => "f64".to_owned(),
-        GenericValue::String(_) => "Cow<'static, str>".to_owned(),
+        GenericValue::String(_) => string_type(options),
+        GenericValue::DateTime(ref value) => datetimes::date_time_type_string(value, options),
+        GenericValue::Bytes(_) => slice_type("u8", options),
+        GenericValue::Version(_) => versions::version_type_string(options),
         GenericValue::Option(ref value) => {
             let element_type = match *value {
-                Some(ref value) => type_string_with_options(value, max_array_size),
-                None => type_string_with_options(&GenericValue::Unit, max_array_size),
+                Some(ref value) => type_string_with_options(value, options),
+                None => type_string_with_options(&GenericValue::Unit, options),
             };
             format!("Option<{}>", element_type)
         }
+        GenericValue::NoneOf(ref inner_type) => format!("Option<{}>", inner_type),
         GenericValue::Array(ref values) => {
-            let element_type = match values.get(0) {
-                Some(element) => type_string_with_options(element, max_array_size),
-                None => type_string_with_options(&GenericValue::Unit, max_array_size),
+            let element_type = match GenericValue::typed_element(values) {
+                Some(element) => type_string_with_options(element, options),
+                None => type_string_with_options(&GenericValue::Unit, options),
             };
-            if !values.is_empty() && values.len() <= max_array_size {
+            if options.uses_fixed_array(values.len()) {
                 format!("[{}; {}]", element_type, values.len())
             } else {
-                format!("Cow<'static, [{}]>", element_type)
+                slice_type(&element_type, options)
             }
         }
+        GenericValue::EmptyArray(ref element_type) => slice_type(element_type, options),
+        GenericValue::Tuple(ref values) => {
+            let element_types = values
+                .iter()
+                .map(|value| type_string_with_options(value, options))
+                .collect::<Vec<String>>();
+            tuple_string(&element_types)
+        }
         GenericValue::Struct(ref struct_value) => struct_value.struct_name.clone(),
+        GenericValue::Enum(ref enum_value) => enum_value.enum_name.clone(),
+        GenericValue::Newtype(ref newtype) => newtype.type_name.clone(),
+        GenericValue::TaggedUnion(ref union) => union.enum_name.clone(),
+        GenericValue::Map(ref map) => {
+            let value_type = match GenericValue::typed_element(&map.values) {
+                Some(value) => type_string_with_options(value, options),
+                None => type_string_with_options(&GenericValue::Unit, options),
+            };
+            match options.map_type {
+                MapType::Slice => slice_type(
+                    &format!("({}, {})", string_type(options), value_type),
+                    options,
+                ),
+                MapType::HashMap => format!(
+                    "::std::collections::HashMap<{}, {}>",
+                    string_type(options),
+                    value_type
+                ),
+            }
+        }
     }
 }
 
-fn value_string(value: &GenericValue, indentation: usize, max_array_size: usize) -> String {
+pub fn value_string(
+    value: &GenericValue,
+    context: ArrayEmitContext,
+    options: &StructOptions,
+) -> String {
+    let mut output = String::new();
+    write_value(&mut output, value, context, options);
+    output
+}
+
+/// Write the expression for `value` to `output`.
+///
+/// Everything is written straight into the one buffer, rather than
+/// joining strings built for each element, since large configs can have
+/// tens of thousands of values.
+pub fn write_value(
+    output: &mut String,
+    value: &GenericValue,
+    context: ArrayEmitContext,
+    options: &StructOptions,
+) {
     match *value {
-        GenericValue::Unit => "()".to_string(),
-        GenericValue::Bool(value) => value.to_string(),
-        GenericValue::Char(value) => format!("'{}'", value),
-        GenericValue::I8(value) => value.to_string(),
-        GenericValue::I16(value) => value.to_string(),
-        GenericValue::I32(value) => value.to_string(),
-        GenericValue::I64(value) => value.to_string(),
-        GenericValue::U8(value) => value.to_string(),
-        GenericValue::U16(value) => value.to_string(),
-        GenericValue::U32(value) => value.to_string(),
-        GenericValue::U64(value) => value.to_string(),
-        GenericValue::ISize(value) => value.to_string(),
-        GenericValue::Usize(value) => value.to_string(),
-        GenericValue::F32(value) => float_string(value),
-        GenericValue::F64(value) => float_string(value),
-        GenericValue::String(ref value) => format!("Cow::Borrowed(\"{}\")", value),
+        GenericValue::Unit => output.push_str("()"),
+        GenericValue::Bool(value) => push_display(output, value),
+        GenericValue::Char(value) => output.push_str(&char_literal(value)),
+        GenericValue::I8(value) => push_display(output, value),
+        GenericValue::I16(value) => push_display(output, value),
+        GenericValue::I32(value) => push_display(output, value),
+        GenericValue::I64(value) => push_display(output, value),
+        GenericValue::I128(value) => push_display(output, value),
+        GenericValue::U8(value) => push_display(output, value),
+        GenericValue::U16(value) => push_display(output, value),
+        GenericValue::U32(value) => push_display(output, value),
+        GenericValue::U64(value) => push_display(output, value),
+        GenericValue::U128(value) => push_display(output, value),
+        GenericValue::ISize(value) => push_display(output, value),
+        GenericValue::Usize(value) => push_display(output, value),
+        GenericValue::F32(value) => output.push_str(&float_string(value, "f32")),
+        GenericValue::F64(value) => output.push_str(&float_string(value, "f64")),
+        GenericValue::String(ref value) => {
+            write_string_value(output, value, context.indentation, options)
+        }
+        GenericValue::DateTime(ref value) => {
+            output.push_str(&datetimes::date_time_value_string(value, options))
+        }
+        GenericValue::Bytes(ref bytes) => write_slice(output, bytes, options, |output, byte| {
+            push_display(output, format_args!("0x{:02x}", byte))
+        }),
+        GenericValue::Version(ref text) => {
+            output.push_str(&versions::version_value_string(text, options))
+        }
         GenericValue::Option(ref value) => match *value {
             Some(ref value) => {
-                format!("Some({})", value_string(value, indentation, max_array_size))
+                output.push_str("Some(");
+                write_value(output, value, context, options);
+                output.push(')');
             }
-            None => "None".to_string(),
+            None => output.push_str("None"),
         },
+        GenericValue::NoneOf(_) => output.push_str("None"),
         GenericValue::Array(ref values) => {
-            let value_strings = values
-                .iter()
-                .map(|value| value_string(value, indentation + 4, max_array_size))
-                .collect::<Vec<String>>();
-
-            if !values.is_empty() && values.len() <= max_array_size {
-                format!("[{}]", value_strings.join(", "))
+            let (prefix, suffix) = if context.uses_fixed_array(values.len()) {
+                ("[", "]")
             } else {
-                format!("Cow::Borrowed(&[{}])", value_strings.join(", "))
+                slice_delimiters(options)
+            };
+            let write_element = |output: &mut String, value: &GenericValue, indentation| {
+                write_value(output, value, context.at(indentation), options)
+            };
+            let inline = !values.iter().any(spans_lines);
+            output.push_str(prefix);
+            write_elements(
+                output,
+                values,
+                suffix,
+                context.indentation,
+                inline,
+                options,
+                write_element,
+            );
+        }
+        GenericValue::EmptyArray(_) => {
+            write_slice(output, &[] as &[()], options, |_, _| ());
+        }
+        GenericValue::Tuple(ref values) => {
+            output.push('(');
+            write_separated(output, values, |output, value| {
+                write_value(output, value, context.indented(), options)
+            });
+            if values.len() == 1 {
+                output.push(',');
             }
+            output.push(')');
         }
         GenericValue::Struct(ref struct_value) => {
-            struct_value_string(struct_value, indentation, max_array_size)
+            write_struct_value(output, struct_value, context, options)
+        }
+        GenericValue::Enum(ref enum_value) => {
+            output.push_str(&enum_value.enum_name);
+            output.push_str("::");
+            output.push_str(&pascal_case(&enum_value.value));
+        }
+        GenericValue::Newtype(ref newtype) => {
+            output.push_str(&newtype.type_name);
+            output.push('(');
+            write_value(output, &newtype.value, context, options);
+            output.push(')');
+        }
+        GenericValue::TaggedUnion(ref union) => {
+            output.push_str(&union.enum_name);
+            output.push_str("::");
+            output.push_str(&union.variant);
+            write_struct_fields(output, &union.value, context, options);
+        }
+        GenericValue::Map(ref map) => {
+            let entries = map.keys.iter().zip(&map.values).collect::<Vec<_>>();
+            let inline = !map.values.iter().any(spans_lines);
+            let write_entry =
+                |output: &mut String, &(key, value): &(&String, &GenericValue), indentation| {
+                    output.push('(');
+                    write_string_value(output, key, indentation, options);
+                    output.push_str(", ");
+                    write_value(output, value, context.at(indentation), options);
+                    output.push(')');
+                };
+
+            match options.map_type {
+                MapType::Slice => {
+                    let (prefix, suffix) = slice_delimiters(options);
+                    output.push_str(prefix);
+                    write_elements(
+                        output,
+                        &entries,
+                        suffix,
+                        context.indentation,
+                        inline,
+                        options,
+                        write_entry,
+                    );
+                }
+                MapType::HashMap if entries.is_empty() => {
+                    output.push_str("::std::collections::HashMap::new()")
+                }
+                MapType::HashMap => {
+                    output.push_str("vec![");
+                    write_elements(
+                        output,
+                        &entries,
+                        "]",
+                        context.indentation,
+                        inline,
+                        options,
+                        write_entry,
+                    );
+                    output.push_str(".into_iter().collect()");
+                }
+            }
+        }
+    }
+}
+
+fn push_display<T: fmt::Display>(output: &mut String, value: T) {
+    write!(output, "{}", value).expect("Writing to a String can't fail.");
+}
+
+fn push_indentation(output: &mut String, indentation: usize) {
+    output.extend(std::iter::repeat_n(' ', indentation));
+}
+
+/// Write `elements`, separated by commas.
+fn write_separated<T, F>(output: &mut String, elements: &[T], mut write_element: F)
+where
+    F: FnMut(&mut String, &T),
+{
+    for (index, element) in elements.iter().enumerate() {
+        if index > 0 {
+            output.push_str(", ");
+        }
+        write_element(output, element);
+    }
+}
+
+pub fn string_type(options: &StructOptions) -> String {
+    match options.string_type {
+        StringType::Cow => "Cow<'static, str>".to_owned(),
+        StringType::StaticStr => "&'static str".to_owned(),
+        StringType::String => "String".to_owned(),
+    }
+}
+
+pub fn string_value(value: &str, options: &StructOptions) -> String {
+    let mut output = String::new();
+    write_string_value(&mut output, value, 0, options);
+    output
+}
+
+fn write_string_value(
+    output: &mut String,
+    value: &str,
+    indentation: usize,
+    options: &StructOptions,
+) {
+    let (prefix, suffix) = match options.string_type {
+        StringType::Cow => ("Cow::Borrowed(", ")"),
+        StringType::StaticStr => ("", ""),
+        StringType::String => ("String::from(", ")"),
+    };
+    output.push_str(prefix);
+    write_string_literal(output, value, indentation);
+    output.push_str(suffix);
+}
+
+/// Write a Rust string literal for a value, like
+/// [`string_literal`](fn.string_literal.html), but with a value of
+/// several lines written over several lines too.
+///
+/// That's a raw string if it shows the value exactly. Otherwise, such as
+/// when there are carriage returns, it's a `concat!` of a literal for each
+/// line, one level deeper than `indentation`. Lines ending in whitespace
+/// are written that way too, since editors often strip that whitespace.
+fn write_string_literal(output: &mut String, value: &str, indentation: usize) {
+    if !value.contains('\n') {
+        push_display(output, format_args!("{:?}", value));
+    } else if fits_raw_string(value) {
+        let hashes = "#".repeat(raw_string_hashes(value));
+        push_display(output, format_args!("r{}\"{}\"{}", hashes, value, hashes));
+    } else {
+        output.push_str("concat!(\n");
+        for line in value.split_inclusive('\n') {
+            push_indentation(output, indentation + 4);
+            push_display(output, format_args!("{:?},\n", line));
+        }
+        push_indentation(output, indentation);
+        output.push(')');
+    }
+}
+
+/// Whether a raw string holding `value` would show it exactly: with no
+/// control characters other than newlines and tabs, no characters which
+/// change the direction of text, and no whitespace at the ends of lines.
+fn fits_raw_string(value: &str) -> bool {
+    let mut lines = value.split('\n');
+    // The last line is followed by the closing quote.
+    lines.next_back();
+
+    lines.all(|line| !line.ends_with(char::is_whitespace))
+        && !value.chars().any(|c| {
+            (c.is_control() && c != '\n' && c != '\t')
+                || matches!(c, '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+        })
+}
+
+/// The fewest `#`s, at least one, which let a raw string hold `value`
+/// without it closing the string early.
+fn raw_string_hashes(value: &str) -> usize {
+    (1..)
+        .find(|&count| !value.contains(&format!("\"{}", "#".repeat(count))))
+        .expect("a long enough run of `#`s can't be in the value")
+}
+
+/// A Rust string literal for `value`, with quotes, backslashes and
+/// control characters escaped.
+///
+/// The `Debug` output of a string uses the same escapes as Rust source,
+/// and leaves other characters, like emoji, as they are.
+pub fn string_literal(value: &str) -> String {
+    format!("{:?}", value)
+}
+
+/// A Rust char literal for `value`, escaped like
+/// [`string_literal`](fn.string_literal.html).
+pub fn char_literal(value: char) -> String {
+    format!("{:?}", value)
+}
+
+fn slice_type(element_type: &str, options: &StructOptions) -> String {
+    match options.string_type {
+        StringType::Cow => format!("Cow<'static, [{}]>", element_type),
+        StringType::StaticStr => format!("&'static [{}]", element_type),
+        StringType::String => format!("Vec<{}>", element_type),
+    }
+}
+
+/// The text around the elements of a slice (or `Vec`) of the configured
+/// type.
+fn slice_delimiters(options: &StructOptions) -> (&'static str, &'static str) {
+    match options.string_type {
+        StringType::Cow => ("Cow::Borrowed(&[", "])"),
+        StringType::StaticStr => ("&[", "]"),
+        StringType::String => ("vec![", "]"),
+    }
+}
+
+/// Write `elements` as a slice (or `Vec`) of the configured type, all on
+/// one line.
+fn write_slice<T, F>(output: &mut String, elements: &[T], options: &StructOptions, write_element: F)
+where
+    F: FnMut(&mut String, &T),
+{
+    let (prefix, suffix) = slice_delimiters(options);
+    output.push_str(prefix);
+    write_separated(output, elements, write_element);
+    output.push_str(suffix);
+}
+
+/// Write the elements of an array, then `suffix` to close it.
+///
+/// The elements are written on the current line if `inline` allows it,
+/// there are at most `options.max_inline_array_len` of them, and the line
+/// stays within `options.max_inline_array_width`. Otherwise each goes on its own line,
+/// one level deeper than `indentation`, so that the fields of structs in
+/// the array are indented past the struct they're in.
+fn write_elements<T, F>(
+    output: &mut String,
+    elements: &[T],
+    suffix: &str,
+    indentation: usize,
+    inline: bool,
+    options: &StructOptions,
+    mut write_element: F,
+) where
+    F: FnMut(&mut String, &T, usize),
+{
+    if inline
+        && elements.len() <= options.max_inline_array_len
+        && write_inline(
+            output,
+            elements,
+            suffix,
+            indentation,
+            options,
+            &mut write_element,
+        )
+    {
+        return;
+    }
+
+    for element in elements {
+        output.push('\n');
+        push_indentation(output, indentation + 4);
+        write_element(output, element, indentation + 4);
+        output.push(',');
+    }
+    output.push('\n');
+    push_indentation(output, indentation);
+    output.push_str(suffix);
+}
+
+/// Write the elements of an array and `suffix` on the current line,
+/// returning whether they fit. If they don't, nothing is written, and the
+/// elements after the one that didn't fit aren't tried.
+fn write_inline<T, F>(
+    output: &mut String,
+    elements: &[T],
+    suffix: &str,
+    indentation: usize,
+    options: &StructOptions,
+    write_element: &mut F,
+) -> bool
+where
+    F: FnMut(&mut String, &T, usize),
+{
+    let start = output.len();
+    let line_start = output.rfind('\n').map_or(0, |newline| newline + 1);
+    // Text written by the caller before `output` may start the line.
+    let extra_indentation = if line_start == 0 { indentation } else { 0 };
+    let fits = |output: &str| {
+        !output[start..].contains('\n')
+            && extra_indentation + output.len() - line_start <= options.max_inline_array_width
+    };
+
+    for (index, element) in elements.iter().enumerate() {
+        if index > 0 {
+            output.push_str(", ");
+        }
+        write_element(output, element, indentation + 4);
+        if !fits(output) {
+            output.truncate(start);
+            return false;
+        }
+    }
+    output.push_str(suffix);
+    if !fits(output) {
+        output.truncate(start);
+        return false;
+    }
+    true
+}
+
+/// Whether the expression for `value` always takes up several lines,
+/// because there's a struct or a string of several lines somewhere in it.
+fn spans_lines(value: &GenericValue) -> bool {
+    match *value {
+        GenericValue::Struct(_) | GenericValue::TaggedUnion(_) => true,
+        GenericValue::String(ref text) => text.contains('\n'),
+        GenericValue::Option(Some(ref value)) => spans_lines(value),
+        GenericValue::Array(ref values) | GenericValue::Tuple(ref values) => {
+            values.iter().any(spans_lines)
         }
+        GenericValue::Map(ref map) => map.values.iter().any(spans_lines),
+        _ => false,
+    }
+}
+
+fn tuple_string(elements: &[String]) -> String {
+    if elements.len() == 1 {
+        format!("({},)", elements[0])
+    } else {
+        format!("({})", elements.join(", "))
     }
 }
 
 pub fn struct_value_string(
     value: &GenericStruct,
-    indentation: usize,
-    max_array_size: usize,
+    context: ArrayEmitContext,
+    options: &StructOptions,
 ) -> String {
-    let values = value
-        .fields
-        .iter()
-        .map(|(field, value)| {
-            format!(
-                "{:indent$}{}: {},\n",
-                "",
-                field,
-                value_string(value, indentation + 4, max_array_size),
-                indent = indentation + 4
-            )
-        })
-        .collect::<Vec<String>>();
-    format!(
-        "{} {{\n{}{:indent$}}}",
-        value.struct_name,
-        values.join(""),
-        "",
-        indent = indentation
-    )
+    let mut output = String::new();
+    write_struct_value(&mut output, value, context, options);
+    output
 }
 
-fn float_string<T>(float: T) -> String
+fn write_struct_value(
+    output: &mut String,
+    value: &GenericStruct,
+    context: ArrayEmitContext,
+    options: &StructOptions,
+) {
+    output.push_str(&value.struct_name);
+    if !is_unit_struct(value, options) {
+        write_struct_fields(output, value, context, options);
+    }
+}
+
+/// Write the braced fields of a struct value, or of the variant of an enum
+/// in `tagged_unions`.
+fn write_struct_fields(
+    output: &mut String,
+    value: &GenericStruct,
+    context: ArrayEmitContext,
+    options: &StructOptions,
+) {
+    if value.fields.is_empty() {
+        output.push_str(" {}");
+        return;
+    }
+
+    let field_context = context.indented();
+    output.push_str(" {\n");
+    for (field, field_value) in value.ordered_fields(options.field_order) {
+        output.push_str(&cfg_attribute(
+            value.field_cfgs.get(field),
+            field_context.indentation,
+        ));
+        push_indentation(output, field_context.indentation);
+        output.push_str(field);
+        output.push_str(": ");
+        write_value(output, field_value, field_context, options);
+        output.push_str(",\n");
+    }
+    push_indentation(output, context.indentation);
+    output.push('}');
+}
+
+/// A float literal which parses back to the same value, or the constant
+/// for values which have no literal, like `f64::NAN`.
+pub(crate) fn float_string<T>(float: T, type_name: &str) -> String
 where
-    T: ToString + Copy,
+    T: fmt::Debug + Into<f64> + Copy,
 {
-    let mut result = float.to_string();
-    if !result.contains('.') {
-        result.push_str(".0");
+    let value: f64 = float.into();
+    if value.is_nan() {
+        format!("{}::NAN", type_name)
+    } else if value == f64::INFINITY {
+        format!("{}::INFINITY", type_name)
+    } else if value == f64::NEG_INFINITY {
+        format!("{}::NEG_INFINITY", type_name)
+    } else {
+        // The `Debug` format is the shortest which round-trips, and always
+        // has a decimal point or an exponent.
+        format!("{:?}", float)
     }
-    result
 }
 
 #[cfg(test)]
@@ -208,15 +1112,26 @@ mod tests {
 
     const U: GenericValue = GenericValue::Unit;
 
+    fn top_level_value_string(value: &GenericValue, options: &StructOptions) -> String {
+        value_string(value, ArrayEmitContext::new(options, 0), options)
+    }
+
+    fn array_options(max_array_size: usize) -> StructOptions {
+        StructOptions {
+            max_array_size,
+            ..StructOptions::default()
+        }
+    }
+
     #[test]
     fn empty_slice_type() {
         assert_eq!(
-            type_string_with_options(&GenericValue::Array(vec![]), 0),
+            type_string_with_options(&GenericValue::Array(vec![]), &array_options(0)),
             "Cow<'static, [()]>",
         );
 
         assert_eq!(
-            type_string_with_options(&GenericValue::Array(vec![]), 4),
+            type_string_with_options(&GenericValue::Array(vec![]), &array_options(4)),
             "Cow<'static, [()]>",
         );
     }
@@ -224,7 +1139,7 @@ mod tests {
     #[test]
     fn non_empty_slice_type() {
         assert_eq!(
-            type_string_with_options(&GenericValue::Array(vec![U, U, U]), 0),
+            type_string_with_options(&GenericValue::Array(vec![U, U, U]), &array_options(0)),
             "Cow<'static, [()]>",
         );
     }
@@ -232,7 +1147,7 @@ mod tests {
     #[test]
     fn non_empty_array_type() {
         assert_eq!(
-            type_string_with_options(&GenericValue::Array(vec![U, U, U]), 4),
+            type_string_with_options(&GenericValue::Array(vec![U, U, U]), &array_options(4)),
             "[(); 3]",
         );
     }
@@ -240,12 +1155,12 @@ mod tests {
     #[test]
     fn empty_slice_value() {
         assert_eq!(
-            value_string(&GenericValue::Array(vec![]), 0, 0),
+            top_level_value_string(&GenericValue::Array(vec![]), &array_options(0)),
             "Cow::Borrowed(&[])",
         );
 
         assert_eq!(
-            value_string(&GenericValue::Array(vec![]), 0, 4),
+            top_level_value_string(&GenericValue::Array(vec![]), &array_options(4)),
             "Cow::Borrowed(&[])",
         );
     }
@@ -253,16 +1168,134 @@ mod tests {
     #[test]
     fn non_empty_slice_value() {
         assert_eq!(
-            value_string(&GenericValue::Array(vec![U, U, U]), 0, 0),
+            top_level_value_string(&GenericValue::Array(vec![U, U, U]), &array_options(0)),
             "Cow::Borrowed(&[(), (), ()])",
         );
     }
 
+    #[test]
+    fn default_impl_without_const() {
+        let mut fields = std::collections::BTreeMap::new();
+        fields.insert("x".to_owned(), GenericValue::I64(5));
+        let config = GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields,
+            original_keys: Default::default(),
+            doc: None,
+            field_docs: Default::default(),
+            field_cfgs: Default::default(),
+            field_spans: Default::default(),
+            flattened_fields: Default::default(),
+            absent_fields: Default::default(),
+            field_order: Vec::new(),
+        };
+
+        let code = generate_default_impls(&config, &StructOptions::default(), None);
+        assert!(code.contains("impl Default for Config"));
+        assert!(code.contains("x: 5,"));
+
+        let code = generate_default_impls(&config, &StructOptions::default(), Some("CONFIG"));
+        assert!(code.contains("CONFIG.clone()"));
+    }
+
+    #[test]
+    fn serde_attributes_follow_derives() {
+        let mut fields = std::collections::BTreeMap::new();
+        fields.insert("x".to_owned(), GenericValue::I64(5));
+        let config = GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields,
+            original_keys: Default::default(),
+            doc: None,
+            field_docs: Default::default(),
+            field_cfgs: Default::default(),
+            field_spans: Default::default(),
+            flattened_fields: Default::default(),
+            absent_fields: Default::default(),
+            field_order: Vec::new(),
+        };
+
+        let mut options = StructOptions::serde_default();
+        options.serde_attributes.all_structs = vec!["deny_unknown_fields".to_owned()];
+        options
+            .serde_attributes
+            .by_struct
+            .insert("Config".to_owned(), vec!["default".to_owned()]);
+
+        let code = generate_structs(&config, &options);
+        assert!(code.contains(
+            "serde::Deserialize)]\n#[serde(deny_unknown_fields, default)]\npub struct Config {"
+        ));
+    }
+
     #[test]
     fn non_empty_array_value() {
         assert_eq!(
-            value_string(&GenericValue::Array(vec![U, U, U]), 0, 4),
+            top_level_value_string(&GenericValue::Array(vec![U, U, U]), &array_options(4)),
             "[(), (), ()]",
         );
     }
+
+    #[test]
+    fn string_literals_are_escaped() {
+        assert_eq!(string_literal("plain"), r#""plain""#);
+        assert_eq!(string_literal(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(string_literal(r"C:\path"), r#""C:\\path""#);
+        assert_eq!(string_literal("a\nb\tc\0"), r#""a\nb\tc\0""#);
+        assert_eq!(string_literal("😇 ok"), "\"😇 ok\"");
+        assert_eq!(
+            string_literal(r#""); panic!("boom"); (""#),
+            r#""\"); panic!(\"boom\"); (\"""#
+        );
+    }
+
+    #[test]
+    fn char_literals_are_escaped() {
+        assert_eq!(char_literal('a'), "'a'");
+        assert_eq!(char_literal('\''), r"'\''");
+        assert_eq!(char_literal('\\'), r"'\\'");
+        assert_eq!(char_literal('\n'), r"'\n'");
+        assert_eq!(char_literal('😇'), "'😇'");
+    }
+
+    #[test]
+    fn nested_strings_are_escaped() {
+        let value = GenericValue::Array(vec![GenericValue::String("\"".to_owned())]);
+
+        assert_eq!(
+            top_level_value_string(&value, &StructOptions::default()),
+            r#"Cow::Borrowed(&[Cow::Borrowed("\"")])"#
+        );
+    }
+
+    #[test]
+    fn float_literals_round_trip() {
+        let values = [
+            1.0,
+            0.1,
+            1e-7,
+            -0.0,
+            2.5e300,
+            5e-324,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+        ];
+        for &value in &values {
+            let literal = float_string(value, "f64");
+            let parsed: f64 = literal.parse().unwrap();
+            assert_eq!(parsed.to_bits(), value.to_bits(), "{}", literal);
+        }
+
+        assert_eq!(float_string(1.0, "f64"), "1.0");
+        assert_eq!(float_string(-0.0, "f64"), "-0.0");
+        assert_eq!(float_string(1e-7, "f64"), "1e-7");
+        assert_eq!(float_string(0.1f32, "f32"), "0.1");
+    }
+
+    #[test]
+    fn special_floats_are_constants() {
+        assert_eq!(float_string(f64::NAN, "f64"), "f64::NAN");
+        assert_eq!(float_string(f64::INFINITY, "f64"), "f64::INFINITY");
+        assert_eq!(float_string(f32::NEG_INFINITY, "f32"), "f32::NEG_INFINITY");
+    }
 }
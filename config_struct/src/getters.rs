@@ -0,0 +1,262 @@
+use crate::{
+    error::GenerationError,
+    generation::{declared_structs, string_type, type_string},
+    options::{DateTimeType, MapType, StringType, StructOptions},
+    value::{GenericStruct, GenericValue},
+};
+
+/// Generate a getter for every field of the root struct and each nested
+/// struct, for use with `FieldAccess::Getters`.
+///
+/// Strings, arrays, structs and other larger values are returned by
+/// reference, and scalars are returned by value.
+pub fn generate_getters(
+    struct_value: &GenericStruct,
+    options: &StructOptions,
+) -> Result<String, GenerationError> {
+    check_reserved_names(struct_value, options)?;
+
+    let mut buffer = String::new();
+    for struct_value in declared_structs(struct_value) {
+        generate_struct_getters(&mut buffer, &struct_value, options);
+    }
+
+    Ok(buffer)
+}
+
+/// Fail if a getter would have the same name as one of the other methods
+/// generated for its struct.
+fn check_reserved_names(
+    struct_value: &GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let mut reserved = Vec::new();
+    if options.generate_load_fns {
+        reserved.extend(&["load", "try_load", "load_from", "try_load_from"]);
+    }
+    if options.env_overrides.is_some() {
+        reserved.push("load_with_env_overrides");
+    }
+    if options.generate_get_by_path {
+        reserved.push("get");
+    }
+
+    // Diff methods are generated for every struct, not just the root.
+    let mut reserved_everywhere = Vec::new();
+    if options.generate_diff {
+        reserved_everywhere.extend(&["diff", "apply"]);
+    }
+
+    let root_clash = struct_value
+        .fields
+        .keys()
+        .find(|name| reserved.contains(&name.as_str()));
+    let clash = root_clash.cloned().or_else(|| {
+        declared_structs(struct_value)
+            .iter()
+            .find_map(|struct_value| {
+                struct_value
+                    .fields
+                    .keys()
+                    .find(|name| reserved_everywhere.contains(&name.as_str()))
+                    .cloned()
+            })
+    });
+
+    match clash {
+        Some(name) => Err(GenerationError::ReservedGetterName(name)),
+        None => Ok(()),
+    }
+}
+
+fn generate_struct_getters(
+    output: &mut String,
+    struct_value: &GenericStruct,
+    options: &StructOptions,
+) {
+    let getters = struct_value
+        .ordered_fields(options.field_order)
+        .into_iter()
+        .map(|(name, value)| {
+            let (return_type, expression) = getter(name, value, options);
+            format!(
+                "    pub fn {}(&self) -> {} {{\n        {}\n    }}\n",
+                name, return_type, expression
+            )
+        })
+        .collect::<Vec<String>>();
+
+    output.push_str(&format!(
+        "impl {} {{\n{}}}\n\n",
+        struct_value.struct_name,
+        getters.join("\n")
+    ));
+}
+
+/// The return type and body of the getter for a field.
+fn getter(name: &str, value: &GenericValue, options: &StructOptions) -> (String, String) {
+    let field = format!("self.{}", name);
+
+    if is_copy(value, options) {
+        return (type_string(value, options), field);
+    }
+
+    match *value {
+        GenericValue::String(_) | GenericValue::DateTime(_) => match options.string_type {
+            StringType::StaticStr => ("&'static str".to_owned(), field),
+            StringType::Cow | StringType::String => ("&str".to_owned(), format!("&{}", field)),
+        },
+        GenericValue::Array(ref values) if !options.uses_fixed_array(values.len()) => {
+            let element_type = match GenericValue::typed_element(values) {
+                Some(element) => type_string(element, options),
+                None => type_string(&GenericValue::Unit, options),
+            };
+            slice_getter(&element_type, field, options)
+        }
+        GenericValue::EmptyArray(ref element_type) => slice_getter(element_type, field, options),
+        GenericValue::Bytes(_) => slice_getter("u8", field, options),
+        GenericValue::Map(ref map) if options.map_type == MapType::Slice => {
+            let value_type = match GenericValue::typed_element(&map.values) {
+                Some(value) => type_string(value, options),
+                None => type_string(&GenericValue::Unit, options),
+            };
+            let element_type = format!("({}, {})", string_type(options), value_type);
+            slice_getter(&element_type, field, options)
+        }
+        GenericValue::Option(Some(ref inner)) => (
+            format!("Option<&{}>", type_string(inner, options)),
+            format!("{}.as_ref()", field),
+        ),
+        GenericValue::NoneOf(ref inner_type) => (
+            format!("Option<&{}>", inner_type),
+            format!("{}.as_ref()", field),
+        ),
+        _ => (
+            format!("&{}", type_string(value, options)),
+            format!("&{}", field),
+        ),
+    }
+}
+
+fn slice_getter(element_type: &str, field: String, options: &StructOptions) -> (String, String) {
+    match options.string_type {
+        StringType::StaticStr => (format!("&'static [{}]", element_type), field),
+        StringType::Cow | StringType::String => {
+            (format!("&[{}]", element_type), format!("&{}", field))
+        }
+    }
+}
+
+/// Whether the type of a value is `Copy`, so that a getter can return it
+/// by value.
+pub fn is_copy(value: &GenericValue, options: &StructOptions) -> bool {
+    match *value {
+        GenericValue::Unit
+        | GenericValue::Bool(_)
+        | GenericValue::Char(_)
+        | GenericValue::I8(_)
+        | GenericValue::I16(_)
+        | GenericValue::I32(_)
+        | GenericValue::I64(_)
+        | GenericValue::I128(_)
+        | GenericValue::U8(_)
+        | GenericValue::U16(_)
+        | GenericValue::U32(_)
+        | GenericValue::U64(_)
+        | GenericValue::U128(_)
+        | GenericValue::ISize(_)
+        | GenericValue::Usize(_)
+        | GenericValue::F32(_)
+        | GenericValue::F64(_)
+        | GenericValue::Enum(_)
+        | GenericValue::Newtype(_) => true,
+        GenericValue::DateTime(_) => options.date_time_type != DateTimeType::String,
+        GenericValue::Option(None) => true,
+        GenericValue::Option(Some(ref value)) => is_copy(value, options),
+        GenericValue::String(_)
+        | GenericValue::Bytes(_)
+        | GenericValue::Version(_)
+        | GenericValue::Array(_)
+        | GenericValue::EmptyArray(_)
+        | GenericValue::NoneOf(_)
+        | GenericValue::Tuple(_)
+        | GenericValue::Struct(_)
+        | GenericValue::TaggedUnion(_)
+        | GenericValue::Map(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> GenericStruct {
+        let server = GenericStruct {
+            struct_name: "_Config__server".to_owned(),
+            fields: vec![("port".to_owned(), GenericValue::U16(80))]
+                .into_iter()
+                .collect(),
+            ..GenericStruct::default()
+        };
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![
+                ("name".to_owned(), GenericValue::String("x".to_owned())),
+                ("version".to_owned(), GenericValue::I64(1)),
+                (
+                    "tags".to_owned(),
+                    GenericValue::Array(vec![GenericValue::String("a".to_owned())]),
+                ),
+                (
+                    "nickname".to_owned(),
+                    GenericValue::Option(Some(Box::new(GenericValue::String("y".to_owned())))),
+                ),
+                ("server".to_owned(), GenericValue::Struct(server)),
+            ]
+            .into_iter()
+            .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    #[test]
+    fn getters_return_references_or_copies() {
+        let code = generate_getters(&config(), &StructOptions::default()).unwrap();
+
+        assert!(code.contains("pub fn name(&self) -> &str {\n        &self.name\n    }"));
+        assert!(code.contains("pub fn version(&self) -> i64 {\n        self.version\n    }"));
+        assert!(code.contains("pub fn tags(&self) -> &[Cow<'static, str>] {"));
+        assert!(code.contains(
+            "pub fn nickname(&self) -> Option<&Cow<'static, str>> {\n        self.nickname.as_ref()"
+        ));
+        assert!(code.contains("pub fn server(&self) -> &_Config__server {"));
+        assert!(code.contains("impl _Config__server {"));
+        assert!(code.contains("pub fn port(&self) -> u16 {"));
+    }
+
+    #[test]
+    fn static_strings_are_returned_directly() {
+        let options = StructOptions {
+            string_type: StringType::StaticStr,
+            ..StructOptions::default()
+        };
+        let code = generate_getters(&config(), &options).unwrap();
+
+        assert!(code.contains("pub fn name(&self) -> &'static str {\n        self.name\n    }"));
+        assert!(code.contains("pub fn tags(&self) -> &'static [&'static str] {"));
+    }
+
+    #[test]
+    fn reserved_names_are_errors() {
+        let mut config = config();
+        config
+            .fields
+            .insert("load".to_owned(), GenericValue::Bool(true));
+
+        assert!(generate_getters(&config, &StructOptions::default()).is_ok());
+        match generate_getters(&config, &StructOptions::serde_default()) {
+            Err(GenerationError::ReservedGetterName(name)) => assert_eq!(name, "load"),
+            other => panic!("Expected reserved name error, found {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    error::GenerationError,
+    format::Format,
+    options::{IncludeOptions, StructOptions},
+    parse_config, root_arrays,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Replace each include table in a config with the contents of the file
+/// it names, if `options.resolve_includes` is set.
+///
+/// Includes are relative to the directory of `filepath`, or the current
+/// directory if there is none. Every included file is added to
+/// `included`, so that the `create_*` functions can tell cargo about them.
+pub fn resolve_includes(
+    config: &mut GenericStruct,
+    filepath: Option<&Path>,
+    options: &StructOptions,
+    included: &mut Vec<PathBuf>,
+) -> Result<(), GenerationError> {
+    let include_options = match options.resolve_includes {
+        Some(ref include_options) => include_options,
+        None => return Ok(()),
+    };
+
+    let mut resolver = Resolver {
+        include_options,
+        options,
+        chain: filepath
+            .and_then(|path| path.canonicalize().ok())
+            .into_iter()
+            .collect(),
+        included,
+    };
+
+    let directory = filepath
+        .and_then(Path::parent)
+        .unwrap_or_else(|| Path::new(""));
+    resolver.resolve_struct(config, directory)
+}
+
+struct Resolver<'a> {
+    include_options: &'a IncludeOptions,
+    options: &'a StructOptions,
+
+    /// The files being included, from the outermost one, to detect
+    /// cycles.
+    chain: Vec<PathBuf>,
+    included: &'a mut Vec<PathBuf>,
+}
+
+impl<'a> Resolver<'a> {
+    fn resolve_struct(
+        &mut self,
+        struct_value: &mut GenericStruct,
+        directory: &Path,
+    ) -> Result<(), GenerationError> {
+        for value in struct_value.fields.values_mut() {
+            self.resolve_value(value, directory)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_value(
+        &mut self,
+        value: &mut GenericValue,
+        directory: &Path,
+    ) -> Result<(), GenerationError> {
+        match *value {
+            GenericValue::Struct(ref mut struct_value) => match self.include_path(struct_value) {
+                Some(path) => {
+                    let path = directory.join(path);
+                    let struct_name = struct_value.struct_name.clone();
+                    *value = self.include(&path, &struct_name)?;
+                    Ok(())
+                }
+                None => self.resolve_struct(struct_value, directory),
+            },
+            GenericValue::Option(Some(ref mut value)) => self.resolve_value(value, directory),
+            GenericValue::Array(ref mut values)
+            | GenericValue::Tuple(ref mut values)
+            | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+                for value in values {
+                    self.resolve_value(value, directory)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The path named by an include table, if the struct is one.
+    fn include_path(&self, struct_value: &GenericStruct) -> Option<String> {
+        if struct_value.fields.len() != 1 {
+            return None;
+        }
+
+        let (name, value) = struct_value.fields.iter().next()?;
+        match *value {
+            GenericValue::String(ref path)
+                if struct_value.original_key(name) == self.include_options.key =>
+            {
+                Some(path.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse an included file into a value to replace the include table,
+    /// giving its structs names as if they had been in the including
+    /// file.
+    fn include(&mut self, path: &Path, struct_name: &str) -> Result<GenericValue, GenerationError> {
+        let include_error = |error: &dyn ToString| {
+            GenerationError::IncludeFailed(path.display().to_string(), error.to_string())
+        };
+
+        let canonical_path = path.canonicalize().map_err(|error| include_error(&error))?;
+        if self.chain.contains(&canonical_path) {
+            let cycle = self
+                .chain
+                .iter()
+                .skip_while(|chain_path| **chain_path != canonical_path)
+                .chain(std::iter::once(&canonical_path))
+                .map(|chain_path| chain_path.display().to_string())
+                .collect::<Vec<String>>();
+            return Err(GenerationError::IncludeCycle(cycle.join(" -> ")));
+        }
+
+        let format = Format::from_filename(path).map_err(|error| include_error(&error))?;
+        let source = std::fs::read_to_string(path).map_err(|error| include_error(&error))?;
+        let mut config = parse_config(format, &source, self.options)?;
+        self.included.push(path.to_owned());
+
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        self.chain.push(canonical_path);
+        self.resolve_struct(&mut config, directory)?;
+        self.chain.pop();
+
+        let rename = |mut struct_value: GenericStruct| {
+            struct_value.replace_name_prefix("_Config", struct_name);
+            struct_value.struct_name = struct_name.to_owned();
+            struct_value
+        };
+
+        // The elements of an included root array become an array.
+        if let Some(elements) = root_arrays::root_elements(&config) {
+            let elements = elements
+                .iter()
+                .cloned()
+                .map(|element| match element {
+                    GenericValue::Struct(element) => GenericValue::Struct(rename(element)),
+                    element => element,
+                })
+                .collect();
+            return Ok(GenericValue::Array(elements));
+        }
+
+        Ok(GenericValue::Struct(rename(config)))
+    }
+}
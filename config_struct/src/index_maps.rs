@@ -0,0 +1,215 @@
+use crate::{
+    error::GenerationError,
+    options::{IndexGapBehavior, StructOptions},
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Replace the tables at the paths listed in `options.index_map_paths`
+/// with arrays of their values, ordered by their keys.
+///
+/// Structs among the values are renamed after the table itself, in the
+/// same way as the elements of an array, so that later passes can give
+/// them a single declaration.
+pub fn apply_index_map_paths(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if options.index_map_paths.is_empty() {
+        return Ok(());
+    }
+
+    apply_to_struct(struct_value, "", options)
+}
+
+fn apply_to_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    for (key, value) in &mut struct_value.fields {
+        let path = paths::join(parent_path, key);
+
+        if options.index_map_paths.contains(&path) {
+            convert_to_array(value, &path, options)?;
+        }
+        apply_to_value(value, &path, options)?;
+    }
+    Ok(())
+}
+
+fn apply_to_value(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => apply_to_struct(struct_value, path, options),
+        GenericValue::Option(Some(ref mut value)) => apply_to_value(value, path, options),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                apply_to_value(value, path, options)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn convert_to_array(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let array_value = match *value {
+        GenericValue::Struct(ref mut struct_value) => {
+            let struct_name = struct_value.struct_name.clone();
+            let mut entries = Vec::with_capacity(struct_value.fields.len());
+            for (name, value) in std::mem::take(&mut struct_value.fields) {
+                let key = struct_value.original_key(&name);
+                let index = key
+                    .parse::<u64>()
+                    .map_err(|_| GenerationError::InvalidIndexKey(path.into(), key.into()))?;
+                entries.push((index, name, value));
+            }
+            entries.sort_by_key(|(index, ..)| *index);
+
+            if options.index_map_gaps == IndexGapBehavior::Error {
+                if let Some(missing) = first_missing_index(&entries) {
+                    return Err(GenerationError::MissingIndex(path.into(), missing));
+                }
+            }
+
+            let values = entries
+                .into_iter()
+                .map(|(_, name, mut value)| {
+                    value.replace_name_prefix(&format!("{}__{}", struct_name, name), &struct_name);
+                    value
+                })
+                .collect();
+            GenericValue::Array(values)
+        }
+        GenericValue::Option(None) => return Ok(()),
+        GenericValue::Option(Some(ref mut value)) => return convert_to_array(value, path, options),
+        GenericValue::Array(ref mut values) => {
+            for value in values {
+                convert_to_array(value, path, options)?;
+            }
+            return Ok(());
+        }
+        _ => return Err(GenerationError::InvalidIndexMapPath(path.into())),
+    };
+
+    *value = array_value;
+    Ok(())
+}
+
+/// The first index missing from sorted `entries`, which may start at
+/// either 0 or 1.
+fn first_missing_index<T>(entries: &[(u64, String, T)]) -> Option<u64> {
+    let first = entries.first().map_or(0, |(index, ..)| *index);
+    if first > 1 {
+        return Some(if first == 2 { 1 } else { 0 });
+    }
+
+    entries
+        .windows(2)
+        .find(|pair| pair[1].0 != pair[0].0 + 1)
+        .map(|pair| pair[0].0 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_struct(name: &str, fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: name.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    fn options_with_index_map(path: &str) -> StructOptions {
+        StructOptions {
+            index_map_paths: vec![path.to_owned()],
+            ..StructOptions::default()
+        }
+    }
+
+    fn levels(keys: &[&str]) -> GenericStruct {
+        make_struct(
+            "Config",
+            vec![(
+                "levels",
+                GenericValue::Struct(make_struct(
+                    "_Config__levels",
+                    keys.iter()
+                        .map(|key| (*key, GenericValue::String(key.to_string())))
+                        .collect(),
+                )),
+            )],
+        )
+    }
+
+    #[test]
+    fn table_becomes_array_in_index_order() {
+        let mut config = levels(&["10", "2", "1", "3", "4", "5", "6", "7", "8", "9"]);
+        apply_index_map_paths(&mut config, &options_with_index_map("levels")).unwrap();
+
+        match config.fields["levels"] {
+            GenericValue::Array(ref values) => {
+                let keys = values
+                    .iter()
+                    .map(|value| match *value {
+                        GenericValue::String(ref key) => key.as_str(),
+                        ref other => panic!("Expected string, found {:?}", other),
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(keys, ["1", "2", "3", "4", "5", "6", "7", "8", "9", "10"]);
+            }
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn gaps_are_errors_unless_compacted() {
+        let mut config = levels(&["0", "1", "3"]);
+        match apply_index_map_paths(&mut config, &options_with_index_map("levels")) {
+            Err(GenerationError::MissingIndex(path, 2)) => assert_eq!(path, "levels"),
+            other => panic!("Expected missing index error, found {:?}", other),
+        }
+
+        let mut config = levels(&["2", "3"]);
+        match apply_index_map_paths(&mut config, &options_with_index_map("levels")) {
+            Err(GenerationError::MissingIndex(_, 1)) => (),
+            other => panic!("Expected missing index error, found {:?}", other),
+        }
+
+        let options = StructOptions {
+            index_map_gaps: IndexGapBehavior::Compact,
+            ..options_with_index_map("levels")
+        };
+        let mut config = levels(&["0", "1", "3"]);
+        apply_index_map_paths(&mut config, &options).unwrap();
+        match config.fields["levels"] {
+            GenericValue::Array(ref values) => assert_eq!(values.len(), 3),
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn non_index_keys_are_errors() {
+        let mut config = levels(&["1", "-2"]);
+        match apply_index_map_paths(&mut config, &options_with_index_map("levels")) {
+            Err(GenerationError::InvalidIndexKey(path, key)) => {
+                assert_eq!((path.as_str(), key.as_str()), ("levels", "-2"))
+            }
+            other => panic!("Expected invalid key error, found {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,315 @@
+//! Parsing utilities for INI and `.properties` config files. (Requires the
+//! `ini-parsing` feature.)
+//!
+//! 1.  Keys before the first `[section]` header become fields of the root
+//!     struct, and each section becomes a nested struct.
+//! 2.  Dots in section names and keys nest further, so `[server.tls]` and
+//!     `server.tls.enabled = true` both describe the same field.
+//! 3.  Values are `true` or `false`, integers, floats, or otherwise
+//!     strings. Quoting a value with `"` keeps it as a string.
+//! 4.  Lines starting with `;` or `#` are comments, and either `=` or `:`
+//!     separates a key from its value.
+//! 5.  Keys which appear more than once are handled according to
+//!     `StructOptions::duplicate_keys`.
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    error::{GenerationError, ParseError},
+    options::{DuplicateKeyBehavior, StructOptions},
+    parsing, paths,
+    value::{GenericStruct, GenericValue},
+};
+
+pub fn parse_ini(ini: &str, options: &StructOptions) -> Result<GenericStruct, GenerationError> {
+    // Fields are added as they're read, since sections can be reopened.
+    let mut root =
+        parsing::parsed_to_generic_struct(BTreeMap::new(), options, |_, _, value, _| value);
+    let mut section = Vec::<String>::new();
+    // The line each key first appears on, for duplicate key errors.
+    let mut key_lines = HashMap::new();
+
+    for (index, line) in ini.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+
+        let line_error = |message: &str| {
+            let message = format!("line {}: {}", index + 1, message);
+            GenerationError::ParseFailed(ParseError::from_message(
+                message,
+                ini,
+                Some(index + 1),
+                None,
+            ))
+        };
+
+        if line.starts_with('[') {
+            let name = line
+                .strip_suffix(']')
+                .map(|name| name[1..].trim())
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| line_error("invalid section header."))?;
+            section = split_key(name);
+            insert_section(&mut root, "_Config", &section, "")
+                .map_err(|message| line_error(&message))?;
+            continue;
+        }
+
+        let separator = line
+            .find(['=', ':'])
+            .ok_or_else(|| line_error("expected `key = value`."))?;
+        let key = line[..separator].trim();
+        if key.is_empty() {
+            return Err(line_error("missing key."));
+        }
+
+        let mut key_path = section.clone();
+        key_path.extend(split_key(key));
+        let value = line[separator + 1..].trim();
+        parsing::check_integer_literal(&key_path.join("."), value)?;
+        let value = parse_value(value, options);
+
+        insert_value(&mut root, "_Config", &key_path, "", value, options).map_err(|error| {
+            match error {
+                InsertError::Duplicate(path) => {
+                    let first_line = key_lines[&path];
+                    GenerationError::DuplicateKeyAt(path, first_line, index + 1)
+                }
+                InsertError::Conflict(message) => line_error(&message),
+            }
+        })?;
+        key_lines.entry(key_path.join(".")).or_insert(index + 1);
+    }
+
+    Ok(root)
+}
+
+fn split_key(key: &str) -> Vec<String> {
+    key.split('.').map(|part| part.trim().to_owned()).collect()
+}
+
+fn parse_value(value: &str, options: &StructOptions) -> GenericValue {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        return GenericValue::String(value[1..value.len() - 1].to_owned());
+    }
+
+    if let Ok(value) = value.parse::<bool>() {
+        GenericValue::Bool(value)
+    } else if let Ok(value) = value.parse::<i64>() {
+        GenericValue::I64(value)
+    } else if let Ok(value) = value.parse::<u64>() {
+        GenericValue::U64(value)
+    } else if let Some(value) = parsing::large_integer(value) {
+        value
+    } else if let Some(value) = parse_float(value) {
+        parsing::preferred_float(value, options.default_float_size)
+    } else {
+        GenericValue::String(value.to_owned())
+    }
+}
+
+/// Parse a float, without accepting words like `inf` or `NaN`, which
+/// are more likely to be strings.
+fn parse_float(value: &str) -> Option<f64> {
+    let numeric = value
+        .chars()
+        .all(|c| c.is_ascii_digit() || "+-.eE".contains(c));
+    let has_digit = value.chars().any(|c| c.is_ascii_digit());
+
+    if numeric && has_digit {
+        value.parse().ok()
+    } else {
+        None
+    }
+}
+
+enum InsertError {
+    Duplicate(String),
+    Conflict(String),
+}
+
+fn insert_section(
+    struct_value: &mut GenericStruct,
+    struct_name: &str,
+    key_path: &[String],
+    parent_path: &str,
+) -> Result<(), String> {
+    let (key, rest) = match key_path.split_first() {
+        Some(split) => split,
+        None => return Ok(()),
+    };
+    let path = paths::join(parent_path, key);
+    let nested_name = format!("{}__{}", struct_name, key);
+
+    match nested_struct(struct_value, key, &nested_name) {
+        Some(nested) => insert_section(nested, &nested_name, rest, &path),
+        None => Err(format!("`{}` is both a value and a section.", path)),
+    }
+}
+
+fn insert_value(
+    struct_value: &mut GenericStruct,
+    struct_name: &str,
+    key_path: &[String],
+    parent_path: &str,
+    value: GenericValue,
+    options: &StructOptions,
+) -> Result<(), InsertError> {
+    let (key, rest) = key_path.split_first().expect("Keys are never empty.");
+    let path = paths::join(parent_path, key);
+
+    if !rest.is_empty() {
+        let nested_name = format!("{}__{}", struct_name, key);
+        return match nested_struct(struct_value, key, &nested_name) {
+            Some(nested) => insert_value(nested, &nested_name, rest, &path, value, options),
+            None => Err(InsertError::Conflict(format!(
+                "`{}` is both a value and a section.",
+                path
+            ))),
+        };
+    }
+
+    match struct_value.fields.get_mut(key) {
+        None => {
+            struct_value.field_order.push(key.clone());
+            struct_value.fields.insert(key.clone(), value);
+            Ok(())
+        }
+        Some(GenericValue::Struct(_)) => Err(InsertError::Conflict(format!(
+            "`{}` is both a value and a section.",
+            path
+        ))),
+        Some(existing) => match options.duplicate_keys {
+            DuplicateKeyBehavior::Error => Err(InsertError::Duplicate(path)),
+            DuplicateKeyBehavior::Array => {
+                match *existing {
+                    GenericValue::Array(ref mut values) => values.push(value),
+                    _ => {
+                        let first = std::mem::replace(existing, GenericValue::Unit);
+                        *existing = GenericValue::Array(vec![first, value]);
+                    }
+                }
+                Ok(())
+            }
+            DuplicateKeyBehavior::FirstWins => Ok(()),
+            DuplicateKeyBehavior::LastWins => {
+                *existing = value;
+                Ok(())
+            }
+        },
+    }
+}
+
+/// The nested struct under `key`, created if it doesn't exist yet, or
+/// `None` if `key` already holds some other value.
+fn nested_struct<'a>(
+    struct_value: &'a mut GenericStruct,
+    key: &str,
+    nested_name: &str,
+) -> Option<&'a mut GenericStruct> {
+    if !struct_value.fields.contains_key(key) {
+        struct_value.field_order.push(key.to_owned());
+    }
+
+    let value = struct_value
+        .fields
+        .entry(key.to_owned())
+        .or_insert_with(|| {
+            GenericValue::Struct(GenericStruct {
+                struct_name: nested_name.to_owned(),
+                ..GenericStruct::default()
+            })
+        });
+
+    match *value {
+        GenericValue::Struct(ref mut nested) => Some(nested),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field<'a>(struct_value: &'a GenericStruct, key: &str) -> &'a GenericValue {
+        &struct_value.fields[key]
+    }
+
+    #[test]
+    fn sections_become_nested_structs() {
+        let source = "
+; A comment
+name = Example
+debug = true
+
+[server]
+port = 8080
+ratio: 0.5
+tls.enabled = false
+";
+        let config = parse_ini(source, &StructOptions::default()).unwrap();
+
+        assert!(matches!(field(&config, "name"), GenericValue::String(name) if name == "Example"));
+        assert!(matches!(field(&config, "debug"), GenericValue::Bool(true)));
+        assert_eq!(config.field_order, vec!["name", "debug", "server"]);
+
+        let server = match field(&config, "server") {
+            GenericValue::Struct(server) => server,
+            other => panic!("Expected struct, found {:?}", other),
+        };
+        assert_eq!(server.struct_name, "_Config__server");
+        assert!(matches!(field(server, "port"), GenericValue::I64(8080)));
+        assert!(matches!(field(server, "ratio"), GenericValue::F64(_)));
+
+        match field(server, "tls") {
+            GenericValue::Struct(tls) => {
+                assert_eq!(tls.struct_name, "_Config__server__tls");
+                assert!(matches!(field(tls, "enabled"), GenericValue::Bool(false)));
+            }
+            other => panic!("Expected struct, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn quoted_values_are_strings() {
+        let config = parse_ini("version = \"10\"\nword = inf", &StructOptions::default()).unwrap();
+
+        assert!(matches!(field(&config, "version"), GenericValue::String(value) if value == "10"));
+        assert!(matches!(field(&config, "word"), GenericValue::String(value) if value == "inf"));
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let source = "[hosts]\nname = a\nname = b";
+
+        match parse_ini(source, &StructOptions::default()) {
+            Err(GenerationError::DuplicateKeyAt(path, first, second)) => {
+                assert_eq!(path, "hosts.name");
+                assert_eq!((first, second), (2, 3));
+            }
+            other => panic!("Expected duplicate key error, found {:?}", other),
+        }
+
+        let options = StructOptions {
+            duplicate_keys: DuplicateKeyBehavior::Array,
+            ..StructOptions::default()
+        };
+        let config = parse_ini(source, &options).unwrap();
+        match field(&config, "hosts") {
+            GenericValue::Struct(hosts) => {
+                assert!(
+                    matches!(field(hosts, "name"), GenericValue::Array(values) if values.len() == 2)
+                )
+            }
+            other => panic!("Expected struct, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_lines_are_errors() {
+        assert!(parse_ini("just words", &StructOptions::default()).is_err());
+        assert!(parse_ini("[unclosed", &StructOptions::default()).is_err());
+        assert!(parse_ini("a = 1\n[a]", &StructOptions::default()).is_err());
+    }
+}
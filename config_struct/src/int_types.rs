@@ -0,0 +1,498 @@
+use std::{collections::HashMap, convert::TryFrom};
+
+use crate::{
+    error::GenerationError,
+    options::{IntSize, StructOptions},
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Convert the integers in the config to the types they'll be declared
+/// with, checking that each value fits.
+///
+/// Integers at the paths listed in `options.field_int_types` get the types
+/// given there, and the rest get `options.default_int_size`. Parsers read
+/// integers as `i64`, or the first of `u64`, `i128` and `u128` they fit
+/// in, so this is where they're narrowed.
+///
+/// With `IntSize::Auto`, every integer of a field gets the smallest type
+/// which fits all of them, wherever they are in arrays or maps, so the
+/// field has a single type.
+pub fn apply_int_types(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let mut ranges = HashMap::new();
+    visit_struct(struct_value, "", options, &mut |value, path, int_size| {
+        if int_size == IntSize::Auto {
+            ranges
+                .entry(path.to_owned())
+                .or_insert_with(IntRange::default)
+                .include(value);
+        }
+        Ok(())
+    })?;
+
+    let auto_sizes = ranges
+        .into_iter()
+        .map(|(path, range)| {
+            let int_size = range.smallest_size(&path)?;
+            Ok((path, int_size))
+        })
+        .collect::<Result<HashMap<_, _>, GenerationError>>()?;
+
+    visit_struct(struct_value, "", options, &mut |value, path, int_size| {
+        let int_size = match int_size {
+            IntSize::Auto => auto_sizes[path],
+            int_size => int_size,
+        };
+        convert_integer(value, path, int_size)
+    })
+}
+
+/// Call `visit` with each integer in the config, the path its type is
+/// chosen by, and the type it should have.
+fn visit_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+    visit: &mut IntegerVisitor,
+) -> Result<(), GenerationError> {
+    for (key, value) in &mut struct_value.fields {
+        let path = paths::join(parent_path, key);
+
+        match options.field_int_types.get(&path) {
+            Some(&int_size) => visit_integers(value, &path, int_size, visit)?,
+            None => visit_value(value, &path, options, visit)?,
+        }
+    }
+    Ok(())
+}
+
+type IntegerVisitor<'a> =
+    dyn FnMut(&mut GenericValue, &str, IntSize) -> Result<(), GenerationError> + 'a;
+
+fn visit_value(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+    visit: &mut IntegerVisitor,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => {
+            visit_struct(struct_value, path, options, visit)
+        }
+        GenericValue::Option(Some(ref mut value)) => visit_value(value, path, options, visit),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                visit_value(value, path, options, visit)?;
+            }
+            Ok(())
+        }
+        _ if is_integer(value) => visit(value, path, options.default_int_size),
+        _ => Ok(()),
+    }
+}
+
+/// Visit every integer under a path listed in `field_int_types`, which
+/// must hold only integers.
+fn visit_integers(
+    value: &mut GenericValue,
+    path: &str,
+    int_size: IntSize,
+    visit: &mut IntegerVisitor,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Option(None) => Ok(()),
+        GenericValue::Option(Some(ref mut value)) => visit_integers(value, path, int_size, visit),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                visit_integers(value, path, int_size, visit)?;
+            }
+            Ok(())
+        }
+        _ if is_integer(value) => visit(value, path, int_size),
+        _ => Err(GenerationError::InvalidIntegerField(path.into())),
+    }
+}
+
+/// The smallest and largest integers of a field with `IntSize::Auto`.
+/// The range always includes zero, which fits in every type.
+#[derive(Default)]
+struct IntRange {
+    min: i128,
+    max: i128,
+    /// The largest value which is too large for `i128`, if any.
+    beyond_i128: Option<u128>,
+}
+
+impl IntRange {
+    fn include(&mut self, value: &GenericValue) {
+        match integer_value(value) {
+            Some(value) => {
+                self.min = self.min.min(value);
+                self.max = self.max.max(value);
+            }
+            None => {
+                if let GenericValue::U128(value) = *value {
+                    self.beyond_i128 = self.beyond_i128.max(Some(value));
+                }
+            }
+        }
+    }
+
+    /// The smallest type which fits the whole range, preferring signed
+    /// types of the same size. Negative values can't share a field with
+    /// values too large for `i64`.
+    fn smallest_size(&self, path: &str) -> Result<IntSize, GenerationError> {
+        const AUTO_SIZES: [IntSize; 10] = [
+            IntSize::I8,
+            IntSize::U8,
+            IntSize::I16,
+            IntSize::U16,
+            IntSize::I32,
+            IntSize::U32,
+            IntSize::I64,
+            IntSize::U64,
+            IntSize::I128,
+            IntSize::U128,
+        ];
+
+        let max = match self.beyond_i128 {
+            Some(max) => max.to_string(),
+            None => self.max.to_string(),
+        };
+        if self.min < 0 && (self.beyond_i128.is_some() || self.max > i128::from(i64::MAX)) {
+            return Err(GenerationError::MixedSignIntegers(
+                path.into(),
+                self.min.to_string(),
+                max,
+            ));
+        }
+
+        AUTO_SIZES
+            .iter()
+            .cloned()
+            .find(|&int_size| {
+                let fits_i128 = sized_integer(self.min, int_size).is_some()
+                    && sized_integer(self.max, int_size).is_some();
+                fits_i128 && (self.beyond_i128.is_none() || int_size == IntSize::U128)
+            })
+            .ok_or_else(|| GenerationError::IntegerOutOfRange(path.into(), max, "u128".into()))
+    }
+}
+
+fn convert_integer(
+    value: &mut GenericValue,
+    path: &str,
+    int_size: IntSize,
+) -> Result<(), GenerationError> {
+    *value = resize_integer(value, int_size).ok_or_else(|| {
+        GenerationError::IntegerOutOfRange(
+            path.into(),
+            integer_string(value),
+            int_size_name(int_size).into(),
+        )
+    })?;
+    Ok(())
+}
+
+fn is_integer(value: &GenericValue) -> bool {
+    integer_value(value).is_some() || matches!(*value, GenericValue::U128(_))
+}
+
+/// The value of an integer, unless it's not an integer, or is a `u128`
+/// too large for `i128`.
+pub fn integer_value(value: &GenericValue) -> Option<i128> {
+    let integer = match *value {
+        GenericValue::I8(value) => i128::from(value),
+        GenericValue::I16(value) => i128::from(value),
+        GenericValue::I32(value) => i128::from(value),
+        GenericValue::I64(value) => i128::from(value),
+        GenericValue::I128(value) => value,
+        GenericValue::ISize(value) => value as i128,
+        GenericValue::U8(value) => i128::from(value),
+        GenericValue::U16(value) => i128::from(value),
+        GenericValue::U32(value) => i128::from(value),
+        GenericValue::U64(value) => i128::from(value),
+        GenericValue::U128(value) => i128::try_from(value).ok()?,
+        GenericValue::Usize(value) => value as i128,
+        _ => return None,
+    };
+    Some(integer)
+}
+
+fn integer_string(value: &GenericValue) -> String {
+    match *value {
+        GenericValue::U128(value) => value.to_string(),
+        _ => integer_value(value)
+            .map(|value| value.to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// The integer `value` converted to `int_size`, if it's an integer which
+/// fits.
+///
+/// Values are never converted to `Auto`, which only picks a concrete
+/// type for a field.
+pub fn resize_integer(value: &GenericValue, int_size: IntSize) -> Option<GenericValue> {
+    match *value {
+        GenericValue::U128(value) if i128::try_from(value).is_err() => match int_size {
+            IntSize::U128 => Some(GenericValue::U128(value)),
+            _ => None,
+        },
+        _ => sized_integer(integer_value(value)?, int_size),
+    }
+}
+
+fn sized_integer(value: i128, int_size: IntSize) -> Option<GenericValue> {
+    let value = match int_size {
+        IntSize::I8 => GenericValue::I8(i8::try_from(value).ok()?),
+        IntSize::I16 => GenericValue::I16(i16::try_from(value).ok()?),
+        IntSize::I32 => GenericValue::I32(i32::try_from(value).ok()?),
+        IntSize::I64 => GenericValue::I64(i64::try_from(value).ok()?),
+        IntSize::I128 => GenericValue::I128(value),
+        IntSize::ISize => GenericValue::ISize(isize::try_from(value).ok()?),
+        IntSize::U8 => GenericValue::U8(u8::try_from(value).ok()?),
+        IntSize::U16 => GenericValue::U16(u16::try_from(value).ok()?),
+        IntSize::U32 => GenericValue::U32(u32::try_from(value).ok()?),
+        IntSize::U64 => GenericValue::U64(u64::try_from(value).ok()?),
+        IntSize::U128 => GenericValue::U128(u128::try_from(value).ok()?),
+        IntSize::USize => GenericValue::Usize(usize::try_from(value).ok()?),
+        IntSize::Auto => return None,
+    };
+    Some(value)
+}
+
+/// The type of an integer, unless it's not an integer.
+pub fn int_size_of(value: &GenericValue) -> Option<IntSize> {
+    let int_size = match *value {
+        GenericValue::I8(_) => IntSize::I8,
+        GenericValue::I16(_) => IntSize::I16,
+        GenericValue::I32(_) => IntSize::I32,
+        GenericValue::I64(_) => IntSize::I64,
+        GenericValue::I128(_) => IntSize::I128,
+        GenericValue::ISize(_) => IntSize::ISize,
+        GenericValue::U8(_) => IntSize::U8,
+        GenericValue::U16(_) => IntSize::U16,
+        GenericValue::U32(_) => IntSize::U32,
+        GenericValue::U64(_) => IntSize::U64,
+        GenericValue::U128(_) => IntSize::U128,
+        GenericValue::Usize(_) => IntSize::USize,
+        _ => return None,
+    };
+    Some(int_size)
+}
+
+pub fn int_size_name(int_size: IntSize) -> &'static str {
+    match int_size {
+        IntSize::I8 => "i8",
+        IntSize::I16 => "i16",
+        IntSize::I32 => "i32",
+        IntSize::I64 => "i64",
+        IntSize::I128 => "i128",
+        IntSize::ISize => "isize",
+        IntSize::U8 => "u8",
+        IntSize::U16 => "u16",
+        IntSize::U32 => "u32",
+        IntSize::U64 => "u64",
+        IntSize::U128 => "u128",
+        IntSize::USize => "usize",
+        IntSize::Auto => "i128` or `u128",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::*;
+
+    fn options_with_int_type(path: &str, int_size: IntSize) -> StructOptions {
+        let mut options = StructOptions::default();
+        options.field_int_types.insert(path.to_owned(), int_size);
+        options
+    }
+
+    fn config_with(key: &str, value: GenericValue) -> GenericStruct {
+        let mut fields = BTreeMap::new();
+        fields.insert(key.to_owned(), value);
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields,
+            original_keys: BTreeMap::new(),
+            doc: None,
+            field_docs: BTreeMap::new(),
+            field_cfgs: BTreeMap::new(),
+            field_spans: BTreeMap::new(),
+            flattened_fields: BTreeSet::new(),
+            absent_fields: BTreeSet::new(),
+            field_order: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn integers_are_converted() {
+        let options = options_with_int_type("ports", IntSize::U16);
+        let mut config = config_with(
+            "ports",
+            GenericValue::Array(vec![GenericValue::I64(80), GenericValue::I64(443)]),
+        );
+
+        apply_int_types(&mut config, &options).unwrap();
+
+        match config.fields["ports"] {
+            GenericValue::Array(ref values) => match values[..] {
+                [GenericValue::U16(80), GenericValue::U16(443)] => (),
+                ref other => panic!("Unexpected values: {:?}", other),
+            },
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn out_of_range_values_are_an_error() {
+        let options = options_with_int_type("port", IntSize::U16);
+        let mut config = config_with("port", GenericValue::I64(-1));
+
+        match apply_int_types(&mut config, &options) {
+            Err(GenerationError::IntegerOutOfRange(path, value, int_type)) => {
+                assert_eq!(path, "port");
+                assert_eq!(value, "-1");
+                assert_eq!(int_type, "u16");
+            }
+            other => panic!("Expected range error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn non_integer_is_an_error() {
+        let options = options_with_int_type("port", IntSize::U16);
+        let mut config = config_with("port", GenericValue::String("80".into()));
+
+        assert!(apply_int_types(&mut config, &options).is_err());
+    }
+
+    #[test]
+    fn default_size_is_applied() {
+        let options = StructOptions {
+            default_int_size: IntSize::I16,
+            ..StructOptions::default()
+        };
+        let mut config = config_with("port", GenericValue::I64(8080));
+
+        apply_int_types(&mut config, &options).unwrap();
+
+        assert!(matches!(config.fields["port"], GenericValue::I16(8080)));
+    }
+
+    #[test]
+    fn auto_arrays_are_upgraded() {
+        let options = StructOptions {
+            default_int_size: IntSize::Auto,
+            ..StructOptions::default()
+        };
+        let mut config = config_with(
+            "ids",
+            GenericValue::Array(vec![GenericValue::I64(1), GenericValue::U64(u64::MAX)]),
+        );
+
+        apply_int_types(&mut config, &options).unwrap();
+
+        match config.fields["ids"] {
+            GenericValue::Array(ref values) => match values[..] {
+                [GenericValue::U64(1), GenericValue::U64(u64::MAX)] => (),
+                ref other => panic!("Unexpected values: {:?}", other),
+            },
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn auto_arrays_cannot_mix_signs_beyond_i64() {
+        let options = StructOptions {
+            default_int_size: IntSize::Auto,
+            ..StructOptions::default()
+        };
+        let mut config = config_with(
+            "ids",
+            GenericValue::Array(vec![GenericValue::I64(-1), GenericValue::U64(u64::MAX)]),
+        );
+
+        match apply_int_types(&mut config, &options) {
+            Err(GenerationError::MixedSignIntegers(path, min, max)) => {
+                assert_eq!(path, "ids");
+                assert_eq!(min, "-1");
+                assert_eq!(max, u64::MAX.to_string());
+            }
+            other => panic!("Expected mixed sign error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn auto_picks_the_smallest_type_per_field() {
+        let options = StructOptions {
+            default_int_size: IntSize::Auto,
+            ..StructOptions::default()
+        };
+        let point = |x, y| {
+            GenericValue::Struct(GenericStruct {
+                struct_name: "_Config__points".to_owned(),
+                fields: vec![
+                    ("x".to_owned(), GenericValue::I64(x)),
+                    ("y".to_owned(), GenericValue::I64(y)),
+                ]
+                .into_iter()
+                .collect(),
+                ..GenericStruct::default()
+            })
+        };
+        let mut config = config_with(
+            "points",
+            GenericValue::Array(vec![point(1, -1), point(300, 200)]),
+        );
+
+        apply_int_types(&mut config, &options).unwrap();
+
+        match config.fields["points"] {
+            GenericValue::Array(ref values) => {
+                for value in values {
+                    match *value {
+                        GenericValue::Struct(ref point) => {
+                            assert!(matches!(point.fields["x"], GenericValue::I16(_)));
+                            assert!(matches!(point.fields["y"], GenericValue::I16(_)));
+                        }
+                        ref other => panic!("Expected struct, found {:?}", other),
+                    }
+                }
+            }
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+
+        let mut config = config_with("port", GenericValue::I64(255));
+        apply_int_types(&mut config, &options).unwrap();
+        assert!(matches!(config.fields["port"], GenericValue::U8(255)));
+    }
+
+    #[test]
+    fn large_u128_values_only_fit_u128() {
+        let mut config = config_with("id", GenericValue::U128(u128::MAX));
+        let options = options_with_int_type("id", IntSize::I128);
+
+        match apply_int_types(&mut config, &options) {
+            Err(GenerationError::IntegerOutOfRange(path, value, int_type)) => {
+                assert_eq!(path, "id");
+                assert_eq!(value, u128::MAX.to_string());
+                assert_eq!(int_type, "i128");
+            }
+            other => panic!("Expected range error, found {:?}", other),
+        }
+
+        let options = options_with_int_type("id", IntSize::U128);
+        apply_int_types(&mut config, &options).unwrap();
+        assert!(matches!(config.fields["id"], GenericValue::U128(u128::MAX)));
+    }
+}
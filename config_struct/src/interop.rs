@@ -0,0 +1,370 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    error::GenerationError,
+    generation::{float_string, string_literal},
+    int_types::integer_value,
+    naming::upper_snake_case,
+    options::{Rule, StringType, StructOptions},
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// The derive which makes the root struct a `clap` parser.
+pub(crate) const CLAP_PARSER: &str = "clap::Parser";
+
+/// The derive for `schemars` schemas.
+const JSON_SCHEMA: &str = "schemars::JsonSchema";
+
+/// Whether the structs are command-line parsers, with the attributes
+/// `clap` needs on their fields.
+pub fn derives_clap(options: &StructOptions) -> bool {
+    cfg!(feature = "clap-support")
+        && options
+            .derived_traits
+            .iter()
+            .any(|name| name == CLAP_PARSER)
+}
+
+/// Whether the structs derive `schemars::JsonSchema`, with attributes for
+/// the rules in `validations`.
+pub fn derives_schemars(options: &StructOptions) -> bool {
+    cfg!(feature = "schemars-support")
+        && options
+            .derived_traits
+            .iter()
+            .any(|name| name == JSON_SCHEMA)
+}
+
+/// What the derives in `derived_traits` are applied to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeriveTarget {
+    RootStruct,
+    NestedStruct,
+    Enum,
+}
+
+/// Replace `clap::Parser` with the `clap` derive for the type: nested
+/// structs are flattened into the parser as `clap::Args`, and enums are
+/// values.
+pub fn adjust_derives(
+    derived_traits: &mut [String],
+    target: DeriveTarget,
+    options: &StructOptions,
+) {
+    if !derives_clap(options) {
+        return;
+    }
+
+    let replacement = match target {
+        DeriveTarget::RootStruct => return,
+        DeriveTarget::NestedStruct => "clap::Args",
+        DeriveTarget::Enum => "clap::ValueEnum",
+    };
+    for name in derived_traits {
+        if name == CLAP_PARSER {
+            *name = replacement.to_owned();
+        }
+    }
+}
+
+/// The paths to a declared struct.
+#[derive(Debug, Clone, Default)]
+pub struct StructPath {
+    /// The dotted keys of the config, for naming command-line arguments.
+    pub keys: String,
+
+    /// The dotted field names, as `validations` are written.
+    pub fields: String,
+}
+
+/// The paths to each struct, by name. The structs of array elements are
+/// at the array's path with a `*` added, like `servers.*`.
+///
+/// A struct shared by several paths, after `deduplicate_structs`, is at
+/// the first of them.
+pub fn struct_paths(root: &GenericStruct) -> BTreeMap<String, StructPath> {
+    let mut struct_paths = BTreeMap::new();
+    collect_struct_paths(root, StructPath::default(), &mut struct_paths);
+    struct_paths
+}
+
+fn collect_struct_paths(
+    struct_value: &GenericStruct,
+    path: StructPath,
+    struct_paths: &mut BTreeMap<String, StructPath>,
+) {
+    if struct_paths.contains_key(&struct_value.struct_name) {
+        return;
+    }
+    struct_paths.insert(struct_value.struct_name.clone(), path.clone());
+
+    for (name, value) in &struct_value.fields {
+        let field_path = StructPath {
+            keys: paths::join(&path.keys, struct_value.original_key(name)),
+            fields: paths::join(&path.fields, name),
+        };
+        collect_value_paths(value, field_path, struct_paths);
+    }
+}
+
+fn collect_value_paths(
+    value: &GenericValue,
+    path: StructPath,
+    struct_paths: &mut BTreeMap<String, StructPath>,
+) {
+    match *value {
+        GenericValue::Struct(ref struct_value) => {
+            collect_struct_paths(struct_value, path, struct_paths)
+        }
+        GenericValue::Option(Some(ref value)) => collect_value_paths(value, path, struct_paths),
+        GenericValue::Array(ref values)
+        | GenericValue::Tuple(ref values)
+        | GenericValue::Map(GenericMap { ref values, .. }) => {
+            let element_path = StructPath {
+                keys: paths::join(&path.keys, "*"),
+                fields: paths::join(&path.fields, "*"),
+            };
+            for value in values {
+                collect_value_paths(value, element_path.clone(), struct_paths);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// The `clap` and `schemars` attributes for a field of a struct at
+/// `path`, each on its own line.
+pub fn field_attributes(
+    struct_value: &GenericStruct,
+    name: &str,
+    value: &GenericValue,
+    path: &StructPath,
+    options: &StructOptions,
+) -> String {
+    let mut attributes = String::new();
+
+    if derives_clap(options) {
+        let key_path = paths::join(&path.keys, struct_value.original_key(name));
+        attributes.push_str(&clap_attribute(value, &key_path));
+    }
+
+    if derives_schemars(options) {
+        let field_path = paths::join(&path.fields, name);
+        let rules = options
+            .validations
+            .iter()
+            .filter(|(pattern, _)| paths::matches_glob(pattern, &field_path))
+            .filter_map(|(_, rule)| schemars_rule(value, rule))
+            .collect::<Vec<String>>();
+        if !rules.is_empty() {
+            attributes.push_str(&format!("    #[schemars({})]\n", rules.join(", ")));
+        }
+    }
+
+    attributes
+}
+
+/// The name of the command-line argument for a dotted key path, like
+/// `server-port` for `server.port`.
+pub fn flag_name(key_path: &str) -> String {
+    upper_snake_case(key_path).to_lowercase().replace('_', "-")
+}
+
+fn clap_attribute(value: &GenericValue, key_path: &str) -> String {
+    if let GenericValue::Struct(_) = *value {
+        return "    #[command(flatten)]\n".to_owned();
+    }
+
+    let flag = string_literal(&flag_name(key_path));
+    let mut args = vec![format!("id = {}", flag), format!("long = {}", flag)];
+    match *value {
+        // Flags would always be `true` when given, so bools take a value.
+        GenericValue::Bool(_) => args.push("action = clap::ArgAction::Set".to_owned()),
+        GenericValue::Array(ref values) => {
+            args.push("value_delimiter = ','".to_owned());
+            if !values.is_empty() {
+                let defaults = values
+                    .iter()
+                    .filter_map(argument_text)
+                    .map(|text| string_literal(&text))
+                    .collect::<Vec<String>>();
+                args.push(format!("default_values = [{}]", defaults.join(", ")));
+            }
+        }
+        _ => (),
+    }
+    if let Some(text) = argument_text(value) {
+        args.push(format!("default_value = {}", string_literal(&text)));
+    }
+
+    format!("    #[arg({})]\n", args.join(", "))
+}
+
+/// A value as it would be given on the command line, if it can be.
+fn argument_text(value: &GenericValue) -> Option<String> {
+    match *value {
+        GenericValue::Bool(value) => Some(value.to_string()),
+        GenericValue::Char(value) => Some(value.to_string()),
+        GenericValue::F32(value) => Some(format!("{:?}", value)),
+        GenericValue::F64(value) => Some(format!("{:?}", value)),
+        GenericValue::String(ref text)
+        | GenericValue::DateTime(ref text)
+        | GenericValue::Version(ref text) => Some(text.clone()),
+        GenericValue::Enum(ref enum_value) => Some(enum_value.value.clone()),
+        GenericValue::Option(Some(ref value)) => argument_text(value),
+        _ => integer_value(value).map(|value| value.to_string()),
+    }
+}
+
+/// Whether a value has a type `clap` can parse from a single argument.
+fn is_argument(value: &GenericValue) -> bool {
+    !matches!(
+        *value,
+        GenericValue::Unit
+            | GenericValue::Bytes(_)
+            | GenericValue::Option(_)
+            | GenericValue::NoneOf(_)
+            | GenericValue::Newtype(_)
+            | GenericValue::Array(_)
+            | GenericValue::EmptyArray(_)
+            | GenericValue::Tuple(_)
+            | GenericValue::Struct(_)
+            | GenericValue::TaggedUnion(_)
+            | GenericValue::Map(_)
+    )
+}
+
+/// Check that every field of the config can be a command-line argument,
+/// when deriving `clap::Parser`, failing with the paths of those which
+/// can't.
+pub fn check_cli_fields(
+    struct_value: &GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if !derives_clap(options) {
+        return Ok(());
+    }
+
+    let mut checker = CliChecker {
+        options,
+        flags: BTreeMap::new(),
+        structs: BTreeSet::new(),
+        unsupported: Vec::new(),
+    };
+    checker.check_struct(struct_value, "");
+
+    if checker.unsupported.is_empty() {
+        Ok(())
+    } else {
+        Err(GenerationError::UnsupportedCliFields(checker.unsupported))
+    }
+}
+
+struct CliChecker<'a> {
+    options: &'a StructOptions,
+    /// The key path each flag name was taken by.
+    flags: BTreeMap<String, String>,
+    structs: BTreeSet<String>,
+    unsupported: Vec<String>,
+}
+
+impl<'a> CliChecker<'a> {
+    fn check_struct(&mut self, struct_value: &GenericStruct, parent_path: &str) {
+        // A struct flattened in twice would have the same flags twice.
+        if !self.structs.insert(struct_value.struct_name.clone()) {
+            self.unsupported.push(parent_path.to_owned());
+            return;
+        }
+
+        for (name, value) in &struct_value.fields {
+            let path = paths::join(parent_path, struct_value.original_key(name));
+            self.check_value(value, path);
+        }
+    }
+
+    fn check_value(&mut self, value: &GenericValue, path: String) {
+        let value = match *value {
+            GenericValue::Option(Some(ref value)) if is_argument(value) => value,
+            _ => value,
+        };
+
+        let supported = match *value {
+            GenericValue::Struct(ref struct_value) => {
+                self.check_struct(struct_value, &path);
+                return;
+            }
+            // Only `Vec`s are parsed as several values.
+            GenericValue::Array(ref values) => {
+                self.options.string_type == StringType::String && values.iter().all(is_argument)
+            }
+            _ => is_argument(value),
+        };
+
+        let flag = flag_name(&path);
+        if !supported || self.flags.contains_key(&flag) {
+            self.unsupported.push(path);
+        } else {
+            self.flags.insert(flag, path);
+        }
+    }
+}
+
+/// The `schemars` validation attribute for a rule on a field, if it has
+/// one. Rules on arrays apply to their elements, as they do in
+/// `validations`.
+fn schemars_rule(value: &GenericValue, rule: &Rule) -> Option<String> {
+    let attribute = match *rule {
+        Rule::IntRange(min, max) => format!("range(min = {}, max = {})", min, max),
+        Rule::FloatRange(min, max) => format!(
+            "range(min = {}, max = {})",
+            float_string(min, "f64"),
+            float_string(max, "f64")
+        ),
+        Rule::NonEmptyString | Rule::NonEmptyArray => "length(min = 1)".to_owned(),
+        Rule::OneOf(_) => return None,
+        #[cfg(feature = "regex")]
+        Rule::MatchesRegex(ref pattern) => format!("regex(pattern = {})", string_literal(pattern)),
+    };
+
+    let is_array = match *value {
+        GenericValue::Option(Some(ref value)) => matches!(**value, GenericValue::Array(_)),
+        GenericValue::Array(_) => true,
+        _ => false,
+    };
+    if is_array && *rule != Rule::NonEmptyArray {
+        Some(format!("inner({})", attribute))
+    } else {
+        Some(attribute)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_are_kebab_case_paths() {
+        assert_eq!(flag_name("server.port"), "server-port");
+        assert_eq!(flag_name("logLevel"), "log-level");
+        assert_eq!(flag_name("db.max_connections"), "db-max-connections");
+    }
+
+    #[test]
+    fn array_rules_apply_to_elements() {
+        let ports = GenericValue::Array(vec![GenericValue::I64(80)]);
+
+        assert_eq!(
+            schemars_rule(&ports, &Rule::IntRange(1, 65535)).unwrap(),
+            "inner(range(min = 1, max = 65535))"
+        );
+        assert_eq!(
+            schemars_rule(&ports, &Rule::NonEmptyArray).unwrap(),
+            "length(min = 1)"
+        );
+        assert_eq!(
+            schemars_rule(&ports, &Rule::OneOf(vec!["a".to_owned()])),
+            None
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Number, Value};
+
+use crate::{
+    error::{GenerationError, ParseError},
+    json_parsing,
+    options::{DuplicateKeyBehavior, StructOptions},
+    paths,
+    value::GenericStruct,
+};
+
+/// Parse JSON5, which includes JSON with comments and trailing commas
+/// (JSONC). Once parsed, it's handled like JSON with `allow_non_finite`.
+pub fn parse_json5(json5: &str, options: &StructOptions) -> Result<GenericStruct, GenerationError> {
+    let keep_first = options.duplicate_keys == DuplicateKeyBehavior::FirstWins;
+    let mut duplicates = Vec::new();
+    let mut text = json5.to_owned();
+    let json_value = loop {
+        duplicates.clear();
+        let seed = Json5Seed {
+            path: "",
+            duplicates: &mut duplicates,
+            keep_first,
+        };
+        let mut deserializer =
+            json5::Deserializer::from_str(&text).map_err(|err| parse_error(err, json5))?;
+        match seed.deserialize(&mut deserializer) {
+            Ok(value) => break value,
+            Err(err) => match integer_as_float(&text, &err) {
+                Some(rewritten) => text = rewritten,
+                None => return Err(parse_error(err, json5)),
+            },
+        }
+    };
+
+    match options.duplicate_keys {
+        DuplicateKeyBehavior::Error | DuplicateKeyBehavior::Array => {
+            if let Some(path) = duplicates.into_iter().next() {
+                return Err(GenerationError::DuplicateKey(path));
+            }
+        }
+        DuplicateKeyBehavior::FirstWins | DuplicateKeyBehavior::LastWins => (),
+    }
+
+    let mut options = options.clone();
+    options.format_options.json.allow_non_finite = true;
+    json_parsing::json_value_to_generic_struct(json_value, json5, &options)
+}
+
+/// Reads a JSON value from JSON5. Unlike `serde_json::Value`'s own
+/// deserializer, this keeps `Infinity` and `NaN`, as the strings
+/// `json_parsing` reads non-finite numbers as, and records the paths of
+/// keys repeated in the same object.
+struct Json5Seed<'a> {
+    path: &'a str,
+    duplicates: &'a mut Vec<String>,
+    keep_first: bool,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for Json5Seed<'a> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for Json5Seed<'a> {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON5 value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Value, E> {
+        Ok(match Number::from_f64(value) {
+            Some(number) => Value::Number(number),
+            None => Value::String(json_parsing::non_finite_string(value)),
+        })
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = Vec::new();
+        loop {
+            let seed = Json5Seed {
+                path: self.path,
+                duplicates: &mut *self.duplicates,
+                keep_first: self.keep_first,
+            };
+            match seq.next_element_seed(seed)? {
+                Some(value) => values.push(value),
+                None => return Ok(Value::Array(values)),
+            }
+        }
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut fields = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let path = paths::join(self.path, &key);
+            let value = map.next_value_seed(Json5Seed {
+                path: &path,
+                duplicates: &mut *self.duplicates,
+                keep_first: self.keep_first,
+            })?;
+
+            if fields.contains_key(&key) {
+                self.duplicates.push(path);
+                if self.keep_first {
+                    continue;
+                }
+            }
+            fields.insert(key, value);
+        }
+        Ok(Value::Object(fields))
+    }
+}
+
+/// `json5` only reads integers which fit in `i64`. If `err` is for a
+/// larger integer, rewrite it in `json5` as a float, which is how the
+/// `json5` crate reads it into a float field at runtime.
+fn integer_as_float(json5: &str, err: &json5::Error) -> Option<String> {
+    let json5::Error::Message {
+        ref msg,
+        ref location,
+    } = *err;
+    let location = location.as_ref()?;
+    if msg != "error parsing integer" {
+        return None;
+    }
+
+    let line_start = match location.line {
+        1 => 0,
+        line => json5.match_indices('\n').nth(line - 2)?.0 + 1,
+    };
+    let (column, _) = json5[line_start..]
+        .char_indices()
+        .nth(location.column - 1)?;
+    let start = line_start + column;
+    let digits = json5[start..].trim_start_matches(['+', '-']);
+    let end = json5.len()
+        - digits
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    if end == json5.len() - digits.len() {
+        return None;
+    }
+
+    Some(format!("{}.0{}", &json5[..end], &json5[end..]))
+}
+
+fn parse_error(err: json5::Error, json5: &str) -> GenerationError {
+    let json5::Error::Message { ref location, .. } = err;
+    let (line, column) = match *location {
+        Some(ref location) => (Some(location.line), Some(location.column)),
+        None => (None, None),
+    };
+    GenerationError::ParseFailed(ParseError::new(err, json5, line, column))
+}
@@ -1,23 +1,374 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use serde_json::{self, Value};
 
 use crate::{
-    error::GenerationError,
-    options::StructOptions,
-    parsing,
+    duplicate_keys::{line_at, resolve_duplicate_keys, DuplicateKey},
+    error::{GenerationError, ParseError},
+    nulls,
+    options::{DuplicateKeyBehavior, StructOptions},
+    parsing, paths,
     value::{GenericStruct, GenericValue},
 };
 
 pub fn parse_json(json: &str, options: &StructOptions) -> Result<GenericStruct, GenerationError> {
+    // An empty file has no fields, as it would in the other formats.
+    if json.trim().is_empty() {
+        return Ok(parsing::parsed_to_generic_struct(
+            parsing::ParsedFields::new(),
+            options,
+            json_to_raw_value,
+        ));
+    }
+
+    // `serde_json` gives up on deep nesting without saying where.
+    check_text_depth(json, options)?;
+
+    let replaced;
+    let text = if options.format_options.json.allow_non_finite {
+        replaced = replace_non_finite(json);
+        &replaced
+    } else {
+        json
+    };
+    let mut json_value = read_value(text, json, options)?;
+
+    // Duplicates can only be found once the JSON is known to be valid.
+    if options.duplicate_keys != DuplicateKeyBehavior::LastWins {
+        let duplicates = find_duplicate_keys(text);
+        if let Some(deduplicated) = resolve_duplicate_keys(text, duplicates, options)? {
+            json_value = read_value(&deduplicated, json, options)?;
+        }
+    }
+
+    json_value_to_generic_struct(json_value, json, options)
+}
+
+/// Read the value in `text`, reporting errors against the original
+/// `json`. With `allow_trailing_characters`, only the first value is
+/// read.
+fn read_value(text: &str, json: &str, options: &StructOptions) -> Result<Value, GenerationError> {
+    let value = if options.format_options.json.allow_trailing_characters {
+        let mut values = serde_json::Deserializer::from_str(text).into_iter::<Value>();
+        // An empty stream has no first value, but should fail as usual.
+        values.next().unwrap_or_else(|| serde_json::from_str(text))
+    } else {
+        serde_json::from_str(text)
+    };
+    value.map_err(|err| parse_error(err, json))
+}
+
+/// The words JavaScript writes non-finite numbers as, and their values.
+const NON_FINITE: [(&str, f64); 3] = [
+    ("-Infinity", f64::NEG_INFINITY),
+    ("Infinity", f64::INFINITY),
+    ("NaN", f64::NAN),
+];
+
+/// `serde_json` can't read the non-finite words, so outside of strings
+/// they're replaced with strings no config would have: the word after a
+/// NUL. `json_to_raw_value` turns those back into floats.
+fn replace_non_finite(json: &str) -> String {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut replaced = String::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut rest = json;
+
+    while let Some(c) = rest.chars().next() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => (),
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if !replaced.ends_with(is_word_char) {
+            let word = NON_FINITE.iter().map(|&(word, _)| word).find(|word| {
+                rest.starts_with(word) && !rest[word.len()..].starts_with(is_word_char)
+            });
+            if let Some(word) = word {
+                replaced.push_str("\"\\u0000");
+                replaced.push_str(word);
+                replaced.push('"');
+                rest = &rest[word.len()..];
+                continue;
+            }
+        }
+
+        replaced.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    replaced
+}
+
+/// The string `replace_non_finite` would read a non-finite `value` as.
+pub fn non_finite_string(value: f64) -> String {
+    let word = match value {
+        _ if value.is_nan() => "NaN",
+        _ if value > 0.0 => "Infinity",
+        _ => "-Infinity",
+    };
+    format!("\0{}", word)
+}
+
+/// Convert a parsed JSON value, read from `json`, to a struct.
+pub fn json_value_to_generic_struct(
+    json_value: Value,
+    json: &str,
+    options: &StructOptions,
+) -> Result<GenericStruct, GenerationError> {
     use parsing::ParsedFields;
 
-    let json_struct: ParsedFields<Value> = serde_json::from_str(json)
-        .map_err(|err| GenerationError::DeserializationFailed(err.to_string()))?;
+    parsing::check_depth(&json_value, options)?;
+    check_integer_sizes(&json_value, "")?;
+
+    if let Value::Array(elements) = json_value {
+        let elements = elements
+            .into_iter()
+            .map(|element| match element {
+                Value::Object(fields) => Ok(fields.into_iter().collect()),
+                other => Err(other),
+            })
+            .collect();
+        let mut generic_struct =
+            parsing::parsed_to_root_array(elements, options, json_to_raw_value);
+        nulls::apply_null_behavior(&mut generic_struct, options)?;
+        return Ok(generic_struct);
+    }
+
+    let json_struct: ParsedFields<Value> =
+        serde_json::from_value(json_value).map_err(|err| parse_error(err, json))?;
 
-    let generic_struct = parsing::parsed_to_generic_struct(json_struct, options, json_to_raw_value);
+    let mut generic_struct =
+        parsing::parsed_to_generic_struct(json_struct, options, json_to_raw_value);
+    nulls::apply_null_behavior(&mut generic_struct, options)?;
 
     Ok(generic_struct)
 }
 
+fn parse_error(err: serde_json::Error, json: &str) -> GenerationError {
+    // Errors without a location have line 0.
+    let (line, column) = match err.line() {
+        0 => (None, None),
+        line => (Some(line), Some(err.column())),
+    };
+    GenerationError::ParseFailed(ParseError::new(err, json, line, column))
+}
+
+impl parsing::Nested for Value {
+    fn children(&self) -> Option<Vec<(Option<&str>, &Self)>> {
+        match *self {
+            Value::Object(ref fields) => Some(
+                fields
+                    .iter()
+                    .map(|(key, value)| (Some(key.as_str()), value))
+                    .collect(),
+            ),
+            Value::Array(ref values) => Some(values.iter().map(|value| (None, value)).collect()),
+            _ => None,
+        }
+    }
+}
+
+/// Fail if the containers in `json` are nested more than
+/// `options.max_depth` deep, scanning the text so that the error can name
+/// the path before `serde_json` fails. Invalid JSON is left to
+/// `serde_json`.
+fn check_text_depth(json: &str, options: &StructOptions) -> Result<(), GenerationError> {
+    // The path of each open container, and whether it's an object.
+    let mut containers: Vec<(String, bool)> = Vec::new();
+    let mut key = String::new();
+    let mut expecting_key = false;
+    let mut position = 0;
+    let bytes = json.as_bytes();
+
+    while position < bytes.len() {
+        match bytes[position] {
+            b'"' => {
+                let start = position;
+                position += 1;
+                while position < bytes.len() && bytes[position] != b'"' {
+                    position += if bytes[position] == b'\\' { 2 } else { 1 };
+                }
+                if expecting_key {
+                    key = serde_json::from_str(&json[start..(position + 1).min(json.len())])
+                        .unwrap_or_default();
+                    expecting_key = false;
+                }
+            }
+            open @ (b'{' | b'[') => {
+                let path = match containers.last() {
+                    Some((path, true)) => paths::join(path, &key),
+                    Some((path, false)) => path.clone(),
+                    None => String::new(),
+                };
+                let depth = containers.len();
+                if depth > options.max_depth {
+                    return Err(GenerationError::MaxDepthExceeded { path, depth });
+                }
+                containers.push((path, open == b'{'));
+                expecting_key = open == b'{';
+            }
+            b'}' | b']' => {
+                containers.pop();
+            }
+            b',' => expecting_key = matches!(containers.last(), Some((_, true))),
+            _ => (),
+        }
+        position += 1;
+    }
+
+    Ok(())
+}
+
+/// Find the keys which appear more than once in the same object of some
+/// valid JSON.
+fn find_duplicate_keys(json: &str) -> Vec<DuplicateKey> {
+    let mut scanner = DuplicateScanner {
+        json,
+        position: 0,
+        duplicates: Vec::new(),
+    };
+    scanner.value("");
+    scanner.duplicates
+}
+
+/// Scans the structure of JSON which `serde_json` has already accepted,
+/// so it skips over scalars without checking them.
+struct DuplicateScanner<'a> {
+    json: &'a str,
+    position: usize,
+    duplicates: Vec<DuplicateKey>,
+}
+
+impl DuplicateScanner<'_> {
+    fn value(&mut self, path: &str) {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(path),
+            Some(b'[') => self.array(path),
+            Some(b'"') => self.string(),
+            _ => {
+                while !matches!(
+                    self.peek(),
+                    None | Some(b',' | b']' | b'}' | b' ' | b'\t' | b'\n' | b'\r')
+                ) {
+                    self.position += 1;
+                }
+            }
+        }
+    }
+
+    fn object(&mut self, path: &str) {
+        let mut keys = BTreeMap::new();
+        let mut previous_end = self.position;
+        self.position += 1;
+
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b'}') | None => break,
+                Some(b',') => {
+                    self.position += 1;
+                    continue;
+                }
+                _ => (),
+            }
+
+            let key_start = self.position;
+            self.string();
+            let key = serde_json::from_str::<String>(&self.json[key_start..self.position])
+                .expect("Keys are valid strings.");
+            let key_path = paths::join(path, &key);
+
+            self.skip_whitespace();
+            self.position += 1; // The `:`.
+            self.value(&key_path);
+
+            match keys.get(&key) {
+                Some(&first_start) => self.duplicates.push(DuplicateKey {
+                    path: key_path,
+                    lines: (
+                        line_at(self.json, first_start),
+                        line_at(self.json, key_start),
+                    ),
+                    removal: previous_end..self.position,
+                }),
+                None => {
+                    keys.insert(key, key_start);
+                }
+            }
+            previous_end = self.position;
+        }
+        self.position += 1;
+    }
+
+    fn array(&mut self, path: &str) {
+        self.position += 1;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b']') | None => break,
+                Some(b',') => self.position += 1,
+                _ => self.value(path),
+            }
+        }
+        self.position += 1;
+    }
+
+    fn string(&mut self) {
+        self.position += 1;
+        while let Some(byte) = self.peek() {
+            self.position += if byte == b'\\' { 2 } else { 1 };
+            if byte == b'"' {
+                break;
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.json.as_bytes().get(self.position).copied()
+    }
+}
+
+/// Check that no integer is too large for 128 bits. Numbers keep their
+/// original text, so these can be told apart from floats.
+fn check_integer_sizes(value: &Value, path: &str) -> Result<(), GenerationError> {
+    match *value {
+        Value::Number(ref number) => parsing::check_integer_literal(path, &number.to_string()),
+        Value::Array(ref values) => values
+            .iter()
+            .try_for_each(|value| check_integer_sizes(value, path)),
+        Value::Object(ref fields) => fields
+            .iter()
+            .try_for_each(|(key, value)| check_integer_sizes(value, &paths::join(path, key))),
+        _ => Ok(()),
+    }
+}
+
+/// The float a string read by `replace_non_finite` stands for, if it is
+/// one.
+fn non_finite_value(text: &str, options: &StructOptions) -> Option<f64> {
+    if !options.format_options.json.allow_non_finite {
+        return None;
+    }
+    let word = text.strip_prefix('\0')?;
+    NON_FINITE
+        .iter()
+        .find(|&&(non_finite, _)| non_finite == word)
+        .map(|&(_, value)| value)
+}
+
 fn json_to_raw_value(
     super_struct: &str,
     super_key: &str,
@@ -25,16 +376,19 @@ fn json_to_raw_value(
     options: &StructOptions,
 ) -> GenericValue {
     match value {
-        Value::Null => GenericValue::Option(None),
+        Value::Null => nulls::null_value(options),
         Value::Bool(value) => GenericValue::Bool(value),
         Value::Number(value) => match (value.as_i64(), value.as_u64(), value.as_f64()) {
-            // TODO: Add some unit tests for this
-            (Some(x), _, _) => parsing::preferred_int(x, options.default_int_size),
+            (Some(x), _, _) => GenericValue::I64(x),
             (None, Some(x), _) => GenericValue::U64(x),
-            (None, None, Some(x)) => parsing::preferred_float(x, options.default_float_size),
+            (None, None, Some(x)) => parsing::large_integer(&value.to_string())
+                .unwrap_or_else(|| parsing::preferred_float(x, options.default_float_size)),
             _ => unimplemented!("Should handle error here"), // TODO
         },
-        Value::String(value) => GenericValue::String(value),
+        Value::String(value) => match non_finite_value(&value, options) {
+            Some(value) => parsing::preferred_float(value, options.default_float_size),
+            None => GenericValue::String(value),
+        },
         Value::Array(values) => GenericValue::Array(
             values
                 .into_iter()
@@ -53,6 +407,14 @@ fn json_to_raw_value(
             GenericValue::Struct(GenericStruct {
                 struct_name: sub_struct_name,
                 fields: values,
+                original_keys: BTreeMap::new(),
+                doc: None,
+                field_docs: BTreeMap::new(),
+                field_cfgs: BTreeMap::new(),
+                field_spans: BTreeMap::new(),
+                flattened_fields: BTreeSet::new(),
+                absent_fields: BTreeSet::new(),
+                field_order: Vec::new(),
             })
         }
     }
@@ -0,0 +1,366 @@
+//! Generation of a JSON Schema describing the config, for tools which
+//! check or edit config files outside of Rust.
+use std::fmt::Write;
+
+use crate::{
+    datetimes::{self, DateTimeKind},
+    options::{MissingFields, StructOptions},
+    root_arrays, root_tables,
+    tagged_unions::variant_templates,
+    unification::element_template,
+    value::{GenericMap, GenericStruct, GenericTaggedUnion, GenericValue},
+};
+
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// A JSON value, with the keys of objects kept in order.
+#[derive(Clone)]
+enum Json {
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn string(value: &str) -> Json {
+        Json::String(value.to_owned())
+    }
+
+    fn number<T: ToString>(value: T) -> Json {
+        Json::Number(value.to_string())
+    }
+}
+
+/// Generate a JSON Schema (draft 2020-12) for a processed config.
+///
+/// The schema describes the config file rather than the generated code,
+/// so properties use the keys from the config, not the field names.
+pub fn json_schema(config: &GenericStruct, options: &StructOptions) -> String {
+    let mut schema = vec![
+        ("$schema".to_owned(), Json::string(SCHEMA_DIALECT)),
+        ("title".to_owned(), Json::string(&options.struct_name)),
+    ];
+    if let Some(tables) = root_tables::root_tables(config) {
+        schema.extend(tables_schema(tables, options));
+    } else if let Some(elements) = root_arrays::root_elements(config) {
+        schema.extend(array_schema(elements, options));
+    } else {
+        schema.extend(struct_schema(config, options));
+    }
+
+    let mut output = String::new();
+    write_json(&mut output, &Json::Object(schema), 0);
+    output.push('\n');
+    output
+}
+
+fn struct_schema(struct_value: &GenericStruct, options: &StructOptions) -> Vec<(String, Json)> {
+    let serde_attributes = options
+        .serde_attributes
+        .for_struct(&struct_value.struct_name);
+    let fields_have_defaults = options.serde_missing_fields != MissingFields::Error
+        || serde_attributes.contains(&"default");
+
+    let mut properties = Vec::new();
+    let mut required = Vec::new();
+    for (name, value) in struct_value.ordered_fields(options.field_order) {
+        let key = struct_value.original_key(name);
+
+        let mut schema = value_schema(value, options);
+        if let Some(doc) = struct_value.field_docs.get(name) {
+            schema.push(("description".to_owned(), Json::string(doc)));
+        }
+        properties.push((key.to_owned(), Json::Object(schema)));
+
+        let optional = matches!(*value, GenericValue::Option(_) | GenericValue::NoneOf(_));
+        if !fields_have_defaults && !optional {
+            required.push(Json::string(key));
+        }
+    }
+
+    let mut schema = vec![("type".to_owned(), Json::string("object"))];
+    if let Some(ref doc) = struct_value.doc {
+        schema.push(("description".to_owned(), Json::string(doc)));
+    }
+    schema.push(("properties".to_owned(), Json::Object(properties)));
+    if !required.is_empty() {
+        schema.push(("required".to_owned(), Json::Array(required)));
+    }
+    if serde_attributes.contains(&"deny_unknown_fields") {
+        schema.push(("additionalProperties".to_owned(), Json::Bool(false)));
+    }
+    schema
+}
+
+/// The schema of a table from `tagged_unions`: one of its variants, each
+/// with its tag.
+fn union_schema(union: &GenericTaggedUnion, options: &StructOptions) -> Vec<(String, Json)> {
+    let bodies = variant_templates(&[union]);
+    let variants = union
+        .variants
+        .iter()
+        .map(|(tag_value, name)| {
+            let empty = GenericStruct::default();
+            let body = bodies.get(name).unwrap_or(&empty);
+            let tag_schema = vec![("const".to_owned(), Json::string(tag_value))];
+
+            let mut schema = struct_schema(body, options);
+            let mut has_required = false;
+            for (key, value) in &mut schema {
+                match (key.as_str(), value) {
+                    ("properties", Json::Object(properties)) => {
+                        properties.insert(0, (union.tag.clone(), Json::Object(tag_schema.clone())))
+                    }
+                    ("required", Json::Array(required)) => {
+                        required.insert(0, Json::string(&union.tag));
+                        has_required = true;
+                    }
+                    _ => (),
+                }
+            }
+            if !has_required {
+                schema.push((
+                    "required".to_owned(),
+                    Json::Array(vec![Json::string(&union.tag)]),
+                ));
+            }
+            Json::Object(schema)
+        })
+        .collect();
+
+    vec![("oneOf".to_owned(), Json::Array(variants))]
+}
+
+/// The schema of a config with a const per top-level table. The tables
+/// are all required, and any other top-level value must be a table of
+/// the same shape.
+fn tables_schema(tables: &GenericMap, options: &StructOptions) -> Vec<(String, Json)> {
+    let table_schema = element_template(&tables.values)
+        .map(|table| value_schema(&table, options))
+        .unwrap_or_default();
+
+    let properties = tables
+        .keys
+        .iter()
+        .map(|key| (key.clone(), Json::Object(table_schema.clone())))
+        .collect();
+    let required = tables.keys.iter().map(|key| Json::string(key)).collect();
+
+    vec![
+        ("type".to_owned(), Json::string("object")),
+        ("properties".to_owned(), Json::Object(properties)),
+        ("required".to_owned(), Json::Array(required)),
+        (
+            "additionalProperties".to_owned(),
+            Json::Object(table_schema),
+        ),
+    ]
+}
+
+fn array_schema(values: &[GenericValue], options: &StructOptions) -> Vec<(String, Json)> {
+    let mut schema = vec![("type".to_owned(), Json::string("array"))];
+    if let Some(element) = element_template(values) {
+        schema.push((
+            "items".to_owned(),
+            Json::Object(value_schema(&element, options)),
+        ));
+    }
+    if options.uses_fixed_array(values.len()) {
+        schema.push(("minItems".to_owned(), Json::number(values.len())));
+        schema.push(("maxItems".to_owned(), Json::number(values.len())));
+    }
+    schema
+}
+
+fn value_schema(value: &GenericValue, options: &StructOptions) -> Vec<(String, Json)> {
+    let type_schema = |type_name: &str| vec![("type".to_owned(), Json::string(type_name))];
+    let integer_schema = |min: i128, max: u128| {
+        vec![
+            ("type".to_owned(), Json::string("integer")),
+            ("minimum".to_owned(), Json::number(min)),
+            ("maximum".to_owned(), Json::number(max)),
+        ]
+    };
+
+    match *value {
+        GenericValue::Unit | GenericValue::Option(None) => type_schema("null"),
+        GenericValue::Bool(_) => type_schema("boolean"),
+        GenericValue::Char(_) => {
+            let mut schema = type_schema("string");
+            schema.push(("minLength".to_owned(), Json::number(1)));
+            schema.push(("maxLength".to_owned(), Json::number(1)));
+            schema
+        }
+        GenericValue::I8(_) => integer_schema(i8::MIN.into(), i8::MAX as u128),
+        GenericValue::I16(_) => integer_schema(i16::MIN.into(), i16::MAX as u128),
+        GenericValue::I32(_) => integer_schema(i32::MIN.into(), i32::MAX as u128),
+        GenericValue::I64(_) => integer_schema(i64::MIN.into(), i64::MAX as u128),
+        GenericValue::U8(_) => integer_schema(0, u8::MAX.into()),
+        GenericValue::U16(_) => integer_schema(0, u16::MAX.into()),
+        GenericValue::U32(_) => integer_schema(0, u32::MAX.into()),
+        GenericValue::U64(_) => integer_schema(0, u64::MAX.into()),
+        GenericValue::I128(_) | GenericValue::ISize(_) => type_schema("integer"),
+        GenericValue::U128(_) | GenericValue::Usize(_) => {
+            let mut schema = type_schema("integer");
+            schema.push(("minimum".to_owned(), Json::number(0)));
+            schema
+        }
+        GenericValue::F32(_) | GenericValue::F64(_) => type_schema("number"),
+        GenericValue::String(_) | GenericValue::Version(_) => type_schema("string"),
+        GenericValue::DateTime(ref text) => {
+            let mut schema = type_schema("string");
+            let format = match datetimes::date_time_kind(text) {
+                DateTimeKind::OffsetDateTime => Some("date-time"),
+                DateTimeKind::LocalDate => Some("date"),
+                DateTimeKind::LocalDateTime | DateTimeKind::LocalTime => None,
+            };
+            if let Some(format) = format {
+                schema.push(("format".to_owned(), Json::string(format)));
+            }
+            schema
+        }
+        GenericValue::Bytes(_) => {
+            let mut schema = type_schema("string");
+            schema.push(("contentEncoding".to_owned(), Json::string("base64")));
+            schema
+        }
+        GenericValue::Option(Some(ref value)) => vec![(
+            "anyOf".to_owned(),
+            Json::Array(vec![
+                Json::Object(value_schema(value, options)),
+                Json::Object(type_schema("null")),
+            ]),
+        )],
+        GenericValue::Array(ref values) => array_schema(values, options),
+        GenericValue::EmptyArray(_) => type_schema("array"),
+        // The inner type is Rust source, so any value is allowed.
+        GenericValue::NoneOf(_) => Vec::new(),
+        GenericValue::Tuple(ref values) => {
+            let items = values
+                .iter()
+                .map(|value| Json::Object(value_schema(value, options)))
+                .collect();
+            let mut schema = type_schema("array");
+            schema.push(("prefixItems".to_owned(), Json::Array(items)));
+            schema.push(("items".to_owned(), Json::Bool(false)));
+            schema
+        }
+        GenericValue::Struct(ref struct_value) => struct_schema(struct_value, options),
+        // Newtypes are written as the numbers they hold.
+        GenericValue::Newtype(ref newtype) => value_schema(&newtype.value, options),
+        GenericValue::TaggedUnion(ref union) => union_schema(union, options),
+        GenericValue::Enum(ref enum_value) => {
+            let variants = enum_value
+                .variants
+                .iter()
+                .map(|variant| Json::string(variant))
+                .collect();
+            let mut schema = type_schema("string");
+            schema.push(("enum".to_owned(), Json::Array(variants)));
+            schema
+        }
+        GenericValue::Map(ref map) => {
+            let mut schema = type_schema("object");
+            if let Some(value) = element_template(&map.values) {
+                schema.push((
+                    "additionalProperties".to_owned(),
+                    Json::Object(value_schema(&value, options)),
+                ));
+            }
+            schema
+        }
+    }
+}
+
+fn write_json(output: &mut String, value: &Json, indentation: usize) {
+    match *value {
+        Json::Bool(value) => write!(output, "{}", value).unwrap(),
+        Json::Number(ref value) => output.push_str(value),
+        Json::String(ref value) => write_json_string(output, value),
+        Json::Array(ref values) if values.is_empty() => output.push_str("[]"),
+        Json::Array(ref values) => {
+            output.push_str("[\n");
+            for (index, value) in values.iter().enumerate() {
+                push_indentation(output, indentation + 2);
+                write_json(output, value, indentation + 2);
+                output.push_str(if index + 1 < values.len() {
+                    ",\n"
+                } else {
+                    "\n"
+                });
+            }
+            push_indentation(output, indentation);
+            output.push(']');
+        }
+        Json::Object(ref entries) if entries.is_empty() => output.push_str("{}"),
+        Json::Object(ref entries) => {
+            output.push_str("{\n");
+            for (index, (key, value)) in entries.iter().enumerate() {
+                push_indentation(output, indentation + 2);
+                write_json_string(output, key);
+                output.push_str(": ");
+                write_json(output, value, indentation + 2);
+                output.push_str(if index + 1 < entries.len() {
+                    ",\n"
+                } else {
+                    "\n"
+                });
+            }
+            push_indentation(output, indentation);
+            output.push('}');
+        }
+    }
+}
+
+fn write_json_string(output: &mut String, value: &str) {
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => write!(output, "\\u{:04x}", c as u32).unwrap(),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+fn push_indentation(output: &mut String, indentation: usize) {
+    output.extend(std::iter::repeat_n(' ', indentation));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_is_pretty_printed() {
+        let json = Json::Object(vec![
+            (
+                "a".to_owned(),
+                Json::Array(vec![Json::number(1), Json::Bool(true)]),
+            ),
+            ("b".to_owned(), Json::Object(vec![])),
+        ]);
+        let mut output = String::new();
+        write_json(&mut output, &json, 0);
+
+        assert_eq!(
+            output,
+            "{\n  \"a\": [\n    1,\n    true\n  ],\n  \"b\": {}\n}"
+        );
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        let mut output = String::new();
+        write_json_string(&mut output, "say \"hi\"\n\\\u{1}");
+
+        assert_eq!(output, r#""say \"hi\"\n\\\u0001""#);
+    }
+}
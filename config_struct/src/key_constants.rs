@@ -0,0 +1,177 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    diagnostics::{Warning, WarningKind},
+    generation::string_literal,
+    naming::upper_snake_case,
+    paths,
+    value::{GenericStruct, GenericValue},
+};
+
+/// Generate `pub mod keys`, with a const holding the dotted path of each
+/// field which isn't a table.
+pub fn generate_key_constants(struct_value: &GenericStruct) -> String {
+    let consts = key_constants(struct_value, &mut Vec::new())
+        .into_iter()
+        .map(|(name, path)| {
+            format!(
+                "    pub const {}: &str = {};\n",
+                name,
+                string_literal(&path)
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "
+/// The dotted paths of the keys in `{}`, for naming them in logs and
+/// metrics.
+pub mod keys {{
+{}}}
+",
+        struct_value.struct_name, consts
+    )
+}
+
+/// Warn about the paths whose const names had to be changed to avoid
+/// another's.
+pub fn check_key_constants(struct_value: &GenericStruct, warnings: &mut Vec<Warning>) {
+    key_constants(struct_value, warnings);
+}
+
+/// The name and path of the const for each field which isn't a table,
+/// ordered by path.
+///
+/// Arrays, including arrays of tables, are named by their own path, since
+/// their elements have no key. Paths which name the same const after
+/// casing, like `server.port` and `server_port`, are numbered in order.
+fn key_constants(
+    struct_value: &GenericStruct,
+    warnings: &mut Vec<Warning>,
+) -> Vec<(String, String)> {
+    let mut paths = Vec::new();
+    collect_paths(struct_value, "", &mut paths);
+    paths.sort();
+
+    let mut taken = BTreeSet::new();
+    let mut consts = Vec::new();
+    for path in paths {
+        let mut base_name = upper_snake_case(&path);
+        if base_name.chars().all(|c| c == '_') {
+            base_name.insert_str(0, "KEY");
+        }
+        let name = (1..)
+            .map(|n| match n {
+                1 => base_name.clone(),
+                n => format!("{}_{}", base_name, n),
+            })
+            .find(|name| !taken.contains(name))
+            .expect("some name is free");
+
+        if name != base_name {
+            let taken_by = consts
+                .iter()
+                .find(|(taken_name, _)| *taken_name == base_name)
+                .map(|(_, path): &(String, String)| path.as_str())
+                .unwrap_or_default();
+            warnings.push(Warning::new(
+                WarningKind::KeyConstantCollision,
+                path.clone(),
+                format!(
+                    "The key constant for `{}` would be `{}`, which was taken by `{}`, so it was named `{}`.",
+                    path, base_name, taken_by, name
+                ),
+            ));
+        }
+        taken.insert(name.clone());
+        consts.push((name, path));
+    }
+    consts
+}
+
+fn collect_paths(struct_value: &GenericStruct, parent_path: &str, paths: &mut Vec<String>) {
+    for (name, value) in &struct_value.fields {
+        let path = paths::join(parent_path, struct_value.original_key(name));
+        match table(value) {
+            Some(table) => collect_paths(table, &path, paths),
+            None => paths.push(path),
+        }
+    }
+}
+
+fn table(value: &GenericValue) -> Option<&GenericStruct> {
+    match *value {
+        GenericValue::Struct(ref struct_value) => Some(struct_value),
+        GenericValue::Option(Some(ref value)) => table(value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_struct(name: &str, fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: name.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    #[test]
+    fn leaf_fields_are_named_by_path() {
+        let server = make_struct(
+            "_Config__server",
+            vec![
+                ("port", GenericValue::I64(80)),
+                ("tags", GenericValue::Array(vec![])),
+            ],
+        );
+        let config = make_struct(
+            "Config",
+            vec![
+                ("server", GenericValue::Struct(server)),
+                ("logLevel", GenericValue::String("info".to_owned())),
+            ],
+        );
+
+        assert_eq!(
+            key_constants(&config, &mut Vec::new()),
+            [
+                ("LOG_LEVEL".to_owned(), "logLevel".to_owned()),
+                ("SERVER_PORT".to_owned(), "server.port".to_owned()),
+                ("SERVER_TAGS".to_owned(), "server.tags".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn collisions_are_numbered_and_reported() {
+        let server = make_struct("_Config__server", vec![("port", GenericValue::I64(80))]);
+        let config = make_struct(
+            "Config",
+            vec![
+                ("server_port", GenericValue::I64(81)),
+                ("server", GenericValue::Struct(server)),
+            ],
+        );
+
+        let mut warnings = Vec::new();
+        let consts = key_constants(&config, &mut warnings);
+
+        assert_eq!(
+            consts,
+            [
+                ("SERVER_PORT".to_owned(), "server.port".to_owned()),
+                ("SERVER_PORT_2".to_owned(), "server_port".to_owned()),
+            ]
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::KeyConstantCollision);
+        assert_eq!(warnings[0].path, "server_port");
+    }
+}
@@ -12,14 +12,28 @@
 //! include the relevant feature for whatever language your config
 //! file is written in. Choices are:
 //!
-//! 1.  `json-parsing`
-//! 2.  `ron-parsing`
-//! 3.  `toml-parsing`
-//! 4.  `yaml-parsing`
+//! 1.  `ini-parsing`
+//! 2.  `json-parsing`
+//! 3.  `json5-parsing` (JSON5, including JSON with comments)
+//! 4.  `ron-parsing`
+//! 5.  `toml-parsing`
+//! 6.  `yaml-parsing`
 //!
 //! Only `toml-parsing` is included by default, so be sure to specify
 //! the features you need in your `Cargo.toml` file.
 //!
+//! The optional `chrono` feature allows TOML dates and times to be
+//! generated as `chrono` types (see `DateTimeType`), the optional
+//! `semver` feature allows versions to be generated as `semver::Version`
+//! (see `VersionType`), and the optional `regex` feature allows strings
+//! to be checked against regexes (see `Rule`). The `clap-support` and
+//! `schemars-support` features add the attributes those crates need
+//! when deriving `clap::Parser` or `schemars::JsonSchema` (see
+//! `derived_traits`).
+//!
+//! Files ending in `.json5` or `.jsonc` are parsed as JSON5. The
+//! generated load functions for them use the `json5` crate.
+//!
 //! # Examples
 //!
 //! ```rust,no_run
@@ -54,7 +68,6 @@
 //! use std::borrow::Cow;
 //!
 //! #[derive(Debug, Clone)]
-//! #[allow(non_camel_case_types)]
 //! pub struct Config {
 //!     pub features: Cow<'static, [Cow<'static, str>]>,
 //!     pub name: Cow<'static, str>,
@@ -72,9 +85,15 @@
 //! the entire Config struct to be either heap allocated at runtime,
 //! or a compile time constant, as shown above.
 
+#[cfg(feature = "ini-parsing")]
+mod ini_parsing;
+
 #[cfg(feature = "json-parsing")]
 mod json_parsing;
 
+#[cfg(feature = "json5-parsing")]
+mod json5_parsing;
+
 #[cfg(feature = "ron-parsing")]
 mod ron_parsing;
 
@@ -84,31 +103,127 @@ mod toml_parsing;
 #[cfg(feature = "yaml-parsing")]
 mod yaml_parsing;
 
+#[cfg(any(feature = "json-parsing", feature = "yaml-parsing"))]
+mod duplicate_keys;
+
+mod binary;
+mod builders;
+mod cfg_sections;
+mod chars;
+mod comments;
+mod computed;
+mod config_dir;
+mod conversions;
+mod datetimes;
+mod deduplication;
+mod descriptions;
+mod diagnostics;
+mod diffs;
+mod display;
+mod empty_arrays;
+mod enums;
 mod error;
+mod field_consts;
+mod filtering;
+mod fixed_arrays;
+mod flattening;
 mod format;
+mod generated_tests;
 mod generation;
+mod getters;
+mod includes;
+mod index_maps;
+mod int_types;
+mod interop;
+mod json_schema;
+mod key_constants;
+mod limits;
 mod load_fns;
+mod lookups;
+mod maps;
+mod merging;
+mod module;
+mod naming;
+mod nested_naming;
+mod newtypes;
+#[cfg(any(feature = "json-parsing", feature = "yaml-parsing"))]
+mod nulls;
+mod option_types;
 mod options;
+mod options_builder;
+mod ordering;
+mod owned;
+mod parallel;
 mod parsing;
+mod paths;
+mod renaming;
+mod report;
+mod root_arrays;
+mod root_tables;
+mod rules;
+mod rustfmt;
+mod schema;
+mod serde_defaults;
+mod serde_impls;
+mod session;
+mod spans;
+mod statics;
+mod tagged_unions;
+mod transforms;
+mod tuples;
+mod unification;
 mod validation;
 mod value;
+mod versions;
+mod widening;
 
 #[cfg(not(any(
+    feature = "ini-parsing",
     feature = "json-parsing",
     feature = "ron-parsing",
     feature = "toml-parsing",
     feature = "yaml-parsing"
 )))]
-compile_error!("The config_struct crate requires at least one parsing feature to be enabled:\n {json-parsing, ron-parsing, toml-parsing, yaml-parsing}");
+compile_error!("The config_struct crate requires at least one parsing feature to be enabled:\n {ini-parsing, json-parsing, ron-parsing, toml-parsing, yaml-parsing}");
 
-use std::path::Path;
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
 
-use crate::value::GenericStruct;
+use crate::error::file_error;
 
 pub use crate::{
-    error::{Error, GenerationError, OptionsError},
+    config_dir::DirOptions,
+    descriptions::{
+        FieldDescription, GeneratedModule, TypeDescription, TypeKind, VariantDescription,
+    },
+    diagnostics::{GenerationOutput, Warning, WarningKind},
+    error::{
+        Error, ExceededLimit, GenerationError, NewtypeConflict, OptionsError, ParseError,
+        SourceLocation, Violation,
+    },
     format::Format,
-    options::{DynamicLoading, FloatSize, IntSize, SerdeSupport, StructOptions},
+    module::ModuleInput,
+    options::{
+        ArrayElementNaming, ArrayStyle, Computation, ComputedField, ConstFallback, DateTimeType,
+        DisplayStyle, DocumentSelector, DuplicateKeyBehavior, DynamicLoading, DynamicPath,
+        EmptyArrayBehavior, EmptyRootStyle, EnvOptions, FieldAccess, FieldOrder, FlattenStyle,
+        FloatSize, FormatOptions, HeaderStyle, IncludeOptions, IndexGapBehavior, IntSize,
+        IntoConversion, JsonOptions, KeySanitization, MapType, MissingFields, MixedArrayBehavior,
+        NestedNaming, NullBehavior, ResourceLimits, RootMode, Rule, SerdeAttributes, SerdeSupport,
+        StringType, StructOptions, TaggedUnionSpec, TomlOptions, UntypedOptionBehavior,
+        ValueTransform, VersionType, YamlOptions, YamlScalarStyle,
+    },
+    options_builder::StructOptionsBuilder,
+    parallel::GenerationJob,
+    report::CreateReport,
+    schema::{Mismatch, Schema, SchemaField, SchemaMismatch},
+    session::ConfigSession,
+    value::{
+        GenericEnum, GenericMap, GenericNewtype, GenericStruct, GenericTaggedUnion, GenericValue,
+        Span,
+    },
 };
 
 /// Generate Rust source code defining structs based on a config file.
@@ -128,9 +243,7 @@ pub fn generate_config<P: AsRef<Path>>(
     filepath: P,
     options: &StructOptions,
 ) -> Result<String, Error> {
-    let format = Format::from_filename(filepath.as_ref())?;
-
-    generate_config_with_format(format, filepath, options)
+    generate_config_with_diagnostics(filepath, options).map(|output| output.code)
 }
 
 /// Generate Rust source code defining structs based on a config file
@@ -155,212 +268,1810 @@ pub fn generate_config_with_format<P: AsRef<Path>>(
     filepath: P,
     options: &StructOptions,
 ) -> Result<String, Error> {
-    let path = filepath.as_ref();
-    let source = std::fs::read_to_string(path)?;
-    let output = generate_config_from_source_with_filepath(format, &source, options, Some(path))?;
-
-    Ok(output)
+    generate_config_from_file(
+        format,
+        filepath.as_ref(),
+        options,
+        &mut Vec::new(),
+        &mut Vec::new(),
+    )
+    .map(|module| module.code)
 }
 
-/// Generate Rust source code defining structs from a config string
-/// in some specified format.
+/// Generate Rust source code defining structs based on a config file,
+/// along with any warnings about it.
+///
+/// The format of the config file will be auto-detected from its
+/// extension. The warnings describe things which were handled silently
+/// by [`generate_config`](fn.generate_config.html), like empty arrays,
+/// and could be printed by a build script.
 ///
 /// # Examples
-/// ```rust
-/// use config_struct::{StructOptions, Format};
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// let output = config_struct::generate_config_with_diagnostics(
+///     "config.toml",
+///     &Default::default())?;
 ///
-/// let code = config_struct::generate_config_from_source(
-///     Format::Toml,
-///     "number = 100  # This is valid TOML.",
-///     &StructOptions::default()).unwrap();
+/// for warning in &output.warnings {
+///     println!("cargo:warning={}", warning);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn generate_config_with_diagnostics<P: AsRef<Path>>(
+    filepath: P,
+    options: &StructOptions,
+) -> Result<GenerationOutput, Error> {
+    let filepath = filepath.as_ref();
+    let format = Format::from_filename(filepath)?;
+
+    let mut warnings = Vec::new();
+    let code =
+        generate_config_from_file(format, filepath, options, &mut warnings, &mut Vec::new())?.code;
+
+    Ok(GenerationOutput { code, warnings })
+}
+
+/// Generate Rust source code defining structs based on a config file,
+/// along with descriptions of the types it declares.
 ///
-/// assert!(code.contains("pub struct Config"));
-/// assert!(code.contains("pub number: i64"));
-/// assert!(code.contains("number: 100"));
+/// The format of the config file will be auto-detected from its
+/// extension. The descriptions give the name, config path, fields and
+/// variants of each struct, enum and newtype, for build scripts which
+/// generate more code from them, like documentation or bindings.
+///
+/// # Examples
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// let module = config_struct::generate_config_described(
+///     "config.toml",
+///     &Default::default())?;
+///
+/// for field in &module.types[0].fields {
+///     println!("{}: {}", field.key, field.type_name);
+/// }
+/// # Ok(())
+/// # }
 /// ```
-pub fn generate_config_from_source<S: AsRef<str>>(
-    format: Format,
-    source: S,
+pub fn generate_config_described<P: AsRef<Path>>(
+    filepath: P,
     options: &StructOptions,
-) -> Result<String, GenerationError> {
-    generate_config_from_source_with_filepath(format, source.as_ref(), options, None)
+) -> Result<GeneratedModule, Error> {
+    let filepath = filepath.as_ref();
+    let format = Format::from_filename(filepath)?;
+
+    generate_config_from_file(format, filepath, options, &mut Vec::new(), &mut Vec::new())
 }
 
-fn generate_config_from_source_with_filepath(
+fn generate_config_from_file(
     format: Format,
-    source: &str,
+    path: &Path,
     options: &StructOptions,
-    filepath: Option<&Path>,
-) -> Result<String, GenerationError> {
-    options.validate()?;
+    warnings: &mut Vec<Warning>,
+    included: &mut Vec<PathBuf>,
+) -> Result<GeneratedModule, Error> {
+    let mut session = ConfigSession::open_with_format(format, path)?;
+    let output = session.generate_with(options, warnings, included)?;
 
-    let config = {
-        let mut root_struct: GenericStruct = match format {
-            #[cfg(feature = "json-parsing")]
-            Format::Json => json_parsing::parse_json(source, options)?,
+    Ok(output)
+}
 
-            #[cfg(feature = "ron-parsing")]
-            Format::Ron => ron_parsing::parse_ron(source, options)?,
+/// Read, parse and resolve the includes of a config file.
+fn read_config_file(
+    format: Format,
+    path: &Path,
+    options: &StructOptions,
+    included: &mut Vec<PathBuf>,
+) -> Result<GenericStruct, Error> {
+    let source = std::fs::read_to_string(path).map_err(file_error(path))?;
+    let mut config = parse_config(format, &source, options).map_err(|error| error.in_file(path))?;
+    includes::resolve_includes(&mut config, Some(path), options, included)?;
 
-            #[cfg(feature = "toml-parsing")]
-            Format::Toml => toml_parsing::parse_toml(source, options)?,
+    Ok(config)
+}
 
-            #[cfg(feature = "yaml-parsing")]
-            Format::Yaml => yaml_parsing::parse_yaml(source, options)?,
-        };
-        root_struct.struct_name = options.struct_name.clone();
-        root_struct
-    };
+/// Check that a config file has the same shape as a reference config,
+/// such as the one the config struct was generated from.
+///
+/// Both files are parsed and have their types inferred as if generating
+/// code with the given options. The keys and types of their fields are
+/// compared, ignoring their values, and any differences are returned.
+///
+/// # Examples
+/// ```rust,no_run
+/// use config_struct::{Format, SchemaMismatch, StructOptions};
+///
+/// let result = config_struct::validate_file_against(
+///     Format::Yaml,
+///     "deploy/production.yaml".as_ref(),
+///     "config.yaml".as_ref(),
+///     &StructOptions::default());
+///
+/// if let Err(SchemaMismatch::Mismatches(mismatches)) = result {
+///     for mismatch in mismatches {
+///         eprintln!("{}", mismatch);
+///     }
+/// }
+/// ```
+pub fn validate_file_against(
+    format: Format,
+    candidate: &Path,
+    reference: &Path,
+    options: &StructOptions,
+) -> Result<(), SchemaMismatch> {
+    options.validate().map_err(GenerationError::from)?;
 
-    validation::validate_struct(&config)?;
+    let read_config = |path: &Path| -> Result<GenericStruct, SchemaMismatch> {
+        let config = read_config_file(format, path, options, &mut Vec::new())?;
+        Ok(process_config(config, options, &mut Vec::new())?)
+    };
 
-    let mut code = String::new();
+    let candidate = read_config(candidate)?;
+    let reference = read_config(reference)?;
 
-    const HEADER: &str = "#![cfg_attr(rustfmt, rustfmt_skip)]
-#![allow(dead_code)]
+    let mismatches = schema::compare_structs(&candidate, &reference, options);
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(SchemaMismatch::Mismatches(mismatches))
+    }
+}
 
-use std::borrow::Cow;\n\n";
-    code.push_str(HEADER);
+/// Generate Rust source code defining structs based on multiple config
+/// files, merged together.
+///
+/// The files are merged in order, with values from later files taking
+/// precedence over earlier ones. Nested structs are merged recursively,
+/// while arrays and other values are replaced. The format of each file
+/// is auto-detected from its extension.
+///
+/// Dynamic loading functions cannot be generated when merging more
+/// than one file.
+///
+/// # Examples
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// let code = config_struct::generate_config_from_files(
+///     &["base.toml", "overrides.toml"],
+///     &Default::default())?;
+///
+/// assert!(code.contains("pub struct Config"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn generate_config_from_files<P: AsRef<Path>>(
+    filepaths: &[P],
+    options: &StructOptions,
+) -> Result<String, Error> {
+    generate_merged_config(filepaths, options, &mut Vec::new())
+}
 
-    let structs = generation::generate_structs(&config, options);
-    code.push_str(&structs);
+fn generate_merged_config<P: AsRef<Path>>(
+    filepaths: &[P],
+    options: &StructOptions,
+    included: &mut Vec<PathBuf>,
+) -> Result<String, Error> {
+    options.validate().map_err(GenerationError::from)?;
 
-    let requires_const =
-        options.generate_load_fns && options.dynamic_loading != DynamicLoading::Always;
+    let requires_dynamic_loading =
+        options.generate_load_fns && options.dynamic_loading != DynamicLoading::Never;
+    if filepaths.len() > 1 && requires_dynamic_loading {
+        return Err(GenerationError::MultipleFilePaths.into());
+    }
 
-    let struct_name = &options.struct_name;
-    let const_name = &options.real_const_name();
+    let mut merged: Option<(GenericStruct, Format, &Path)> = None;
+    for path in filepaths {
+        let path = path.as_ref();
+        let format = Format::from_filename(path)?;
+        let config = read_config_file(format, path, options, included)?;
 
-    if options.generate_const || requires_const {
-        code.push_str(&format!(
-            "pub const {}: {} = {};\n",
-            const_name,
-            struct_name,
-            generation::struct_value_string(&config, 0, options.max_array_size)
-        ));
+        merged = match merged {
+            None => Some((config, format, path)),
+            Some((mut base, format, path)) => {
+                merging::merge_structs(&mut base, config)?;
+                Some((base, format, path))
+            }
+        };
     }
 
-    if options.generate_load_fns {
-        let filepath = filepath.ok_or(GenerationError::MissingFilePath);
+    let (config, format, path) = merged.ok_or(GenerationError::NoInputFiles)?;
+    let output = generate_config_from_generic_struct(
+        config,
+        options,
+        Some((format, path)),
+        &mut Vec::new(),
+    )?;
 
-        let dynamic_impl =
-            filepath.map(|path| load_fns::dynamic_load_impl(format, struct_name, path));
+    Ok(output.code)
+}
 
-        let static_impl = load_fns::static_load_impl(struct_name, const_name);
+/// Generate Rust source code defining structs for several config
+/// files, in a single module.
+///
+/// Each input gives the struct and const names for its file, while the
+/// rest of the `options` are shared. The header and imports are only
+/// emitted once, and inputs which would define items with the same name
+/// are rejected before any file is read.
+///
+/// # Examples
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::{ModuleInput, StructOptions};
+///
+/// let code = config_struct::generate_config_module(
+///     &[
+///         ModuleInput::new("graphics.toml", "Graphics"),
+///         ModuleInput::new("audio.toml", "Audio"),
+///     ],
+///     &StructOptions::default())?;
+///
+/// assert!(code.contains("pub struct Graphics"));
+/// assert!(code.contains("pub struct Audio"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn generate_config_module(
+    inputs: &[ModuleInput],
+    options: &StructOptions,
+) -> Result<String, Error> {
+    generate_module(inputs, options, &mut Vec::new(), &mut Vec::new())
+}
 
-        let impl_string = match options.dynamic_loading {
-            DynamicLoading::Always => dynamic_impl?,
-            DynamicLoading::Never => static_impl,
-            DynamicLoading::DebugOnly => format!(
-                "
-#[cfg(debug_assertions)]
-{}
+/// Generate Rust source code declaring the structs for several config
+/// files in one module, along with any warnings about them.
+///
+/// See [`generate_config_module`](fn.generate_config_module.html) for
+/// how the files are combined.
+///
+/// # Examples
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::{ModuleInput, StructOptions};
+///
+/// let output = config_struct::generate_config_module_with_diagnostics(
+///     &[
+///         ModuleInput::new("graphics.toml", "Graphics"),
+///         ModuleInput::new("audio.toml", "Audio"),
+///     ],
+///     &StructOptions::default())?;
+///
+/// for warning in &output.warnings {
+///     println!("cargo:warning={}", warning);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn generate_config_module_with_diagnostics(
+    inputs: &[ModuleInput],
+    options: &StructOptions,
+) -> Result<GenerationOutput, Error> {
+    let mut warnings = Vec::new();
+    let code = generate_module(inputs, options, &mut warnings, &mut Vec::new())?;
 
-#[cfg(not(debug_assertions))]
-{}
-",
-                dynamic_impl?, static_impl,
-            ),
-        };
+    Ok(GenerationOutput { code, warnings })
+}
 
-        code.push_str(&impl_string);
+fn generate_module(
+    inputs: &[ModuleInput],
+    options: &StructOptions,
+    warnings: &mut Vec<Warning>,
+    included: &mut Vec<PathBuf>,
+) -> Result<String, Error> {
+    module::check_name_collisions(inputs, options)?;
+
+    let sources = inputs
+        .iter()
+        .map(|input| input.path.as_path())
+        .collect::<Vec<_>>();
+    let mut code = String::new();
+    let mut types = Vec::new();
+    for input in inputs {
+        let options = input.options(options);
+        options.validate().map_err(GenerationError::from)?;
+
+        let path = input.path.as_path();
+        let format = Format::from_filename(path)?;
+        let config = read_config_file(format, path, &options, included)?;
+        let config = process_config(config, &options, warnings)?;
+
+        let module = generate_items(&config, &config, &options, Some((format, path)), None)?;
+        code.push_str(&module.code);
+        types.extend(module.types);
     }
+    let code = generate_header(options, &sources, &types) + &code;
 
-    Ok(code)
+    Ok(rustfmt::format_output(code, options, warnings))
 }
 
-/// Generate a Rust module containing struct definitions based on a
-/// given config file.
+/// Generate Rust source code defining a struct shared by every config
+/// file in a directory, with one const per file.
 ///
-/// The format of the config is auto-detected from its filename
-/// extension.
+/// Files whose extension isn't a recognized config format are skipped.
+/// Every config must have the same shape, as checked by
+/// [`validate_file_against`](fn.validate_file_against.html), or the
+/// error names the file and keys which differ. Each const is named after
+/// its file stem in uppercase, so `dev.toml` becomes `DEV`, and a lookup
+/// function like `for_env("dev")` returns it by name.
 ///
 /// # Examples
-///
 /// ```rust,no_run
 /// # fn main() -> Result<(), config_struct::Error> {
-/// use config_struct::StructOptions;
+/// let code = config_struct::generate_config_from_dir("config", &Default::default())?;
 ///
-/// config_struct::create_config("config.toml", "src/config.rs", &StructOptions::default())?;
+/// assert!(code.contains("pub const DEV: Config"));
+/// assert!(code.contains("pub fn for_env(name: &str) -> Option<&'static Config>"));
 /// # Ok(())
 /// # }
 /// ```
-pub fn create_config<SrcPath: AsRef<Path>, DstPath: AsRef<Path>>(
-    filepath: SrcPath,
-    destination: DstPath,
-    options: &StructOptions,
-) -> Result<(), Error> {
-    let output = generate_config(filepath, options)?;
-    ensure_destination(destination.as_ref(), options)?;
-    write_destination(destination.as_ref(), output, options)?;
-
-    Ok(())
+pub fn generate_config_from_dir<P: AsRef<Path>>(
+    dir: P,
+    options: &DirOptions,
+) -> Result<String, Error> {
+    generate_dir_config(dir.as_ref(), options, &mut Vec::new(), &mut Vec::new())
 }
 
-/// Generate a Rust module containing struct definitions based on a
-/// given config file with an explicitly specified format.
+/// Generate Rust source code defining a struct shared by every config
+/// file in a directory, along with any warnings about them.
 ///
-/// # Examples
+/// See [`generate_config_from_dir`](fn.generate_config_from_dir.html)
+/// for how the files are combined.
 ///
+/// # Examples
 /// ```rust,no_run
 /// # fn main() -> Result<(), config_struct::Error> {
-/// use config_struct::{Format, StructOptions};
+/// let output =
+///     config_struct::generate_config_from_dir_with_diagnostics("config", &Default::default())?;
 ///
-/// config_struct::create_config_with_format(
-///     Format::Toml,
-///     "config.toml",
-///     "src/config.rs",
-///     &StructOptions::default())?;
+/// for warning in &output.warnings {
+///     println!("cargo:warning={}", warning);
+/// }
 /// # Ok(())
 /// # }
 /// ```
-pub fn create_config_with_format<SrcPath: AsRef<Path>, DstPath: AsRef<Path>>(
+pub fn generate_config_from_dir_with_diagnostics<P: AsRef<Path>>(
+    dir: P,
+    options: &DirOptions,
+) -> Result<GenerationOutput, Error> {
+    let mut warnings = Vec::new();
+    let code = generate_dir_config(dir.as_ref(), options, &mut warnings, &mut Vec::new())?;
+
+    Ok(GenerationOutput { code, warnings })
+}
+
+fn generate_dir_config(
+    dir: &Path,
+    options: &DirOptions,
+    warnings: &mut Vec<Warning>,
+    read: &mut Vec<PathBuf>,
+) -> Result<String, Error> {
+    options.validate()?;
+
+    let mut configs = Vec::new();
+    for (path, format) in config_dir::config_files(dir)? {
+        let file_options = config_dir::file_options(&path, options);
+        file_options.validate().map_err(GenerationError::from)?;
+
+        read.push(path.clone());
+        let config = read_config_file(format, &path, &file_options, read)?;
+        let config = process_config(config, &file_options, warnings)?;
+        configs.push(config_dir::DirConfig {
+            path,
+            options: file_options,
+            config,
+        });
+    }
+    config_dir::unify_nulls(&mut configs);
+    config_dir::check_configs(&configs)?;
+
+    let items = config_dir::generate_dir_items(&configs, options)?;
+    let code = generate_header(&options.struct_options, &[dir], &items.types) + &items.code;
+
+    Ok(rustfmt::format_output(
+        code,
+        &options.struct_options,
+        warnings,
+    ))
+}
+
+/// Generate Rust source code defining structs from a config string
+/// in some specified format.
+///
+/// # Examples
+/// ```rust
+/// use config_struct::{StructOptions, Format};
+///
+/// let code = config_struct::generate_config_from_source(
+///     Format::Toml,
+///     "number = 100  # This is valid TOML.",
+///     &StructOptions::default()).unwrap();
+///
+/// assert!(code.contains("pub struct Config"));
+/// assert!(code.contains("pub number: i64"));
+/// assert!(code.contains("number: 100"));
+/// ```
+pub fn generate_config_from_source<S: AsRef<str>>(
     format: Format,
-    filepath: SrcPath,
-    destination: DstPath,
+    source: S,
     options: &StructOptions,
-) -> Result<(), Error> {
-    let output = generate_config_with_format(format, filepath, options)?;
-    ensure_destination(destination.as_ref(), options)?;
-    write_destination(destination.as_ref(), output, options)?;
+) -> Result<String, GenerationError> {
+    generate_config_from_source_with_diagnostics(format, source, options).map(|output| output.code)
+}
 
-    Ok(())
+/// Generate Rust source code defining structs from a config string
+/// in some specified format, along with any warnings about it.
+///
+/// # Examples
+/// ```rust
+/// use config_struct::{Format, StructOptions, WarningKind};
+///
+/// let output = config_struct::generate_config_from_source_with_diagnostics(
+///     Format::Toml,
+///     "plugins = []",
+///     &StructOptions::default()).unwrap();
+///
+/// assert!(output.code.contains("pub plugins: Cow<'static, [()]>"));
+/// assert_eq!(output.warnings[0].kind, WarningKind::EmptyArray);
+/// assert_eq!(output.warnings[0].path, "plugins");
+/// ```
+pub fn generate_config_from_source_with_diagnostics<S: AsRef<str>>(
+    format: Format,
+    source: S,
+    options: &StructOptions,
+) -> Result<GenerationOutput, GenerationError> {
+    let mut warnings = Vec::new();
+    let code = generate_config_from_source_with_filepath(
+        format,
+        source.as_ref(),
+        options,
+        None,
+        &mut warnings,
+        &mut Vec::new(),
+    )?
+    .code;
+
+    Ok(GenerationOutput { code, warnings })
 }
 
-/// Generate a Rust module containing struct definitions from a
-/// config string in some specified format.
+/// Generate Rust source code defining structs from a config string
+/// in some specified format, along with descriptions of the types it
+/// declares.
 ///
 /// # Examples
+/// ```rust
+/// use config_struct::{Format, KeySanitization, StructOptions, TypeKind};
 ///
-/// ```rust,no_run
+/// let options = StructOptions {
+///     key_sanitization: KeySanitization::Sanitize,
+///     ..StructOptions::default()
+/// };
+///
+/// let module = config_struct::generate_config_from_source_described(
+///     Format::Toml,
+///     "[server]\nmax-connections = 100",
+///     &options).unwrap();
+///
+/// assert!(module.code.contains("pub struct _Config__server"));
+///
+/// let server = &module.types[1];
+/// assert_eq!(server.name, "_Config__server");
+/// assert_eq!(server.path, "server");
+/// assert_eq!(server.kind, TypeKind::Struct);
+/// assert_eq!(server.fields[0].name, "max_connections");
+/// assert_eq!(server.fields[0].key, "max-connections");
+/// assert_eq!(server.fields[0].type_name, "i64");
+/// ```
+pub fn generate_config_from_source_described<S: AsRef<str>>(
+    format: Format,
+    source: S,
+    options: &StructOptions,
+) -> Result<GeneratedModule, GenerationError> {
+    generate_config_from_source_with_filepath(
+        format,
+        source.as_ref(),
+        options,
+        None,
+        &mut Vec::new(),
+        &mut Vec::new(),
+    )
+}
+
+/// Generate Rust source code defining structs from a config read from
+/// `reader`, in some specified format.
+///
+/// This takes anything implementing `std::io::Read`, such as a network
+/// response or a decompressor, so a config which doesn't come from a file
+/// doesn't have to be written to one first. The parsers need the whole
+/// config at once, so it's read to the end before parsing.
+///
+/// # Examples
+/// ```rust
 /// # fn main() -> Result<(), config_struct::Error> {
 /// use config_struct::{Format, StructOptions};
 ///
-/// config_struct::create_config_from_source(
+/// let response: &[u8] = b"number = 100";
+/// let code = config_struct::generate_config_from_reader(
 ///     Format::Toml,
-///     "number = 100  # This is valid TOML.",
-///     "src/config.rs",
+///     response,
 ///     &StructOptions::default())?;
+///
+/// assert!(code.contains("pub number: i64"));
 /// # Ok(())
 /// # }
 /// ```
-pub fn create_config_from_source<S: AsRef<str>, P: AsRef<Path>>(
+pub fn generate_config_from_reader<R: Read>(
     format: Format,
-    source: S,
-    destination: P,
+    reader: R,
     options: &StructOptions,
-) -> Result<(), Error> {
+) -> Result<String, Error> {
+    let source = read_source(reader, options)?;
     let output = generate_config_from_source(format, source, options)?;
-    ensure_destination(destination.as_ref(), options)?;
-    write_destination(destination.as_ref(), output, options)?;
 
-    Ok(())
+    Ok(output)
 }
 
-fn ensure_destination(path: &Path, options: &StructOptions) -> Result<(), Error> {
+/// Read all of a config from `reader`, stopping just past
+/// `resource_limits.max_source_bytes` rather than reading a source which
+/// is too long to the end.
+fn read_source<R: Read>(reader: R, options: &StructOptions) -> Result<String, Error> {
+    let max_bytes = options.resource_limits.max_source_bytes;
+    let mut source = Vec::new();
+    reader
+        .take((max_bytes as u64).saturating_add(1))
+        .read_to_end(&mut source)?;
+    limits::check_source_len(source.len(), &options.resource_limits)?;
+
+    let source = String::from_utf8(source)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    Ok(source)
+}
+
+/// Generate Rust source code defining structs from a JSON value which
+/// has already been parsed, or built in code.
+///
+/// The value is converted just as a JSON config string would be once it
+/// was parsed, so a root which isn't an object or an array of objects is
+/// the same error. Since there is no config file, dynamic loading
+/// functions cannot be generated, and `field_order: SourceOrder` has no
+/// order to follow.
+///
+/// # Examples
+/// ```rust
+/// use config_struct::StructOptions;
+///
+/// let value = serde_json::json!({ "name": "app", "workers": 4 });
+/// let code = config_struct::generate_config_from_json_value(
+///     &value,
+///     &StructOptions::default()).unwrap();
+///
+/// assert!(code.contains("pub workers: i64"));
+/// ```
+#[cfg(feature = "json-parsing")]
+pub fn generate_config_from_json_value(
+    value: &serde_json::Value,
+    options: &StructOptions,
+) -> Result<String, GenerationError> {
+    generate_config_from_value(options, || {
+        json_parsing::json_value_to_generic_struct(value.clone(), "", options)
+    })
+}
+
+/// Generate Rust source code defining structs from a TOML value which
+/// has already been parsed, or built in code.
+///
+/// The value is converted just as a TOML config string would be once it
+/// was parsed. A root which isn't a table is a
+/// [`ParseFailed`](enum.GenerationError.html#variant.ParseFailed) error.
+/// Since there is no config file, dynamic loading functions cannot be
+/// generated, and `field_order: SourceOrder` has no order to follow.
+///
+/// # Examples
+/// ```rust
+/// use config_struct::StructOptions;
+///
+/// let mut table = toml::value::Table::new();
+/// table.insert("workers".to_owned(), toml::Value::Integer(4));
+/// let code = config_struct::generate_config_from_toml_value(
+///     &toml::Value::Table(table),
+///     &StructOptions::default()).unwrap();
+///
+/// assert!(code.contains("pub workers: i64"));
+/// ```
+#[cfg(feature = "toml-parsing")]
+pub fn generate_config_from_toml_value(
+    value: &toml::Value,
+    options: &StructOptions,
+) -> Result<String, GenerationError> {
+    generate_config_from_value(options, || {
+        toml_parsing::toml_value_to_generic_struct(value.clone(), "", options)
+    })
+}
+
+/// Generate Rust source code defining structs from a YAML value which
+/// has already been parsed, or built in code.
+///
+/// The value is converted just as a YAML config string would be once it
+/// was parsed, including expanding merge keys, so a root which isn't a
+/// mapping or a sequence of mappings is the same error. Its scalars
+/// already have types, so `yaml_scalars` doesn't apply. Since there is no
+/// config file, dynamic loading functions cannot be generated, and
+/// `field_order: SourceOrder` has no order to follow.
+///
+/// # Examples
+/// ```rust
+/// use config_struct::StructOptions;
+///
+/// let value: serde_yaml::Value = serde_yaml::from_str("workers: 4").unwrap();
+/// let code = config_struct::generate_config_from_yaml_value(
+///     &value,
+///     &StructOptions::default()).unwrap();
+///
+/// assert!(code.contains("pub workers: i64"));
+/// ```
+#[cfg(feature = "yaml-parsing")]
+pub fn generate_config_from_yaml_value(
+    value: &serde_yaml::Value,
+    options: &StructOptions,
+) -> Result<String, GenerationError> {
+    generate_config_from_value(options, || {
+        yaml_parsing::yaml_value_to_generic_struct(value.clone(), "", options)
+    })
+}
+
+/// Generate code from a config converted from a parsed value by
+/// `convert`, checking it as a parsed config string would be.
+#[cfg(any(
+    feature = "json-parsing",
+    feature = "toml-parsing",
+    feature = "yaml-parsing"
+))]
+fn generate_config_from_value<F>(
+    options: &StructOptions,
+    convert: F,
+) -> Result<String, GenerationError>
+where
+    F: FnOnce() -> Result<GenericStruct, GenerationError>,
+{
+    options.validate()?;
+
+    let mut config = convert()?;
+    limits::check_config(&config, options)?;
+    includes::resolve_includes(&mut config, None, options, &mut Vec::new())?;
+
+    generate_config_from_generic_struct(config, options, None, &mut Vec::new())
+        .map(|module| module.code)
+}
+
+/// Generate Rust source code defining structs from a config string
+/// in an unknown format.
+///
+/// The format is guessed with [`Format::detect`](enum.Format.html#method.detect).
+/// If the guess doesn't parse, every enabled format is tried, and the
+/// config is only accepted if exactly one of them parses it.
+///
+/// # Examples
+/// ```rust
+/// use config_struct::StructOptions;
+///
+/// let code = config_struct::generate_config_auto(
+///     "number = 100",
+///     &StructOptions::default()).unwrap();
+///
+/// assert!(code.contains("pub number: i64"));
+/// ```
+pub fn generate_config_auto<S: AsRef<str>>(
+    source: S,
+    options: &StructOptions,
+) -> Result<String, GenerationError> {
+    let source = source.as_ref();
+    options.validate()?;
+
+    let guess = Format::detect(source);
+    if let Some(format) = guess {
+        if let Ok(mut config) = parse_config(format, source, options) {
+            includes::resolve_includes(&mut config, None, options, &mut Vec::new())?;
+            return generate_config_from_generic_struct(config, options, None, &mut Vec::new())
+                .map(|module| module.code);
+        }
+    }
+
+    let mut parsed = Vec::new();
+    let mut failures = Vec::new();
+    for &format in Format::ENABLED {
+        match parse_config(format, source, options) {
+            Ok(config) => parsed.push((format, config)),
+            Err(error) => failures.push(format!("{}: {}", format, error)),
+        }
+    }
+
+    if parsed.len() == 1 {
+        let (_, mut config) = parsed.pop().unwrap();
+        includes::resolve_includes(&mut config, None, options, &mut Vec::new())?;
+        return generate_config_from_generic_struct(config, options, None, &mut Vec::new())
+            .map(|module| module.code);
+    }
+
+    let reason = if parsed.is_empty() {
+        format!("it did not parse as any format.\n{}", failures.join("\n"))
+    } else {
+        let formats = parsed
+            .iter()
+            .map(|(format, _)| format.to_string())
+            .collect::<Vec<String>>();
+        format!("it parsed as multiple formats: {}.", formats.join(", "))
+    };
+
+    Err(GenerationError::UndetectedFormat(reason))
+}
+
+fn generate_config_from_source_with_filepath(
+    format: Format,
+    source: &str,
+    options: &StructOptions,
+    filepath: Option<&Path>,
+    warnings: &mut Vec<Warning>,
+    included: &mut Vec<PathBuf>,
+) -> Result<GeneratedModule, GenerationError> {
+    options.validate()?;
+
+    let config =
+        parse_config_with_warnings(format, source, options, warnings).map_err(|error| {
+            match filepath {
+                Some(path) => error.in_file(path),
+                None => error,
+            }
+        })?;
+
+    generate_parsed_config(
+        format, source, config, options, filepath, warnings, included,
+    )
+}
+
+/// Generate code from a config parsed from `source`, resolving its
+/// includes, and locating errors in the source.
+fn generate_parsed_config(
+    format: Format,
+    source: &str,
+    mut config: GenericStruct,
+    options: &StructOptions,
+    filepath: Option<&Path>,
+    warnings: &mut Vec<Warning>,
+    included: &mut Vec<PathBuf>,
+) -> Result<GeneratedModule, GenerationError> {
+    // Included fields are in other files, so are located at their include.
+    let spans = spans::key_spans(&config);
+    let locate = |error| match filepath {
+        Some(path) => spans::locate_error(error, &spans, path, source),
+        None => error,
+    };
+
+    includes::resolve_includes(&mut config, filepath, options, included).map_err(locate)?;
+
+    generate_config_from_generic_struct(
+        config,
+        options,
+        filepath.map(|path| (format, path)),
+        warnings,
+    )
+    .map_err(locate)
+}
+
+/// Generate a JSON Schema (draft 2020-12) describing a config string in
+/// some specified format, for tools which check config files outside of
+/// Rust.
+///
+/// The schema is inferred exactly as the structs would be with the same
+/// options, so it describes what the generated code expects: nested
+/// structs become objects, arrays have item types, and fields in
+/// `enum_fields` only accept their listed values. Properties are named
+/// after the keys in the config, and are required unless they are
+/// optional or have serde defaults.
+///
+/// # Examples
+/// ```rust
+/// use config_struct::{Format, StructOptions};
+///
+/// let schema = config_struct::generate_schema(
+///     Format::Toml,
+///     "name = \"app\"\nports = [80, 443]",
+///     &StructOptions::default()).unwrap();
+///
+/// assert!(schema.contains("\"$schema\": \"https://json-schema.org/draft/2020-12/schema\""));
+/// assert!(schema.contains("\"name\": {\n      \"type\": \"string\"\n    }"));
+/// ```
+pub fn generate_schema<S: AsRef<str>>(
+    format: Format,
+    source: S,
+    options: &StructOptions,
+) -> Result<String, GenerationError> {
+    generate_schema_with_filepath(format, source.as_ref(), options, None, &mut Vec::new())
+}
+
+fn generate_schema_with_filepath(
+    format: Format,
+    source: &str,
+    options: &StructOptions,
+    filepath: Option<&Path>,
+    included: &mut Vec<PathBuf>,
+) -> Result<String, GenerationError> {
+    options.validate()?;
+
+    let mut config = parse_config(format, source, options).map_err(|error| match filepath {
+        Some(path) => error.in_file(path),
+        None => error,
+    })?;
+    includes::resolve_includes(&mut config, filepath, options, included)?;
+    let config = process_config(config, options, &mut Vec::new())?;
+
+    Ok(json_schema::json_schema(&config, options))
+}
+
+/// Parse a config string into a [`GenericStruct`](struct.GenericStruct.html),
+/// without generating any code.
+///
+/// The result can be modified before passing it to
+/// [`generate_config_from_struct`](fn.generate_config_from_struct.html).
+///
+/// If the root of the config is an array, the result has a single field
+/// with an empty key, holding the elements.
+///
+/// # Examples
+/// ```rust
+/// use config_struct::{Format, GenericValue, StructOptions};
+///
+/// let options = StructOptions::default();
+/// let mut config = config_struct::parse_config(
+///     Format::Toml,
+///     "number = 100",
+///     &options).unwrap();
+///
+/// config.fields.insert("build_time".to_owned(), GenericValue::String("noon".to_owned()));
+///
+/// let code = config_struct::generate_config_from_struct(&config, &options).unwrap();
+/// assert!(code.contains("pub build_time: Cow<'static, str>"));
+/// ```
+pub fn parse_config(
+    format: Format,
+    source: &str,
+    options: &StructOptions,
+) -> Result<GenericStruct, GenerationError> {
+    parse_config_with_warnings(format, source, options, &mut Vec::new())
+}
+
+/// Like `parse_config`, but keeps any warnings from parsing.
+#[cfg_attr(not(feature = "ron-parsing"), allow(unused_variables))]
+fn parse_config_with_warnings(
+    format: Format,
+    source: &str,
+    options: &StructOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<GenericStruct, GenerationError> {
+    limits::check_source_len(source.len(), &options.resource_limits)?;
+
+    let mut root_struct = match format {
+        #[cfg(feature = "ini-parsing")]
+        Format::Ini => ini_parsing::parse_ini(source, options)?,
+
+        #[cfg(feature = "json-parsing")]
+        Format::Json => json_parsing::parse_json(source, options)?,
+
+        #[cfg(feature = "json5-parsing")]
+        Format::Json5 => json5_parsing::parse_json5(source, options)?,
+
+        #[cfg(feature = "ron-parsing")]
+        Format::Ron => ron_parsing::parse_ron(source, options, warnings)?,
+
+        #[cfg(feature = "toml-parsing")]
+        Format::Toml => toml_parsing::parse_toml(source, options)?,
+
+        #[cfg(feature = "yaml-parsing")]
+        Format::Yaml => yaml_parsing::parse_yaml(source, options)?,
+    };
+    limits::check_config(&root_struct, options)?;
+
+    let spans = match format {
+        #[cfg(feature = "toml-parsing")]
+        Format::Toml => spans::toml_key_spans(source),
+
+        #[allow(unreachable_patterns)]
+        _ => Default::default(),
+    };
+    spans::apply_spans(&mut root_struct, &spans);
+
+    if options.preserve_comments {
+        let docs = match format {
+            #[cfg(feature = "toml-parsing")]
+            Format::Toml => comments::toml_comments(source),
+
+            #[cfg(feature = "yaml-parsing")]
+            Format::Yaml => comments::yaml_comments(source),
+
+            #[allow(unreachable_patterns)]
+            _ => Default::default(),
+        };
+        comments::apply_comments(&mut root_struct, &docs);
+    }
+
+    if options.field_order == FieldOrder::SourceOrder {
+        let order = ordering::key_order(format, source);
+        ordering::apply_key_order(&mut root_struct, &order);
+    }
+
+    Ok(root_struct)
+}
+
+/// Generate Rust source code defining structs from a
+/// [`GenericStruct`](struct.GenericStruct.html), such as one returned by
+/// [`parse_config`](fn.parse_config.html).
+///
+/// The root struct is named according to the `options`, whatever its
+/// `struct_name`. Since there is no config file, dynamic loading functions
+/// cannot be generated.
+pub fn generate_config_from_struct(
+    config: &GenericStruct,
+    options: &StructOptions,
+) -> Result<String, GenerationError> {
+    generate_config_from_struct_described(config, options).map(|module| module.code)
+}
+
+/// Generate Rust source code defining structs from a
+/// [`GenericStruct`](struct.GenericStruct.html), along with descriptions
+/// of the types it declares.
+///
+/// This behaves like
+/// [`generate_config_from_struct`](fn.generate_config_from_struct.html),
+/// for build scripts which generate more code from the config's types.
+pub fn generate_config_from_struct_described(
+    config: &GenericStruct,
+    options: &StructOptions,
+) -> Result<GeneratedModule, GenerationError> {
+    options.validate()?;
+
+    generate_config_from_generic_struct(config.clone(), options, None, &mut Vec::new())
+}
+
+fn generate_config_from_generic_struct(
+    config: GenericStruct,
+    options: &StructOptions,
+    source_file: Option<(Format, &Path)>,
+    warnings: &mut Vec<Warning>,
+) -> Result<GeneratedModule, GenerationError> {
+    let config = process_config(config, options, warnings)?;
+
+    schema::generate_processed(&config, &config, options, source_file, warnings)
+}
+
+/// Run every pass over a parsed config, then validate the result.
+fn process_config(
+    mut config: GenericStruct,
+    options: &StructOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<GenericStruct, GenerationError> {
+    config.struct_name = options.struct_name.clone();
+    parsing::check_fields_depth(&config.fields, options)?;
+    root_arrays::prepare_root_array(&mut config, options)?;
+    root_tables::prepare_root_tables(&mut config, options)?;
+    filtering::apply_path_filters(&mut config, options, warnings);
+    maps::apply_map_paths(&mut config, options)?;
+    index_maps::apply_index_map_paths(&mut config, options)?;
+    int_types::apply_int_types(&mut config, options)?;
+    transforms::apply_value_transforms(&mut config, options)?;
+    computed::apply_computed_fields(&mut config, options)?;
+    rules::check_validations(&config, options)?;
+    binary::apply_binary_paths(&mut config, options)?;
+    chars::apply_char_paths(&mut config, options)?;
+    versions::apply_semver_paths(&mut config, options)?;
+    widening::widen_numeric_arrays(&mut config, options);
+    tagged_unions::apply_tagged_unions(&mut config, options, warnings)?;
+    unification::unify_array_structs(&mut config, options)?;
+    enums::apply_enum_fields(&mut config, options)?;
+    newtypes::apply_newtype_paths(&mut config, options)?;
+    renaming::apply_field_renames(&mut config, options, warnings)?;
+    flattening::apply_flatten_paths(&mut config, options)?;
+    tuples::convert_mixed_arrays(&mut config, options);
+    cfg_sections::apply_cfg_sections(&mut config, options);
+    nested_naming::apply_nested_naming(&mut config, options)?;
+    empty_arrays::apply_empty_array_types(&mut config, options, warnings)?;
+    option_types::apply_option_types(&mut config, options, warnings)?;
+    deduplication::deduplicate_structs(&mut config, options);
+
+    validation::validate_struct(&config, options)?;
+    fixed_arrays::check_fixed_arrays(&config, options)?;
+    interop::check_cli_fields(&config, options)?;
+    if options.generate_key_constants {
+        key_constants::check_key_constants(&config, warnings);
+    }
+
+    Ok(config)
+}
+
+/// Generate the attributes and imports at the top of a generated file,
+/// which was generated from the config files at `sources`, and which
+/// declares `types`.
+fn generate_header(
+    options: &StructOptions,
+    sources: &[&Path],
+    types: &[TypeDescription],
+) -> String {
+    let mut code = generate_attributes(options, sources);
+    code.push_str(&generate_imports(options, types));
+    code
+}
+
+/// Generate the comments and attributes at the top of a generated file.
+fn generate_attributes(options: &StructOptions, sources: &[&Path]) -> String {
+    let mut code = String::new();
+
+    if options.generated_by_comment {
+        code.push_str(&format!(
+            "// Generated by config_struct {}",
+            env!("CARGO_PKG_VERSION")
+        ));
+        let names = sources
+            .iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| format!("`{}`", name.to_string_lossy()))
+            .collect::<Vec<_>>();
+        if !names.is_empty() {
+            code.push_str(&format!(" from {}", names.join(", ")));
+        }
+        code.push_str(".\n\n");
+    }
+
+    match options.header {
+        HeaderStyle::InnerAttributes => {
+            if !options.rustfmt_output {
+                code.push_str("#![cfg_attr(rustfmt, rustfmt_skip)]\n");
+            }
+            code.push_str("#![allow(dead_code)]\n\n");
+        }
+        HeaderStyle::OuterComment if options.generated_by_comment => (),
+        HeaderStyle::OuterComment => code.push_str("// Generated by config_struct.\n\n"),
+        HeaderStyle::Custom(ref header) => {
+            code.push_str(header.trim_end());
+            code.push_str("\n\n");
+        }
+    }
+
+    code
+}
+
+/// Generate the imports used by the generated items declaring `types`.
+fn generate_imports(options: &StructOptions, types: &[TypeDescription]) -> String {
+    let mut code = String::new();
+
+    let std_crate = if options.no_std { "alloc" } else { "std" };
+    if uses_cow(types, options) {
+        code.push_str(&format!("use {}::borrow::Cow;\n\n", std_crate));
+    }
+    if (options.string_type == StringType::String || options.generate_owned_variant)
+        && options.no_std
+    {
+        code.push_str("use alloc::{string::String, vec, vec::Vec};\n\n");
+    }
+
+    code
+}
+
+/// Whether the items declaring `types` use `Cow`. Fields only do when
+/// their types do, so empty configs and unit structs don't, but load
+/// functions always return a `Cow`, and path lookups read `Cow` strings.
+fn uses_cow(types: &[TypeDescription], options: &StructOptions) -> bool {
+    let mut fields = types.iter().flat_map(|description| {
+        let variant_fields = description
+            .variants
+            .iter()
+            .flat_map(|variant| &variant.fields);
+        description.fields.iter().chain(variant_fields)
+    });
+
+    options.generate_load_fns
+        || (options.generate_get_by_path && options.string_type == StringType::Cow)
+        || fields.any(|field| field.type_name.contains("Cow<"))
+}
+
+/// Generate the structs, values and impls for a config which has been
+/// through every pass and validated, along with descriptions of the
+/// declared types. The structs are declared from the `schema`, which
+/// `config` fits.
+///
+/// If `root_values` is given, the root value is always declared, but
+/// into it rather than the returned code.
+fn generate_items(
+    schema: &GenericStruct,
+    config: &GenericStruct,
+    options: &StructOptions,
+    source_file: Option<(Format, &Path)>,
+    root_values: Option<&mut String>,
+) -> Result<GeneratedModule, GenerationError> {
+    let mut code = String::new();
+
+    // The elements of a root array are declared instead of the wrapper.
+    let root_elements = root_arrays::root_elements(config);
+    let declared_root = match root_arrays::root_elements(schema) {
+        Some(elements) => root_arrays::element_template(elements, options),
+        None => schema.clone(),
+    };
+
+    let structs = generation::generate_structs(&declared_root, options);
+    code.push_str(&structs);
+    let types = descriptions::describe_types(&declared_root, options);
+
+    let requires_const = options.load_fns_use_const();
+
+    let struct_name = &options.struct_name;
+
+    let generates_const = options.generate_const || requires_const || root_values.is_some();
+
+    let root_value = if generates_const {
+        let root_value = statics::RootValue::choose(config, options)?;
+        let declaration = root_value.declaration(config, options);
+        match root_values {
+            Some(root_values) => root_values.push_str(&declaration),
+            None => code.push_str(&declaration),
+        }
+        Some(root_value)
+    } else {
+        None
+    };
+
+    let root_expression = root_value.map(|root_value| root_value.expression(options));
+    let derives_clone = options.derived_traits.iter().any(|name| name == "Clone");
+    let root_const = root_expression.as_deref().filter(|_| derives_clone);
+
+    if options.impl_default {
+        code.push_str(&generation::generate_default_impls(
+            config, options, root_const,
+        ));
+    }
+
+    if options.generate_builder {
+        code.push_str(&builders::generate_builders(config, options, root_const));
+    }
+
+    if options.field_access == FieldAccess::Getters {
+        code.push_str(&getters::generate_getters(&declared_root, options)?);
+    }
+
+    if options.generate_diff {
+        code.push_str(&diffs::generate_diffs(&declared_root, options));
+    }
+
+    if options.generate_owned_variant {
+        code.push_str(&owned::generate_owned_variant(&declared_root, options));
+    }
+
+    if options.generate_get_by_path {
+        code.push_str(&lookups::generate_lookups(&declared_root, options));
+    }
+
+    if options.generate_key_constants {
+        code.push_str(&key_constants::generate_key_constants(&declared_root));
+    }
+
+    if options.generate_field_consts {
+        code.push_str(&field_consts::generate_field_consts(
+            &declared_root,
+            config,
+            options,
+        ));
+    }
+
+    if let Some(ref target) = options.impl_into {
+        code.push_str(&conversions::generate_into_impl(
+            &declared_root,
+            target,
+            options,
+        ));
+    }
+
+    code.push_str(&display::generate_display(
+        &declared_root.struct_name,
+        source_file.map(|(format, _)| format),
+        options,
+    )?);
+
+    if options.serde_missing_fields == MissingFields::UseConst {
+        code.push_str(&serde_defaults::generate_default_fns(
+            &declared_root,
+            options,
+        ));
+    }
+
+    if options.generate_load_fns {
+        let source_file = source_file.ok_or(GenerationError::MissingFilePath);
+
+        let root_reference = root_value.map(|root_value| root_value.reference(options));
+        let dynamic_impl = source_file.and_then(|(format, path)| {
+            let root_array = root_elements.is_some();
+            match options.dynamic_loading {
+                DynamicLoading::Embedded => {
+                    load_fns::embedded_load_impl(format, struct_name, path, root_array)
+                }
+                _ => load_fns::dynamic_load_impl(
+                    format,
+                    struct_name,
+                    path,
+                    root_array,
+                    &options.dynamic_path,
+                    root_reference.as_deref(),
+                ),
+            }
+        });
+
+        // The root value is always generated when the static impl is needed.
+        let static_impl = root_value
+            .map(|root_value| {
+                load_fns::static_load_impl(
+                    struct_name,
+                    &root_value.reference(options),
+                    root_elements.is_some(),
+                    options,
+                )
+            })
+            .unwrap_or_default();
+
+        let impl_string = match options.dynamic_loading {
+            DynamicLoading::Always | DynamicLoading::Embedded => dynamic_impl?,
+            DynamicLoading::Never => static_impl,
+            DynamicLoading::DebugOnly => format!(
+                "
+#[cfg(debug_assertions)]
+{}
+
+#[cfg(not(debug_assertions))]
+{}
+",
+                dynamic_impl?, static_impl,
+            ),
+        };
+
+        if !options.no_std {
+            code.push_str(&load_fns::load_error_declaration(struct_name));
+        }
+        code.push_str(&impl_string);
+    }
+
+    if let Some(ref env_options) = options.env_overrides {
+        code.push_str(&load_fns::env_overrides_impl(
+            config,
+            options,
+            env_options,
+            root_expression.as_deref(),
+        ));
+    }
+
+    if options.generate_tests {
+        let (format, path) = source_file.ok_or(GenerationError::MissingTestFile)?;
+        let root_reference = root_value
+            .map(|root_value| root_value.reference(options))
+            .expect("the root value is generated for tests");
+        code.push_str(&generated_tests::generate_tests(
+            format,
+            path,
+            &root_reference,
+            root_elements.is_some(),
+            options,
+        )?);
+    }
+
+    Ok(GeneratedModule { code, types })
+}
+
+/// Generate a Rust module containing struct definitions based on a
+/// given config file.
+///
+/// The format of the config is auto-detected from its filename
+/// extension.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::StructOptions;
+///
+/// config_struct::create_config("config.toml", "src/config.rs", &StructOptions::default())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn create_config<SrcPath: AsRef<Path>, DstPath: AsRef<Path>>(
+    filepath: SrcPath,
+    destination: DstPath,
+    options: &StructOptions,
+) -> Result<(), Error> {
+    create_config_ext(filepath, destination, options)?;
+
+    Ok(())
+}
+
+/// Generate a file containing struct definitions based on a given config
+/// file, in the `OUT_DIR` of a build script, and return its path.
+///
+/// The file is named after `module_name`, and meant to be included with
+/// `include!`, which keeps generated code out of the source tree. Since
+/// `include!` doesn't allow inner attributes, the default
+/// `HeaderStyle::InnerAttributes` is replaced with
+/// `HeaderStyle::OuterComment`.
+///
+/// This must be called from a build script, where cargo sets `OUT_DIR`.
+///
+/// # Examples
+///
+/// In `build.rs`:
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::StructOptions;
+///
+/// config_struct::create_config_in_out_dir("config.toml", "config", &StructOptions::default())?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Then in the crate:
+///
+/// ```rust,ignore
+/// #[allow(dead_code)]
+/// mod config {
+///     include!(concat!(env!("OUT_DIR"), "/config.rs"));
+/// }
+/// ```
+pub fn create_config_in_out_dir<SrcPath: AsRef<Path>>(
+    filepath: SrcPath,
+    module_name: &str,
+    options: &StructOptions,
+) -> Result<PathBuf, Error> {
+    let out_dir = std::env::var_os("OUT_DIR").ok_or(GenerationError::MissingOutDir)?;
+    let destination = Path::new(&out_dir).join(format!("{}.rs", module_name));
+
+    let options = match options.header {
+        HeaderStyle::InnerAttributes => StructOptions {
+            header: HeaderStyle::OuterComment,
+            ..options.clone()
+        },
+        _ => options.clone(),
+    };
+    create_config(filepath, &destination, &options)?;
+
+    Ok(destination)
+}
+
+/// Generate the struct definitions for a config file into one file, and
+/// its const into another.
+///
+/// The types file holds everything but the const, so it only changes
+/// when the shape of the config does, and can be committed. The values
+/// file begins with `options.values_import` to bring the types into
+/// scope, so by default it should be a child module of the types. Both
+/// files get the same header, and `write_only_if_changed` applies to
+/// each of them separately.
+///
+/// Anything in the types file which uses the const, like the static load
+/// functions, refers to it by name, so it must be in scope there too.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::StructOptions;
+///
+/// let values = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("values.rs");
+///
+/// config_struct::create_config_split(
+///     "config.toml",
+///     "src/config.rs",
+///     values,
+///     &StructOptions::default())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn create_config_split<
+    SrcPath: AsRef<Path>,
+    TypesPath: AsRef<Path>,
+    ValuesPath: AsRef<Path>,
+>(
+    filepath: SrcPath,
+    types_destination: TypesPath,
+    values_destination: ValuesPath,
+    options: &StructOptions,
+) -> Result<(), Error> {
+    let filepath = filepath.as_ref();
+    emit_rerun_directive(filepath, options);
+
+    let format = Format::from_filename(filepath)?;
+    let mut included = Vec::new();
+    let (types, values) = generate_split_config(format, filepath, options, &mut included)?;
+    emit_rerun_directives(&included, options);
+
+    write_destination(types_destination.as_ref(), types, options)?;
+    write_destination(values_destination.as_ref(), values, options)?;
+
+    Ok(())
+}
+
+/// Generate the code for the types and for the values of a config file,
+/// for [`create_config_split`](fn.create_config_split.html).
+fn generate_split_config(
+    format: Format,
+    path: &Path,
+    options: &StructOptions,
+    included: &mut Vec<PathBuf>,
+) -> Result<(String, String), Error> {
+    options.validate().map_err(GenerationError::from)?;
+
+    let config = read_config_file(format, path, options, included)?;
+    let mut warnings = Vec::new();
+    let config = process_config(config, options, &mut warnings)?;
+
+    let mut root_values = String::new();
+    let items = generate_items(
+        &config,
+        &config,
+        options,
+        Some((format, path)),
+        Some(&mut root_values),
+    )?;
+    let types = generate_header(options, &[path], &items.types) + &items.code;
+
+    let mut values = generate_attributes(options, &[path]);
+    if !options.values_import.is_empty() {
+        values.push_str(&generate_imports(options, &items.types));
+        values.push_str(options.values_import.trim_end());
+        values.push_str("\n\n");
+    }
+    values.push_str(&root_values);
+
+    Ok((
+        rustfmt::format_output(types, options, &mut warnings),
+        rustfmt::format_output(values, options, &mut warnings),
+    ))
+}
+
+/// Generate a Rust module containing struct definitions based on a
+/// given config file, and report what was done.
+///
+/// This behaves like [`create_config`](fn.create_config.html), but
+/// returns a [`CreateReport`](struct.CreateReport.html) with the generated
+/// code and whether the destination file was written. With `dry_run` set
+/// in the options, nothing is written, so the report can be used to check
+/// whether an existing file is stale.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::StructOptions;
+///
+/// let options = StructOptions {
+///     dry_run: true,
+///     ..StructOptions::default()
+/// };
+///
+/// let report = config_struct::create_config_ext("config.toml", "src/config.rs", &options)?;
+/// if report.changed {
+///     panic!("src/config.rs is out of date.");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn create_config_ext<SrcPath: AsRef<Path>, DstPath: AsRef<Path>>(
+    filepath: SrcPath,
+    destination: DstPath,
+    options: &StructOptions,
+) -> Result<CreateReport, Error> {
+    ConfigSession::open(filepath)?.create(destination, options)
+}
+
+/// Generate a Rust module containing struct definitions based on a
+/// given config file with an explicitly specified format.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::{Format, StructOptions};
+///
+/// config_struct::create_config_with_format(
+///     Format::Toml,
+///     "config.toml",
+///     "src/config.rs",
+///     &StructOptions::default())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn create_config_with_format<SrcPath: AsRef<Path>, DstPath: AsRef<Path>>(
+    format: Format,
+    filepath: SrcPath,
+    destination: DstPath,
+    options: &StructOptions,
+) -> Result<(), Error> {
+    ConfigSession::open_with_format(format, filepath)?.create(destination, options)?;
+
+    Ok(())
+}
+
+/// Generate a Rust module containing struct definitions based on
+/// multiple config files, merged together.
+///
+/// See [`generate_config_from_files`](fn.generate_config_from_files.html)
+/// for details of how the files are merged.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::StructOptions;
+///
+/// config_struct::create_config_from_files(
+///     &["base.toml", "overrides.toml"],
+///     "src/config.rs",
+///     &StructOptions::default())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn create_config_from_files<SrcPath: AsRef<Path>, DstPath: AsRef<Path>>(
+    filepaths: &[SrcPath],
+    destination: DstPath,
+    options: &StructOptions,
+) -> Result<(), Error> {
+    for filepath in filepaths {
+        emit_rerun_directive(filepath.as_ref(), options);
+    }
+    let mut included = Vec::new();
+    let output = generate_merged_config(filepaths, options, &mut included)?;
+    emit_rerun_directives(&included, options);
+
+    write_destination(destination.as_ref(), output, options)?;
+
+    Ok(())
+}
+
+/// Generate a single Rust module containing struct definitions for
+/// several config files.
+///
+/// See [`generate_config_module`](fn.generate_config_module.html) for
+/// details of how the inputs are combined.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::{ModuleInput, StructOptions};
+///
+/// config_struct::create_config_module(
+///     &[
+///         ModuleInput::new("graphics.toml", "Graphics"),
+///         ModuleInput::new("audio.toml", "Audio"),
+///         ModuleInput::new("input.toml", "Input"),
+///     ],
+///     "src/config.rs",
+///     &StructOptions::default())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn create_config_module<DstPath: AsRef<Path>>(
+    inputs: &[ModuleInput],
+    destination: DstPath,
+    options: &StructOptions,
+) -> Result<(), Error> {
+    for input in inputs {
+        emit_rerun_directive(&input.path, options);
+    }
+    let mut included = Vec::new();
+    let output = generate_module(inputs, options, &mut Vec::new(), &mut included)?;
+    emit_rerun_directives(&included, options);
+
+    write_destination(destination.as_ref(), output, options)?;
+
+    Ok(())
+}
+
+/// Generate a Rust module for each of several config files at once,
+/// on a pool of threads, and report what was done for each.
+///
+/// Each job is run like [`create_config_ext`](fn.create_config_ext.html),
+/// with its own options, and the results are in the same order as the
+/// jobs, so a failed job doesn't stop the others. With
+/// `cache_source_hash` set in a job's options, the job is skipped
+/// entirely when its config and options haven't changed since its
+/// destination was written, which makes rebuilds of many configs cheap.
+/// Jobs must write to different destinations.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::{GenerationJob, StructOptions};
+///
+/// let options = StructOptions {
+///     cache_source_hash: true,
+///     ..StructOptions::default()
+/// };
+///
+/// let jobs = ["graphics", "audio", "input"]
+///     .iter()
+///     .map(|name| {
+///         GenerationJob::new(
+///             format!("config/{}.toml", name),
+///             format!("src/config/{}.rs", name),
+///             options.clone(),
+///         )
+///     })
+///     .collect::<Vec<_>>();
+///
+/// for report in config_struct::create_configs_parallel(&jobs) {
+///     for warning in report?.warnings {
+///         println!("cargo:warning={}", warning);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn create_configs_parallel(jobs: &[GenerationJob]) -> Vec<Result<CreateReport, Error>> {
+    parallel::run_jobs(jobs)
+}
+
+/// Generate a Rust module containing a struct shared by every config
+/// file in a directory, with one const per file.
+///
+/// See [`generate_config_from_dir`](fn.generate_config_from_dir.html)
+/// for details of how the files are combined.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::DirOptions;
+///
+/// config_struct::create_config_from_dir("config", "src/config.rs", &DirOptions::default())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn create_config_from_dir<SrcPath: AsRef<Path>, DstPath: AsRef<Path>>(
+    dir: SrcPath,
+    destination: DstPath,
+    options: &DirOptions,
+) -> Result<(), Error> {
+    let dir = dir.as_ref();
+    // Rerunning when the directory changes picks up added and removed files.
+    emit_rerun_directive(dir, &options.struct_options);
+
+    let mut read = Vec::new();
+    let output = generate_dir_config(dir, options, &mut Vec::new(), &mut read)?;
+    emit_rerun_directives(&read, &options.struct_options);
+
+    write_destination(destination.as_ref(), output, &options.struct_options)?;
+
+    Ok(())
+}
+
+/// Generate a Rust module containing struct definitions from a
+/// config string in some specified format.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::{Format, StructOptions};
+///
+/// config_struct::create_config_from_source(
+///     Format::Toml,
+///     "number = 100  # This is valid TOML.",
+///     "src/config.rs",
+///     &StructOptions::default())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn create_config_from_source<S: AsRef<str>, P: AsRef<Path>>(
+    format: Format,
+    source: S,
+    destination: P,
+    options: &StructOptions,
+) -> Result<(), Error> {
+    let mut included = Vec::new();
+    let output = generate_config_from_source_with_filepath(
+        format,
+        source.as_ref(),
+        options,
+        None,
+        &mut Vec::new(),
+        &mut included,
+    )?;
+    emit_rerun_directives(&included, options);
+
+    write_destination(destination.as_ref(), output.code, options)?;
+
+    Ok(())
+}
+
+/// Generate a Rust module containing struct definitions from a config
+/// read from `reader`, in some specified format.
+///
+/// See [`generate_config_from_reader`](fn.generate_config_from_reader.html)
+/// for details.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::{Format, StructOptions};
+///
+/// let response = std::io::stdin();
+/// config_struct::create_config_from_reader(
+///     Format::Json,
+///     response.lock(),
+///     "src/config.rs",
+///     &StructOptions::default())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn create_config_from_reader<R: Read, P: AsRef<Path>>(
+    format: Format,
+    reader: R,
+    destination: P,
+    options: &StructOptions,
+) -> Result<(), Error> {
+    let source = read_source(reader, options)?;
+    create_config_from_source(format, source, destination, options)
+}
+
+/// Generate a JSON Schema file describing a config file, as
+/// [`generate_schema`](fn.generate_schema.html) does.
+///
+/// Generating the schema and the config struct from the same file and
+/// options, in the same build script, keeps them from drifting apart.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::StructOptions;
+///
+/// let options = StructOptions::default();
+/// config_struct::create_config("config.toml", "src/config.rs", &options)?;
+/// config_struct::create_schema("config.toml", "config.schema.json", &options)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn create_schema<SrcPath: AsRef<Path>, DstPath: AsRef<Path>>(
+    filepath: SrcPath,
+    destination: DstPath,
+    options: &StructOptions,
+) -> Result<(), Error> {
+    let filepath = filepath.as_ref();
+    emit_rerun_directive(filepath, options);
+
+    let format = Format::from_filename(filepath)?;
+    let source = std::fs::read_to_string(filepath).map_err(file_error(filepath))?;
+    let mut included = Vec::new();
+    let output =
+        generate_schema_with_filepath(format, &source, options, Some(filepath), &mut included)?;
+    emit_rerun_directives(&included, options);
+
+    write_destination(destination.as_ref(), output, options)?;
+
+    Ok(())
+}
+
+/// Tell cargo to rerun the build script if a config file changes, when
+/// running in a build script.
+fn emit_rerun_directive(path: &Path, options: &StructOptions) {
+    // Cargo only sets `OUT_DIR` for build scripts.
+    if options.emit_rerun_directives && std::env::var_os("OUT_DIR").is_some() {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+}
+
+fn emit_rerun_directives(paths: &[PathBuf], options: &StructOptions) {
+    for path in paths {
+        emit_rerun_directive(path, options);
+    }
+}
+
+fn ensure_destination(path: &Path, options: &StructOptions) -> Result<(), Error> {
     if options.create_dirs {
         if let Some(dir) = path.parent() {
-            std::fs::create_dir_all(dir)?;
+            std::fs::create_dir_all(dir).map_err(file_error(dir))?;
         }
     }
 
@@ -371,20 +2082,24 @@ fn write_destination(
     destination: &Path,
     output: String,
     options: &StructOptions,
-) -> Result<(), std::io::Error> {
-    let should_write = if options.write_only_if_changed {
-        let existing = std::fs::read_to_string(destination);
-        match existing {
-            Ok(existing) => existing != output,
-            Err(_) => true,
-        }
-    } else {
-        true
+) -> Result<CreateReport, Error> {
+    let changed = match std::fs::read_to_string(destination) {
+        Ok(existing) => existing != output,
+        Err(_) => true,
     };
+    let should_write = !options.dry_run && (changed || !options.write_only_if_changed);
 
     if should_write {
-        std::fs::write(destination, output)
-    } else {
-        Ok(())
+        ensure_destination(destination, options)?;
+        std::fs::write(destination, &output).map_err(file_error(destination))?;
     }
+
+    Ok(CreateReport {
+        destination: destination.to_owned(),
+        written: should_write,
+        changed,
+        bytes: output.len(),
+        code: output,
+        warnings: Vec::new(),
+    })
 }
@@ -0,0 +1,196 @@
+//! Checking configs against `StructOptions::resource_limits`, for
+//! generating code from configs which aren't trusted.
+use crate::{
+    error::{ExceededLimit, GenerationError},
+    options::{ResourceLimits, StructOptions},
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Counts the values of a config as it's read, failing as soon as it
+/// passes one of its limits, or `StructOptions::max_depth`.
+pub struct LimitCounter<'a> {
+    limits: &'a ResourceLimits,
+    max_depth: usize,
+    nodes: usize,
+}
+
+impl<'a> LimitCounter<'a> {
+    pub fn new(options: &'a StructOptions) -> Self {
+        LimitCounter {
+            limits: &options.resource_limits,
+            max_depth: options.max_depth,
+            nodes: 0,
+        }
+    }
+
+    /// Count `count` more values, the last of them at `path`.
+    pub fn add_nodes(&mut self, count: usize, path: &str) -> Result<(), GenerationError> {
+        self.nodes = self.nodes.saturating_add(count);
+        check("max_nodes", self.nodes, self.limits.max_nodes, path)
+    }
+
+    /// Check a struct or array nested `depth` structs and arrays below
+    /// the root.
+    pub fn check_depth(&self, depth: usize, path: &str) -> Result<(), GenerationError> {
+        if depth <= self.max_depth {
+            return Ok(());
+        }
+        Err(GenerationError::MaxDepthExceeded {
+            path: path.to_owned(),
+            depth,
+        })
+    }
+
+    /// Check a string or key `len` bytes long.
+    pub fn check_string(&self, len: usize, path: &str) -> Result<(), GenerationError> {
+        check("max_string_len", len, self.limits.max_string_len, path)
+    }
+}
+
+fn check(limit: &'static str, value: usize, max: usize, path: &str) -> Result<(), GenerationError> {
+    if value <= max {
+        return Ok(());
+    }
+    Err(GenerationError::LimitExceeded(ExceededLimit {
+        limit,
+        max,
+        path: path.to_owned(),
+    }))
+}
+
+/// Check the length of a config's source, before it's parsed.
+pub fn check_source_len(len: usize, limits: &ResourceLimits) -> Result<(), GenerationError> {
+    check("max_source_bytes", len, limits.max_source_bytes, "")
+}
+
+/// Check a parsed config against its limits. This walks the values with
+/// a stack of its own, like `parsing::check_depth`, so that it can't
+/// overflow on the configs it rejects.
+pub fn check_config(
+    config: &GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let mut counter = LimitCounter::new(options);
+    counter.add_nodes(1, "")?;
+
+    let mut stack = Vec::new();
+    push_fields(config, "", 1, &counter, &mut stack)?;
+
+    while let Some((path, depth, value)) = stack.pop() {
+        counter.add_nodes(1, &path)?;
+        match *value {
+            GenericValue::String(ref string) => counter.check_string(string.len(), &path)?,
+            GenericValue::Option(Some(ref value)) => stack.push((path, depth, value)),
+            GenericValue::Struct(ref struct_value) => {
+                counter.check_depth(depth, &path)?;
+                push_fields(struct_value, &path, depth + 1, &counter, &mut stack)?;
+            }
+            GenericValue::Map(GenericMap {
+                ref keys,
+                ref values,
+            }) => {
+                counter.check_depth(depth, &path)?;
+                for (key, value) in keys.iter().zip(values) {
+                    let path = paths::join(&path, key);
+                    counter.check_string(key.len(), &path)?;
+                    stack.push((path, depth + 1, value));
+                }
+            }
+            GenericValue::Array(ref values) | GenericValue::Tuple(ref values) => {
+                counter.check_depth(depth, &path)?;
+                stack.extend(values.iter().map(|value| (path.clone(), depth + 1, value)));
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+fn push_fields<'v>(
+    struct_value: &'v GenericStruct,
+    parent_path: &str,
+    depth: usize,
+    counter: &LimitCounter,
+    stack: &mut Vec<(String, usize, &'v GenericValue)>,
+) -> Result<(), GenerationError> {
+    for (key, value) in &struct_value.fields {
+        let key = struct_value.original_key(key);
+        let path = paths::join(parent_path, key);
+        counter.check_string(key.len(), &path)?;
+        stack.push((path, depth, value));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    fn limits(max_nodes: usize, max_string_len: usize) -> StructOptions {
+        StructOptions {
+            resource_limits: ResourceLimits {
+                max_nodes,
+                max_string_len,
+                ..ResourceLimits::default()
+            },
+            ..StructOptions::default()
+        }
+    }
+
+    fn exceeded(result: Result<(), GenerationError>) -> (&'static str, String) {
+        match result {
+            Err(GenerationError::LimitExceeded(exceeded)) => (exceeded.limit, exceeded.path),
+            other => panic!("Expected an exceeded limit, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn nodes_are_counted_through_arrays() {
+        let ports = GenericValue::Array((0..10).map(GenericValue::I64).collect());
+        let config = config(vec![("ports", ports)]);
+
+        assert!(check_config(&config, &limits(12, usize::MAX)).is_ok());
+        assert_eq!(
+            exceeded(check_config(&config, &limits(11, usize::MAX))),
+            ("max_nodes", "ports".to_owned())
+        );
+    }
+
+    #[test]
+    fn strings_and_depth_name_their_path() {
+        let inner = config(vec![("name", GenericValue::String("x".repeat(10)))]);
+        let config = config(vec![(
+            "outer",
+            GenericValue::Array(vec![GenericValue::Struct(inner)]),
+        )]);
+
+        assert_eq!(
+            exceeded(check_config(&config, &limits(usize::MAX, 9))),
+            ("max_string_len", "outer.name".to_owned())
+        );
+
+        let options = StructOptions {
+            max_depth: 1,
+            ..StructOptions::default()
+        };
+        match check_config(&config, &options) {
+            Err(GenerationError::MaxDepthExceeded { path, depth }) => {
+                assert_eq!((path.as_str(), depth), ("outer", 2))
+            }
+            other => panic!("Expected too deep a value, found {:?}", other),
+        }
+    }
+}
@@ -1,12 +1,38 @@
-use std::path::Path;
+use std::{
+    env,
+    path::{self, Component, Path, PathBuf},
+};
 
-use crate::format::Format;
+use crate::{
+    error::GenerationError,
+    format::Format,
+    generation,
+    options::{DateTimeType, DynamicPath, EnvOptions, StringType, StructOptions},
+    value::{GenericStruct, GenericValue},
+};
+
+/// The expression which deserializes the `file_contents` of a config in
+/// the given format.
+#[cfg_attr(
+    not(any(
+        feature = "json-parsing",
+        feature = "ron-parsing",
+        feature = "toml-parsing",
+        feature = "yaml-parsing"
+    )),
+    allow(unreachable_code)
+)]
+pub fn load_expression(format: Format) -> Result<&'static str, GenerationError> {
+    Ok(match format {
+        #[cfg(feature = "ini-parsing")]
+        Format::Ini => return Err(GenerationError::UnsupportedDynamicLoading(format.to_string())),
 
-pub fn dynamic_load_impl(format: Format, struct_name: &str, filepath: &Path) -> String {
-    let load_expression = match format {
         #[cfg(feature = "json-parsing")]
         Format::Json => "::serde_json::from_str(&file_contents)",
 
+        #[cfg(feature = "json5-parsing")]
+        Format::Json5 => "::json5::from_str(&file_contents)",
+
         #[cfg(feature = "ron-parsing")]
         Format::Ron => "::ron::de::from_str(&file_contents)",
 
@@ -15,34 +41,552 @@ pub fn dynamic_load_impl(format: Format, struct_name: &str, filepath: &Path) ->
 
         #[cfg(feature = "yaml-parsing")]
         Format::Yaml => "::serde_yaml::from_str(&file_contents)",
+    })
+}
+
+/// Whether configs in the given format can be loaded at runtime.
+pub fn supports_dynamic_loading(format: Format) -> bool {
+    load_expression(format).is_ok()
+}
+
+pub fn dynamic_load_impl(
+    format: Format,
+    struct_name: &str,
+    filepath: &Path,
+    root_array: bool,
+    dynamic_path: &DynamicPath,
+    root_reference: Option<&str>,
+) -> Result<String, GenerationError> {
+    let load_expression = load_expression(format)?;
+    let (owned, borrowed) = loaded_types(root_array);
+    let error_name = load_error_name(struct_name);
+    let load_file = load_file_fn(owned, &error_name, load_expression);
+
+    if *dynamic_path == DynamicPath::Argument {
+        return Ok(format!(
+r#"impl {struct_name} {{
+    pub fn load_from<P: AsRef<::std::path::Path>>(filepath: P) -> Result<Cow<'static, {borrowed}>, Box<dyn ::std::error::Error>> {{
+        Ok(Cow::Owned(Self::try_load_from(filepath)?))
+    }}
+
+    pub fn try_load_from<P: AsRef<::std::path::Path>>(filepath: P) -> Result<{owned}, {error_name}> {{
+        Self::load_file(filepath.as_ref(), {argument_source})
+    }}
+{load_file}}}"#, struct_name=struct_name, owned=owned, borrowed=borrowed, error_name=error_name, argument_source=ARGUMENT_SOURCE, load_file=load_file));
+    }
+
+    let (skip_if_unset, try_load_body) = match *dynamic_path {
+        DynamicPath::EnvVar(ref name) => {
+            let name = generation::string_literal(name);
+            let root_reference =
+                root_reference.expect("the root value is generated for an environment variable path");
+            (
+                format!(
+r#"        if ::std::env::var_os({name}).is_none() {{
+            return Cow::Borrowed({root_reference});
+        }}
+"#, name=name, root_reference=root_reference),
+                format!(
+r#"match ::std::env::var_os({name}) {{
+            Some(filepath) => Self::load_file(filepath.as_ref(), {source}),
+            None => Ok(Self::load().into_owned()),
+        }}"#, name=name, source=path_source(dynamic_path)),
+            )
+        }
+        _ => {
+            let filepath = match *dynamic_path {
+                DynamicPath::Fixed(ref path) => generation::string_literal(path),
+                DynamicPath::ManifestRelative => manifest_relative_path(filepath),
+                _ => build_time_path(filepath),
+            };
+            (
+                String::new(),
+                format!(
+r#"let filepath = {filepath};
+        Self::load_file(filepath.as_ref(), {source})"#, filepath=filepath, source=path_source(dynamic_path)),
+            )
+        }
     };
 
+    Ok(format!(
+r#"impl {struct_name} {{
+    pub fn load() -> Cow<'static, {borrowed}> {{
+{skip_if_unset}        match Self::try_load() {{
+            Ok(config) => Cow::Owned(config),
+            Err(error) => panic!("Failed to load {struct_name}: {{}}", error),
+        }}
+    }}
+
+    pub fn try_load() -> Result<{owned}, {error_name}> {{
+        {try_load_body}
+    }}
+
+    pub fn load_from(filepath: &::std::path::Path) -> Result<Cow<'static, {borrowed}>, Box<dyn ::std::error::Error>> {{
+        Ok(Cow::Owned(Self::load_file(filepath, {argument_source})?))
+    }}
+{load_file}}}"#, struct_name=struct_name, owned=owned, borrowed=borrowed, error_name=error_name, skip_if_unset=skip_if_unset, try_load_body=try_load_body, argument_source=ARGUMENT_SOURCE, load_file=load_file))
+}
+
+/// Where errors say the path given to `load_from` came from.
+const ARGUMENT_SOURCE: &str = "\"the path argument\"";
+
+/// A string literal saying where the path of the config file came from,
+/// for the errors of the load functions.
+fn path_source(dynamic_path: &DynamicPath) -> String {
+    let source = match *dynamic_path {
+        DynamicPath::BuildTimePath => "the build-time path".to_owned(),
+        DynamicPath::ManifestRelative => "the path under `CARGO_MANIFEST_DIR`".to_owned(),
+        DynamicPath::Fixed(_) => "the fixed path".to_owned(),
+        DynamicPath::EnvVar(ref name) => format!("the `{}` environment variable", name),
+        DynamicPath::Argument => return ARGUMENT_SOURCE.to_owned(),
+    };
+    generation::string_literal(&source)
+}
+
+/// The private function which reads and deserializes the config file
+/// for the other load functions.
+fn load_file_fn(owned: &str, error_name: &str, load_expression: &str) -> String {
     format!(
+r#"
+    fn load_file(filepath: &::std::path::Path, path_source: &'static str) -> Result<{owned}, {error_name}> {{
+        let file_contents = ::std::fs::read_to_string(filepath)
+            .map_err(|error| {error_name}::Io(filepath.to_owned(), path_source, error))?;
+        let result: {owned} = {load_expression}
+            .map_err(|error| {error_name}::Deserialize(filepath.to_owned(), path_source, Box::new(error)))?;
+        Ok(result)
+    }}
+"#, owned=owned, error_name=error_name, load_expression=load_expression)
+}
+
+/// Load functions which parse a copy of the config file embedded in the
+/// binary, once, on first use.
+pub fn embedded_load_impl(
+    format: Format,
+    struct_name: &str,
+    filepath: &Path,
+    root_array: bool,
+) -> Result<String, GenerationError> {
+    let load_expression = load_expression(format)?;
+    let (owned, borrowed) = loaded_types(root_array);
+    let owned_concrete = owned.replace("Self", struct_name);
+    let error_name = load_error_name(struct_name);
+
+    let (contents, filepath) = match absolute_path(filepath).as_deref().and_then(Path::to_str) {
+        Some(path) => {
+            let literal = path_literal(path);
+            (format!("include_str!({})", literal), literal)
+        }
+        None => {
+            let message = format!(
+                "Couldn't find the absolute path of `{}` to embed it.",
+                filepath.display()
+            );
+            let literal = generation::string_literal(&filepath.to_string_lossy());
+            (format!("compile_error!({})", generation::string_literal(&message)), literal)
+        }
+    };
+
+    Ok(format!(
 r#"impl {struct_name} {{
-    pub fn load() -> Cow<'static, Self> {{
-        let filepath = concat!(env!("CARGO_MANIFEST_DIR"), "/{filepath}");
-        Self::load_from(filepath.as_ref()).expect("Failed to load {struct_name}.")
+    pub fn load() -> Cow<'static, {borrowed}> {{
+        static EMBEDDED: ::std::sync::LazyLock<{owned_concrete}> = ::std::sync::LazyLock::new(|| {{
+            match {struct_name}::try_load() {{
+                Ok(config) => config,
+                Err(error) => panic!("Failed to load {struct_name}: {{}}", error),
+            }}
+        }});
+        Cow::Borrowed(&*EMBEDDED)
     }}
 
-    pub fn load_from(filepath: &::std::path::Path) -> Result<Cow<'static, Self>, Box<dyn ::std::error::Error>> {{
-        let file_contents = ::std::fs::read_to_string(filepath)?;
-        let result: Self = {load_expression}?;
-        Ok(Cow::Owned(result))
+    pub fn try_load() -> Result<{owned}, {error_name}> {{
+        let file_contents: &str = {contents};
+        let result: {owned} = {load_expression}
+            .map_err(|error| {error_name}::Deserialize({filepath}.into(), "the embedded copy", Box::new(error)))?;
+        Ok(result)
     }}
-}}"#, struct_name=struct_name, filepath=filepath.to_str().unwrap().replace("\\", "/"), load_expression=load_expression)
+
+    pub fn load_from(filepath: &::std::path::Path) -> Result<Cow<'static, {borrowed}>, Box<dyn ::std::error::Error>> {{
+        Ok(Cow::Owned(Self::load_file(filepath, {argument_source})?))
+    }}
+{load_file}}}"#, struct_name=struct_name, owned=owned, owned_concrete=owned_concrete, borrowed=borrowed, error_name=error_name, contents=contents, filepath=filepath, load_expression=load_expression, argument_source=ARGUMENT_SOURCE, load_file=load_file_fn(owned, &error_name, load_expression)))
+}
+
+/// The expression for the path a config file was generated from, with a
+/// relative path taken from `CARGO_MANIFEST_DIR` when the code is
+/// compiled.
+fn build_time_path(filepath: &Path) -> String {
+    match filepath.to_str() {
+        Some(path) if filepath.is_absolute() => path_literal(path),
+        Some(path) => format!(
+            "concat!(env!(\"CARGO_MANIFEST_DIR\"), {})",
+            path_literal(&format!("/{}", path))
+        ),
+        None => non_utf8_path_error(filepath),
+    }
+}
+
+/// The expression for the path a config file was generated from, joined
+/// onto `CARGO_MANIFEST_DIR` at runtime one component at a time, so that
+/// each platform uses its own separators. An absolute path is made
+/// relative to `CARGO_MANIFEST_DIR` at build time where it's under it.
+fn manifest_relative_path(filepath: &Path) -> String {
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from);
+    let relative = match manifest_dir {
+        Some(ref dir) if filepath.is_absolute() => filepath.strip_prefix(dir).ok(),
+        _ if filepath.is_absolute() => None,
+        _ => Some(filepath),
+    };
+
+    let relative = match relative {
+        Some(relative) => relative,
+        None => {
+            return match filepath.to_str() {
+                Some(path) => format!("::std::path::Path::new({})", path_literal(path)),
+                None => non_utf8_path_error(filepath),
+            }
+        }
+    };
+
+    let mut expression = "::std::path::Path::new(env!(\"CARGO_MANIFEST_DIR\"))".to_owned();
+    for component in relative.components() {
+        let component = match component {
+            Component::Normal(name) => name,
+            Component::ParentDir => "..".as_ref(),
+            _ => continue,
+        };
+        match component.to_str() {
+            Some(name) => {
+                expression.push_str(&format!(".join({})", generation::string_literal(name)))
+            }
+            None => return non_utf8_path_error(filepath),
+        }
+    }
+    expression
 }
 
-pub fn static_load_impl(struct_name: &str, const_name: &str) -> String {
+/// A string literal for a path at build time. Backslashes are escaped,
+/// and on Windows are made forward slashes, which Windows accepts as
+/// well, except in verbatim paths like `\\?\C:\config.toml`.
+pub fn path_literal(path: &str) -> String {
+    if path::MAIN_SEPARATOR == '\\' && !path.starts_with(r"\\?\") {
+        generation::string_literal(&path.replace('\\', "/"))
+    } else {
+        generation::string_literal(path)
+    }
+}
+
+/// A `compile_error!` for a path which can't be written in a string
+/// literal.
+fn non_utf8_path_error(filepath: &Path) -> String {
+    let message = format!(
+        "The path of `{}` isn't valid UTF-8, so it can't be loaded from.",
+        filepath.display()
+    );
+    format!("compile_error!({})", generation::string_literal(&message))
+}
+
+/// The absolute path of a config file. A relative path is taken from
+/// the directory of the crate being built, when generating from a build
+/// script, or else from the current directory.
+pub fn absolute_path(filepath: &Path) -> Option<PathBuf> {
+    if filepath.is_absolute() {
+        return Some(filepath.to_owned());
+    }
+
+    let base = env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .or_else(|| env::current_dir().ok())?;
+    Some(base.join(filepath))
+}
+
+pub fn static_load_impl(struct_name: &str, root_reference: &str, root_array: bool, options: &StructOptions) -> String {
+    let (owned, borrowed) = loaded_types(root_array);
+
+    // Without std, there is no error type or path to load from.
+    if options.no_std {
+        return format!(
+r#"impl {struct_name} {{
+    #[inline(always)]
+    pub fn load() -> Cow<'static, {borrowed}> {{
+        Cow::Borrowed({root_reference})
+    }}
+}}"#, struct_name=struct_name, borrowed=borrowed, root_reference=root_reference);
+    }
+
+    // Matches the dynamic impl, which has no path to load from without
+    // an argument.
+    if options.dynamic_path == DynamicPath::Argument {
+        return format!(
+r#"impl {struct_name} {{
+    #[inline(always)]
+    pub fn load_from<P: AsRef<::std::path::Path>>(_: P) -> Result<Cow<'static, {borrowed}>, Box<dyn ::std::error::Error>> {{
+        Ok(Cow::Borrowed({root_reference}))
+    }}
+
+    #[inline(always)]
+    pub fn try_load_from<P: AsRef<::std::path::Path>>(_: P) -> Result<{owned}, {error_name}> {{
+        let config: Cow<'static, {borrowed}> = Cow::Borrowed({root_reference});
+        Ok(config.into_owned())
+    }}
+}}"#, struct_name=struct_name, owned=owned, borrowed=borrowed, error_name=load_error_name(struct_name), root_reference=root_reference);
+    }
+
     format!(
 r#"impl {struct_name} {{
     #[inline(always)]
-    pub fn load() -> Cow<'static, Self> {{
-        Cow::Borrowed(&{const_name})
+    pub fn load() -> Cow<'static, {borrowed}> {{
+        Cow::Borrowed({root_reference})
     }}
 
     #[inline(always)]
-    pub fn load_from(_: &::std::path::Path) -> Result<Cow<'static, Self>, Box<dyn ::std::error::Error>> {{
-        Ok(Cow::Borrowed(&{const_name}))
+    pub fn try_load() -> Result<{owned}, {error_name}> {{
+        Ok(Self::load().into_owned())
+    }}
+
+    #[inline(always)]
+    pub fn load_from(_: &::std::path::Path) -> Result<Cow<'static, {borrowed}>, Box<dyn ::std::error::Error>> {{
+        Ok(Self::load())
+    }}
+}}"#, struct_name=struct_name, owned=owned, borrowed=borrowed, error_name=load_error_name(struct_name), root_reference=root_reference)
+}
+
+/// The owned and borrowed types returned by the load functions: the
+/// struct itself, or a `Vec` and slice of it when the root of the config
+/// is an array.
+fn loaded_types(root_array: bool) -> (&'static str, &'static str) {
+    if root_array {
+        ("Vec<Self>", "[Self]")
+    } else {
+        ("Self", "Self")
+    }
+}
+
+/// The error type returned by `try_load`, which is declared once for
+/// both the dynamic and static load functions.
+pub fn load_error_declaration(struct_name: &str) -> String {
+    format!(
+r#"
+#[derive(Debug)]
+pub enum {error_name} {{
+    Io(::std::path::PathBuf, &'static str, ::std::io::Error),
+    Deserialize(::std::path::PathBuf, &'static str, Box<dyn ::std::error::Error + Send + Sync>),
+}}
+
+impl ::std::fmt::Display for {error_name} {{
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {{
+        match self {{
+            {error_name}::Io(path, source, error) => write!(f, "Failed to read `{{}}` (from {{}}): {{}}", path.display(), source, error),
+            {error_name}::Deserialize(path, source, error) => write!(f, "Failed to deserialize `{{}}` (from {{}}): {{}}", path.display(), source, error),
+        }}
+    }}
+}}
+
+impl ::std::error::Error for {error_name} {{
+    fn source(&self) -> Option<&(dyn ::std::error::Error + 'static)> {{
+        match self {{
+            {error_name}::Io(_, _, error) => Some(error),
+            {error_name}::Deserialize(_, _, error) => Some(&**error),
+        }}
+    }}
+}}
+"#, error_name=load_error_name(struct_name))
+}
+
+fn load_error_name(struct_name: &str) -> String {
+    format!("{}LoadError", struct_name)
+}
+
+pub fn env_overrides_impl(config: &GenericStruct, options: &StructOptions, env_options: &EnvOptions, root_value: Option<&str>) -> String {
+    // Options validation ensures there's a root value without load functions.
+    let initial_value = match root_value {
+        Some(root_value) if !options.generate_load_fns => format!("{}.clone()", root_value),
+        _ => "Self::load().into_owned()".to_owned(),
+    };
+
+    let mut overrides = String::new();
+    push_struct_overrides(&mut overrides, config, "config", &[], options, env_options);
+
+    format!(
+r#"
+impl {struct_name} {{
+    pub fn load_with_env_overrides() -> Result<Self, Box<dyn ::std::error::Error>> {{
+        let mut config = {initial_value};
+{overrides}
+        Ok(config)
     }}
-}}"#, struct_name=struct_name, const_name=const_name)
+}}
+"#, struct_name=options.struct_name, initial_value=initial_value, overrides=overrides)
+}
+
+fn push_struct_overrides(
+    output: &mut String,
+    struct_value: &GenericStruct,
+    access_path: &str,
+    keys: &[&str],
+    options: &StructOptions,
+    env_options: &EnvOptions,
+) {
+    for (key, value) in struct_value.ordered_fields(options.field_order) {
+        let access_path = format!("{}.{}", access_path, key);
+        let mut keys = keys.to_vec();
+        keys.push(struct_value.original_key(key));
+
+        if let GenericValue::Struct(ref value) = *value {
+            push_struct_overrides(output, value, &access_path, &keys, options, env_options);
+            continue;
+        }
+
+        let var_name = env_var_name(&keys, env_options);
+        if let Some(expression) = override_expression(value, &var_name, options) {
+            output.push_str(&format!(
+r#"        if let Ok(value) = ::std::env::var("{var_name}") {{
+            {access_path} = {expression};
+        }}
+"#, var_name=var_name, access_path=access_path, expression=expression));
+        }
+    }
+}
+
+/// The expression which converts the `String` in `value` to the type of
+/// a field, or `None` if the field can't be overridden.
+fn override_expression(value: &GenericValue, var_name: &str, options: &StructOptions) -> Option<String> {
+    match *value {
+        GenericValue::String(_) => string_override_expression(options),
+        GenericValue::DateTime(_) => match options.date_time_type {
+            DateTimeType::String => string_override_expression(options),
+            #[cfg(feature = "chrono")]
+            DateTimeType::ChronoDateTime => Some(parse_override_expression(var_name)),
+        },
+        GenericValue::Bool(_)
+        | GenericValue::Char(_)
+        | GenericValue::I8(_)
+        | GenericValue::I16(_)
+        | GenericValue::I32(_)
+        | GenericValue::I64(_)
+        | GenericValue::I128(_)
+        | GenericValue::U8(_)
+        | GenericValue::U16(_)
+        | GenericValue::U32(_)
+        | GenericValue::U64(_)
+        | GenericValue::U128(_)
+        | GenericValue::ISize(_)
+        | GenericValue::Usize(_)
+        | GenericValue::F32(_)
+        | GenericValue::F64(_)
+        | GenericValue::Version(_) => Some(parse_override_expression(var_name)),
+        GenericValue::Option(Some(ref value)) => override_expression(value, var_name, options)
+            .map(|expression| format!("Some({})", expression)),
+        GenericValue::Newtype(ref newtype) => {
+            override_expression(&newtype.value, var_name, options)
+                .map(|expression| format!("{}({})", newtype.type_name, expression))
+        }
+        _ => None,
+    }
+}
+
+fn string_override_expression(options: &StructOptions) -> Option<String> {
+    match options.string_type {
+        StringType::Cow => Some("Cow::Owned(value)".to_owned()),
+        StringType::String => Some("value".to_owned()),
+        StringType::StaticStr => None,
+    }
+}
+
+fn parse_override_expression(var_name: &str) -> String {
+    format!(
+        r#"value.parse().map_err(|error| format!("Invalid value for {}: {{}}", error))?"#,
+        var_name
+    )
+}
+
+fn env_var_name(keys: &[&str], env_options: &EnvOptions) -> String {
+    let path = keys
+        .iter()
+        .map(|key| {
+            key.chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join(&env_options.separator);
+
+    format!("{}_{}", env_options.prefix, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(all(unix, feature = "toml-parsing"))]
+    fn embedded_paths_are_absolute_literals() {
+        let code = embedded_load_impl(Format::Toml, "Config", Path::new("/etc/app.toml"), false)
+            .unwrap();
+        assert!(code.contains(r#"include_str!("/etc/app.toml")"#));
+
+        let code = embedded_load_impl(Format::Toml, "Config", Path::new(r"/etc/a\b.toml"), false)
+            .unwrap();
+        assert!(code.contains(r#"include_str!("/etc/a\\b.toml")"#));
+
+        let manifest_dir = env!("CARGO_MANIFEST_DIR");
+        let code =
+            embedded_load_impl(Format::Toml, "Config", Path::new("app.toml"), false).unwrap();
+        assert!(code.contains(&format!(r#"include_str!("{}/app.toml")"#, manifest_dir)));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn build_time_paths_are_escaped() {
+        assert_eq!(
+            build_time_path(Path::new("my configs/café.toml")),
+            r#"concat!(env!("CARGO_MANIFEST_DIR"), "/my configs/café.toml")"#
+        );
+        // A backslash is part of the name on Unix, so it's only escaped.
+        assert_eq!(
+            build_time_path(Path::new(r"configs\new\things.toml")),
+            r#"concat!(env!("CARGO_MANIFEST_DIR"), "/configs\\new\\things.toml")"#
+        );
+        assert_eq!(
+            build_time_path(Path::new("/etc/app config.toml")),
+            r#""/etc/app config.toml""#
+        );
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn windows_paths_use_forward_slashes() {
+        assert_eq!(path_literal(r"C:\new\things.toml"), r#""C:/new/things.toml""#);
+        assert_eq!(
+            path_literal(r"\\?\C:\new\things.toml"),
+            r#""\\\\?\\C:\\new\\things.toml""#
+        );
+    }
+
+    #[test]
+    fn manifest_relative_paths_are_joined() {
+        let joined = r#"::std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("configs").join("café app.toml")"#;
+        assert_eq!(
+            manifest_relative_path(Path::new("./configs/café app.toml")),
+            joined
+        );
+
+        let absolute = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("configs")
+            .join("café app.toml");
+        assert_eq!(manifest_relative_path(&absolute), joined);
+    }
+
+    #[test]
+    fn env_var_names() {
+        let env_options = EnvOptions::default();
+        assert_eq!(env_var_name(&["name"], &env_options), "CONFIG_NAME");
+        assert_eq!(env_var_name(&["server", "port"], &env_options), "CONFIG_SERVER__PORT");
+        assert_eq!(env_var_name(&["max-connections"], &env_options), "CONFIG_MAX_CONNECTIONS");
+
+        let env_options = EnvOptions {
+            prefix: "APP".to_owned(),
+            separator: "_".to_owned(),
+        };
+        assert_eq!(env_var_name(&["server", "port"], &env_options), "APP_SERVER_PORT");
+    }
 }
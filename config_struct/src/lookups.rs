@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    generation::{collect_enums, declared_structs, string_literal, string_type},
+    naming::pascal_case,
+    options::{DateTimeType, StringType, StructOptions},
+    value::{GenericStruct, GenericValue},
+};
+
+/// The types which `{Config}Value` is implemented for, with the variant
+/// of `{Config}ValueRef` each becomes, and how.
+const SCALAR_IMPLS: &[(&str, &str)] = &[
+    ("bool", "Bool(*self)"),
+    ("char", "Char(*self)"),
+    ("i8", "I64(*self as i64)"),
+    ("i16", "I64(*self as i64)"),
+    ("i32", "I64(*self as i64)"),
+    ("i64", "I64(*self)"),
+    ("isize", "I64(*self as i64)"),
+    ("i128", "I128(*self)"),
+    ("u8", "U64(*self as u64)"),
+    ("u16", "U64(*self as u64)"),
+    ("u32", "U64(*self as u64)"),
+    ("u64", "U64(*self)"),
+    ("usize", "U64(*self as u64)"),
+    ("u128", "U128(*self)"),
+    ("f32", "F64(*self as f64)"),
+    ("f64", "F64(*self)"),
+];
+
+/// Generate `get` on the root struct, which finds a value by a dotted
+/// path of keys and array indices, along with the types it returns.
+///
+/// Each struct matches on its own keys and hands the rest of the path
+/// back to `get`, so the code grows with the number of fields rather than
+/// the number of paths. Tuples, maps, versions and `chrono` date-times
+/// have no lookup, so their paths give `None`.
+pub fn generate_lookups(struct_value: &GenericStruct, options: &StructOptions) -> String {
+    let prefix = &options.struct_name;
+    let mut buffer = support_declarations(prefix, options);
+
+    let mut enums = BTreeMap::new();
+    collect_enums(struct_value, &mut enums);
+    for enum_value in enums.values() {
+        let arms = enum_value
+            .variants
+            .iter()
+            .map(|variant| {
+                format!(
+                    "            {}::{} => {},\n",
+                    enum_value.enum_name,
+                    pascal_case(variant),
+                    string_literal(variant)
+                )
+            })
+            .collect::<String>();
+        buffer.push_str(&format!(
+            "
+impl {prefix}Value for {enum_name} {{
+    fn value_ref(&self) -> {prefix}ValueRef<'_> {{
+        {prefix}ValueRef::Str(match *self {{
+{arms}        }})
+    }}
+}}
+",
+            prefix = prefix,
+            enum_name = enum_value.enum_name,
+            arms = arms
+        ));
+    }
+
+    for struct_value in declared_structs(struct_value) {
+        generate_struct_lookup(&mut buffer, &struct_value, options);
+    }
+
+    buffer.push_str(&format!(
+        "
+impl {struct_name} {{
+    /// The value at a dotted path of keys and array indices, like
+    /// `servers.2.port`, or `None` if there is no value there.
+    pub fn get(&self, path: &str) -> Option<{prefix}ValueRef<'_>> {{
+        let mut value = {prefix}ValueRef::Struct(self);
+        for key in path.split('.') {{
+            value = match value {{
+                {prefix}ValueRef::Struct(fields) => fields.field(key)?,
+                {prefix}ValueRef::Array(elements) => elements.element(key.parse().ok()?)?,
+                _ => return None,
+            }};
+        }}
+        Some(value)
+    }}
+}}
+",
+        struct_name = struct_value.struct_name,
+        prefix = prefix
+    ));
+
+    buffer
+}
+
+fn generate_struct_lookup(
+    output: &mut String,
+    struct_value: &GenericStruct,
+    options: &StructOptions,
+) {
+    let prefix = &options.struct_name;
+    let fields = struct_value
+        .ordered_fields(options.field_order)
+        .into_iter()
+        .filter(|(_, value)| has_lookup(value, options))
+        .map(|(name, _)| (name, struct_value.original_key(name)))
+        .collect::<Vec<_>>();
+
+    let names = fields
+        .iter()
+        .map(|(_, key)| string_literal(key))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let arms = fields
+        .iter()
+        .map(|(name, key)| {
+            format!(
+                "            {} => Some(self.{}.value_ref()),\n",
+                string_literal(key),
+                name
+            )
+        })
+        .collect::<String>();
+
+    output.push_str(&format!(
+        "
+impl {prefix}Value for {struct_name} {{
+    fn value_ref(&self) -> {prefix}ValueRef<'_> {{
+        {prefix}ValueRef::Struct(self)
+    }}
+}}
+
+impl {prefix}Fields for {struct_name} {{
+    fn field_names(&self) -> ::core::slice::Iter<'static, &'static str> {{
+        const FIELD_NAMES: &[&str] = &[{names}];
+        FIELD_NAMES.iter()
+    }}
+
+    fn field(&self, key: &str) -> Option<{prefix}ValueRef<'_>> {{
+        match key {{
+{arms}            _ => None,
+        }}
+    }}
+}}
+",
+        prefix = prefix,
+        struct_name = struct_value.struct_name,
+        names = names,
+        arms = arms
+    ));
+}
+
+/// Whether a value's type implements `{Config}Value`.
+fn has_lookup(value: &GenericValue, options: &StructOptions) -> bool {
+    match *value {
+        GenericValue::Tuple(_)
+        | GenericValue::Map(_)
+        | GenericValue::Version(_)
+        | GenericValue::Newtype(_)
+        | GenericValue::TaggedUnion(_) => false,
+        GenericValue::DateTime(_) => options.date_time_type == DateTimeType::String,
+        GenericValue::EmptyArray(ref element_type) => {
+            *element_type == "()"
+                || *element_type == string_type(options)
+                || SCALAR_IMPLS.iter().any(|(name, _)| name == element_type)
+        }
+        GenericValue::Option(Some(ref value)) => has_lookup(value, options),
+        GenericValue::Array(ref values) => {
+            GenericValue::typed_element(values).is_none_or(|element| has_lookup(element, options))
+        }
+        _ => true,
+    }
+}
+
+/// The value type, traits and impls shared by every struct of a config.
+fn support_declarations(prefix: &str, options: &StructOptions) -> String {
+    let mut impls = SCALAR_IMPLS
+        .iter()
+        .map(|(type_name, value)| value_impl(prefix, "", type_name, value))
+        .collect::<Vec<_>>();
+    impls.push(value_impl(prefix, "", "()", "Unit"));
+    impls.push(value_impl(prefix, "", "&str", "Str(self)"));
+    impls.push(elements_impls(prefix, "<T: {prefix}Value>", "&[T]"));
+    impls.push(elements_impls(
+        prefix,
+        "<T: {prefix}Value, const N: usize>",
+        "[T; N]",
+    ));
+    match options.string_type {
+        StringType::Cow => {
+            impls.push(value_impl(prefix, "", "Cow<'_, str>", "Str(self)"));
+            impls.push(elements_impls(
+                prefix,
+                "<T: {prefix}Value + Clone>",
+                "Cow<'_, [T]>",
+            ));
+        }
+        StringType::String => {
+            impls.push(value_impl(prefix, "", "String", "Str(self)"));
+            impls.push(elements_impls(prefix, "<T: {prefix}Value>", "Vec<T>"));
+        }
+        StringType::StaticStr => (),
+    }
+
+    format!(
+        "
+/// A value in a `{prefix}`, found by `{prefix}::get`.
+#[derive(Clone, Copy)]
+pub enum {prefix}ValueRef<'a> {{
+    Unit,
+    Null,
+    Bool(bool),
+    Char(char),
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    F64(f64),
+    Str(&'a str),
+    Array(&'a dyn {prefix}Elements),
+    Struct(&'a dyn {prefix}Fields),
+}}
+
+/// A struct in a `{prefix}`, whose fields can be found by key.
+pub trait {prefix}Fields {{
+    /// The keys of the fields, as in the config file.
+    fn field_names(&self) -> ::core::slice::Iter<'static, &'static str>;
+
+    /// The field with the given key, if there is one.
+    fn field(&self, key: &str) -> Option<{prefix}ValueRef<'_>>;
+}}
+
+/// An array in a `{prefix}`, whose elements can be found by index.
+pub trait {prefix}Elements {{
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {{
+        self.len() == 0
+    }}
+
+    /// The element at the given index, if there is one.
+    fn element(&self, index: usize) -> Option<{prefix}ValueRef<'_>>;
+}}
+
+/// A value in a `{prefix}` which `{prefix}::get` can return.
+pub trait {prefix}Value {{
+    fn value_ref(&self) -> {prefix}ValueRef<'_>;
+}}
+
+impl<T: {prefix}Value> {prefix}Value for Option<T> {{
+    fn value_ref(&self) -> {prefix}ValueRef<'_> {{
+        match *self {{
+            Some(ref value) => value.value_ref(),
+            None => {prefix}ValueRef::Null,
+        }}
+    }}
+}}
+{impls}",
+        prefix = prefix,
+        impls = impls.join("")
+    )
+}
+
+fn value_impl(prefix: &str, generics: &str, type_name: &str, value: &str) -> String {
+    format!(
+        "
+impl{generics} {prefix}Value for {type_name} {{
+    fn value_ref(&self) -> {prefix}ValueRef<'_> {{
+        {prefix}ValueRef::{value}
+    }}
+}}
+",
+        generics = generics,
+        prefix = prefix,
+        type_name = type_name,
+        value = value
+    )
+}
+
+/// The impls for an array type, given with `{prefix}` standing for the
+/// prefix in its generics.
+fn elements_impls(prefix: &str, generics: &str, type_name: &str) -> String {
+    let generics = generics.replace("{prefix}", prefix);
+    let mut code = value_impl(prefix, &generics, type_name, "Array(self)");
+    code.push_str(&format!(
+        "
+impl{generics} {prefix}Elements for {type_name} {{
+    fn len(&self) -> usize {{
+        <[T]>::len(self)
+    }}
+
+    fn element(&self, index: usize) -> Option<{prefix}ValueRef<'_>> {{
+        self.get(index).map(|value| value.value_ref())
+    }}
+}}
+",
+        generics = generics,
+        prefix = prefix,
+        type_name = type_name
+    ));
+    code
+}
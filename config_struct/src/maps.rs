@@ -0,0 +1,200 @@
+use crate::{
+    error::GenerationError,
+    options::StructOptions,
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Replace the tables at the paths listed in `options.map_paths` with
+/// maps.
+///
+/// Structs among the values of a map are renamed after the map itself,
+/// in the same way as the elements of an array, so that later passes
+/// can give them a single declaration.
+pub fn apply_map_paths(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if options.map_paths.is_empty() {
+        return Ok(());
+    }
+
+    apply_to_struct(struct_value, "", options)
+}
+
+fn apply_to_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    for (key, value) in &mut struct_value.fields {
+        let path = paths::join(parent_path, key);
+
+        if options.map_paths.contains(&path) {
+            convert_to_map(value, &path)?;
+        }
+        apply_to_value(value, &path, options)?;
+    }
+    Ok(())
+}
+
+fn apply_to_value(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => apply_to_struct(struct_value, path, options),
+        GenericValue::Option(Some(ref mut value)) => apply_to_value(value, path, options),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                apply_to_value(value, path, options)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn convert_to_map(value: &mut GenericValue, path: &str) -> Result<(), GenerationError> {
+    let map_value = match *value {
+        GenericValue::Struct(ref mut struct_value) => {
+            let struct_name = struct_value.struct_name.clone();
+            let mut fields = std::mem::take(&mut struct_value.fields)
+                .into_iter()
+                .collect::<Vec<_>>();
+
+            // Keys follow the source order if it's known.
+            let field_order = &struct_value.field_order;
+            fields.sort_by_key(|(key, _)| {
+                field_order
+                    .iter()
+                    .position(|ordered_key| ordered_key == key)
+                    .unwrap_or(usize::MAX)
+            });
+
+            let mut map = GenericMap::default();
+            for (key, mut value) in fields {
+                value.replace_name_prefix(&format!("{}__{}", struct_name, key), &struct_name);
+                map.keys.push(key);
+                map.values.push(value);
+            }
+            GenericValue::Map(map)
+        }
+        GenericValue::Option(None) => return Ok(()),
+        GenericValue::Option(Some(ref mut value)) => return convert_to_map(value, path),
+        GenericValue::Array(ref mut values) => {
+            for value in values {
+                convert_to_map(value, path)?;
+            }
+            return Ok(());
+        }
+        _ => return Err(GenerationError::InvalidMapPath(path.into())),
+    };
+
+    *value = map_value;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_struct(name: &str, fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: name.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            original_keys: Default::default(),
+            doc: None,
+            field_docs: Default::default(),
+            field_cfgs: Default::default(),
+            field_spans: Default::default(),
+            flattened_fields: Default::default(),
+            absent_fields: Default::default(),
+            field_order: Vec::new(),
+        }
+    }
+
+    fn options_with_map(path: &str) -> StructOptions {
+        StructOptions {
+            map_paths: vec![path.to_owned()],
+            ..StructOptions::default()
+        }
+    }
+
+    #[test]
+    fn table_becomes_map() {
+        let mut config = make_struct(
+            "Config",
+            vec![(
+                "ports",
+                GenericValue::Struct(make_struct(
+                    "_Config__ports",
+                    vec![
+                        ("http", GenericValue::I64(80)),
+                        ("https", GenericValue::I64(443)),
+                    ],
+                )),
+            )],
+        );
+
+        apply_map_paths(&mut config, &options_with_map("ports")).unwrap();
+
+        match config.fields["ports"] {
+            GenericValue::Map(ref map) => {
+                assert_eq!(map.keys, vec!["http", "https"]);
+                assert_eq!(map.values.len(), 2);
+            }
+            ref other => panic!("Expected map, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn struct_values_are_named_after_the_map() {
+        let server =
+            |name| GenericValue::Struct(make_struct(name, vec![("port", GenericValue::I64(80))]));
+        let mut config = make_struct(
+            "Config",
+            vec![(
+                "servers",
+                GenericValue::Struct(make_struct(
+                    "_Config__servers",
+                    vec![
+                        ("alpha", server("_Config__servers__alpha")),
+                        ("beta-2", server("_Config__servers__beta-2")),
+                    ],
+                )),
+            )],
+        );
+
+        apply_map_paths(&mut config, &options_with_map("servers")).unwrap();
+
+        match config.fields["servers"] {
+            GenericValue::Map(ref map) => {
+                for value in &map.values {
+                    match *value {
+                        GenericValue::Struct(ref value) => {
+                            assert_eq!(value.struct_name, "_Config__servers")
+                        }
+                        ref other => panic!("Expected struct, found {:?}", other),
+                    }
+                }
+            }
+            ref other => panic!("Expected map, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn non_table_is_an_error() {
+        let mut config = make_struct("Config", vec![("ports", GenericValue::I64(80))]);
+
+        match apply_map_paths(&mut config, &options_with_map("ports")) {
+            Err(GenerationError::InvalidMapPath(path)) => assert_eq!(path, "ports"),
+            other => panic!("Expected map path error, found {:?}", other),
+        }
+    }
+}
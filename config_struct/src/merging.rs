@@ -0,0 +1,190 @@
+use std::mem::discriminant;
+
+use crate::{
+    error::GenerationError,
+    paths, root_arrays,
+    value::{GenericStruct, GenericValue},
+};
+
+/// Deep-merge `overrides` into `base`.
+///
+/// Nested structs are merged recursively, while all other values
+/// (including arrays) in `overrides` replace those in `base`. Configs
+/// whose root is an array can't be merged.
+pub fn merge_structs(
+    base: &mut GenericStruct,
+    overrides: GenericStruct,
+) -> Result<(), GenerationError> {
+    if root_arrays::root_elements(base).is_some()
+        || root_arrays::root_elements(&overrides).is_some()
+    {
+        return Err(GenerationError::UnsupportedRootArray("merging".to_owned()));
+    }
+
+    merge_fields(base, overrides, "")
+}
+
+fn merge_fields(
+    base: &mut GenericStruct,
+    overrides: GenericStruct,
+    parent_path: &str,
+) -> Result<(), GenerationError> {
+    for key in overrides.field_order {
+        if !base.field_order.contains(&key) {
+            base.field_order.push(key);
+        }
+    }
+
+    for (key, value) in overrides.fields {
+        let path = paths::join(parent_path, &key);
+
+        match base.fields.get_mut(&key) {
+            Some(existing) => merge_value(existing, value, &path)?,
+            None => {
+                base.fields.insert(key, value);
+            }
+        }
+    }
+
+    base.field_docs.extend(overrides.field_docs);
+    base.field_cfgs.extend(overrides.field_cfgs);
+    if overrides.doc.is_some() {
+        base.doc = overrides.doc;
+    }
+    Ok(())
+}
+
+fn merge_value(
+    base: &mut GenericValue,
+    value: GenericValue,
+    path: &str,
+) -> Result<(), GenerationError> {
+    match (base, value) {
+        (GenericValue::Struct(base), GenericValue::Struct(value)) => {
+            merge_fields(base, value, path)
+        }
+        (base, value) => {
+            if !compatible(base, &value) {
+                return Err(GenerationError::IncompatibleMerge(path.into()));
+            }
+            *base = value;
+            Ok(())
+        }
+    }
+}
+
+fn compatible(base: &GenericValue, value: &GenericValue) -> bool {
+    match (base, value) {
+        (GenericValue::Option(None), _) | (_, GenericValue::Option(None)) => true,
+        (base, value) => discriminant(base) == discriminant(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_struct(fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            original_keys: Default::default(),
+            doc: None,
+            field_docs: Default::default(),
+            field_cfgs: Default::default(),
+            field_spans: Default::default(),
+            flattened_fields: Default::default(),
+            absent_fields: Default::default(),
+            field_order: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn later_values_win() {
+        let mut base = make_struct(vec![
+            ("a", GenericValue::I64(1)),
+            ("b", GenericValue::I64(2)),
+        ]);
+        let overrides = make_struct(vec![("b", GenericValue::I64(3))]);
+
+        merge_structs(&mut base, overrides).unwrap();
+
+        match (&base.fields["a"], &base.fields["b"]) {
+            (GenericValue::I64(1), GenericValue::I64(3)) => (),
+            other => panic!("Unexpected merge result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn nested_structs_merge_recursively() {
+        let mut base = make_struct(vec![(
+            "server",
+            GenericValue::Struct(make_struct(vec![
+                ("host", GenericValue::String("localhost".into())),
+                ("port", GenericValue::I64(80)),
+            ])),
+        )]);
+        let overrides = make_struct(vec![(
+            "server",
+            GenericValue::Struct(make_struct(vec![("port", GenericValue::I64(8080))])),
+        )]);
+
+        merge_structs(&mut base, overrides).unwrap();
+
+        match base.fields["server"] {
+            GenericValue::Struct(ref server) => {
+                assert_eq!(server.fields.len(), 2);
+                match server.fields["port"] {
+                    GenericValue::I64(8080) => (),
+                    ref other => panic!("Unexpected port: {:?}", other),
+                }
+            }
+            ref other => panic!("Expected struct, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn arrays_are_replaced() {
+        let mut base = make_struct(vec![(
+            "list",
+            GenericValue::Array(vec![GenericValue::I64(1), GenericValue::I64(2)]),
+        )]);
+        let overrides = make_struct(vec![(
+            "list",
+            GenericValue::Array(vec![GenericValue::I64(3)]),
+        )]);
+
+        merge_structs(&mut base, overrides).unwrap();
+
+        match base.fields["list"] {
+            GenericValue::Array(ref values) => assert_eq!(values.len(), 1),
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn incompatible_types_are_an_error() {
+        let mut base = make_struct(vec![(
+            "server",
+            GenericValue::Struct(make_struct(vec![(
+                "port",
+                GenericValue::String("80".into()),
+            )])),
+        )]);
+        let overrides = make_struct(vec![(
+            "server",
+            GenericValue::Struct(make_struct(vec![(
+                "port",
+                GenericValue::Struct(make_struct(vec![])),
+            )])),
+        )]);
+
+        match merge_structs(&mut base, overrides) {
+            Err(GenerationError::IncompatibleMerge(path)) => assert_eq!(path, "server.port"),
+            other => panic!("Expected merge error, found {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use crate::{error::GenerationError, options::StructOptions};
+
+/// One of the config files generated into a shared module by
+/// [`create_config_module`](fn.create_config_module.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleInput {
+    /// The config file. Its format is auto-detected from its extension.
+    pub path: PathBuf,
+
+    /// The name of the struct generated for this file.
+    pub struct_name: String,
+
+    /// The name of the const generated for this file, if any.
+    ///
+    /// Defaults to the value of `struct_name` in uppercase.
+    pub const_name: Option<String>,
+}
+
+impl ModuleInput {
+    /// An input for the file at `path`, generating a struct called
+    /// `struct_name`.
+    pub fn new<P: Into<PathBuf>, S: Into<String>>(path: P, struct_name: S) -> Self {
+        ModuleInput {
+            path: path.into(),
+            struct_name: struct_name.into(),
+            const_name: None,
+        }
+    }
+
+    /// The shared `options`, with the names of this input.
+    pub(crate) fn options(&self, options: &StructOptions) -> StructOptions {
+        StructOptions {
+            struct_name: self.struct_name.clone(),
+            const_name: self.const_name.clone(),
+            ..options.clone()
+        }
+    }
+}
+
+/// Check that no two inputs would generate a struct or const with the
+/// same name.
+pub fn check_name_collisions(
+    inputs: &[ModuleInput],
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if inputs.is_empty() {
+        return Err(GenerationError::NoInputFiles);
+    }
+
+    // Each config would declare its own `generated_tests` and `keys`
+    // modules.
+    if options.generate_tests && inputs.len() > 1 {
+        return Err(GenerationError::DuplicateModuleName(
+            "generated_tests".to_owned(),
+        ));
+    }
+    if options.generate_key_constants && inputs.len() > 1 {
+        return Err(GenerationError::DuplicateModuleName("keys".to_owned()));
+    }
+
+    let mut struct_names = BTreeSet::new();
+    let mut const_names = BTreeSet::new();
+    for input in inputs {
+        let input_options = input.options(options);
+
+        if !struct_names.insert(input_options.struct_name.clone()) {
+            return Err(GenerationError::DuplicateModuleName(
+                input_options.struct_name,
+            ));
+        }
+        let generates_const = input_options.generate_const || input_options.generate_load_fns;
+        if generates_const && !const_names.insert(input_options.real_const_name()) {
+            return Err(GenerationError::DuplicateModuleName(
+                input_options.real_const_name(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distinct_names_are_allowed() {
+        let inputs = vec![
+            ModuleInput::new("graphics.toml", "Graphics"),
+            ModuleInput::new("audio.toml", "Audio"),
+        ];
+
+        assert!(check_name_collisions(&inputs, &StructOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn duplicate_names_are_errors() {
+        let inputs = vec![
+            ModuleInput::new("graphics.toml", "Graphics"),
+            ModuleInput::new("video.toml", "Graphics"),
+        ];
+        match check_name_collisions(&inputs, &StructOptions::default()) {
+            Err(GenerationError::DuplicateModuleName(name)) => assert_eq!(name, "Graphics"),
+            other => panic!("Expected duplicate name error, found {:?}", other),
+        }
+
+        let mut audio = ModuleInput::new("audio.toml", "Audio");
+        audio.const_name = Some("GRAPHICS".to_owned());
+        let inputs = vec![ModuleInput::new("graphics.toml", "Graphics"), audio];
+        match check_name_collisions(&inputs, &StructOptions::default()) {
+            Err(GenerationError::DuplicateModuleName(name)) => assert_eq!(name, "GRAPHICS"),
+            other => panic!("Expected duplicate name error, found {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+/// Convert a config key like `log_level` or `warn-only` into a
+/// `PascalCase` name suitable for a type or enum variant.
+pub fn pascal_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut capitalize_next = true;
+
+    for c in name.chars() {
+        if c == '_' || c == '-' || c == ' ' || c == '.' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            result.extend(c.to_uppercase());
+            capitalize_next = false;
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+/// Convert a config key like `dev`, `eu-west` or `stagingEu` into an
+/// `UPPER_SNAKE_CASE` name suitable for a const.
+pub fn upper_snake_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut previous: Option<char> = None;
+
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            result.push('_');
+        } else {
+            let starts_word = c.is_ascii_uppercase()
+                && previous.is_some_and(|previous| {
+                    previous.is_ascii_lowercase() || previous.is_ascii_digit()
+                });
+            if starts_word {
+                result.push('_');
+            }
+            result.push(c.to_ascii_uppercase());
+        }
+        previous = Some(c);
+    }
+
+    if result.starts_with(|c: char| c.is_ascii_digit()) {
+        result.insert(0, '_');
+    }
+    result
+}
+
+/// Whether `name` is `UpperCamelCase`, as the `non_camel_case_types`
+/// lint checks it, so that generated types only allow the lint when
+/// they need to.
+pub fn is_camel_case(name: &str) -> bool {
+    let name = name.trim_matches('_');
+    let has_case = |c: char| c.is_lowercase() || c.is_uppercase();
+    let chars = name.chars().collect::<Vec<_>>();
+
+    !chars.first().is_some_and(|c| c.is_lowercase())
+        && !name.contains("__")
+        && !chars.windows(2).any(|pair| {
+            (has_case(pair[0]) && pair[1] == '_') || (pair[0] == '_' && has_case(pair[1]))
+        })
+}
+
+/// The attribute allowing the `non_camel_case_types` lint on a type
+/// named `name`, if its name needs it.
+pub fn allow_non_camel_case(name: &str) -> &'static str {
+    if is_camel_case(name) {
+        ""
+    } else {
+        "#[allow(non_camel_case_types)]\n"
+    }
+}
+
+/// Keywords which can't be used as identifiers, but can be used as raw
+/// identifiers (like `r#type`).
+const RAW_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Keywords which can't be used as identifiers at all.
+const RESERVED_KEYWORDS: &[&str] = &["crate", "self", "Self", "super"];
+
+/// Whether `name` is a keyword which can't be used as a plain identifier.
+pub fn is_keyword(name: &str) -> bool {
+    RAW_KEYWORDS.contains(&name) || RESERVED_KEYWORDS.contains(&name)
+}
+
+/// Convert a config key into a field name, escaping keywords as raw
+/// identifiers where possible.
+pub fn field_name(key: &str) -> String {
+    if RAW_KEYWORDS.contains(&key) {
+        format!("r#{}", key)
+    } else {
+        key.to_owned()
+    }
+}
+
+/// Make a config key into a valid field name, by replacing invalid
+/// characters with underscores, putting an underscore before a leading
+/// digit, and escaping keywords.
+pub fn sanitized_field_name(key: &str) -> String {
+    let mut name = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    } else if name.is_empty() || name == "_" {
+        name = "__".to_owned();
+    }
+    if RESERVED_KEYWORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+
+    field_name(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pascal_case_names() {
+        assert_eq!(pascal_case("debug"), "Debug");
+        assert_eq!(pascal_case("log_level"), "LogLevel");
+        assert_eq!(pascal_case("warn-only"), "WarnOnly");
+        assert_eq!(pascal_case("already_Pascal"), "AlreadyPascal");
+        assert_eq!(pascal_case("__leading"), "Leading");
+        assert_eq!(pascal_case(""), "");
+    }
+
+    #[test]
+    fn upper_snake_case_names() {
+        assert_eq!(upper_snake_case("dev"), "DEV");
+        assert_eq!(upper_snake_case("eu-west"), "EU_WEST");
+        assert_eq!(upper_snake_case("stagingEu"), "STAGING_EU");
+        assert_eq!(upper_snake_case("HTTP"), "HTTP");
+        assert_eq!(upper_snake_case("2fa"), "_2FA");
+    }
+
+    #[test]
+    fn camel_case_names() {
+        assert!(is_camel_case("Config"));
+        assert!(is_camel_case("ConfigServerTls"));
+        assert!(is_camel_case("Config2"));
+        assert!(is_camel_case("_Config"));
+        assert!(!is_camel_case("_Config__server"));
+        assert!(!is_camel_case("Config_server"));
+        assert!(!is_camel_case("config"));
+        assert_eq!(allow_non_camel_case("Config"), "");
+        assert_eq!(
+            allow_non_camel_case("Config__server"),
+            "#[allow(non_camel_case_types)]\n"
+        );
+    }
+
+    #[test]
+    fn keywords_are_escaped() {
+        assert_eq!(field_name("name"), "name");
+        assert_eq!(field_name("type"), "r#type");
+        assert_eq!(field_name("match"), "r#match");
+        assert_eq!(field_name("self"), "self");
+        assert!(is_keyword("self"));
+        assert!(!is_keyword("name"));
+    }
+
+    #[test]
+    fn keys_are_sanitized() {
+        assert_eq!(sanitized_field_name("404-page"), "_404_page");
+        assert_eq!(sanitized_field_name("2fa_enabled"), "_2fa_enabled");
+        assert_eq!(sanitized_field_name("max connections"), "max_connections");
+        assert_eq!(sanitized_field_name("café"), "caf_");
+        assert_eq!(sanitized_field_name("type"), "r#type");
+        assert_eq!(sanitized_field_name("self"), "self_");
+        assert_eq!(sanitized_field_name(""), "__");
+        assert_eq!(sanitized_field_name("_"), "__");
+    }
+}
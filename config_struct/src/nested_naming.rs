@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use crate::{
+    error::GenerationError,
+    naming::{is_keyword, pascal_case},
+    options::{ArrayElementNaming, NestedNaming, StructOptions},
+    paths,
+    validation::valid_identifier,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// How the root struct is described when its name collides with a
+/// nested struct.
+const ROOT_PATH: &str = "(root)";
+
+/// The types already named, and the paths they were named for.
+#[derive(Default)]
+struct Names {
+    structs: HashMap<String, String>,
+    enums: HashMap<String, String>,
+}
+
+/// Rename the structs of nested tables according to
+/// `options.nested_naming`, and those of the elements of arrays according
+/// to `options.array_element_naming`.
+///
+/// The names generated while parsing are `Concatenated` names, so they
+/// are only replaced for the other strategies. Names which collide with
+/// each other, with an enum, or with the root struct are errors.
+pub fn apply_nested_naming(
+    config: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if options.nested_naming == NestedNaming::Concatenated
+        && options.array_element_naming == ArrayElementNaming::Nested
+    {
+        return Ok(());
+    }
+
+    let mut names = Names::default();
+    names
+        .structs
+        .insert(config.struct_name.clone(), ROOT_PATH.to_owned());
+
+    name_fields(config, "", options, &mut names)
+}
+
+fn name_fields(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+    names: &mut Names,
+) -> Result<(), GenerationError> {
+    let original_keys = struct_value.original_keys.clone();
+    for (key, value) in &mut struct_value.fields {
+        let key = original_keys.get(key).unwrap_or(key);
+        name_value(value, &paths::join(parent_path, key), options, names)?;
+    }
+    Ok(())
+}
+
+fn name_value(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+    names: &mut Names,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => {
+            // The elements of a root array are named like the root.
+            if !path.is_empty() {
+                let name = nested_name(path, &struct_value.struct_name, options)?;
+                claim_struct_name(&name, path, names)?;
+                struct_value.struct_name = name;
+            }
+            name_fields(struct_value, path, options, names)
+        }
+        GenericValue::Enum(ref enum_value) => claim_enum_name(&enum_value.enum_name, path, names),
+        GenericValue::TaggedUnion(ref mut union) => {
+            claim_enum_name(&union.enum_name, path, names)?;
+            name_fields(&mut union.value, path, options, names)
+        }
+        GenericValue::Option(Some(ref mut value)) => name_value(value, path, options, names),
+        GenericValue::Array(ref mut values) if !path.is_empty() => {
+            match element_name(path, options)? {
+                Some(name) => name_elements(values, &name, path, options, names),
+                None => {
+                    for value in values {
+                        name_value(value, path, options, names)?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                name_value(value, path, options, names)?;
+            }
+            Ok(())
+        }
+        GenericValue::Tuple(ref mut values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                name_value(
+                    value,
+                    &paths::join(path, &index.to_string()),
+                    options,
+                    names,
+                )?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn nested_name(
+    path: &str,
+    current_name: &str,
+    options: &StructOptions,
+) -> Result<String, GenerationError> {
+    let name = match options.nested_naming {
+        NestedNaming::Concatenated => current_name.to_owned(),
+        NestedNaming::PascalCase => pascal_case(path),
+        NestedNaming::Explicit(ref names) => names
+            .get(path)
+            .cloned()
+            .unwrap_or_else(|| current_name.to_owned()),
+    };
+
+    if !valid_identifier(&name) || is_keyword(&name) {
+        return Err(GenerationError::InvalidNestedStructName(
+            path.to_owned(),
+            name,
+        ));
+    }
+    Ok(name)
+}
+
+/// Give the structs among the elements of an array, and of any arrays
+/// nested in it, the same `name`.
+fn name_elements(
+    values: &mut [GenericValue],
+    name: &str,
+    path: &str,
+    options: &StructOptions,
+    names: &mut Names,
+) -> Result<(), GenerationError> {
+    for value in values {
+        match *value {
+            GenericValue::Struct(ref mut struct_value) => {
+                claim_struct_name(name, path, names)?;
+                struct_value.struct_name = name.to_owned();
+                name_fields(struct_value, path, options, names)?;
+            }
+            GenericValue::Option(Some(ref mut value)) => name_elements(
+                std::slice::from_mut(&mut **value),
+                name,
+                path,
+                options,
+                names,
+            )?,
+            GenericValue::Array(ref mut values) => {
+                name_elements(values, name, path, options, names)?
+            }
+            _ => name_value(value, path, options, names)?,
+        }
+    }
+    Ok(())
+}
+
+/// The name for the structs among the elements of the array at `path`,
+/// if `options.array_element_naming` gives them one.
+fn element_name(path: &str, options: &StructOptions) -> Result<Option<String>, GenerationError> {
+    let singulars = match options.array_element_naming {
+        ArrayElementNaming::Nested => return Ok(None),
+        ArrayElementNaming::Singular(ref singulars) => singulars,
+    };
+
+    let (parent_path, key) = match path.rfind('.') {
+        Some(index) => (&path[..index], &path[index + 1..]),
+        None => ("", path),
+    };
+    let singular = match singulars.get(key) {
+        Some(singular) => singular.as_str(),
+        None => key
+            .strip_suffix('s')
+            .filter(|key| !key.is_empty())
+            .unwrap_or(key),
+    };
+    let name = format!(
+        "{}{}",
+        options.struct_name,
+        pascal_case(&paths::join(parent_path, singular))
+    );
+
+    if !valid_identifier(&name) || is_keyword(&name) {
+        return Err(GenerationError::InvalidNestedStructName(
+            path.to_owned(),
+            name,
+        ));
+    }
+    Ok(Some(name))
+}
+
+/// Record the name of the struct for a path. The structs for the
+/// elements of an array share a path, so they can share a name.
+fn claim_struct_name(name: &str, path: &str, names: &mut Names) -> Result<(), GenerationError> {
+    let other_path = names
+        .structs
+        .get(name)
+        .or_else(|| names.enums.get(name))
+        .filter(|other_path| *other_path != path);
+
+    if let Some(other_path) = other_path {
+        return Err(GenerationError::ConflictingStructNames(
+            name.to_owned(),
+            other_path.clone(),
+            path.to_owned(),
+        ));
+    }
+
+    names.structs.insert(name.to_owned(), path.to_owned());
+    Ok(())
+}
+
+fn claim_enum_name(enum_name: &str, path: &str, names: &mut Names) -> Result<(), GenerationError> {
+    if let Some(other_path) = names.structs.get(enum_name) {
+        return Err(GenerationError::ConflictingStructNames(
+            enum_name.to_owned(),
+            other_path.clone(),
+            path.to_owned(),
+        ));
+    }
+    names
+        .enums
+        .entry(enum_name.to_owned())
+        .or_insert_with(|| path.to_owned());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(name: &str, fields: Vec<(&str, GenericValue)>) -> GenericValue {
+        GenericValue::Struct(GenericStruct {
+            struct_name: name.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        })
+    }
+
+    fn config(fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        match table("Config", fields) {
+            GenericValue::Struct(config) => config,
+            _ => unreachable!(),
+        }
+    }
+
+    fn struct_name(value: &GenericValue) -> &str {
+        match *value {
+            GenericValue::Struct(ref struct_value) => &struct_value.struct_name,
+            ref other => panic!("Expected struct, found {:?}", other),
+        }
+    }
+
+    fn options(nested_naming: NestedNaming) -> StructOptions {
+        StructOptions {
+            nested_naming,
+            ..StructOptions::default()
+        }
+    }
+
+    #[test]
+    fn pascal_case_names() {
+        let tls = table(
+            "_Config__server__tls",
+            vec![("cert", GenericValue::Bool(true))],
+        );
+        let mut config = config(vec![(
+            "server",
+            table("_Config__server", vec![("tls", tls)]),
+        )]);
+
+        apply_nested_naming(&mut config, &options(NestedNaming::PascalCase)).unwrap();
+
+        let server = &config.fields["server"];
+        assert_eq!(struct_name(server), "Server");
+        match *server {
+            GenericValue::Struct(ref server) => {
+                assert_eq!(struct_name(&server.fields["tls"]), "ServerTls")
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn explicit_names() {
+        let names = vec![("server".to_owned(), "ServerConfig".to_owned())]
+            .into_iter()
+            .collect();
+        let mut config = config(vec![
+            ("server", table("_Config__server", vec![])),
+            ("client", table("_Config__client", vec![])),
+        ]);
+
+        apply_nested_naming(&mut config, &options(NestedNaming::Explicit(names))).unwrap();
+
+        assert_eq!(struct_name(&config.fields["server"]), "ServerConfig");
+        assert_eq!(struct_name(&config.fields["client"]), "_Config__client");
+    }
+
+    #[test]
+    fn collisions_are_errors() {
+        let tls = table("_Config__server__tls", vec![]);
+        let mut config = config(vec![
+            ("server", table("_Config__server", vec![("tls", tls)])),
+            ("server_tls", table("_Config__server_tls", vec![])),
+        ]);
+
+        match apply_nested_naming(&mut config, &options(NestedNaming::PascalCase)) {
+            Err(GenerationError::ConflictingStructNames(name, first, second)) => {
+                assert_eq!(name, "ServerTls");
+                assert_eq!(first, "server.tls");
+                assert_eq!(second, "server_tls");
+            }
+            other => panic!("Expected conflicting names error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn array_elements_share_a_name() {
+        let mut config = config(vec![(
+            "servers",
+            GenericValue::Array(vec![
+                table("_Config__servers", vec![]),
+                table("_Config__servers", vec![]),
+            ]),
+        )]);
+
+        apply_nested_naming(&mut config, &options(NestedNaming::PascalCase)).unwrap();
+    }
+
+    #[test]
+    fn root_collisions_are_errors() {
+        let mut config = config(vec![("config", table("_Config__config", vec![]))]);
+
+        match apply_nested_naming(&mut config, &options(NestedNaming::PascalCase)) {
+            Err(GenerationError::ConflictingStructNames(name, first, second)) => {
+                assert_eq!(name, "Config");
+                assert_eq!(first, ROOT_PATH);
+                assert_eq!(second, "config");
+            }
+            other => panic!("Expected conflicting names error, found {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    error::{GenerationError, NewtypeConflict},
+    fixed_arrays::ArrayEmitContext,
+    generation::{derive_string, type_string, value_string},
+    int_types::integer_value,
+    interop::CLAP_PARSER,
+    naming::is_keyword,
+    options::{SerdeSupport, StructOptions},
+    paths, serde_impls,
+    validation::valid_identifier,
+    value::{GenericMap, GenericNewtype, GenericStruct, GenericValue},
+};
+
+/// Wrap the numbers at the paths listed in `options.newtype_paths` in
+/// their newtypes, checking that fields sharing a newtype hold the same
+/// type of number.
+pub fn apply_newtype_paths(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if options.newtype_paths.is_empty() {
+        return Ok(());
+    }
+
+    for (path, name) in &options.newtype_paths {
+        if !valid_identifier(name) || is_keyword(name) {
+            return Err(GenerationError::InvalidNewtypeName(
+                path.clone(),
+                name.clone(),
+            ));
+        }
+    }
+
+    // The type each newtype wraps, and the path it was first found at.
+    let mut inner_types = BTreeMap::new();
+    apply_to_struct(struct_value, "", options, &mut inner_types)
+}
+
+fn apply_to_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+    inner_types: &mut BTreeMap<String, (String, String)>,
+) -> Result<(), GenerationError> {
+    let original_keys = struct_value.original_keys.clone();
+    for (key, value) in &mut struct_value.fields {
+        let key = original_keys.get(key).unwrap_or(key);
+        let path = paths::join(parent_path, key);
+
+        match options.newtype_paths.get(&path) {
+            Some(name) => convert_to_newtype(value, &path, name, options, inner_types)?,
+            None => apply_to_value(value, &path, options, inner_types)?,
+        }
+    }
+    Ok(())
+}
+
+fn apply_to_value(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+    inner_types: &mut BTreeMap<String, (String, String)>,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => {
+            apply_to_struct(struct_value, path, options, inner_types)
+        }
+        GenericValue::Option(Some(ref mut value)) => {
+            apply_to_value(value, path, options, inner_types)
+        }
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                apply_to_value(value, path, options, inner_types)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn convert_to_newtype(
+    value: &mut GenericValue,
+    path: &str,
+    name: &str,
+    options: &StructOptions,
+    inner_types: &mut BTreeMap<String, (String, String)>,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Option(None) => return Ok(()),
+        GenericValue::Option(Some(ref mut value)) => {
+            return convert_to_newtype(value, path, name, options, inner_types)
+        }
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                convert_to_newtype(value, path, name, options, inner_types)?;
+            }
+            return Ok(());
+        }
+        ref number if is_number(number) => (),
+        ref other => {
+            return Err(GenerationError::InvalidNewtypeField(
+                path.into(),
+                value_string(other, ArrayEmitContext::new(options, 0), options),
+            ));
+        }
+    }
+
+    let inner_type = type_string(value, options);
+    match inner_types.get(name) {
+        Some((other_type, other_path)) if *other_type != inner_type => {
+            return Err(GenerationError::ConflictingNewtypes(Box::new(
+                NewtypeConflict {
+                    name: name.into(),
+                    first_type: other_type.clone(),
+                    first_path: other_path.clone(),
+                    second_type: inner_type,
+                    second_path: path.into(),
+                },
+            )));
+        }
+        Some(_) => (),
+        None => {
+            inner_types.insert(name.to_owned(), (inner_type, path.to_owned()));
+        }
+    }
+
+    let inner = std::mem::replace(value, GenericValue::Unit);
+    *value = GenericValue::Newtype(GenericNewtype {
+        type_name: name.to_owned(),
+        value: Box::new(inner),
+    });
+    Ok(())
+}
+
+fn is_number(value: &GenericValue) -> bool {
+    is_integer(value) || matches!(*value, GenericValue::F32(_) | GenericValue::F64(_))
+}
+
+fn is_integer(value: &GenericValue) -> bool {
+    integer_value(value).is_some() || matches!(*value, GenericValue::U128(_))
+}
+
+/// Collect the newtypes used in a config, each with a value, by name.
+pub fn collect_newtypes<'a>(
+    struct_value: &'a GenericStruct,
+    newtypes: &mut BTreeMap<String, &'a GenericValue>,
+) {
+    fn collect_from_value<'a>(
+        value: &'a GenericValue,
+        newtypes: &mut BTreeMap<String, &'a GenericValue>,
+    ) {
+        match *value {
+            GenericValue::Newtype(ref newtype) => {
+                newtypes.insert(newtype.type_name.clone(), &newtype.value);
+            }
+            GenericValue::Struct(ref struct_value) => collect_newtypes(struct_value, newtypes),
+            GenericValue::Option(Some(ref value)) => collect_from_value(value, newtypes),
+            GenericValue::Array(ref values)
+            | GenericValue::Tuple(ref values)
+            | GenericValue::Map(GenericMap { ref values, .. }) => {
+                for value in values {
+                    collect_from_value(value, newtypes);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    for value in struct_value.fields.values() {
+        collect_from_value(value, newtypes);
+    }
+}
+
+/// Declare each newtype used in a config, once.
+pub fn generate_newtypes(struct_value: &GenericStruct, options: &StructOptions) -> String {
+    let mut newtypes = BTreeMap::new();
+    collect_newtypes(struct_value, &mut newtypes);
+
+    let mut code = String::new();
+    for (name, inner) in newtypes {
+        code.push_str(&newtype_declaration(&name, inner, options));
+    }
+    code
+}
+
+fn newtype_declaration(name: &str, inner: &GenericValue, options: &StructOptions) -> String {
+    // Newtypes must support everything the structs holding them derive,
+    // and whatever else their number does. Floats have no total order.
+    let integer = is_integer(inner);
+    let mut derived_traits = vec![
+        "Debug",
+        "Clone",
+        "Copy",
+        "Default",
+        "PartialEq",
+        "PartialOrd",
+    ];
+    if integer {
+        derived_traits.extend(vec!["Eq", "Ord", "Hash"]);
+    }
+    let mut derived_traits = derived_traits
+        .into_iter()
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    for name in &options.derived_traits {
+        let total_order = matches!(name.as_str(), "Eq" | "Ord" | "Hash");
+        if !derived_traits.contains(name) && (integer || !total_order) && name != CLAP_PARSER {
+            derived_traits.push(name.clone());
+        }
+    }
+
+    let transparent = if options.serde_support.derived_ser_de().is_some() {
+        "#[serde(transparent)]\n"
+    } else {
+        ""
+    };
+    let mut code = format!(
+        "{}{}pub struct {}(pub {});\n\n",
+        derive_string(derived_traits, options),
+        transparent,
+        name,
+        type_string(inner, options)
+    );
+    if options.serde_support == SerdeSupport::ManualImpl {
+        code.push_str(&serde_impls::generate_newtype_impls(name, options));
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with(fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    fn options_with(paths: Vec<(&str, &str)>) -> StructOptions {
+        StructOptions {
+            newtype_paths: paths
+                .into_iter()
+                .map(|(path, name)| (path.to_owned(), name.to_owned()))
+                .collect(),
+            ..StructOptions::default()
+        }
+    }
+
+    #[test]
+    fn numbers_are_wrapped() {
+        let mut config = config_with(vec![
+            ("timeout_ms", GenericValue::I64(500)),
+            ("ratio", GenericValue::F64(0.5)),
+        ]);
+        let options = options_with(vec![("timeout_ms", "TimeoutMs"), ("ratio", "Ratio")]);
+
+        apply_newtype_paths(&mut config, &options).unwrap();
+
+        assert_eq!(
+            value_string(
+                &config.fields["timeout_ms"],
+                ArrayEmitContext::new(&options, 0),
+                &options
+            ),
+            "TimeoutMs(500)"
+        );
+        assert_eq!(type_string(&config.fields["ratio"], &options), "Ratio");
+
+        let code = generate_newtypes(&config, &options);
+        assert!(code.contains(
+            "#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]\npub struct Ratio(pub f64);"
+        ));
+        assert!(code.contains(
+            "#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]\npub struct TimeoutMs(pub i64);"
+        ));
+    }
+
+    #[test]
+    fn shared_newtypes_need_the_same_type() {
+        let options = options_with(vec![("read_ms", "Millis"), ("write_ms", "Millis")]);
+
+        let mut config = config_with(vec![
+            ("read_ms", GenericValue::I64(10)),
+            ("write_ms", GenericValue::I64(20)),
+        ]);
+        apply_newtype_paths(&mut config, &options).unwrap();
+        assert_eq!(
+            generate_newtypes(&config, &options)
+                .matches("pub struct Millis")
+                .count(),
+            1
+        );
+
+        let mut config = config_with(vec![
+            ("read_ms", GenericValue::I64(10)),
+            ("write_ms", GenericValue::F64(2.5)),
+        ]);
+        match apply_newtype_paths(&mut config, &options) {
+            Err(GenerationError::ConflictingNewtypes(conflict)) => {
+                assert_eq!(conflict.name, "Millis")
+            }
+            other => panic!("Expected conflicting newtypes, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn non_numbers_are_an_error() {
+        let mut config = config_with(vec![("name", GenericValue::String("app".to_owned()))]);
+        let options = options_with(vec![("name", "Name")]);
+
+        assert!(matches!(
+            apply_newtype_paths(&mut config, &options),
+            Err(GenerationError::InvalidNewtypeField(..))
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+//! Handling `null` values from JSON and YAML configs, according to
+//! `StructOptions::null_values`.
+//!
+//! Other formats have no nulls: RON's `None` is an explicit option, and
+//! is left alone.
+use crate::{
+    error::GenerationError,
+    options::{NullBehavior, StructOptions},
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// The value a `null` is parsed into.
+pub fn null_value(options: &StructOptions) -> GenericValue {
+    match options.null_values {
+        NullBehavior::Unit => GenericValue::Unit,
+        NullBehavior::Option | NullBehavior::Error => GenericValue::Option(None),
+    }
+}
+
+/// Reject any nulls, or make the other elements of arrays containing
+/// nulls optional, depending on `options.null_values`.
+pub fn apply_null_behavior(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    match options.null_values {
+        NullBehavior::Unit => Ok(()),
+        NullBehavior::Option => {
+            optional_struct(struct_value);
+            Ok(())
+        }
+        NullBehavior::Error => match null_path(struct_value, "") {
+            Some(path) => Err(GenerationError::NullValue(path)),
+            None => Ok(()),
+        },
+    }
+}
+
+fn null_path(struct_value: &GenericStruct, parent_path: &str) -> Option<String> {
+    struct_value.fields.iter().find_map(|(key, value)| {
+        let path = paths::join(parent_path, key);
+        value_null_path(value, path)
+    })
+}
+
+fn value_null_path(value: &GenericValue, path: String) -> Option<String> {
+    match *value {
+        GenericValue::Option(None) => Some(path),
+        GenericValue::Struct(ref struct_value) => null_path(struct_value, &path),
+        GenericValue::Array(ref values) | GenericValue::Map(GenericMap { ref values, .. }) => {
+            values
+                .iter()
+                .find_map(|value| value_null_path(value, path.clone()))
+        }
+        _ => None,
+    }
+}
+
+fn optional_struct(struct_value: &mut GenericStruct) {
+    for value in struct_value.fields.values_mut() {
+        optional_value(value);
+    }
+}
+
+/// Wrap the other elements of any array which contains a null in
+/// `Some`, so that the null becomes a `None` of their type.
+fn optional_value(value: &mut GenericValue) {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => optional_struct(struct_value),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values.iter_mut() {
+                optional_value(value);
+            }
+
+            let has_null = values
+                .iter()
+                .any(|value| matches!(*value, GenericValue::Option(None)));
+            if has_null {
+                for value in values.iter_mut() {
+                    if !matches!(*value, GenericValue::Option(_)) {
+                        let inner = std::mem::replace(value, GenericValue::Unit);
+                        *value = GenericValue::Option(Some(Box::new(inner)));
+                    }
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(value: GenericValue) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![("ports".to_owned(), value)].into_iter().collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    fn options(null_values: NullBehavior) -> StructOptions {
+        StructOptions {
+            null_values,
+            ..StructOptions::default()
+        }
+    }
+
+    #[test]
+    fn array_elements_become_optional() {
+        let mut config = config(GenericValue::Array(vec![
+            GenericValue::I64(80),
+            GenericValue::Option(None),
+        ]));
+        apply_null_behavior(&mut config, &options(NullBehavior::Option)).unwrap();
+
+        assert_eq!(
+            format!("{:?}", config.fields["ports"]),
+            format!(
+                "{:?}",
+                GenericValue::Array(vec![
+                    GenericValue::Option(Some(Box::new(GenericValue::I64(80)))),
+                    GenericValue::Option(None),
+                ])
+            )
+        );
+    }
+
+    #[test]
+    fn nulls_can_be_errors() {
+        let mut config = config(GenericValue::Array(vec![GenericValue::Option(None)]));
+
+        match apply_null_behavior(&mut config, &options(NullBehavior::Error)) {
+            Err(GenerationError::NullValue(path)) => assert_eq!(path, "ports"),
+            other => panic!("Expected a null value error, found {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! Typing options which are `None` wherever they appear, with
+//! `StructOptions::option_types` and `StructOptions::default_option_type`.
+//!
+//! A `None` says nothing about the type it would hold. Options which are
+//! `None` everywhere (in every element of an array of structs, for
+//! example) are given the type from the options, and the rest are
+//! reported, or rejected, according to `untyped_options`.
+use std::collections::BTreeSet;
+
+use crate::{
+    diagnostics::{Warning, WarningKind},
+    error::GenerationError,
+    options::{StructOptions, UntypedOptionBehavior},
+    paths,
+    unification::{array_structs, array_structs_mut, struct_template},
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Give each option which is always `None` the type configured for its
+/// path, and report any which have none.
+pub fn apply_option_types(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), GenerationError> {
+    let mut none_paths = BTreeSet::new();
+    collect_nones(struct_value, "", &mut none_paths);
+
+    let mut typed_paths = BTreeSet::new();
+    for path in none_paths {
+        match (inner_type(&path, options), options.untyped_options) {
+            (Some(_), _) => {
+                typed_paths.insert(path);
+            }
+            (None, UntypedOptionBehavior::Warn) => {
+                let message = format!(
+                    "`{}` is always `None`, so it is given the type `Option<()>`. \
+                     Give it a type in `option_types` to fix.",
+                    path
+                );
+                warnings.push(Warning::new(WarningKind::UntypedOption, path, message));
+            }
+            (None, UntypedOptionBehavior::Error) => {
+                return Err(GenerationError::UntypedOption(path));
+            }
+        }
+    }
+
+    if !typed_paths.is_empty() {
+        type_struct(struct_value, "", &typed_paths, options);
+    }
+
+    Ok(())
+}
+
+/// The inner type given for the option at a path, if any.
+fn inner_type<'a>(path: &str, options: &'a StructOptions) -> Option<&'a String> {
+    options
+        .option_types
+        .get(path)
+        .or(options.default_option_type.as_ref())
+}
+
+/// Collect the paths of the options which are `None` wherever they
+/// appear.
+///
+/// For arrays of structs, only the fields which are `None` in every
+/// element count, since the others get their type from the rest.
+fn collect_nones(struct_value: &GenericStruct, parent_path: &str, paths: &mut BTreeSet<String>) {
+    for (key, value) in &struct_value.fields {
+        let path = paths::join(parent_path, struct_value.original_key(key));
+        collect_value_nones(value, &path, paths);
+    }
+}
+
+fn collect_value_nones(value: &GenericValue, path: &str, paths: &mut BTreeSet<String>) {
+    match *value {
+        GenericValue::Option(None) => {
+            paths.insert(path.to_owned());
+        }
+        GenericValue::Struct(ref struct_value) => collect_nones(struct_value, path, paths),
+        GenericValue::Option(Some(ref value)) => collect_value_nones(value, path, paths),
+        GenericValue::Array(ref values) | GenericValue::Map(GenericMap { ref values, .. }) => {
+            let structs = array_structs(values);
+            if !structs.is_empty() {
+                collect_nones(&struct_template(&structs), path, paths);
+            } else if let Some(value) = GenericValue::typed_element(values) {
+                collect_value_nones(value, path, paths);
+            }
+        }
+        GenericValue::Tuple(ref values) => {
+            for value in values {
+                collect_value_nones(value, path, paths);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn type_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    typed_paths: &BTreeSet<String>,
+    options: &StructOptions,
+) {
+    let original_keys = struct_value.original_keys.clone();
+    for (key, value) in &mut struct_value.fields {
+        let key = original_keys.get(key).unwrap_or(key);
+        let path = paths::join(parent_path, key);
+        type_value(value, &path, typed_paths, options);
+    }
+}
+
+fn type_value(
+    value: &mut GenericValue,
+    path: &str,
+    typed_paths: &BTreeSet<String>,
+    options: &StructOptions,
+) {
+    match *value {
+        GenericValue::Option(None) if typed_paths.contains(path) => {
+            let inner_type = inner_type(path, options).expect("typed paths have a type");
+            *value = GenericValue::NoneOf(inner_type.clone());
+        }
+        GenericValue::Struct(ref mut struct_value) => {
+            type_struct(struct_value, path, typed_paths, options)
+        }
+        GenericValue::Option(Some(ref mut value)) => type_value(value, path, typed_paths, options),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            let mut structs = array_structs_mut(values);
+            if !structs.is_empty() {
+                for struct_value in &mut structs {
+                    type_struct(struct_value, path, typed_paths, options);
+                }
+            } else {
+                for value in values {
+                    type_value(value, path, typed_paths, options);
+                }
+            }
+        }
+        GenericValue::Tuple(ref mut values) => {
+            for value in values {
+                type_value(value, path, typed_paths, options);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(port: GenericValue) -> GenericValue {
+        GenericValue::Struct(GenericStruct {
+            struct_name: "_Config__servers".to_owned(),
+            fields: vec![("port".to_owned(), port)].into_iter().collect(),
+            ..GenericStruct::default()
+        })
+    }
+
+    fn config() -> GenericStruct {
+        let none = || GenericValue::Option(None);
+        let some = |port| GenericValue::Option(Some(Box::new(GenericValue::I64(port))));
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![
+                ("nothing".to_owned(), none()),
+                (
+                    "servers".to_owned(),
+                    GenericValue::Array(vec![element(none()), element(none())]),
+                ),
+                (
+                    "mixed".to_owned(),
+                    GenericValue::Array(vec![element(none()), element(some(80))]),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    #[test]
+    fn options_always_none_are_reported_by_path() {
+        let mut config = config();
+        let mut warnings = Vec::new();
+        apply_option_types(&mut config, &StructOptions::default(), &mut warnings).unwrap();
+
+        let paths = warnings
+            .iter()
+            .map(|warning| (warning.kind, warning.path.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                (WarningKind::UntypedOption, "nothing"),
+                (WarningKind::UntypedOption, "servers.port"),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_options_none_everywhere_are_typed() {
+        let mut config = config();
+        let options = StructOptions {
+            option_types: vec![("servers.port".to_owned(), "u16".to_owned())]
+                .into_iter()
+                .collect(),
+            untyped_options: UntypedOptionBehavior::Error,
+            ..StructOptions::default()
+        };
+
+        match apply_option_types(&mut config, &options, &mut Vec::new()) {
+            Err(GenerationError::UntypedOption(path)) => assert_eq!(path, "nothing"),
+            other => panic!("Expected an untyped option error, found {:?}", other),
+        }
+
+        let options = StructOptions {
+            default_option_type: Some("bool".to_owned()),
+            ..options
+        };
+        apply_option_types(&mut config, &options, &mut Vec::new()).unwrap();
+
+        let port = |field: &str, index: usize| match config.fields[field] {
+            GenericValue::Array(ref values) => match values[index] {
+                GenericValue::Struct(ref element) => element.fields["port"].clone(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        assert!(matches!(config.fields["nothing"], GenericValue::NoneOf(ref t) if t == "bool"));
+        assert!(matches!(port("servers", 1), GenericValue::NoneOf(ref t) if t == "u16"));
+        assert!(matches!(port("mixed", 0), GenericValue::Option(None)));
+    }
+}
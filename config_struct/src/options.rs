@@ -1,9 +1,16 @@
-use crate::{error::OptionsError, validation};
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    cfg_sections, display, error::OptionsError, fixed_arrays::ArrayEmitContext, format::Format,
+    generated_tests, interop, load_fns, naming, options_builder::StructOptionsBuilder, paths,
+    validation,
+};
 
 /// Options for serde support.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
 pub enum SerdeSupport {
     /// Do not derive any serde traits for the struct.
+    #[default]
     No,
 
     /// Derive `Serialize` and `Deserialize` for the struct.
@@ -12,13 +19,32 @@ pub enum SerdeSupport {
     /// Derive any combination of `Serialize` and `Deserialize`
     /// for the struct.
     Mixed { serialize: bool, deserialize: bool },
+
+    /// Derive only `Serialize` for the struct.
+    SerializeOnly,
+
+    /// Derive only `Deserialize` for the struct.
+    DeserializeOnly,
+
+    /// Implement `Serialize` and `Deserialize` in the generated code,
+    /// rather than deriving them, so that `serde` is needed without its
+    /// `derive` feature.
+    ///
+    /// The impls behave like the derived ones, except that unknown keys
+    /// are always ignored, so this can't be used with `serde_attributes`
+    /// or `FlattenStyle::SerdeFlatten`.
+    ManualImpl,
 }
 
 impl SerdeSupport {
+    /// Which of `Serialize` and `Deserialize` are implemented, whether
+    /// they're derived or not.
     pub(crate) fn should_derive_ser_de(self) -> Option<(bool, bool)> {
         match self {
             Self::No => None,
-            Self::Yes => Some((true, true)),
+            Self::Yes | Self::ManualImpl => Some((true, true)),
+            Self::SerializeOnly => Some((true, false)),
+            Self::DeserializeOnly => Some((false, true)),
             Self::Mixed {
                 serialize,
                 deserialize,
@@ -31,32 +57,760 @@ impl SerdeSupport {
             }
         }
     }
+
+    /// Which of `Serialize` and `Deserialize` are derived, rather than
+    /// implemented in the generated code.
+    pub(crate) fn derived_ser_de(self) -> Option<(bool, bool)> {
+        match self {
+            Self::ManualImpl => None,
+            _ => self.should_derive_ser_de(),
+        }
+    }
+}
+
+/// Serde container attributes to add to the generated structs, such as
+/// `deny_unknown_fields` or `rename_all = "kebab-case"`.
+///
+/// Each attribute is written as it would appear inside `#[serde(...)]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SerdeAttributes {
+    /// Attributes for every generated struct.
+    pub all_structs: Vec<String>,
+
+    /// Attributes for specific structs, indexed by the generated struct
+    /// name (for example `"Config"` or `"_Config__server"`).
+    pub by_struct: HashMap<String, Vec<String>>,
+}
+
+impl SerdeAttributes {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.all_structs.is_empty() && self.by_struct.values().all(Vec::is_empty)
+    }
+
+    pub(crate) fn for_struct(&self, struct_name: &str) -> Vec<&str> {
+        let specific = self.by_struct.get(struct_name).into_iter().flatten();
+        self.all_structs
+            .iter()
+            .chain(specific)
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// The type used to represent strings and arrays in the generated
+/// struct.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StringType {
+    /// Use `Cow<'static, str>` for strings and `Cow<'static, [T]>` for
+    /// arrays. This allows the struct to be either a compile time
+    /// constant, or loaded at runtime.
+    #[default]
+    Cow,
+
+    /// Use `&'static str` for strings and `&'static [T]` for arrays.
+    ///
+    /// This avoids the `Cow` wrapper, but cannot be used with dynamic
+    /// loading or `Deserialize`, as a config loaded at runtime can't
+    /// produce static references.
+    StaticStr,
+
+    /// Use `String` for strings and `Vec<T>` for arrays.
+    ///
+    /// These types can't be used in a `const`, so this requires
+    /// `generate_const` to be `false`, and the load functions (if any)
+    /// to always load dynamically.
+    String,
+}
+
+/// The type used to represent date and time values, like TOML's
+/// `released = 2023-05-01T10:00:00Z`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DateTimeType {
+    /// Use the same type as other strings (see `string_type`), holding
+    /// the date or time as written in the config file.
+    #[default]
+    String,
+
+    /// Use `chrono` types, constructed in the generated `const`:
+    ///
+    /// - `chrono::DateTime<chrono::Utc>` for date-times with an offset,
+    ///   converted to UTC.
+    /// - `chrono::NaiveDateTime` for local date-times.
+    /// - `chrono::NaiveDate` for local dates.
+    /// - `chrono::NaiveTime` for local times.
+    ///
+    /// The crate using the generated code needs a dependency on `chrono`
+    /// 0.4.35 or later.
+    #[cfg(feature = "chrono")]
+    ChronoDateTime,
+}
+
+/// The type used to represent the semantic versions listed in
+/// `semver_paths`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VersionType {
+    /// Generate a struct for versions, named after the config with
+    /// `Version` appended (like `ConfigVersion`), with `major`, `minor`
+    /// and `patch` numbers, and `pre` and `build` strings. It orders
+    /// versions by semver precedence, and implements `Display` and
+    /// `FromStr`, so that versions known at runtime can be compared with
+    /// it. With `serde_support`, it's serialized as a string.
+    #[default]
+    Struct,
+
+    /// Use `semver::Version`. Versions without pre-release or build
+    /// identifiers are built in a const, and others need
+    /// `const_fallback`.
+    ///
+    /// The crate using the generated code needs a dependency on `semver`
+    /// 1.0, with its `serde` feature if `serde_support` is used.
+    #[cfg(feature = "semver")]
+    Semver,
+}
+
+/// What to do when the keys of a table listed in `index_map_paths` skip
+/// an index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndexGapBehavior {
+    /// Fail generation with an error naming the missing index.
+    #[default]
+    Error,
+
+    /// Ignore the gap, keeping the values in the order of their keys.
+    Compact,
+}
+
+/// How the tables listed in `flatten_paths` are flattened into their
+/// parents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FlattenStyle {
+    /// Replace the table with a field in its parent for each of its
+    /// fields, named by joining the table's name and the field's with
+    /// `flatten_joiner`, like `limits_max`. The table's struct isn't
+    /// declared.
+    #[default]
+    Fields,
+
+    /// Keep the table's struct, but mark its field with
+    /// `#[serde(flatten)]`, so that its fields are (de)serialized as
+    /// though they were in the parent. This requires serde support.
+    SerdeFlatten,
+}
+
+/// The type used to represent the tables listed in `map_paths`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MapType {
+    /// Use a slice of key-value pairs, like
+    /// `Cow<'static, [(Cow<'static, str>, V)]>`, following `string_type`.
+    ///
+    /// This can be used in a const, but can't be deserialized from a
+    /// map, so cannot be used with dynamic loading.
+    #[default]
+    Slice,
+
+    /// Use a `std::collections::HashMap`, with keys following
+    /// `string_type`.
+    ///
+    /// A `HashMap` can't be built in a const, so if the config contains
+    /// any maps, it is generated according to `const_fallback` instead.
+    HashMap,
+}
+
+/// What to generate in place of the const, when the config contains
+/// values which can't be built in a const context (such as a `HashMap`).
+///
+/// Configs without such values are always generated as a const.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConstFallback {
+    /// Fail to generate the config.
+    Error,
+
+    /// Generate a `static` of type `std::sync::LazyLock<Config>`, which
+    /// is built on first use. This requires Rust 1.80 or later.
+    #[default]
+    LazyStatic,
+
+    /// Generate a function, like `pub fn config() -> &'static Config`,
+    /// which builds the config on its first call. The function is named
+    /// after the const, in lowercase. This requires Rust 1.70 or later.
+    RuntimeFn,
+}
+
+/// How the root of the config is generated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RootMode {
+    /// Generate a struct for the whole config, and a single const.
+    #[default]
+    Wrapper,
+
+    /// Generate one const for each top-level table, all of the same
+    /// struct, and no struct for the root. Every top-level value must be
+    /// a table, and the tables must have the same shape.
+    ///
+    /// Each const is named after its key in upper snake case, followed by
+    /// the const name, so `[dev]` becomes `DEV_CONFIG` by default. Paths
+    /// in other options are relative to each table, as if it were the
+    /// root.
+    ConstPerTopLevelTable,
+}
+
+/// How to handle keys which appear more than once in the same mapping of
+/// a JSON, JSON5 or YAML config, or the same section of an INI file.
+///
+/// TOML doesn't allow duplicate keys, so they are always an error there.
+/// Errors for JSON5 don't give the lines of the keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeyBehavior {
+    /// Fail generation with an error naming the key, and the lines it
+    /// appears on where they are known.
+    #[default]
+    Error,
+
+    /// Collect the values into an array, in the order they appear. Only
+    /// INI files support this; other formats treat it like `Error`.
+    Array,
+
+    /// Use the value from the first occurrence of the key.
+    FirstWins,
+
+    /// Use the value from the last occurrence of the key.
+    LastWins,
+}
+
+/// How to read the type of an unquoted scalar in a YAML config, like
+/// `3.0`, `0o777` or `nan`.
+///
+/// Quoted scalars are always strings, whatever they look like.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YamlScalarStyle {
+    /// Read scalars as `serde_yaml` does. Besides the YAML 1.2 forms,
+    /// this reads anything Rust can parse as a float as one, so the
+    /// words `inf` and `nan` are floats, and only lowercase `true`,
+    /// `false` and `null` are booleans and null.
+    #[default]
+    Yaml11,
+
+    /// Read scalars by the YAML 1.2 core schema: `null`, `true` and
+    /// `false` in any of their three capitalizations, decimal, octal
+    /// (`0o`) and hexadecimal (`0x`) integers, decimal floats, and
+    /// `.inf` and `.nan`. Anything else is a string.
+    CoreSchema,
+
+    /// Like `CoreSchema`, but where the values in the same place in an
+    /// array, like the elements of `["1.0", 1.1]`, mix quoted strings
+    /// which look like other types with unquoted values of those types,
+    /// the unquoted values are kept as strings too.
+    StringsWhenQuotedAmbiguity,
+}
+
+/// Options for the parsers of particular formats. Each format only reads
+/// its own options.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub yaml: YamlOptions,
+    pub json: JsonOptions,
+    pub toml: TomlOptions,
+}
+
+/// Options for parsing YAML configs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct YamlOptions {
+    /// Which document of a file with several, separated by `---`, the
+    /// config is generated from.
+    ///
+    /// The load functions still read the file with `serde_yaml`, which
+    /// only reads files with a single document.
+    ///
+    /// Defaults to `DocumentSelector::First`.
+    pub document: DocumentSelector,
+}
+
+/// Which documents of a YAML file with several a config is generated
+/// from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DocumentSelector {
+    /// The first document.
+    #[default]
+    First,
+
+    /// The document at an index, counting from 0. It's an error if the
+    /// file has fewer documents.
+    Index(usize),
+
+    /// All of the documents, merged in order like the files given to
+    /// [`generate_config_from_files`](fn.generate_config_from_files.html),
+    /// so later documents override values from earlier ones. It's an
+    /// error if a key has incompatible types in different documents.
+    MergeAll,
+}
+
+/// Options for parsing JSON configs. JSON5 configs are read by their own
+/// rules, and ignore these.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// Whether to read the bare words `NaN`, `Infinity` and `-Infinity`
+    /// as floats, as JavaScript writes them, rather than failing.
+    ///
+    /// The load functions still read the file with `serde_json`, which
+    /// rejects them.
+    ///
+    /// Defaults to `false`.
+    pub allow_non_finite: bool,
+
+    /// Whether to ignore anything after the config's value, like a second
+    /// value in a stream, rather than failing.
+    ///
+    /// Defaults to `false`.
+    pub allow_trailing_characters: bool,
+}
+
+/// Options for parsing TOML configs. There are none yet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TomlOptions {}
+
+/// Limits on the size of a config, for generating code from configs which
+/// aren't trusted. A config over any of them is a
+/// `GenerationError::LimitExceeded`, found while it's parsed rather than
+/// after it's been read into memory whole.
+///
+/// The defaults are far above anything written by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// The length of the config's source, in bytes.
+    ///
+    /// Defaults to 64 MiB.
+    pub max_source_bytes: usize,
+
+    /// How many values the config may hold, counting structs, arrays and
+    /// each of their elements. The values a YAML alias refers to count
+    /// again each time it's used, since a few lines of aliases can stand
+    /// for billions of values.
+    ///
+    /// Defaults to `1_000_000`.
+    pub max_nodes: usize,
+
+    /// The length of the longest string or key, in bytes.
+    ///
+    /// Defaults to 16 MiB.
+    pub max_string_len: usize,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        ResourceLimits {
+            max_source_bytes: 64 * 1024 * 1024,
+            max_nodes: 1_000_000,
+            max_string_len: 16 * 1024 * 1024,
+        }
+    }
+}
+
+/// How the root struct implements `Display`, for printing the config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DisplayStyle {
+    /// Don't implement `Display`.
+    #[default]
+    None,
+
+    /// Display the config as its pretty `Debug` output, `{:#?}`, with
+    /// one field per line, indented, in the order the fields are
+    /// declared. `Debug` is derived for the structs.
+    PrettyDebug,
+
+    /// Display the config in the format it was read from, serialized
+    /// with the serde crate for that format, like `toml` for a TOML
+    /// config. This requires deriving `Serialize`, and the crate must be
+    /// a dependency. A value which fails to serialize fails to display.
+    SourceFormat,
+}
+
+/// How to handle keys which aren't valid Rust field names, like
+/// `404-page` or `2fa_enabled`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeySanitization {
+    /// Fail generation with an error naming the key.
+    #[default]
+    Error,
+
+    /// Make the key into a valid name: replace invalid characters with
+    /// underscores, put an underscore before a leading digit, and escape
+    /// keywords, so that `404-page` becomes `_404_page`. A number is added
+    /// to a name which is already taken, like `_404_page_2`, with a
+    /// [`Warning`](struct.Warning.html).
+    ///
+    /// The field is (de)serialized by its new name.
+    Sanitize,
+
+    /// Sanitize the key as above, but keep the original key for
+    /// (de)serialization with a `#[serde(rename)]` attribute, so that
+    /// the generated load functions can still read the config.
+    SanitizeWithSerdeRename,
+}
+
+/// How the structs generated for nested tables are named.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum NestedNaming {
+    /// Join the root struct name and the keys of the table with `__`, so
+    /// that `server.tls` becomes `_Config__server__tls`.
+    #[default]
+    Concatenated,
+
+    /// Convert the path of the table to `PascalCase`, so that
+    /// `server.tls` becomes `ServerTls`.
+    ///
+    /// Tables whose names would collide, such as `server_tls` and
+    /// `server.tls`, cause a generation error.
+    PascalCase,
+
+    /// Name the tables at the given dotted paths explicitly, and use
+    /// `Concatenated` names for the rest.
+    Explicit(HashMap<String, String>),
+}
+
+/// How the structs for the elements of arrays of tables are named.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ArrayElementNaming {
+    /// Name them like any other nested table, by `nested_naming`.
+    #[default]
+    Nested,
+
+    /// Name them after the root struct and the path of the array, with
+    /// its last key made singular, so that `items` becomes `ConfigItem`
+    /// and `server.ports` becomes `ConfigServerPort`. Arrays of arrays of
+    /// tables share a single element struct in the same way.
+    ///
+    /// Keys are made singular by removing a trailing `s`. The map gives
+    /// the singular of any keys this doesn't suit, like `"people"` to
+    /// `"person"`.
+    ///
+    /// Names which collide with another struct or enum cause a
+    /// generation error.
+    Singular(HashMap<String, String>),
+}
+
+/// The order of the fields in generated structs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldOrder {
+    /// The order the keys appear in the config file.
+    ///
+    /// Keys which are only known from another source, such as a later
+    /// file when merging, come after the others.
+    SourceOrder,
+
+    /// Alphabetical order of the field names.
+    #[default]
+    Alphabetical,
+
+    /// Whichever order is cheapest to produce. This is currently the same
+    /// as `Alphabetical`, but may change.
+    Unspecified,
+}
+
+/// How the fields of generated structs are accessed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldAccess {
+    /// Make every field `pub`.
+    #[default]
+    PublicFields,
+
+    /// Make every field private, and generate a getter method with the
+    /// same name for each one.
+    ///
+    /// Scalars, enums and `chrono` date-times are returned by value, and
+    /// everything else by reference: strings as `&str`, arrays and
+    /// slice maps as slices, and options as `Option<&T>`. (With
+    /// `StringType::StaticStr`, strings and slices are returned with a
+    /// `'static` lifetime.)
+    ///
+    /// The generated const, `Default` impls, builders and loading
+    /// functions are in the same module as the structs, so they can
+    /// still use the private fields.
+    Getters,
+}
+
+/// How each field is converted by the `From` impl generated for
+/// `impl_into`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntoConversion {
+    /// Move each field into the target as it is.
+    Move,
+
+    /// Convert each field with `.into()`, so that a `Cow<'static, str>`
+    /// can become a `String`, for example.
+    #[default]
+    Into,
+
+    /// Convert each field with `.try_into()`, panicking if it fails, so
+    /// that an `i64` can become a `u32`, for example.
+    TryInto,
+}
+
+/// How deserialization handles keys which are missing from a config
+/// file, such as when loading it dynamically.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingFields {
+    /// Fail to deserialize the config.
+    #[default]
+    Error,
+
+    /// Use the value from the config file the code was generated from.
+    ///
+    /// Each field gets a `#[serde(default = "...")]` attribute, naming a
+    /// generated function which returns that value.
+    UseConst,
+
+    /// Use the `Default` value of the field's type, with a
+    /// `#[serde(default)]` attribute on each field.
+    ///
+    /// Every field type must implement `Default`, so nested structs
+    /// derive it unless `impl_default` is set, and `enum_fields` can't
+    /// be used.
+    UseTypeDefault,
+}
+
+/// How to handle arrays whose elements have different types.
+///
+/// Arrays which only mix numbers, like `[1, 2.5]`, are first widened to a
+/// common type, so they don't count as mixed.
+///
+/// This doesn't apply to TOML, which doesn't allow arrays of mixed
+/// types: the TOML parser rejects them with a
+/// `GenerationError::ParseFailed` before this option is consulted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MixedArrayBehavior {
+    /// Fail generation with an error naming the array's field.
+    #[default]
+    Error,
+
+    /// Represent the array as a tuple, with one element per value.
+    /// For example, `[1, "x", true]` becomes `(i64, Cow<'static, str>, bool)`.
+    Tuple,
+}
+
+/// Whether arrays are generated as fixed-size arrays, like `[i64; 3]`,
+/// or as slices, like `Cow<'static, [i64]>`.
+///
+/// Fixed-size arrays avoid an indirection, and can be indexed in a
+/// `const fn`. Empty arrays are always generated as slices.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArrayStyle {
+    /// Always use slices.
+    #[default]
+    AlwaysCow,
+
+    /// Use fixed-size arrays for arrays with at most this many elements,
+    /// and slices for longer ones. `FixedUpTo(0)` always uses slices, the
+    /// same as `AlwaysCow`.
+    FixedUpTo(usize),
+
+    /// Always use fixed-size arrays.
+    AlwaysFixed,
+}
+
+/// How to handle empty arrays which aren't given an element type by
+/// `empty_array_types` or `default_empty_array_type`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyArrayBehavior {
+    /// Generate the array with `()` elements, and report a
+    /// [`Warning`](struct.Warning.html) for it.
+    #[default]
+    Warn,
+
+    /// Fail generation with an error naming the array's field.
+    Error,
+}
+
+/// How to declare the root struct of a config with no fields, such as an
+/// empty file or one with only comments.
+///
+/// Nested tables with no fields are always braced structs, like
+/// `pub struct _Config__telemetry {}`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyRootStyle {
+    /// A unit struct, `pub struct Config;`, with `pub const CONFIG: Config
+    /// = Config;`.
+    ///
+    /// Derived serde traits read and write structs as maps, and unit
+    /// structs as unit values, which an empty file isn't, so with serde
+    /// derives the root is braced instead.
+    #[default]
+    Unit,
+
+    /// A braced struct, `pub struct Config {}`, with
+    /// `pub const CONFIG: Config = Config {};`.
+    Braced,
+}
+
+/// How to handle options which are `None` wherever they appear, and
+/// aren't given a type by `option_types` or `default_option_type`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UntypedOptionBehavior {
+    /// Generate the option as an `Option<()>`, and report a
+    /// [`Warning`](struct.Warning.html) for it.
+    #[default]
+    Warn,
+
+    /// Fail generation with an error naming the option's field.
+    Error,
+}
+
+/// How to generate `null` values in JSON and YAML configs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NullBehavior {
+    /// Generate nulls as `()`.
+    Unit,
+
+    /// Generate nulls as `None`. The `Option` takes its type from the
+    /// other elements of the same array, or from the same field in the
+    /// other elements of an array of structs, or in the other files of a
+    /// config directory. With nothing to take a type from, it takes its
+    /// type from `option_types`, or is an `Option<()>`.
+    #[default]
+    Option,
+
+    /// Fail generation with an error naming the null's field.
+    Error,
+}
+
+/// What to put at the top of a generated file, before its `use` lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum HeaderStyle {
+    /// Inner attributes allowing dead code and, unless `rustfmt_output`
+    /// is set, skipping `rustfmt`. These are only allowed at the start
+    /// of a module, so the file must be used as a module of its own.
+    #[default]
+    InnerAttributes,
+
+    /// A `// Generated by config_struct.` comment. The file can be
+    /// `include!`d anywhere, such as inside another module.
+    OuterComment,
+
+    /// The given text, such as attributes or a licence comment.
+    Custom(String),
+}
+
+/// Options for replacing tables in a config with the contents of other
+/// config files.
+///
+/// A table whose only key is the `key`, holding a path, like
+/// `database = { include = "db.toml" }`, is replaced by the parsed
+/// contents of that file. The path is relative to the file containing
+/// the table, and the format of the included file is detected from its
+/// extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeOptions {
+    /// The key marking a table as an include.
+    ///
+    /// Defaults to `"include"`.
+    pub key: String,
+}
+
+impl Default for IncludeOptions {
+    fn default() -> Self {
+        IncludeOptions {
+            key: "include".to_owned(),
+        }
+    }
+}
+
+/// Options for overriding config values from environment variables.
+///
+/// The variable for each field is the `prefix`, an underscore, then the
+/// path to the field in uppercase, with nested keys joined by the
+/// `separator`. So with the default options, `server.port` can be
+/// overridden by `CONFIG_SERVER__PORT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvOptions {
+    /// The prefix of every environment variable.
+    ///
+    /// Defaults to `"CONFIG"`.
+    pub prefix: String,
+
+    /// The separator between the keys of nested fields.
+    ///
+    /// Defaults to `"__"`.
+    pub separator: String,
 }
 
-impl Default for SerdeSupport {
+impl Default for EnvOptions {
     fn default() -> Self {
-        Self::No
+        EnvOptions {
+            prefix: "CONFIG".to_owned(),
+            separator: "__".to_owned(),
+        }
     }
 }
 
 /// When to perform dynamic loading from the config file itself.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum DynamicLoading {
     /// Always load the config from file.
     Always,
 
     /// Load from file in debug mode, but use the statically-included
     /// const in release mode.
+    #[default]
     DebugOnly,
 
     /// Never load dynamically. Always use the statically-included
     /// const.
     Never,
+
+    /// Embed the config file in the binary with `include_str!`, and
+    /// parse it on first use, so that serde's defaults and validation
+    /// apply in every build. `load_from` still reads the given file.
+    ///
+    /// The file is included by its absolute path. A relative path is
+    /// taken from `CARGO_MANIFEST_DIR` when generating from a build
+    /// script, or else from the current directory. If the path can't be
+    /// made absolute, the generated code fails to compile.
+    Embedded,
 }
 
-impl Default for DynamicLoading {
-    fn default() -> Self {
-        Self::DebugOnly
+impl DynamicLoading {
+    /// Whether the load functions use the const in some builds.
+    pub(crate) fn uses_const(self) -> bool {
+        match self {
+            DynamicLoading::DebugOnly | DynamicLoading::Never => true,
+            DynamicLoading::Always | DynamicLoading::Embedded => false,
+        }
+    }
+}
+
+/// Where the dynamic load functions find the config file at runtime.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DynamicPath {
+    /// The path of the config file when the code was generated, under
+    /// `CARGO_MANIFEST_DIR`. This only suits running from the source tree.
+    #[default]
+    BuildTimePath,
+
+    /// The path of the config file when the code was generated, relative
+    /// to `CARGO_MANIFEST_DIR` and joined onto it at runtime, like
+    /// `Path::new(env!("CARGO_MANIFEST_DIR")).join("config.toml")`. Each
+    /// component is joined separately, so that the platform's own
+    /// separators are used.
+    ManifestRelative,
+
+    /// A path given here, which is used as it is at runtime, so a
+    /// relative path is taken from the working directory.
+    Fixed(String),
+
+    /// The path in the named environment variable, like
+    /// `"APP_CONFIG_PATH"`. When it isn't set, `load()` returns the const.
+    EnvVar(String),
+
+    /// No path is built in. In place of `load()` and `try_load()`, the
+    /// load functions are `load_from(path)` and `try_load_from(path)`,
+    /// which take any `AsRef<Path>`, with the same signatures whether the
+    /// config is loaded dynamically or not.
+    Argument,
+}
+
+impl DynamicPath {
+    /// Whether the dynamic load functions fall back on the const.
+    pub(crate) fn uses_const(&self) -> bool {
+        matches!(*self, DynamicPath::EnvVar(_))
     }
 }
 
@@ -78,89 +832,824 @@ pub struct StructOptions {
     /// Defaults to `true`.
     pub generate_const: bool,
 
+    /// What to generate instead of the const, if the config can't be
+    /// built in a const context.
+    ///
+    /// Load functions and builders use whichever is generated.
+    ///
+    /// Defaults to `LazyStatic`.
+    pub const_fallback: ConstFallback,
+
+    /// Whether to generate a struct and const for the whole config, or a
+    /// const for each of its top-level tables.
+    ///
+    /// Defaults to `RootMode::Wrapper`.
+    pub root_mode: RootMode,
+
+    /// How to declare the root struct when the config has no fields. This
+    /// includes the elements of an empty root array, like the JSON `[]`.
+    ///
+    /// Defaults to `EmptyRootStyle::Unit`.
+    pub empty_root: EmptyRootStyle,
+
     /// A list of traits for the struct to derive.
     ///
     /// Defaults to `["Debug", "Clone"]`
     ///
     /// (Note that the `serde_support` option below may add to this
     /// list.)
+    ///
+    /// With the `clap-support` feature, `"clap::Parser"` makes the root
+    /// struct a command-line parser with an argument per field, named
+    /// after its dotted path (like `--server-port`) and defaulting to its
+    /// value in the config. Nested structs derive `clap::Args` and are
+    /// flattened in, and enums derive `clap::ValueEnum`. With the
+    /// `schemars-support` feature, `"schemars::JsonSchema"` adds the
+    /// `validations` that JSON Schema can express to each field's schema.
     pub derived_traits: Vec<String>,
 
-    /// Shorthand for generating the Serialize and Deserialize traits.
+    /// Whether or not to implement `Default` for the struct, and any
+    /// nested structs, returning the values from the config file.
     ///
-    /// Defaults to `No`.
-    pub serde_support: SerdeSupport,
+    /// This cannot be combined with deriving `Default` in
+    /// `derived_traits`.
+    ///
+    /// Defaults to `false`.
+    pub impl_default: bool,
 
-    /// The recommended way to derive Serialize and Deserialize
-    /// is via the `serde` crate's
-    /// [`derive` feature](https://serde.rs/derive.html).
+    /// Whether or not to generate a builder for the struct, and any
+    /// nested structs.
     ///
-    /// If you instead need to use the old method of including the
-    /// `serde_derive` crate, set this flag to `true`.
-    pub use_serde_derive_crate: bool,
+    /// Each builder has a `with_<field>` method per field and a `build`
+    /// method. If a const is generated and `Clone` is derived, the root
+    /// builder also has a `from_const` constructor starting from the
+    /// const.
+    ///
+    /// Defaults to `false`.
+    pub generate_builder: bool,
 
-    /// Whether or not to generate helper functions to load the
-    /// struct at runtime.
+    /// The path of a type to generate a `From` impl for, converting the
+    /// root struct into it, such as `crate::settings::AppSettings`.
     ///
-    /// Defaults to `true`.
+    /// The target is constructed field by field, so it must have a field
+    /// with the same name as each generated field, including any
+    /// `field_renames`. Fields which don't match cause compile errors in
+    /// the generated code.
     ///
-    /// **Note:** These load functions depend on the `Deserialize`
-    /// trait, as well as the relevant serde library for the config
-    /// format.
+    /// Defaults to `None`.
+    pub impl_into: Option<String>,
+
+    /// Whether to generate a diff type for the struct and any nested
+    /// structs, named like `ConfigDiff`, with an `Option` per field.
     ///
-    /// So for example, if you generate a struct from `config.json`
-    /// then you will have to enable `serde_support` for the
-    /// `Deserialize` trait, and you will also have to include the
-    /// `serde_json` library in your crate.
-    pub generate_load_fns: bool,
+    /// Each struct gets a `diff` method, returning the fields which differ
+    /// in another value, and an `apply` method, which updates the fields
+    /// set in a diff. Nested structs are compared field by field, and
+    /// everything else, including arrays, as a whole.
+    ///
+    /// This requires `Clone` and `PartialEq` in `derived_traits`.
+    ///
+    /// Defaults to `false`.
+    pub generate_diff: bool,
 
-    /// Whether the load functions, if generated, are dynamic,
-    /// and when.
+    /// Whether to generate an owned variant of the struct and any nested
+    /// structs, named like `ConfigOwned`, with `String` and `Vec` in place
+    /// of each `Cow`, for configs which are changed at runtime.
     ///
-    /// Defaults to `DebugOnly`.
-    pub dynamic_loading: DynamicLoading,
+    /// The owned structs have public fields and the same derives,
+    /// including serde's, so they can be written back out. `From` impls
+    /// convert `Config` into `ConfigOwned`, and `&ConfigOwned` back into
+    /// `Config`. `Default` is implemented for them when it's implemented
+    /// for the structs they mirror.
+    ///
+    /// This requires `Clone` in `derived_traits`, and can't be used with a
+    /// `string_type` of `StaticStr`.
+    ///
+    /// Defaults to `false`.
+    pub generate_owned_variant: bool,
 
-    /// Whether or not to create the parent directories of the
-    /// output file, if they don't exist.
+    /// Whether to generate a `get` method on the root struct, which finds
+    /// a value by a dotted path of keys and array indices given at
+    /// runtime, like `config.get("servers.2.port")`.
     ///
-    /// Defaults to `true`.
-    pub create_dirs: bool,
+    /// Values are returned as a `ConfigValueRef` (named after the root
+    /// struct), which borrows strings, and gives access to the fields of
+    /// structs and the elements of arrays. Tuples, maps, versions and
+    /// `chrono` date-times can't be found this way.
+    ///
+    /// Defaults to `false`.
+    pub generate_get_by_path: bool,
 
-    /// Whether to check if the destination file would be changed
-    /// before writing output.
+    /// Whether to generate a `pub mod keys`, with a `&str` const holding
+    /// the dotted path of each field which isn't a table, like
+    /// `keys::SERVER_PORT` for `"server.port"`. This gives names for keys
+    /// in logs and metrics which can't drift from the config.
     ///
-    /// This is to avoid unnecessary writes from marking the
-    /// destination file as changed (which could, for example,
-    /// trigger a process which is watching for changes). This
-    /// option only works with the `create_*` functions.
+    /// Arrays are named by their own path. Paths which would give the same
+    /// name, like `server.port` and `server_port`, are numbered in order
+    /// of their paths, and reported as warnings. This can't be used for
+    /// more than one config in a module.
     ///
-    /// Defaults to `true`.
-    pub write_only_if_changed: bool,
+    /// Defaults to `false`.
+    pub generate_key_constants: bool,
 
-    /// The type of floating point values in the config, where the
-    /// format does not make it explicit.
+    /// Whether to generate an associated const on each struct for each of
+    /// its string and integer fields, holding the field's value, like
+    /// `Config::MODE` for `mode = "fast"`. These can be matched on, and
+    /// used in const contexts, even without `generate_const`.
     ///
-    /// Defaults to `F64`.
-    pub default_float_size: FloatSize,
+    /// Consts are named after their fields in upper snake case, or after
+    /// their whole key path if two fields give the same name. Only
+    /// structs with a single value get consts, so not the elements of
+    /// arrays, nor configs whose root is an array.
+    ///
+    /// Defaults to `false`.
+    pub generate_field_consts: bool,
 
-    /// The type of integer values in the config, where the
-    /// format does not make it explicit.
+    /// Whether to generate a `#[cfg(test)] mod generated_tests`, checking
+    /// that the const is unchanged by serializing and deserializing it
+    /// in the config's format, and, when the config is loaded
+    /// dynamically, that parsing the config file gives the const. This
+    /// catches a generated file which is out of date with its config.
     ///
-    /// Defaults to `I64`.
-    pub default_int_size: IntSize,
+    /// The config file is embedded in the tests with `include_str!`, by
+    /// its absolute path. `PartialEq` is derived for the structs, and the
+    /// crate for the format must be a dependency. This requires the const
+    /// and serde support, and can't be used for INI files, configs which
+    /// aren't read from a file, or more than one config in a module.
+    ///
+    /// Defaults to `false`.
+    pub generate_tests: bool,
 
-    /// The maximum array size, over which array values in the
-    /// config will be represented as slices instead.
+    /// Whether the root struct implements `Display`, and how. Nested
+    /// structs are displayed as part of the root, so they have no
+    /// `Display` of their own.
     ///
-    /// If set to `0`, slices will always be used.
+    /// `DisplayStyle::SourceFormat` can't be used for INI files, configs
+    /// which aren't read from a file, or with `no_std`.
     ///
-    /// Defaults to `0`.
-    pub max_array_size: usize,
-}
+    /// Defaults to `DisplayStyle::None`.
+    pub impl_display: DisplayStyle,
 
-/// Represents a floating-point type.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum FloatSize {
+    /// How each field is converted by the `impl_into` impl.
+    ///
+    /// Defaults to `IntoConversion::Into`.
+    pub into_conversion: IntoConversion,
+
+    /// Whether to turn comments in the config file into doc comments on
+    /// the generated fields.
+    ///
+    /// A comment is used if it is on the lines directly above a key. A
+    /// comment above a table also documents the generated struct for it.
+    /// Comments are only read from TOML and YAML files, and are ignored
+    /// if they can't be matched to a single key, such as when array
+    /// elements have different comments for the same field.
+    ///
+    /// Defaults to `false`.
+    pub preserve_comments: bool,
+
+    /// The order of the fields in the generated structs, and in the
+    /// values built from them.
+    ///
+    /// The same order is used for every declaration and initializer, and
+    /// is stable between runs, so the generated file only changes when
+    /// the config does.
+    ///
+    /// Defaults to `FieldOrder::Alphabetical`.
+    pub field_order: FieldOrder,
+
+    /// Whether the fields of the generated structs are public, or private
+    /// with getters.
+    ///
+    /// Defaults to `FieldAccess::PublicFields`.
+    pub field_access: FieldAccess,
+
+    /// How the structs generated for nested tables are named.
+    ///
+    /// The structs for the elements of an array of tables, or the values
+    /// of a map, are named by the path of the array or map.
+    ///
+    /// Defaults to `NestedNaming::Concatenated`.
+    pub nested_naming: NestedNaming,
+
+    /// How the structs for the elements of arrays of tables are named,
+    /// overriding `nested_naming` for them.
+    ///
+    /// Defaults to `ArrayElementNaming::Nested`.
+    pub array_element_naming: ArrayElementNaming,
+
+    /// Shorthand for generating the Serialize and Deserialize traits.
+    ///
+    /// Defaults to `No`.
+    pub serde_support: SerdeSupport,
+
+    /// The recommended way to derive Serialize and Deserialize
+    /// is via the `serde` crate's
+    /// [`derive` feature](https://serde.rs/derive.html).
+    ///
+    /// If you instead need to use the old method of including the
+    /// `serde_derive` crate, set this flag to `true`.
+    pub use_serde_derive_crate: bool,
+
+    /// Serde container attributes to add to the generated structs.
+    ///
+    /// These require `serde_support` to derive at least one of
+    /// `Serialize` or `Deserialize`.
+    ///
+    /// Defaults to none.
+    pub serde_attributes: SerdeAttributes,
+
+    /// How the derived `Deserialize` impls handle keys which are missing
+    /// from the config, so that a config being edited can still be
+    /// loaded dynamically after removing a key.
+    ///
+    /// Anything other than `MissingFields::Error` requires `serde_support`
+    /// to derive `Deserialize`.
+    ///
+    /// Defaults to `MissingFields::Error`.
+    pub serde_missing_fields: MissingFields,
+
+    /// The types used to represent strings and arrays.
+    ///
+    /// Defaults to `Cow`.
+    pub string_type: StringType,
+
+    /// The types used to represent dates and times.
+    ///
+    /// Defaults to `String`.
+    pub date_time_type: DateTimeType,
+
+    /// The type used to represent the tables listed in `map_paths`.
+    ///
+    /// Defaults to `Slice`.
+    pub map_type: MapType,
+
+    /// Whether the generated code should work in a `#![no_std]` crate.
+    ///
+    /// `Cow` (and `String`, if used) is imported from `alloc`, so the
+    /// crate needs `extern crate alloc;`. With a `string_type` of
+    /// `StaticStr`, neither `Cow` nor `alloc` is needed.
+    ///
+    /// Load functions can't load dynamically, and only `load()` is
+    /// generated. Environment overrides and `HashMap` maps aren't
+    /// supported.
+    ///
+    /// Defaults to `false`.
+    pub no_std: bool,
+
+    /// Whether or not to generate helper functions to load the
+    /// struct at runtime.
+    ///
+    /// `load()` panics if the config can't be loaded, while `try_load()`
+    /// returns a generated `<struct_name>LoadError` instead, which
+    /// includes the path of the config file.
+    ///
+    /// Defaults to `true`.
+    ///
+    /// **Note:** These load functions depend on the `Deserialize`
+    /// trait, as well as the relevant serde library for the config
+    /// format.
+    ///
+    /// So for example, if you generate a struct from `config.json`
+    /// then you will have to enable `serde_support` for the
+    /// `Deserialize` trait, and you will also have to include the
+    /// `serde_json` library in your crate.
+    pub generate_load_fns: bool,
+
+    /// Whether the load functions, if generated, are dynamic,
+    /// and when.
+    ///
+    /// Defaults to `DebugOnly`.
+    pub dynamic_loading: DynamicLoading,
+
+    /// Where the dynamic load functions read the config file from at
+    /// runtime. Errors from the load functions say which of these the
+    /// path came from.
+    ///
+    /// This can't be combined with `DynamicLoading::Embedded`, which
+    /// always reads the embedded copy, and is ignored with `no_std`.
+    ///
+    /// Defaults to `DynamicPath::BuildTimePath`.
+    pub dynamic_path: DynamicPath,
+
+    /// Whether to generate a `load_with_env_overrides` function, which
+    /// loads the config as usual and then replaces any string, number or
+    /// boolean field whose environment variable is set.
+    ///
+    /// The function starts from `load()` if load functions are generated,
+    /// otherwise from a clone of the const.
+    ///
+    /// Defaults to `None`.
+    pub env_overrides: Option<EnvOptions>,
+
+    /// Whether or not to create the parent directories of the
+    /// output file, if they don't exist.
+    ///
+    /// Defaults to `true`.
+    pub create_dirs: bool,
+
+    /// Whether to check if the destination file would be changed
+    /// before writing output.
+    ///
+    /// This is to avoid unnecessary writes from marking the
+    /// destination file as changed (which could, for example,
+    /// trigger a process which is watching for changes). This
+    /// option only works with the `create_*` functions.
+    ///
+    /// Defaults to `true`.
+    pub write_only_if_changed: bool,
+
+    /// Whether `write_only_if_changed` should also skip generating the
+    /// code when nothing has changed since it was last written.
+    ///
+    /// A hash of the config and the options is kept in a file next to the
+    /// destination, with `.hash` added to its name (like `config.rs.hash`).
+    /// If the hashes match and the destination hasn't been edited, the
+    /// config isn't parsed at all. Configs with includes or environment
+    /// variable substitutions are always generated, since they depend on
+    /// more than the config file. This only applies to the `create_*`
+    /// functions which write a single file, and to
+    /// [`ConfigSession::create`](struct.ConfigSession.html#method.create).
+    ///
+    /// Defaults to `false`.
+    pub cache_source_hash: bool,
+
+    /// Whether the `create_*` functions should print a
+    /// `cargo:rerun-if-changed` directive for each config file they read,
+    /// so that the build script is rerun when the config changes.
+    ///
+    /// Directives are only printed when running in a build script.
+    ///
+    /// Defaults to `true`.
+    pub emit_rerun_directives: bool,
+
+    /// Whether to format the generated code with `rustfmt`, rather than
+    /// marking it with `#![cfg_attr(rustfmt, rustfmt_skip)]`.
+    ///
+    /// The `rustfmt` binary is found like cargo does, from the `RUSTFMT`
+    /// environment variable or the `PATH`. If it can't be run, the code
+    /// is generated unformatted, with a `RustfmtFailed` warning.
+    ///
+    /// Defaults to `false`.
+    pub rustfmt_output: bool,
+
+    /// What to put at the top of the generated file.
+    ///
+    /// Defaults to `InnerAttributes`.
+    pub header: HeaderStyle,
+
+    /// Whether to start the generated file with a comment naming the
+    /// config file it was generated from and the version of
+    /// `config_struct` which generated it, so that it can be traced
+    /// back to its input.
+    ///
+    /// Defaults to `false`.
+    pub generated_by_comment: bool,
+
+    /// The line which begins the values file written by
+    /// [`create_config_split`](fn.create_config_split.html), after the
+    /// header, to bring the generated structs into scope.
+    ///
+    /// The default suits a values file declared as a child module of the
+    /// types. If the values are included into the same module as the
+    /// types, this can be empty, and they share its imports.
+    ///
+    /// Defaults to `"use super::*;"`.
+    pub values_import: String,
+
+    /// Whether the `create_*` functions should only generate the output,
+    /// without creating directories or writing the destination file.
+    ///
+    /// Combined with [`create_config_ext`](fn.create_config_ext.html),
+    /// this can be used to check whether a generated file is up to date.
+    ///
+    /// Defaults to `false`.
+    pub dry_run: bool,
+
+    /// The type of floating point values in the config, where the
+    /// format does not make it explicit.
+    ///
+    /// Defaults to `F64`.
+    pub default_float_size: FloatSize,
+
+    /// The type of integer values in the config, where the
+    /// format does not make it explicit.
+    ///
+    /// Generation fails if a value doesn't fit in this type, including
+    /// negative values with an unsigned type, and values which only fit in
+    /// `u64` when it is `I64`. Use `Auto` to give each field the smallest
+    /// type its values fit in instead, up to `i128` or `u128` for values
+    /// beyond 64 bits. JSON, YAML and INI configs can hold integers of up
+    /// to 128 bits, but TOML and RON integers are limited to 64 bits.
+    ///
+    /// Defaults to `I64`.
+    pub default_int_size: IntSize,
+
+    /// The maximum array size, over which array values in the
+    /// config will be represented as slices instead.
+    ///
+    /// If set to `0`, there is no size over which arrays become slices:
+    /// slices will always be used, as they are for `ArrayStyle::AlwaysCow`.
+    /// Any other size is the same as setting `array_style` to
+    /// `ArrayStyle::FixedUpTo(size)`, and can't be combined with another
+    /// `array_style`.
+    ///
+    /// Defaults to `0`.
+    pub max_array_size: usize,
+
+    /// How many structs and arrays deep a value may be nested below the
+    /// root of the config. A deeper config is an error naming the path
+    /// where the limit was passed, rather than a stack overflow in the
+    /// passes over it. YAML aliases count as if expanded, and are checked
+    /// before the config is loaded, along with `resource_limits`.
+    ///
+    /// JSON is also limited to 128 levels by `serde_json` itself.
+    ///
+    /// Defaults to `128`.
+    pub max_depth: usize,
+
+    /// Limits on the size of the config, for configs which aren't
+    /// trusted.
+    ///
+    /// Defaults to `ResourceLimits::default()`.
+    pub resource_limits: ResourceLimits,
+
+    /// Which arrays to generate as fixed-size arrays, rather than slices.
+    ///
+    /// Arrays in the same position, like a field of the structs in an
+    /// array, must then have the same length, so that they have the same
+    /// type. When deriving serde traits, fixed-size arrays can have at
+    /// most 32 elements, as serde doesn't implement its traits for longer
+    /// arrays.
+    ///
+    /// Defaults to `AlwaysCow`.
+    pub array_style: ArrayStyle,
+
+    /// The most elements an array value can have and still be written on
+    /// one line. Longer arrays are written with each element on its own
+    /// line, indented one level past the line the array starts on.
+    ///
+    /// Defaults to `16`.
+    pub max_inline_array_len: usize,
+
+    /// The widest a line holding a whole array value can be, counting
+    /// its indentation, before the array is written with each element on
+    /// its own line. Arrays of structs always are, since each struct
+    /// takes up several lines.
+    ///
+    /// Defaults to `100`.
+    pub max_inline_array_width: usize,
+
+    /// What to do with arrays containing values of different types.
+    ///
+    /// Defaults to `Error`.
+    pub mixed_arrays: MixedArrayBehavior,
+
+    /// Element types for empty arrays, as Rust source (like
+    /// `"Cow<'static, str>"`), indexed by their dotted path.
+    ///
+    /// An empty array says nothing about the type of its elements, so
+    /// without one of these it is generated as a slice of `()`, which
+    /// can't hold anything when the config is loaded at runtime. Arrays
+    /// which are not empty, or are empty in only some elements of an
+    /// array of structs, are unaffected.
+    ///
+    /// Defaults to empty.
+    pub empty_array_types: HashMap<String, String>,
+
+    /// The element type for empty arrays which aren't listed in
+    /// `empty_array_types`.
+    ///
+    /// Defaults to `None`.
+    pub default_empty_array_type: Option<String>,
+
+    /// What to do with empty arrays which have no element type from
+    /// `empty_array_types` or `default_empty_array_type`.
+    ///
+    /// Defaults to `Warn`.
+    pub untyped_empty_arrays: EmptyArrayBehavior,
+
+    /// The inner types of optional fields which are `None` wherever they
+    /// appear, like a JSON `null` or a key missing from every element of
+    /// an array of tables, as Rust source (for example `"u16"`), indexed
+    /// by their dotted path.
+    ///
+    /// A `None` says nothing about the type it would hold, so without one
+    /// of these it is generated as an `Option<()>`, which can't hold
+    /// anything when the config is loaded at runtime.
+    ///
+    /// Defaults to empty.
+    pub option_types: HashMap<String, String>,
+
+    /// The inner type for options which are always `None` and aren't
+    /// listed in `option_types`.
+    ///
+    /// Defaults to `None`.
+    pub default_option_type: Option<String>,
+
+    /// What to do with options which are always `None` and have no type
+    /// from `option_types` or `default_option_type`.
+    ///
+    /// Defaults to `Warn`.
+    pub untyped_options: UntypedOptionBehavior,
+
+    /// How to generate `null` values in JSON and YAML configs.
+    ///
+    /// Defaults to `NullBehavior::Option`.
+    pub null_values: NullBehavior,
+
+    /// How to handle keys which appear more than once in the same mapping
+    /// of a JSON or YAML config, or the same section of an INI file.
+    /// JSON5 configs always use the last value, and duplicate keys in TOML
+    /// are always an error.
+    ///
+    /// Defaults to `DuplicateKeyBehavior::Error`.
+    pub duplicate_keys: DuplicateKeyBehavior,
+
+    /// How to read the types of unquoted scalars in a YAML config.
+    ///
+    /// The load functions still read the file with `serde_yaml`, which
+    /// can't read `True` or `NULL` as a boolean or null.
+    ///
+    /// Defaults to `YamlScalarStyle::Yaml11`.
+    pub yaml_scalars: YamlScalarStyle,
+
+    /// Options for the parsers of particular formats, like which document
+    /// of a YAML file with several to read.
+    ///
+    /// Defaults to `FormatOptions::default()`.
+    pub format_options: FormatOptions,
+
+    /// Whether to replace tables like `{ include = "db.toml" }` with the
+    /// contents of the file they name, and which key marks them.
+    ///
+    /// Included files may include other files, but not in a cycle. The
+    /// `create_*` functions print a `cargo:rerun-if-changed` directive for
+    /// each included file, if `emit_rerun_directives` is set. Since the
+    /// load functions can't follow includes, this can't be combined with
+    /// dynamic loading.
+    ///
+    /// Paths in configs which aren't read from a file are relative to the
+    /// current directory.
+    ///
+    /// Defaults to `None`.
+    pub resolve_includes: Option<IncludeOptions>,
+
+    /// Whether to declare a single struct for nested structs with
+    /// identical field names and types, instead of one per key.
+    ///
+    /// The shared struct takes the name of the first such struct in the
+    /// config. If `impl_default` is set, its `Default` impl returns
+    /// that struct's values.
+    ///
+    /// Defaults to `false`.
+    pub deduplicate_structs: bool,
+
+    /// String fields which should be generated as enums, rather than
+    /// strings, mapped to the list of values they are allowed to take.
+    ///
+    /// Fields are identified by their dotted path from the root of the
+    /// config, for example `"log_level"` or `"server.mode"`. The enum
+    /// type is named after the field (`LogLevel`) and each allowed value
+    /// becomes a variant (`"debug"` becomes `Debug`).
+    ///
+    /// Generation fails if the value in the config is not one of the
+    /// allowed values.
+    ///
+    /// Defaults to empty.
+    pub enum_fields: HashMap<String, Vec<String>>,
+
+    /// Number fields which should be wrapped in a newtype, like a unit of
+    /// measure, mapped to the name of the newtype.
+    ///
+    /// `"timeout_ms"` mapped to `"TimeoutMs"` declares
+    /// `pub struct TimeoutMs(pub i64);` and gives the field that type,
+    /// so a timeout can't be passed where a size is expected. Several
+    /// paths can share a newtype if their numbers have the same type.
+    /// With serde support, newtypes are transparent, so they're read and
+    /// written as plain numbers.
+    ///
+    /// Defaults to empty.
+    pub newtype_paths: HashMap<String, String>,
+
+    /// Tables which hold one of several variants, chosen by the value of
+    /// a tag key, to generate as enums.
+    ///
+    /// A spec for `"backend"`, with the tag `"kind"` and the variants
+    /// `"s3"` and `"local"`, declares
+    /// `pub enum Backend { S3 { bucket: .. }, Local { path: .. } }` and
+    /// gives the field that type, with the variant the config has in the
+    /// const. Each variant has the fields of its tables in the config, so
+    /// a variant which isn't in the config has none, with a warning. With
+    /// serde support, the enum is tagged by the tag key, so it's read and
+    /// written as a table.
+    ///
+    /// Generation fails if a table's tag isn't one of the listed values.
+    ///
+    /// Defaults to empty.
+    pub tagged_unions: Vec<TaggedUnionSpec>,
+
+    /// Names to use for fields in the generated struct, in place of their
+    /// keys in the config, indexed by their dotted path (for example
+    /// `"server.max-connections"`).
+    ///
+    /// If serde support is enabled, the original keys are still used
+    /// for (de)serialization. Fields without a rename whose key is a
+    /// Rust keyword are made into raw identifiers, like `r#type`.
+    ///
+    /// Defaults to empty.
+    pub field_renames: HashMap<String, String>,
+
+    /// How to handle keys which aren't valid field names, and aren't
+    /// renamed by `field_renames`.
+    ///
+    /// Defaults to `Error`.
+    pub key_sanitization: KeySanitization,
+
+    /// Integer types to use for specific fields in place of
+    /// `default_int_size`, indexed by their dotted path (for example
+    /// `"server.port"`).
+    ///
+    /// The type applies to every integer under that path, so an array
+    /// of integers becomes an array of the given type. Generation fails
+    /// if a value in the config doesn't fit in the type.
+    ///
+    /// Defaults to empty.
+    pub field_int_types: HashMap<String, IntSize>,
+
+    /// Tables which should be generated as maps from string keys to
+    /// values, rather than as structs, identified by their dotted path
+    /// (for example `"servers"` or `"database.replicas"`).
+    ///
+    /// This allows keys which aren't valid field names, and tables
+    /// whose keys aren't known ahead of time. All values in a map must
+    /// have the same type, and any structs among them share a
+    /// declaration, as in an array. YAML keys which are numbers or
+    /// booleans, like `404:`, are read as strings.
+    ///
+    /// Defaults to empty.
+    pub map_paths: Vec<String>,
+
+    /// Tables whose keys are indices, like `{"1": ..., "2": ...}`, which
+    /// should be generated as arrays of their values, identified by their
+    /// dotted path.
+    ///
+    /// Every key must be a non-negative integer, written as a string or,
+    /// in YAML, as a number like `1:`. The values are ordered by
+    /// their keys, which must count up from 0 or 1 unless
+    /// `index_map_gaps` allows gaps. Structs among the values share a
+    /// declaration, as in any array. Since the file still holds a table,
+    /// these can't be combined with dynamic loading.
+    ///
+    /// Defaults to empty.
+    pub index_map_paths: Vec<String>,
+
+    /// What to do when the keys of a table in `index_map_paths` skip an
+    /// index.
+    ///
+    /// Defaults to `IndexGapBehavior::Error`.
+    pub index_map_gaps: IndexGapBehavior,
+
+    /// Tables which only group fields, like `[limits]`, which should be
+    /// flattened into their parent, identified by their dotted path.
+    ///
+    /// A field which would share a name with another in the parent is an
+    /// error. Since the file still holds a table, these can't be combined
+    /// with dynamic loading.
+    ///
+    /// Defaults to empty.
+    pub flatten_paths: Vec<String>,
+
+    /// How the tables in `flatten_paths` are flattened.
+    ///
+    /// Defaults to `FlattenStyle::Fields`.
+    pub flatten_style: FlattenStyle,
+
+    /// What joins the name of a table in `flatten_paths` to the names of
+    /// its fields, with `FlattenStyle::Fields`. It may only contain
+    /// letters, digits and underscores.
+    ///
+    /// Defaults to `"_"`.
+    pub flatten_joiner: String,
+
+    /// Strings which hold base64-encoded binary data, identified by their
+    /// dotted path. They are decoded during generation, and generated as
+    /// byte slices like `Cow<'static, [u8]>`, following `string_type`.
+    ///
+    /// A path to an array of strings decodes each of them. Generation
+    /// fails if a value isn't valid base64.
+    ///
+    /// Defaults to empty.
+    pub binary_paths: Vec<String>,
+
+    /// Strings which should be generated as a `char`, identified by
+    /// their dotted path.
+    ///
+    /// A path to an array of strings converts each of them. Generation
+    /// fails if a value isn't exactly one character long.
+    ///
+    /// Defaults to empty.
+    pub char_paths: Vec<String>,
+
+    /// Transformations to apply to values while generating, each with the
+    /// dotted path of the values it applies to. A path ending in `.*`,
+    /// like `"paths.*"`, applies to everything inside that table, and a
+    /// path to an array applies to each of its elements.
+    ///
+    /// The transformations for a value are applied in the order they're
+    /// listed, so `EnvSubstitute` can come before `ParseByteSize`. Since
+    /// they change what's in the config, they can't be combined with
+    /// dynamic loading.
+    ///
+    /// Defaults to empty.
+    pub value_transforms: Vec<(String, ValueTransform)>,
+
+    /// Rules which the values in the config must follow, each with the
+    /// dotted path of the values it applies to. Generation fails with
+    /// every value which breaks a rule, so mistakes in the config are
+    /// caught before it's used.
+    ///
+    /// The elements of arrays are numbered in paths, so
+    /// `"servers.*.port"` checks the `port` of every server, with `*`
+    /// matching any one key or index. A rule other than `NonEmptyArray`
+    /// which names an array applies to each of its elements, and a
+    /// missing optional value follows every rule.
+    ///
+    /// Values are checked as they're generated, after any
+    /// `value_transforms`. Configs loaded dynamically aren't checked when
+    /// they're loaded.
+    ///
+    /// Defaults to empty.
+    pub validations: Vec<(String, Rule)>,
+
+    /// Fields to add to the config, computed from its values when it's
+    /// generated, like the sum of every item's `cost`. This saves working
+    /// out static totals each time the program starts.
+    ///
+    /// The paths the fields read can use `*` for any one key or index,
+    /// like `"items.*.cost"`, and an array at the end of a path is read
+    /// as its elements. The fields are computed in the order they're
+    /// listed, after any `value_transforms`, so a field can read the ones
+    /// before it. Generation fails if a path is missing or holds values
+    /// a field can't use, naming the field and the path. Since the fields
+    /// aren't in the config file, they can't be combined with dynamic
+    /// loading.
+    ///
+    /// Defaults to empty.
+    pub computed_fields: Vec<ComputedField>,
+
+    /// Strings which hold semantic versions, like `"1.4.0"`, identified
+    /// by their dotted path. They are checked during generation, and
+    /// generated as the type chosen by `version_type`, so that they
+    /// compare by version rather than as strings.
+    ///
+    /// A path to an array of strings converts each of them. Generation
+    /// fails if a value isn't a valid version.
+    ///
+    /// Defaults to empty.
+    pub semver_paths: Vec<String>,
+
+    /// The type used to represent the versions at `semver_paths`.
+    ///
+    /// Defaults to `VersionType::Struct`.
+    pub version_type: VersionType,
+
+    /// Fields which should only be compiled under a `#[cfg(...)]`,
+    /// identified by their dotted path, with the predicate to use, like
+    /// `target_os = "windows"`.
+    ///
+    /// The field is gated in its struct's declaration and in the
+    /// generated values. Nested structs which are only used by fields
+    /// gated with the same predicate are gated too. This can't be combined
+    /// with options which generate code for each field, like
+    /// `generate_builder`.
+    ///
+    /// Defaults to empty.
+    pub cfg_sections: HashMap<String, String>,
+
+    /// Keys to leave out of the generated struct and const, identified
+    /// by their dotted path. A path ending in `.*`, like `"ui.*"`, leaves
+    /// out everything inside that table.
+    ///
+    /// Paths which don't match any key are reported as warnings.
+    ///
+    /// Defaults to empty.
+    pub exclude_paths: Vec<String>,
+
+    /// If not empty, the only keys to include in the generated struct and
+    /// const, identified by their dotted path as in `exclude_paths`.
+    /// Tables containing an included key are kept, but only with the
+    /// included keys.
+    ///
+    /// A key can't be both included and excluded.
+    ///
+    /// Defaults to empty.
+    pub include_paths: Vec<String>,
+}
+
+/// Represents a floating-point type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatSize {
     F32,
     F64,
 }
@@ -172,36 +1661,599 @@ pub enum IntSize {
     I16,
     I32,
     I64,
+    I128,
     ISize,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    USize,
+    /// The smallest type which fits every value of the field, preferring
+    /// a signed type to an unsigned one of the same size. The integers in
+    /// an array, or in the same field of structs in an array, all get one
+    /// type. A field can't mix negative values with values too large for
+    /// `i64`.
+    Auto,
+}
+
+/// A transformation applied to the values at some paths, listed in
+/// `value_transforms`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueTransform {
+    /// Replace each `${NAME}` in a string with the value of the
+    /// environment variable `NAME` when the config is generated.
+    /// Generation fails if the variable isn't set.
+    ///
+    /// Build scripts should print a `cargo:rerun-if-env-changed`
+    /// directive for each variable, so the config is regenerated when
+    /// it changes.
+    EnvSubstitute,
+    /// Remove whitespace from the start and end of a string.
+    TrimWhitespace,
+    /// Read a string like `"512"`, `"10MB"` or `"1.5 GiB"` as a `u64`
+    /// number of bytes. `KB`, `MB`, `GB` and `TB` are powers of 1000,
+    /// and `KiB`, `MiB`, `GiB` and `TiB` are powers of 1024. Integers are
+    /// kept as a number of bytes.
+    ParseByteSize,
+    /// Read a string like `"100ms"`, `"5s"`, `"2m"` or `"1h"` as a `u64`
+    /// number of milliseconds. Integers are kept as a number of
+    /// milliseconds.
+    ParseDurationMs,
+}
+
+/// A rule which the values at some paths must follow, listed in
+/// `validations`.
+#[derive(Debug, Clone)]
+pub enum Rule {
+    /// An integer from the first number to the second, inclusive.
+    IntRange(i64, i64),
+    /// A number from the first number to the second, inclusive.
+    /// Integers are allowed too.
+    FloatRange(f64, f64),
+    /// A string which isn't empty.
+    NonEmptyString,
+    /// An array which isn't empty.
+    NonEmptyArray,
+    /// A string which is one of the listed strings.
+    OneOf(Vec<String>),
+    /// A string which the regular expression matches, anywhere in the
+    /// string unless it's anchored with `^` and `$`.
+    #[cfg(feature = "regex")]
+    MatchesRegex(String),
+}
+
+// `FloatRange` bounds are compared by their bits, so that a NaN bound
+// equals itself and `Eq` holds for every rule.
+impl PartialEq for Rule {
+    fn eq(&self, other: &Rule) -> bool {
+        match (self, other) {
+            (Rule::IntRange(min, max), Rule::IntRange(other_min, other_max)) => {
+                min == other_min && max == other_max
+            }
+            (Rule::FloatRange(min, max), Rule::FloatRange(other_min, other_max)) => {
+                min.to_bits() == other_min.to_bits() && max.to_bits() == other_max.to_bits()
+            }
+            (Rule::NonEmptyString, Rule::NonEmptyString) => true,
+            (Rule::NonEmptyArray, Rule::NonEmptyArray) => true,
+            (Rule::OneOf(values), Rule::OneOf(other_values)) => values == other_values,
+            #[cfg(feature = "regex")]
+            (Rule::MatchesRegex(pattern), Rule::MatchesRegex(other_pattern)) => {
+                pattern == other_pattern
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Rule {}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Rule::IntRange(min, max) => write!(f, "an integer from {} to {}", min, max),
+            Rule::FloatRange(min, max) => write!(f, "a number from {:?} to {:?}", min, max),
+            Rule::NonEmptyString => write!(f, "a non-empty string"),
+            Rule::NonEmptyArray => write!(f, "a non-empty array"),
+            Rule::OneOf(ref values) => write!(f, "one of {:?}", values),
+            #[cfg(feature = "regex")]
+            Rule::MatchesRegex(ref pattern) => {
+                write!(f, "a string matching the regex `{}`", pattern)
+            }
+        }
+    }
+}
+
+/// A field added to the config, listed in `computed_fields`.
+///
+/// ```rust
+/// use config_struct::{Computation, ComputedField};
+///
+/// let total = ComputedField::new("total_budget", Computation::Sum("items.*.cost".into()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComputedField {
+    /// The dotted path to add the field at, like `"stats.total_budget"`.
+    /// Its parent must be a table which doesn't have its key.
+    pub path: String,
+
+    /// How the value of the field is computed.
+    pub computation: Computation,
+}
+
+impl ComputedField {
+    /// A field at `path`, holding the result of `computation`.
+    pub fn new<P: Into<String>>(path: P, computation: Computation) -> Self {
+        ComputedField {
+            path: path.into(),
+            computation,
+        }
+    }
+}
+
+/// How the value of a [`ComputedField`](struct.ComputedField.html) is
+/// computed from the values at some paths.
+///
+/// Nulls are skipped. Integer results have the type of the integers
+/// they're computed from if those all have the same type, and `i64`
+/// otherwise, while results computed from any floats are floats.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Computation {
+    /// The number of values at the path, as a `usize`.
+    Len(String),
+    /// The sum of the numbers at the path, or `0` if there are none.
+    Sum(String),
+    /// The smallest of the numbers at the path.
+    Min(String),
+    /// The largest of the numbers at the path.
+    Max(String),
+    /// The strings, numbers and booleans at each of the paths, joined
+    /// into a string by the separator.
+    Concat(Vec<String>, String),
+}
+
+/// A table which holds one of several variants, chosen by the value of
+/// its tag key, listed in `tagged_unions`.
+///
+/// ```rust
+/// use config_struct::TaggedUnionSpec;
+///
+/// let backend = TaggedUnionSpec::new("backend", "kind")
+///     .variant("s3", "S3")
+///     .variant("local", "Local");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaggedUnionSpec {
+    /// The dotted path of the table, like `"storage.backend"`. The enum
+    /// is named after its last key, like `Backend`.
+    pub path: String,
+
+    /// The key of the tag, like `"kind"`.
+    pub tag: String,
+
+    /// Each value the tag can have, like `"s3"`, with the name of its
+    /// variant, like `"S3"`, in the order they're declared.
+    pub variants: Vec<(String, String)>,
+}
+
+impl TaggedUnionSpec {
+    /// A spec for the table at `path`, tagged by the key `tag`, with no
+    /// variants yet.
+    pub fn new<P: Into<String>, T: Into<String>>(path: P, tag: T) -> Self {
+        TaggedUnionSpec {
+            path: path.into(),
+            tag: tag.into(),
+            variants: Vec::new(),
+        }
+    }
+
+    /// Add the variant called `name`, for tables whose tag is
+    /// `tag_value`.
+    pub fn variant<V: Into<String>, N: Into<String>>(mut self, tag_value: V, name: N) -> Self {
+        self.variants.push((tag_value.into(), name.into()));
+        self
+    }
 }
 
 impl StructOptions {
     pub(crate) fn validate(&self) -> Result<(), OptionsError> {
-        if !validation::valid_identifier(&self.struct_name) {
+        if !valid_type_name(&self.struct_name) {
             return Err(OptionsError::InvalidStructName(self.struct_name.clone()));
         }
 
+        if let Some(ref const_name) = self.const_name {
+            if !valid_type_name(const_name) {
+                return Err(OptionsError::InvalidConstName(const_name.clone()));
+            }
+        }
+
+        if let Some(ref target) = self.impl_into {
+            if !valid_type_path(target) {
+                return Err(OptionsError::InvalidIntoTarget(target.clone()));
+            }
+        }
+
+        if self.generate_diff
+            && !["Clone", "PartialEq"]
+                .iter()
+                .all(|required| self.derived_traits.iter().any(|name| name == required))
+        {
+            return Err(OptionsError::DiffWithoutTraits);
+        }
+
+        if interop::derives_clap(self) && self.string_type == StringType::StaticStr {
+            return Err(OptionsError::ClapWithStaticStr);
+        }
+
+        if self.generate_owned_variant {
+            if self.string_type == StringType::StaticStr {
+                return Err(OptionsError::OwnedVariantWithStaticStr);
+            }
+            if !self.derived_traits.iter().any(|name| name == "Clone") {
+                return Err(OptionsError::OwnedVariantWithoutClone);
+            }
+        }
+
+        if self.impl_default && self.derived_traits.iter().any(|name| name == "Default") {
+            return Err(OptionsError::ConflictingDefault);
+        }
+
+        if !self.serde_attributes.is_empty() && self.serde_support.should_derive_ser_de().is_none()
+        {
+            return Err(OptionsError::SerdeAttributesWithoutSerde);
+        }
+
+        if self.serde_support == SerdeSupport::ManualImpl {
+            if !self.serde_attributes.is_empty() {
+                return Err(OptionsError::ManualImplWith("serde_attributes"));
+            }
+            if !self.flatten_paths.is_empty() && self.flatten_style == FlattenStyle::SerdeFlatten {
+                return Err(OptionsError::ManualImplWith("flatten_style"));
+            }
+            if !self.tagged_unions.is_empty() {
+                return Err(OptionsError::ManualImplWith("tagged_unions"));
+            }
+        }
+
+        let loads_dynamically =
+            self.generate_load_fns && self.dynamic_loading != DynamicLoading::Never;
+        let requires_const = self.load_fns_use_const();
+        let derives_deserialize =
+            matches!(self.serde_support.should_derive_ser_de(), Some((_, true)));
+
+        if self.serde_missing_fields != MissingFields::Error && !derives_deserialize {
+            return Err(OptionsError::MissingFieldsWithoutDeserialize);
+        }
+
+        if self.generate_tests {
+            let round_trips = self.serde_support.should_derive_ser_de() == Some((true, true));
+            if !(round_trips || (derives_deserialize && loads_dynamically)) {
+                return Err(OptionsError::TestsWithoutSerde);
+            }
+            if !(self.generate_const || requires_const) {
+                return Err(OptionsError::TestsWithoutConst);
+            }
+        }
+
+        if self.impl_display == DisplayStyle::SourceFormat
+            && !matches!(self.serde_support.should_derive_ser_de(), Some((true, _)))
+        {
+            return Err(OptionsError::DisplayWithoutSerialize);
+        }
+
+        match self.string_type {
+            StringType::StaticStr if loads_dynamically => {
+                return Err(OptionsError::StaticStrWithDynamicLoading);
+            }
+            StringType::StaticStr if derives_deserialize => {
+                return Err(OptionsError::StaticStrWithDeserialize);
+            }
+            StringType::String if self.generate_const || requires_const => {
+                return Err(OptionsError::OwnedStringsInConst);
+            }
+            _ => (),
+        }
+
+        if !self.map_paths.is_empty() && self.map_type == MapType::Slice && loads_dynamically {
+            return Err(OptionsError::SliceMapsWithDynamicLoading);
+        }
+
+        if !self.binary_paths.is_empty() && loads_dynamically {
+            return Err(OptionsError::BinaryPathsWithDynamicLoading);
+        }
+
+        for (path, rule) in &self.validations {
+            if let Some(reason) = rule.invalid_reason() {
+                return Err(OptionsError::InvalidRule(path.clone(), reason));
+            }
+        }
+
+        if !self.value_transforms.is_empty() && loads_dynamically {
+            return Err(OptionsError::ValueTransformsWithDynamicLoading);
+        }
+
+        if !self.computed_fields.is_empty() && loads_dynamically {
+            return Err(OptionsError::ComputedFieldsWithDynamicLoading);
+        }
+
+        if !self.index_map_paths.is_empty() && loads_dynamically {
+            return Err(OptionsError::IndexMapsWithDynamicLoading);
+        }
+
+        if !self.flatten_paths.is_empty() {
+            if loads_dynamically {
+                return Err(OptionsError::FlattenPathsWithDynamicLoading);
+            }
+            if self.flatten_style == FlattenStyle::SerdeFlatten
+                && self.serde_support.should_derive_ser_de().is_none()
+            {
+                return Err(OptionsError::SerdeFlattenWithoutSerde);
+            }
+        }
+
+        if self
+            .flatten_joiner
+            .contains(|c: char| !(c == '_' || c.is_ascii_alphanumeric()))
+        {
+            return Err(OptionsError::InvalidFlattenJoiner(
+                self.flatten_joiner.clone(),
+            ));
+        }
+
+        for (path, cfg) in &self.cfg_sections {
+            if !cfg_sections::valid_cfg_predicate(cfg) {
+                return Err(OptionsError::InvalidCfgPredicate(path.clone(), cfg.clone()));
+            }
+        }
+
+        if !self.cfg_sections.is_empty() {
+            let per_field_option = if self.generate_builder {
+                Some("generate_builder")
+            } else if self.field_access == FieldAccess::Getters {
+                Some("field_access")
+            } else if self.generate_diff {
+                Some("generate_diff")
+            } else if self.generate_get_by_path {
+                Some("generate_get_by_path")
+            } else if self.impl_into.is_some() {
+                Some("impl_into")
+            } else if self.generate_owned_variant {
+                Some("generate_owned_variant")
+            } else if self.env_overrides.is_some() {
+                Some("env_overrides")
+            } else {
+                None
+            };
+            if let Some(option) = per_field_option {
+                return Err(OptionsError::CfgSectionsWith(option));
+            }
+        }
+
+        if self.root_mode == RootMode::ConstPerTopLevelTable {
+            let single_root_option = if self.generate_load_fns {
+                Some("generate_load_fns")
+            } else if self.impl_default {
+                Some("impl_default")
+            } else if self.generate_builder {
+                Some("generate_builder")
+            } else if self.env_overrides.is_some() {
+                Some("env_overrides")
+            } else if self.generate_tests {
+                Some("generate_tests")
+            } else if self.generate_field_consts {
+                Some("generate_field_consts")
+            } else {
+                None
+            };
+            if let Some(option) = single_root_option {
+                return Err(OptionsError::RootModeWith(option));
+            }
+        }
+
+        if self.resolve_includes.is_some() && loads_dynamically {
+            return Err(OptionsError::IncludesWithDynamicLoading);
+        }
+
+        if self.dynamic_loading == DynamicLoading::Embedded
+            && self.dynamic_path != DynamicPath::BuildTimePath
+        {
+            return Err(OptionsError::EmbeddedWithDynamicPath);
+        }
+
+        if self.generate_load_fns
+            && self.dynamic_path == DynamicPath::Argument
+            && self.env_overrides.is_some()
+        {
+            return Err(OptionsError::ArgumentPathWithEnvOverrides);
+        }
+
+        if self.no_std {
+            if loads_dynamically {
+                return Err(OptionsError::NoStdWithDynamicLoading);
+            }
+            if self.env_overrides.is_some() {
+                return Err(OptionsError::NoStdWithEnvOverrides);
+            }
+            if !self.map_paths.is_empty() && self.map_type == MapType::HashMap {
+                return Err(OptionsError::NoStdWithHashMaps);
+            }
+            if self.impl_display == DisplayStyle::SourceFormat {
+                return Err(OptionsError::NoStdWithSourceDisplay);
+            }
+        }
+
+        for include in &self.include_paths {
+            if self
+                .exclude_paths
+                .iter()
+                .any(|exclude| paths::matches_within(exclude, include))
+            {
+                return Err(OptionsError::ConflictingPathFilters(include.clone()));
+            }
+        }
+
+        if self.env_overrides.is_some() {
+            if !(self.generate_const || self.generate_load_fns) {
+                return Err(OptionsError::EnvOverridesWithoutSource);
+            }
+            if self.string_type == StringType::StaticStr {
+                return Err(OptionsError::StaticStrWithEnvOverrides);
+            }
+        }
+
+        if self.max_array_size != 0 && self.array_style != ArrayStyle::AlwaysCow {
+            return Err(OptionsError::ConflictingArrayStyle);
+        }
+
         Ok(())
     }
 
+    /// Check the options, and that they can be used to generate code for
+    /// a config in the given format, before reading any config.
+    ///
+    /// Some options need a serde crate for the format at runtime: loading
+    /// the config dynamically, displaying it in its source format, and
+    /// generating tests. The other errors are the same as generating
+    /// would return.
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "ini-parsing")] {
+    /// use config_struct::{DynamicLoading, Format, OptionsError, StructOptions};
+    ///
+    /// let options = StructOptions {
+    ///     dynamic_loading: DynamicLoading::Always,
+    ///     ..StructOptions::serde_default()
+    /// };
+    ///
+    /// match options.validate_for(Format::Ini) {
+    ///     Err(OptionsError::FormatWith(format, option)) => {
+    ///         assert_eq!(format, "ini");
+    ///         assert_eq!(option, "dynamic_loading");
+    ///     }
+    ///     other => panic!("Expected a format error, found {:?}", other),
+    /// }
+    /// # }
+    /// ```
+    pub fn validate_for(&self, format: Format) -> Result<(), OptionsError> {
+        self.validate()?;
+
+        let loads_dynamically =
+            self.generate_load_fns && self.dynamic_loading != DynamicLoading::Never;
+        let conflicting_option = if loads_dynamically && !load_fns::supports_dynamic_loading(format)
+        {
+            Some("dynamic_loading")
+        } else if self.impl_display == DisplayStyle::SourceFormat
+            && !display::supports_source_display(format)
+        {
+            Some("impl_display")
+        } else if self.generate_tests && !generated_tests::supports_tests(format, self) {
+            Some("generate_tests")
+        } else {
+            None
+        };
+        if let Some(option) = conflicting_option {
+            return Err(OptionsError::FormatWith(format.to_string(), option));
+        }
+
+        Ok(())
+    }
+
+    /// Whether an array with `len` elements should be generated as a
+    /// fixed-size array, rather than a slice.
+    pub(crate) fn uses_fixed_array(&self, len: usize) -> bool {
+        ArrayEmitContext::new(self, 0).uses_fixed_array(len)
+    }
+
+    /// Whether any arrays could be generated as fixed-size arrays.
+    pub(crate) fn uses_fixed_arrays(&self) -> bool {
+        self.uses_fixed_array(1)
+    }
+
+    /// A copy of these options for comparing the types of values, where
+    /// array lengths shouldn't affect whether element types match.
+    pub(crate) fn for_type_comparison(&self) -> StructOptions {
+        StructOptions {
+            max_array_size: 0,
+            array_style: ArrayStyle::AlwaysCow,
+            ..self.clone()
+        }
+    }
+
+    /// Start building options from the defaults, validating them once
+    /// they are built.
+    ///
+    /// ```rust
+    /// use config_struct::{SerdeSupport, StructOptions};
+    ///
+    /// let options = StructOptions::builder()
+    ///     .struct_name("Settings")
+    ///     .serde_support(SerdeSupport::Yes)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(options.struct_name, "Settings");
+    /// ```
+    pub fn builder() -> StructOptionsBuilder {
+        StructOptionsBuilder::default()
+    }
+
     pub(crate) fn real_const_name(&self) -> String {
         self.const_name
             .clone()
             .unwrap_or_else(|| self.struct_name.to_uppercase())
     }
 
+    /// Whether the generated load functions use the const in some builds.
+    pub(crate) fn load_fns_use_const(&self) -> bool {
+        self.generate_load_fns
+            && (self.dynamic_loading.uses_const() || self.dynamic_path.uses_const())
+    }
+
+    /// Options for the least code: the structs and the const, deriving
+    /// only `Clone`, which slices of structs need.
+    ///
+    /// There are no load functions, serde derives or key constants, so
+    /// nothing is generated whose name could collide with the rest of the
+    /// module.
+    ///
+    /// ```rust
+    /// use config_struct::{StructOptions, SerdeSupport};
+    ///
+    /// let options = StructOptions::minimal();
+    ///
+    /// assert_eq!(options, StructOptions {
+    ///     derived_traits: vec!["Clone".to_owned()],
+    ///     serde_support: SerdeSupport::No,
+    ///     generate_load_fns: false,
+    ///     generate_key_constants: false,
+    ///     .. StructOptions::default()
+    /// });
+    /// ```
+    pub fn minimal() -> Self {
+        StructOptions {
+            derived_traits: vec!["Clone".to_owned()],
+            serde_support: SerdeSupport::No,
+            generate_load_fns: false,
+            generate_key_constants: false,
+            ..Self::default()
+        }
+    }
+
     /// The default options plus serde support. This includes
     /// `Serialize`/`Deserialize` traits, plus helpers functions
-    /// to load the config.
+    /// to load the config, which read the config file at runtime in
+    /// debug builds. The build script is rerun when the config changes.
     ///
     /// ```rust
-    /// use config_struct::{StructOptions, SerdeSupport};
+    /// use config_struct::{DynamicLoading, StructOptions, SerdeSupport};
     ///
     /// let options = StructOptions::serde_default();
     ///
     /// assert_eq!(options, StructOptions {
     ///     serde_support: SerdeSupport::Yes,
     ///     generate_load_fns: true,
+    ///     dynamic_loading: DynamicLoading::DebugOnly,
+    ///     emit_rerun_directives: true,
     ///     .. StructOptions::default()
     /// });
     /// ```
@@ -209,32 +2261,148 @@ impl StructOptions {
         StructOptions {
             serde_support: SerdeSupport::Yes,
             generate_load_fns: true,
+            dynamic_loading: DynamicLoading::DebugOnly,
+            emit_rerun_directives: true,
             ..Self::default()
         }
     }
+
+    /// Options for code which works in a `#![no_std]` crate without
+    /// `alloc`: strings are `&'static str`s, so nothing is a `Cow`, and
+    /// there are no load functions or serde derives.
+    ///
+    /// ```rust
+    /// use config_struct::{StringType, StructOptions, SerdeSupport};
+    ///
+    /// let options = StructOptions::embedded();
+    ///
+    /// assert_eq!(options, StructOptions {
+    ///     no_std: true,
+    ///     string_type: StringType::StaticStr,
+    ///     serde_support: SerdeSupport::No,
+    ///     generate_load_fns: false,
+    ///     .. StructOptions::default()
+    /// });
+    /// ```
+    pub fn embedded() -> Self {
+        StructOptions {
+            no_std: true,
+            string_type: StringType::StaticStr,
+            serde_support: SerdeSupport::No,
+            generate_load_fns: false,
+            ..Self::default()
+        }
+    }
+}
+
+/// Whether a name can be used for the generated struct or const.
+fn valid_type_name(name: &str) -> bool {
+    validation::valid_identifier(name) && !naming::is_keyword(name)
+}
+
+/// Whether a path like `crate::settings::AppSettings` can name the
+/// target of `impl_into`.
+fn valid_type_path(path: &str) -> bool {
+    let path = path.strip_prefix("::").unwrap_or(path);
+    path.split("::").all(validation::valid_identifier)
 }
 
 impl Default for StructOptions {
     /// ```rust
     /// use config_struct::*;
+    /// use std::collections::HashMap;
     ///
     /// let default_options = StructOptions {
     ///     struct_name: "Config".to_owned(),
     ///     const_name: None,
     ///     generate_const: true,
+    ///     const_fallback: ConstFallback::LazyStatic,
+    ///     root_mode: RootMode::Wrapper,
+    ///     empty_root: EmptyRootStyle::Unit,
     ///     derived_traits: vec![
     ///         "Debug".to_owned(),
     ///         "Clone".to_owned(),
     ///     ],
+    ///     impl_default: false,
+    ///     generate_builder: false,
+    ///     impl_into: None,
+    ///     generate_diff: false,
+    ///     generate_owned_variant: false,
+    ///     generate_get_by_path: false,
+    ///     generate_key_constants: false,
+    ///     generate_field_consts: false,
+    ///     generate_tests: false,
+    ///     impl_display: DisplayStyle::None,
+    ///     into_conversion: IntoConversion::Into,
+    ///     preserve_comments: false,
+    ///     field_order: FieldOrder::Alphabetical,
+    ///     field_access: FieldAccess::PublicFields,
+    ///     nested_naming: NestedNaming::Concatenated,
+    ///     array_element_naming: ArrayElementNaming::Nested,
     ///     serde_support: SerdeSupport::No,
     ///     use_serde_derive_crate: false,
+    ///     serde_attributes: SerdeAttributes::default(),
+    ///     serde_missing_fields: MissingFields::Error,
+    ///     string_type: StringType::Cow,
+    ///     date_time_type: DateTimeType::String,
+    ///     map_type: MapType::Slice,
+    ///     no_std: false,
     ///     generate_load_fns: false,
     ///     dynamic_loading: DynamicLoading::DebugOnly,
+    ///     dynamic_path: DynamicPath::BuildTimePath,
+    ///     env_overrides: None,
     ///     create_dirs: true,
     ///     write_only_if_changed: true,
+    ///     cache_source_hash: false,
+    ///     emit_rerun_directives: true,
+    ///     rustfmt_output: false,
+    ///     header: HeaderStyle::InnerAttributes,
+    ///     generated_by_comment: false,
+    ///     values_import: "use super::*;".to_owned(),
+    ///     dry_run: false,
     ///     default_float_size: FloatSize::F64,
     ///     default_int_size: IntSize::I64,
     ///     max_array_size: 0,
+    ///     max_depth: 128,
+    ///     resource_limits: ResourceLimits::default(),
+    ///     array_style: ArrayStyle::AlwaysCow,
+    ///     max_inline_array_len: 16,
+    ///     max_inline_array_width: 100,
+    ///     mixed_arrays: MixedArrayBehavior::Error,
+    ///     empty_array_types: HashMap::new(),
+    ///     default_empty_array_type: None,
+    ///     untyped_empty_arrays: EmptyArrayBehavior::Warn,
+    ///     option_types: HashMap::new(),
+    ///     default_option_type: None,
+    ///     untyped_options: UntypedOptionBehavior::Warn,
+    ///     null_values: NullBehavior::Option,
+    ///     duplicate_keys: DuplicateKeyBehavior::Error,
+    ///     yaml_scalars: YamlScalarStyle::Yaml11,
+    ///     format_options: FormatOptions::default(),
+    ///     resolve_includes: None,
+    ///     deduplicate_structs: false,
+    ///     enum_fields: HashMap::new(),
+    ///     newtype_paths: HashMap::new(),
+    ///     tagged_unions: vec![],
+    ///     field_renames: HashMap::new(),
+    ///     key_sanitization: KeySanitization::Error,
+    ///     field_int_types: HashMap::new(),
+    ///     map_paths: vec![],
+    ///     index_map_paths: vec![],
+    ///     index_map_gaps: IndexGapBehavior::Error,
+    ///     flatten_paths: vec![],
+    ///     flatten_style: FlattenStyle::Fields,
+    ///     flatten_joiner: "_".to_owned(),
+    ///     binary_paths: vec![],
+    ///     char_paths: vec![],
+    ///     value_transforms: vec![],
+    ///     validations: vec![],
+    ///     computed_fields: vec![],
+    ///     semver_paths: vec![],
+    ///     version_type: VersionType::Struct,
+    ///     cfg_sections: HashMap::new(),
+    ///     exclude_paths: vec![],
+    ///     include_paths: vec![],
     /// };
     /// assert_eq!(default_options, StructOptions::default());
     /// ```
@@ -243,16 +2411,90 @@ impl Default for StructOptions {
             struct_name: "Config".to_owned(),
             const_name: None,
             generate_const: true,
+            const_fallback: ConstFallback::LazyStatic,
+            root_mode: RootMode::Wrapper,
+            empty_root: EmptyRootStyle::Unit,
             derived_traits: vec!["Debug".to_owned(), "Clone".to_owned()],
+            impl_default: false,
+            generate_builder: false,
+            impl_into: None,
+            generate_diff: false,
+            generate_owned_variant: false,
+            generate_get_by_path: false,
+            generate_key_constants: false,
+            generate_field_consts: false,
+            generate_tests: false,
+            impl_display: DisplayStyle::None,
+            into_conversion: IntoConversion::Into,
+            preserve_comments: false,
+            field_order: FieldOrder::Alphabetical,
+            field_access: FieldAccess::PublicFields,
+            nested_naming: NestedNaming::Concatenated,
+            array_element_naming: ArrayElementNaming::Nested,
             serde_support: SerdeSupport::default(),
             use_serde_derive_crate: false,
+            serde_attributes: SerdeAttributes::default(),
+            serde_missing_fields: MissingFields::Error,
+            string_type: StringType::Cow,
+            date_time_type: DateTimeType::String,
+            map_type: MapType::Slice,
+            no_std: false,
             generate_load_fns: false,
             dynamic_loading: DynamicLoading::DebugOnly,
+            dynamic_path: DynamicPath::BuildTimePath,
+            env_overrides: None,
             create_dirs: true,
             write_only_if_changed: true,
+            cache_source_hash: false,
+            emit_rerun_directives: true,
+            rustfmt_output: false,
+            header: HeaderStyle::InnerAttributes,
+            generated_by_comment: false,
+            values_import: "use super::*;".to_owned(),
+            dry_run: false,
             default_float_size: FloatSize::F64,
             default_int_size: IntSize::I64,
             max_array_size: 0,
+            max_depth: 128,
+            resource_limits: ResourceLimits::default(),
+            array_style: ArrayStyle::AlwaysCow,
+            max_inline_array_len: 16,
+            max_inline_array_width: 100,
+            mixed_arrays: MixedArrayBehavior::Error,
+            empty_array_types: HashMap::new(),
+            default_empty_array_type: None,
+            untyped_empty_arrays: EmptyArrayBehavior::Warn,
+            option_types: HashMap::new(),
+            default_option_type: None,
+            untyped_options: UntypedOptionBehavior::Warn,
+            null_values: NullBehavior::Option,
+            duplicate_keys: DuplicateKeyBehavior::Error,
+            yaml_scalars: YamlScalarStyle::Yaml11,
+            format_options: FormatOptions::default(),
+            resolve_includes: None,
+            deduplicate_structs: false,
+            enum_fields: HashMap::new(),
+            newtype_paths: HashMap::new(),
+            tagged_unions: vec![],
+            field_renames: HashMap::new(),
+            key_sanitization: KeySanitization::Error,
+            field_int_types: HashMap::new(),
+            map_paths: vec![],
+            index_map_paths: vec![],
+            index_map_gaps: IndexGapBehavior::Error,
+            flatten_paths: vec![],
+            flatten_style: FlattenStyle::Fields,
+            flatten_joiner: "_".to_owned(),
+            binary_paths: vec![],
+            char_paths: vec![],
+            value_transforms: vec![],
+            validations: vec![],
+            computed_fields: vec![],
+            semver_paths: vec![],
+            version_type: VersionType::Struct,
+            cfg_sections: HashMap::new(),
+            exclude_paths: vec![],
+            include_paths: vec![],
         }
     }
 }
@@ -0,0 +1,220 @@
+//! A builder for [`StructOptions`](struct.StructOptions.html), which
+//! validates the options as soon as they are built, rather than when
+//! they are first used to generate a config.
+use std::collections::HashMap;
+
+use crate::{
+    error::OptionsError,
+    options::{
+        ArrayElementNaming, ArrayStyle, ComputedField, ConstFallback, DateTimeType, DisplayStyle,
+        DuplicateKeyBehavior, DynamicLoading, DynamicPath, EmptyArrayBehavior, EmptyRootStyle,
+        EnvOptions, FieldAccess, FieldOrder, FlattenStyle, FloatSize, FormatOptions, HeaderStyle,
+        IncludeOptions, IndexGapBehavior, IntSize, IntoConversion, KeySanitization, MapType,
+        MissingFields, MixedArrayBehavior, NestedNaming, NullBehavior, ResourceLimits, RootMode,
+        Rule, SerdeAttributes, SerdeSupport, StringType, StructOptions, TaggedUnionSpec,
+        UntypedOptionBehavior, ValueTransform, VersionType, YamlScalarStyle,
+    },
+};
+
+/// Builds a [`StructOptions`](struct.StructOptions.html) value, starting
+/// from the defaults.
+///
+/// Each setter has the same name as the option it sets. Calling `build`
+/// checks the options, so that invalid names and conflicting options are
+/// reported before any config is generated. The
+/// [`OptionsError::option_names`](enum.OptionsError.html#method.option_names)
+/// of an error name the setters which conflict.
+///
+/// ```rust
+/// use config_struct::{OptionsError, StructOptions};
+///
+/// let result = StructOptions::builder().struct_name("type").build();
+///
+/// match result {
+///     Err(error @ OptionsError::InvalidStructName(_)) => {
+///         assert_eq!(error.option_names(), &["struct_name"]);
+///     }
+///     other => panic!("Expected invalid struct name, found {:?}", other),
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StructOptionsBuilder {
+    options: StructOptions,
+}
+
+macro_rules! setters {
+    ($($name:ident: $value_type:ty,)*) => {
+        $(
+            #[doc = concat!("Set the `", stringify!($name), "` option.")]
+            pub fn $name(mut self, value: $value_type) -> Self {
+                self.options.$name = value;
+                self
+            }
+        )*
+    };
+}
+
+impl StructOptionsBuilder {
+    /// Start building from the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `struct_name` option.
+    pub fn struct_name<S: Into<String>>(mut self, value: S) -> Self {
+        self.options.struct_name = value.into();
+        self
+    }
+
+    /// Set the `const_name` option, or use the default name if it is
+    /// `None`.
+    pub fn const_name(mut self, value: Option<&str>) -> Self {
+        self.options.const_name = value.map(str::to_owned);
+        self
+    }
+
+    setters! {
+        generate_const: bool,
+        const_fallback: ConstFallback,
+        root_mode: RootMode,
+        empty_root: EmptyRootStyle,
+        derived_traits: Vec<String>,
+        impl_default: bool,
+        generate_builder: bool,
+        impl_into: Option<String>,
+        generate_diff: bool,
+        generate_owned_variant: bool,
+        generate_get_by_path: bool,
+        generate_key_constants: bool,
+        generate_field_consts: bool,
+        generate_tests: bool,
+        impl_display: DisplayStyle,
+        into_conversion: IntoConversion,
+        preserve_comments: bool,
+        field_order: FieldOrder,
+        field_access: FieldAccess,
+        nested_naming: NestedNaming,
+        array_element_naming: ArrayElementNaming,
+        serde_support: SerdeSupport,
+        use_serde_derive_crate: bool,
+        serde_attributes: SerdeAttributes,
+        serde_missing_fields: MissingFields,
+        string_type: StringType,
+        date_time_type: DateTimeType,
+        map_type: MapType,
+        no_std: bool,
+        generate_load_fns: bool,
+        dynamic_loading: DynamicLoading,
+        dynamic_path: DynamicPath,
+        env_overrides: Option<EnvOptions>,
+        create_dirs: bool,
+        write_only_if_changed: bool,
+        cache_source_hash: bool,
+        emit_rerun_directives: bool,
+        rustfmt_output: bool,
+        header: HeaderStyle,
+        generated_by_comment: bool,
+        values_import: String,
+        dry_run: bool,
+        default_float_size: FloatSize,
+        default_int_size: IntSize,
+        max_array_size: usize,
+        max_depth: usize,
+        resource_limits: ResourceLimits,
+        array_style: ArrayStyle,
+        max_inline_array_len: usize,
+        max_inline_array_width: usize,
+        mixed_arrays: MixedArrayBehavior,
+        empty_array_types: HashMap<String, String>,
+        default_empty_array_type: Option<String>,
+        untyped_empty_arrays: EmptyArrayBehavior,
+        option_types: HashMap<String, String>,
+        default_option_type: Option<String>,
+        untyped_options: UntypedOptionBehavior,
+        null_values: NullBehavior,
+        duplicate_keys: DuplicateKeyBehavior,
+        yaml_scalars: YamlScalarStyle,
+        format_options: FormatOptions,
+        resolve_includes: Option<IncludeOptions>,
+        deduplicate_structs: bool,
+        enum_fields: HashMap<String, Vec<String>>,
+        newtype_paths: HashMap<String, String>,
+        tagged_unions: Vec<TaggedUnionSpec>,
+        field_renames: HashMap<String, String>,
+        key_sanitization: KeySanitization,
+        field_int_types: HashMap<String, IntSize>,
+        map_paths: Vec<String>,
+        index_map_paths: Vec<String>,
+        index_map_gaps: IndexGapBehavior,
+        flatten_paths: Vec<String>,
+        flatten_style: FlattenStyle,
+        flatten_joiner: String,
+        binary_paths: Vec<String>,
+        char_paths: Vec<String>,
+        value_transforms: Vec<(String, ValueTransform)>,
+        validations: Vec<(String, Rule)>,
+        computed_fields: Vec<ComputedField>,
+        semver_paths: Vec<String>,
+        version_type: VersionType,
+        cfg_sections: HashMap<String, String>,
+        exclude_paths: Vec<String>,
+        include_paths: Vec<String>,
+    }
+
+    /// Validate the options and return them.
+    pub fn build(self) -> Result<StructOptions, OptionsError> {
+        self.options.validate()?;
+        Ok(self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setters_set_options() {
+        let options = StructOptionsBuilder::new()
+            .struct_name("Settings")
+            .const_name(Some("SETTINGS"))
+            .max_array_size(4)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            options,
+            StructOptions {
+                struct_name: "Settings".to_owned(),
+                const_name: Some("SETTINGS".to_owned()),
+                max_array_size: 4,
+                ..StructOptions::default()
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_names_are_errors() {
+        for name in &["", "1Config", "My Config", "Self", "struct"] {
+            match StructOptionsBuilder::new().struct_name(*name).build() {
+                Err(OptionsError::InvalidStructName(invalid)) => assert_eq!(invalid, *name),
+                other => panic!("Expected invalid struct name, found {:?}", other),
+            }
+
+            match StructOptionsBuilder::new().const_name(Some(name)).build() {
+                Err(OptionsError::InvalidConstName(invalid)) => assert_eq!(invalid, *name),
+                other => panic!("Expected invalid const name, found {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn conflicting_options_are_errors() {
+        let error = StructOptionsBuilder::new()
+            .impl_default(true)
+            .derived_traits(vec!["Default".to_owned()])
+            .build()
+            .unwrap_err();
+
+        assert_eq!(error.option_names(), &["impl_default", "derived_traits"]);
+    }
+}
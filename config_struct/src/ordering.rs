@@ -0,0 +1,290 @@
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::{
+    format::Format,
+    root_arrays,
+    value::{GenericStruct, GenericValue},
+};
+
+/// The order of the keys in a config file, with the order of the keys
+/// nested under each of them.
+///
+/// The keys of all the tables in an array are collected together, in
+/// the order they are first seen, since they become fields of the same
+/// struct.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyOrder {
+    keys: Vec<(String, KeyOrder)>,
+}
+
+/// Read the order of the keys in a config file.
+///
+/// Returns an empty order if the file can't be read, so that fields fall
+/// back on alphabetical order.
+#[cfg_attr(
+    not(any(
+        feature = "json-parsing",
+        feature = "ron-parsing",
+        feature = "toml-parsing",
+        feature = "yaml-parsing"
+    )),
+    allow(unused_variables)
+)]
+pub fn key_order(format: Format, source: &str) -> KeyOrder {
+    let order = match format {
+        // The INI parser records the order itself.
+        #[cfg(feature = "ini-parsing")]
+        Format::Ini => None,
+
+        #[cfg(feature = "json-parsing")]
+        Format::Json => serde_json::from_str(source).ok(),
+
+        #[cfg(feature = "json5-parsing")]
+        Format::Json5 => json5::from_str(source).ok(),
+
+        #[cfg(feature = "ron-parsing")]
+        Format::Ron => ron::de::from_str(source).ok(),
+
+        #[cfg(feature = "toml-parsing")]
+        Format::Toml => toml::from_str(source).ok(),
+
+        #[cfg(feature = "yaml-parsing")]
+        Format::Yaml => serde_yaml::from_str(source)
+            .ok()
+            .map(|mut order: KeyOrder| {
+                order.expand_merge_keys();
+                order
+            }),
+    };
+
+    order.unwrap_or_default()
+}
+
+impl KeyOrder {
+    /// Replace any YAML merge keys (`<<`) with the keys they merge in.
+    #[cfg(feature = "yaml-parsing")]
+    fn expand_merge_keys(&mut self) {
+        let keys = std::mem::take(&mut self.keys);
+        for (key, mut nested) in keys {
+            nested.expand_merge_keys();
+            if key == "<<" {
+                for (key, nested) in nested.keys {
+                    self.insert(key, nested);
+                }
+            } else {
+                self.insert(key, nested);
+            }
+        }
+    }
+
+    fn insert(&mut self, key: String, nested: KeyOrder) {
+        match self.keys.iter_mut().find(|(existing, _)| *existing == key) {
+            Some((_, existing)) => existing.extend(nested),
+            None => self.keys.push((key, nested)),
+        }
+    }
+
+    fn extend(&mut self, other: KeyOrder) {
+        for (key, nested) in other.keys {
+            self.insert(key, nested);
+        }
+    }
+}
+
+/// Record the order of the keys in `struct_value`, and any structs
+/// nested in it, so that `FieldOrder::SourceOrder` can be followed.
+///
+/// Structs whose order isn't known keep any order they already have. If
+/// the root of the config is an array, the order applies to each of its
+/// elements.
+pub fn apply_key_order(struct_value: &mut GenericStruct, order: &KeyOrder) {
+    if root_arrays::root_elements(struct_value).is_some() {
+        for value in struct_value.fields.values_mut() {
+            apply_to_value(value, order);
+        }
+        return;
+    }
+
+    if order.keys.is_empty() {
+        return;
+    }
+
+    struct_value.field_order = order.keys.iter().map(|(key, _)| key.clone()).collect();
+
+    for (key, nested) in &order.keys {
+        if let Some(value) = struct_value.fields.get_mut(key) {
+            apply_to_value(value, nested);
+        }
+    }
+}
+
+fn apply_to_value(value: &mut GenericValue, order: &KeyOrder) {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => apply_key_order(struct_value, order),
+        GenericValue::Option(Some(ref mut value)) => apply_to_value(value, order),
+        GenericValue::Array(ref mut values) | GenericValue::Tuple(ref mut values) => {
+            for value in values {
+                apply_to_value(value, order);
+            }
+        }
+        _ => (),
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyOrder {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(KeyOrderVisitor)
+    }
+}
+
+struct KeyOrderVisitor;
+
+macro_rules! visit_scalars {
+    ($($method:ident: $type:ty),*) => {
+        $(
+            fn $method<E: de::Error>(self, _: $type) -> Result<KeyOrder, E> {
+                Ok(KeyOrder::default())
+            }
+        )*
+    };
+}
+
+impl<'de> Visitor<'de> for KeyOrderVisitor {
+    type Value = KeyOrder;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any config value")
+    }
+
+    visit_scalars!(
+        visit_bool: bool,
+        visit_i64: i64,
+        visit_u64: u64,
+        visit_f64: f64,
+        visit_char: char,
+        visit_str: &str,
+        visit_bytes: &[u8]
+    );
+
+    fn visit_unit<E: de::Error>(self) -> Result<KeyOrder, E> {
+        Ok(KeyOrder::default())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<KeyOrder, E> {
+        Ok(KeyOrder::default())
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<KeyOrder, D::Error> {
+        KeyOrder::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<KeyOrder, D::Error> {
+        KeyOrder::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<KeyOrder, A::Error> {
+        let mut order = KeyOrder::default();
+        while let Some(element) = seq.next_element::<KeyOrder>()? {
+            order.extend(element);
+        }
+        Ok(order)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<KeyOrder, A::Error> {
+        let mut order = KeyOrder::default();
+        while let Some(key) = map.next_key::<Key>()? {
+            let nested = map.next_value::<KeyOrder>()?;
+            if let Key(Some(key)) = key {
+                order.insert(key, nested);
+            }
+        }
+        Ok(order)
+    }
+}
+
+/// A map key, which is only kept if it is a string.
+struct Key(Option<String>);
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(KeyVisitor)
+    }
+}
+
+struct KeyVisitor;
+
+macro_rules! visit_other_keys {
+    ($($method:ident: $type:ty),*) => {
+        $(
+            fn $method<E: de::Error>(self, _: $type) -> Result<Key, E> {
+                Ok(Key(None))
+            }
+        )*
+    };
+}
+
+impl<'de> Visitor<'de> for KeyVisitor {
+    type Value = Key;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map key")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Key, E> {
+        Ok(Key(Some(value.to_owned())))
+    }
+
+    visit_other_keys!(
+        visit_bool: bool,
+        visit_i64: i64,
+        visit_u64: u64,
+        visit_f64: f64,
+        visit_char: char,
+        visit_bytes: &[u8]
+    );
+
+    fn visit_unit<E: de::Error>(self) -> Result<Key, E> {
+        Ok(Key(None))
+    }
+}
+
+#[cfg(all(test, feature = "json-parsing"))]
+mod tests {
+    use super::*;
+
+    fn keys(order: &KeyOrder) -> Vec<&str> {
+        order.keys.iter().map(|(key, _)| key.as_str()).collect()
+    }
+
+    #[test]
+    fn keys_are_in_source_order() {
+        let source = r#"{ "zeta": 1, "alpha": { "y": 2, "x": 3 }, "mid": [1, 2] }"#;
+        let order = key_order(Format::Json, source);
+
+        assert_eq!(keys(&order), vec!["zeta", "alpha", "mid"]);
+        assert_eq!(keys(&order.keys[1].1), vec!["y", "x"]);
+    }
+
+    #[test]
+    fn array_tables_are_combined() {
+        let source = r#"{ "items": [{ "b": 1 }, { "c": 2, "a": 3, "b": 4 }] }"#;
+        let order = key_order(Format::Json, source);
+
+        assert_eq!(keys(&order.keys[0].1), vec!["b", "c", "a"]);
+    }
+
+    #[test]
+    #[cfg(feature = "yaml-parsing")]
+    fn merge_keys_are_expanded() {
+        let source = r#"{ "z": 1, "<<": { "y": 2, "z": 3 }, "x": 4 }"#;
+        let mut order: KeyOrder = serde_json::from_str(source).unwrap();
+        order.expand_merge_keys();
+
+        assert_eq!(keys(&order), vec!["z", "y", "x"]);
+    }
+}
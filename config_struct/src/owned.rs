@@ -0,0 +1,429 @@
+use crate::{
+    generation::{declared_structs, generate_struct_declarations, type_string},
+    getters,
+    options::{FieldAccess, MapType, MissingFields, StringType, StructOptions},
+    serde_defaults,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Generate the owned variant of the root struct and each nested struct,
+/// with `From` impls converting to and from them.
+pub fn generate_owned_variant(struct_value: &GenericStruct, options: &StructOptions) -> String {
+    let converter = Converter::new(options);
+    let owned_root = owned_struct(struct_value);
+
+    let mut declarations = generate_struct_declarations(&owned_root, &converter.owned_options);
+    if options.serde_missing_fields == MissingFields::UseConst {
+        declarations.push_str(&serde_defaults::generate_default_fns(
+            &owned_root,
+            &converter.owned_options,
+        ));
+    }
+    let mut buffer = format!("\n{}", declarations);
+
+    for struct_value in declared_structs(struct_value) {
+        buffer.push_str(&converter.generate_impls(&struct_value));
+    }
+
+    buffer
+}
+
+/// The name of the owned variant of a struct.
+pub fn owned_struct_name(struct_name: &str) -> String {
+    format!("{}Owned", struct_name)
+}
+
+/// A copy of a struct with it and every nested struct renamed to their
+/// owned variants.
+fn owned_struct(struct_value: &GenericStruct) -> GenericStruct {
+    let mut owned = struct_value.clone();
+    rename_struct(&mut owned);
+    owned
+}
+
+fn rename_struct(struct_value: &mut GenericStruct) {
+    struct_value.struct_name = owned_struct_name(&struct_value.struct_name);
+    for value in struct_value.fields.values_mut() {
+        rename_value(value);
+    }
+}
+
+fn rename_value(value: &mut GenericValue) {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => rename_struct(struct_value),
+        GenericValue::Option(Some(ref mut value)) => rename_value(value),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Tuple(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                rename_value(value);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Writes the expressions converting each field to and from its owned
+/// variant.
+struct Converter<'a> {
+    options: &'a StructOptions,
+    /// The options the owned structs are declared with.
+    owned_options: StructOptions,
+}
+
+impl<'a> Converter<'a> {
+    fn new(options: &'a StructOptions) -> Self {
+        let mut owned_options = options.clone();
+        owned_options.string_type = StringType::String;
+        owned_options.field_access = FieldAccess::PublicFields;
+        owned_options.serde_attributes.by_struct = options
+            .serde_attributes
+            .by_struct
+            .iter()
+            .map(|(name, attributes)| (owned_struct_name(name), attributes.clone()))
+            .collect();
+
+        Converter {
+            options,
+            owned_options,
+        }
+    }
+
+    fn generate_impls(&self, struct_value: &GenericStruct) -> String {
+        let struct_name = &struct_value.struct_name;
+        let owned_name = owned_struct_name(struct_name);
+        let fields = struct_value.ordered_fields(self.options.field_order);
+
+        let into_owned = fields
+            .iter()
+            .map(|&(name, value)| {
+                let field = format!("value.{}", name);
+                format!(
+                    "            {}: {},\n",
+                    name,
+                    self.owned_expression(&field, value)
+                )
+            })
+            .collect::<String>();
+        let to_borrowed = fields
+            .iter()
+            .map(|&(name, value)| {
+                let field = format!("value.{}", name);
+                format!(
+                    "            {}: {},\n",
+                    name,
+                    self.borrowed_expression(&field, false, value)
+                )
+            })
+            .collect::<String>();
+
+        let mut buffer = format!(
+            "impl From<{name}> for {owned_name} {{
+    fn from({value}: {name}) -> Self {{
+        {owned_name} {{
+{into_owned}        }}
+    }}
+}}
+
+impl From<&{owned_name}> for {name} {{
+    fn from({value}: &{owned_name}) -> Self {{
+        {name} {{
+{to_borrowed}        }}
+    }}
+}}
+
+",
+            name = struct_name,
+            owned_name = owned_name,
+            // An empty struct has no fields to convert.
+            value = if fields.is_empty() { "_value" } else { "value" },
+            into_owned = into_owned,
+            to_borrowed = to_borrowed
+        );
+
+        if self.options.impl_default {
+            buffer.push_str(&format!(
+                "impl Default for {owned_name} {{
+    fn default() -> Self {{
+        {name}::default().into()
+    }}
+}}
+
+",
+                name = struct_name,
+                owned_name = owned_name
+            ));
+        }
+
+        buffer
+    }
+
+    /// Whether the owned variant of a value has a different type.
+    fn changes(&self, value: &GenericValue) -> bool {
+        let mut owned = value.clone();
+        rename_value(&mut owned);
+        type_string(value, self.options) != type_string(&owned, &self.owned_options)
+    }
+
+    fn uses_cow(&self) -> bool {
+        self.options.string_type == StringType::Cow
+    }
+
+    /// An expression converting `expression`, of a value's type, into the
+    /// owned variant of the value.
+    fn owned_expression(&self, expression: &str, value: &GenericValue) -> String {
+        if !self.changes(value) {
+            return expression.to_owned();
+        }
+
+        match *value {
+            GenericValue::Struct(_) => format!("{}.into()", expression),
+            GenericValue::Option(Some(ref value)) => format!(
+                "{}.map(|value| {})",
+                expression,
+                self.owned_expression("value", value)
+            ),
+            GenericValue::Array(ref values) => {
+                let element = GenericValue::typed_element(values).unwrap_or(&GenericValue::Unit);
+                if self.options.uses_fixed_array(values.len()) {
+                    format!(
+                        "{}.map(|value| {})",
+                        expression,
+                        self.owned_expression("value", element)
+                    )
+                } else if !self.changes(element) {
+                    format!("{}.into_owned()", expression)
+                } else {
+                    format!(
+                        "{}.map(|value| {}).collect()",
+                        self.elements(expression),
+                        self.owned_expression("value", element)
+                    )
+                }
+            }
+            GenericValue::Tuple(ref values) => tuple(values, |index, value| {
+                self.owned_expression(&format!("{}.{}", expression, index), value)
+            }),
+            GenericValue::Map(ref map) => {
+                let value = GenericValue::typed_element(&map.values).unwrap_or(&GenericValue::Unit);
+                let key = if self.uses_cow() {
+                    "key.into_owned()"
+                } else {
+                    "key"
+                };
+                let elements = match self.options.map_type {
+                    MapType::Slice => self.elements(expression),
+                    MapType::HashMap => format!("{}.into_iter()", expression),
+                };
+                format!(
+                    "{}.map(|(key, value)| ({}, {})).collect()",
+                    elements,
+                    key,
+                    self.owned_expression("value", value)
+                )
+            }
+            // Strings, dates, bytes and empty arrays are owned by the `Cow`
+            // which holds them.
+            _ => format!("{}.into_owned()", expression),
+        }
+    }
+
+    /// An iterator over the elements of an array or slice map, by value.
+    fn elements(&self, expression: &str) -> String {
+        if self.uses_cow() {
+            format!("{}.iter().cloned()", expression)
+        } else {
+            format!("{}.into_iter()", expression)
+        }
+    }
+
+    /// An expression converting the owned variant of a value back into
+    /// the value's type.
+    ///
+    /// `place` is the owned value, or a reference to it if `by_reference`.
+    fn borrowed_expression(&self, place: &str, by_reference: bool, value: &GenericValue) -> String {
+        if !self.changes(value) {
+            return match (is_copy(value, self.options), by_reference) {
+                (true, true) => format!("*{}", place),
+                (true, false) => place.to_owned(),
+                (false, _) => format!("{}.clone()", place),
+            };
+        }
+
+        match *value {
+            GenericValue::Struct(ref struct_value) => {
+                let reference = if by_reference { "" } else { "&" };
+                format!("{}::from({}{})", struct_value.struct_name, reference, place)
+            }
+            GenericValue::Option(Some(ref value)) => format!(
+                "{}.as_ref().map({})",
+                place,
+                closure(self.borrowed_expression("value", true, value))
+            ),
+            GenericValue::Array(ref values) => {
+                let element = GenericValue::typed_element(values).unwrap_or(&GenericValue::Unit);
+                if self.options.uses_fixed_array(values.len()) {
+                    // Arrays of arrays need an index for each level.
+                    let index = match place.matches("[index").count() {
+                        0 => "index".to_owned(),
+                        depth => format!("index{}", depth + 1),
+                    };
+                    format!(
+                        "::core::array::from_fn(|{}| {})",
+                        index,
+                        self.borrowed_expression(&format!("{}[{}]", place, index), false, element)
+                    )
+                } else if !self.changes(element) {
+                    format!("Cow::Owned({}.clone())", place)
+                } else {
+                    self.slice(format!(
+                        "{}.iter().map({}).collect()",
+                        place,
+                        closure(self.borrowed_expression("value", true, element))
+                    ))
+                }
+            }
+            GenericValue::Tuple(ref values) => tuple(values, |index, value| {
+                self.borrowed_expression(&format!("{}.{}", place, index), false, value)
+            }),
+            GenericValue::Map(ref map) => {
+                let value = GenericValue::typed_element(&map.values).unwrap_or(&GenericValue::Unit);
+                let key = if self.uses_cow() {
+                    "Cow::Owned(key.clone())"
+                } else {
+                    "key.clone()"
+                };
+                let entries = format!(
+                    "{}.iter().map(|(key, value)| ({}, {})).collect()",
+                    place,
+                    key,
+                    self.borrowed_expression("value", true, value)
+                );
+                match self.options.map_type {
+                    MapType::Slice => self.slice(entries),
+                    MapType::HashMap => entries,
+                }
+            }
+            _ => format!("Cow::Owned({}.clone())", place),
+        }
+    }
+
+    /// Wrap a `Vec` in a `Cow` if slices are `Cow`s.
+    fn slice(&self, vec: String) -> String {
+        if self.uses_cow() {
+            format!("Cow::Owned({})", vec)
+        } else {
+            vec
+        }
+    }
+}
+
+/// Whether a value's type is `Copy`. Unlike a getter, this needs to know
+/// about tuples and fixed size arrays too.
+fn is_copy(value: &GenericValue, options: &StructOptions) -> bool {
+    match *value {
+        GenericValue::Option(Some(ref value)) => is_copy(value, options),
+        GenericValue::Tuple(ref values) => values.iter().all(|value| is_copy(value, options)),
+        GenericValue::Array(ref values) if options.uses_fixed_array(values.len()) => {
+            GenericValue::typed_element(values).is_none_or(|value| is_copy(value, options))
+        }
+        _ => getters::is_copy(value, options),
+    }
+}
+
+/// A closure taking `value` and returning `body`, or the function it
+/// calls, if that's all it does.
+fn closure(body: String) -> String {
+    match body.strip_suffix("(value)") {
+        Some(function) if !function.contains(['(', ' ']) => function.to_owned(),
+        _ => format!("|value| {}", body),
+    }
+}
+
+fn tuple<F>(values: &[GenericValue], mut element: F) -> String
+where
+    F: FnMut(usize, &GenericValue) -> String,
+{
+    let elements = values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| element(index, value))
+        .collect::<Vec<String>>();
+    if elements.len() == 1 {
+        format!("({},)", elements[0])
+    } else {
+        format!("({})", elements.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> GenericStruct {
+        let server = GenericStruct {
+            struct_name: "_Config__server".to_owned(),
+            fields: vec![
+                ("host".to_owned(), GenericValue::String("a".to_owned())),
+                ("port".to_owned(), GenericValue::I64(80)),
+            ]
+            .into_iter()
+            .collect(),
+            ..GenericStruct::default()
+        };
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![
+                ("server".to_owned(), GenericValue::Struct(server.clone())),
+                (
+                    "backups".to_owned(),
+                    GenericValue::Array(vec![GenericValue::Struct(server)]),
+                ),
+                (
+                    "tags".to_owned(),
+                    GenericValue::Array(vec![GenericValue::String("x".to_owned())]),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    #[test]
+    fn nested_structs_are_renamed() {
+        let owned = owned_struct(&config());
+
+        assert_eq!(owned.struct_name, "ConfigOwned");
+        match owned.fields["server"] {
+            GenericValue::Struct(ref server) => {
+                assert_eq!(server.struct_name, "_Config__serverOwned")
+            }
+            ref other => panic!("Expected a struct, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn fields_are_converted_both_ways() {
+        let options = StructOptions::default();
+        let converter = Converter::new(&options);
+        let config = config();
+
+        assert_eq!(
+            converter.owned_expression("value.tags", &config.fields["tags"]),
+            "value.tags.iter().cloned().map(|value| value.into_owned()).collect()"
+        );
+        assert_eq!(
+            converter.owned_expression("value.backups", &config.fields["backups"]),
+            "value.backups.iter().cloned().map(|value| value.into()).collect()"
+        );
+        assert_eq!(
+            converter.borrowed_expression("value.backups", false, &config.fields["backups"]),
+            "Cow::Owned(value.backups.iter().map(_Config__server::from).collect())"
+        );
+        assert_eq!(
+            converter.borrowed_expression("value.server", false, &config.fields["server"]),
+            "_Config__server::from(&value.server)"
+        );
+    }
+}
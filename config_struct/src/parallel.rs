@@ -0,0 +1,181 @@
+use std::{
+    collections::BTreeSet,
+    num::NonZeroUsize,
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::{
+    error::{Error, GenerationError},
+    options::StructOptions,
+    report::CreateReport,
+    session::ConfigSession,
+};
+
+/// A config file to generate a module from, with
+/// [`create_configs_parallel`](fn.create_configs_parallel.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerationJob {
+    /// The config file. Its format is auto-detected from its extension.
+    pub source: PathBuf,
+
+    /// The file to write the generated code to.
+    pub destination: PathBuf,
+
+    /// The options to generate the code with.
+    pub options: StructOptions,
+}
+
+impl GenerationJob {
+    /// A job generating the config at `source` into `destination`.
+    pub fn new<S: Into<PathBuf>, D: Into<PathBuf>>(
+        source: S,
+        destination: D,
+        options: StructOptions,
+    ) -> Self {
+        GenerationJob {
+            source: source.into(),
+            destination: destination.into(),
+            options,
+        }
+    }
+
+    fn create(&self) -> Result<CreateReport, Error> {
+        ConfigSession::open(&self.source)?.create(&self.destination, &self.options)
+    }
+}
+
+/// The path as an absolute path without `.` or `..` components, so that
+/// different spellings of the same destination compare equal.
+fn normalized(path: &Path) -> PathBuf {
+    let path = match std::env::current_dir() {
+        Ok(dir) => dir.join(path),
+        Err(_) => path.to_owned(),
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Run each job on one of a pool of threads, returning their results in
+/// the same order as the jobs.
+///
+/// A job which writes to the same destination as an earlier job fails
+/// without being run, so that what's written doesn't depend on which
+/// thread finishes last.
+pub fn run_jobs(jobs: &[GenerationJob]) -> Vec<Result<CreateReport, Error>> {
+    let mut destinations = BTreeSet::new();
+    let duplicates = jobs
+        .iter()
+        .map(|job| !destinations.insert(normalized(&job.destination)))
+        .collect::<Vec<_>>();
+
+    let workers = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(jobs.len());
+    let next_job = AtomicUsize::new(0);
+    let mut results = jobs.iter().map(|_| None).collect::<Vec<_>>();
+
+    thread::scope(|scope| {
+        let workers = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut finished = Vec::new();
+                    loop {
+                        let index = next_job.fetch_add(1, Ordering::Relaxed);
+                        let job = match jobs.get(index) {
+                            Some(job) => job,
+                            None => break,
+                        };
+
+                        let result = if duplicates[index] {
+                            let destination = job.destination.display().to_string();
+                            Err(GenerationError::DuplicateDestination(destination).into())
+                        } else {
+                            job.create()
+                        };
+                        finished.push((index, result));
+                    }
+                    finished
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for worker in workers {
+            let finished = worker
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            for (index, result) in finished {
+                results[index] = Some(result);
+            }
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|result| result.expect("every job is run"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_jobs() {
+        assert!(run_jobs(&[]).is_empty());
+    }
+
+    #[test]
+    fn later_jobs_with_the_same_destination_fail() {
+        let job = GenerationJob::new("missing.toml", "config.rs", StructOptions::default());
+        let results = run_jobs(&[job.clone(), job]);
+
+        assert!(matches!(results[0], Err(Error::FileIO(..))));
+        match results[1] {
+            Err(Error::Generation(GenerationError::DuplicateDestination(ref path))) => {
+                assert_eq!(path, "config.rs")
+            }
+            ref other => panic!("Expected a duplicate destination error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn destinations_are_compared_after_normalizing() {
+        assert_eq!(
+            normalized(Path::new("out/./a.rs")),
+            normalized(Path::new("out/a.rs"))
+        );
+        assert_eq!(
+            normalized(Path::new("out/b/../a.rs")),
+            normalized(Path::new("out/a.rs"))
+        );
+
+        let current = std::env::current_dir().unwrap();
+        assert_eq!(normalized(Path::new("out/a.rs")), current.join("out/a.rs"));
+        assert_eq!(
+            normalized(&current.join("out/a.rs")),
+            current.join("out/a.rs")
+        );
+
+        let job =
+            |destination| GenerationJob::new("missing.toml", destination, StructOptions::default());
+        let results = run_jobs(&[job("out/a.rs"), job("out/./a.rs")]);
+        match results[1] {
+            Err(Error::Generation(GenerationError::DuplicateDestination(ref path))) => {
+                assert_eq!(Path::new(path), Path::new("out/./a.rs"))
+            }
+            ref other => panic!("Expected a duplicate destination error, found {:?}", other),
+        }
+    }
+}
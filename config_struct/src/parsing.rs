@@ -1,12 +1,100 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::{
-    options::{FloatSize, IntSize, StructOptions},
-    value::{GenericStruct, GenericValue},
+    error::GenerationError,
+    options::{FloatSize, StructOptions},
+    paths, root_arrays,
+    value::{GenericMap, GenericStruct, GenericValue},
 };
 
 pub type ParsedFields<T> = BTreeMap<String, T>;
 
+/// A parsed value whose nesting can be measured before the recursive
+/// passes over it.
+pub trait Nested {
+    /// The values directly inside this one, if it's a struct or array,
+    /// with their keys. The elements of an array have no key, since they
+    /// share its path.
+    fn children(&self) -> Option<Vec<(Option<&str>, &Self)>>;
+}
+
+impl Nested for GenericValue {
+    fn children(&self) -> Option<Vec<(Option<&str>, &Self)>> {
+        match *self {
+            GenericValue::Struct(ref struct_value) => Some(
+                struct_value
+                    .fields
+                    .iter()
+                    .map(|(key, value)| (Some(key.as_str()), value))
+                    .collect(),
+            ),
+            GenericValue::Map(GenericMap {
+                ref keys,
+                ref values,
+            }) => Some(
+                keys.iter()
+                    .map(|key| Some(key.as_str()))
+                    .zip(values)
+                    .collect(),
+            ),
+            GenericValue::Array(ref values) | GenericValue::Tuple(ref values) => {
+                Some(values.iter().map(|value| (None, value)).collect())
+            }
+            GenericValue::Option(Some(ref value)) => value.children(),
+            _ => None,
+        }
+    }
+}
+
+/// Fail if a value is nested in more than `options.max_depth` structs
+/// and arrays below the root. This walks the values with a stack of its
+/// own, so that it can't overflow on the configs it rejects.
+pub fn check_depth<V: Nested>(root: &V, options: &StructOptions) -> Result<(), GenerationError> {
+    check_children_depth(root.children().unwrap_or_default(), options)
+}
+
+/// Like `check_depth`, for the fields of the root.
+pub fn check_fields_depth<V: Nested>(
+    fields: &ParsedFields<V>,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let children = fields
+        .iter()
+        .map(|(key, value)| (Some(key.as_str()), value))
+        .collect();
+    check_children_depth(children, options)
+}
+
+fn check_children_depth<V: Nested>(
+    children: Vec<(Option<&str>, &V)>,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let mut stack = children
+        .into_iter()
+        .map(|(key, value)| (key.unwrap_or("").to_owned(), 1, value))
+        .collect::<Vec<_>>();
+
+    while let Some((path, depth, value)) = stack.pop() {
+        let children = match value.children() {
+            Some(children) => children,
+            None => continue,
+        };
+        if depth > options.max_depth {
+            return Err(GenerationError::MaxDepthExceeded { path, depth });
+        }
+
+        for (key, child) in children {
+            let child_path = match key {
+                Some(key) => paths::join(&path, key),
+                None => path.clone(),
+            };
+            stack.push((child_path, depth + 1, child));
+        }
+    }
+
+    Ok(())
+}
+
 pub fn parsed_to_generic_struct<T, F>(
     parsed_config: ParsedFields<T>,
     options: &StructOptions,
@@ -28,9 +116,41 @@ where
     GenericStruct {
         struct_name,
         fields,
+        original_keys: BTreeMap::new(),
+        doc: None,
+        field_docs: BTreeMap::new(),
+        field_cfgs: BTreeMap::new(),
+        field_spans: BTreeMap::new(),
+        flattened_fields: BTreeSet::new(),
+        absent_fields: BTreeSet::new(),
+        field_order: Vec::new(),
     }
 }
 
+/// Convert the elements of an array at the root of a config. Tables
+/// become structs, as if each were the root, and anything else is
+/// converted as usual, to be rejected later.
+pub fn parsed_to_root_array<T, F>(
+    elements: Vec<Result<ParsedFields<T>, T>>,
+    options: &StructOptions,
+    convert_fn: F,
+) -> GenericStruct
+where
+    F: Fn(&str, &str, T, &StructOptions) -> GenericValue,
+{
+    let elements = elements
+        .into_iter()
+        .map(|element| match element {
+            Ok(fields) => {
+                GenericValue::Struct(parsed_to_generic_struct(fields, options, &convert_fn))
+            }
+            Err(value) => convert_fn("_Config", root_arrays::ROOT_ARRAY_KEY, value, options),
+        })
+        .collect();
+
+    root_arrays::root_array_struct(elements)
+}
+
 pub fn preferred_float(value: f64, preferred: FloatSize) -> GenericValue {
     match preferred {
         FloatSize::F32 => GenericValue::F32(value as f32),
@@ -38,12 +158,51 @@ pub fn preferred_float(value: f64, preferred: FloatSize) -> GenericValue {
     }
 }
 
-pub fn preferred_int(value: i64, preferred: IntSize) -> GenericValue {
-    match preferred {
-        IntSize::I8 => GenericValue::I8(value as i8),
-        IntSize::I16 => GenericValue::I16(value as i16),
-        IntSize::I32 => GenericValue::I32(value as i32),
-        IntSize::I64 => GenericValue::I64(value),
-        IntSize::ISize => GenericValue::ISize(value as isize),
+/// Check that a number written as an integer fits in `i128` or `u128`,
+/// since parsers would otherwise read it as a float.
+#[cfg(any(
+    feature = "ini-parsing",
+    feature = "json-parsing",
+    feature = "yaml-parsing"
+))]
+pub fn check_integer_literal(
+    path: &str,
+    literal: &str,
+) -> Result<(), crate::error::GenerationError> {
+    if is_integer_literal(literal) && large_integer(literal).is_none() {
+        return Err(crate::error::GenerationError::IntegerTooLarge(
+            path.to_owned(),
+            literal.to_owned(),
+        ));
     }
+    Ok(())
+}
+
+/// Parse a number written as an integer which is too large for `i64`
+/// and `u64`, as an `i128` or `u128`.
+#[cfg(any(
+    feature = "ini-parsing",
+    feature = "json-parsing",
+    feature = "yaml-parsing"
+))]
+pub fn large_integer(literal: &str) -> Option<GenericValue> {
+    if !is_integer_literal(literal) {
+        None
+    } else if let Ok(value) = literal.parse::<i128>() {
+        Some(GenericValue::I128(value))
+    } else {
+        literal.parse::<u128>().ok().map(GenericValue::U128)
+    }
+}
+
+#[cfg(any(
+    feature = "ini-parsing",
+    feature = "json-parsing",
+    feature = "yaml-parsing"
+))]
+fn is_integer_literal(literal: &str) -> bool {
+    let digits = literal.trim_start_matches(['-', '+']);
+    literal.len() - digits.len() <= 1
+        && !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit())
 }
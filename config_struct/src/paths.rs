@@ -0,0 +1,68 @@
+/// Append a key to a dotted key path, such as `server.port`.
+pub fn join(parent_path: &str, key: &str) -> String {
+    if parent_path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{}.{}", parent_path, key)
+    }
+}
+
+/// Whether a path matches a pattern, which is either a path or a path
+/// ending in `.*`, matching everything under it.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    match pattern.strip_suffix(".*") {
+        Some(parent_path) => path
+            .strip_prefix(parent_path)
+            .is_some_and(|rest| rest.starts_with('.')),
+        None => pattern == path,
+    }
+}
+
+/// Whether a path matches a pattern in which `*` stands for any one key
+/// or array index, like `servers.*.port`.
+pub fn matches_glob(pattern: &str, path: &str) -> bool {
+    let mut keys = path.split('.');
+    pattern
+        .split('.')
+        .all(|part| keys.next().is_some_and(|key| part == "*" || part == key))
+        && keys.next().is_none()
+}
+
+/// Whether a path, or any path containing it, matches a pattern.
+pub fn matches_within(pattern: &str, path: &str) -> bool {
+    path.match_indices('.')
+        .map(|(index, _)| &path[..index])
+        .chain(std::iter::once(path))
+        .any(|path| matches(pattern, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joining_paths() {
+        assert_eq!(join("", "server"), "server");
+        assert_eq!(join("server", "port"), "server.port");
+        assert_eq!(join("a.b", "c"), "a.b.c");
+    }
+
+    #[test]
+    fn matching_patterns() {
+        assert!(matches("server", "server"));
+        assert!(!matches("server", "server.port"));
+        assert!(matches("ui.*", "ui.theme"));
+        assert!(matches("ui.*", "ui.theme.colors"));
+        assert!(!matches("ui.*", "ui"));
+        assert!(!matches("ui.*", "uix.theme"));
+
+        assert!(matches_glob("servers.*.port", "servers.1.port"));
+        assert!(matches_glob("*", "name"));
+        assert!(!matches_glob("servers.*.port", "servers.port"));
+        assert!(!matches_glob("servers.*", "servers.1.port"));
+
+        assert!(matches_within("server", "server.port"));
+        assert!(matches_within("ui.*", "ui.theme.colors"));
+        assert!(!matches_within("server.port", "server"));
+    }
+}
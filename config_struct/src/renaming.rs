@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    diagnostics::{Warning, WarningKind},
+    error::GenerationError,
+    naming,
+    options::{KeySanitization, StructOptions},
+    paths, validation,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Give fields their Rust names, using `options.field_renames` where a
+/// rename is listed and escaping keywords otherwise. Keys which still
+/// aren't valid names are sanitized, if `options.key_sanitization` allows.
+///
+/// The original keys are kept in each struct's `original_keys`, so that
+/// they can still be used to (de)serialize the config.
+pub fn apply_field_renames(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), GenerationError> {
+    rename_fields(struct_value, "", options, warnings)
+}
+
+/// The key a field of a struct is (de)serialized by, if it isn't the
+/// field's name. Serde strips the `r#` from raw identifiers itself.
+pub fn serde_rename<'a>(
+    struct_value: &'a GenericStruct,
+    name: &'a str,
+    options: &StructOptions,
+) -> Option<&'a str> {
+    let original_key = struct_value.original_key(name);
+    if naming::field_name(original_key) != *name
+        && !serialized_by_sanitized_name(name, original_key, options)
+    {
+        Some(original_key)
+    } else {
+        None
+    }
+}
+
+/// Whether the field `name` was named by sanitizing its key, and should
+/// be (de)serialized by that name rather than the key.
+pub fn serialized_by_sanitized_name(name: &str, key: &str, options: &StructOptions) -> bool {
+    options.key_sanitization == KeySanitization::Sanitize
+        && !options
+            .field_renames
+            .values()
+            .any(|new_name| new_name == name)
+        && name
+            .strip_prefix(&naming::sanitized_field_name(key))
+            .is_some_and(|suffix| {
+                suffix.is_empty()
+                    || suffix
+                        .strip_prefix('_')
+                        .is_some_and(|number| number.parse::<usize>().is_ok())
+            })
+}
+
+/// The field names for the keys of a struct, leaving out keys which
+/// should be sanitized.
+///
+/// Sanitized names are chosen after the others, so that a valid key
+/// never loses its name to a sanitized one.
+fn field_names(
+    keys: &[String],
+    parent_path: &str,
+    options: &StructOptions,
+) -> BTreeMap<String, String> {
+    let sanitizes = options.key_sanitization != KeySanitization::Error;
+
+    keys.iter()
+        .filter_map(|key| {
+            let path = paths::join(parent_path, key);
+            let name = match options.field_renames.get(&path) {
+                Some(new_name) => new_name.clone(),
+                None if !sanitizes || valid_field_name(key) => naming::field_name(key),
+                None => return None,
+            };
+            Some((key.clone(), name))
+        })
+        .collect()
+}
+
+/// Whether a key is a valid field name, possibly as a raw identifier.
+fn valid_field_name(key: &str) -> bool {
+    validation::valid_identifier(key)
+        && (!naming::is_keyword(key) || naming::field_name(key) != key)
+}
+
+fn rename_fields(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), GenerationError> {
+    let fields = std::mem::take(&mut struct_value.fields);
+    let mut field_docs = std::mem::take(&mut struct_value.field_docs);
+    let mut field_spans = std::mem::take(&mut struct_value.field_spans);
+    let mut absent_fields = std::mem::take(&mut struct_value.absent_fields);
+
+    // Nested struct names are derived from their key, so they should
+    // follow any rename. (The root struct is renamed after parsing, so
+    // its children still use the parser's name for it.)
+    let name_prefix = if parent_path.is_empty() {
+        "_Config"
+    } else {
+        &struct_value.struct_name
+    }
+    .to_owned();
+
+    let keys = fields.keys().cloned().collect::<Vec<_>>();
+    let mut names = field_names(&keys, parent_path, options);
+    let mut taken = names.values().cloned().collect::<BTreeSet<_>>();
+
+    for (key, mut value) in fields {
+        let path = paths::join(parent_path, &key);
+
+        let field_name = match names.remove(&key) {
+            Some(name) => {
+                if let Some(new_name) = options.field_renames.get(&path) {
+                    let old_prefix = format!("{}__{}", name_prefix, key);
+                    let new_prefix = format!("{}__{}", name_prefix, new_name);
+                    value.replace_name_prefix(&old_prefix, &new_prefix);
+                }
+                name
+            }
+            None => {
+                let sanitized = naming::sanitized_field_name(&key);
+                let name = (1..)
+                    .map(|n| match n {
+                        1 => sanitized.clone(),
+                        n => format!("{}_{}", sanitized, n),
+                    })
+                    .find(|name| !taken.contains(name))
+                    .expect("some name is free");
+                if name != sanitized {
+                    warnings.push(Warning::new(
+                        WarningKind::SanitizedKeyCollision,
+                        path.clone(),
+                        format!(
+                            "The key `{}` was sanitized to `{}`, which was taken, so it was named `{}`.",
+                            path, sanitized, name
+                        ),
+                    ));
+                }
+                taken.insert(name.clone());
+
+                // Struct names don't need raw identifiers.
+                let old_prefix = format!("{}__{}", name_prefix, key);
+                let new_prefix = format!("{}__{}", name_prefix, name.trim_start_matches("r#"));
+                value.replace_name_prefix(&old_prefix, &new_prefix);
+                name
+            }
+        };
+
+        rename_value(&mut value, &path, options, warnings)?;
+
+        if struct_value.fields.contains_key(&field_name) {
+            return Err(GenerationError::DuplicateFieldName(paths::join(
+                parent_path,
+                &field_name,
+            )));
+        }
+
+        if let Some(doc) = field_docs.remove(&key) {
+            struct_value.field_docs.insert(field_name.clone(), doc);
+        }
+        if let Some(span) = field_spans.remove(&key) {
+            struct_value.field_spans.insert(field_name.clone(), span);
+        }
+        if absent_fields.remove(&key) {
+            struct_value.absent_fields.insert(field_name.clone());
+        }
+        if field_name != key {
+            struct_value
+                .original_keys
+                .insert(field_name.clone(), key.clone());
+        }
+        struct_value.fields.insert(field_name, value);
+    }
+
+    Ok(())
+}
+
+fn rename_value(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => {
+            rename_fields(struct_value, path, options, warnings)
+        }
+        GenericValue::TaggedUnion(ref mut union) => {
+            rename_fields(&mut union.value, path, options, warnings)
+        }
+        GenericValue::Option(Some(ref mut value)) => rename_value(value, path, options, warnings),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Tuple(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                rename_value(value, path, options, warnings)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options_with_rename(path: &str, new_name: &str) -> StructOptions {
+        let mut options = StructOptions::default();
+        options
+            .field_renames
+            .insert(path.to_owned(), new_name.to_owned());
+        options
+    }
+
+    fn make_struct(struct_name: &str, fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: struct_name.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            original_keys: Default::default(),
+            doc: None,
+            field_docs: Default::default(),
+            field_cfgs: Default::default(),
+            field_spans: Default::default(),
+            flattened_fields: Default::default(),
+            absent_fields: Default::default(),
+            field_order: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn renamed_fields_keep_original_key() {
+        let options = options_with_rename("server.max-connections", "max_connections");
+        let mut config = make_struct(
+            "Config",
+            vec![(
+                "server",
+                GenericValue::Struct(make_struct(
+                    "_Config__server",
+                    vec![("max-connections", GenericValue::I64(10))],
+                )),
+            )],
+        );
+
+        apply_field_renames(&mut config, &options, &mut Vec::new()).unwrap();
+
+        match config.fields["server"] {
+            GenericValue::Struct(ref server) => {
+                assert!(server.fields.contains_key("max_connections"));
+                assert_eq!(server.original_key("max_connections"), "max-connections");
+            }
+            ref other => panic!("Expected struct, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn keywords_become_raw_identifiers() {
+        let mut config = make_struct("Config", vec![("type", GenericValue::Bool(true))]);
+
+        apply_field_renames(&mut config, &StructOptions::default(), &mut Vec::new()).unwrap();
+
+        assert!(config.fields.contains_key("r#type"));
+        assert_eq!(config.original_key("r#type"), "type");
+    }
+
+    #[test]
+    fn renamed_structs_follow_the_new_name() {
+        let options = options_with_rename("server-info", "server");
+        let mut config = make_struct(
+            "Config",
+            vec![(
+                "server-info",
+                GenericValue::Struct(make_struct("_Config__server-info", vec![])),
+            )],
+        );
+
+        apply_field_renames(&mut config, &options, &mut Vec::new()).unwrap();
+
+        match config.fields["server"] {
+            GenericValue::Struct(ref server) => {
+                assert_eq!(server.struct_name, "_Config__server")
+            }
+            ref other => panic!("Expected struct, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn duplicate_names_are_an_error() {
+        let options = options_with_rename("a", "b");
+        let mut config = make_struct(
+            "Config",
+            vec![("a", GenericValue::I64(1)), ("b", GenericValue::I64(2))],
+        );
+
+        match apply_field_renames(&mut config, &options, &mut Vec::new()) {
+            Err(GenerationError::DuplicateFieldName(path)) => assert_eq!(path, "b"),
+            other => panic!("Expected duplicate field error, found {:?}", other),
+        }
+    }
+}
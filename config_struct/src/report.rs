@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+use crate::diagnostics::Warning;
+
+/// A description of the output of
+/// [`create_config_ext`](fn.create_config_ext.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateReport {
+    /// The file the generated code was written, or would have been
+    /// written, to.
+    pub destination: PathBuf,
+
+    /// Whether the destination file was written.
+    ///
+    /// This is `false` for a dry run, or when `write_only_if_changed`
+    /// is set and the file was already up to date.
+    pub written: bool,
+
+    /// Whether the generated code differs from the existing contents of
+    /// the destination file, or the file doesn't exist yet.
+    pub changed: bool,
+
+    /// The length of the generated code, in bytes.
+    pub bytes: usize,
+
+    /// The generated code.
+    pub code: String,
+
+    /// Any problems found which didn't stop the code being generated.
+    pub warnings: Vec<Warning>,
+}
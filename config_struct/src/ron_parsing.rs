@@ -2,54 +2,553 @@
 //!
 //! Not all of the RON syntax is currently supported:
 //!
-//! 1.  Maps are not supported, for example: `{ "a": 1 }`, because `ron` cannot parse them as
-//!     structs.
-//! 2.  Named structs are not supported, for example: `Person(age: 20)`, because the struct name
-//!     is not available at build time, and so cannot match the name in the config file.
-//! 3.  Tuples are not supported, for example: `(1, 2, 3)`. It was attempted and did not work for
-//!     some reason.
+//! 1.  Maps, for example: `{ "a": 1 }`, are parsed as structs, because `ron` cannot tell them
+//!     apart. List their paths in `StructOptions::map_paths` to generate them as maps instead.
+//!     This also makes `{}` the way to write an empty nested struct, since `()` is a unit.
+//! 2.  Named structs, for example: `Person(age: 20)`, are read as if they had no name, with a
+//!     warning, because the struct name is not available at build time, and so cannot match the
+//!     name in the config file.
+//! 3.  Tuples, for example: `(1, 2, 3)`, are generated as tuples when
+//!     `StructOptions::mixed_arrays` is `Tuple`, and are an error otherwise. `ron` can't parse
+//!     them into a `Value`, so they are found in the text and parsed as sequences.
+//! 4.  Enum variants, for example: `Warn`, and maps with keys which aren't strings are errors,
+//!     giving the key and position they occur at.
+use std::collections::{BTreeMap, BTreeSet};
+
 use ron::{self, value::Value};
 
 use crate::{
-    error::GenerationError,
-    options::StructOptions,
-    parsing,
+    diagnostics::{Warning, WarningKind},
+    error::{GenerationError, ParseError},
+    options::{MixedArrayBehavior, StructOptions},
+    parsing::{self, ParsedFields},
+    paths, tuples,
     value::{GenericStruct, GenericValue},
 };
 
-pub fn parse_ron(ron: &str, options: &StructOptions) -> Result<GenericStruct, GenerationError> {
-    use parsing::ParsedFields;
+pub fn parse_ron(
+    ron: &str,
+    options: &StructOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<GenericStruct, GenerationError> {
+    if is_blank(ron, options) {
+        return Ok(parsing::parsed_to_generic_struct(
+            ParsedFields::new(),
+            options,
+            ron_to_raw_value,
+        ));
+    }
 
-    let ron_struct = {
-        let ron_object: Value = ron::de::from_str(ron)
-            .map_err(|err| GenerationError::DeserializationFailed(err.to_string()))?;
+    let constructs = scan_constructs(ron, options)?;
+    warnings.extend(constructs.warnings);
 
-        if let Value::Map(mapping) = ron_object {
-            mapping
-                .into_iter()
-                .map(|(key, value)| {
-                    let key = {
-                        if let Value::String(key) = key {
-                            key
-                        } else {
-                            let m = "Top-level keys in RON map must be strings.".to_owned();
-                            return Err(GenerationError::DeserializationFailed(m));
-                        }
-                    };
-                    Ok((key, value))
-                })
-                .collect::<Result<ParsedFields<Value>, GenerationError>>()?
-        } else {
-            let m = "Root RON object must be a struct or map.".to_owned();
-            return Err(GenerationError::DeserializationFailed(m));
+    let mut generic_struct = {
+        // Rewriting keeps every byte in place, so positions in errors
+        // still match the config.
+        let ron_object: Value = ron::de::from_str(&constructs.rewritten).map_err(|err| {
+            let (line, column) = match err {
+                ron::de::Error::Parser(_, ref position) => {
+                    (Some(position.line), Some(position.col))
+                }
+                _ => (None, None),
+            };
+            GenerationError::ParseFailed(ParseError::new(err, ron, line, column))
+        })?;
+        parsing::check_depth(&ron_object, options)?;
+
+        match ron_object {
+            Value::Map(mapping) => {
+                parsing::parsed_to_generic_struct(ron_fields(mapping)?, options, ron_to_raw_value)
+            }
+            Value::Seq(elements) => {
+                let elements = elements
+                    .into_iter()
+                    .map(|element| match element {
+                        Value::Map(mapping) => ron_fields(mapping).map(Ok),
+                        other => Ok(Err(other)),
+                    })
+                    .collect::<Result<_, _>>()?;
+                parsing::parsed_to_root_array(elements, options, ron_to_raw_value)
+            }
+            // An empty struct, `()`, reads as a unit.
+            Value::Unit => {
+                parsing::parsed_to_generic_struct(ParsedFields::new(), options, ron_to_raw_value)
+            }
+            _ => {
+                let m = "Root RON object must be a struct, map or sequence.".to_owned();
+                return Err(GenerationError::DeserializationFailed(m));
+            }
         }
     };
 
-    let generic_struct = parsing::parsed_to_generic_struct(ron_struct, options, ron_to_raw_value);
+    if !constructs.tuples.is_empty() {
+        for (key, value) in &mut generic_struct.fields {
+            convert_tuples(value, key, &constructs.tuples);
+        }
+    }
 
     Ok(generic_struct)
 }
 
+/// The parts of RON text which `ron::Value` can't represent.
+#[derive(Default)]
+struct RonConstructs {
+    /// The text, with tuples made into sequences and the names of structs
+    /// and tuples blanked out.
+    rewritten: String,
+
+    /// Where the tuples were found. Each is the path of its key, with `[]`
+    /// added for each sequence it's in, and the index for each tuple it's
+    /// in, so that tuples in an array can be told apart from the array.
+    tuples: BTreeSet<String>,
+
+    /// A warning for each name which was blanked out.
+    warnings: Vec<Warning>,
+}
+
+/// Find the tuples, names and unsupported constructs in RON text.
+///
+/// If the text isn't valid RON, it's left for `ron` to report the error,
+/// and nothing is rewritten.
+fn scan_constructs(ron: &str, options: &StructOptions) -> Result<RonConstructs, GenerationError> {
+    let mut scanner = Scanner {
+        source: ron,
+        rewritten: ron.as_bytes().to_vec(),
+        position: 0,
+        options,
+        tuples: BTreeSet::new(),
+        warnings: Vec::new(),
+    };
+
+    match scanner.value("", "") {
+        Ok(()) => Ok(RonConstructs {
+            // Only ASCII bytes were replaced, with other ASCII bytes.
+            rewritten: String::from_utf8(scanner.rewritten).expect("rewritten RON is UTF-8"),
+            tuples: scanner.tuples,
+            warnings: scanner.warnings,
+        }),
+        Err(Stop::Unsupported(error)) => Err(error),
+        Err(Stop::Malformed) => Ok(RonConstructs {
+            rewritten: ron.to_owned(),
+            ..RonConstructs::default()
+        }),
+    }
+}
+
+/// Whether RON text has nothing but whitespace and comments, like an
+/// empty config file.
+fn is_blank(ron: &str, options: &StructOptions) -> bool {
+    let mut scanner = Scanner {
+        source: ron,
+        rewritten: Vec::new(),
+        position: 0,
+        options,
+        tuples: BTreeSet::new(),
+        warnings: Vec::new(),
+    };
+    scanner.skip_whitespace();
+    scanner.peek().is_none()
+}
+
+/// Why scanning stopped before the end of the text.
+enum Stop {
+    /// The text isn't valid RON.
+    Malformed,
+
+    /// The text uses a construct which can't be generated.
+    Unsupported(GenerationError),
+}
+
+/// A scanner which follows the structure of RON text just closely enough to
+/// know the path of each value, without parsing the values themselves.
+struct Scanner<'a> {
+    source: &'a str,
+    rewritten: Vec<u8>,
+    position: usize,
+    options: &'a StructOptions,
+    tuples: BTreeSet<String>,
+    warnings: Vec<Warning>,
+}
+
+impl<'a> Scanner<'a> {
+    /// Scan the value starting at the current position, under the key
+    /// `path`, where `locator` is as in `RonConstructs::tuples`.
+    fn value(&mut self, path: &str, locator: &str) -> Result<(), Stop> {
+        self.skip_whitespace();
+        let start = self.position;
+
+        match self.peek().ok_or(Stop::Malformed)? {
+            b'"' => self.string().map(drop),
+            b'\'' => self.char(),
+            b'[' => {
+                self.position += 1;
+                self.elements(path, locator, b']', false)
+            }
+            b'{' => self.map(path, locator),
+            b'(' => self.parentheses(path, locator, None),
+            _ if self.at_raw_string() => self.string().map(drop),
+            _ => match self.identifier() {
+                Some("true") | Some("false") | Some("None") => Ok(()),
+                Some("Some") => {
+                    self.expect(b'(')?;
+                    self.value(path, locator)?;
+                    self.comma();
+                    self.expect(b')')
+                }
+                Some(name) => {
+                    self.skip_whitespace();
+                    if self.peek() == Some(b'(') {
+                        self.parentheses(path, locator, Some((name, start)))
+                    } else {
+                        Err(self.unsupported(&format!("enum variant `{}`", name), path, start))
+                    }
+                }
+                None => self.number(),
+            },
+        }
+    }
+
+    /// Scan a struct or tuple, which may have a name, starting at the
+    /// opening parenthesis.
+    fn parentheses(
+        &mut self,
+        path: &str,
+        locator: &str,
+        name: Option<(&str, usize)>,
+    ) -> Result<(), Stop> {
+        let open = self.position;
+        self.position += 1;
+
+        // A struct starts with a field name and a colon, or is empty.
+        self.skip_whitespace();
+        let is_struct = self.peek() == Some(b')') || {
+            let is_field = self.identifier().is_some() && {
+                self.skip_whitespace();
+                self.peek() == Some(b':')
+            };
+            self.position = open + 1;
+            is_field
+        };
+
+        if let Some((name, name_start)) = name {
+            for byte in &mut self.rewritten[name_start..name_start + name.len()] {
+                *byte = b' ';
+            }
+            let location = if path.is_empty() {
+                "at the root".to_owned()
+            } else {
+                format!("under key `{}`", path)
+            };
+            let kind = if is_struct { "struct" } else { "tuple" };
+            self.warnings.push(Warning::new(
+                WarningKind::IgnoredRonName,
+                path,
+                format!(
+                    "The name `{}` of the RON {} {} was ignored.",
+                    name, kind, location
+                ),
+            ));
+        }
+
+        if is_struct {
+            loop {
+                self.skip_whitespace();
+                if self.peek() == Some(b')') {
+                    self.position += 1;
+                    return Ok(());
+                }
+                let key = self.identifier().ok_or(Stop::Malformed)?;
+                self.expect(b':')?;
+                self.value(&paths::join(path, key), &paths::join(locator, key))?;
+                if !self.comma() {
+                    return self.expect(b')');
+                }
+            }
+        }
+
+        if self.options.mixed_arrays != MixedArrayBehavior::Tuple {
+            let (line, column) = self.line_and_column(open);
+            return Err(Stop::Unsupported(GenerationError::RonTupleWithoutTuples {
+                path: path.to_owned(),
+                line,
+                column,
+            }));
+        }
+
+        self.tuples.insert(locator.to_owned());
+        self.elements(path, locator, b')', true)?;
+        self.rewritten[open] = b'[';
+        self.rewritten[self.position - 1] = b']';
+        Ok(())
+    }
+
+    /// Scan the elements of a sequence or tuple, after the opening bracket,
+    /// up to and including `close`.
+    fn elements(
+        &mut self,
+        path: &str,
+        locator: &str,
+        close: u8,
+        is_tuple: bool,
+    ) -> Result<(), Stop> {
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(close) {
+                self.position += 1;
+                return Ok(());
+            }
+            self.value(path, &element_locator(locator, index, is_tuple))?;
+            if !self.comma() {
+                return self.expect(close);
+            }
+            index += 1;
+        }
+    }
+
+    /// Scan a map, starting at the opening brace.
+    fn map(&mut self, path: &str, locator: &str) -> Result<(), Stop> {
+        self.position += 1;
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(b'}') {
+                self.position += 1;
+                return Ok(());
+            }
+            if self.peek() != Some(b'"') && !self.at_raw_string() {
+                let position = self.position;
+                return Err(self.unsupported(
+                    "map with a key which isn't a string",
+                    path,
+                    position,
+                ));
+            }
+            let key = self.string()?;
+            self.expect(b':')?;
+            self.value(&paths::join(path, key), &paths::join(locator, key))?;
+            if !self.comma() {
+                return self.expect(b'}');
+            }
+        }
+    }
+
+    /// Scan a string, returning its contents without unescaping them.
+    fn string(&mut self) -> Result<&'a str, Stop> {
+        let bytes = self.source.as_bytes();
+        if bytes[self.position] == b'r' {
+            let hashes = bytes[self.position + 1..]
+                .iter()
+                .take_while(|&&byte| byte == b'#')
+                .count();
+            let start = self.position + 1 + hashes + 1;
+            let terminator = format!("\"{}", "#".repeat(hashes));
+            let length = self
+                .source
+                .get(start..)
+                .and_then(|rest| rest.find(&terminator))
+                .ok_or(Stop::Malformed)?;
+            self.position = start + length + terminator.len();
+            return Ok(&self.source[start..start + length]);
+        }
+
+        let start = self.position + 1;
+        self.position = start;
+        loop {
+            match bytes.get(self.position) {
+                Some(b'"') => break,
+                Some(b'\\') => self.position += 2,
+                Some(_) => self.position += 1,
+                None => return Err(Stop::Malformed),
+            }
+        }
+        self.position += 1;
+        self.source
+            .get(start..self.position - 1)
+            .ok_or(Stop::Malformed)
+    }
+
+    fn char(&mut self) -> Result<(), Stop> {
+        self.position += 1;
+        loop {
+            match self.peek() {
+                Some(b'\'') => break,
+                Some(b'\\') => self.position += 2,
+                Some(_) => self.position += 1,
+                None => return Err(Stop::Malformed),
+            }
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn number(&mut self) -> Result<(), Stop> {
+        let length = self.source.as_bytes()[self.position..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_alphanumeric() || b"+-._".contains(byte))
+            .count();
+        if length == 0 {
+            return Err(Stop::Malformed);
+        }
+        self.position += length;
+        Ok(())
+    }
+
+    fn identifier(&mut self) -> Option<&'a str> {
+        let bytes = &self.source.as_bytes()[self.position..];
+        if !bytes
+            .first()
+            .is_some_and(|byte| byte.is_ascii_alphabetic() || *byte == b'_')
+            || self.at_raw_string()
+        {
+            return None;
+        }
+        let length = bytes
+            .iter()
+            .take_while(|byte| byte.is_ascii_alphanumeric() || **byte == b'_')
+            .count();
+        let identifier = &self.source[self.position..self.position + length];
+        self.position += length;
+        Some(identifier)
+    }
+
+    fn at_raw_string(&self) -> bool {
+        let bytes = &self.source.as_bytes()[self.position..];
+        bytes.first() == Some(&b'r') && matches!(bytes.get(1), Some(b'"') | Some(b'#'))
+    }
+
+    /// Skip past a comma, if there is one.
+    fn comma(&mut self) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(b',');
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), Stop> {
+        self.skip_whitespace();
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(Stop::Malformed)
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = &self.source.as_bytes()[self.position..];
+            self.position += if rest.first().is_some_and(u8::is_ascii_whitespace) {
+                1
+            } else if rest.starts_with(b"//") {
+                rest.iter().take_while(|&&byte| byte != b'\n').count()
+            } else if rest.starts_with(b"/*") {
+                self.source[self.position + 2..]
+                    .find("*/")
+                    .map_or(rest.len(), |end| end + 4)
+            } else {
+                return;
+            };
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.source.as_bytes().get(self.position).copied()
+    }
+
+    fn unsupported(&self, construct: &str, path: &str, position: usize) -> Stop {
+        let (line, column) = self.line_and_column(position);
+        Stop::Unsupported(GenerationError::UnsupportedRon {
+            construct: construct.to_owned(),
+            path: path.to_owned(),
+            line,
+            column,
+        })
+    }
+
+    /// The line and column, counting from 1, of a byte offset.
+    fn line_and_column(&self, position: usize) -> (usize, usize) {
+        let before = &self.source[..position];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        (
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
+}
+
+/// The locator, as in `RonConstructs::tuples`, of an element of a sequence
+/// or tuple.
+fn element_locator(locator: &str, index: usize, is_tuple: bool) -> String {
+    if is_tuple {
+        format!("{}[{}]", locator, index)
+    } else {
+        format!("{}[]", locator)
+    }
+}
+
+/// Make the arrays which were tuples in the RON text into tuples again.
+fn convert_tuples(value: &mut GenericValue, locator: &str, locators: &BTreeSet<String>) {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => {
+            for (key, value) in &mut struct_value.fields {
+                convert_tuples(value, &paths::join(locator, key), locators);
+            }
+        }
+        GenericValue::Option(Some(ref mut value)) => convert_tuples(value, locator, locators),
+        GenericValue::Array(ref mut values) => {
+            let is_tuple = locators.contains(locator);
+            for (index, value) in values.iter_mut().enumerate() {
+                convert_tuples(value, &element_locator(locator, index, is_tuple), locators);
+            }
+        }
+        _ => (),
+    }
+
+    if locators.contains(locator) {
+        if let GenericValue::Array(values) = std::mem::replace(value, GenericValue::Unit) {
+            *value = GenericValue::Tuple(tuples::rename_tuple_structs(values));
+        }
+    }
+}
+
+impl parsing::Nested for Value {
+    fn children(&self) -> Option<Vec<(Option<&str>, &Self)>> {
+        match *self {
+            Value::Map(ref fields) => Some(
+                fields
+                    .iter()
+                    .map(|(key, value)| match *key {
+                        Value::String(ref key) => (Some(key.as_str()), value),
+                        _ => (None, value),
+                    })
+                    .collect(),
+            ),
+            Value::Seq(ref values) => Some(values.iter().map(|value| (None, value)).collect()),
+            Value::Option(Some(ref value)) => value.children(),
+            _ => None,
+        }
+    }
+}
+
+fn ron_fields(mapping: BTreeMap<Value, Value>) -> Result<ParsedFields<Value>, GenerationError> {
+    mapping
+        .into_iter()
+        .map(|(key, value)| {
+            let key = {
+                if let Value::String(key) = key {
+                    key
+                } else {
+                    let m = "Top-level keys in RON map must be strings.".to_owned();
+                    return Err(GenerationError::DeserializationFailed(m));
+                }
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
+
 #[allow(clippy::float_cmp)]
 fn ron_to_raw_value(
     super_struct: &str,
@@ -64,8 +563,13 @@ fn ron_to_raw_value(
         Value::Number(value) => {
             let float = value.get();
 
-            if float.trunc() == float {
-                parsing::preferred_int(float as i64, options.default_int_size)
+            // RON numbers are all floats, so whole numbers are treated as
+            // integers, unless they don't fit in one. Negative zero stays a
+            // float, so that it keeps its sign.
+            let fits_int = float.abs() < i64::MAX as f64;
+            let negative_zero = float == 0.0 && float.is_sign_negative();
+            if float.trunc() == float && fits_int && !negative_zero {
+                GenericValue::I64(float as i64)
             } else {
                 parsing::preferred_float(float, options.default_float_size)
             }
@@ -90,7 +594,7 @@ fn ron_to_raw_value(
                         if let Value::String(key) = key {
                             key
                         } else {
-                            unimplemented!("We should handle an error here");
+                            unreachable!("maps with other keys are rejected when scanning");
                         }
                     };
                     let value = ron_to_raw_value(&sub_struct_name, &key, value, options);
@@ -100,6 +604,14 @@ fn ron_to_raw_value(
             GenericValue::Struct(GenericStruct {
                 struct_name: sub_struct_name,
                 fields: values,
+                original_keys: BTreeMap::new(),
+                doc: None,
+                field_docs: BTreeMap::new(),
+                field_cfgs: BTreeMap::new(),
+                field_spans: BTreeMap::new(),
+                flattened_fields: BTreeSet::new(),
+                absent_fields: BTreeSet::new(),
+                field_order: Vec::new(),
             })
         }
     }
@@ -112,12 +624,19 @@ mod tests {
     #[test]
     fn test_non_string_keys() {
         let ron_code = r#"(100: "One hundred")"#;
-        assert!(parse_ron(ron_code, &StructOptions::default()).is_err());
+        assert!(parse_ron(ron_code, &StructOptions::default(), &mut Vec::new()).is_err());
     }
 
     #[test]
     fn test_non_struct_root_object() {
-        let ron_code = r#"["key", "value"]"#;
-        assert!(parse_ron(ron_code, &StructOptions::default()).is_err());
+        let ron_code = r#""value""#;
+        assert!(parse_ron(ron_code, &StructOptions::default(), &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_root_seq() {
+        let ron_code = r#"[{ "name": "a" }, { "name": "b" }]"#;
+        let config = parse_ron(ron_code, &StructOptions::default(), &mut Vec::new()).unwrap();
+        assert_eq!(crate::root_arrays::root_elements(&config).unwrap().len(), 2);
     }
 }
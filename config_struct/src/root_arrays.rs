@@ -0,0 +1,172 @@
+//! Support for configs whose root is an array of tables, like the JSON
+//! `[{ "name": "a" }, { "name": "b" }]`.
+//!
+//! These are parsed into a wrapper struct with a single field, whose key
+//! is empty, holding the elements. Since the key is empty, the paths of
+//! the elements' fields are the same as if each element were the root,
+//! so all the usual options and passes apply to them unchanged.
+//!
+//! The wrapper itself is never declared: the elements become instances
+//! of a struct named by `options.struct_name`, and the generated const
+//! is a slice of them.
+use std::collections::BTreeMap;
+
+use crate::{
+    error::GenerationError,
+    options::StructOptions,
+    unification::{array_structs, struct_template},
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// The key of the field holding the elements of a root array.
+pub const ROOT_ARRAY_KEY: &str = "";
+
+/// Build the wrapper struct for a root array.
+pub fn root_array_struct(elements: Vec<GenericValue>) -> GenericStruct {
+    let mut fields = BTreeMap::new();
+    fields.insert(ROOT_ARRAY_KEY.to_owned(), GenericValue::Array(elements));
+
+    GenericStruct {
+        struct_name: "Config".to_owned(),
+        fields,
+        ..GenericStruct::default()
+    }
+}
+
+/// The elements of the config, if its root is an array, or its tables,
+/// if it generates a const per top-level table. Either way, each is
+/// declared and processed as if it were the root.
+pub fn root_elements(config: &GenericStruct) -> Option<&[GenericValue]> {
+    if config.fields.len() != 1 {
+        return None;
+    }
+
+    match config.fields.get(ROOT_ARRAY_KEY) {
+        Some(GenericValue::Array(ref elements)) => Some(elements),
+        Some(GenericValue::Map(GenericMap { ref values, .. })) => Some(values),
+        _ => None,
+    }
+}
+
+fn root_elements_mut(config: &mut GenericStruct) -> Option<&mut Vec<GenericValue>> {
+    if config.fields.len() != 1 {
+        return None;
+    }
+
+    match config.fields.get_mut(ROOT_ARRAY_KEY) {
+        Some(GenericValue::Array(ref mut elements)) => Some(elements),
+        _ => None,
+    }
+}
+
+/// Check that every element of a root array is a table, and that the
+/// options don't need a single root value, then give the elements the
+/// struct name from the options.
+///
+/// Does nothing if the root of the config isn't an array.
+pub fn prepare_root_array(
+    config: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let elements = match root_elements_mut(config) {
+        Some(elements) => elements,
+        None => return Ok(()),
+    };
+
+    let non_tables = elements
+        .iter()
+        .enumerate()
+        .filter(|(_, element)| !matches!(element, GenericValue::Struct(_)))
+        .map(|(index, _)| index)
+        .collect::<Vec<usize>>();
+    if !non_tables.is_empty() {
+        return Err(GenerationError::NonTableRootElements(non_tables));
+    }
+
+    let unsupported = if options.impl_default {
+        Some("impl_default")
+    } else if options.generate_builder {
+        Some("generate_builder")
+    } else if options.env_overrides.is_some() {
+        Some("env_overrides")
+    } else {
+        None
+    };
+    if let Some(option) = unsupported {
+        return Err(GenerationError::UnsupportedRootArray(option.to_owned()));
+    }
+
+    for element in elements {
+        if let GenericValue::Struct(ref mut struct_value) = *element {
+            struct_value.struct_name = options.struct_name.clone();
+        }
+    }
+
+    Ok(())
+}
+
+/// The struct to declare for the elements of a root array, with the
+/// fields of all of them.
+pub fn element_template(elements: &[GenericValue], options: &StructOptions) -> GenericStruct {
+    let structs = array_structs(elements);
+    if structs.is_empty() {
+        GenericStruct {
+            struct_name: options.struct_name.clone(),
+            ..GenericStruct::default()
+        }
+    } else {
+        struct_template(&structs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(name: &str) -> GenericValue {
+        GenericValue::Struct(GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![("name".to_owned(), GenericValue::String(name.to_owned()))]
+                .into_iter()
+                .collect(),
+            ..GenericStruct::default()
+        })
+    }
+
+    #[test]
+    fn elements_are_named_from_the_options() {
+        let mut config = root_array_struct(vec![element("a"), element("b")]);
+        let options = StructOptions {
+            struct_name: "Entry".to_owned(),
+            ..StructOptions::default()
+        };
+        prepare_root_array(&mut config, &options).unwrap();
+
+        let elements = root_elements(&config).unwrap();
+        assert_eq!(elements.len(), 2);
+        assert_eq!(element_template(elements, &options).struct_name, "Entry");
+    }
+
+    #[test]
+    fn structs_are_not_root_arrays() {
+        let config = GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![("items".to_owned(), GenericValue::Array(Vec::new()))]
+                .into_iter()
+                .collect(),
+            ..GenericStruct::default()
+        };
+
+        assert!(root_elements(&config).is_none());
+    }
+
+    #[test]
+    fn non_table_elements_are_errors() {
+        let mut config = root_array_struct(vec![element("a"), GenericValue::I64(1), element("b")]);
+
+        match prepare_root_array(&mut config, &StructOptions::default()) {
+            Err(GenerationError::NonTableRootElements(indices)) => assert_eq!(indices, vec![1]),
+            other => panic!("Expected non-table elements error, found {:?}", other),
+        }
+    }
+}
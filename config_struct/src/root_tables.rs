@@ -0,0 +1,253 @@
+//! Support for generating a const per top-level table, with
+//! `RootMode::ConstPerTopLevelTable`.
+//!
+//! The tables are moved into a map held by the same wrapper as a root
+//! array, so that each is processed and declared as if it were the root,
+//! and the passes give them a single struct. The keys of the map name
+//! the consts.
+use std::mem;
+
+use crate::{
+    error::GenerationError,
+    naming,
+    options::{RootMode, StructOptions},
+    paths,
+    root_arrays::{self, ROOT_ARRAY_KEY},
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Check that every top-level value is a table, and that the tables
+/// have the same shape, then move them into a map at the root, named
+/// from the options.
+///
+/// Does nothing unless `options.root_mode` asks for a const per table.
+pub fn prepare_root_tables(
+    config: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if options.root_mode != RootMode::ConstPerTopLevelTable {
+        return Ok(());
+    }
+    if root_arrays::root_elements(config).is_some() {
+        return Err(GenerationError::UnsupportedRootArray(
+            "root_mode".to_owned(),
+        ));
+    }
+
+    let original_keys = mem::take(&mut config.original_keys);
+    let mut tables = mem::take(&mut config.fields)
+        .into_iter()
+        .map(|(name, value)| {
+            let key = original_keys.get(&name).cloned().unwrap_or(name);
+            (key, value)
+        })
+        .collect::<Vec<_>>();
+
+    // Tables follow the source order if it's known.
+    let field_order = mem::take(&mut config.field_order);
+    tables.sort_by_key(|(key, _)| {
+        field_order
+            .iter()
+            .position(|ordered_key| ordered_key == key)
+            .unwrap_or(usize::MAX)
+    });
+
+    if let Some((key, _)) = tables
+        .iter()
+        .find(|(_, value)| !matches!(*value, GenericValue::Struct(_)))
+    {
+        return Err(GenerationError::NonTableTopLevelKey(key.clone()));
+    }
+
+    if let Some((first_key, first)) = tables.first() {
+        for (key, table) in &tables[1..] {
+            if let Some(path) = shape_difference(first, table, "") {
+                return Err(GenerationError::MismatchedTopLevelTables(
+                    path,
+                    first_key.clone(),
+                    key.clone(),
+                ));
+            }
+        }
+    }
+
+    let mut map = GenericMap::default();
+    for (key, mut table) in tables {
+        if let GenericValue::Struct(ref mut table) = table {
+            let table_name = table.struct_name.clone();
+            table.replace_name_prefix(&table_name, "_Config");
+            table.struct_name = options.struct_name.clone();
+        }
+        map.keys.push(key);
+        map.values.push(table);
+    }
+    config
+        .fields
+        .insert(ROOT_ARRAY_KEY.to_owned(), GenericValue::Map(map));
+
+    Ok(())
+}
+
+/// The keys and tables of a config which generates a const per
+/// top-level table.
+pub fn root_tables(config: &GenericStruct) -> Option<&GenericMap> {
+    if config.fields.len() != 1 {
+        return None;
+    }
+
+    match config.fields.get(ROOT_ARRAY_KEY) {
+        Some(GenericValue::Map(ref map)) => Some(map),
+        _ => None,
+    }
+}
+
+/// The name of the const for the top-level table at `key`, like
+/// `DEV_CONFIG`.
+pub fn const_name(key: &str, options: &StructOptions) -> String {
+    format!(
+        "{}_{}",
+        naming::upper_snake_case(key),
+        options.real_const_name()
+    )
+}
+
+/// The path of the first place where `value` doesn't have the same
+/// shape as `first`, if any. Nulls and empty arrays fit anything.
+fn shape_difference(first: &GenericValue, value: &GenericValue, path: &str) -> Option<String> {
+    match (first, value) {
+        (GenericValue::Option(None), _) | (_, GenericValue::Option(None)) => None,
+        (GenericValue::Option(Some(ref first)), _) => shape_difference(first, value, path),
+        (_, GenericValue::Option(Some(ref value))) => shape_difference(first, value, path),
+        (GenericValue::Struct(ref first), GenericValue::Struct(ref value)) => {
+            let keys = first.fields.keys().chain(value.fields.keys());
+            for key in keys {
+                let path = paths::join(path, first.original_key(key));
+                match (first.fields.get(key), value.fields.get(key)) {
+                    (Some(first), Some(value)) => {
+                        if let Some(path) = shape_difference(first, value, &path) {
+                            return Some(path);
+                        }
+                    }
+                    _ => return Some(path),
+                }
+            }
+            None
+        }
+        (GenericValue::Array(ref first), GenericValue::Array(ref values)) => {
+            match (first.first(), values.first()) {
+                (Some(first), Some(value)) => shape_difference(first, value, path),
+                _ => None,
+            }
+        }
+        (GenericValue::Tuple(ref first), GenericValue::Tuple(ref values)) => {
+            if first.len() != values.len() {
+                return Some(path.to_owned());
+            }
+            first
+                .iter()
+                .zip(values)
+                .find_map(|(first, value)| shape_difference(first, value, path))
+        }
+        _ if mem::discriminant(first) == mem::discriminant(value) => None,
+        _ => Some(path.to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(name: &str, fields: Vec<(&str, GenericValue)>) -> GenericValue {
+        GenericValue::Struct(GenericStruct {
+            struct_name: name.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        })
+    }
+
+    fn config(tables: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: tables
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    fn per_table_options() -> StructOptions {
+        StructOptions {
+            root_mode: RootMode::ConstPerTopLevelTable,
+            ..StructOptions::default()
+        }
+    }
+
+    #[test]
+    fn tables_become_a_map() {
+        let server = |port| {
+            table(
+                "_Config__dev__server",
+                vec![("port", GenericValue::I64(port))],
+            )
+        };
+        let mut config = config(vec![
+            ("dev", table("_Config__dev", vec![("server", server(80))])),
+            (
+                "prod",
+                table("_Config__prod", vec![("server", server(443))]),
+            ),
+        ]);
+        prepare_root_tables(&mut config, &per_table_options()).unwrap();
+
+        let tables = root_tables(&config).unwrap();
+        assert_eq!(tables.keys, vec!["dev", "prod"]);
+        match tables.values[0] {
+            GenericValue::Struct(ref table) => {
+                assert_eq!(table.struct_name, "Config");
+                match table.fields["server"] {
+                    GenericValue::Struct(ref server) => {
+                        assert_eq!(server.struct_name, "_Config__server")
+                    }
+                    ref other => panic!("Expected a struct, found {:?}", other),
+                }
+            }
+            ref other => panic!("Expected a struct, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn mismatched_tables_are_errors() {
+        let mut config = config(vec![
+            (
+                "dev",
+                table("_Config__dev", vec![("port", GenericValue::I64(80))]),
+            ),
+            (
+                "prod",
+                table("_Config__prod", vec![("port", GenericValue::F64(1.5))]),
+            ),
+        ]);
+
+        match prepare_root_tables(&mut config, &per_table_options()) {
+            Err(GenerationError::MismatchedTopLevelTables(path, first, other)) => {
+                assert_eq!(
+                    (path.as_str(), first.as_str(), other.as_str()),
+                    ("port", "dev", "prod")
+                );
+            }
+            other => panic!("Expected mismatched tables error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn wrapper_mode_is_unchanged() {
+        let mut config = config(vec![("name", GenericValue::String("a".to_owned()))]);
+        prepare_root_tables(&mut config, &StructOptions::default()).unwrap();
+
+        assert!(root_tables(&config).is_none());
+    }
+}
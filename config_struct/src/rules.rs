@@ -0,0 +1,301 @@
+#[cfg(feature = "regex")]
+use std::collections::HashMap;
+
+use crate::{
+    error::{GenerationError, Violation},
+    generation::string_literal,
+    int_types::integer_value,
+    options::{Rule, StructOptions},
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+impl Rule {
+    /// Why the rule can never be followed, if it can't.
+    pub(crate) fn invalid_reason(&self) -> Option<String> {
+        match *self {
+            Rule::IntRange(min, max) if min > max => {
+                Some(format!("the minimum {} is above the maximum {}", min, max))
+            }
+            Rule::FloatRange(min, max) if min.is_nan() || max.is_nan() => {
+                Some("the range can't be NaN".to_owned())
+            }
+            Rule::FloatRange(min, max) if min > max => Some(format!(
+                "the minimum {:?} is above the maximum {:?}",
+                min, max
+            )),
+            Rule::OneOf(ref values) if values.is_empty() => Some("no values are listed".to_owned()),
+            #[cfg(feature = "regex")]
+            Rule::MatchesRegex(ref pattern) => regex::Regex::new(pattern)
+                .err()
+                .map(|error| error.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Check the values in the config against the rules in
+/// `options.validations`, failing with every value which breaks one.
+pub fn check_validations(
+    struct_value: &GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if options.validations.is_empty() {
+        return Ok(());
+    }
+
+    let mut checker = Checker {
+        rules: &options.validations,
+        #[cfg(feature = "regex")]
+        regexes: compile_regexes(&options.validations),
+        violations: Vec::new(),
+    };
+    checker.check_struct(struct_value, "");
+
+    if checker.violations.is_empty() {
+        Ok(())
+    } else {
+        Err(GenerationError::ValidationFailed(checker.violations))
+    }
+}
+
+/// The regexes in `rules`, by their patterns. They were checked to compile
+/// when the options were validated.
+#[cfg(feature = "regex")]
+fn compile_regexes(rules: &[(String, Rule)]) -> HashMap<&str, regex::Regex> {
+    rules
+        .iter()
+        .filter_map(|(_, rule)| match *rule {
+            Rule::MatchesRegex(ref pattern) => Some(pattern.as_str()),
+            _ => None,
+        })
+        .map(|pattern| {
+            let regex = regex::Regex::new(pattern).expect("Regexes are validated with the options");
+            (pattern, regex)
+        })
+        .collect()
+}
+
+struct Checker<'a> {
+    rules: &'a [(String, Rule)],
+    #[cfg(feature = "regex")]
+    regexes: HashMap<&'a str, regex::Regex>,
+    violations: Vec<Violation>,
+}
+
+impl<'a> Checker<'a> {
+    fn check_struct(&mut self, struct_value: &GenericStruct, parent_path: &str) {
+        for (key, value) in &struct_value.fields {
+            self.check_value(value, &paths::join(parent_path, key));
+        }
+    }
+
+    /// Check a value against the rules for its path, then check the values
+    /// inside it against theirs.
+    fn check_value(&mut self, value: &GenericValue, path: &str) {
+        let rules = self.rules;
+        for (pattern, rule) in rules {
+            if paths::matches_glob(pattern, path) {
+                self.apply_rule(value, path, rule);
+            }
+        }
+        self.check_children(value, path);
+    }
+
+    fn check_children(&mut self, value: &GenericValue, path: &str) {
+        match *value {
+            GenericValue::Struct(ref struct_value) => self.check_struct(struct_value, path),
+            GenericValue::Option(Some(ref value)) => self.check_children(value, path),
+            GenericValue::Array(ref values) | GenericValue::Tuple(ref values) => {
+                for (index, value) in values.iter().enumerate() {
+                    self.check_value(value, &paths::join(path, &index.to_string()));
+                }
+            }
+            GenericValue::Map(GenericMap {
+                ref keys,
+                ref values,
+                ..
+            }) => {
+                for (key, value) in keys.iter().zip(values) {
+                    self.check_value(value, &paths::join(path, key));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Check a value against a rule, or each element of an array against
+    /// a rule for its elements.
+    fn apply_rule(&mut self, value: &GenericValue, path: &str, rule: &Rule) {
+        match *value {
+            GenericValue::Option(None) => (),
+            GenericValue::Option(Some(ref value)) => self.apply_rule(value, path, rule),
+            GenericValue::Array(ref values) if *rule != Rule::NonEmptyArray => {
+                for (index, value) in values.iter().enumerate() {
+                    self.apply_rule(value, &paths::join(path, &index.to_string()), rule);
+                }
+            }
+            _ => {
+                if !self.follows(value, rule) {
+                    self.violations.push(Violation {
+                        path: path.to_owned(),
+                        rule: rule.clone(),
+                        value: value_text(value),
+                    });
+                }
+            }
+        }
+    }
+
+    fn follows(&self, value: &GenericValue, rule: &Rule) -> bool {
+        match *rule {
+            Rule::IntRange(min, max) => integer_value(value)
+                .is_some_and(|value| (i128::from(min)..=i128::from(max)).contains(&value)),
+            Rule::FloatRange(min, max) => {
+                number_value(value).is_some_and(|value| (min..=max).contains(&value))
+            }
+            Rule::NonEmptyString => {
+                matches!(*value, GenericValue::String(ref text) if !text.is_empty())
+            }
+            Rule::NonEmptyArray => {
+                matches!(*value, GenericValue::Array(ref values) if !values.is_empty())
+            }
+            Rule::OneOf(ref allowed) => {
+                matches!(*value, GenericValue::String(ref text) if allowed.contains(text))
+            }
+            #[cfg(feature = "regex")]
+            Rule::MatchesRegex(ref pattern) => match *value {
+                GenericValue::String(ref text) => self.regexes[pattern.as_str()].is_match(text),
+                _ => false,
+            },
+        }
+    }
+}
+
+/// The value of a float, or an integer as a float.
+fn number_value(value: &GenericValue) -> Option<f64> {
+    match *value {
+        GenericValue::F32(value) => Some(f64::from(value)),
+        GenericValue::F64(value) => Some(value),
+        _ => integer_value(value).map(|value| value as f64),
+    }
+}
+
+/// The value as it would be written in a config, or a description of it
+/// if it's a table or array.
+fn value_text(value: &GenericValue) -> String {
+    match *value {
+        GenericValue::String(ref text) | GenericValue::DateTime(ref text) => string_literal(text),
+        GenericValue::Bool(value) => value.to_string(),
+        GenericValue::Char(value) => string_literal(&value.to_string()),
+        GenericValue::F32(value) => format!("{:?}", value),
+        GenericValue::F64(value) => format!("{:?}", value),
+        GenericValue::Array(ref values) if values.is_empty() => "an empty array".to_owned(),
+        GenericValue::EmptyArray(_) => "an empty array".to_owned(),
+        GenericValue::Array(_) => "an array".to_owned(),
+        GenericValue::Struct(_) | GenericValue::Map(_) => "a table".to_owned(),
+        _ => integer_value(value)
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| format!("{:?}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options_with_rules(rules: Vec<(&str, Rule)>) -> StructOptions {
+        StructOptions {
+            validations: rules
+                .into_iter()
+                .map(|(path, rule)| (path.to_owned(), rule))
+                .collect(),
+            ..StructOptions::default()
+        }
+    }
+
+    fn make_struct(fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    fn violations(config: &GenericStruct, options: &StructOptions) -> Vec<Violation> {
+        match check_validations(config, options) {
+            Ok(()) => Vec::new(),
+            Err(GenerationError::ValidationFailed(violations)) => violations,
+            Err(other) => panic!("Expected a validation error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn every_violation_is_collected() {
+        let server =
+            |port| GenericValue::Struct(make_struct(vec![("port", GenericValue::I64(port))]));
+        let config = make_struct(vec![
+            (
+                "servers",
+                GenericValue::Array(vec![server(80), server(0), server(70000)]),
+            ),
+            ("name", GenericValue::String(String::new())),
+        ]);
+        let options = options_with_rules(vec![
+            ("servers.*.port", Rule::IntRange(1, 65535)),
+            ("name", Rule::NonEmptyString),
+        ]);
+
+        let violations = violations(&config, &options);
+        let paths = violations
+            .iter()
+            .map(|violation| violation.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["name", "servers.1.port", "servers.2.port"]);
+        assert_eq!(violations[2].value, "70000");
+        assert_eq!(violations[0].value, "\"\"");
+    }
+
+    #[test]
+    fn rules_on_arrays_apply_to_their_elements() {
+        let config = make_struct(vec![
+            (
+                "levels",
+                GenericValue::Array(vec![
+                    GenericValue::String("info".to_owned()),
+                    GenericValue::String("loud".to_owned()),
+                ]),
+            ),
+            ("tags", GenericValue::Array(vec![])),
+            ("ratio", GenericValue::I64(1)),
+            ("missing", GenericValue::Option(None)),
+        ]);
+        let options = options_with_rules(vec![
+            (
+                "levels",
+                Rule::OneOf(vec!["info".to_owned(), "warn".to_owned()]),
+            ),
+            ("tags", Rule::NonEmptyArray),
+            ("ratio", Rule::FloatRange(0.0, 1.0)),
+            ("missing", Rule::NonEmptyString),
+        ]);
+
+        let violations = violations(&config, &options);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].path, "levels.1");
+        assert_eq!(violations[0].value, "\"loud\"");
+        assert_eq!(violations[1].path, "tags");
+        assert_eq!(violations[1].value, "an empty array");
+    }
+
+    #[test]
+    fn impossible_rules_are_invalid() {
+        assert!(Rule::IntRange(2, 1).invalid_reason().is_some());
+        assert!(Rule::FloatRange(0.0, f64::NAN).invalid_reason().is_some());
+        assert!(Rule::OneOf(vec![]).invalid_reason().is_some());
+        assert!(Rule::IntRange(1, 1).invalid_reason().is_none());
+    }
+}
@@ -0,0 +1,62 @@
+//! Formatting generated code with `rustfmt`, when
+//! `StructOptions::rustfmt_output` is set.
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use crate::{
+    diagnostics::{Warning, WarningKind},
+    options::StructOptions,
+};
+
+/// Format the generated code, if the options ask for it.
+///
+/// If `rustfmt` can't be run, or fails, the code is returned unformatted
+/// and a warning says why, since unformatted code still compiles.
+pub fn format_output(code: String, options: &StructOptions, warnings: &mut Vec<Warning>) -> String {
+    if !options.rustfmt_output {
+        return code;
+    }
+
+    match run_rustfmt(&code) {
+        Ok(formatted) => formatted,
+        Err(reason) => {
+            let message = format!("The generated code was not formatted: {}", reason);
+            warnings.push(Warning::new(WarningKind::RustfmtFailed, "", message));
+            code
+        }
+    }
+}
+
+/// Run the `rustfmt` named by the `RUSTFMT` environment variable, as
+/// cargo does, or the one on the `PATH`.
+fn run_rustfmt(code: &str) -> Result<String, String> {
+    let program = std::env::var_os("RUSTFMT").unwrap_or_else(|| "rustfmt".into());
+
+    let mut child = Command::new(&program)
+        .args(["--edition", "2018"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| format!("could not run `{}`: {}", program.to_string_lossy(), error))?;
+
+    // Write from another thread, so a full stdout pipe can't block both.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = code.to_owned();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let output = child
+        .wait_with_output()
+        .map_err(|error| format!("`rustfmt` failed: {}", error))?;
+    let written = writer.join().expect("the writer thread doesn't panic");
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("`rustfmt` failed: {}", stderr.trim()));
+    }
+    written.map_err(|error| format!("could not write to `rustfmt`: {}", error))?;
+
+    String::from_utf8(output.stdout).map_err(|error| error.to_string())
+}
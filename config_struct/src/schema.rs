@@ -0,0 +1,495 @@
+//! The shape of a config, separate from its values, and comparing the
+//! shape of a config against a reference config, to check that a
+//! deployed config still matches the generated struct.
+use std::{fmt, path::Path};
+
+use failure::Fail;
+
+use crate::{
+    descriptions::GeneratedModule,
+    diagnostics::Warning,
+    error::{Error, GenerationError},
+    format::Format,
+    generation::type_string,
+    options::StructOptions,
+    paths, rustfmt,
+    unification::{self, struct_template},
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// The shape of a config: the structs it declares, with their field
+/// names and types, apart from the values of any one config.
+///
+/// A schema can be inferred once, from one config or several merged
+/// together, and then used to generate code for any config with the same
+/// shape. The one-shot functions like
+/// [`generate_config`](fn.generate_config.html) infer a schema from the
+/// config they generate the values of.
+///
+/// # Examples
+/// ```rust
+/// use config_struct::{Format, Schema, StructOptions};
+///
+/// let options = StructOptions::default();
+/// let schema = Schema::infer(Format::Toml, "name = \"dev\"", &options).unwrap()
+///     .merge(&Schema::infer(Format::Toml, "name = \"prod\"\nretries = 3", &options).unwrap())
+///     .unwrap();
+///
+/// let values = config_struct::parse_config(Format::Toml, "name = \"dev\"", &options).unwrap();
+/// let code = schema.generate_code(&values, &options).unwrap();
+/// assert!(code.contains("pub retries: Option<i64>,"));
+/// assert!(code.contains("retries: None,"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Schema {
+    /// A processed config, whose values are only used for their types.
+    template: GenericStruct,
+
+    /// The options the schema was inferred with, which decide the types
+    /// of its fields.
+    options: StructOptions,
+}
+
+/// A field of a [`Schema`](struct.Schema.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaField {
+    /// The dotted path of the field's key, like `server.port`.
+    pub path: String,
+
+    /// The Rust type the field is declared with, like `Option<i64>`.
+    pub type_name: String,
+}
+
+impl Schema {
+    /// Infer the schema of a config string in the given format.
+    pub fn infer(
+        format: Format,
+        source: &str,
+        options: &StructOptions,
+    ) -> Result<Schema, GenerationError> {
+        let config = crate::parse_config(format, source, options)?;
+        Schema::from_struct(&config, options)
+    }
+
+    /// Infer the schema of a config which has already been parsed, such
+    /// as one returned by [`parse_config`](fn.parse_config.html).
+    pub fn from_struct(
+        config: &GenericStruct,
+        options: &StructOptions,
+    ) -> Result<Schema, GenerationError> {
+        options.validate()?;
+
+        let template = crate::process_config(config.clone(), options, &mut Vec::new())?;
+        Ok(Schema::from_processed(template, options))
+    }
+
+    pub(crate) fn from_processed(template: GenericStruct, options: &StructOptions) -> Schema {
+        Schema {
+            template,
+            options: options.clone(),
+        }
+    }
+
+    /// Combine two schemas into one which both fit, as for the elements
+    /// of an array of structs. Fields which are missing from either
+    /// schema become optional.
+    ///
+    /// Fails if a field has different types in the two schemas.
+    pub fn merge(&self, other: &Schema) -> Result<Schema, GenerationError> {
+        let mut merged = self.template.clone();
+        let mut other = other.template.clone();
+
+        unification::unify_struct_set(vec![&mut merged, &mut other], &self.options).map_err(
+            |error| match error {
+                GenerationError::ConflictingArrayElement(path, _) => {
+                    GenerationError::ConflictingSchemas(path)
+                }
+                other => other,
+            },
+        )?;
+
+        let template = struct_template(&[&merged, &other]);
+        Ok(Schema::from_processed(template, &self.options))
+    }
+
+    /// The fields of every struct in the schema, with their types, in
+    /// order of their paths.
+    pub fn fields(&self) -> Vec<SchemaField> {
+        let mut fields = Vec::new();
+        collect_fields(&self.template, "", &self.options, &mut fields);
+        fields.sort_by(|a, b| a.path.cmp(&b.path));
+        fields
+    }
+
+    /// Generate Rust source code declaring the structs of the schema,
+    /// with the given config as their values.
+    ///
+    /// The values are processed with the `options`, which should give
+    /// the same types as the options the schema was inferred with. Values
+    /// for optional fields of the schema are wrapped in `Some`, and those
+    /// missing are `None`. Fails if the values don't otherwise fit the
+    /// schema.
+    pub fn generate_code(
+        &self,
+        values: &GenericStruct,
+        options: &StructOptions,
+    ) -> Result<String, GenerationError> {
+        options.validate()?;
+
+        let mut warnings = Vec::new();
+        let mut values = crate::process_config(values.clone(), options, &mut warnings)?;
+        conform_struct(&mut values, &self.template);
+
+        let mismatches = compare_structs(&values, &self.template, options);
+        if !mismatches.is_empty() {
+            let details = mismatches
+                .iter()
+                .map(|mismatch| format!("\n  {}", mismatch))
+                .collect::<String>();
+            return Err(GenerationError::NonConformingConfig(details));
+        }
+
+        generate_processed(&self.template, &values, options, None, &mut warnings)
+            .map(|module| module.code)
+    }
+}
+
+/// Generate the code for values which have been processed and fit the
+/// processed `template`, which is often the values themselves.
+pub(crate) fn generate_processed(
+    template: &GenericStruct,
+    values: &GenericStruct,
+    options: &StructOptions,
+    source_file: Option<(Format, &Path)>,
+    warnings: &mut Vec<Warning>,
+) -> Result<GeneratedModule, GenerationError> {
+    let sources = source_file
+        .iter()
+        .map(|&(_, path)| path)
+        .collect::<Vec<_>>();
+    let module = crate::generate_items(template, values, options, source_file, None)?;
+    let code = crate::generate_header(options, &sources, &module.types) + &module.code;
+
+    Ok(GeneratedModule {
+        code: rustfmt::format_output(code, options, warnings),
+        types: module.types,
+    })
+}
+
+fn collect_fields(
+    struct_value: &GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+    fields: &mut Vec<SchemaField>,
+) {
+    for (key, value) in &struct_value.fields {
+        let path = paths::join(parent_path, struct_value.original_key(key));
+        fields.push(SchemaField {
+            path: path.clone(),
+            type_name: type_string(value, options),
+        });
+        collect_nested_fields(value, &path, options, fields);
+    }
+}
+
+fn collect_nested_fields(
+    value: &GenericValue,
+    path: &str,
+    options: &StructOptions,
+    fields: &mut Vec<SchemaField>,
+) {
+    match *value {
+        GenericValue::Struct(ref struct_value) => {
+            collect_fields(struct_value, path, options, fields)
+        }
+        GenericValue::Option(Some(ref value)) => {
+            collect_nested_fields(value, path, options, fields)
+        }
+        GenericValue::Array(ref values) | GenericValue::Map(GenericMap { ref values, .. }) => {
+            if let Some(value) = GenericValue::typed_element(values) {
+                collect_nested_fields(value, path, options, fields);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Make processed values fit the optional fields of a schema, and name
+/// their structs as the schema does.
+fn conform_struct(values: &mut GenericStruct, schema: &GenericStruct) {
+    values.struct_name = schema.struct_name.clone();
+
+    for (key, schema_value) in &schema.fields {
+        match values.fields.get_mut(key) {
+            Some(value) => conform_value(value, schema_value),
+            None if matches!(*schema_value, GenericValue::Option(_)) => {
+                values
+                    .fields
+                    .insert(key.clone(), GenericValue::Option(None));
+            }
+            // Reported as a missing key.
+            None => (),
+        }
+    }
+}
+
+fn conform_value(value: &mut GenericValue, schema: &GenericValue) {
+    match (value, schema) {
+        (&mut GenericValue::Option(None), _) => (),
+        (
+            &mut GenericValue::Option(Some(ref mut value)),
+            &GenericValue::Option(Some(ref schema)),
+        ) => conform_value(value, schema),
+        (value, &GenericValue::Option(Some(ref schema))) => {
+            let mut inner = std::mem::replace(value, GenericValue::Unit);
+            conform_value(&mut inner, schema);
+            *value = GenericValue::Option(Some(Box::new(inner)));
+        }
+        (GenericValue::Struct(values), GenericValue::Struct(schema)) => {
+            conform_struct(values, schema)
+        }
+        (&mut GenericValue::Array(ref mut values), &GenericValue::Array(ref schema))
+        | (
+            &mut GenericValue::Map(GenericMap { ref mut values, .. }),
+            &GenericValue::Map(GenericMap {
+                values: ref schema, ..
+            }),
+        ) => {
+            if let Some(schema) = GenericValue::typed_element(schema) {
+                for value in values {
+                    conform_value(value, schema);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// A difference between the shape of a config and the reference config
+/// it was checked against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// A key in the reference config is missing from the config.
+    MissingKey(String),
+
+    /// A key in the config is not in the reference config.
+    ExtraKey(String),
+
+    /// A value in the config has a different type than in the reference
+    /// config.
+    TypeConflict {
+        path: String,
+        expected: String,
+        found: String,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Mismatch::MissingKey(ref path) => write!(f, "Missing key `{}`.", path),
+            Mismatch::ExtraKey(ref path) => write!(f, "Unexpected key `{}`.", path),
+            Mismatch::TypeConflict {
+                ref path,
+                ref expected,
+                ref found,
+            } => write!(
+                f,
+                "Key `{}` has type `{}`, but `{}` was expected.",
+                path, found, expected
+            ),
+        }
+    }
+}
+
+/// An error type for when a config does not match the reference config
+/// it was checked against, or could not be checked.
+#[derive(Debug)]
+pub enum SchemaMismatch {
+    /// Occurs when either config could not be read or parsed.
+    Error(Error),
+
+    /// Occurs when the shapes of the configs differ.
+    Mismatches(Vec<Mismatch>),
+}
+
+impl fmt::Display for SchemaMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SchemaMismatch::Error(ref error) => write!(f, "Failed to check the config: {}", error),
+            SchemaMismatch::Mismatches(ref mismatches) => {
+                write!(f, "The config does not match the reference:")?;
+                for mismatch in mismatches {
+                    write!(f, "\n  {}", mismatch)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Fail for SchemaMismatch {
+    fn cause(&self) -> Option<&dyn Fail> {
+        match *self {
+            SchemaMismatch::Error(ref error) => Some(error),
+            SchemaMismatch::Mismatches(_) => None,
+        }
+    }
+}
+
+impl<E: Into<Error>> From<E> for SchemaMismatch {
+    fn from(error: E) -> Self {
+        SchemaMismatch::Error(error.into())
+    }
+}
+
+/// Compare the field sets and types of two processed configs, ignoring
+/// their values.
+pub fn compare_structs(
+    candidate: &GenericStruct,
+    reference: &GenericStruct,
+    options: &StructOptions,
+) -> Vec<Mismatch> {
+    let type_options = options.for_type_comparison();
+
+    let mut mismatches = Vec::new();
+    compare_fields(candidate, reference, "", &type_options, &mut mismatches);
+    mismatches
+}
+
+fn compare_fields(
+    candidate: &GenericStruct,
+    reference: &GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+    mismatches: &mut Vec<Mismatch>,
+) {
+    for (name, reference_value) in &reference.fields {
+        let path = paths::join(parent_path, reference.original_key(name));
+        match candidate.fields.get(name) {
+            Some(candidate_value) => {
+                compare_values(candidate_value, reference_value, &path, options, mismatches)
+            }
+            None => mismatches.push(Mismatch::MissingKey(path)),
+        }
+    }
+
+    for name in candidate.fields.keys() {
+        if !reference.fields.contains_key(name) {
+            let path = paths::join(parent_path, candidate.original_key(name));
+            mismatches.push(Mismatch::ExtraKey(path));
+        }
+    }
+}
+
+fn compare_values(
+    candidate: &GenericValue,
+    reference: &GenericValue,
+    path: &str,
+    options: &StructOptions,
+    mismatches: &mut Vec<Mismatch>,
+) {
+    match (candidate, reference) {
+        (GenericValue::Struct(ref candidate), GenericValue::Struct(ref reference)) => {
+            compare_fields(candidate, reference, path, options, mismatches)
+        }
+        (GenericValue::Option(Some(ref candidate)), GenericValue::Option(Some(ref reference))) => {
+            compare_values(candidate, reference, path, options, mismatches)
+        }
+        // Without a value, either option could hold anything.
+        (GenericValue::Option(_), GenericValue::Option(_)) => (),
+        (GenericValue::Array(ref candidate), GenericValue::Array(ref reference))
+        | (
+            GenericValue::Map(GenericMap {
+                values: ref candidate,
+                ..
+            }),
+            GenericValue::Map(GenericMap {
+                values: ref reference,
+                ..
+            }),
+        ) => {
+            // Elements have been unified, so the first one has every field.
+            if let (Some(candidate), Some(reference)) = (candidate.first(), reference.first()) {
+                compare_values(candidate, reference, path, options, mismatches);
+            }
+        }
+        (GenericValue::Tuple(ref candidate), GenericValue::Tuple(ref reference))
+            if candidate.len() == reference.len() =>
+        {
+            for (index, (candidate, reference)) in candidate.iter().zip(reference).enumerate() {
+                let path = paths::join(path, &index.to_string());
+                compare_values(candidate, reference, &path, options, mismatches);
+            }
+        }
+        _ => {
+            let expected = type_string(reference, options);
+            let found = type_string(candidate, options);
+            if expected != found {
+                mismatches.push(Mismatch::TypeConflict {
+                    path: path.to_owned(),
+                    expected,
+                    found,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    #[test]
+    fn identical_shapes_match() {
+        let reference = config(vec![
+            ("name", GenericValue::String("a".to_owned())),
+            ("ports", GenericValue::Array(vec![GenericValue::I64(1)])),
+        ]);
+        let candidate = config(vec![
+            ("name", GenericValue::String("b".to_owned())),
+            (
+                "ports",
+                GenericValue::Array(vec![GenericValue::I64(2), GenericValue::I64(3)]),
+            ),
+        ]);
+
+        assert!(compare_structs(&candidate, &reference, &StructOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn differences_are_listed() {
+        let reference = config(vec![
+            ("name", GenericValue::String("a".to_owned())),
+            ("port", GenericValue::I64(1)),
+        ]);
+        let candidate = config(vec![
+            ("port", GenericValue::String("80".to_owned())),
+            ("extra", GenericValue::Bool(true)),
+        ]);
+
+        assert_eq!(
+            compare_structs(&candidate, &reference, &StructOptions::default()),
+            vec![
+                Mismatch::MissingKey("name".to_owned()),
+                Mismatch::TypeConflict {
+                    path: "port".to_owned(),
+                    expected: "i64".to_owned(),
+                    found: "Cow<'static, str>".to_owned(),
+                },
+                Mismatch::ExtraKey("extra".to_owned()),
+            ]
+        );
+    }
+}
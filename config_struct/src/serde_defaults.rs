@@ -0,0 +1,101 @@
+use crate::{
+    cfg_sections::{cfg_attribute, struct_cfgs},
+    fixed_arrays::ArrayEmitContext,
+    generation::{declared_structs, type_string, value_string},
+    options::StructOptions,
+    value::GenericStruct,
+};
+
+/// Generate a function for each field of the root struct and each nested
+/// struct, returning the value from the config, for use with
+/// `MissingFields::UseConst`.
+///
+/// The functions are private associated functions of their struct, named
+/// by [`default_fn_path`](fn.default_fn_path.html). Structs declared for
+/// the elements of an array use the first value found for each field.
+pub fn generate_default_fns(struct_value: &GenericStruct, options: &StructOptions) -> String {
+    let mut buffer = String::new();
+    let cfgs = struct_cfgs(struct_value);
+
+    for struct_value in declared_structs(struct_value) {
+        let functions = struct_value
+            .ordered_fields(options.field_order)
+            .into_iter()
+            .map(|(name, value)| {
+                format!(
+                    "{}    fn {}() -> {} {{\n        {}\n    }}\n",
+                    cfg_attribute(struct_value.field_cfgs.get(name), 4),
+                    default_fn_name(name),
+                    type_string(value, options),
+                    value_string(value, ArrayEmitContext::new(options, 8), options)
+                )
+            })
+            .collect::<Vec<String>>();
+
+        buffer.push_str(&format!(
+            "{}impl {} {{\n{}}}\n\n",
+            cfg_attribute(cfgs.get(&struct_value.struct_name), 0),
+            struct_value.struct_name,
+            functions.join("\n")
+        ));
+    }
+
+    buffer
+}
+
+/// The path of the function returning the default value of a field, for
+/// use in a `#[serde(default = "...")]` attribute.
+pub fn default_fn_path(struct_name: &str, field_name: &str) -> String {
+    format!("{}::{}", struct_name, default_fn_name(field_name))
+}
+
+fn default_fn_name(field_name: &str) -> String {
+    let field_name = field_name.strip_prefix("r#").unwrap_or(field_name);
+    format!("default_{}", field_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::GenericValue;
+
+    #[test]
+    fn functions_return_config_values() {
+        let server = GenericStruct {
+            struct_name: "_Config__server".to_owned(),
+            fields: vec![("port".to_owned(), GenericValue::U16(80))]
+                .into_iter()
+                .collect(),
+            ..GenericStruct::default()
+        };
+        let config = GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![
+                ("r#type".to_owned(), GenericValue::String("x".to_owned())),
+                ("server".to_owned(), GenericValue::Struct(server)),
+            ]
+            .into_iter()
+            .collect(),
+            ..GenericStruct::default()
+        };
+
+        let code = generate_default_fns(&config, &StructOptions::default());
+
+        assert!(code.contains(
+            "impl Config {\n    fn default_type() -> Cow<'static, str> {\n        Cow::Borrowed(\"x\")\n    }"
+        ));
+        assert!(code
+            .contains("    fn default_server() -> _Config__server {\n        _Config__server {"));
+        assert!(code
+            .contains("impl _Config__server {\n    fn default_port() -> u16 {\n        80\n    }"));
+    }
+
+    #[test]
+    fn paths_strip_raw_identifiers() {
+        assert_eq!(default_fn_path("Config", "r#type"), "Config::default_type");
+        assert_eq!(
+            default_fn_path("_Config__a", "port"),
+            "_Config__a::default_port"
+        );
+    }
+}
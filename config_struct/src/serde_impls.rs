@@ -0,0 +1,431 @@
+use crate::{
+    cfg_sections::cfg_attribute,
+    generation::string_literal,
+    naming::pascal_case,
+    options::{MissingFields, StructOptions},
+    renaming, serde_defaults,
+    value::{GenericEnum, GenericStruct, GenericValue},
+};
+
+/// Generate `Serialize` and `Deserialize` impls for a struct, for
+/// `SerdeSupport::ManualImpl`, behaving like the derived ones would.
+///
+/// Fields are (de)serialized by the same keys the derives would be
+/// renamed to, missing fields are handled as `serde_missing_fields` says,
+/// and missing `Option`s are `None`. Unknown keys are ignored.
+pub fn generate_struct_impls(
+    struct_value: &GenericStruct,
+    cfg: Option<&String>,
+    options: &StructOptions,
+) -> String {
+    let (serialize, deserialize) = match options.serde_support.should_derive_ser_de() {
+        Some(directions) => directions,
+        None => return String::new(),
+    };
+
+    let fields = struct_value
+        .ordered_fields(options.field_order)
+        .into_iter()
+        .map(|(name, value)| Field {
+            name,
+            key: serialized_key(struct_value, name, options),
+            binding: format!("field_{}", name.strip_prefix("r#").unwrap_or(name)),
+            cfg: cfg_attribute(struct_value.field_cfgs.get(name), 0),
+            value,
+        })
+        .collect::<Vec<Field>>();
+
+    let mut code = String::new();
+    if serialize {
+        code.push_str(&serialize_struct_impl(struct_value, &fields, cfg));
+    }
+    if deserialize {
+        code.push_str(&deserialize_struct_impl(
+            struct_value,
+            &fields,
+            cfg,
+            options,
+        ));
+    }
+    code
+}
+
+/// A field of a struct, as its impls need it.
+struct Field<'a> {
+    name: &'a str,
+    /// The key the field is (de)serialized by.
+    key: &'a str,
+    /// The local variable holding the field's value while deserializing.
+    binding: String,
+    /// The field's `#[cfg]` attribute, if it has one, without indentation.
+    cfg: String,
+    value: &'a GenericValue,
+}
+
+impl<'a> Field<'a> {
+    /// The field's `#[cfg]` attribute, indented.
+    fn cfg(&self, indentation: usize) -> String {
+        if self.cfg.is_empty() {
+            String::new()
+        } else {
+            format!("{:indent$}{}", "", self.cfg, indent = indentation)
+        }
+    }
+}
+
+/// The key a field is (de)serialized by.
+fn serialized_key<'a>(
+    struct_value: &'a GenericStruct,
+    name: &'a str,
+    options: &StructOptions,
+) -> &'a str {
+    renaming::serde_rename(struct_value, name, options)
+        .unwrap_or_else(|| name.strip_prefix("r#").unwrap_or(name))
+}
+
+fn serialize_struct_impl(
+    struct_value: &GenericStruct,
+    fields: &[Field],
+    cfg: Option<&String>,
+) -> String {
+    let serialize_fields = fields
+        .iter()
+        .map(|field| {
+            let key = string_literal(field.key);
+            if struct_value.absent_fields.contains(field.name) {
+                format!(
+                    "{cfg}        if self.{name}.is_some() {{
+            state.serialize_field({key}, &self.{name})?;
+        }} else {{
+            state.skip_field({key})?;
+        }}
+",
+                    cfg = field.cfg(8),
+                    name = field.name,
+                    key = key
+                )
+            } else {
+                format!(
+                    "{}        state.serialize_field({}, &self.{})?;\n",
+                    field.cfg(8),
+                    key,
+                    field.name
+                )
+            }
+        })
+        .collect::<String>();
+
+    // An empty struct never writes to its state.
+    let state = if fields.is_empty() {
+        "state"
+    } else {
+        "mut state"
+    };
+
+    format!(
+        "{cfg}impl ::serde::Serialize for {name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        use ::serde::ser::SerializeStruct;
+
+        let {state} = serializer.serialize_struct({name_literal}, {len})?;
+{serialize_fields}        state.end()
+    }}
+}}
+
+",
+        cfg = cfg_attribute(cfg, 0),
+        name = struct_value.struct_name,
+        name_literal = string_literal(&struct_value.struct_name),
+        len = fields.len(),
+        state = state,
+        serialize_fields = serialize_fields
+    )
+}
+
+fn deserialize_struct_impl(
+    struct_value: &GenericStruct,
+    fields: &[Field],
+    cfg: Option<&String>,
+    options: &StructOptions,
+) -> String {
+    let std_crate = if options.no_std { "alloc" } else { "std" };
+
+    let bindings = fields
+        .iter()
+        .map(|field| {
+            format!(
+                "{}                let mut {} = None;\n",
+                field.cfg(16),
+                field.binding
+            )
+        })
+        .collect::<String>();
+    let arms = fields
+        .iter()
+        .map(|field| {
+            format!(
+                "{}                        {} => {} = Some(map.next_value()?),\n",
+                field.cfg(24),
+                string_literal(field.key),
+                field.binding
+            )
+        })
+        .collect::<String>();
+    let values = fields
+        .iter()
+        .map(|field| {
+            format!(
+                "{}                    {}: {},\n",
+                field.cfg(20),
+                field.name,
+                missing_value(struct_value, field, options)
+            )
+        })
+        .collect::<String>();
+    let keys = fields
+        .iter()
+        .map(|field| string_literal(field.key))
+        .collect::<Vec<String>>();
+
+    format!(
+        "{cfg}impl<'de> ::serde::Deserialize<'de> for {name} {{
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+        struct __Visitor;
+
+        impl<'de> ::serde::de::Visitor<'de> for __Visitor {{
+            type Value = {name};
+
+            fn expecting(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {{
+                f.write_str({expecting})
+            }}
+
+            fn visit_map<A: ::serde::de::MapAccess<'de>>(self, mut map: A) -> Result<{name}, A::Error> {{
+{bindings}                while let Some(key) = map.next_key::<::{std}::string::String>()? {{
+                    match key.as_str() {{
+{arms}                        _ => {{
+                            map.next_value::<::serde::de::IgnoredAny>()?;
+                        }}
+                    }}
+                }}
+
+                Ok({name} {{
+{values}                }})
+            }}
+        }}
+
+        deserializer.deserialize_struct({name_literal}, &[{keys}], __Visitor)
+    }}
+}}
+
+",
+        cfg = cfg_attribute(cfg, 0),
+        name = struct_value.struct_name,
+        expecting = string_literal(&format!("struct {}", struct_value.struct_name)),
+        bindings = bindings,
+        std = std_crate,
+        arms = arms,
+        values = values,
+        name_literal = string_literal(&struct_value.struct_name),
+        keys = keys.join(", ")
+    )
+}
+
+/// The expression for a field's value, from its binding, which is `None`
+/// if the field was missing.
+fn missing_value(struct_value: &GenericStruct, field: &Field, options: &StructOptions) -> String {
+    match options.serde_missing_fields {
+        MissingFields::UseConst => format!(
+            "{}.unwrap_or_else({})",
+            field.binding,
+            serde_defaults::default_fn_path(&struct_value.struct_name, field.name)
+        ),
+        MissingFields::UseTypeDefault => format!("{}.unwrap_or_default()", field.binding),
+        MissingFields::Error => match *field.value {
+            GenericValue::Option(_) | GenericValue::NoneOf(_) => {
+                format!("{}.unwrap_or_default()", field.binding)
+            }
+            _ => format!(
+                "{}.ok_or_else(|| ::serde::de::Error::missing_field({}))?",
+                field.binding,
+                string_literal(field.key)
+            ),
+        },
+    }
+}
+
+/// Generate `Serialize` and `Deserialize` impls for an enum, for
+/// `SerdeSupport::ManualImpl`. Variants are (de)serialized by their names
+/// in the config.
+pub fn generate_enum_impls(enum_value: &GenericEnum, options: &StructOptions) -> String {
+    let (serialize, deserialize) = match options.serde_support.should_derive_ser_de() {
+        Some(directions) => directions,
+        None => return String::new(),
+    };
+    let name = &enum_value.enum_name;
+
+    let mut code = String::new();
+    if serialize {
+        let arms = enum_value
+            .variants
+            .iter()
+            .enumerate()
+            .map(|(index, variant)| {
+                format!(
+                    "            {}::{} => serializer.serialize_unit_variant({}, {}, {}),\n",
+                    name,
+                    pascal_case(variant),
+                    string_literal(name),
+                    index,
+                    string_literal(variant)
+                )
+            })
+            .collect::<String>();
+
+        code.push_str(&format!(
+            "impl ::serde::Serialize for {name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        match *self {{
+{arms}        }}
+    }}
+}}
+
+",
+            name = name,
+            arms = arms
+        ));
+    }
+
+    if deserialize {
+        let std_crate = if options.no_std { "alloc" } else { "std" };
+        let arms = enum_value
+            .variants
+            .iter()
+            .map(|variant| {
+                format!(
+                    "            {} => Ok({}::{}),\n",
+                    string_literal(variant),
+                    name,
+                    pascal_case(variant)
+                )
+            })
+            .collect::<String>();
+        let variants = enum_value
+            .variants
+            .iter()
+            .map(|variant| string_literal(variant))
+            .collect::<Vec<String>>();
+
+        code.push_str(&format!(
+            "impl<'de> ::serde::Deserialize<'de> for {name} {{
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+        let text = <::{std}::string::String as ::serde::Deserialize>::deserialize(deserializer)?;
+        match text.as_str() {{
+{arms}            other => Err(::serde::de::Error::unknown_variant(other, &[{variants}])),
+        }}
+    }}
+}}
+
+",
+            name = name,
+            std = std_crate,
+            arms = arms,
+            variants = variants.join(", ")
+        ));
+    }
+
+    code
+}
+
+/// Generate `Serialize` and `Deserialize` impls for a newtype from
+/// `newtype_paths`, for `SerdeSupport::ManualImpl`. It's (de)serialized as
+/// the number it holds.
+pub fn generate_newtype_impls(name: &str, options: &StructOptions) -> String {
+    let (serialize, deserialize) = match options.serde_support.should_derive_ser_de() {
+        Some(directions) => directions,
+        None => return String::new(),
+    };
+
+    let mut code = String::new();
+    if serialize {
+        code.push_str(&format!(
+            "impl ::serde::Serialize for {name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        ::serde::Serialize::serialize(&self.0, serializer)
+    }}
+}}
+
+",
+            name = name
+        ));
+    }
+    if deserialize {
+        code.push_str(&format!(
+            "impl<'de> ::serde::Deserialize<'de> for {name} {{
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+        ::serde::Deserialize::deserialize(deserializer).map({name})
+    }}
+}}
+
+",
+            name = name
+        ));
+    }
+
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::SerdeSupport;
+
+    fn options() -> StructOptions {
+        StructOptions {
+            serde_support: SerdeSupport::ManualImpl,
+            ..StructOptions::default()
+        }
+    }
+
+    #[test]
+    fn missing_options_are_none() {
+        let config = GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![
+                ("r#type".to_owned(), GenericValue::String("x".to_owned())),
+                (
+                    "args".to_owned(),
+                    GenericValue::Option(Some(Box::new(GenericValue::I64(1)))),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            ..GenericStruct::default()
+        };
+
+        let code = generate_struct_impls(&config, None, &options());
+
+        assert!(code.contains("state.serialize_field(\"type\", &self.r#type)?;"));
+        assert!(code.contains("\"type\" => field_type = Some(map.next_value()?),"));
+        assert!(code.contains("args: field_args.unwrap_or_default(),"));
+        assert!(code.contains(
+            "r#type: field_type.ok_or_else(|| ::serde::de::Error::missing_field(\"type\"))?,"
+        ));
+        assert!(code.contains("deserialize_struct(\"Config\", &[\"args\", \"type\"], __Visitor)"));
+    }
+
+    #[test]
+    fn enums_use_config_names() {
+        let level = GenericEnum {
+            enum_name: "LogLevel".to_owned(),
+            variants: vec!["info".to_owned(), "warn-only".to_owned()],
+            value: "info".to_owned(),
+        };
+
+        let code = generate_enum_impls(&level, &options());
+
+        assert!(code.contains(
+            "LogLevel::WarnOnly => serializer.serialize_unit_variant(\"LogLevel\", 1, \"warn-only\"),"
+        ));
+        assert!(code.contains("\"warn-only\" => Ok(LogLevel::WarnOnly),"));
+    }
+}
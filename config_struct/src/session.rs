@@ -0,0 +1,359 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    hash::Hasher,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    descriptions::GeneratedModule,
+    diagnostics::Warning,
+    emit_rerun_directive, emit_rerun_directives,
+    error::{file_error, Error, GenerationError},
+    format::Format,
+    generate_parsed_config,
+    options::{
+        ArrayElementNaming, DuplicateKeyBehavior, FieldOrder, FloatSize, FormatOptions,
+        MixedArrayBehavior, NestedNaming, ResourceLimits, StructOptions, ValueTransform,
+        YamlScalarStyle,
+    },
+    parse_config_with_warnings,
+    report::CreateReport,
+    value::GenericStruct,
+    write_destination,
+};
+
+/// A config which is read once, and parsed once for each set of parsing
+/// options, to generate code from repeatedly.
+///
+/// This is for build scripts which generate several modules from the same
+/// large config with different options, like different struct names.
+/// The one-off functions, like [`create_config`](fn.create_config.html),
+/// use a session of their own.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), config_struct::Error> {
+/// use config_struct::{ConfigSession, StructOptions};
+///
+/// let mut session = ConfigSession::open("config.yaml")?;
+/// for name in &["ServerConfig", "ClientConfig"] {
+///     let options = StructOptions {
+///         struct_name: name.to_string(),
+///         ..StructOptions::default()
+///     };
+///     session.create(format!("src/{}.rs", name.to_lowercase()), &options)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ConfigSession {
+    format: Format,
+    path: Option<PathBuf>,
+    source: String,
+    parsed: Vec<(ParseOptions, GenericStruct, Vec<Warning>)>,
+}
+
+/// The options which change how a config is parsed. Configs parsed with
+/// the same ones are the same.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ParseOptions {
+    default_float_size: FloatSize,
+    duplicate_keys: DuplicateKeyBehavior,
+    max_depth: usize,
+    resource_limits: ResourceLimits,
+    mixed_arrays: MixedArrayBehavior,
+    yaml_scalars: YamlScalarStyle,
+    format_options: FormatOptions,
+    preserve_comments: bool,
+    source_order: bool,
+}
+
+impl ParseOptions {
+    fn new(options: &StructOptions) -> Self {
+        ParseOptions {
+            default_float_size: options.default_float_size,
+            duplicate_keys: options.duplicate_keys,
+            max_depth: options.max_depth,
+            resource_limits: options.resource_limits,
+            mixed_arrays: options.mixed_arrays,
+            yaml_scalars: options.yaml_scalars,
+            format_options: options.format_options.clone(),
+            preserve_comments: options.preserve_comments,
+            source_order: options.field_order == FieldOrder::SourceOrder,
+        }
+    }
+}
+
+impl ConfigSession {
+    /// Read a config file, with its format auto-detected from its
+    /// extension.
+    pub fn open<P: AsRef<Path>>(filepath: P) -> Result<Self, Error> {
+        let filepath = filepath.as_ref();
+        let format = Format::from_filename(filepath)?;
+        Self::open_with_format(format, filepath)
+    }
+
+    /// Read a config file of an explicit format.
+    pub fn open_with_format<P: AsRef<Path>>(format: Format, filepath: P) -> Result<Self, Error> {
+        let filepath = filepath.as_ref();
+        let source = std::fs::read_to_string(filepath).map_err(file_error(filepath))?;
+
+        Ok(ConfigSession {
+            format,
+            path: Some(filepath.to_owned()),
+            source,
+            parsed: Vec::new(),
+        })
+    }
+
+    /// Start a session with a config string in some specified format.
+    pub fn with_source<S: Into<String>>(format: Format, source: S) -> Self {
+        ConfigSession {
+            format,
+            path: None,
+            source: source.into(),
+            parsed: Vec::new(),
+        }
+    }
+
+    /// Generate Rust source code defining structs based on the config.
+    ///
+    /// This behaves like
+    /// [`generate_config_with_format`](fn.generate_config_with_format.html),
+    /// but only parses the config the first time it's called with each
+    /// set of parsing options, like `default_float_size`.
+    pub fn generate(&mut self, options: &StructOptions) -> Result<String, GenerationError> {
+        self.generate_with(options, &mut Vec::new(), &mut Vec::new())
+            .map(|module| module.code)
+    }
+
+    pub(crate) fn generate_with(
+        &mut self,
+        options: &StructOptions,
+        warnings: &mut Vec<Warning>,
+        included: &mut Vec<PathBuf>,
+    ) -> Result<GeneratedModule, GenerationError> {
+        options.validate()?;
+
+        let (config, parse_warnings) = self.parse(options)?;
+        warnings.extend(parse_warnings);
+        generate_parsed_config(
+            self.format,
+            &self.source,
+            config,
+            options,
+            self.path.as_deref(),
+            warnings,
+            included,
+        )
+    }
+
+    /// Generate a Rust module containing struct definitions based on the
+    /// config, and report what was done, like
+    /// [`create_config_ext`](fn.create_config_ext.html).
+    ///
+    /// With `cache_source_hash`, nothing is generated if the destination
+    /// is up to date with the config and options. The report then has the
+    /// destination's contents as its code, and no warnings.
+    pub fn create<P: AsRef<Path>>(
+        &mut self,
+        destination: P,
+        options: &StructOptions,
+    ) -> Result<CreateReport, Error> {
+        let destination = destination.as_ref();
+        if let Some(ref path) = self.path {
+            emit_rerun_directive(path, options);
+        }
+
+        let inputs_hash = self.inputs_hash(options);
+        if let Some(inputs_hash) = inputs_hash {
+            if let Some(code) = up_to_date_code(destination, inputs_hash) {
+                return Ok(CreateReport {
+                    destination: destination.to_owned(),
+                    written: false,
+                    changed: false,
+                    bytes: code.len(),
+                    code,
+                    warnings: Vec::new(),
+                });
+            }
+        }
+
+        let mut warnings = Vec::new();
+        let mut included = Vec::new();
+        let output = self
+            .generate_with(options, &mut warnings, &mut included)?
+            .code;
+        emit_rerun_directives(&included, options);
+
+        let mut report = write_destination(destination, output, options)?;
+        report.warnings = warnings;
+
+        if let Some(inputs_hash) = inputs_hash {
+            let hashes = format!("{:016x} {:016x}\n", inputs_hash, hash(&report.code));
+            let hash_path = hash_path(destination);
+            std::fs::write(&hash_path, hashes).map_err(file_error(&hash_path))?;
+        }
+
+        Ok(report)
+    }
+
+    /// The config parsed with the parsing options in `options`, and the
+    /// warnings from parsing it.
+    fn parse(
+        &mut self,
+        options: &StructOptions,
+    ) -> Result<(GenericStruct, Vec<Warning>), GenerationError> {
+        let parse_options = ParseOptions::new(options);
+        let parsed = self
+            .parsed
+            .iter()
+            .find(|(parsed_options, _, _)| *parsed_options == parse_options);
+
+        if let Some((_, config, warnings)) = parsed {
+            return Ok((config.clone(), warnings.clone()));
+        }
+
+        let mut warnings = Vec::new();
+        let config = parse_config_with_warnings(self.format, &self.source, options, &mut warnings)
+            .map_err(|error| match self.path {
+                Some(ref path) => error.in_file(path),
+                None => error,
+            })?;
+        self.parsed
+            .push((parse_options, config.clone(), warnings.clone()));
+
+        Ok((config, warnings))
+    }
+
+    /// A hash of everything the code generated with `options` depends on,
+    /// if `cache_source_hash` applies to it.
+    fn inputs_hash(&self, options: &StructOptions) -> Option<u64> {
+        let substitutes_env_vars = options
+            .value_transforms
+            .iter()
+            .any(|(_, transform)| *transform == ValueTransform::EnvSubstitute);
+        if !options.cache_source_hash
+            || !options.write_only_if_changed
+            || options.dry_run
+            || options.resolve_includes.is_some()
+            || substitutes_env_vars
+        {
+            return None;
+        }
+
+        let mut hasher = Fnv1a::default();
+        hasher.write(env!("CARGO_PKG_VERSION").as_bytes());
+        hasher.write(format!("{:?} {:?}", self.format, self.path).as_bytes());
+        hasher.write_u64(hash(&self.source));
+        hasher.write(options_text(options).as_bytes());
+        Some(hasher.finish())
+    }
+}
+
+/// The options, written out the same way every time.
+///
+/// The `Debug` output of a `HashMap` is in an arbitrary order, so the
+/// maps are written separately, sorted.
+fn options_text(options: &StructOptions) -> String {
+    fn sorted<V: Debug>(map: &HashMap<String, V>) -> String {
+        format!("{:?}", map.iter().collect::<BTreeMap<_, _>>())
+    }
+
+    let mut options = options.clone();
+    let mut names = HashMap::new();
+    if let NestedNaming::Explicit(ref mut map) = options.nested_naming {
+        names = std::mem::take(map);
+    }
+    let mut singulars = HashMap::new();
+    if let ArrayElementNaming::Singular(ref mut map) = options.array_element_naming {
+        singulars = std::mem::take(map);
+    }
+    let maps = [
+        sorted(&names),
+        sorted(&singulars),
+        sorted(&std::mem::take(&mut options.serde_attributes.by_struct)),
+        sorted(&std::mem::take(&mut options.empty_array_types)),
+        sorted(&std::mem::take(&mut options.option_types)),
+        sorted(&std::mem::take(&mut options.enum_fields)),
+        sorted(&std::mem::take(&mut options.newtype_paths)),
+        sorted(&std::mem::take(&mut options.field_renames)),
+        sorted(&std::mem::take(&mut options.field_int_types)),
+        sorted(&std::mem::take(&mut options.cfg_sections)),
+    ];
+    format!("{:?} {:?}", options, maps)
+}
+
+/// The existing code at `destination`, if it was generated from inputs
+/// with the given hash and hasn't been changed since.
+fn up_to_date_code(destination: &Path, inputs_hash: u64) -> Option<String> {
+    let hashes = std::fs::read_to_string(hash_path(destination)).ok()?;
+    let code = std::fs::read_to_string(destination).ok()?;
+
+    let expected = format!("{:016x} {:016x}", inputs_hash, hash(&code));
+    if hashes.trim_end() == expected {
+        Some(code)
+    } else {
+        None
+    }
+}
+
+/// The file next to `destination` which holds the hashes of its inputs
+/// and contents.
+fn hash_path(destination: &Path) -> PathBuf {
+    let mut file_name = destination.file_name().unwrap_or_default().to_owned();
+    file_name.push(".hash");
+    destination.with_file_name(file_name)
+}
+
+fn hash(text: &str) -> u64 {
+    let mut hasher = Fnv1a::default();
+    hasher.write(text.as_bytes());
+    hasher.finish()
+}
+
+/// The 64-bit FNV-1a hash, which, unlike the standard library's hashers,
+/// is the same in every build, so it can be saved between builds.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_matches_reference_values() {
+        assert_eq!(hash(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(hash("foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn hash_files_are_next_to_the_destination() {
+        assert_eq!(
+            hash_path(Path::new("src/config.rs")),
+            Path::new("src/config.rs.hash")
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    error::{GenerationError, SourceLocation},
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue, Span},
+};
+
+/// Attach the spans in `spans`, indexed by path, to the fields whose keys
+/// they locate.
+pub fn apply_spans(struct_value: &mut GenericStruct, spans: &HashMap<String, Span>) {
+    if spans.is_empty() {
+        return;
+    }
+
+    apply_to_struct(struct_value, "", spans)
+}
+
+fn apply_to_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    spans: &HashMap<String, Span>,
+) {
+    for (key, value) in &mut struct_value.fields {
+        let path = paths::join(parent_path, key);
+
+        if let Some(span) = spans.get(&path) {
+            struct_value.field_spans.insert(key.clone(), *span);
+        }
+        apply_to_value(value, &path, spans);
+    }
+}
+
+fn apply_to_value(value: &mut GenericValue, path: &str, spans: &HashMap<String, Span>) {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => apply_to_struct(struct_value, path, spans),
+        GenericValue::Option(Some(ref mut value)) => apply_to_value(value, path, spans),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Tuple(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                apply_to_value(value, path, spans);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// The spans of the fields of a config, indexed by the paths of their
+/// keys. Where elements of an array locate the same path differently,
+/// the first is kept.
+pub fn key_spans(struct_value: &GenericStruct) -> HashMap<String, Span> {
+    let mut spans = HashMap::new();
+    collect_struct(struct_value, "", &mut spans);
+    spans
+}
+
+fn collect_struct(
+    struct_value: &GenericStruct,
+    parent_path: &str,
+    spans: &mut HashMap<String, Span>,
+) {
+    for (name, value) in &struct_value.fields {
+        let path = paths::join(parent_path, struct_value.original_key(name));
+
+        if let Some(span) = struct_value.field_spans.get(name) {
+            spans.entry(path.clone()).or_insert(*span);
+        }
+        collect_value(value, &path, spans);
+    }
+}
+
+fn collect_value(value: &GenericValue, path: &str, spans: &mut HashMap<String, Span>) {
+    match *value {
+        GenericValue::Struct(ref struct_value) => collect_struct(struct_value, path, spans),
+        GenericValue::Option(Some(ref value)) => collect_value(value, path, spans),
+        GenericValue::Array(ref values)
+        | GenericValue::Tuple(ref values)
+        | GenericValue::Map(GenericMap { ref values, .. }) => {
+            for value in values {
+                collect_value(value, path, spans);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Wrap an error concerning a field with where the field's key is in
+/// `file`, whose text is `source`.
+///
+/// Keys which weren't located, like those in multi-line arrays, are given
+/// the location of the nearest key containing them.
+pub fn locate_error(
+    error: GenerationError,
+    spans: &HashMap<String, Span>,
+    file: &Path,
+    source: &str,
+) -> GenerationError {
+    let span = error.path().and_then(|path| {
+        let parent_paths = path.match_indices('.').map(|(index, _)| &path[..index]);
+        std::iter::once(path)
+            .chain(parent_paths.rev())
+            .find_map(|path| spans.get(path))
+            .copied()
+    });
+
+    match span {
+        Some(span) => {
+            let source_line = source.lines().nth(span.line - 1).unwrap_or_default();
+            let location = SourceLocation {
+                file: file.to_owned(),
+                span,
+                source_line: source_line.to_owned(),
+            };
+            GenerationError::Located(Box::new(error), location)
+        }
+        None => error,
+    }
+}
+
+/// Find where the keys and tables in a TOML file are.
+///
+/// Dotted keys and table headers also locate the tables they imply, unless
+/// those are located elsewhere first. Keys in arrays of tables and inline
+/// tables share the path of the array, so are located in its first
+/// element.
+#[cfg(feature = "toml-parsing")]
+pub fn toml_key_spans(source: &str) -> HashMap<String, Span> {
+    use crate::comments::toml_scanner::{self, ValueScanner};
+
+    let mut spans = HashMap::new();
+    let mut table = String::new();
+    let mut value = ValueScanner::default();
+
+    for (index, line) in source.lines().enumerate() {
+        // Lines continuing a multi-line array or string aren't keys.
+        if value.is_open() {
+            value.scan(line);
+            continue;
+        }
+
+        let locator = LineLocator {
+            line,
+            line_number: index + 1,
+        };
+        let content = line.trim_start();
+        if let Some(header) = content.strip_prefix('[') {
+            let header = header.strip_prefix('[').unwrap_or(header);
+            table = match toml_scanner::parse_key(header) {
+                Some((keys, rest)) if rest.trim_start().starts_with(']') => {
+                    insert_keys(&mut spans, "", &keys, locator.span(header.trim_start()))
+                }
+                // Keys can't be located in a table which wasn't understood.
+                _ => "\0".to_owned(),
+            };
+        } else if let Some((keys, rest)) = toml_scanner::parse_key(content) {
+            if let Some(rest) = rest.trim_start().strip_prefix('=') {
+                let path = insert_keys(&mut spans, &table, &keys, locator.span(content));
+                locator.scan_inline_value(rest, &path, &mut spans);
+                value.scan(rest);
+            }
+        }
+    }
+
+    spans
+}
+
+/// Locate a possibly dotted key, and any tables it implies, returning its
+/// path.
+#[cfg(feature = "toml-parsing")]
+fn insert_keys(
+    spans: &mut HashMap<String, Span>,
+    parent_path: &str,
+    keys: &[String],
+    span: Span,
+) -> String {
+    let mut path = parent_path.to_owned();
+    for key in keys {
+        path = paths::join(&path, key);
+        spans.entry(path.clone()).or_insert(span);
+    }
+    path
+}
+
+/// Finds spans within a line of TOML.
+#[cfg(feature = "toml-parsing")]
+struct LineLocator<'a> {
+    line: &'a str,
+    line_number: usize,
+}
+
+#[cfg(feature = "toml-parsing")]
+impl<'a> LineLocator<'a> {
+    /// The span of the start of `rest`, which is the end of the line.
+    fn span(&self, rest: &str) -> Span {
+        let start = self.line.len() - rest.len();
+        Span {
+            line: self.line_number,
+            column: self.line[..start].chars().count() + 1,
+        }
+    }
+
+    /// Locate the keys of any inline tables in the value at the start of
+    /// `text`, returning the text after the value, or `None` if the value
+    /// doesn't end on this line.
+    fn scan_inline_value<'t>(
+        &self,
+        text: &'t str,
+        path: &str,
+        spans: &mut HashMap<String, Span>,
+    ) -> Option<&'t str> {
+        use crate::comments::toml_scanner;
+
+        let text = text.trim_start();
+        if let Some(mut rest) = text.strip_prefix('{') {
+            loop {
+                rest = rest.trim_start();
+                if let Some(after) = rest.strip_prefix('}') {
+                    return Some(after);
+                }
+                let (keys, after) = toml_scanner::parse_key(rest)?;
+                let key_path = insert_keys(spans, path, &keys, self.span(rest));
+                let after = after.trim_start().strip_prefix('=')?;
+                rest = self
+                    .scan_inline_value(after, &key_path, spans)?
+                    .trim_start();
+                rest = rest.strip_prefix(',').unwrap_or(rest);
+            }
+        } else if let Some(mut rest) = text.strip_prefix('[') {
+            loop {
+                rest = rest.trim_start();
+                if let Some(after) = rest.strip_prefix(']') {
+                    return Some(after);
+                }
+                rest = self.scan_inline_value(rest, path, spans)?.trim_start();
+                rest = rest.strip_prefix(',').unwrap_or(rest);
+            }
+        } else {
+            skip_scalar(text)
+        }
+    }
+}
+
+/// The text after the string, number or other scalar at the start of
+/// `text`, if it ends on this line.
+#[cfg(feature = "toml-parsing")]
+fn skip_scalar(text: &str) -> Option<&str> {
+    for quotes in &["\"\"\"", "'''"] {
+        if let Some(rest) = text.strip_prefix(quotes) {
+            let end = rest.find(quotes)?;
+            return Some(&rest[end + quotes.len()..]);
+        }
+    }
+    if let Some(rest) = text.strip_prefix('"') {
+        let mut escaped = false;
+        let end = rest.find(|c| {
+            let end = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            end
+        })?;
+        return Some(&rest[end + 1..]);
+    }
+    if let Some(rest) = text.strip_prefix('\'') {
+        let end = rest.find('\'')?;
+        return Some(&rest[end + 1..]);
+    }
+
+    let end = text.find([',', ']', '}', '#']).unwrap_or(text.len());
+    if text[..end].trim().is_empty() {
+        None
+    } else {
+        Some(&text[end..])
+    }
+}
+
+#[cfg(all(test, feature = "toml-parsing"))]
+mod tests {
+    use super::*;
+
+    fn span(line: usize, column: usize) -> Span {
+        Span { line, column }
+    }
+
+    #[test]
+    fn keys_and_tables_are_located() {
+        let spans = toml_key_spans(
+            "name = \"app\"\n\n[server]\n  port = 80\n\n[[plugins]]\nid = 1\n\n[[plugins]]\nid = 2\n",
+        );
+
+        assert_eq!(spans["name"], span(1, 1));
+        assert_eq!(spans["server"], span(3, 2));
+        assert_eq!(spans["server.port"], span(4, 3));
+        assert_eq!(spans["plugins"], span(6, 3));
+        assert_eq!(spans["plugins.id"], span(7, 1));
+    }
+
+    #[test]
+    fn dotted_keys_locate_their_tables() {
+        let spans = toml_key_spans("[a.b]\nc.d = 1\n");
+
+        assert_eq!(spans["a"], span(1, 2));
+        assert_eq!(spans["a.b"], span(1, 2));
+        assert_eq!(spans["a.b.c"], span(2, 1));
+        assert_eq!(spans["a.b.c.d"], span(2, 1));
+    }
+
+    #[test]
+    fn inline_tables_are_located_like_tables() {
+        let spans = toml_key_spans(
+            "server = { host = \"a, b}\", port = 80 }\npoints = [{ x = 1 }, { y = 2 }]\n",
+        );
+
+        assert_eq!(spans["server"], span(1, 1));
+        assert_eq!(spans["server.host"], span(1, 12));
+        assert_eq!(spans["server.port"], span(1, 28));
+        assert_eq!(spans["points.x"], span(2, 13));
+        assert_eq!(spans["points.y"], span(2, 24));
+    }
+
+    #[test]
+    fn multi_line_values_are_skipped() {
+        let spans =
+            toml_key_spans("list = [\n  1,\n  2,\n]\ntext = \"\"\"\na = 1\n\"\"\"\nb = 2\n");
+
+        assert!(!spans.contains_key("a"));
+        assert_eq!(spans["b"], span(8, 1));
+    }
+}
@@ -0,0 +1,254 @@
+use crate::{
+    error::GenerationError,
+    fixed_arrays::ArrayEmitContext,
+    generation,
+    options::{ConstFallback, MapType, StructOptions},
+    paths, root_arrays, root_tables,
+    value::{GenericMap, GenericStruct, GenericValue},
+    versions,
+};
+
+/// How the root value of the config is declared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootValue {
+    Const,
+    LazyStatic,
+    RuntimeFn,
+}
+
+impl RootValue {
+    /// Choose how to declare the root value, falling back on
+    /// `options.const_fallback` only if some field can't be built in a
+    /// const.
+    pub fn choose(
+        config: &GenericStruct,
+        options: &StructOptions,
+    ) -> Result<Self, GenerationError> {
+        let path = match non_const_path(config, "", options) {
+            Some(path) => path,
+            None => return Ok(RootValue::Const),
+        };
+
+        match options.const_fallback {
+            ConstFallback::Error => Err(GenerationError::NonConstField(path)),
+            ConstFallback::LazyStatic => Ok(RootValue::LazyStatic),
+            ConstFallback::RuntimeFn => Ok(RootValue::RuntimeFn),
+        }
+    }
+
+    /// Generate the declaration of the root value.
+    ///
+    /// If the root of the config is an array, the root value is a slice
+    /// of its elements. With a const per top-level table, there is a root
+    /// value for each table instead.
+    pub fn declaration(self, config: &GenericStruct, options: &StructOptions) -> String {
+        if let Some(tables) = root_tables::root_tables(config) {
+            return tables
+                .keys
+                .iter()
+                .zip(&tables.values)
+                .filter_map(|(key, table)| match *table {
+                    GenericValue::Struct(ref table) => Some(self.declare(
+                        &root_tables::const_name(key, options),
+                        &RootParts::from_struct(table, options),
+                    )),
+                    _ => None,
+                })
+                .collect();
+        }
+
+        self.declare(&options.real_const_name(), &RootParts::new(config, options))
+    }
+
+    fn declare(self, const_name: &str, parts: &RootParts) -> String {
+        match self {
+            RootValue::Const => format!(
+                "pub const {}: {} = {};\n",
+                const_name, parts.const_type, parts.const_value
+            ),
+            RootValue::LazyStatic => format!(
+                "pub static {}: ::std::sync::LazyLock<{}> = ::std::sync::LazyLock::new(|| {});\n",
+                const_name, parts.owned_type, parts.owned_value
+            ),
+            RootValue::RuntimeFn => format!(
+                "pub fn {}() -> &'static {} {{
+    static {}: ::std::sync::OnceLock<{}> = ::std::sync::OnceLock::new();
+    {}.get_or_init(|| {})
+}}
+",
+                const_name.to_lowercase(),
+                parts.borrowed_type,
+                const_name,
+                parts.owned_type,
+                const_name,
+                parts.owned_value
+            ),
+        }
+    }
+
+    /// An expression for a `&'static` reference to the root value.
+    pub fn reference(self, options: &StructOptions) -> String {
+        match self {
+            RootValue::Const | RootValue::LazyStatic => format!("&{}", options.real_const_name()),
+            RootValue::RuntimeFn => format!("{}()", options.real_const_name().to_lowercase()),
+        }
+    }
+
+    /// An expression for the root value, which can be cloned.
+    pub fn expression(self, options: &StructOptions) -> String {
+        match self {
+            RootValue::Const | RootValue::LazyStatic => options.real_const_name(),
+            RootValue::RuntimeFn => format!("{}()", options.real_const_name().to_lowercase()),
+        }
+    }
+}
+
+/// The types and values used to declare the root value.
+struct RootParts {
+    const_type: String,
+    const_value: String,
+    owned_type: String,
+    owned_value: String,
+    borrowed_type: String,
+}
+
+impl RootParts {
+    fn new(config: &GenericStruct, options: &StructOptions) -> Self {
+        let struct_name = &options.struct_name;
+
+        match root_arrays::root_elements(config) {
+            Some(elements) => {
+                let elements = elements
+                    .iter()
+                    .filter_map(|element| match *element {
+                        GenericValue::Struct(ref element) => Some(generation::struct_value_string(
+                            element,
+                            ArrayEmitContext::new(options, 0),
+                            options,
+                        )),
+                        _ => None,
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ");
+
+                RootParts {
+                    const_type: format!("&[{}]", struct_name),
+                    const_value: format!("&[{}]", elements),
+                    owned_type: format!("Vec<{}>", struct_name),
+                    owned_value: format!("vec![{}]", elements),
+                    borrowed_type: format!("[{}]", struct_name),
+                }
+            }
+            None => RootParts::from_struct(config, options),
+        }
+    }
+
+    fn from_struct(struct_value: &GenericStruct, options: &StructOptions) -> Self {
+        let struct_name = &options.struct_name;
+        let value = generation::struct_value_string(
+            struct_value,
+            ArrayEmitContext::new(options, 0),
+            options,
+        );
+
+        RootParts {
+            const_type: struct_name.clone(),
+            const_value: value.clone(),
+            owned_type: struct_name.clone(),
+            owned_value: value,
+            borrowed_type: struct_name.clone(),
+        }
+    }
+}
+
+/// The path of the first field which can't be built in a const, if any.
+fn non_const_path(
+    struct_value: &GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+) -> Option<String> {
+    struct_value.fields.iter().find_map(|(key, value)| {
+        let path = paths::join(parent_path, struct_value.original_key(key));
+        non_const_value_path(value, path, options)
+    })
+}
+
+fn non_const_value_path(
+    value: &GenericValue,
+    path: String,
+    options: &StructOptions,
+) -> Option<String> {
+    match *value {
+        GenericValue::Map(_) if options.map_type == MapType::HashMap => Some(path),
+        GenericValue::Version(ref text) if !versions::version_is_const(text, options) => Some(path),
+        GenericValue::Struct(ref struct_value) => non_const_path(struct_value, &path, options),
+        GenericValue::Option(Some(ref value)) => non_const_value_path(value, path, options),
+        GenericValue::Array(ref values)
+        | GenericValue::Tuple(ref values)
+        | GenericValue::Map(GenericMap { ref values, .. }) => values
+            .iter()
+            .find_map(|value| non_const_value_path(value, path.clone(), options)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_map() -> GenericStruct {
+        let map = GenericMap {
+            keys: vec!["a".to_owned()],
+            values: vec![GenericValue::I64(1)],
+        };
+        let server = GenericStruct {
+            struct_name: "_Config__server".to_owned(),
+            fields: vec![("scores".to_owned(), GenericValue::Map(map))]
+                .into_iter()
+                .collect(),
+            ..GenericStruct::default()
+        };
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![("server".to_owned(), GenericValue::Struct(server))]
+                .into_iter()
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    fn options_with(map_type: MapType, const_fallback: ConstFallback) -> StructOptions {
+        StructOptions {
+            map_type,
+            const_fallback,
+            ..StructOptions::default()
+        }
+    }
+
+    #[test]
+    fn const_when_nothing_requires_fallback() {
+        let config = config_with_map();
+        let options = options_with(MapType::Slice, ConstFallback::Error);
+
+        assert_eq!(
+            RootValue::choose(&config, &options).unwrap(),
+            RootValue::Const
+        );
+    }
+
+    #[test]
+    fn fallback_when_required() {
+        let config = config_with_map();
+
+        let options = options_with(MapType::HashMap, ConstFallback::RuntimeFn);
+        let root_value = RootValue::choose(&config, &options).unwrap();
+        assert_eq!(root_value, RootValue::RuntimeFn);
+        assert_eq!(root_value.reference(&options), "config()");
+
+        let options = options_with(MapType::HashMap, ConstFallback::Error);
+        match RootValue::choose(&config, &options) {
+            Err(GenerationError::NonConstField(path)) => assert_eq!(path, "server.scores"),
+            other => panic!("Expected non-const field error, found {:?}", other),
+        }
+    }
+}
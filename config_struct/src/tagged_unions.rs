@@ -0,0 +1,549 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    diagnostics::{Warning, WarningKind},
+    enums::enum_name_for_path,
+    error::GenerationError,
+    fixed_arrays::ArrayEmitContext,
+    generation::{derive_string, doc_string, string_literal, type_string, value_string},
+    interop::CLAP_PARSER,
+    naming::is_keyword,
+    options::{MissingFields, StructOptions, TaggedUnionSpec},
+    paths, renaming,
+    unification::struct_template,
+    validation::valid_identifier,
+    value::{GenericMap, GenericStruct, GenericTaggedUnion, GenericValue},
+};
+
+/// Replace the tables at the paths listed in `options.tagged_unions` with
+/// the variants their tags choose, checking that each tag is one of those
+/// listed.
+pub fn apply_tagged_unions(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), GenerationError> {
+    if options.tagged_unions.is_empty() {
+        return Ok(());
+    }
+
+    let mut specs = BTreeMap::new();
+    let mut enum_paths = options
+        .enum_fields
+        .keys()
+        .map(|path| (enum_name_for_path(path), path.as_str()))
+        .collect::<BTreeMap<_, _>>();
+    for spec in &options.tagged_unions {
+        validate_spec(spec, &mut enum_paths)?;
+        specs.insert(spec.path.as_str(), spec);
+    }
+
+    // The variants found at each path.
+    let mut found = BTreeMap::new();
+    apply_to_struct(struct_value, "", &specs, &mut found)?;
+
+    for (path, variants) in found {
+        let spec = specs[path.as_str()];
+        for (tag_value, name) in &spec.variants {
+            if !variants.contains(name) {
+                let message = format!(
+                    "No table at `{}` has the tag `{}`, so `{}::{}` has no fields.",
+                    path,
+                    tag_value,
+                    enum_name_for_path(&path),
+                    name
+                );
+                warnings.push(Warning::new(
+                    WarningKind::EmptyVariant,
+                    path.clone(),
+                    message,
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_spec<'a>(
+    spec: &'a TaggedUnionSpec,
+    enum_paths: &mut BTreeMap<String, &'a str>,
+) -> Result<(), GenerationError> {
+    let enum_name = enum_name_for_path(&spec.path);
+    if !valid_identifier(&enum_name) || is_keyword(&enum_name) {
+        return Err(GenerationError::InvalidEnumName(spec.path.clone()));
+    }
+
+    let mut tag_values = BTreeSet::new();
+    let mut names = BTreeSet::new();
+    for (tag_value, name) in &spec.variants {
+        if !valid_identifier(name)
+            || is_keyword(name)
+            || !tag_values.insert(tag_value)
+            || !names.insert(name)
+        {
+            return Err(GenerationError::InvalidEnumVariant(
+                spec.path.clone(),
+                name.clone(),
+            ));
+        }
+    }
+
+    if let Some(other_path) = enum_paths.get(&enum_name) {
+        return Err(GenerationError::ConflictingEnumNames(
+            enum_name,
+            (*other_path).into(),
+            spec.path.clone(),
+        ));
+    }
+    enum_paths.insert(enum_name, &spec.path);
+
+    Ok(())
+}
+
+fn apply_to_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    specs: &BTreeMap<&str, &TaggedUnionSpec>,
+    found: &mut BTreeMap<String, BTreeSet<String>>,
+) -> Result<(), GenerationError> {
+    let original_keys = struct_value.original_keys.clone();
+    for (key, value) in &mut struct_value.fields {
+        let key = original_keys.get(key).unwrap_or(key);
+        let path = paths::join(parent_path, key);
+
+        match specs.get(path.as_str()) {
+            Some(spec) => convert_to_union(value, &path, spec, specs, found)?,
+            None => apply_to_value(value, &path, specs, found)?,
+        }
+    }
+    Ok(())
+}
+
+fn apply_to_value(
+    value: &mut GenericValue,
+    path: &str,
+    specs: &BTreeMap<&str, &TaggedUnionSpec>,
+    found: &mut BTreeMap<String, BTreeSet<String>>,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => {
+            apply_to_struct(struct_value, path, specs, found)
+        }
+        GenericValue::Option(Some(ref mut value)) => apply_to_value(value, path, specs, found),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                apply_to_value(value, path, specs, found)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn convert_to_union(
+    value: &mut GenericValue,
+    path: &str,
+    spec: &TaggedUnionSpec,
+    specs: &BTreeMap<&str, &TaggedUnionSpec>,
+    found: &mut BTreeMap<String, BTreeSet<String>>,
+) -> Result<(), GenerationError> {
+    let body = match *value {
+        GenericValue::Struct(ref mut struct_value) => struct_value,
+        GenericValue::Option(None) => return Ok(()),
+        GenericValue::Option(Some(ref mut value)) => {
+            return convert_to_union(value, path, spec, specs, found)
+        }
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                convert_to_union(value, path, spec, specs, found)?;
+            }
+            return Ok(());
+        }
+        _ => return Err(GenerationError::InvalidTaggedUnionField(path.into())),
+    };
+
+    let tag_field = body
+        .fields
+        .keys()
+        .find(|name| body.original_key(name) == spec.tag)
+        .cloned();
+    let tag = match tag_field.as_ref().and_then(|name| body.fields.get(name)) {
+        Some(GenericValue::String(tag)) => tag.clone(),
+        _ => return Err(GenerationError::MissingTag(path.into(), spec.tag.clone())),
+    };
+    let variant = match spec
+        .variants
+        .iter()
+        .find(|(tag_value, _)| *tag_value == tag)
+    {
+        Some((_, name)) => name.clone(),
+        None => {
+            return Err(GenerationError::UnknownTag(
+                path.into(),
+                tag,
+                spec.variants
+                    .iter()
+                    .map(|(tag_value, _)| tag_value.clone())
+                    .collect(),
+            ));
+        }
+    };
+
+    let mut body = std::mem::take(body);
+    remove_field(&mut body, &tag_field.expect("the tag was found"));
+    apply_to_struct(&mut body, path, specs, found)?;
+
+    found
+        .entry(path.to_owned())
+        .or_default()
+        .insert(variant.clone());
+    *value = GenericValue::TaggedUnion(GenericTaggedUnion {
+        enum_name: enum_name_for_path(path),
+        tag: spec.tag.clone(),
+        variants: spec.variants.clone(),
+        variant,
+        value: body,
+        known_variants: BTreeMap::new(),
+    });
+    Ok(())
+}
+
+fn remove_field(struct_value: &mut GenericStruct, name: &str) {
+    let key = struct_value.original_key(name).to_owned();
+    struct_value.fields.remove(name);
+    struct_value.original_keys.remove(name);
+    struct_value.field_docs.remove(name);
+    struct_value.field_cfgs.remove(name);
+    struct_value.field_spans.remove(name);
+    struct_value.absent_fields.remove(name);
+    struct_value
+        .field_order
+        .retain(|ordered_key| *ordered_key != key);
+}
+
+/// Build a template for the variants of several values of the same enum,
+/// with the fields of each variant from all of the values which have it,
+/// by variant name.
+pub fn variant_templates(unions: &[&GenericTaggedUnion]) -> BTreeMap<String, GenericStruct> {
+    let mut variants = BTreeMap::<&str, Vec<&GenericStruct>>::new();
+    for union in unions {
+        variants
+            .entry(&union.variant)
+            .or_default()
+            .push(&union.value);
+        for (name, value) in &union.known_variants {
+            variants.entry(name).or_default().push(value);
+        }
+    }
+
+    variants
+        .into_iter()
+        .map(|(name, bodies)| (name.to_owned(), struct_template(&bodies)))
+        .collect()
+}
+
+/// Build a template for several values of the same enum: the first value,
+/// knowing the fields of every variant any of them have.
+pub fn union_template(unions: &[&GenericTaggedUnion]) -> GenericTaggedUnion {
+    let mut template = unions[0].clone();
+    template.known_variants = variant_templates(unions);
+    template
+}
+
+/// Collect the values of each enum in `tagged_unions` in a config, by
+/// name.
+pub fn collect_unions<'a>(
+    struct_value: &'a GenericStruct,
+    unions: &mut BTreeMap<String, Vec<&'a GenericTaggedUnion>>,
+) {
+    fn collect_from_value<'a>(
+        value: &'a GenericValue,
+        unions: &mut BTreeMap<String, Vec<&'a GenericTaggedUnion>>,
+    ) {
+        match *value {
+            GenericValue::TaggedUnion(ref union) => {
+                unions
+                    .entry(union.enum_name.clone())
+                    .or_default()
+                    .push(union);
+                collect_unions(&union.value, unions);
+                for variant in union.known_variants.values() {
+                    collect_unions(variant, unions);
+                }
+            }
+            GenericValue::Struct(ref struct_value) => collect_unions(struct_value, unions),
+            GenericValue::Option(Some(ref value)) => collect_from_value(value, unions),
+            GenericValue::Array(ref values)
+            | GenericValue::Tuple(ref values)
+            | GenericValue::Map(GenericMap { ref values, .. }) => {
+                for value in values {
+                    collect_from_value(value, unions);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    for value in struct_value.fields.values() {
+        collect_from_value(value, unions);
+    }
+}
+
+/// Declare each enum in `tagged_unions` used in a config, once.
+pub fn generate_tagged_unions(struct_value: &GenericStruct, options: &StructOptions) -> String {
+    let mut unions = BTreeMap::new();
+    collect_unions(struct_value, &mut unions);
+
+    let mut code = String::new();
+    for values in unions.values() {
+        code.push_str(&union_declaration(&union_template(values), options));
+    }
+    code
+}
+
+fn union_declaration(union: &GenericTaggedUnion, options: &StructOptions) -> String {
+    // Variants with fields can't derive `Default` or be command-line
+    // values, so `Default` returns the variant in the config instead.
+    let derives_default = options.derived_traits.iter().any(|name| name == "Default");
+    let derived_traits = options
+        .derived_traits
+        .iter()
+        .filter(|name| *name != "Default" && *name != CLAP_PARSER)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let serde_derives = options.serde_support.derived_ser_de();
+    let attribute = if serde_derives.is_some() {
+        format!(
+            "#[serde(tag = {}, rename_all = \"lowercase\")]\n",
+            string_literal(&union.tag)
+        )
+    } else {
+        String::new()
+    };
+
+    let variant_strings = union
+        .variants
+        .iter()
+        .map(|(tag_value, name)| {
+            let mut attributes = String::new();
+            if serde_derives.is_some() && name.to_lowercase() != *tag_value {
+                attributes.push_str(&format!(
+                    "    #[serde(rename = {})]\n",
+                    string_literal(tag_value)
+                ));
+            }
+
+            let fields = match union.known_variants.get(name) {
+                Some(body) => {
+                    if let Some(ref doc) = body.doc {
+                        attributes.insert_str(0, &doc_string(doc, "    "));
+                    }
+                    body.ordered_fields(options.field_order)
+                        .into_iter()
+                        .map(|(field, value)| field_string(body, field, value, options))
+                        .collect::<Vec<_>>()
+                }
+                None => Vec::new(),
+            };
+            let body = if fields.is_empty() {
+                " {}".to_owned()
+            } else {
+                format!(" {{\n{}\n    }}", fields.join("\n"))
+            };
+
+            format!("{}    {}{},", attributes, name, body)
+        })
+        .collect::<Vec<_>>();
+
+    let mut code = format!(
+        "{}{}pub enum {} {{
+{}
+}}
+
+",
+        derive_string(derived_traits, options),
+        attribute,
+        union.enum_name,
+        variant_strings.join("\n")
+    );
+
+    if derives_default {
+        let value = value_string(
+            &GenericValue::TaggedUnion(union.clone()),
+            ArrayEmitContext::new(options, 8),
+            options,
+        );
+        code.push_str(&format!(
+            "impl Default for {} {{
+    fn default() -> Self {{
+        {}
+    }}
+}}
+
+",
+            union.enum_name, value
+        ));
+    }
+
+    code
+}
+
+fn field_string(
+    body: &GenericStruct,
+    name: &str,
+    value: &GenericValue,
+    options: &StructOptions,
+) -> String {
+    let mut serde_args = Vec::new();
+    if let Some((serialize, deserialize)) = options.serde_support.derived_ser_de() {
+        if let Some(key) = renaming::serde_rename(body, name, options) {
+            serde_args.push(format!("rename = {}", string_literal(key)));
+        }
+        // Variants have no consts for their defaults, so only fields
+        // missing from some tables are defaulted, to `None`.
+        let absent = body.absent_fields.contains(name);
+        if serialize && absent {
+            serde_args.push("skip_serializing_if = \"Option::is_none\"".to_owned());
+        }
+        if deserialize && (absent || options.serde_missing_fields == MissingFields::UseTypeDefault)
+        {
+            serde_args.push("default".to_owned());
+        }
+    }
+
+    let doc = match body.field_docs.get(name) {
+        Some(doc) => doc_string(doc, "        "),
+        None => String::new(),
+    };
+    let attribute = if serde_args.is_empty() {
+        String::new()
+    } else {
+        format!("        #[serde({})]\n", serde_args.join(", "))
+    };
+
+    format!(
+        "{}{}        {}: {},",
+        doc,
+        attribute,
+        name,
+        type_string(value, options)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_struct(fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: "_Config__backend".to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    fn config_with_backend(backend: GenericValue) -> GenericStruct {
+        let mut config = make_struct(vec![("backend", backend)]);
+        config.struct_name = "Config".to_owned();
+        config
+    }
+
+    fn backend(kind: &str, key: &str) -> GenericValue {
+        GenericValue::Struct(make_struct(vec![
+            ("kind", GenericValue::String(kind.to_owned())),
+            (key, GenericValue::String("value".to_owned())),
+        ]))
+    }
+
+    fn options() -> StructOptions {
+        StructOptions {
+            tagged_unions: vec![TaggedUnionSpec::new("backend", "kind")
+                .variant("s3", "S3")
+                .variant("local", "Local")],
+            ..StructOptions::default()
+        }
+    }
+
+    #[test]
+    fn tables_become_variants() {
+        let mut config = config_with_backend(backend("s3", "bucket"));
+        let mut warnings = Vec::new();
+
+        apply_tagged_unions(&mut config, &options(), &mut warnings).unwrap();
+
+        match config.fields["backend"] {
+            GenericValue::TaggedUnion(ref union) => {
+                assert_eq!(union.enum_name, "Backend");
+                assert_eq!(union.variant, "S3");
+                assert!(union.value.fields.contains_key("bucket"));
+                assert!(!union.value.fields.contains_key("kind"));
+            }
+            ref other => panic!("Expected tagged union, found {:?}", other),
+        }
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::EmptyVariant);
+    }
+
+    #[test]
+    fn unknown_tags_are_an_error() {
+        let mut config = config_with_backend(backend("gcs", "bucket"));
+
+        match apply_tagged_unions(&mut config, &options(), &mut Vec::new()) {
+            Err(GenerationError::UnknownTag(path, tag, known)) => {
+                assert_eq!(path, "backend");
+                assert_eq!(tag, "gcs");
+                assert_eq!(known, vec!["s3", "local"]);
+            }
+            other => panic!("Expected an unknown tag error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_tags_are_an_error() {
+        let mut config = config_with_backend(GenericValue::Struct(make_struct(vec![(
+            "kind",
+            GenericValue::I64(1),
+        )])));
+
+        assert!(matches!(
+            apply_tagged_unions(&mut config, &options(), &mut Vec::new()),
+            Err(GenerationError::MissingTag(..))
+        ));
+    }
+
+    #[test]
+    fn variants_are_merged() {
+        let mut config = config_with_backend(GenericValue::Array(vec![
+            backend("s3", "bucket"),
+            backend("local", "path"),
+        ]));
+        let options = options();
+        let mut warnings = Vec::new();
+
+        apply_tagged_unions(&mut config, &options, &mut warnings).unwrap();
+        assert!(warnings.is_empty());
+
+        let code = generate_tagged_unions(&config, &options);
+        assert_eq!(
+            code,
+            "#[derive(Debug, Clone)]
+pub enum Backend {
+    S3 {
+        bucket: Cow<'static, str>,
+    },
+    Local {
+        path: Cow<'static, str>,
+    },
+}
+
+"
+        );
+    }
+}
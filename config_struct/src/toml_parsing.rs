@@ -1,23 +1,96 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use toml::{self, Value};
 
 use crate::{
-    error::GenerationError,
+    error::{GenerationError, ParseError},
     options::StructOptions,
-    parsing,
+    parsing, paths,
     value::{GenericStruct, GenericValue},
 };
 
 pub fn parse_toml(toml: &str, options: &StructOptions) -> Result<GenericStruct, GenerationError> {
+    let toml_value: Value = toml::from_str(toml).map_err(|err| {
+        if let Some(path) = duplicate_key_path(&err.to_string()) {
+            return GenerationError::DuplicateKey(path);
+        }
+
+        // `toml` counts lines and columns from 0.
+        let (line, column) = match err.line_col() {
+            Some((line, column)) => (Some(line + 1), Some(column + 1)),
+            None => (None, None),
+        };
+        GenerationError::ParseFailed(ParseError::new(err, toml, line, column))
+    })?;
+
+    toml_value_to_generic_struct(toml_value, toml, options)
+}
+
+/// Convert a parsed TOML value, read from `toml`, to a struct. Only a
+/// table can be the root of a config.
+pub fn toml_value_to_generic_struct(
+    toml_value: Value,
+    toml: &str,
+    options: &StructOptions,
+) -> Result<GenericStruct, GenerationError> {
     use parsing::ParsedFields;
 
-    let toml_struct: ParsedFields<Value> = toml::from_str(toml)
-        .map_err(|err| GenerationError::DeserializationFailed(err.to_string()))?;
+    parsing::check_depth(&toml_value, options)?;
+    // Deserializing would turn datetimes into strings, so the table is
+    // taken as it is.
+    let toml_struct: ParsedFields<Value> = match toml_value {
+        Value::Table(fields) => fields,
+        other => {
+            let message = format!("invalid type: {}, expected a map", other.type_str());
+            return Err(GenerationError::ParseFailed(ParseError::from_message(
+                message, toml, None, None,
+            )));
+        }
+    };
 
     let generic_struct = parsing::parsed_to_generic_struct(toml_struct, options, toml_to_raw_value);
 
     Ok(generic_struct)
 }
 
+/// The path of the key in a duplicate key or table error, since TOML
+/// never allows them. `toml` only gives the key and its parent table, as
+/// in "duplicate key: `port` for key `server`".
+fn duplicate_key_path(message: &str) -> Option<String> {
+    let quoted = |text: &str| {
+        let text = text.strip_prefix('`')?;
+        text.find('`').map(|end| text[..end].to_owned())
+    };
+
+    let key = message
+        .strip_prefix("duplicate key: ")
+        .or_else(|| message.strip_prefix("redefinition of table "))?;
+    let key = quoted(key)?;
+    let parent = message
+        .find(" for key ")
+        .and_then(|index| quoted(&message[index + " for key ".len()..]));
+
+    match parent {
+        Some(ref parent) if *parent != key => Some(paths::join(parent, &key)),
+        _ => Some(key),
+    }
+}
+
+impl parsing::Nested for Value {
+    fn children(&self) -> Option<Vec<(Option<&str>, &Self)>> {
+        match *self {
+            Value::Table(ref fields) => Some(
+                fields
+                    .iter()
+                    .map(|(key, value)| (Some(key.as_str()), value))
+                    .collect(),
+            ),
+            Value::Array(ref values) => Some(values.iter().map(|value| (None, value)).collect()),
+            _ => None,
+        }
+    }
+}
+
 fn toml_to_raw_value(
     super_struct: &str,
     super_key: &str,
@@ -26,10 +99,10 @@ fn toml_to_raw_value(
 ) -> GenericValue {
     match value {
         Value::Boolean(value) => GenericValue::Bool(value),
-        Value::Integer(value) => parsing::preferred_int(value, options.default_int_size),
+        Value::Integer(value) => GenericValue::I64(value),
         Value::Float(value) => parsing::preferred_float(value, options.default_float_size),
         Value::String(value) => GenericValue::String(value),
-        Value::Datetime(value) => GenericValue::String(value.to_string()),
+        Value::Datetime(value) => GenericValue::DateTime(value.to_string()),
         Value::Array(values) => GenericValue::Array(
             values
                 .into_iter()
@@ -48,6 +121,14 @@ fn toml_to_raw_value(
             GenericValue::Struct(GenericStruct {
                 struct_name: sub_struct_name,
                 fields: values,
+                original_keys: BTreeMap::new(),
+                doc: None,
+                field_docs: BTreeMap::new(),
+                field_cfgs: BTreeMap::new(),
+                field_spans: BTreeMap::new(),
+                flattened_fields: BTreeSet::new(),
+                absent_fields: BTreeSet::new(),
+                field_order: Vec::new(),
             })
         }
     }
@@ -0,0 +1,305 @@
+use std::convert::TryFrom;
+
+use crate::{
+    error::GenerationError,
+    int_types::integer_value,
+    options::{StructOptions, ValueTransform},
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// The units of a byte size, in lowercase, with the number of bytes in
+/// each.
+const BYTE_UNITS: &[(&str, u64)] = &[
+    ("", 1),
+    ("b", 1),
+    ("kb", 1_000),
+    ("mb", 1_000_000),
+    ("gb", 1_000_000_000),
+    ("tb", 1_000_000_000_000),
+    ("kib", 1 << 10),
+    ("mib", 1 << 20),
+    ("gib", 1 << 30),
+    ("tib", 1 << 40),
+];
+
+/// The units of a duration, with the number of milliseconds in each.
+const DURATION_UNITS: &[(&str, u64)] = &[("ms", 1), ("s", 1_000), ("m", 60_000), ("h", 3_600_000)];
+
+/// Apply the transformations in `options.value_transforms` to the values
+/// at the paths they're listed with, in the order they're listed.
+///
+/// This runs after integers have been given their types, so that byte
+/// sizes and durations stay `u64`.
+pub fn apply_value_transforms(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if options.value_transforms.is_empty() {
+        return Ok(());
+    }
+
+    apply_to_struct(struct_value, "", options)
+}
+
+fn apply_to_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    for (key, value) in &mut struct_value.fields {
+        let path = paths::join(parent_path, key);
+
+        for (pattern, transform) in &options.value_transforms {
+            if paths::matches(pattern, &path) {
+                transform_value(value, &path, *transform)?;
+            }
+        }
+        apply_to_value(value, &path, options)?;
+    }
+    Ok(())
+}
+
+fn apply_to_value(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => apply_to_struct(struct_value, path, options),
+        GenericValue::Option(Some(ref mut value)) => apply_to_value(value, path, options),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                apply_to_value(value, path, options)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Apply a transformation to a value, or to each element of an array or
+/// map. String transformations leave other values alone.
+fn transform_value(
+    value: &mut GenericValue,
+    path: &str,
+    transform: ValueTransform,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Option(None) => return Ok(()),
+        GenericValue::Option(Some(ref mut value)) => {
+            return transform_value(value, path, transform)
+        }
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                transform_value(value, path, transform)?;
+            }
+            return Ok(());
+        }
+        _ => (),
+    }
+
+    let transformed = match transform {
+        ValueTransform::EnvSubstitute => match *value {
+            GenericValue::String(ref text) => {
+                GenericValue::String(substitute_env_vars(text, path)?)
+            }
+            _ => return Ok(()),
+        },
+        ValueTransform::TrimWhitespace => match *value {
+            GenericValue::String(ref text) => GenericValue::String(text.trim().to_owned()),
+            _ => return Ok(()),
+        },
+        ValueTransform::ParseByteSize => GenericValue::U64(
+            parse_quantity(value, BYTE_UNITS)
+                .ok_or_else(|| GenerationError::InvalidByteSize(path.into(), value_text(value)))?,
+        ),
+        ValueTransform::ParseDurationMs => GenericValue::U64(
+            parse_quantity(value, DURATION_UNITS)
+                .ok_or_else(|| GenerationError::InvalidDuration(path.into(), value_text(value)))?,
+        ),
+    };
+
+    *value = transformed;
+    Ok(())
+}
+
+/// Replace each `${NAME}` in `text` with the value of the environment
+/// variable `NAME`. An unclosed `${` is left as it is.
+fn substitute_env_vars(text: &str, path: &str) -> Result<String, GenerationError> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let name = &rest[start + 2..end];
+        let value = std::env::var(name)
+            .map_err(|_| GenerationError::MissingEnvVar(path.into(), name.into()))?;
+
+        result.push_str(&rest[..start]);
+        result.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Read a number followed by one of `units`, like `1.5 GiB`, as a whole
+/// number of the smallest unit. Non-negative integers are already in the
+/// smallest unit.
+fn parse_quantity(value: &GenericValue, units: &[(&str, u64)]) -> Option<u64> {
+    let text = match *value {
+        GenericValue::String(ref text) => text.trim(),
+        _ => return integer_value(value).and_then(|value| u64::try_from(value).ok()),
+    };
+
+    let number_end = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(number_end);
+    let unit = unit.trim_start().to_lowercase();
+    let &(_, scale) = units.iter().find(|(name, _)| *name == unit)?;
+
+    let (whole, fraction) = match number.find('.') {
+        Some(point) => (&number[..point], &number[point + 1..]),
+        None => (number, ""),
+    };
+    if whole.is_empty() || fraction.contains('.') || (number.contains('.') && fraction.is_empty()) {
+        return None;
+    }
+
+    // Work in integers, so that `1.1s` is exactly 1100ms.
+    let digits = format!("{}{}", whole, fraction).parse::<u128>().ok()?;
+    let divisor = 10u128.checked_pow(fraction.len() as u32)?;
+    let scaled = digits.checked_mul(u128::from(scale))?;
+    if scaled % divisor != 0 {
+        return None;
+    }
+    u64::try_from(scaled / divisor).ok()
+}
+
+/// The value as written in an error message.
+fn value_text(value: &GenericValue) -> String {
+    match *value {
+        GenericValue::String(ref text) => text.clone(),
+        GenericValue::Bool(value) => value.to_string(),
+        GenericValue::F32(value) => value.to_string(),
+        GenericValue::F64(value) => value.to_string(),
+        GenericValue::Struct(_) => "a table".to_owned(),
+        _ => integer_value(value)
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| format!("{:?}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options_with_transform(path: &str, transform: ValueTransform) -> StructOptions {
+        StructOptions {
+            value_transforms: vec![(path.to_owned(), transform)],
+            ..StructOptions::default()
+        }
+    }
+
+    fn config(value: GenericValue) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![("value".to_owned(), value)].into_iter().collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    fn parse(text: &str, transform: ValueTransform) -> Result<u64, GenerationError> {
+        let mut config = config(GenericValue::String(text.to_owned()));
+        apply_value_transforms(&mut config, &options_with_transform("value", transform))?;
+        match config.fields["value"] {
+            GenericValue::U64(value) => Ok(value),
+            ref other => panic!("Expected a u64, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn durations_are_parsed() {
+        let duration = |text| parse(text, ValueTransform::ParseDurationMs).unwrap();
+
+        assert_eq!(duration("100ms"), 100);
+        assert_eq!(duration("5s"), 5_000);
+        assert_eq!(duration("2m"), 120_000);
+        assert_eq!(duration("1h"), 3_600_000);
+        assert_eq!(duration("1.1s"), 1_100);
+
+        for text in &["", "5", "5 days", "s", "1.s", "0.0001s", "-5s"] {
+            match parse(text, ValueTransform::ParseDurationMs) {
+                Err(GenerationError::InvalidDuration(path, value)) => {
+                    assert_eq!(path, "value");
+                    assert_eq!(value, *text);
+                }
+                other => panic!("Expected an invalid duration error, found {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn byte_sizes_are_parsed() {
+        let size = |text| parse(text, ValueTransform::ParseByteSize).unwrap();
+
+        assert_eq!(size("512"), 512);
+        assert_eq!(size("10MB"), 10_000_000);
+        assert_eq!(size("1.5 GiB"), 3 << 29);
+        assert_eq!(size("4kib"), 4096);
+
+        assert!(matches!(
+            parse("99999999999TB", ValueTransform::ParseByteSize),
+            Err(GenerationError::InvalidByteSize(..))
+        ));
+    }
+
+    #[test]
+    fn strings_are_trimmed_in_arrays() {
+        let mut config = config(GenericValue::Array(vec![
+            GenericValue::String(" a ".to_owned()),
+            GenericValue::I64(1),
+        ]));
+        apply_value_transforms(
+            &mut config,
+            &options_with_transform("value", ValueTransform::TrimWhitespace),
+        )
+        .unwrap();
+
+        assert_eq!(
+            format!("{:?}", config.fields["value"]),
+            format!(
+                "{:?}",
+                GenericValue::Array(vec![
+                    GenericValue::String("a".to_owned()),
+                    GenericValue::I64(1)
+                ])
+            )
+        );
+    }
+
+    #[test]
+    fn missing_env_vars_are_errors() {
+        let mut config = config(GenericValue::String(
+            "${CONFIG_STRUCT_TRANSFORM_TEST_UNSET}".to_owned(),
+        ));
+        match apply_value_transforms(
+            &mut config,
+            &options_with_transform("value", ValueTransform::EnvSubstitute),
+        ) {
+            Err(GenerationError::MissingEnvVar(path, name)) => {
+                assert_eq!(path, "value");
+                assert_eq!(name, "CONFIG_STRUCT_TRANSFORM_TEST_UNSET");
+            }
+            other => panic!("Expected a missing variable error, found {:?}", other),
+        }
+    }
+}
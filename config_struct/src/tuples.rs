@@ -0,0 +1,170 @@
+use crate::{
+    options::{MixedArrayBehavior, StructOptions},
+    validation::array_is_homogenous,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// Convert arrays with elements of different types into tuples, if the
+/// options allow it.
+///
+/// Arrays which are still heterogenous after this will be rejected
+/// during validation.
+pub fn convert_mixed_arrays(struct_value: &mut GenericStruct, options: &StructOptions) {
+    if options.mixed_arrays != MixedArrayBehavior::Tuple {
+        return;
+    }
+
+    let type_options = options.for_type_comparison();
+
+    convert_struct(struct_value, &type_options);
+}
+
+fn convert_struct(struct_value: &mut GenericStruct, options: &StructOptions) {
+    for value in struct_value.fields.values_mut() {
+        convert_value(value, options);
+    }
+}
+
+fn convert_value(value: &mut GenericValue, options: &StructOptions) {
+    let is_mixed_array = match *value {
+        GenericValue::Struct(ref mut struct_value) => {
+            convert_struct(struct_value, options);
+            false
+        }
+        GenericValue::Option(Some(ref mut value)) => {
+            convert_value(value, options);
+            false
+        }
+        GenericValue::Array(ref mut values) => {
+            for value in values.iter_mut() {
+                convert_value(value, options);
+            }
+            !array_is_homogenous(values, options)
+        }
+        GenericValue::Tuple(ref mut values) => {
+            for value in values.iter_mut() {
+                convert_value(value, options);
+            }
+            false
+        }
+        GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values.iter_mut() {
+                convert_value(value, options);
+            }
+            false
+        }
+        _ => false,
+    };
+
+    if is_mixed_array {
+        if let GenericValue::Array(values) = std::mem::replace(value, GenericValue::Unit) {
+            *value = GenericValue::Tuple(rename_tuple_structs(values));
+        }
+    }
+}
+
+/// Give each struct in a tuple its own name, since they may not share a
+/// shape the way array elements do.
+pub fn rename_tuple_structs(mut values: Vec<GenericValue>) -> Vec<GenericValue> {
+    for (index, value) in values.iter_mut().enumerate() {
+        if let GenericValue::Struct(ref mut struct_value) = *value {
+            let old_prefix = struct_value.struct_name.clone();
+            let new_prefix = format!("{}__{}", old_prefix, index);
+            struct_value.replace_name_prefix(&old_prefix, &new_prefix);
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tuple_options() -> StructOptions {
+        StructOptions {
+            mixed_arrays: MixedArrayBehavior::Tuple,
+            ..StructOptions::default()
+        }
+    }
+
+    fn config_with(key: &str, value: GenericValue) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![(key.to_owned(), value)].into_iter().collect(),
+            original_keys: Default::default(),
+            doc: None,
+            field_docs: Default::default(),
+            field_cfgs: Default::default(),
+            field_spans: Default::default(),
+            flattened_fields: Default::default(),
+            absent_fields: Default::default(),
+            field_order: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn mixed_array_becomes_tuple() {
+        let mut config = config_with(
+            "point",
+            GenericValue::Array(vec![
+                GenericValue::I64(1),
+                GenericValue::String("x".into()),
+                GenericValue::Bool(true),
+            ]),
+        );
+
+        convert_mixed_arrays(&mut config, &tuple_options());
+
+        match config.fields["point"] {
+            GenericValue::Tuple(ref values) => assert_eq!(values.len(), 3),
+            ref other => panic!("Expected tuple, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn homogenous_array_is_unchanged() {
+        let mut config = config_with(
+            "list",
+            GenericValue::Array(vec![GenericValue::I64(1), GenericValue::I64(2)]),
+        );
+
+        convert_mixed_arrays(&mut config, &tuple_options());
+
+        match config.fields["list"] {
+            GenericValue::Array(_) => (),
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn arrays_of_matching_tuples_stay_arrays() {
+        let pair =
+            || GenericValue::Array(vec![GenericValue::I64(1), GenericValue::String("x".into())]);
+        let mut config = config_with("pairs", GenericValue::Array(vec![pair(), pair()]));
+
+        convert_mixed_arrays(&mut config, &tuple_options());
+
+        match config.fields["pairs"] {
+            GenericValue::Array(ref values) => match values[0] {
+                GenericValue::Tuple(_) => (),
+                ref other => panic!("Expected tuple, found {:?}", other),
+            },
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn mixed_arrays_are_left_alone_by_default() {
+        let mut config = config_with(
+            "point",
+            GenericValue::Array(vec![GenericValue::I64(1), GenericValue::Bool(true)]),
+        );
+
+        convert_mixed_arrays(&mut config, &StructOptions::default());
+
+        match config.fields["point"] {
+            GenericValue::Array(_) => (),
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+    }
+}
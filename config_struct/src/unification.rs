@@ -0,0 +1,531 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    error::GenerationError,
+    generation::type_string,
+    options::StructOptions,
+    paths,
+    tagged_unions::union_template,
+    value::{GenericMap, GenericStruct, GenericTaggedUnion, GenericValue},
+};
+
+/// Give all structs in the same array the same fields.
+///
+/// Fields which are missing (or null) in some elements become optional,
+/// with `None` used for the elements that lack them. Fields with
+/// different types in different elements are an error.
+pub fn unify_array_structs(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let type_options = options.for_type_comparison();
+
+    unify_fields(struct_value, "", &type_options)
+}
+
+/// Give several structs (like the roots of different configs) the same
+/// fields, as for the elements of an array of structs.
+pub fn unify_struct_set(
+    structs: Vec<&mut GenericStruct>,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let type_options = options.for_type_comparison();
+
+    unify_structs(structs, "", &type_options)
+}
+
+/// Build a struct with the fields of all of the given structs, using the
+/// first non-null value for each field.
+///
+/// After unification, every element of an array of structs has the same
+/// fields, but a `None` in the first element says nothing about the
+/// field's type. This template can be used to declare the struct instead.
+pub fn struct_template(structs: &[&GenericStruct]) -> GenericStruct {
+    let mut template = structs[0].clone();
+
+    for (key, value) in &mut template.fields {
+        let values = structs
+            .iter()
+            .filter_map(|struct_value| struct_value.fields.get(key))
+            .collect::<Vec<_>>();
+        *value = value_template(&values);
+    }
+
+    for struct_value in &structs[1..] {
+        for (key, value) in &struct_value.original_keys {
+            template
+                .original_keys
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+        for (key, doc) in &struct_value.field_docs {
+            template
+                .field_docs
+                .entry(key.clone())
+                .or_insert_with(|| doc.clone());
+        }
+        for (key, cfg) in &struct_value.field_cfgs {
+            template
+                .field_cfgs
+                .entry(key.clone())
+                .or_insert_with(|| cfg.clone());
+        }
+        for (key, span) in &struct_value.field_spans {
+            template.field_spans.entry(key.clone()).or_insert(*span);
+        }
+        template
+            .flattened_fields
+            .extend(struct_value.flattened_fields.iter().cloned());
+        template
+            .absent_fields
+            .extend(struct_value.absent_fields.iter().cloned());
+        if template.doc.is_none() {
+            template.doc = struct_value.doc.clone();
+        }
+        for key in &struct_value.field_order {
+            if !template.field_order.contains(key) {
+                template.field_order.push(key.clone());
+            }
+        }
+    }
+
+    template
+}
+
+/// Collect the structs in an array, including those in nested arrays
+/// and maps.
+pub fn array_structs(values: &[GenericValue]) -> Vec<&GenericStruct> {
+    let mut structs = Vec::new();
+    for value in values {
+        match *unwrap_option(value) {
+            GenericValue::Struct(ref struct_value) => structs.push(struct_value),
+            GenericValue::Array(ref values) | GenericValue::Map(GenericMap { ref values, .. }) => {
+                structs.extend(array_structs(values))
+            }
+            _ => (),
+        }
+    }
+    structs
+}
+
+/// Collect the values of enums in `tagged_unions` in an array, including
+/// those in nested arrays and maps.
+pub fn array_unions(values: &[GenericValue]) -> Vec<&GenericTaggedUnion> {
+    let mut unions = Vec::new();
+    for value in values {
+        match *unwrap_option(value) {
+            GenericValue::TaggedUnion(ref union) => unions.push(union),
+            GenericValue::Array(ref values) | GenericValue::Map(GenericMap { ref values, .. }) => {
+                unions.extend(array_unions(values))
+            }
+            _ => (),
+        }
+    }
+    unions
+}
+
+/// Build a template for the elements of an array (or the values of a
+/// map), like [`struct_template`](fn.struct_template.html) does for the
+/// fields of structs.
+pub fn element_template(values: &[GenericValue]) -> Option<GenericValue> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(value_template(&values.iter().collect::<Vec<_>>()))
+    }
+}
+
+fn value_template(values: &[&GenericValue]) -> GenericValue {
+    let present = values
+        .iter()
+        .cloned()
+        .filter(|value| !is_null(value))
+        .collect::<Vec<_>>();
+
+    let first = match present.first() {
+        Some(first) => *first,
+        None => return values[0].clone(),
+    };
+
+    let is_optional = matches!(*first, GenericValue::Option(_));
+    let template = match *unwrap_option(first) {
+        GenericValue::Struct(_) => {
+            let structs = present
+                .iter()
+                .filter_map(|value| match *unwrap_option(value) {
+                    GenericValue::Struct(ref struct_value) => Some(struct_value),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            GenericValue::Struct(struct_template(&structs))
+        }
+        GenericValue::TaggedUnion(_) => {
+            let unions = present
+                .iter()
+                .filter_map(|value| match *unwrap_option(value) {
+                    GenericValue::TaggedUnion(ref union) => Some(union),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            GenericValue::TaggedUnion(union_template(&unions))
+        }
+        GenericValue::Array(_) => {
+            let arrays = present
+                .iter()
+                .filter_map(|value| match *unwrap_option(value) {
+                    GenericValue::Array(ref values) => Some(values),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let elements = arrays.iter().flat_map(|values| values.iter());
+
+            // Arrays of the same length keep that length, in case they
+            // are generated as fixed-size arrays.
+            let len = arrays[0].len();
+            if len != 0 && arrays.iter().all(|values| values.len() == len) {
+                let element = value_template(&elements.collect::<Vec<_>>());
+                GenericValue::Array(vec![element; len])
+            } else {
+                GenericValue::Array(elements.cloned().collect())
+            }
+        }
+        GenericValue::Map(_) => {
+            let mut template = GenericMap::default();
+            for value in &present {
+                if let GenericValue::Map(ref map) = *unwrap_option(value) {
+                    template.keys.extend(map.keys.iter().cloned());
+                    template.values.extend(map.values.iter().cloned());
+                }
+            }
+            GenericValue::Map(template)
+        }
+        ref other => other.clone(),
+    };
+
+    if is_optional {
+        GenericValue::Option(Some(Box::new(template)))
+    } else {
+        template
+    }
+}
+
+fn unify_fields(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    for (key, value) in &mut struct_value.fields {
+        unify_value(value, &paths::join(parent_path, key), options)?;
+    }
+    Ok(())
+}
+
+fn unify_value(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => unify_fields(struct_value, path, options),
+        GenericValue::TaggedUnion(ref mut union) => unify_fields(&mut union.value, path, options),
+        GenericValue::Option(Some(ref mut value)) => unify_value(value, path, options),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            let mut unions = Vec::new();
+            let structs = array_values_mut(values, &mut unions);
+            if !structs.is_empty() {
+                unify_structs(structs, path, options)?;
+            }
+            unify_unions(unions, path, options)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn unify_structs(
+    mut structs: Vec<&mut GenericStruct>,
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let keys = structs
+        .iter()
+        .flat_map(|struct_value| struct_value.fields.keys().cloned())
+        .collect::<BTreeSet<String>>();
+
+    for key in keys {
+        let field_path = paths::join(path, &key);
+        check_field_types(&structs, &key, &field_path, options)?;
+
+        let mut nested_structs = Vec::new();
+        let mut nested_unions = Vec::new();
+        for struct_value in &mut structs {
+            if let Some(value) = struct_value.fields.get_mut(&key) {
+                match *unwrap_option_mut(value) {
+                    GenericValue::Struct(ref mut struct_value) => nested_structs.push(struct_value),
+                    GenericValue::TaggedUnion(ref mut union) => nested_unions.push(union),
+                    GenericValue::Array(ref mut values)
+                    | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+                        nested_structs.extend(array_values_mut(values, &mut nested_unions))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        if !nested_structs.is_empty() {
+            unify_structs(nested_structs, &field_path, options)?;
+        }
+        unify_unions(nested_unions, &field_path, options)?;
+
+        let optional = structs.iter().any(|struct_value| {
+            !matches!(
+                struct_value.fields.get(&key),
+                Some(value) if !matches!(*value, GenericValue::Option(_))
+            )
+        });
+        if optional {
+            let absent = structs
+                .iter()
+                .any(|struct_value| !struct_value.fields.contains_key(&key));
+            for struct_value in &mut structs {
+                if absent {
+                    struct_value.absent_fields.insert(key.clone());
+                }
+                let value = struct_value
+                    .fields
+                    .entry(key.clone())
+                    .or_insert(GenericValue::Option(None));
+
+                if !matches!(*value, GenericValue::Option(_)) {
+                    let inner = std::mem::replace(value, GenericValue::Unit);
+                    *value = GenericValue::Option(Some(Box::new(inner)));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Give the values of each variant of an enum in `tagged_unions` the same
+/// fields, as for the elements of an array of structs.
+fn unify_unions(
+    unions: Vec<&mut GenericTaggedUnion>,
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let mut variants = BTreeMap::<String, Vec<&mut GenericStruct>>::new();
+    for union in unions {
+        variants
+            .entry(union.variant.clone())
+            .or_default()
+            .push(&mut union.value);
+    }
+
+    for bodies in variants.into_values() {
+        unify_structs(bodies, path, options)?;
+    }
+    Ok(())
+}
+
+fn check_field_types(
+    structs: &[&mut GenericStruct],
+    key: &str,
+    field_path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let mut expected_type = None;
+
+    for (index, struct_value) in structs.iter().enumerate() {
+        let value = match struct_value.fields.get(key) {
+            Some(value) if !is_null(value) => unwrap_option(value),
+            _ => continue,
+        };
+
+        let value_type = type_string(value, options);
+        match expected_type {
+            None => expected_type = Some(value_type),
+            Some(ref expected) if *expected != value_type => {
+                return Err(GenerationError::ConflictingArrayElement(
+                    field_path.into(),
+                    index,
+                ));
+            }
+            Some(_) => (),
+        }
+    }
+
+    Ok(())
+}
+
+/// Collect the structs in an array, including those in nested arrays
+/// and maps.
+pub fn array_structs_mut(values: &mut [GenericValue]) -> Vec<&mut GenericStruct> {
+    let mut structs = Vec::new();
+    for value in values {
+        match *unwrap_option_mut(value) {
+            GenericValue::Struct(ref mut struct_value) => structs.push(struct_value),
+            GenericValue::Array(ref mut values)
+            | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+                structs.extend(array_structs_mut(values))
+            }
+            _ => (),
+        }
+    }
+    structs
+}
+
+/// Collect the structs in an array, like
+/// [`array_structs_mut`](fn.array_structs_mut.html), and the values of
+/// enums in `tagged_unions` in it into `unions`.
+fn array_values_mut<'a>(
+    values: &'a mut [GenericValue],
+    unions: &mut Vec<&'a mut GenericTaggedUnion>,
+) -> Vec<&'a mut GenericStruct> {
+    let mut structs = Vec::new();
+    for value in values {
+        match *unwrap_option_mut(value) {
+            GenericValue::Struct(ref mut struct_value) => structs.push(struct_value),
+            GenericValue::TaggedUnion(ref mut union) => unions.push(union),
+            GenericValue::Array(ref mut values)
+            | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+                structs.extend(array_values_mut(values, unions))
+            }
+            _ => (),
+        }
+    }
+    structs
+}
+
+fn is_null(value: &GenericValue) -> bool {
+    matches!(*value, GenericValue::Option(None))
+}
+
+pub fn unwrap_option(value: &GenericValue) -> &GenericValue {
+    match *value {
+        GenericValue::Option(Some(ref value)) => unwrap_option(value),
+        ref other => other,
+    }
+}
+
+pub fn unwrap_option_mut(value: &mut GenericValue) -> &mut GenericValue {
+    match *value {
+        GenericValue::Option(Some(ref mut value)) => unwrap_option_mut(value),
+        ref mut other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_struct(fields: Vec<(&str, GenericValue)>) -> GenericStruct {
+        GenericStruct {
+            struct_name: "_Config__servers".to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            original_keys: Default::default(),
+            doc: None,
+            field_docs: Default::default(),
+            field_cfgs: Default::default(),
+            field_spans: Default::default(),
+            flattened_fields: Default::default(),
+            absent_fields: Default::default(),
+            field_order: Vec::new(),
+        }
+    }
+
+    fn config_with_servers(servers: Vec<GenericStruct>) -> GenericStruct {
+        let mut config = make_struct(vec![(
+            "servers",
+            GenericValue::Array(servers.into_iter().map(GenericValue::Struct).collect()),
+        )]);
+        config.struct_name = "Config".to_owned();
+        config
+    }
+
+    fn servers(config: &GenericStruct) -> Vec<&GenericStruct> {
+        match config.fields["servers"] {
+            GenericValue::Array(ref values) => array_structs(values),
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_fields_become_optional() {
+        let mut config = config_with_servers(vec![
+            make_struct(vec![
+                ("host", GenericValue::String("a".into())),
+                ("port", GenericValue::I64(1)),
+            ]),
+            make_struct(vec![("host", GenericValue::String("b".into()))]),
+        ]);
+
+        unify_array_structs(&mut config, &StructOptions::default()).unwrap();
+
+        let servers = servers(&config);
+        match (&servers[0].fields["port"], &servers[1].fields["port"]) {
+            (GenericValue::Option(Some(_)), GenericValue::Option(None)) => (),
+            other => panic!("Unexpected ports: {:?}", other),
+        }
+        match servers[1].fields["host"] {
+            GenericValue::String(_) => (),
+            ref other => panic!("Expected string, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn template_uses_present_values() {
+        let mut config = config_with_servers(vec![
+            make_struct(vec![]),
+            make_struct(vec![("port", GenericValue::I64(1))]),
+        ]);
+
+        unify_array_structs(&mut config, &StructOptions::default()).unwrap();
+
+        let template = struct_template(&servers(&config));
+        assert_eq!(
+            type_string(&template.fields["port"], &StructOptions::default()),
+            "Option<i64>"
+        );
+    }
+
+    #[test]
+    fn nested_structs_are_unified() {
+        let mut config = config_with_servers(vec![
+            make_struct(vec![(
+                "tls",
+                GenericValue::Struct(make_struct(vec![("cert", GenericValue::Bool(true))])),
+            )]),
+            make_struct(vec![("tls", GenericValue::Struct(make_struct(vec![])))]),
+        ]);
+
+        unify_array_structs(&mut config, &StructOptions::default()).unwrap();
+
+        match servers(&config)[1].fields["tls"] {
+            GenericValue::Struct(ref tls) => match tls.fields["cert"] {
+                GenericValue::Option(None) => (),
+                ref other => panic!("Expected None, found {:?}", other),
+            },
+            ref other => panic!("Expected struct, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn conflicting_types_are_an_error() {
+        let mut config = config_with_servers(vec![
+            make_struct(vec![("port", GenericValue::I64(1))]),
+            make_struct(vec![]),
+            make_struct(vec![("port", GenericValue::String("2".into()))]),
+        ]);
+
+        match unify_array_structs(&mut config, &StructOptions::default()) {
+            Err(GenerationError::ConflictingArrayElement(path, index)) => {
+                assert_eq!(path, "servers.port");
+                assert_eq!(index, 2);
+            }
+            other => panic!("Expected conflicting element error, found {:?}", other),
+        }
+    }
+}
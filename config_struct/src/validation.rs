@@ -1,6 +1,11 @@
 use crate::{
+    datetimes,
     error::GenerationError,
     generation::type_string, // TODO: I wish we weren't comparing types by string,
+    naming,
+    options::StructOptions,
+    paths,
+    root_arrays,
     value::{GenericStruct, GenericValue},
 };
 
@@ -12,48 +17,132 @@ pub fn valid_identifier(name: &str) -> bool {
     good_start && good_end && name != "_"
 }
 
-pub fn validate_struct(struct_value: &GenericStruct) -> Result<(), GenerationError> {
+pub fn validate_struct(
+    struct_value: &GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let type_options = options.for_type_comparison();
+
+    // The elements of a root array are validated as if each were the root.
+    match root_arrays::root_elements(struct_value) {
+        Some(elements) => elements
+            .iter()
+            .try_for_each(|element| validate_value("", element, &type_options)),
+        None => validate_fields(struct_value, "", &type_options),
+    }
+}
+
+fn validate_fields(
+    struct_value: &GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
     for (key, value) in &struct_value.fields {
         validate_field_name(key)?;
-        validate_value(key, value)?;
+        validate_value(&paths::join(parent_path, key), value, options)?;
     }
     Ok(())
 }
 
 fn validate_field_name(field_name: &str) -> Result<(), GenerationError> {
-    if valid_identifier(field_name) {
+    let valid = match field_name.strip_prefix("r#") {
+        Some(raw_name) => valid_identifier(raw_name) && naming::field_name(raw_name) == field_name,
+        None => valid_identifier(field_name) && !naming::is_keyword(field_name),
+    };
+
+    if valid {
         Ok(())
     } else {
         Err(GenerationError::InvalidFieldName(field_name.into()))
     }
 }
 
-fn validate_value(key: &str, value: &GenericValue) -> Result<(), GenerationError> {
+fn validate_value(
+    path: &str,
+    value: &GenericValue,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
     match *value {
-        GenericValue::Option(Some(ref value)) => validate_value(key, value)?,
+        GenericValue::Option(Some(ref value)) => validate_value(path, value, options)?,
         GenericValue::Array(ref values) => {
-            validate_array_element_types(key, values)?;
+            validate_array_element_types(path, values, options)?;
+            for value in values {
+                validate_value(path, value, options)?;
+            }
+        }
+        GenericValue::Tuple(ref values) => {
             for value in values {
-                validate_value(key, value)?;
+                validate_value(path, value, options)?;
+            }
+        }
+        GenericValue::Map(ref map) => {
+            if !array_is_homogenous(&map.values, options) {
+                return Err(GenerationError::HeterogenousMap(path.into()));
+            }
+            for value in &map.values {
+                validate_value(path, value, options)?;
             }
         }
-        GenericValue::Struct(ref value) => validate_struct(value)?,
+        GenericValue::Struct(ref value) => validate_fields(value, path, options)?,
+        GenericValue::TaggedUnion(ref union) => validate_fields(&union.value, path, options)?,
+        GenericValue::DateTime(ref value) if !datetimes::valid_date_time(value, options) => {
+            return Err(GenerationError::InvalidDateTime(path.into(), value.clone()));
+        }
         _ => (),
     }
     Ok(())
 }
 
-fn validate_array_element_types(key: &str, values: &[GenericValue]) -> Result<(), GenerationError> {
-    if let Some(ref value) = values.get(0) {
-        // TODO: A more efficient way to compare types would be nice
-        let candidate = type_string(value);
-        let all_same_type = values.iter().map(type_string).all(|s| s == candidate);
+fn validate_array_element_types(
+    path: &str,
+    values: &[GenericValue],
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let conflicting = conflicting_elements(values, options);
+    if !conflicting.is_empty() {
+        return Err(GenerationError::HeterogenousArray(path.into(), conflicting));
+    }
+    Ok(())
+}
 
-        if !all_same_type {
-            return Err(GenerationError::HeterogenousArray(key.into()));
+/// The indices of the elements whose type differs from the first element
+/// which isn't a null.
+fn conflicting_elements(values: &[GenericValue], options: &StructOptions) -> Vec<usize> {
+    let candidate = match GenericValue::typed_element(values) {
+        Some(value) => type_string(value, options),
+        None => return Vec::new(),
+    };
+    values
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| !has_type(value, &candidate, options))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Whether all elements of an array have the same type.
+///
+/// The `options` should only use slices, so that arrays of
+/// different lengths are considered the same type.
+pub fn array_is_homogenous(values: &[GenericValue], options: &StructOptions) -> bool {
+    match GenericValue::typed_element(values) {
+        Some(value) => {
+            // TODO: A more efficient way to compare types would be nice
+            let candidate = type_string(value, options);
+            values
+                .iter()
+                .all(|value| has_type(value, &candidate, options))
         }
+        None => true,
+    }
+}
+
+/// Whether a value has the given type. A null fits any `Option` type.
+fn has_type(value: &GenericValue, candidate: &str, options: &StructOptions) -> bool {
+    match *value {
+        GenericValue::Option(None) => candidate.starts_with("Option<"),
+        _ => type_string(value, options) == candidate,
     }
-    Ok(())
 }
 
 #[cfg(test)]
@@ -83,7 +172,7 @@ mod tests {
     }
 
     fn validate_array_test(values: &[GenericValue]) -> Result<(), GenerationError> {
-        validate_array_element_types("", values)
+        validate_array_element_types("", values, &StructOptions::default())
     }
 
     #[test]
@@ -101,7 +190,15 @@ mod tests {
 
     #[test]
     fn heterogenous_arrays() {
-        assert!(validate_array_test(&[GenericValue::Unit, GenericValue::I64(0)]).is_err());
+        match validate_array_test(&[
+            GenericValue::Unit,
+            GenericValue::I64(0),
+            GenericValue::Unit,
+            GenericValue::Bool(true),
+        ]) {
+            Err(GenerationError::HeterogenousArray(_, indices)) => assert_eq!(indices, vec![1, 3]),
+            other => panic!("Expected heterogenous array error, found {:?}", other),
+        }
         assert!(validate_array_test(&[
             GenericValue::Array(vec![GenericValue::Unit]),
             GenericValue::Array(vec![GenericValue::I64(0)]),
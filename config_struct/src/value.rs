@@ -1,15 +1,176 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use crate::options::FieldOrder;
 
 /// Represents a Rust struct.
-#[derive(Debug, Clone)]
+///
+/// This is the intermediate representation of a parsed config, which can
+/// be obtained with [`parse_config`](fn.parse_config.html) and modified
+/// before generating code from it.
+#[derive(Debug, Clone, Default)]
 pub struct GenericStruct {
+    /// The name of the generated struct. Nested structs are conventionally
+    /// named after the path to them, like `_Config__server`.
     pub struct_name: String,
+
+    /// The fields of the struct, indexed by their Rust names.
     pub fields: BTreeMap<String, GenericValue>,
+
+    /// The keys in the config file for any fields whose Rust name is
+    /// different, indexed by the Rust name.
+    pub original_keys: BTreeMap<String, String>,
+
+    /// The doc comment for the generated struct, if any.
+    pub doc: Option<String>,
+
+    /// The doc comments for any documented fields, indexed by their Rust
+    /// names.
+    pub field_docs: BTreeMap<String, String>,
+
+    /// The `#[cfg]` predicates of any conditionally compiled fields,
+    /// indexed by their Rust names.
+    pub field_cfgs: BTreeMap<String, String>,
+
+    /// Where the keys of the fields are in the config file, for formats
+    /// which record it, indexed by their Rust names. Fields of the
+    /// elements of an array are located in the first element.
+    pub field_spans: BTreeMap<String, Span>,
+
+    /// The fields holding structs which are flattened into this one when
+    /// (de)serialized, by their Rust names.
+    pub flattened_fields: BTreeSet<String>,
+
+    /// The optional fields which are missing from some values of the
+    /// struct, rather than null, by their Rust names. They are left out
+    /// when serialized while they're `None`, so they stay missing.
+    pub absent_fields: BTreeSet<String>,
+
+    /// The keys of the fields in the order they appear in the config
+    /// file, if known. Used when `field_order` is `SourceOrder`.
+    pub field_order: Vec<String>,
+}
+
+impl GenericStruct {
+    /// The key in the config file for the given field.
+    pub fn original_key<'a>(&'a self, field_name: &'a str) -> &'a str {
+        self.original_keys
+            .get(field_name)
+            .map(String::as_str)
+            .unwrap_or(field_name)
+    }
+
+    /// The fields of the struct, in the order they should be generated.
+    ///
+    /// With `FieldOrder::SourceOrder`, fields are sorted by the position of
+    /// their key in `field_order`, and any fields not listed there come
+    /// last. Otherwise, they are sorted by name.
+    pub(crate) fn ordered_fields(&self, order: FieldOrder) -> Vec<(&String, &GenericValue)> {
+        let mut fields = self.fields.iter().collect::<Vec<_>>();
+
+        if order == FieldOrder::SourceOrder && !self.field_order.is_empty() {
+            fields.sort_by_key(|(name, _)| {
+                let key = self.original_key(name);
+                self.field_order
+                    .iter()
+                    .position(|ordered_key| ordered_key == key)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+
+        fields
+    }
+
+    /// Rename this struct and any nested structs whose names start with
+    /// `old_prefix`.
+    pub(crate) fn replace_name_prefix(&mut self, old_prefix: &str, new_prefix: &str) {
+        if self.struct_name.starts_with(old_prefix) {
+            self.struct_name = format!("{}{}", new_prefix, &self.struct_name[old_prefix.len()..]);
+        }
+
+        for value in self.fields.values_mut() {
+            value.replace_name_prefix(old_prefix, new_prefix);
+        }
+    }
+}
+
+/// A position in the source of a config, counting lines and columns
+/// (in characters) from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Represents a Rust enum with unit variants, generated from a string
+/// field with a known set of values.
+#[derive(Debug, Clone)]
+pub struct GenericEnum {
+    pub enum_name: String,
+    pub variants: Vec<String>,
+    pub value: String,
+}
+
+/// Represents a number wrapped in a tuple struct, like
+/// `TimeoutMs(500)`, from one of the `newtype_paths`.
+#[derive(Debug, Clone)]
+pub struct GenericNewtype {
+    pub type_name: String,
+    pub value: Box<GenericValue>,
+}
+
+/// Represents a table from one of the `tagged_unions`, as the variant of
+/// an enum chosen by the table's tag, like `Backend::S3 { bucket: .. }`.
+#[derive(Debug, Clone)]
+pub struct GenericTaggedUnion {
+    pub enum_name: String,
+
+    /// The key of the tag, like `kind`.
+    pub tag: String,
+
+    /// The tag value and name of each variant, in the order they're
+    /// declared.
+    pub variants: Vec<(String, String)>,
+
+    /// The name of the variant the table holds.
+    pub variant: String,
+
+    /// The fields of the table, other than its tag. It keeps the name of
+    /// the table's struct, which prefixes the names of the structs nested
+    /// in it, but is written as the variant, like `Backend::S3 { .. }`.
+    pub value: GenericStruct,
+
+    /// The fields of each variant known from other tables which share the
+    /// enum, like the other elements of an array, or the same table in
+    /// other configs, by variant name. The enum is declared with these.
+    pub known_variants: BTreeMap<String, GenericStruct>,
+}
+
+/// Represents a map from string keys to values of a single type,
+/// generated from a table listed in `map_paths`.
+///
+/// The values are treated like the elements of an array: structs among
+/// them share a single declaration.
+#[derive(Debug, Clone, Default)]
+pub struct GenericMap {
+    pub keys: Vec<String>,
+    pub values: Vec<GenericValue>,
 }
 
 /// Represents a typed Rust value.
+///
+/// The variant determines the type of the generated field: for example an
+/// `Option(None)` becomes `Option<()>`, and an `Array` takes its element
+/// type from its first element.
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub enum GenericValue {
     Unit,
     Bool(bool),
@@ -18,16 +179,77 @@ pub enum GenericValue {
     I16(i16),
     I32(i32),
     I64(i64),
+    I128(i128),
     U8(u8),
     U16(u16),
     U32(u32),
     U64(u64),
+    U128(u128),
     ISize(isize),
     Usize(usize),
     F32(f32),
     F64(f64),
     String(String),
+    /// A date, a time, or a date-time with or without an offset, as
+    /// written in the config (like `2023-05-01T10:00:00Z`).
+    DateTime(String),
+    /// Binary data, decoded from a base64 string at one of the
+    /// `binary_paths`.
+    Bytes(Vec<u8>),
+    /// A semantic version, like `1.4.0-rc.1`, from one of the
+    /// `semver_paths`. It has been checked, and is kept as written.
+    Version(String),
     Option(Option<Box<GenericValue>>),
+    /// A `None` which is `None` wherever it appears, whose inner type was
+    /// given as Rust source in `option_types` or `default_option_type`.
+    NoneOf(String),
     Array(Vec<GenericValue>),
+    /// An empty array whose element type was given as Rust source in
+    /// `empty_array_types` or `default_empty_array_type`.
+    EmptyArray(String),
+    Tuple(Vec<GenericValue>),
     Struct(GenericStruct),
+    Enum(GenericEnum),
+    Newtype(GenericNewtype),
+    TaggedUnion(GenericTaggedUnion),
+    Map(GenericMap),
+}
+
+impl GenericValue {
+    /// The element of an array (or value of a map) to take the type of
+    /// its elements from: the first which isn't a null, so that
+    /// `[None, Some(1)]` has `Option<i64>` elements.
+    pub(crate) fn typed_element(values: &[GenericValue]) -> Option<&GenericValue> {
+        values
+            .iter()
+            .find(|value| !matches!(*value, GenericValue::Option(None)))
+            .or_else(|| values.first())
+    }
+
+    /// Rename any structs within this value whose names start with
+    /// `old_prefix`.
+    pub(crate) fn replace_name_prefix(&mut self, old_prefix: &str, new_prefix: &str) {
+        match *self {
+            GenericValue::Struct(ref mut struct_value) => {
+                struct_value.replace_name_prefix(old_prefix, new_prefix)
+            }
+            GenericValue::Option(Some(ref mut value)) => {
+                value.replace_name_prefix(old_prefix, new_prefix)
+            }
+            GenericValue::TaggedUnion(ref mut union) => {
+                union.value.replace_name_prefix(old_prefix, new_prefix);
+                for variant in union.known_variants.values_mut() {
+                    variant.replace_name_prefix(old_prefix, new_prefix);
+                }
+            }
+            GenericValue::Array(ref mut values)
+            | GenericValue::Tuple(ref mut values)
+            | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+                for value in values {
+                    value.replace_name_prefix(old_prefix, new_prefix);
+                }
+            }
+            _ => (),
+        }
+    }
 }
@@ -0,0 +1,512 @@
+use crate::{
+    error::GenerationError,
+    generation::{declared_structs, string_literal},
+    options::{StructOptions, VersionType},
+    paths,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// A semantic version, split into its parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: String,
+    pub build: String,
+}
+
+/// Convert the strings at the paths listed in `options.semver_paths` into
+/// versions, checking that each is a valid semantic version.
+pub fn apply_semver_paths(
+    struct_value: &mut GenericStruct,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    if options.semver_paths.is_empty() {
+        return Ok(());
+    }
+
+    apply_to_struct(struct_value, "", options)
+}
+
+fn apply_to_struct(
+    struct_value: &mut GenericStruct,
+    parent_path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    for (key, value) in &mut struct_value.fields {
+        let path = paths::join(parent_path, key);
+
+        if options.semver_paths.contains(&path) {
+            convert_strings(value, &path, options)?;
+        } else {
+            apply_to_value(value, &path, options)?;
+        }
+    }
+    Ok(())
+}
+
+fn apply_to_value(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => apply_to_struct(struct_value, path, options),
+        GenericValue::Option(Some(ref mut value)) => apply_to_value(value, path, options),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                apply_to_value(value, path, options)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn convert_strings(
+    value: &mut GenericValue,
+    path: &str,
+    options: &StructOptions,
+) -> Result<(), GenerationError> {
+    let text = match *value {
+        GenericValue::String(ref text) => text.clone(),
+        GenericValue::Version(_) | GenericValue::Option(None) => return Ok(()),
+        GenericValue::Option(Some(ref mut value)) => return convert_strings(value, path, options),
+        GenericValue::Array(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values {
+                convert_strings(value, path, options)?;
+            }
+            return Ok(());
+        }
+        _ => return Err(GenerationError::InvalidVersionField(path.into())),
+    };
+
+    check_version(&text, options)
+        .map_err(|message| GenerationError::InvalidVersion(path.into(), message))?;
+    *value = GenericValue::Version(text);
+    Ok(())
+}
+
+/// Check that `text` is a version the chosen `version_type` can hold,
+/// describing the problem if not.
+fn check_version(text: &str, options: &StructOptions) -> Result<(), String> {
+    match options.version_type {
+        VersionType::Struct => parse_version(text).map(|_| ()),
+        #[cfg(feature = "semver")]
+        VersionType::Semver => semver::Version::parse(text)
+            .map(|_| ())
+            .map_err(|error| error.to_string()),
+    }
+}
+
+/// Parse a semantic version, like `1.4.0-rc.1+build.5`, as described by
+/// <https://semver.org>.
+pub fn parse_version(text: &str) -> Result<ParsedVersion, String> {
+    let (text, build) = match text.find('+') {
+        Some(plus) => (&text[..plus], Some(&text[plus + 1..])),
+        None => (text, None),
+    };
+    let (core, pre) = match text.find('-') {
+        Some(hyphen) => (&text[..hyphen], Some(&text[hyphen + 1..])),
+        None => (text, None),
+    };
+
+    let numbers = core.split('.').collect::<Vec<_>>();
+    if numbers.len() != 3 {
+        return Err(format!(
+            "expected three numbers separated by dots, like `1.4.0`, found `{}`",
+            core
+        ));
+    }
+    let numbers = numbers
+        .iter()
+        .map(|number| parse_number(number))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(pre) = pre {
+        check_identifiers(pre, "pre-release", true)?;
+    }
+    if let Some(build) = build {
+        check_identifiers(build, "build metadata", false)?;
+    }
+
+    Ok(ParsedVersion {
+        major: numbers[0],
+        minor: numbers[1],
+        patch: numbers[2],
+        pre: pre.unwrap_or_default().to_owned(),
+        build: build.unwrap_or_default().to_owned(),
+    })
+}
+
+fn parse_number(text: &str) -> Result<u64, String> {
+    if text.is_empty() || !text.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(format!("`{}` is not a number", text));
+    }
+    if text.len() > 1 && text.starts_with('0') {
+        return Err(format!("`{}` has a leading zero", text));
+    }
+    text.parse()
+        .map_err(|_| format!("`{}` is too large for a `u64`", text))
+}
+
+/// Check the dot-separated identifiers of a pre-release or build metadata.
+/// Numeric pre-release identifiers can't have leading zeros.
+fn check_identifiers(text: &str, part: &str, numeric_rules: bool) -> Result<(), String> {
+    for identifier in text.split('.') {
+        if identifier.is_empty() {
+            return Err(format!("the {} `{}` has an empty identifier", part, text));
+        }
+        if let Some(c) = identifier
+            .chars()
+            .find(|&c| !(c == '-' || c.is_ascii_alphanumeric()))
+        {
+            return Err(format!("the {} `{}` contains `{}`", part, text, c));
+        }
+        if numeric_rules && identifier.bytes().all(|byte| byte.is_ascii_digit()) {
+            parse_number(identifier)?;
+        }
+    }
+    Ok(())
+}
+
+/// The name of the struct generated for versions with
+/// `VersionType::Struct`.
+pub fn version_struct_name(options: &StructOptions) -> String {
+    format!("{}Version", options.struct_name)
+}
+
+pub fn version_type_string(options: &StructOptions) -> String {
+    match options.version_type {
+        VersionType::Struct => version_struct_name(options),
+        #[cfg(feature = "semver")]
+        VersionType::Semver => "semver::Version".to_owned(),
+    }
+}
+
+pub fn version_value_string(text: &str, options: &StructOptions) -> String {
+    const CHECKED: &str = "versions should be checked when they're converted";
+
+    match options.version_type {
+        VersionType::Struct => {
+            let version = parse_version(text).expect(CHECKED);
+            let cow = format!("::{}::borrow::Cow::Borrowed", std_crate(options));
+            format!(
+                "{} {{ major: {}, minor: {}, patch: {}, pre: {}({}), build: {}({}) }}",
+                version_struct_name(options),
+                version.major,
+                version.minor,
+                version.patch,
+                cow,
+                string_literal(&version.pre),
+                cow,
+                string_literal(&version.build)
+            )
+        }
+        #[cfg(feature = "semver")]
+        VersionType::Semver => {
+            let version = semver::Version::parse(text).expect(CHECKED);
+            if version.pre.is_empty() && version.build.is_empty() {
+                format!(
+                    "semver::Version::new({}, {}, {})",
+                    version.major, version.minor, version.patch
+                )
+            } else {
+                format!("semver::Version::parse({}).unwrap()", string_literal(text))
+            }
+        }
+    }
+}
+
+/// Whether the version can be built in a const.
+#[cfg_attr(not(feature = "semver"), allow(unused_variables))]
+pub fn version_is_const(text: &str, options: &StructOptions) -> bool {
+    match options.version_type {
+        VersionType::Struct => true,
+        #[cfg(feature = "semver")]
+        VersionType::Semver => !text.contains(['-', '+']),
+    }
+}
+
+fn std_crate(options: &StructOptions) -> &'static str {
+    if options.no_std {
+        "alloc"
+    } else {
+        "std"
+    }
+}
+
+/// Generate the struct for versions, if the config has any and
+/// `version_type` is `VersionType::Struct`.
+pub fn generate_version_struct(struct_value: &GenericStruct, options: &StructOptions) -> String {
+    let has_versions = declared_structs(struct_value)
+        .iter()
+        .any(|struct_value| struct_value.fields.values().any(contains_version));
+    if options.version_type != VersionType::Struct || !has_versions {
+        return String::new();
+    }
+
+    let name = version_struct_name(options);
+    let std_crate = std_crate(options);
+
+    // Ordering and serialization are implemented by hand, so they're left
+    // out of the derives. Versions are parsed from command-line arguments
+    // with `FromStr`, rather than being parsers themselves.
+    let mut derived_traits = vec!["Debug", "Clone", "PartialEq", "Eq", "Hash", "Default"];
+    for name in &options.derived_traits {
+        if !derived_traits.contains(&name.as_str())
+            && name != "PartialOrd"
+            && name != "Ord"
+            && name != "clap::Parser"
+        {
+            derived_traits.push(name.as_str());
+        }
+    }
+
+    let mut code = format!(
+        "/// A semantic version, like `1.4.0-rc.1`, ordered by semver precedence.
+#[derive({derives})]
+pub struct {name} {{
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: ::{std}::borrow::Cow<'static, str>,
+    pub build: ::{std}::borrow::Cow<'static, str>,
+}}
+
+impl ::core::cmp::PartialOrd for {name} {{
+    fn partial_cmp(&self, other: &Self) -> Option<::core::cmp::Ordering> {{
+        Some(self.cmp(other))
+    }}
+}}
+
+impl ::core::cmp::Ord for {name} {{
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {{
+        use ::core::cmp::Ordering;
+
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {{
+                (true, true) => Ordering::Equal,
+                // A pre-release comes before its release.
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => {{
+                    let mut ours = self.pre.split('.');
+                    let mut theirs = other.pre.split('.');
+                    loop {{
+                        let ordering = match (ours.next(), theirs.next()) {{
+                            (None, None) => return Ordering::Equal,
+                            (None, Some(_)) => return Ordering::Less,
+                            (Some(_), None) => return Ordering::Greater,
+                            (Some(a), Some(b)) => {{
+                                let numeric = |identifier: &str| identifier.bytes().all(|byte| byte.is_ascii_digit());
+                                match (numeric(a), numeric(b)) {{
+                                    (true, true) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+                                    (true, false) => Ordering::Less,
+                                    (false, true) => Ordering::Greater,
+                                    (false, false) => a.cmp(b),
+                                }}
+                            }}
+                        }};
+                        if ordering != Ordering::Equal {{
+                            return ordering;
+                        }}
+                    }}
+                }}
+            }})
+            .then_with(|| self.build.cmp(&other.build))
+    }}
+}}
+
+impl ::core::fmt::Display for {name} {{
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {{
+        write!(f, \"{{}}.{{}}.{{}}\", self.major, self.minor, self.patch)?;
+        if !self.pre.is_empty() {{
+            write!(f, \"-{{}}\", self.pre)?;
+        }}
+        if !self.build.is_empty() {{
+            write!(f, \"+{{}}\", self.build)?;
+        }}
+        Ok(())
+    }}
+}}
+
+impl ::core::str::FromStr for {name} {{
+    type Err = &'static str;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {{
+        const INVALID: &str = \"invalid semantic version\";
+
+        fn number(text: &str) -> Result<u64, &'static str> {{
+            let digits = !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit());
+            if !digits || (text.len() > 1 && text.starts_with('0')) {{
+                return Err(INVALID);
+            }}
+            text.parse().map_err(|_| INVALID)
+        }}
+
+        fn identifiers(text: &str, numeric_rules: bool) -> Result<::{std}::borrow::Cow<'static, str>, &'static str> {{
+            for identifier in text.split('.') {{
+                let valid = |byte: u8| byte == b'-' || byte.is_ascii_alphanumeric();
+                if identifier.is_empty() || !identifier.bytes().all(valid) {{
+                    return Err(INVALID);
+                }}
+                if numeric_rules && identifier.bytes().all(|byte| byte.is_ascii_digit()) {{
+                    number(identifier)?;
+                }}
+            }}
+            Ok(::{std}::borrow::Cow::Owned(::{std}::string::String::from(text)))
+        }}
+
+        let (text, build) = match text.split_once('+') {{
+            Some((text, build)) => (text, identifiers(build, false)?),
+            None => (text, ::{std}::borrow::Cow::Borrowed(\"\")),
+        }};
+        let (core, pre) = match text.split_once('-') {{
+            Some((core, pre)) => (core, identifiers(pre, true)?),
+            None => (text, ::{std}::borrow::Cow::Borrowed(\"\")),
+        }};
+        let mut numbers = core.split('.');
+        let mut next = || number(numbers.next().ok_or(INVALID)?);
+        let (major, minor, patch) = (next()?, next()?, next()?);
+        if numbers.next().is_some() {{
+            return Err(INVALID);
+        }}
+
+        Ok({name} {{ major, minor, patch, pre, build }})
+    }}
+}}
+",
+        derives = derived_traits.join(", "),
+        name = name,
+        std = std_crate
+    );
+
+    if let Some((serialize, deserialize)) = options.serde_support.should_derive_ser_de() {
+        if serialize {
+            code.push_str(&format!(
+                "
+impl ::serde::Serialize for {name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        serializer.collect_str(self)
+    }}
+}}
+",
+                name = name
+            ));
+        }
+        if deserialize {
+            code.push_str(&format!(
+                "
+impl<'de> ::serde::Deserialize<'de> for {name} {{
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+        let text = <::{std}::string::String as ::serde::Deserialize>::deserialize(deserializer)?;
+        text.parse().map_err(::serde::de::Error::custom)
+    }}
+}}
+",
+                name = name,
+                std = std_crate
+            ));
+        }
+    }
+
+    code
+}
+
+fn contains_version(value: &GenericValue) -> bool {
+    match *value {
+        GenericValue::Version(_) => true,
+        GenericValue::Option(Some(ref value)) => contains_version(value),
+        GenericValue::Array(ref values)
+        | GenericValue::Tuple(ref values)
+        | GenericValue::Map(GenericMap { ref values, .. }) => values.iter().any(contains_version),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options_with_semver(path: &str) -> StructOptions {
+        StructOptions {
+            semver_paths: vec![path.to_owned()],
+            ..StructOptions::default()
+        }
+    }
+
+    fn config(value: GenericValue) -> GenericStruct {
+        GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![("version".to_owned(), value)].into_iter().collect(),
+            ..GenericStruct::default()
+        }
+    }
+
+    #[test]
+    fn versions_are_parsed() {
+        assert_eq!(
+            parse_version("1.4.0-rc.1+build-5").unwrap(),
+            ParsedVersion {
+                major: 1,
+                minor: 4,
+                patch: 0,
+                pre: "rc.1".to_owned(),
+                build: "build-5".to_owned(),
+            }
+        );
+        assert_eq!(parse_version("0.0.1+007").unwrap().build, "007");
+
+        for text in &[
+            "1.4",
+            "1.4.0.1",
+            "01.4.0",
+            "1.x.0",
+            "1.4.0-",
+            "1.4.0-rc.01",
+            "1.4.0+a_b",
+        ] {
+            assert!(parse_version(text).is_err(), "{} should be invalid", text);
+        }
+    }
+
+    #[test]
+    fn strings_in_arrays_are_converted() {
+        let mut config = config(GenericValue::Array(vec![
+            GenericValue::String("1.0.0".to_owned()),
+            GenericValue::String("2.0.0-beta".to_owned()),
+        ]));
+        apply_semver_paths(&mut config, &options_with_semver("version")).unwrap();
+
+        assert_eq!(
+            format!("{:?}", config.fields["version"]),
+            format!(
+                "{:?}",
+                GenericValue::Array(vec![
+                    GenericValue::Version("1.0.0".to_owned()),
+                    GenericValue::Version("2.0.0-beta".to_owned()),
+                ])
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_versions_are_errors() {
+        let mut invalid = config(GenericValue::String("1.4".to_owned()));
+        match apply_semver_paths(&mut invalid, &options_with_semver("version")) {
+            Err(GenerationError::InvalidVersion(path, _)) => assert_eq!(path, "version"),
+            other => panic!("Expected an invalid version error, found {:?}", other),
+        }
+
+        let mut number = config(GenericValue::I64(1));
+        match apply_semver_paths(&mut number, &options_with_semver("version")) {
+            Err(GenerationError::InvalidVersionField(path)) => assert_eq!(path, "version"),
+            other => panic!("Expected an invalid field error, found {:?}", other),
+        }
+    }
+}
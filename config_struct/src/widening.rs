@@ -0,0 +1,260 @@
+use crate::{
+    int_types,
+    options::{IntSize, StructOptions},
+    parsing,
+    validation::array_is_homogenous,
+    value::{GenericMap, GenericStruct, GenericValue},
+};
+
+/// The narrowest numeric type which can hold every number in an array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Numeric {
+    /// An empty array, which is compatible with any numeric type.
+    Empty,
+    Int(IntSize),
+    Float,
+}
+
+/// Widen the elements of arrays which mix numeric types, so that they all
+/// have the same type.
+///
+/// Arrays which mix integers and floats are widened to
+/// `options.default_float_size`, and arrays which mix integer sizes of the
+/// same signedness are widened to the largest of those sizes. Any other
+/// mix is left alone, to be rejected during validation.
+pub fn widen_numeric_arrays(struct_value: &mut GenericStruct, options: &StructOptions) {
+    let type_options = options.for_type_comparison();
+
+    widen_struct(struct_value, &type_options);
+}
+
+fn widen_struct(struct_value: &mut GenericStruct, options: &StructOptions) {
+    for value in struct_value.fields.values_mut() {
+        widen_value(value, options);
+    }
+}
+
+fn widen_value(value: &mut GenericValue, options: &StructOptions) {
+    match *value {
+        GenericValue::Struct(ref mut struct_value) => widen_struct(struct_value, options),
+        GenericValue::Option(Some(ref mut value)) => widen_value(value, options),
+        GenericValue::Array(ref mut values) => {
+            for value in values.iter_mut() {
+                widen_value(value, options);
+            }
+            if array_is_homogenous(values, options) {
+                return;
+            }
+            if let Some(numeric) = array_numeric(values) {
+                for value in values.iter_mut() {
+                    widen_to(value, numeric, options);
+                }
+            }
+        }
+        GenericValue::Tuple(ref mut values)
+        | GenericValue::Map(GenericMap { ref mut values, .. }) => {
+            for value in values.iter_mut() {
+                widen_value(value, options);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// The type all the numbers in `value` can be widened to, if it is a
+/// number or a (possibly nested) array of numbers.
+fn value_numeric(value: &GenericValue) -> Option<Numeric> {
+    let numeric = match *value {
+        GenericValue::I8(_) => Numeric::Int(IntSize::I8),
+        GenericValue::I16(_) => Numeric::Int(IntSize::I16),
+        GenericValue::I32(_) => Numeric::Int(IntSize::I32),
+        GenericValue::I64(_) => Numeric::Int(IntSize::I64),
+        GenericValue::I128(_) => Numeric::Int(IntSize::I128),
+        GenericValue::ISize(_) => Numeric::Int(IntSize::ISize),
+        GenericValue::U8(_) => Numeric::Int(IntSize::U8),
+        GenericValue::U16(_) => Numeric::Int(IntSize::U16),
+        GenericValue::U32(_) => Numeric::Int(IntSize::U32),
+        GenericValue::U64(_) => Numeric::Int(IntSize::U64),
+        GenericValue::U128(_) => Numeric::Int(IntSize::U128),
+        GenericValue::Usize(_) => Numeric::Int(IntSize::USize),
+        GenericValue::F32(_) | GenericValue::F64(_) => Numeric::Float,
+        GenericValue::Array(ref values) => return array_numeric(values),
+        _ => return None,
+    };
+    Some(numeric)
+}
+
+fn array_numeric(values: &[GenericValue]) -> Option<Numeric> {
+    values.iter().try_fold(Numeric::Empty, |numeric, value| {
+        combine(numeric, value_numeric(value)?)
+    })
+}
+
+fn combine(a: Numeric, b: Numeric) -> Option<Numeric> {
+    match (a, b) {
+        (Numeric::Empty, other) | (other, Numeric::Empty) => Some(other),
+        (Numeric::Float, _) | (_, Numeric::Float) => Some(Numeric::Float),
+        (Numeric::Int(a), Numeric::Int(b)) if a == b => Some(Numeric::Int(a)),
+        (Numeric::Int(a), Numeric::Int(b)) => {
+            let (a_signed, a_bits) = int_rank(a)?;
+            let (b_signed, b_bits) = int_rank(b)?;
+            if a_signed != b_signed {
+                None
+            } else if a_bits >= b_bits {
+                Some(Numeric::Int(a))
+            } else {
+                Some(Numeric::Int(b))
+            }
+        }
+    }
+}
+
+/// Whether an integer type is signed, and its size in bits.
+///
+/// Pointer-sized integers have no fixed size, so they are never widened
+/// to or from. Values never have the `Auto` type.
+fn int_rank(int_size: IntSize) -> Option<(bool, u8)> {
+    let rank = match int_size {
+        IntSize::I8 => (true, 8),
+        IntSize::I16 => (true, 16),
+        IntSize::I32 => (true, 32),
+        IntSize::I64 => (true, 64),
+        IntSize::I128 => (true, 128),
+        IntSize::U8 => (false, 8),
+        IntSize::U16 => (false, 16),
+        IntSize::U32 => (false, 32),
+        IntSize::U64 => (false, 64),
+        IntSize::U128 => (false, 128),
+        IntSize::ISize | IntSize::USize | IntSize::Auto => return None,
+    };
+    Some(rank)
+}
+
+fn widen_to(value: &mut GenericValue, numeric: Numeric, options: &StructOptions) {
+    if let GenericValue::Array(ref mut values) = *value {
+        for value in values {
+            widen_to(value, numeric, options);
+        }
+        return;
+    }
+
+    let widened = match (numeric, int_types::integer_value(value)) {
+        (Numeric::Int(int_size), _) => int_types::resize_integer(value, int_size),
+        (Numeric::Float, Some(integer)) => Some(parsing::preferred_float(
+            integer as f64,
+            options.default_float_size,
+        )),
+        (Numeric::Float, None) => match *value {
+            GenericValue::F32(float) => Some(parsing::preferred_float(
+                f64::from(float),
+                options.default_float_size,
+            )),
+            GenericValue::F64(float) => {
+                Some(parsing::preferred_float(float, options.default_float_size))
+            }
+            GenericValue::U128(integer) => Some(parsing::preferred_float(
+                integer as f64,
+                options.default_float_size,
+            )),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    if let Some(widened) = widened {
+        *value = widened;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::FloatSize;
+
+    fn widen(values: Vec<GenericValue>, options: &StructOptions) -> String {
+        let mut config = GenericStruct {
+            struct_name: "Config".to_owned(),
+            fields: vec![("values".to_owned(), GenericValue::Array(values))]
+                .into_iter()
+                .collect(),
+            ..GenericStruct::default()
+        };
+        widen_numeric_arrays(&mut config, options);
+        format!("{:?}", config.fields["values"])
+    }
+
+    fn array(values: Vec<GenericValue>) -> String {
+        format!("{:?}", GenericValue::Array(values))
+    }
+
+    #[test]
+    fn ints_and_floats_are_widened_to_floats() {
+        let options = StructOptions {
+            default_float_size: FloatSize::F32,
+            ..StructOptions::default()
+        };
+        let values = vec![
+            GenericValue::I64(1),
+            GenericValue::F32(2.5),
+            GenericValue::I64(3),
+        ];
+
+        assert_eq!(
+            widen(values, &options),
+            array(vec![
+                GenericValue::F32(1.0),
+                GenericValue::F32(2.5),
+                GenericValue::F32(3.0),
+            ])
+        );
+    }
+
+    #[test]
+    fn int_sizes_are_widened_to_the_largest() {
+        let values = vec![
+            GenericValue::I8(1),
+            GenericValue::I32(-70000),
+            GenericValue::I16(3),
+        ];
+
+        assert_eq!(
+            widen(values, &StructOptions::default()),
+            array(vec![
+                GenericValue::I32(1),
+                GenericValue::I32(-70000),
+                GenericValue::I32(3),
+            ])
+        );
+    }
+
+    #[test]
+    fn nested_arrays_are_widened_together() {
+        let values = vec![
+            GenericValue::Array(vec![GenericValue::I64(1)]),
+            GenericValue::Array(vec![GenericValue::F64(0.5), GenericValue::I64(2)]),
+        ];
+
+        assert_eq!(
+            widen(values, &StructOptions::default()),
+            array(vec![
+                GenericValue::Array(vec![GenericValue::F64(1.0)]),
+                GenericValue::Array(vec![GenericValue::F64(0.5), GenericValue::F64(2.0)]),
+            ])
+        );
+    }
+
+    #[test]
+    fn incompatible_arrays_are_unchanged() {
+        let mixed = vec![GenericValue::I64(1), GenericValue::String("x".to_owned())];
+        let signedness = vec![GenericValue::I8(1), GenericValue::U8(2)];
+
+        assert_eq!(
+            widen(mixed.clone(), &StructOptions::default()),
+            array(mixed)
+        );
+        assert_eq!(
+            widen(signedness.clone(), &StructOptions::default()),
+            array(signedness)
+        );
+    }
+}
@@ -1,23 +1,980 @@
-use serde_yaml::{self, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde_yaml::{self, Mapping, Value};
+use yaml_rust::{
+    parser::{Event, MarkedEventReceiver, Parser},
+    scanner::{Marker, ScanError, TScalarStyle, TokenType},
+    Yaml, YamlLoader,
+};
 
 use crate::{
-    error::GenerationError,
-    options::StructOptions,
-    parsing,
+    duplicate_keys::{resolve_duplicate_keys, DuplicateKey},
+    error::{GenerationError, ParseError},
+    limits::LimitCounter,
+    merging, nulls,
+    options::{DocumentSelector, DuplicateKeyBehavior, StructOptions, YamlScalarStyle},
+    parsing, paths,
     value::{GenericStruct, GenericValue},
 };
 
 pub fn parse_yaml(yaml: &str, options: &StructOptions) -> Result<GenericStruct, GenerationError> {
+    let documents = document_sources(yaml)?;
+    let selector = options.format_options.yaml.document;
+    if documents.len() <= 1 {
+        if let DocumentSelector::Index(index) = selector {
+            if index > 0 {
+                return Err(GenerationError::MissingYamlDocument(index, documents.len()));
+            }
+        }
+        return parse_document(yaml, options);
+    }
+
+    match selector {
+        DocumentSelector::First => parse_document(&documents[0], options),
+        DocumentSelector::Index(index) => match documents.get(index) {
+            Some(document) => parse_document(document, options),
+            None => Err(GenerationError::MissingYamlDocument(index, documents.len())),
+        },
+        DocumentSelector::MergeAll => {
+            let mut merged = parse_document(&documents[0], options)?;
+            for (index, document) in documents.iter().enumerate().skip(1) {
+                let overrides = parse_document(document, options)?;
+                merging::merge_structs(&mut merged, overrides).map_err(|err| match err {
+                    GenerationError::IncompatibleMerge(path) => {
+                        GenerationError::YamlDocumentConflict(index, path)
+                    }
+                    other => other,
+                })?;
+            }
+            Ok(merged)
+        }
+    }
+}
+
+/// The source of each document in a YAML file, split where each one
+/// starts. The lines before a document are left blank, so errors in it
+/// are reported on the lines they are on in the file.
+fn document_sources(yaml: &str) -> Result<Vec<String>, GenerationError> {
+    #[derive(Default)]
+    struct DocumentFinder {
+        starts: Vec<usize>,
+    }
+
+    impl MarkedEventReceiver for DocumentFinder {
+        fn on_event(&mut self, event: Event, mark: Marker) {
+            if let Event::DocumentStart = event {
+                self.starts.push(mark.index());
+            }
+        }
+    }
+
+    let mut finder = DocumentFinder::default();
+    Parser::new(yaml.chars())
+        .load(&mut finder, true)
+        .map_err(|err| scan_error(err, yaml))?;
+
+    // Anything before the first document, like a `%YAML` directive,
+    // belongs to it.
+    let mut starts = finder
+        .starts
+        .iter()
+        .map(|&index| byte_offset(yaml, index))
+        .collect::<Vec<usize>>();
+    if let Some(first) = starts.first_mut() {
+        *first = 0;
+    }
+
+    let ends = starts.iter().skip(1).copied().chain(Some(yaml.len()));
+    let documents = starts
+        .iter()
+        .zip(ends)
+        .map(|(&start, end)| {
+            let preceding_lines = yaml[..start].matches('\n').count();
+            "\n".repeat(preceding_lines) + &yaml[start..end]
+        })
+        .collect();
+    Ok(documents)
+}
+
+/// Parse a YAML config with a single document.
+fn parse_document(yaml: &str, options: &StructOptions) -> Result<GenericStruct, GenerationError> {
+    check_recursive_aliases(yaml)?;
+    check_limits(yaml, options)?;
+
+    let deduplicated = match options.duplicate_keys {
+        DuplicateKeyBehavior::LastWins => None,
+        _ => {
+            let duplicates = find_duplicate_keys(yaml)?;
+            resolve_duplicate_keys(yaml, duplicates, options)?
+        }
+    };
+    let yaml = deduplicated.as_deref().unwrap_or(yaml);
+
+    let documents = YamlLoader::load_from_str(yaml).map_err(|err| scan_error(err, yaml))?;
+    match documents.first() {
+        Some(document) => parsing::check_depth(document, options)?,
+        // An empty file, or one with only comments, has no fields.
+        None => {
+            return Ok(parsing::parsed_to_generic_struct(
+                parsing::ParsedFields::new(),
+                options,
+                yaml_to_raw_value,
+            ))
+        }
+    }
+    check_integer_sizes(&documents)?;
+
+    let yaml_value = match options.yaml_scalars {
+        YamlScalarStyle::Yaml11 => {
+            let mut yaml_value: Value =
+                serde_yaml::from_str(yaml).map_err(|err| parse_error(err, yaml))?;
+            if let Some(document) = documents.first() {
+                mark_large_integers(document, &mut yaml_value);
+            }
+            yaml_value
+        }
+        style => resolve_document(yaml, style)?,
+    };
+
+    yaml_value_to_generic_struct(yaml_value, yaml, options)
+}
+
+/// Convert a parsed YAML value, read from `yaml`, to a struct, after
+/// expanding its merge keys.
+pub fn yaml_value_to_generic_struct(
+    mut yaml_value: Value,
+    yaml: &str,
+    options: &StructOptions,
+) -> Result<GenericStruct, GenerationError> {
     use parsing::ParsedFields;
 
-    let yaml_struct: ParsedFields<Value> = serde_yaml::from_str(yaml)
-        .map_err(|err| GenerationError::DeserializationFailed(err.to_string()))?;
+    parsing::check_depth(&yaml_value, options)?;
+    apply_merge_keys(&mut yaml_value)?;
+    stringify_keys(&mut yaml_value, "")?;
+
+    if let Value::Sequence(elements) = yaml_value {
+        let elements = elements
+            .into_iter()
+            .map(|element| match element {
+                Value::Mapping(_) => serde_yaml::from_value(element)
+                    .map(Ok)
+                    .map_err(|err| parse_error(err, yaml)),
+                other => Ok(Err(other)),
+            })
+            .collect::<Result<_, _>>()?;
+        let mut generic_struct =
+            parsing::parsed_to_root_array(elements, options, yaml_to_raw_value);
+        nulls::apply_null_behavior(&mut generic_struct, options)?;
+        return Ok(generic_struct);
+    }
+
+    let yaml_struct: ParsedFields<Value> =
+        serde_yaml::from_value(yaml_value).map_err(|err| parse_error(err, yaml))?;
 
-    let generic_struct = parsing::parsed_to_generic_struct(yaml_struct, options, yaml_to_raw_value);
+    let mut generic_struct =
+        parsing::parsed_to_generic_struct(yaml_struct, options, yaml_to_raw_value);
+    nulls::apply_null_behavior(&mut generic_struct, options)?;
 
     Ok(generic_struct)
 }
 
+fn parse_error(err: serde_yaml::Error, yaml: &str) -> GenerationError {
+    let (line, column) = match err.location() {
+        Some(location) => (Some(location.line()), Some(location.column())),
+        None => (None, None),
+    };
+    GenerationError::ParseFailed(ParseError::new(err, yaml, line, column))
+}
+
+fn scan_error(err: ScanError, yaml: &str) -> GenerationError {
+    // `yaml_rust` counts columns from 0.
+    let (line, column) = (err.marker().line(), err.marker().col() + 1);
+    GenerationError::ParseFailed(ParseError::new(err, yaml, Some(line), Some(column)))
+}
+
+/// Fail if any alias refers to an anchor which contains it, since
+/// expanding it would never finish.
+fn check_recursive_aliases(yaml: &str) -> Result<(), GenerationError> {
+    #[derive(Default)]
+    struct AliasChecker {
+        open_anchors: Vec<usize>,
+        recursive_alias_line: Option<usize>,
+    }
+
+    impl MarkedEventReceiver for AliasChecker {
+        fn on_event(&mut self, event: Event, mark: Marker) {
+            match event {
+                Event::SequenceStart(anchor) | Event::MappingStart(anchor) => {
+                    self.open_anchors.push(anchor)
+                }
+                Event::SequenceEnd | Event::MappingEnd => {
+                    self.open_anchors.pop();
+                }
+                Event::Alias(anchor) if self.open_anchors.contains(&anchor) => {
+                    self.recursive_alias_line.get_or_insert(mark.line());
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let mut checker = AliasChecker::default();
+    Parser::new(yaml.chars())
+        .load(&mut checker, true)
+        .map_err(|err| scan_error(err, yaml))?;
+
+    match checker.recursive_alias_line {
+        Some(line) => Err(GenerationError::DeserializationFailed(format!(
+            "The YAML alias on line {} refers to an anchor which contains it.",
+            line
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Check a YAML document against `resource_limits` and `max_depth`
+/// before it's loaded. Loading copies the value an alias refers to wherever it's used, so a
+/// few lines of aliases to aliases can stand for billions of values: here
+/// each alias counts the values and depth of its anchor again.
+fn check_limits(yaml: &str, options: &StructOptions) -> Result<(), GenerationError> {
+    /// A sequence or mapping being read.
+    struct Frame {
+        anchor: usize,
+        path: String,
+        mapping: bool,
+        expecting_key: bool,
+        key: Option<String>,
+        /// How many values it holds with aliases expanded, counting
+        /// itself.
+        nodes: usize,
+        /// How many sequences and mappings deep the deepest one inside
+        /// it is.
+        height: usize,
+    }
+
+    struct LimitChecker<'a> {
+        counter: LimitCounter<'a>,
+        frames: Vec<Frame>,
+        /// The values and height (if it's a sequence or mapping) of each
+        /// anchored value.
+        anchors: HashMap<usize, (usize, Option<usize>)>,
+        error: Option<GenerationError>,
+    }
+
+    impl LimitChecker<'_> {
+        /// The path of a value starting now, or `None` if it's a key.
+        fn value_path(&self) -> Option<String> {
+            match self.frames.last() {
+                Some(frame) if frame.mapping && frame.expecting_key => None,
+                Some(frame) if frame.mapping => {
+                    Some(paths::join(&frame.path, frame.key.as_deref().unwrap_or("")))
+                }
+                Some(frame) => Some(frame.path.clone()),
+                None => Some(String::new()),
+            }
+        }
+
+        /// Add a value which has been read to the sequence or mapping
+        /// holding it.
+        fn end_value(&mut self, nodes: usize, height: Option<usize>) {
+            if let Some(parent) = self.frames.last_mut() {
+                parent.nodes = parent.nodes.saturating_add(nodes);
+                if let Some(height) = height {
+                    parent.height = parent.height.max(height + 1);
+                }
+                if parent.mapping {
+                    if parent.expecting_key {
+                        parent.key = None;
+                    }
+                    parent.expecting_key = !parent.expecting_key;
+                }
+            }
+        }
+
+        fn check_event(&mut self, event: Event) -> Result<(), GenerationError> {
+            match event {
+                Event::Scalar(ref value, _, anchor, _) => match self.value_path() {
+                    Some(path) => {
+                        self.counter.check_string(value.len(), &path)?;
+                        self.counter.add_nodes(1, &path)?;
+                        if anchor != 0 {
+                            self.anchors.insert(anchor, (1, None));
+                        }
+                        self.end_value(1, None);
+                    }
+                    None => {
+                        let frame = self.frames.last_mut().expect("keys are in mappings");
+                        self.counter
+                            .check_string(value.len(), &paths::join(&frame.path, value))?;
+                        frame.key = Some(value.clone());
+                        frame.expecting_key = false;
+                    }
+                },
+                Event::Alias(anchor) => match self.value_path() {
+                    Some(path) => {
+                        let (nodes, height) =
+                            self.anchors.get(&anchor).copied().unwrap_or((1, None));
+                        if let Some(height) = height {
+                            self.counter
+                                .check_depth(self.frames.len() + height, &path)?;
+                        }
+                        self.counter.add_nodes(nodes, &path)?;
+                        self.end_value(nodes, height);
+                    }
+                    None => self.end_value(0, None),
+                },
+                Event::SequenceStart(anchor) | Event::MappingStart(anchor) => {
+                    let mapping = matches!(event, Event::MappingStart(_));
+                    // A sequence or mapping used as a key is counted as
+                    // if it were in its mapping's place.
+                    let path = self.value_path().unwrap_or_else(|| {
+                        self.frames
+                            .last()
+                            .map_or_else(String::new, |frame| frame.path.clone())
+                    });
+                    self.counter.check_depth(self.frames.len(), &path)?;
+                    self.counter.add_nodes(1, &path)?;
+                    self.frames.push(Frame {
+                        anchor,
+                        path,
+                        mapping,
+                        expecting_key: true,
+                        key: None,
+                        nodes: 1,
+                        height: 0,
+                    });
+                }
+                Event::SequenceEnd | Event::MappingEnd => {
+                    if let Some(frame) = self.frames.pop() {
+                        if frame.anchor != 0 {
+                            self.anchors
+                                .insert(frame.anchor, (frame.nodes, Some(frame.height)));
+                        }
+                        self.end_value(frame.nodes, Some(frame.height));
+                    }
+                }
+                _ => (),
+            }
+            Ok(())
+        }
+    }
+
+    impl MarkedEventReceiver for LimitChecker<'_> {
+        fn on_event(&mut self, event: Event, _mark: Marker) {
+            if self.error.is_none() {
+                self.error = self.check_event(event).err();
+            }
+        }
+    }
+
+    let mut checker = LimitChecker {
+        counter: LimitCounter::new(options),
+        frames: Vec::new(),
+        anchors: HashMap::new(),
+        error: None,
+    };
+    Parser::new(yaml.chars())
+        .load(&mut checker, true)
+        .map_err(|err| scan_error(err, yaml))?;
+
+    match checker.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Find the keys which appear more than once in the same mapping. Merge
+/// keys (`<<`) are left alone.
+fn find_duplicate_keys(yaml: &str) -> Result<Vec<DuplicateKey>, GenerationError> {
+    enum Frame {
+        Sequence {
+            path: String,
+        },
+        Mapping {
+            path: String,
+            flow: bool,
+            expecting_key: bool,
+            /// The key of each entry, if it's a scalar, and the position
+            /// where the entry starts.
+            entries: Vec<(Option<String>, Marker)>,
+        },
+    }
+
+    struct DuplicateFinder<'a> {
+        yaml: &'a str,
+        frames: Vec<Frame>,
+        duplicates: Vec<DuplicateKey>,
+    }
+
+    impl DuplicateFinder<'_> {
+        /// The path of a node starting now, recording it as a key if one
+        /// is expected.
+        fn start_node(&mut self, key: Option<&str>, mark: Marker) -> String {
+            match self.frames.last_mut() {
+                Some(Frame::Sequence { ref path }) => path.clone(),
+                Some(Frame::Mapping {
+                    ref path,
+                    expecting_key,
+                    ref mut entries,
+                    ..
+                }) => {
+                    if *expecting_key {
+                        entries.push((key.map(str::to_owned), mark));
+                        String::new()
+                    } else {
+                        let key = entries.last().and_then(|(key, _)| key.as_deref());
+                        paths::join(path, key.unwrap_or(""))
+                    }
+                }
+                None => String::new(),
+            }
+        }
+
+        fn end_node(&mut self) {
+            if let Some(Frame::Mapping {
+                ref mut expecting_key,
+                ..
+            }) = self.frames.last_mut()
+            {
+                *expecting_key = !*expecting_key;
+            }
+        }
+
+        fn end_mapping(
+            &mut self,
+            path: &str,
+            flow: bool,
+            entries: &[(Option<String>, Marker)],
+            end: Marker,
+        ) {
+            let yaml = self.yaml;
+            let offset = |mark: &Marker| byte_offset(yaml, mark.index());
+            let mut first_lines = BTreeMap::new();
+
+            for (index, (key, mark)) in entries.iter().enumerate() {
+                let key = match *key {
+                    Some(ref key) if key != "<<" => key,
+                    _ => continue,
+                };
+                let first_line = match first_lines.get(key) {
+                    Some(&line) => line,
+                    None => {
+                        first_lines.insert(key, mark.line());
+                        continue;
+                    }
+                };
+
+                let start = offset(mark);
+                let next = entries.get(index + 1).map(|(_, mark)| mark);
+                let removal = match (flow, next) {
+                    // `a: 1, ` up to the next key.
+                    (true, Some(next)) => start..offset(next),
+                    // `, a: 1` up to the closing brace.
+                    (true, None) => {
+                        let comma = self.yaml[..start].trim_end().len() - 1;
+                        comma..offset(&end)
+                    }
+                    // Whole lines, up to the next key or the end of the
+                    // mapping.
+                    (false, next) => {
+                        let end = offset(next.unwrap_or(&end));
+                        line_boundary(self.yaml, start)..line_boundary(self.yaml, end)
+                    }
+                };
+
+                self.duplicates.push(DuplicateKey {
+                    path: paths::join(path, key),
+                    lines: (first_line, mark.line()),
+                    removal,
+                });
+            }
+        }
+    }
+
+    impl MarkedEventReceiver for DuplicateFinder<'_> {
+        fn on_event(&mut self, event: Event, mark: Marker) {
+            match event {
+                Event::Scalar(ref value, ..) => {
+                    self.start_node(Some(value), mark);
+                    self.end_node();
+                }
+                Event::Alias(_) => {
+                    self.start_node(None, mark);
+                    self.end_node();
+                }
+                Event::SequenceStart(_) => {
+                    let path = self.start_node(None, mark);
+                    self.frames.push(Frame::Sequence { path });
+                }
+                Event::MappingStart(_) => {
+                    let path = self.start_node(None, mark);
+                    let flow = self.yaml[byte_offset(self.yaml, mark.index())..].starts_with('{');
+                    self.frames.push(Frame::Mapping {
+                        path,
+                        flow,
+                        expecting_key: true,
+                        entries: Vec::new(),
+                    });
+                }
+                Event::SequenceEnd => {
+                    self.frames.pop();
+                    self.end_node();
+                }
+                Event::MappingEnd => {
+                    if let Some(Frame::Mapping {
+                        path,
+                        flow,
+                        entries,
+                        ..
+                    }) = self.frames.pop()
+                    {
+                        self.end_mapping(&path, flow, &entries, mark);
+                    }
+                    self.end_node();
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let mut finder = DuplicateFinder {
+        yaml,
+        frames: Vec::new(),
+        duplicates: Vec::new(),
+    };
+    Parser::new(yaml.chars())
+        .load(&mut finder, true)
+        .map_err(|err| scan_error(err, yaml))?;
+
+    Ok(finder.duplicates)
+}
+
+/// The byte offset of the character at `index`, since `yaml_rust`
+/// positions count characters.
+fn byte_offset(yaml: &str, index: usize) -> usize {
+    yaml.char_indices()
+        .nth(index)
+        .map_or(yaml.len(), |(offset, _)| offset)
+}
+
+/// The start of the line containing `offset`, if only indentation comes
+/// before it on that line, or else `offset` itself.
+fn line_boundary(yaml: &str, offset: usize) -> usize {
+    let line_start = yaml[..offset].rfind('\n').map_or(0, |newline| newline + 1);
+    if yaml[line_start..offset].trim().is_empty() {
+        line_start
+    } else {
+        offset
+    }
+}
+
+impl parsing::Nested for Yaml {
+    fn children(&self) -> Option<Vec<(Option<&str>, &Self)>> {
+        match *self {
+            Yaml::Hash(ref fields) => Some(
+                fields
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value))
+                    .collect(),
+            ),
+            Yaml::Array(ref values) => Some(values.iter().map(|value| (None, value)).collect()),
+            _ => None,
+        }
+    }
+}
+
+impl parsing::Nested for Value {
+    fn children(&self) -> Option<Vec<(Option<&str>, &Self)>> {
+        match *self {
+            Value::Mapping(ref fields) => Some(
+                fields
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value))
+                    .collect(),
+            ),
+            Value::Sequence(ref values) => Some(values.iter().map(|value| (None, value)).collect()),
+            _ => None,
+        }
+    }
+}
+
+/// Fail if any integer is too large for 128 bits, since it could only be
+/// read as a float.
+fn check_integer_sizes(documents: &[Yaml]) -> Result<(), GenerationError> {
+    fn check(value: &Yaml, path: &str) -> Result<(), GenerationError> {
+        match *value {
+            // Integers which don't fit in `i64` are kept as text.
+            Yaml::Real(ref literal) => parsing::check_integer_literal(path, literal),
+            Yaml::Array(ref values) => values.iter().try_for_each(|value| check(value, path)),
+            Yaml::Hash(ref fields) => fields.iter().try_for_each(|(key, value)| {
+                let key = match *key {
+                    Yaml::String(ref key) | Yaml::Real(ref key) => key.clone(),
+                    Yaml::Integer(key) => key.to_string(),
+                    Yaml::Boolean(key) => key.to_string(),
+                    _ => String::new(),
+                };
+                check(value, &paths::join(path, &key))
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    documents
+        .iter()
+        .try_for_each(|document| check(document, ""))
+}
+
+/// Marks the text of an integer too large for 64 bits, which
+/// `serde_yaml` would read as a float. Such integers are replaced with
+/// strings starting with this before being converted.
+const LARGE_INTEGER_MARKER: &str = "\u{0}large integer:";
+
+/// Replace the integers which `serde_yaml` read as floats, because they
+/// don't fit in 64 bits, with their original text from the `yaml_rust`
+/// document, marked with `LARGE_INTEGER_MARKER`.
+fn mark_large_integers(document: &Yaml, value: &mut Value) {
+    match (document, value) {
+        (Yaml::Real(literal), value) => {
+            let read_as_float = matches!(*value, Value::Number(ref number) if number.is_f64());
+            if read_as_float && parsing::large_integer(literal).is_some() {
+                *value = Value::String(format!("{}{}", LARGE_INTEGER_MARKER, literal));
+            }
+        }
+        (Yaml::Array(documents), Value::Sequence(values)) => {
+            for (document, value) in documents.iter().zip(values) {
+                mark_large_integers(document, value);
+            }
+        }
+        (Yaml::Hash(documents), Value::Mapping(mapping)) => {
+            for (key, document) in documents {
+                let key = match *key {
+                    Yaml::String(ref key) => Value::String(key.clone()),
+                    Yaml::Integer(key) => Value::Number(key.into()),
+                    Yaml::Boolean(key) => Value::Bool(key),
+                    _ => continue,
+                };
+                if let Some(value) = mapping.get_mut(&key) {
+                    mark_large_integers(document, value);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// A node of a YAML document, with its scalars not yet given types.
+#[derive(Clone)]
+enum Node {
+    Scalar {
+        text: String,
+        quoted: bool,
+        tag: Option<String>,
+    },
+    Sequence(Vec<Node>),
+    Mapping(Vec<(Node, Node)>),
+}
+
+/// Read the first document of a YAML config, giving its scalars types
+/// by `style` rather than as `serde_yaml` would.
+fn resolve_document(yaml: &str, style: YamlScalarStyle) -> Result<Value, GenerationError> {
+    enum Frame {
+        Sequence(usize, Vec<Node>),
+        Mapping(usize, Vec<(Node, Node)>, Option<Node>),
+    }
+
+    #[derive(Default)]
+    struct NodeBuilder {
+        frames: Vec<Frame>,
+        anchors: HashMap<usize, Node>,
+        document: Option<Node>,
+    }
+
+    impl NodeBuilder {
+        fn add(&mut self, node: Node, anchor: usize) {
+            if anchor != 0 {
+                self.anchors.insert(anchor, node.clone());
+            }
+            match self.frames.last_mut() {
+                Some(Frame::Sequence(_, ref mut nodes)) => nodes.push(node),
+                Some(Frame::Mapping(_, ref mut entries, ref mut key)) => match key.take() {
+                    Some(key) => entries.push((key, node)),
+                    None => *key = Some(node),
+                },
+                None => {
+                    self.document.get_or_insert(node);
+                }
+            }
+        }
+    }
+
+    impl MarkedEventReceiver for NodeBuilder {
+        fn on_event(&mut self, event: Event, _: Marker) {
+            match event {
+                Event::Scalar(text, scalar_style, anchor, tag) => {
+                    let tag = match tag {
+                        Some(TokenType::Tag(handle, suffix)) => Some(handle + &suffix),
+                        _ => None,
+                    };
+                    let quoted = scalar_style != TScalarStyle::Plain;
+                    self.add(Node::Scalar { text, quoted, tag }, anchor);
+                }
+                Event::Alias(anchor) => {
+                    if let Some(node) = self.anchors.get(&anchor).cloned() {
+                        self.add(node, 0);
+                    }
+                }
+                Event::SequenceStart(anchor) => {
+                    self.frames.push(Frame::Sequence(anchor, Vec::new()))
+                }
+                Event::MappingStart(anchor) => {
+                    self.frames.push(Frame::Mapping(anchor, Vec::new(), None))
+                }
+                Event::SequenceEnd | Event::MappingEnd => match self.frames.pop() {
+                    Some(Frame::Sequence(anchor, nodes)) => self.add(Node::Sequence(nodes), anchor),
+                    Some(Frame::Mapping(anchor, entries, _)) => {
+                        self.add(Node::Mapping(entries), anchor)
+                    }
+                    None => (),
+                },
+                _ => (),
+            }
+        }
+    }
+
+    let mut builder = NodeBuilder::default();
+    Parser::new(yaml.chars())
+        .load(&mut builder, false)
+        .map_err(|err| scan_error(err, yaml))?;
+
+    let mut document = builder.document.ok_or_else(|| {
+        GenerationError::DeserializationFailed("The YAML config is empty.".to_owned())
+    })?;
+    if style == YamlScalarStyle::StringsWhenQuotedAmbiguity {
+        quote_ambiguous_scalars(vec![&mut document]);
+    }
+    Ok(resolve_node(document))
+}
+
+/// Mark the unquoted scalars among `nodes`, which are all in the same
+/// place in the config, as quoted if any of them is a quoted scalar
+/// which would otherwise read as something other than a string. The
+/// elements of arrays, and the values with the same key in mappings, are
+/// then checked in the same way.
+fn quote_ambiguous_scalars(nodes: Vec<&mut Node>) {
+    let mut scalars = Vec::new();
+    let mut elements = Vec::new();
+    let mut fields: BTreeMap<String, Vec<&mut Node>> = BTreeMap::new();
+
+    for node in nodes {
+        match node {
+            Node::Sequence(nodes) => elements.extend(nodes.iter_mut()),
+            Node::Mapping(entries) => {
+                for (key, value) in entries.iter_mut() {
+                    if let Node::Scalar { ref text, .. } = *key {
+                        fields.entry(text.clone()).or_default().push(value);
+                    }
+                }
+            }
+            scalar => scalars.push(scalar),
+        }
+    }
+
+    let ambiguous = scalars.iter().any(|scalar| match **scalar {
+        Node::Scalar {
+            ref text,
+            quoted: true,
+            ..
+        } => !matches!(core_scalar(text), Value::String(_)),
+        _ => false,
+    });
+    if ambiguous {
+        for scalar in scalars {
+            if let Node::Scalar {
+                ref text,
+                ref mut quoted,
+                ref tag,
+            } = *scalar
+            {
+                *quoted = *quoted || (tag.is_none() && !matches!(core_scalar(text), Value::Null));
+            }
+        }
+    }
+
+    if !elements.is_empty() {
+        quote_ambiguous_scalars(elements);
+    }
+    for (_, values) in fields {
+        quote_ambiguous_scalars(values);
+    }
+}
+
+fn resolve_node(node: Node) -> Value {
+    match node {
+        Node::Scalar { text, quoted, tag } => match (quoted, tag.as_deref()) {
+            (true, _) | (false, Some("!!str")) => Value::String(text),
+            (false, None) | (false, Some("!!bool" | "!!int" | "!!float" | "!!null")) => {
+                core_scalar(&text)
+            }
+            // `serde_yaml` reads other tags as strings too.
+            (false, Some(_)) => Value::String(text),
+        },
+        Node::Sequence(nodes) => Value::Sequence(nodes.into_iter().map(resolve_node).collect()),
+        Node::Mapping(entries) => Value::Mapping(
+            entries
+                .into_iter()
+                .map(|(key, value)| {
+                    // Keys are field names, so are never given other types.
+                    let key = match key {
+                        Node::Scalar { text, .. } => Value::String(text),
+                        key => resolve_node(key),
+                    };
+                    (key, resolve_node(value))
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// The value of an unquoted scalar by the YAML 1.2 core schema. Integers
+/// too large for 64 bits are marked with `LARGE_INTEGER_MARKER`.
+fn core_scalar(text: &str) -> Value {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => {
+            return Value::Number(f64::INFINITY.into())
+        }
+        "-.inf" | "-.Inf" | "-.INF" => return Value::Number(f64::NEG_INFINITY.into()),
+        ".nan" | ".NaN" | ".NAN" => return Value::Number(f64::NAN.into()),
+        _ => (),
+    }
+
+    let radix_integer = |digits: &str, radix| match u64::from_str_radix(digits, radix) {
+        Ok(value) if !digits.starts_with(['+', '-']) => Value::Number(value.into()),
+        _ => Value::String(text.to_owned()),
+    };
+    if let Some(digits) = text.strip_prefix("0o") {
+        return radix_integer(digits, 8);
+    }
+    if let Some(digits) = text.strip_prefix("0x") {
+        return radix_integer(digits, 16);
+    }
+
+    let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(index) => (&unsigned[..index], Some(&unsigned[index + 1..])),
+        None => (unsigned, None),
+    };
+    let (whole, fraction) = match mantissa.find('.') {
+        Some(index) => (&mantissa[..index], Some(&mantissa[index + 1..])),
+        None => (mantissa, None),
+    };
+    let all_digits = |digits: &str| digits.chars().all(|c| c.is_ascii_digit());
+
+    if fraction.is_none() && exponent.is_none() && !whole.is_empty() && all_digits(whole) {
+        return if let Ok(value) = text.parse::<i64>() {
+            Value::Number(value.into())
+        } else if let (false, Ok(value)) = (text.starts_with('-'), unsigned.parse::<u64>()) {
+            Value::Number(value.into())
+        } else {
+            Value::String(format!("{}{}", LARGE_INTEGER_MARKER, text))
+        };
+    }
+
+    let valid_mantissa = all_digits(whole)
+        && fraction.is_none_or(all_digits)
+        && !(whole.is_empty() && fraction.is_none_or(str::is_empty));
+    let valid_exponent = exponent.is_none_or(|exponent| {
+        let digits = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+        !digits.is_empty() && all_digits(digits)
+    });
+    match text.parse::<f64>() {
+        Ok(value) if valid_mantissa && valid_exponent => Value::Number(value.into()),
+        _ => Value::String(text.to_owned()),
+    }
+}
+
+/// Expand merge keys (`<<: *defaults`) into the mappings containing
+/// them. Keys already in a mapping take precedence over merged keys, and
+/// earlier mappings take precedence over later ones when merging a list.
+///
+/// Note that `serde_yaml` doesn't do this when deserializing, so the
+/// generated load functions can't load configs which use merge keys.
+fn apply_merge_keys(value: &mut Value) -> Result<(), GenerationError> {
+    match *value {
+        Value::Mapping(ref mut mapping) => {
+            for (_, value) in mapping.iter_mut() {
+                apply_merge_keys(value)?;
+            }
+
+            let merge_key = Value::String("<<".to_owned());
+            if let Some(merged) = mapping.remove(&merge_key) {
+                let merged_mappings = match merged {
+                    Value::Mapping(merged) => vec![merged],
+                    Value::Sequence(values) => values
+                        .into_iter()
+                        .map(|value| match value {
+                            Value::Mapping(merged) => Ok(merged),
+                            _ => Err(invalid_merge_error()),
+                        })
+                        .collect::<Result<Vec<Mapping>, GenerationError>>()?,
+                    _ => return Err(invalid_merge_error()),
+                };
+
+                for merged in merged_mappings {
+                    for (key, value) in merged {
+                        if !mapping.contains_key(&key) {
+                            mapping.insert(key, value);
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+        Value::Sequence(ref mut values) => {
+            for value in values {
+                apply_merge_keys(value)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Replace the number and boolean keys of mappings, like `1:` or `true:`,
+/// with their text, since fields and map keys are named by strings.
+fn stringify_keys(value: &mut Value, path: &str) -> Result<(), GenerationError> {
+    match *value {
+        Value::Mapping(ref mut mapping) => {
+            let fields = std::mem::replace(mapping, Mapping::new());
+            for (key, mut value) in fields {
+                let key = match key {
+                    Value::String(key) => key,
+                    Value::Number(key) => key.to_string(),
+                    Value::Bool(key) => key.to_string(),
+                    _ => return Err(GenerationError::UnsupportedYamlKey(path.to_owned())),
+                };
+                let key_path = paths::join(path, &key);
+                stringify_keys(&mut value, &key_path)?;
+                if mapping.insert(Value::String(key), value).is_some() {
+                    return Err(GenerationError::DuplicateKey(key_path));
+                }
+            }
+            Ok(())
+        }
+        Value::Sequence(ref mut values) => values
+            .iter_mut()
+            .try_for_each(|value| stringify_keys(value, path)),
+        _ => Ok(()),
+    }
+}
+
+fn invalid_merge_error() -> GenerationError {
+    let m = "YAML merge keys (`<<`) must refer to a mapping or a list of mappings.".to_owned();
+    GenerationError::DeserializationFailed(m)
+}
+
 fn yaml_to_raw_value(
     super_struct: &str,
     super_key: &str,
@@ -25,16 +982,18 @@ fn yaml_to_raw_value(
     options: &StructOptions,
 ) -> GenericValue {
     match value {
-        Value::Null => GenericValue::Option(None),
+        Value::Null => nulls::null_value(options),
         Value::Bool(value) => GenericValue::Bool(value),
         Value::Number(value) => match (value.as_i64(), value.as_u64(), value.as_f64()) {
-            // TODO: Add some unit tests for this
-            (Some(x), _, _) => parsing::preferred_int(x, options.default_int_size),
+            (Some(x), _, _) => GenericValue::I64(x),
             (None, Some(x), _) => GenericValue::U64(x),
             (None, None, Some(x)) => parsing::preferred_float(x, options.default_float_size),
             _ => unimplemented!("Should handle error here"), // TODO
         },
-        Value::String(value) => GenericValue::String(value),
+        Value::String(value) => match value.strip_prefix(LARGE_INTEGER_MARKER) {
+            Some(literal) => parsing::large_integer(literal).expect("marked integers are valid"),
+            None => GenericValue::String(value),
+        },
         Value::Sequence(values) => GenericValue::Array(
             values
                 .into_iter()
@@ -46,7 +1005,7 @@ fn yaml_to_raw_value(
             let values = values
                 .into_iter()
                 .map(|(key, value)| {
-                    let key = key.as_str().unwrap().to_owned();
+                    let key = key.as_str().expect("keys are stringified").to_owned();
                     let value = yaml_to_raw_value(&sub_struct_name, &key, value, options);
                     (key, value)
                 })
@@ -54,7 +1013,217 @@ fn yaml_to_raw_value(
             GenericValue::Struct(GenericStruct {
                 struct_name: sub_struct_name,
                 fields: values,
+                original_keys: BTreeMap::new(),
+                doc: None,
+                field_docs: BTreeMap::new(),
+                field_cfgs: BTreeMap::new(),
+                field_spans: BTreeMap::new(),
+                flattened_fields: BTreeSet::new(),
+                absent_fields: BTreeSet::new(),
+                field_order: Vec::new(),
             })
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(yaml: &str) -> Result<GenericStruct, GenerationError> {
+        parse_yaml(yaml, &StructOptions::default())
+    }
+
+    #[test]
+    fn test_merge_keys() {
+        let yaml = "
+defaults: &defaults
+  host: localhost
+  port: 80
+production:
+  <<: *defaults
+  port: 8080
+";
+        let config = parse(yaml).unwrap();
+
+        match config.fields["production"] {
+            GenericValue::Struct(ref production) => {
+                assert_eq!(production.fields.len(), 2);
+                match production.fields["port"] {
+                    GenericValue::I64(8080) => (),
+                    ref other => panic!("Unexpected port: {:?}", other),
+                }
+            }
+            ref other => panic!("Expected struct, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_merging_a_list_of_mappings() {
+        let yaml = "
+a: &a
+  x: 1
+b: &b
+  x: 2
+  y: 2
+merged:
+  <<: [*a, *b]
+";
+        let config = parse(yaml).unwrap();
+
+        match config.fields["merged"] {
+            GenericValue::Struct(ref merged) => match (&merged.fields["x"], &merged.fields["y"]) {
+                (GenericValue::I64(1), GenericValue::I64(2)) => (),
+                other => panic!("Unexpected merge result: {:?}", other),
+            },
+            ref other => panic!("Expected struct, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_aliased_sequences() {
+        let config = parse("a: &list [1, 2]\nb: *list\n").unwrap();
+
+        match config.fields["b"] {
+            GenericValue::Array(ref values) => assert_eq!(values.len(), 2),
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_recursive_aliases() {
+        match parse("a: &a\n  b: *a\n") {
+            Err(GenerationError::DeserializationFailed(message)) => {
+                assert!(message.contains("line 2"))
+            }
+            other => panic!("Expected recursive alias error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_merge_value() {
+        assert!(parse("a:\n  <<: 1\n").is_err());
+    }
+
+    fn parse_with(yaml: &str, yaml_scalars: YamlScalarStyle) -> GenericStruct {
+        let options = StructOptions {
+            yaml_scalars,
+            ..StructOptions::default()
+        };
+        parse_yaml(yaml, &options).unwrap()
+    }
+
+    fn strings(value: &GenericValue) -> Vec<&str> {
+        match *value {
+            GenericValue::Array(ref values) => values
+                .iter()
+                .map(|value| match *value {
+                    GenericValue::String(ref value) => value.as_str(),
+                    ref other => panic!("Expected string, found {:?}", other),
+                })
+                .collect(),
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_quoted_scalars_are_strings() {
+        let yaml = "version: \"3.0\"\nflag: 'true'\nmode: \"0o777\"\nnothing: \"null\"\n";
+        for style in [
+            YamlScalarStyle::Yaml11,
+            YamlScalarStyle::CoreSchema,
+            YamlScalarStyle::StringsWhenQuotedAmbiguity,
+        ] {
+            let config = parse_with(yaml, style);
+            for (key, text) in [
+                ("version", "3.0"),
+                ("flag", "true"),
+                ("mode", "0o777"),
+                ("nothing", "null"),
+            ] {
+                match config.fields[key] {
+                    GenericValue::String(ref value) => assert_eq!(value, text),
+                    ref other => panic!("Expected string for {}, found {:?}", key, other),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_core_schema() {
+        let yaml = "country: NO\nword: nan\nflag: True\nnothing: NULL\nmode: 0o777\nratio: 1e3\n";
+
+        let config = parse_with(yaml, YamlScalarStyle::CoreSchema);
+        match (
+            &config.fields["country"],
+            &config.fields["word"],
+            &config.fields["flag"],
+            &config.fields["nothing"],
+            &config.fields["mode"],
+            &config.fields["ratio"],
+        ) {
+            (
+                GenericValue::String(country),
+                GenericValue::String(word),
+                GenericValue::Bool(true),
+                GenericValue::Option(None),
+                GenericValue::I64(511),
+                GenericValue::F64(ratio),
+            ) if country == "NO" && word == "nan" && *ratio == 1000.0 => (),
+            other => panic!("Unexpected core schema values: {:?}", other),
+        }
+
+        let config = parse_with(yaml, YamlScalarStyle::Yaml11);
+        match (&config.fields["word"], &config.fields["flag"]) {
+            (GenericValue::F64(word), GenericValue::String(flag)) if word.is_nan() => {
+                assert_eq!(flag, "True")
+            }
+            other => panic!("Unexpected serde_yaml values: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_strings_when_quoted_ambiguity() {
+        let yaml = "
+versions: [\"1.0\", 1.1]
+servers:
+  - port: \"80\"
+  - port: 8080
+counts: [1, 2]
+";
+        let config = parse_with(yaml, YamlScalarStyle::StringsWhenQuotedAmbiguity);
+
+        assert_eq!(strings(&config.fields["versions"]), ["1.0", "1.1"]);
+        match config.fields["servers"] {
+            GenericValue::Array(ref servers) => {
+                let ports = servers
+                    .iter()
+                    .map(|server| match *server {
+                        GenericValue::Struct(ref server) => server.fields["port"].clone(),
+                        ref other => panic!("Expected struct, found {:?}", other),
+                    })
+                    .collect();
+                assert_eq!(strings(&GenericValue::Array(ports)), ["80", "8080"]);
+            }
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+        match config.fields["counts"] {
+            GenericValue::Array(ref values) => assert!(matches!(
+                values[..],
+                [GenericValue::I64(1), GenericValue::I64(2)]
+            )),
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+
+        let config = parse_with("versions: [\"1.0\", 1.1]\n", YamlScalarStyle::CoreSchema);
+        match config.fields["versions"] {
+            GenericValue::Array(ref values) => {
+                assert!(matches!(
+                    values[..],
+                    [GenericValue::String(_), GenericValue::F64(_)]
+                ))
+            }
+            ref other => panic!("Expected array, found {:?}", other),
+        }
+    }
+}
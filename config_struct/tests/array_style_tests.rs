@@ -0,0 +1,243 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{
+    ArrayStyle, Format, GenerationError, OptionsError, SerdeSupport, StructOptions,
+};
+
+fn generate(source: &str, options: &StructOptions) -> Result<String, GenerationError> {
+    config_struct::generate_config_from_source(Format::Toml, source, options)
+}
+
+fn style_options(array_style: ArrayStyle) -> StructOptions {
+    StructOptions {
+        array_style,
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_fixed_up_to() {
+    let code = generate(
+        "short = [1, 2]\nlong = [1, 2, 3, 4]\nempty = []\n",
+        &style_options(ArrayStyle::FixedUpTo(3)),
+    )
+    .unwrap();
+
+    assert!(code.contains("pub short: [i64; 2],"));
+    assert!(code.contains("    short: [1, 2],\n"));
+    assert!(code.contains("pub long: Cow<'static, [i64]>,"));
+    assert!(code.contains("    long: Cow::Borrowed(&[1, 2, 3, 4]),\n"));
+    assert!(code.contains("pub empty: Cow<'static, [()]>,"));
+}
+
+#[test]
+fn test_always_fixed() {
+    let values = (0..40).map(|n| n.to_string()).collect::<Vec<_>>();
+    let source = format!("values = [{}]\n", values.join(", "));
+
+    let code = generate(&source, &style_options(ArrayStyle::AlwaysFixed)).unwrap();
+    assert!(code.contains("pub values: [i64; 40],"));
+
+    let code = generate(&source, &style_options(ArrayStyle::AlwaysCow)).unwrap();
+    assert!(code.contains("pub values: Cow<'static, [i64]>,"));
+}
+
+#[test]
+fn test_max_array_size() {
+    let options = StructOptions {
+        max_array_size: 2,
+        ..StructOptions::default()
+    };
+    let code = generate("short = [1, 2]\nlong = [1, 2, 3]\n", &options).unwrap();
+
+    assert!(code.contains("pub short: [i64; 2],"));
+    assert!(code.contains("pub long: Cow<'static, [i64]>,"));
+}
+
+#[test]
+fn test_array_structs_share_lengths() {
+    let source = r#"
+        [[points]]
+        name = "a"
+        position = [1, 2]
+
+        [[points]]
+        name = "b"
+        position = [3, 4]
+    "#;
+    let code = generate(source, &style_options(ArrayStyle::FixedUpTo(4))).unwrap();
+
+    assert!(code.contains("pub position: [i64; 2],"));
+    assert!(code.contains("pub points: [_Config__points; 2],"));
+}
+
+#[test]
+fn test_mismatched_lengths() {
+    let source = r#"
+        [[points]]
+        position = [1, 2]
+
+        [[points]]
+        position = [3]
+    "#;
+
+    match generate(source, &style_options(ArrayStyle::FixedUpTo(4))) {
+        Err(GenerationError::MismatchedArrayLengths(path, lengths, setting)) => {
+            assert_eq!(path, "points.position");
+            assert_eq!(lengths, vec![1, 2]);
+            assert_eq!(setting, "array_style = ArrayStyle::AlwaysCow");
+        }
+        other => panic!("Expected mismatched lengths error, found {:?}", other),
+    }
+
+    // Arrays which are all too long to be fixed can differ in length.
+    assert!(generate(source, &style_options(ArrayStyle::FixedUpTo(0))).is_ok());
+
+    match generate(
+        "nested = [[1, 2], [3]]\n",
+        &style_options(ArrayStyle::AlwaysFixed),
+    ) {
+        Err(GenerationError::MismatchedArrayLengths(path, lengths, _)) => {
+            assert_eq!(path, "nested");
+            assert_eq!(lengths, vec![1, 2]);
+        }
+        other => panic!("Expected mismatched lengths error, found {:?}", other),
+    }
+
+    // `max_array_size` makes arrays fixed-size with the default style, so
+    // the error points to it instead.
+    let options = StructOptions {
+        max_array_size: 4,
+        ..StructOptions::default()
+    };
+    let error = generate(source, &options).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("set `max_array_size = 0` to use slices"));
+}
+
+#[test]
+fn test_serde_array_limit() {
+    let values = (0..33).map(|n| n.to_string()).collect::<Vec<_>>();
+    let source = format!("values = [{}]\n", values.join(", "));
+    let options = StructOptions {
+        array_style: ArrayStyle::AlwaysFixed,
+        serde_support: SerdeSupport::Yes,
+        ..StructOptions::default()
+    };
+
+    match generate(&source, &options) {
+        Err(GenerationError::FixedArrayTooLong(path, len)) => {
+            assert_eq!(path, "values");
+            assert_eq!(len, 33);
+        }
+        other => panic!("Expected array too long error, found {:?}", other),
+    }
+
+    let options = StructOptions {
+        array_style: ArrayStyle::FixedUpTo(32),
+        serde_support: SerdeSupport::Yes,
+        ..StructOptions::default()
+    };
+    let code = generate(&source, &options).unwrap();
+    assert!(code.contains("pub values: Cow<'static, [i64]>,"));
+}
+
+#[test]
+fn test_conflicting_array_options() {
+    let options = StructOptions {
+        max_array_size: 4,
+        array_style: ArrayStyle::AlwaysFixed,
+        ..StructOptions::default()
+    };
+
+    match generate("a = 1\n", &options) {
+        Err(GenerationError::StructOptions(OptionsError::ConflictingArrayStyle)) => (),
+        other => panic!("Expected conflicting options error, found {:?}", other),
+    }
+}
+
+/// Check that `values` is declared and written as a fixed-size array
+/// exactly when `fixed`, for a config generated from `len` values.
+fn assert_array_matches(code: &str, len: usize, fixed: bool, setting: &str) {
+    let elements = (0..len)
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let (declaration, value) = match (len, fixed) {
+        (0, _) => (
+            "pub values: Cow<'static, [()]>,".to_owned(),
+            "values: Cow::Borrowed(&[]),".to_owned(),
+        ),
+        (_, true) => (
+            format!("pub values: [i64; {}],", len),
+            format!("values: [{}],", elements),
+        ),
+        (_, false) => (
+            "pub values: Cow<'static, [i64]>,".to_owned(),
+            format!("values: Cow::Borrowed(&[{}]),", elements),
+        ),
+    };
+
+    assert!(
+        code.contains(&declaration) && code.contains(&value),
+        "{} with {} values:\n{}",
+        setting,
+        len,
+        code
+    );
+}
+
+#[test]
+fn test_array_types_match_values() {
+    let generate_len = |len: usize, options: &StructOptions| {
+        let values = (0..len).map(|n| n.to_string()).collect::<Vec<_>>();
+        generate(&format!("values = [{}]\n", values.join(", ")), options).unwrap()
+    };
+
+    for max_len in 0..4 {
+        let settings = vec![
+            (
+                format!("max_array_size: {}", max_len),
+                StructOptions {
+                    max_array_size: max_len,
+                    ..StructOptions::default()
+                },
+            ),
+            (
+                format!("FixedUpTo({})", max_len),
+                style_options(ArrayStyle::FixedUpTo(max_len)),
+            ),
+        ];
+        for (setting, options) in &settings {
+            for &len in &[0, 1, max_len, max_len + 1] {
+                let code = generate_len(len, options);
+                assert_array_matches(&code, len, len != 0 && len <= max_len, setting);
+            }
+        }
+    }
+
+    for &len in &[0, 1, 5] {
+        let code = generate_len(len, &style_options(ArrayStyle::AlwaysCow));
+        assert_array_matches(&code, len, false, "AlwaysCow");
+
+        let code = generate_len(len, &style_options(ArrayStyle::AlwaysFixed));
+        assert_array_matches(&code, len, len != 0, "AlwaysFixed");
+    }
+}
+
+#[test]
+fn test_zero_limits_match_always_cow() {
+    let source = "one = [1]\nthree = [1, 2, 3]\n";
+    let always_cow = generate(source, &style_options(ArrayStyle::AlwaysCow)).unwrap();
+
+    let zero_max = StructOptions {
+        max_array_size: 0,
+        ..StructOptions::default()
+    };
+    assert_eq!(generate(source, &zero_max).unwrap(), always_cow);
+    assert_eq!(
+        generate(source, &style_options(ArrayStyle::FixedUpTo(0))).unwrap(),
+        always_cow
+    );
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, StructOptions};
+
+fn generate(source: &str, options: &StructOptions) -> String {
+    config_struct::generate_config_from_source(Format::Toml, source, options).unwrap()
+}
+
+/// Compare the generated code for a config with many arrays of tables to
+/// `fixtures/entries.rs.snap`. Run with `UPDATE_SNAPSHOTS=1` to rewrite the
+/// snapshot after an intended change to the output.
+#[test]
+fn test_snapshot() {
+    let code = generate(
+        include_str!("fixtures/entries.toml"),
+        &StructOptions::default(),
+    );
+
+    let snapshot_path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/entries.rs.snap"
+    );
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(snapshot_path, &code).unwrap();
+    }
+    let snapshot = std::fs::read_to_string(snapshot_path).unwrap();
+    assert!(
+        code == snapshot,
+        "The generated code differs from {}:\n{}",
+        snapshot_path,
+        code
+    );
+}
+
+#[test]
+fn test_large_arrays_wrap() {
+    let entries = (0..3000)
+        .map(|n| format!("[[entries]]\nid = {}\nname = \"entry-{}\"\n", n, n))
+        .collect::<String>();
+
+    let code = generate(&entries, &StructOptions::default());
+    assert!(code.contains(
+        "    entries: Cow::Borrowed(&[
+        _Config__entries {
+            id: 0,
+            name: Cow::Borrowed(\"entry-0\"),
+        },
+"
+    ));
+    assert!(code.lines().all(|line| line.len() <= 100));
+}
+
+#[test]
+fn test_limits() {
+    let source = "short = [1, 2, 3]\nwide = [\"aaaaaaaaaa\", \"bbbbbbbbbb\", \"cccccccccc\"]\n";
+
+    let code = generate(source, &StructOptions::default());
+    assert!(code.contains("    short: Cow::Borrowed(&[1, 2, 3]),\n"));
+
+    let options = StructOptions {
+        max_inline_array_len: 2,
+        max_inline_array_width: 40,
+        ..StructOptions::default()
+    };
+    let code = generate(source, &options);
+    assert!(
+        code.contains("    short: Cow::Borrowed(&[\n        1,\n        2,\n        3,\n    ]),\n")
+    );
+    assert!(code.contains("    wide: Cow::Borrowed(&[\n        Cow::Borrowed(\"aaaaaaaaaa\"),\n"));
+}
+
+#[test]
+fn test_nested_arrays_wrap_inside_out() {
+    let source =
+        "grid = [[1, 2, 3], [4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20]]\n";
+
+    let code = generate(source, &StructOptions::default());
+    assert!(code.contains(
+        "    grid: Cow::Borrowed(&[
+        Cow::Borrowed(&[1, 2, 3]),
+        Cow::Borrowed(&[
+            4,
+"
+    ));
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, GenerationError, OptionsError, StringType, StructOptions};
+
+const SOURCE: &str = r#"
+icon = "iVBORw=="
+frames = ["AAE=", "/w=="]
+
+[assets]
+logo = "R0lG"
+"#;
+
+fn binary_options() -> StructOptions {
+    StructOptions {
+        binary_paths: vec![
+            "icon".to_owned(),
+            "frames".to_owned(),
+            "assets.logo".to_owned(),
+        ],
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_binary_fields_are_byte_slices() {
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &binary_options())
+        .unwrap();
+
+    assert!(code.contains("pub icon: Cow<'static, [u8]>,"));
+    assert!(code.contains("icon: Cow::Borrowed(&[0x89, 0x50, 0x4e, 0x47]),"));
+    assert!(code.contains("pub logo: Cow<'static, [u8]>,"));
+    assert!(code.contains("logo: Cow::Borrowed(&[0x47, 0x49, 0x46]),"));
+}
+
+#[test]
+fn test_binary_arrays() {
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &binary_options())
+        .unwrap();
+
+    assert!(code.contains("pub frames: Cow<'static, [Cow<'static, [u8]>]>,"));
+    assert!(code.contains(
+        "frames: Cow::Borrowed(&[Cow::Borrowed(&[0x00, 0x01]), Cow::Borrowed(&[0xff])]),"
+    ));
+}
+
+#[test]
+fn test_binary_static_slices() {
+    let options = StructOptions {
+        string_type: StringType::StaticStr,
+        ..binary_options()
+    };
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(code.contains("pub icon: &'static [u8],"));
+    assert!(code.contains("icon: &[0x89, 0x50, 0x4e, 0x47],"));
+}
+
+#[test]
+fn test_invalid_base64() {
+    let result = config_struct::generate_config_from_source(
+        Format::Toml,
+        "[assets]\nlogo = \"not base64!\"",
+        &binary_options(),
+    );
+
+    match result {
+        Err(GenerationError::InvalidBase64(path, _)) => assert_eq!(path, "assets.logo"),
+        other => panic!("Expected base64 error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_binary_fields_must_be_strings() {
+    let result =
+        config_struct::generate_config_from_source(Format::Toml, "icon = 1", &binary_options());
+
+    match result {
+        Err(GenerationError::InvalidBinaryField(path)) => assert_eq!(path, "icon"),
+        other => panic!("Expected binary field error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_binary_fields_with_dynamic_loading() {
+    let options = StructOptions {
+        generate_load_fns: true,
+        ..binary_options()
+    };
+    let result = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options);
+
+    match result {
+        Err(GenerationError::StructOptions(OptionsError::BinaryPathsWithDynamicLoading)) => (),
+        other => panic!("Expected options error, found {:?}", other),
+    }
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, GenerationError, OptionsError, StructOptions};
+
+const SOURCE: &str = r#"
+name = "App"
+
+[linux]
+shell = "sh"
+
+[windows]
+shell = "cmd"
+"#;
+
+fn cfg_options() -> StructOptions {
+    StructOptions {
+        cfg_sections: vec![
+            ("linux".to_owned(), "target_os = \"linux\"".to_owned()),
+            ("windows".to_owned(), "windows".to_owned()),
+        ]
+        .into_iter()
+        .collect(),
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_gated_fields() {
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, SOURCE, &cfg_options()).unwrap();
+
+    assert!(code.contains("    #[cfg(windows)]\n    pub windows: _Config__windows,"));
+    assert!(code.contains("#[cfg(windows)]\n#[derive(Debug, Clone)]\n#[allow(non_camel_case_types)]\npub struct _Config__windows {"));
+    assert!(code.contains("    #[cfg(target_os = \"linux\")]\n    linux: _Config__linux {"));
+    assert!(code.contains("    pub name: Cow<'static, str>,"));
+}
+
+#[test]
+fn test_shared_structs_are_not_gated() {
+    let options = StructOptions {
+        deduplicate_structs: true,
+        impl_default: true,
+        ..cfg_options()
+    };
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(code.contains(
+        "#[derive(Debug, Clone)]\n#[allow(non_camel_case_types)]\npub struct _Config__linux {"
+    ));
+    assert!(code.contains("    #[cfg(windows)]\n    pub windows: _Config__linux,"));
+    assert!(code.contains("\nimpl Default for _Config__linux {"));
+}
+
+#[test]
+fn test_invalid_predicate() {
+    let mut options = cfg_options();
+    options
+        .cfg_sections
+        .insert("windows".to_owned(), "windows)] fn f() {}".to_owned());
+    let result = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options);
+
+    match result {
+        Err(GenerationError::StructOptions(OptionsError::InvalidCfgPredicate(path, _))) => {
+            assert_eq!(path, "windows")
+        }
+        other => panic!("Expected invalid predicate error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_per_field_options_are_rejected() {
+    let options = StructOptions {
+        generate_builder: true,
+        ..cfg_options()
+    };
+    let result = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options);
+
+    match result {
+        Err(GenerationError::StructOptions(error @ OptionsError::CfgSectionsWith(_))) => {
+            assert_eq!(error.option_names(), &["cfg_sections", "generate_builder"])
+        }
+        other => panic!("Expected conflicting options error, found {:?}", other),
+    }
+}
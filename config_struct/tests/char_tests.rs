@@ -0,0 +1,53 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, GenerationError, StructOptions};
+
+const SOURCE: &str = r#"
+separator = ","
+bullets = ["-", "*"]
+name = "x"
+
+[keys]
+quit = "q"
+"#;
+
+fn char_options() -> StructOptions {
+    StructOptions {
+        char_paths: vec![
+            "separator".to_owned(),
+            "bullets".to_owned(),
+            "keys.quit".to_owned(),
+        ],
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_char_fields() {
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, SOURCE, &char_options()).unwrap();
+
+    assert!(code.contains("pub separator: char,"));
+    assert!(code.contains("separator: ',',"));
+    assert!(code.contains("pub bullets: Cow<'static, [char]>,"));
+    assert!(code.contains("bullets: Cow::Borrowed(&['-', '*']),"));
+    assert!(code.contains("pub quit: char,"));
+    assert!(code.contains("pub name: Cow<'static, str>,"));
+}
+
+#[test]
+fn test_longer_strings_are_errors() {
+    let options = StructOptions {
+        char_paths: vec!["name".to_owned(), "separator".to_owned()],
+        ..StructOptions::default()
+    };
+    let source = "name = \"xy\"\nseparator = \",\"";
+
+    match config_struct::generate_config_from_source(Format::Toml, source, &options) {
+        Err(GenerationError::InvalidChar(path, value)) => {
+            assert_eq!(path, "name");
+            assert_eq!(value, "xy");
+        }
+        other => panic!("Expected an invalid char error, found {:?}", other),
+    }
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, StructOptions};
+
+const SOURCE: &str = r#"
+# The display name.
+name = "x"
+
+# The server to connect to.
+[server]
+# The port, which must be open.
+#
+# Defaults to 80.
+port = 80
+"#;
+
+#[test]
+fn test_comments_are_ignored_by_default() {
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, SOURCE, &StructOptions::default())
+            .unwrap();
+
+    assert!(!code.contains("///"));
+}
+
+#[test]
+fn test_comments_become_doc_comments() {
+    let options = StructOptions {
+        preserve_comments: true,
+        ..StructOptions::default()
+    };
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(code.contains("    /// The display name.\n    pub name: Cow<'static, str>,"));
+    assert!(code.contains("/// The server to connect to.\n#[derive(Debug, Clone)]"));
+    assert!(code.contains(
+        "    /// The port, which must be open.\n    ///\n    /// Defaults to 80.\n    pub port: i64,"
+    ));
+}
+
+#[test]
+fn test_comments_follow_renamed_fields() {
+    let options = StructOptions {
+        preserve_comments: true,
+        field_renames: vec![("name".to_owned(), "title".to_owned())]
+            .into_iter()
+            .collect(),
+        ..StructOptions::default()
+    };
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(code.contains("    /// The display name.\n    pub title: Cow<'static, str>,"));
+}
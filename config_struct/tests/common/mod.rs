@@ -0,0 +1,10 @@
+use std::path::PathBuf;
+
+/// An empty directory called `name` in Cargo's temporary directory for
+/// integration tests, cleared of anything an earlier run left there.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,167 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{
+    Computation, ComputedField, DynamicLoading, Format, GenerationError, OptionsError,
+    StructOptions, ValueTransform,
+};
+
+const SOURCE: &str = r#"
+features = ["logging", "metrics", "tracing"]
+ratios = [0.5, 0.25]
+
+[[items]]
+name = "disk"
+cost = 40
+
+[[items]]
+name = "cpu"
+cost = 25
+
+[[items]]
+name = "ram"
+cost = 15
+
+[stats]
+"#;
+
+fn options(fields: Vec<ComputedField>) -> StructOptions {
+    StructOptions {
+        computed_fields: fields,
+        ..StructOptions::default()
+    }
+}
+
+fn generate(source: &str, options: &StructOptions) -> Result<String, GenerationError> {
+    config_struct::generate_config_from_source(Format::Toml, source, options)
+}
+
+#[test]
+fn test_computed_fields() {
+    let options = options(vec![
+        ComputedField::new("total_budget", Computation::Sum("items.*.cost".into())),
+        ComputedField::new("feature_count", Computation::Len("features".into())),
+        ComputedField::new("stats.cheapest", Computation::Min("items.*.cost".into())),
+        ComputedField::new("stats.largest_ratio", Computation::Max("ratios".into())),
+        ComputedField::new(
+            "stats.names",
+            Computation::Concat(vec!["items.*.name".into(), "features".into()], ", ".into()),
+        ),
+    ]);
+
+    let code = generate(SOURCE, &options).unwrap();
+
+    assert!(code.contains("    pub total_budget: i64,\n"), "{}", code);
+    assert!(code.contains("    pub feature_count: usize,\n"), "{}", code);
+    assert!(code.contains("    pub cheapest: i64,\n"), "{}", code);
+    assert!(code.contains("    pub largest_ratio: f64,\n"), "{}", code);
+    assert!(
+        code.contains("    pub names: Cow<'static, str>,\n"),
+        "{}",
+        code
+    );
+
+    assert!(code.contains("    total_budget: 80,\n"), "{}", code);
+    assert!(code.contains("    feature_count: 3,\n"), "{}", code);
+    assert!(code.contains("        cheapest: 15,\n"), "{}", code);
+    assert!(code.contains("        largest_ratio: 0.5,\n"), "{}", code);
+    assert!(
+        code.contains(
+            "        names: Cow::Borrowed(\"disk, cpu, ram, logging, metrics, tracing\"),\n"
+        ),
+        "{}",
+        code
+    );
+}
+
+#[test]
+fn test_computed_fields_read_transformed_values_and_each_other() {
+    let source = "sizes = [\"1KiB\", \"512\"]\n";
+    let options = StructOptions {
+        value_transforms: vec![("sizes".to_owned(), ValueTransform::ParseByteSize)],
+        ..options(vec![
+            ComputedField::new("total_size", Computation::Sum("sizes".into())),
+            ComputedField::new(
+                "summary",
+                Computation::Concat(vec!["total_size".into()], "".into()),
+            ),
+        ])
+    };
+
+    let code = generate(source, &options).unwrap();
+
+    assert!(code.contains("    pub total_size: u64,\n"), "{}", code);
+    assert!(code.contains("    total_size: 1536,\n"), "{}", code);
+    assert!(
+        code.contains("    summary: Cow::Borrowed(\"1536\"),\n"),
+        "{}",
+        code
+    );
+}
+
+#[test]
+fn test_computed_field_errors() {
+    let sum = |path: &str| {
+        options(vec![ComputedField::new(
+            "total",
+            Computation::Sum(path.into()),
+        )])
+    };
+
+    match generate(SOURCE, &sum("items.*.price")) {
+        Err(error @ GenerationError::MissingComputedPath(..)) => {
+            assert_eq!(error.path(), Some("total"));
+            assert_eq!(
+                error.to_string(),
+                "Computed field `total` reads `items.0.price`, which is not in the config."
+            );
+        }
+        other => panic!("Expected a missing path error, found {:?}", other),
+    }
+
+    match generate(SOURCE, &sum("items.*.name")) {
+        Err(error @ GenerationError::InvalidComputedValue(..)) => assert_eq!(
+            error.to_string(),
+            "Computed field `total` needs numbers, but `items.0.name` is not one."
+        ),
+        other => panic!("Expected an invalid value error, found {:?}", other),
+    }
+
+    let largest = options(vec![ComputedField::new(
+        "largest",
+        Computation::Max("empty".into()),
+    )]);
+    match generate("empty = []\n", &largest) {
+        Err(GenerationError::EmptyComputedValue(field, path)) => {
+            assert_eq!((&*field, &*path), ("largest", "empty"))
+        }
+        other => panic!("Expected an empty value error, found {:?}", other),
+    }
+
+    for path in &["features", "features.count", "missing.count"] {
+        let count = options(vec![ComputedField::new(
+            *path,
+            Computation::Len("features".into()),
+        )]);
+        match generate(SOURCE, &count) {
+            Err(GenerationError::InvalidComputedField(field)) => assert_eq!(field, *path),
+            other => panic!("Expected an invalid field error, found {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_computed_fields_need_generated_values() {
+    let options = StructOptions {
+        generate_load_fns: true,
+        dynamic_loading: DynamicLoading::Always,
+        ..options(vec![ComputedField::new(
+            "count",
+            Computation::Len("features".into()),
+        )])
+    };
+
+    match generate(SOURCE, &options) {
+        Err(GenerationError::StructOptions(OptionsError::ComputedFieldsWithDynamicLoading)) => (),
+        other => panic!("Expected an options error, found {:?}", other),
+    }
+}
@@ -0,0 +1,104 @@
+#![cfg(feature = "toml-parsing")]
+
+use std::path::PathBuf;
+
+use config_struct::{DirOptions, Error, GenerationError};
+
+mod common;
+
+use common::temp_dir;
+
+fn config_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = temp_dir(name);
+    for (file_name, source) in files {
+        std::fs::write(dir.join(file_name), source).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn test_one_const_per_file() {
+    let dir = config_dir(
+        "dir_consts",
+        &[
+            ("dev.toml", "url = \"localhost\"\nretries = 1\n"),
+            ("prod.toml", "url = \"example.com\"\nretries = 5\n"),
+            ("README.md", "Not a config."),
+        ],
+    );
+
+    let code = config_struct::generate_config_from_dir(&dir, &DirOptions::default()).unwrap();
+
+    assert_eq!(code.matches("pub struct Config").count(), 1);
+    assert!(code.contains("pub const DEV: Config = Config {"));
+    assert!(code.contains("pub const PROD: Config = Config {"));
+    assert!(code.contains("url: Cow::Borrowed(\"example.com\"),"));
+    assert!(code.contains("pub fn for_env(name: &str) -> Option<&'static Config> {"));
+    assert!(code.contains("\"dev\" => &DEV,"));
+    assert!(code.contains("\"prod\" => &PROD,"));
+}
+
+#[test]
+fn test_declarations_use_every_file() {
+    let dir = config_dir(
+        "dir_template",
+        &[("a.toml", "tags = []\n"), ("b.toml", "tags = [\"x\"]\n")],
+    );
+
+    let options = DirOptions {
+        lookup_fn: "by_region".to_owned(),
+        ..DirOptions::default()
+    };
+    let code = config_struct::generate_config_from_dir(&dir, &options).unwrap();
+
+    assert!(code.contains("pub tags: Cow<'static, [Cow<'static, str>]>,"));
+    assert!(code.contains("pub fn by_region(name: &str)"));
+}
+
+#[test]
+fn test_shape_mismatches_name_the_file_and_key() {
+    let dir = config_dir(
+        "dir_mismatch",
+        &[
+            ("dev.toml", "[database]\nport = 5432\n"),
+            ("prod.toml", "[database]\nport = \"5432\"\n"),
+        ],
+    );
+
+    match config_struct::generate_config_from_dir(&dir, &DirOptions::default()) {
+        Err(Error::Generation(error @ GenerationError::MismatchedDirConfig(..))) => {
+            let message = error.to_string();
+            assert!(message.contains("prod.toml"));
+            assert!(message.contains("database.port"));
+        }
+        other => panic!("Expected a shape mismatch, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_empty_dir_is_an_error() {
+    let dir = config_dir("dir_empty", &[("notes.txt", "")]);
+
+    match config_struct::generate_config_from_dir(&dir, &DirOptions::default()) {
+        Err(Error::Generation(GenerationError::NoInputFiles)) => (),
+        other => panic!("Expected no input files, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_nulls_take_their_type_from_other_files() {
+    let dir = config_dir(
+        "dir_nulls",
+        &[
+            ("dev.json", r#"{ "proxy": null }"#),
+            ("prod.json", r#"{ "proxy": "proxy.example.com" }"#),
+        ],
+    );
+
+    let code = config_struct::generate_config_from_dir(&dir, &DirOptions::default()).unwrap();
+
+    assert!(code.contains("pub proxy: Option<Cow<'static, str>>,"));
+    assert!(code.contains("proxy: None,"));
+    assert!(code.contains("proxy: Some(Cow::Borrowed(\"proxy.example.com\")),"));
+}
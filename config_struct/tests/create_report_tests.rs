@@ -0,0 +1,89 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::StructOptions;
+
+mod common;
+
+use common::temp_dir;
+
+#[test]
+fn test_report_for_written_file() {
+    let dir = temp_dir("report_written");
+    let source = dir.join("config.toml");
+    let destination = dir.join("config.rs");
+    std::fs::write(&source, "number = 100\n").unwrap();
+
+    let options = StructOptions::default();
+    let report = config_struct::create_config_ext(&source, &destination, &options).unwrap();
+
+    assert!(report.written);
+    assert!(report.changed);
+    assert_eq!(report.destination, destination);
+    assert_eq!(report.bytes, report.code.len());
+    assert_eq!(std::fs::read_to_string(&destination).unwrap(), report.code);
+
+    let report = config_struct::create_config_ext(&source, &destination, &options).unwrap();
+
+    assert!(!report.written);
+    assert!(!report.changed);
+}
+
+#[test]
+fn test_dry_run_does_not_write() {
+    let dir = temp_dir("report_dry_run");
+    let source = dir.join("config.toml");
+    let destination = dir.join("generated/config.rs");
+    std::fs::write(&source, "number = 100\n").unwrap();
+
+    let options = StructOptions {
+        dry_run: true,
+        ..StructOptions::default()
+    };
+    let report = config_struct::create_config_ext(&source, &destination, &options).unwrap();
+
+    assert!(!report.written);
+    assert!(report.changed);
+    assert!(report.code.contains("pub number: i64"));
+    assert!(!dir.join("generated").exists());
+}
+
+#[test]
+fn test_dry_run_detects_stale_file() {
+    let dir = temp_dir("report_stale");
+    let source = dir.join("config.toml");
+    let destination = dir.join("config.rs");
+    std::fs::write(&source, "number = 100\n").unwrap();
+
+    config_struct::create_config(&source, &destination, &StructOptions::default()).unwrap();
+    std::fs::write(&source, "number = 200\n").unwrap();
+
+    let options = StructOptions {
+        dry_run: true,
+        ..StructOptions::default()
+    };
+    let report = config_struct::create_config_ext(&source, &destination, &options).unwrap();
+
+    assert!(report.changed);
+    assert!(!report.written);
+    assert!(std::fs::read_to_string(&destination)
+        .unwrap()
+        .contains("number: 100"));
+}
+
+#[test]
+fn test_report_includes_warnings() {
+    let dir = temp_dir("report_warnings");
+    let source = dir.join("config.toml");
+    let destination = dir.join("config.rs");
+    std::fs::write(&source, "number = 100\n").unwrap();
+
+    let options = StructOptions {
+        exclude_paths: vec!["missing".to_owned()],
+        ..StructOptions::default()
+    };
+    let report = config_struct::create_config_ext(&source, &destination, &options).unwrap();
+
+    assert!(report.written);
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(report.warnings[0].path, "missing");
+}
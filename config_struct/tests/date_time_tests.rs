@@ -0,0 +1,35 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, StructOptions};
+
+const SOURCE: &str = r#"
+released = 2023-05-01T10:00:00Z
+birthday = 1985-11-27
+"#;
+
+#[test]
+fn test_date_times_are_strings_by_default() {
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, SOURCE, &StructOptions::default())
+            .unwrap();
+
+    assert!(code.contains("pub released: Cow<'static, str>,"));
+    assert!(code.contains(r#"released: Cow::Borrowed("2023-05-01T10:00:00Z"),"#));
+    assert!(code.contains(r#"birthday: Cow::Borrowed("1985-11-27"),"#));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_chrono_date_times() {
+    use config_struct::DateTimeType;
+
+    let options = StructOptions {
+        date_time_type: DateTimeType::ChronoDateTime,
+        ..StructOptions::default()
+    };
+
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(code.contains("pub released: chrono::DateTime<chrono::Utc>,"));
+    assert!(code.contains("pub birthday: chrono::NaiveDate,"));
+}
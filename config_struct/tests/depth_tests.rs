@@ -0,0 +1,57 @@
+use config_struct::{Format, GenerationError, StructOptions};
+
+fn generate(format: Format, source: &str, max_depth: usize) -> Result<String, GenerationError> {
+    let options = StructOptions {
+        max_depth,
+        ..StructOptions::default()
+    };
+    config_struct::generate_config_from_source(format, source, &options)
+}
+
+fn assert_too_deep(
+    result: Result<String, GenerationError>,
+    expected_path: &str,
+    expected_depth: usize,
+) {
+    match result {
+        Err(GenerationError::MaxDepthExceeded { path, depth }) => {
+            assert_eq!((path.as_str(), depth), (expected_path, expected_depth))
+        }
+        other => panic!("Expected max depth error, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_deep_json_is_an_error() {
+    let source = format!("{{\"a\": {}1{}}}", "[".repeat(2000), "]".repeat(2000));
+    assert_too_deep(generate(Format::Json, &source, 128), "a", 129);
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_json_path_names_nested_keys() {
+    let source = r#"{"a": {"b": {"c": {"d": 1}}}, "e": "x"}"#;
+    assert_too_deep(generate(Format::Json, source, 2), "a.b.c", 3);
+    assert!(generate(Format::Json, source, 3).is_ok());
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_deep_toml_is_an_error() {
+    assert_too_deep(generate(Format::Toml, "[a.b.c]\nx = 1\n", 2), "a.b.c", 3);
+    assert_too_deep(generate(Format::Toml, "a = [[[1]]]\n", 2), "a", 3);
+    assert!(generate(Format::Toml, "[a.b.c]\nx = 1\n", 3).is_ok());
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_deep_yaml_is_an_error() {
+    assert_too_deep(generate(Format::Yaml, "a:\n  b:\n    - [1]\n", 2), "a.b", 3);
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_default_allows_ordinary_configs() {
+    assert!(generate(Format::Toml, "[server]\nports = [[80, 443]]\n", 128).is_ok());
+}
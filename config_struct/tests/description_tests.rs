@@ -0,0 +1,182 @@
+#![cfg(feature = "toml-parsing")]
+
+use std::collections::HashMap;
+
+use config_struct::{
+    FieldDescription, Format, GeneratedModule, KeySanitization, StructOptions, TaggedUnionSpec,
+    TypeKind,
+};
+
+const SOURCE: &str = r#"
+name = "app"
+
+[server]
+max-connections = 100
+timeout_ms = 500
+level = "debug"
+
+[[server.listeners]]
+port = 80
+
+[backend]
+kind = "s3"
+bucket = "logs"
+"#;
+
+fn options() -> StructOptions {
+    let mut options = StructOptions {
+        tagged_unions: vec![TaggedUnionSpec::new("backend", "kind")
+            .variant("s3", "S3")
+            .variant("local", "Local")],
+        key_sanitization: KeySanitization::Sanitize,
+        ..StructOptions::default()
+    };
+    options.enum_fields.insert(
+        "server.level".to_owned(),
+        vec!["debug".to_owned(), "info".to_owned()],
+    );
+    options.newtype_paths = vec![("server.timeout_ms".to_owned(), "Millis".to_owned())]
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+    options
+}
+
+fn describe(source: &str, options: &StructOptions) -> GeneratedModule {
+    config_struct::generate_config_from_source_described(Format::Toml, source, options)
+        .unwrap_or_else(|error| panic!("Failed to generate config: {}", error))
+}
+
+fn field(name: &str, key: &str, type_name: &str) -> FieldDescription {
+    FieldDescription {
+        name: name.to_owned(),
+        key: key.to_owned(),
+        type_name: type_name.to_owned(),
+    }
+}
+
+#[test]
+fn test_described_code_is_unchanged() {
+    let options = options();
+    let module = describe(SOURCE, &options);
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert_eq!(module.code, code);
+}
+
+#[test]
+fn test_struct_descriptions() {
+    let module = describe(SOURCE, &options());
+
+    let types = module
+        .types
+        .iter()
+        .map(|description| {
+            (
+                description.name.as_str(),
+                description.path.as_str(),
+                description.kind,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        vec![
+            ("Config", "", TypeKind::Struct),
+            ("_Config__server", "server", TypeKind::Struct),
+            (
+                "_Config__server__listeners",
+                "server.listeners.*",
+                TypeKind::Struct
+            ),
+            ("Level", "server.level", TypeKind::Enum),
+            ("Backend", "backend", TypeKind::Enum),
+            ("Millis", "server.timeout_ms", TypeKind::Newtype),
+        ]
+    );
+
+    assert_eq!(
+        module.types[0].fields,
+        vec![
+            field("backend", "backend", "Backend"),
+            field("name", "name", "Cow<'static, str>"),
+            field("server", "server", "_Config__server"),
+        ]
+    );
+    assert_eq!(
+        module.types[1].fields,
+        vec![
+            field("level", "level", "Level"),
+            field(
+                "listeners",
+                "listeners",
+                "Cow<'static, [_Config__server__listeners]>"
+            ),
+            field("max_connections", "max-connections", "i64"),
+            field("timeout_ms", "timeout_ms", "Millis"),
+        ]
+    );
+}
+
+#[test]
+fn test_enum_and_newtype_descriptions() {
+    let module = describe(SOURCE, &options());
+
+    let level = &module.types[3];
+    let variants = level
+        .variants
+        .iter()
+        .map(|variant| (variant.name.as_str(), variant.key.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(variants, vec![("Debug", "debug"), ("Info", "info")]);
+    assert!(level.fields.is_empty());
+
+    let backend = &module.types[4];
+    assert_eq!(backend.variants[0].name, "S3");
+    assert_eq!(backend.variants[0].key, "s3");
+    assert_eq!(
+        backend.variants[0].fields,
+        vec![field("bucket", "bucket", "Cow<'static, str>")]
+    );
+    assert_eq!(backend.variants[1].name, "Local");
+    assert!(backend.variants[1].fields.is_empty());
+
+    let millis = &module.types[5];
+    assert_eq!(millis.fields, vec![field("0", "", "i64")]);
+    assert!(millis.variants.is_empty());
+}
+
+#[test]
+fn test_struct_described() {
+    let config =
+        config_struct::parse_config(Format::Toml, "[a]\nb = 1.5\n", &StructOptions::default())
+            .unwrap();
+    let module =
+        config_struct::generate_config_from_struct_described(&config, &StructOptions::default())
+            .unwrap();
+
+    assert_eq!(module.types.len(), 2);
+    assert_eq!(module.types[1].path, "a");
+    assert_eq!(module.types[1].fields, vec![field("b", "b", "f64")]);
+}
+
+#[cfg(all(feature = "serde-descriptions", feature = "json-parsing"))]
+#[test]
+fn test_serialized_descriptions() {
+    let module = describe("[server]\nport = 80\n", &StructOptions::default());
+
+    let json = serde_json::to_value(&module.types[1]).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "name": "_Config__server",
+            "path": "server",
+            "kind": "Struct",
+            "fields": [{ "name": "port", "key": "port", "type_name": "i64" }],
+            "variants": [],
+        })
+    );
+
+    let json = serde_json::to_string(&module).unwrap();
+    let parsed: GeneratedModule = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, module);
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, StructOptions, WarningKind};
+
+#[test]
+fn test_empty_array_warnings() {
+    let source = r#"
+plugins = []
+tags = ["a"]
+
+[[servers]]
+aliases = []
+
+[[servers]]
+aliases = []
+"#;
+    let output = config_struct::generate_config_from_source_with_diagnostics(
+        Format::Toml,
+        source,
+        &StructOptions::default(),
+    )
+    .unwrap();
+
+    let warnings = output
+        .warnings
+        .iter()
+        .map(|warning| (warning.kind, warning.path.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        vec![
+            (WarningKind::EmptyArray, "plugins"),
+            (WarningKind::EmptyArray, "servers.aliases"),
+        ]
+    );
+    assert!(output.warnings[0]
+        .to_string()
+        .contains("`plugins` is an empty array"));
+}
+
+#[test]
+fn test_unused_exclude_warnings() {
+    let options = StructOptions {
+        exclude_paths: vec!["missing".to_owned()],
+        ..StructOptions::default()
+    };
+    let output = config_struct::generate_config_from_source_with_diagnostics(
+        Format::Toml,
+        "name = \"x\"",
+        &options,
+    )
+    .unwrap();
+
+    assert_eq!(output.warnings.len(), 1);
+    assert_eq!(output.warnings[0].kind, WarningKind::UnusedExcludePath);
+    assert_eq!(output.warnings[0].path, "missing");
+}
+
+#[test]
+fn test_file_diagnostics() {
+    let path = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("diagnostics.toml");
+    std::fs::write(&path, "plugins = []\n").unwrap();
+
+    let output =
+        config_struct::generate_config_with_diagnostics(&path, &StructOptions::default()).unwrap();
+
+    assert!(output.code.contains("pub struct Config"));
+    assert_eq!(output.warnings[0].path, "plugins");
+}
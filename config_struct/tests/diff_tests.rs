@@ -0,0 +1,62 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{FieldAccess, Format, GenerationError, OptionsError, StructOptions};
+
+const SOURCE: &str = r#"
+name = "App"
+
+[server]
+port = 8080
+"#;
+
+fn diff_options() -> StructOptions {
+    StructOptions {
+        generate_diff: true,
+        derived_traits: vec![
+            "Debug".to_owned(),
+            "Clone".to_owned(),
+            "PartialEq".to_owned(),
+        ],
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_diff_types() {
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, SOURCE, &diff_options()).unwrap();
+
+    assert!(code.contains("pub struct ConfigDiff {"));
+    assert!(code.contains("pub struct _Config__serverDiff {"));
+    assert!(code.contains("pub fn diff(&self, other: &Config) -> ConfigDiff {"));
+    assert!(code.contains("pub fn apply(&mut self, diff: &ConfigDiff) {"));
+}
+
+#[test]
+fn test_diff_without_traits() {
+    let options = StructOptions {
+        generate_diff: true,
+        ..StructOptions::default()
+    };
+    let result = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options);
+
+    match result {
+        Err(GenerationError::StructOptions(OptionsError::DiffWithoutTraits)) => {}
+        other => panic!("Expected missing traits error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_reserved_diff_getter() {
+    let options = StructOptions {
+        field_access: FieldAccess::Getters,
+        ..diff_options()
+    };
+    let source = "[server]\napply = true\n";
+    let result = config_struct::generate_config_from_source(Format::Toml, source, &options);
+
+    match result {
+        Err(GenerationError::ReservedGetterName(name)) => assert_eq!(name, "apply"),
+        other => panic!("Expected reserved name error, found {:?}", other),
+    }
+}
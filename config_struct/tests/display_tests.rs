@@ -0,0 +1,77 @@
+#![cfg(feature = "toml-parsing")]
+
+use std::path::PathBuf;
+
+use config_struct::{DisplayStyle, Error, Format, GenerationError, OptionsError, StructOptions};
+
+fn generate_file(name: &str, options: &StructOptions) -> Result<String, Error> {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("display");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.toml", name));
+    std::fs::write(&path, "name = \"app\"\n\n[server]\nport = 80\n").unwrap();
+
+    config_struct::generate_config(&path, options)
+}
+
+#[test]
+fn test_pretty_debug_derives_debug() {
+    let options = StructOptions {
+        derived_traits: vec!["Clone".to_owned()],
+        impl_display: DisplayStyle::PrettyDebug,
+        ..StructOptions::default()
+    };
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, "[server]\nport = 80", &options)
+            .unwrap();
+
+    assert!(code.contains("#[derive(Clone, Debug)]"));
+    assert!(code.contains("impl ::core::fmt::Display for Config {"));
+    assert!(code.contains(r#"write!(f, "{:#?}", self)"#));
+    assert!(!code.contains("impl ::core::fmt::Display for _Config__server"));
+}
+
+#[test]
+fn test_source_format_serializes_in_the_format() {
+    let options = StructOptions {
+        impl_display: DisplayStyle::SourceFormat,
+        ..StructOptions::serde_default()
+    };
+    let code = generate_file("source_format", &options).unwrap();
+
+    assert!(code.contains("::toml::to_string_pretty(&value)"));
+    assert!(code.contains("f.write_str(&text)"));
+}
+
+#[test]
+fn test_source_format_needs_a_file() {
+    let options = StructOptions {
+        impl_display: DisplayStyle::SourceFormat,
+        ..StructOptions::serde_default()
+    };
+
+    match config_struct::generate_config_from_source(Format::Toml, "name = \"app\"", &options) {
+        Err(GenerationError::MissingDisplayFormat) => (),
+        other => panic!("Expected missing format error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_source_format_needs_serialize() {
+    let options = StructOptions {
+        impl_display: DisplayStyle::SourceFormat,
+        ..StructOptions::default()
+    };
+
+    match generate_file("without_serde", &options) {
+        Err(Error::Generation(GenerationError::StructOptions(
+            OptionsError::DisplayWithoutSerialize,
+        ))) => (),
+        other => panic!("Expected options error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_no_display_by_default() {
+    let code = generate_file("none", &StructOptions::default()).unwrap();
+    assert!(!code.contains("fmt::Display for Config"));
+}
@@ -0,0 +1,144 @@
+use config_struct::{DuplicateKeyBehavior, Format, GenerationError, StructOptions};
+
+#[allow(dead_code)]
+fn generate(
+    format: Format,
+    source: &str,
+    duplicate_keys: DuplicateKeyBehavior,
+) -> Result<String, GenerationError> {
+    let options = StructOptions {
+        duplicate_keys,
+        ..StructOptions::default()
+    };
+    config_struct::generate_config_from_source(format, source, &options)
+}
+
+#[test]
+#[cfg(feature = "json-parsing")]
+fn test_json_duplicate_is_an_error() {
+    let source =
+        "{\n  \"server\": {\n    \"port\": 80,\n    \"host\": \"a\",\n    \"port\": 8080\n  }\n}\n";
+
+    match generate(Format::Json, source, DuplicateKeyBehavior::Error) {
+        Err(GenerationError::DuplicateKeyAt(path, 3, 5)) => assert_eq!(path, "server.port"),
+        other => panic!("Expected a duplicate key error, found {:?}", other),
+    }
+}
+
+#[test]
+#[cfg(feature = "json-parsing")]
+fn test_json_first_and_last_wins() {
+    let source = r#"{"port": 80, "host": "a", "port": 8080, "flags": {"x": 1, "x": 2}}"#;
+
+    let code = generate(Format::Json, source, DuplicateKeyBehavior::FirstWins).unwrap();
+    assert!(code.contains("port: 80,"));
+    assert!(code.contains("x: 1,"));
+    assert!(code.contains(r#"host: Cow::Borrowed("a"),"#));
+
+    let code = generate(Format::Json, source, DuplicateKeyBehavior::LastWins).unwrap();
+    assert!(code.contains("port: 8080,"));
+    assert!(code.contains("x: 2,"));
+}
+
+#[test]
+#[cfg(feature = "json-parsing")]
+fn test_json_last_duplicate_is_removed() {
+    let source = r#"{"items": [{"id": 1, "id": 2}], "id": 3, "id": 4}"#;
+
+    let code = generate(Format::Json, source, DuplicateKeyBehavior::FirstWins).unwrap();
+    assert!(code.contains("id: 1,"));
+    assert!(code.contains("id: 3,"));
+}
+
+#[test]
+#[cfg(feature = "json5-parsing")]
+fn test_json5_duplicates() {
+    match generate(Format::Json5, "{a: 1, a: 2}", DuplicateKeyBehavior::Error) {
+        Err(GenerationError::DuplicateKey(path)) => assert_eq!(path, "a"),
+        other => panic!("Expected a duplicate key error, found {:?}", other),
+    }
+
+    let source = "{port: 80, items: [{id: 1, id: 2}], port: 8080}";
+    let code = generate(Format::Json5, source, DuplicateKeyBehavior::FirstWins).unwrap();
+    assert!(code.contains("port: 80,"));
+    assert!(code.contains("id: 1,"));
+
+    let code = generate(Format::Json5, source, DuplicateKeyBehavior::LastWins).unwrap();
+    assert!(code.contains("port: 8080,"));
+    assert!(code.contains("id: 2,"));
+}
+
+#[test]
+#[cfg(feature = "yaml-parsing")]
+fn test_yaml_duplicate_is_an_error() {
+    let source = "server:\n  port: 80\n  host: a\n  port: 8080\n";
+
+    match generate(Format::Yaml, source, DuplicateKeyBehavior::Error) {
+        Err(GenerationError::DuplicateKeyAt(path, 2, 4)) => assert_eq!(path, "server.port"),
+        other => panic!("Expected a duplicate key error, found {:?}", other),
+    }
+}
+
+#[test]
+#[cfg(feature = "yaml-parsing")]
+fn test_yaml_first_and_last_wins() {
+    let source = "\
+server:
+  port: 80
+  limits:
+    max: 1
+  port: 8080
+  limits:
+    max: 2
+    min: 0
+name: a
+";
+
+    let code = generate(Format::Yaml, source, DuplicateKeyBehavior::FirstWins).unwrap();
+    assert!(code.contains("port: 80,"));
+    assert!(code.contains("max: 1,"));
+    assert!(!code.contains("min"));
+    assert!(code.contains(r#"name: Cow::Borrowed("a"),"#));
+
+    let code = generate(Format::Yaml, source, DuplicateKeyBehavior::LastWins).unwrap();
+    assert!(code.contains("port: 8080,"));
+    assert!(code.contains("min: 0,"));
+}
+
+#[test]
+#[cfg(feature = "yaml-parsing")]
+fn test_yaml_flow_mappings() {
+    let source = "a: {x: 1, y: 2, x: 3}\nb: {x: 1, x: 2, y: 3}\n";
+
+    let code = generate(Format::Yaml, source, DuplicateKeyBehavior::FirstWins).unwrap();
+    assert!(!code.contains("x: 3"));
+    assert!(!code.contains("x: 2"));
+
+    match generate(Format::Yaml, source, DuplicateKeyBehavior::Error) {
+        Err(GenerationError::DuplicateKeyAt(path, 1, 1)) => assert_eq!(path, "a.x"),
+        other => panic!("Expected a duplicate key error, found {:?}", other),
+    }
+}
+
+#[test]
+#[cfg(feature = "toml-parsing")]
+fn test_toml_duplicates_are_always_errors() {
+    let source = "[server]\nport = 80\nport = 8080\n";
+
+    match generate(Format::Toml, source, DuplicateKeyBehavior::LastWins) {
+        Err(GenerationError::DuplicateKey(path)) => assert_eq!(path, "server.port"),
+        other => panic!("Expected a duplicate key error, found {:?}", other),
+    }
+}
+
+#[test]
+#[cfg(feature = "ini-parsing")]
+fn test_ini_first_and_last_wins() {
+    let source = "[server]\nport = 80\nport = 8080\n";
+
+    let code = generate(Format::Ini, source, DuplicateKeyBehavior::FirstWins).unwrap();
+    assert!(code.contains("port: 80,"));
+
+    let code = generate(Format::Ini, source, DuplicateKeyBehavior::LastWins).unwrap();
+    assert!(code.contains("port: 8080,"));
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "toml-parsing")]
+
+use std::path::PathBuf;
+
+use config_struct::{
+    DynamicLoading, DynamicPath, EnvOptions, Error, GenerationError, OptionsError, StructOptions,
+};
+
+fn generate(name: &str, options: &StructOptions) -> Result<String, Error> {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("dynamic_path");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.toml", name));
+    std::fs::write(&path, "name = \"app\"\n").unwrap();
+
+    config_struct::generate_config(&path, options)
+}
+
+fn options_with_path(dynamic_path: DynamicPath) -> StructOptions {
+    StructOptions {
+        dynamic_loading: DynamicLoading::Always,
+        dynamic_path,
+        ..StructOptions::serde_default()
+    }
+}
+
+#[test]
+fn test_fixed_path() {
+    let options = options_with_path(DynamicPath::Fixed("/etc/app/config.toml".to_owned()));
+    let code = generate("fixed", &options).unwrap();
+
+    assert!(code.contains(r#"let filepath = "/etc/app/config.toml";"#));
+    assert!(code.contains(r#""the fixed path""#));
+    assert!(!code.contains("CARGO_MANIFEST_DIR"));
+}
+
+#[test]
+fn test_env_var_path_falls_back_to_const() {
+    let options = options_with_path(DynamicPath::EnvVar("APP_CONFIG".to_owned()));
+    let code = generate("env_var", &options).unwrap();
+
+    assert!(code.contains(r#"::std::env::var_os("APP_CONFIG")"#));
+    assert!(code.contains("return Cow::Borrowed(&CONFIG);"));
+    assert!(code.contains("pub const CONFIG: Config"));
+    assert!(code.contains(r#""the `APP_CONFIG` environment variable""#));
+}
+
+#[test]
+fn test_argument_path_only_loads_from() {
+    let options = options_with_path(DynamicPath::Argument);
+    let code = generate("argument", &options).unwrap();
+
+    assert!(code.contains("pub fn load_from<P: AsRef<::std::path::Path>>(filepath: P)"));
+    assert!(code.contains("pub fn try_load_from<P: AsRef<::std::path::Path>>(filepath: P)"));
+    assert!(!code.contains("pub fn load()"));
+    assert!(!code.contains("pub fn try_load()"));
+}
+
+#[test]
+fn test_embedded_rejects_dynamic_path() {
+    let options = StructOptions {
+        dynamic_loading: DynamicLoading::Embedded,
+        ..options_with_path(DynamicPath::Fixed("config.toml".to_owned()))
+    };
+
+    match generate("embedded", &options) {
+        Err(Error::Generation(GenerationError::StructOptions(
+            OptionsError::EmbeddedWithDynamicPath,
+        ))) => (),
+        other => panic!("Expected options error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_argument_path_rejects_env_overrides() {
+    let options = StructOptions {
+        env_overrides: Some(EnvOptions::default()),
+        ..options_with_path(DynamicPath::Argument)
+    };
+
+    match generate("overrides", &options) {
+        Err(Error::Generation(GenerationError::StructOptions(
+            OptionsError::ArgumentPathWithEnvOverrides,
+        ))) => (),
+        other => panic!("Expected options error, found {:?}", other),
+    }
+}
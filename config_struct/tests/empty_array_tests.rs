@@ -0,0 +1,86 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{EmptyArrayBehavior, Format, GenerationError, StructOptions};
+
+const SOURCE: &str = r#"
+plugins = []
+ports = []
+
+[[servers]]
+aliases = []
+"#;
+
+fn generate(options: &StructOptions) -> Result<config_struct::GenerationOutput, GenerationError> {
+    config_struct::generate_config_from_source_with_diagnostics(Format::Toml, SOURCE, options)
+}
+
+#[test]
+fn test_annotated_types() {
+    let options = StructOptions {
+        empty_array_types: vec![
+            ("plugins".to_owned(), "Cow<'static, str>".to_owned()),
+            ("servers.aliases".to_owned(), "u32".to_owned()),
+        ]
+        .into_iter()
+        .collect(),
+        ..StructOptions::default()
+    };
+    let output = generate(&options).unwrap();
+
+    assert!(output
+        .code
+        .contains("pub plugins: Cow<'static, [Cow<'static, str>]>,"));
+    assert!(output.code.contains("pub aliases: Cow<'static, [u32]>,"));
+    assert!(output.code.contains("pub ports: Cow<'static, [()]>,"));
+    assert!(output.code.contains("plugins: Cow::Borrowed(&[]),"));
+
+    let paths = output
+        .warnings
+        .iter()
+        .map(|warning| warning.path.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(paths, vec!["ports"]);
+}
+
+#[test]
+fn test_default_type() {
+    let options = StructOptions {
+        empty_array_types: vec![("plugins".to_owned(), "Cow<'static, str>".to_owned())]
+            .into_iter()
+            .collect(),
+        default_empty_array_type: Some("i64".to_owned()),
+        ..StructOptions::default()
+    };
+    let output = generate(&options).unwrap();
+
+    assert!(output
+        .code
+        .contains("pub plugins: Cow<'static, [Cow<'static, str>]>,"));
+    assert!(output.code.contains("pub ports: Cow<'static, [i64]>,"));
+    assert!(output.warnings.is_empty());
+}
+
+#[test]
+fn test_untyped_arrays_can_be_errors() {
+    let options = StructOptions {
+        untyped_empty_arrays: EmptyArrayBehavior::Error,
+        ..StructOptions::default()
+    };
+
+    match generate(&options) {
+        Err(GenerationError::UntypedEmptyArray(path)) => assert_eq!(path, "plugins"),
+        other => panic!("Unexpected result: {:?}", other.map(|output| output.code)),
+    }
+}
+
+#[test]
+fn test_getters_return_slices() {
+    let options = StructOptions {
+        field_access: config_struct::FieldAccess::Getters,
+        default_empty_array_type: Some("u8".to_owned()),
+        ..StructOptions::default()
+    };
+    let output = generate(&options).unwrap();
+
+    assert!(output.code.contains("pub fn plugins(&self) -> &[u8] {"));
+}
@@ -0,0 +1,132 @@
+use config_struct::{EmptyRootStyle, Format, StructOptions};
+
+fn generate(format: Format, source: &str, options: &StructOptions) -> String {
+    config_struct::generate_config_from_source(format, source, options)
+        .unwrap_or_else(|error| panic!("Failed to generate {:?} config: {}", format, error))
+}
+
+// The formats are pushed one by one, since each has its own feature.
+#[allow(clippy::vec_init_then_push)]
+fn formats() -> Vec<(Format, &'static str, &'static str)> {
+    // Each format, with a comment-only file and nested empty tables.
+    #[allow(unused_mut)]
+    let mut formats = Vec::new();
+
+    #[cfg(feature = "json-parsing")]
+    formats.push((
+        Format::Json,
+        "\n",
+        r#"{ "telemetry": {}, "server": { "name": "app", "tls": {} } }"#,
+    ));
+    #[cfg(feature = "ron-parsing")]
+    formats.push((
+        Format::Ron,
+        "// Nothing yet.\n",
+        r#"(telemetry: {}, server: (name: "app", tls: {}))"#,
+    ));
+    #[cfg(feature = "toml-parsing")]
+    formats.push((
+        Format::Toml,
+        "# Nothing yet.\n",
+        "[telemetry]\n\n[server]\nname = \"app\"\n\n[server.tls]\n",
+    ));
+    #[cfg(feature = "yaml-parsing")]
+    formats.push((
+        Format::Yaml,
+        "# Nothing yet.\n",
+        "telemetry: {}\nserver:\n  name: app\n  tls: {}\n",
+    ));
+
+    formats
+}
+
+#[test]
+fn test_empty_root_is_unit() {
+    let options = StructOptions::default();
+
+    for (format, comments, _) in formats() {
+        for source in &["", comments] {
+            let code = generate(format, source, &options);
+            assert!(code.contains("pub struct Config;\n"), "{}", code);
+            assert!(!code.contains("use std::borrow::Cow;"), "{}", code);
+            assert!(
+                code.contains("pub const CONFIG: Config = Config;\n"),
+                "{}",
+                code
+            );
+        }
+    }
+}
+
+#[cfg(feature = "ron-parsing")]
+#[test]
+fn test_empty_ron_struct_root() {
+    let code = generate(Format::Ron, "()", &StructOptions::default());
+    assert!(code.contains("pub struct Config;\n"), "{}", code);
+}
+
+#[test]
+fn test_empty_root_braced() {
+    let options = StructOptions {
+        empty_root: EmptyRootStyle::Braced,
+        ..StructOptions::default()
+    };
+
+    for (format, comments, _) in formats() {
+        let code = generate(format, comments, &options);
+        assert!(code.contains("pub struct Config {}\n"), "{}", code);
+        assert!(
+            code.contains("pub const CONFIG: Config = Config {};\n"),
+            "{}",
+            code
+        );
+    }
+}
+
+#[test]
+fn test_empty_root_braced_with_serde() {
+    // Derived serde impls read and write a map, which a unit struct isn't.
+    let options = StructOptions {
+        generate_load_fns: false,
+        ..StructOptions::serde_default()
+    };
+
+    for (format, comments, _) in formats() {
+        let code = generate(format, comments, &options);
+        assert!(code.contains("pub struct Config {}\n"), "{}", code);
+        assert!(
+            code.contains("pub const CONFIG: Config = Config {};\n"),
+            "{}",
+            code
+        );
+    }
+}
+
+#[test]
+fn test_nested_empty_tables() {
+    let options = StructOptions::default();
+
+    for (format, _, tables) in formats() {
+        let code = generate(format, tables, &options);
+        assert!(
+            code.contains("pub struct _Config__telemetry {}\n"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains("pub struct _Config__server__tls {}\n"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains("    telemetry: _Config__telemetry {},\n"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains("        tls: _Config__server__tls {},\n"),
+            "{}",
+            code
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::path::PathBuf;
+
+use config_struct::{Error, Format, GenerationError, IntSize, Span, StructOptions};
+use failure::Fail;
+
+fn generate(format: Format, source: &str, options: &StructOptions) -> GenerationError {
+    config_struct::generate_config_from_source(format, source, options).unwrap_err()
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_json_parse_error_location() {
+    let source = "{\n  \"name\": \"app\",\n  \"port\": 80 \"host\"\n}";
+    let error = generate(Format::Json, source, &StructOptions::default());
+
+    match error {
+        GenerationError::ParseFailed(ref parse_error) => {
+            assert_eq!(parse_error.line, Some(3));
+            assert_eq!(parse_error.column, Some(14));
+            assert_eq!(
+                parse_error.source_line.as_deref(),
+                Some("  \"port\": 80 \"host\"")
+            );
+            assert!(parse_error.file.is_none());
+        }
+        ref other => panic!("Expected parse error, found {:?}", other),
+    }
+
+    assert!(error.cause().and_then(Fail::cause).is_some());
+    assert!(error.path().is_none());
+
+    let diagnostic = error.to_diagnostic_string();
+    assert!(diagnostic.starts_with("Parsing failed: "));
+    assert!(diagnostic.contains("\n  --> <config>:3:14\n"));
+    assert!(diagnostic.contains("\n 3 |   \"port\": 80 \"host\"\n"));
+    assert!(diagnostic.ends_with("\n   |              ^"));
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_toml_parse_error_location() {
+    let error = generate(Format::Toml, "a = 1\nb = \n", &StructOptions::default());
+
+    match error {
+        GenerationError::ParseFailed(ref parse_error) => {
+            assert_eq!(parse_error.line, Some(2));
+            assert_eq!(parse_error.source_line.as_deref(), Some("b = "));
+        }
+        ref other => panic!("Expected parse error, found {:?}", other),
+    }
+    assert!(error.cause().and_then(Fail::cause).is_some());
+}
+
+#[cfg(feature = "ini-parsing")]
+#[test]
+fn test_ini_parse_error_location() {
+    let error = generate(Format::Ini, "a = 1\n[section\n", &StructOptions::default());
+
+    match error {
+        GenerationError::ParseFailed(ref parse_error) => {
+            assert_eq!(parse_error.line, Some(2));
+            assert_eq!(parse_error.column, None);
+            assert!(parse_error.message.contains("line 2"));
+        }
+        ref other => panic!("Expected parse error, found {:?}", other),
+    }
+    // INI errors are found by this crate, so have no underlying cause.
+    assert!(error.cause().and_then(Fail::cause).is_none());
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_field_path() {
+    let options = StructOptions {
+        default_int_size: IntSize::U8,
+        ..StructOptions::default()
+    };
+    let error = generate(Format::Toml, "[server]\nport = 8080\n", &options);
+
+    assert_eq!(error.path(), Some("server.port"));
+    assert_eq!(
+        error.to_diagnostic_string(),
+        format!("{}\n  --> field `server.port`", error)
+    );
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_parse_error_file() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("parse_error_file");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    std::fs::write(&path, "name = \"app\"\nport = \n").unwrap();
+
+    match config_struct::generate_config(&path, &StructOptions::default()) {
+        Err(Error::Generation(error)) => {
+            match error {
+                GenerationError::ParseFailed(ref parse_error) => {
+                    assert_eq!(parse_error.file.as_deref(), Some(path.as_path()))
+                }
+                ref other => panic!("Expected parse error, found {:?}", other),
+            }
+            let location = format!("\n  --> {}:2:", path.display());
+            assert!(error.to_diagnostic_string().contains(&location));
+        }
+        other => panic!("Expected parse error, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_field_errors_are_located_in_the_file() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("located_error_file");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    std::fs::write(&path, "name = \"app\"\n\n[server]\n    port = 8080\n").unwrap();
+    let options = StructOptions {
+        default_int_size: IntSize::U8,
+        ..StructOptions::default()
+    };
+
+    match config_struct::generate_config(&path, &options) {
+        Err(Error::Generation(error)) => {
+            let location = error.location().expect("error should be located");
+            assert_eq!(location.span, Span { line: 4, column: 5 });
+            assert_eq!(location.source_line, "    port = 8080");
+            assert_eq!(location.to_string(), format!("{}:4:5", path.display()));
+
+            match *error.unlocated() {
+                GenerationError::IntegerOutOfRange(ref field, ..) => {
+                    assert_eq!(field, "server.port")
+                }
+                ref other => panic!("Expected integer error, found {:?}", other),
+            }
+            assert_eq!(error.path(), Some("server.port"));
+            assert_eq!(
+                error.to_diagnostic_string(),
+                format!(
+                    "{}\n  --> {}:4:5\n   |\n 4 |     port = 8080\n   |     ^",
+                    error,
+                    path.display()
+                )
+            );
+        }
+        other => panic!("Expected located error, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_inline_table_keys_are_located() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("located_inline_error_file");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    std::fs::write(&path, "server = { port = 80, retries = 300 }\n").unwrap();
+    let options = StructOptions {
+        default_int_size: IntSize::U8,
+        ..StructOptions::default()
+    };
+
+    match config_struct::generate_config(&path, &options) {
+        Err(Error::Generation(error)) => {
+            assert_eq!(error.path(), Some("server.retries"));
+            assert_eq!(
+                error.location().map(|location| location.span),
+                Some(Span {
+                    line: 1,
+                    column: 23
+                })
+            );
+        }
+        other => panic!("Expected located error, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_parsed_fields_have_spans() {
+    let config = config_struct::parse_config(
+        Format::Toml,
+        "name = \"app\"\n[server]\nport = 80\n",
+        &StructOptions::default(),
+    )
+    .unwrap();
+
+    assert_eq!(config.field_spans["name"], Span { line: 1, column: 1 });
+    match config.fields["server"] {
+        config_struct::GenericValue::Struct(ref server) => {
+            assert_eq!(server.field_spans["port"], Span { line: 3, column: 1 })
+        }
+        ref other => panic!("Expected struct, found {:?}", other),
+    }
+}
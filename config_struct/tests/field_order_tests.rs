@@ -0,0 +1,132 @@
+use config_struct::{FieldOrder, Format, StructOptions};
+
+fn source_order() -> StructOptions {
+    StructOptions {
+        field_order: FieldOrder::SourceOrder,
+        ..StructOptions::default()
+    }
+}
+
+/// The position of each needle in the code, which should be increasing.
+fn positions(code: &str, needles: &[&str]) -> Vec<usize> {
+    needles
+        .iter()
+        .map(|needle| {
+            code.find(needle)
+                .unwrap_or_else(|| panic!("`{}` not found in:\n{}", needle, code))
+        })
+        .collect()
+}
+
+fn assert_in_order(code: &str, needles: &[&str]) {
+    let positions = positions(code, needles);
+    let mut sorted = positions.clone();
+    sorted.sort();
+    assert_eq!(
+        positions, sorted,
+        "Expected {:?} in order in:\n{}",
+        needles, code
+    );
+}
+
+#[test]
+#[cfg(feature = "toml-parsing")]
+fn test_toml_source_order() {
+    let source = r#"
+zeta = 1
+alpha = "a"
+
+[server]
+port = 80
+host = "localhost"
+
+[[items]]
+name = "x"
+count = 1
+
+[[items]]
+weight = 2.0
+name = "y"
+count = 2
+"#;
+
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, source, &source_order()).unwrap();
+
+    assert_in_order(
+        &code,
+        &["pub zeta: i64", "pub alpha:", "pub server:", "pub items:"],
+    );
+    assert_in_order(&code, &["pub port: i64", "pub host:"]);
+    assert_in_order(
+        &code,
+        &["pub name:", "pub count: i64", "pub weight: Option<f64>"],
+    );
+    assert_in_order(
+        &code,
+        &["    zeta: 1,", "    alpha: ", "    server: ", "    items: "],
+    );
+    assert_in_order(&code, &["port: 80,", "host: Cow::Borrowed(\"localhost\")"]);
+}
+
+#[test]
+#[cfg(feature = "json-parsing")]
+fn test_json_source_order() {
+    let source = r#"{ "b": 1, "c": { "z": true, "y": false }, "a": 2 }"#;
+
+    let code =
+        config_struct::generate_config_from_source(Format::Json, source, &source_order()).unwrap();
+
+    assert_in_order(&code, &["pub b: i64", "pub c:", "pub a: i64"]);
+    assert_in_order(&code, &["pub z: bool", "pub y: bool"]);
+    assert_in_order(&code, &["    b: 1,", "    c: ", "    a: 2,"]);
+}
+
+#[test]
+#[cfg(feature = "yaml-parsing")]
+fn test_yaml_source_order() {
+    let source = "
+defaults: &defaults
+  timeout: 5
+  retries: 3
+service:
+  name: api
+  <<: *defaults
+";
+
+    let code =
+        config_struct::generate_config_from_source(Format::Yaml, source, &source_order()).unwrap();
+
+    assert_in_order(&code, &["pub defaults:", "pub service:"]);
+    let service = &code[code.find("pub struct _Config__service").unwrap()..];
+    assert_in_order(
+        service,
+        &["pub name:", "pub timeout: i64", "pub retries: i64"],
+    );
+}
+
+#[test]
+#[cfg(feature = "ron-parsing")]
+fn test_ron_source_order() {
+    let source = "(second: 2, first: 1)";
+
+    let code =
+        config_struct::generate_config_from_source(Format::Ron, source, &source_order()).unwrap();
+
+    assert_in_order(&code, &["pub second: i64", "pub first: i64"]);
+}
+
+#[test]
+#[cfg(feature = "json-parsing")]
+fn test_alphabetical_order() {
+    let source = r#"{ "b": 1, "a": 2 }"#;
+    let options = StructOptions {
+        field_order: FieldOrder::Alphabetical,
+        ..StructOptions::default()
+    };
+
+    let code = config_struct::generate_config_from_source(Format::Json, source, &options).unwrap();
+
+    assert_in_order(&code, &["pub a: i64", "pub b: i64"]);
+    assert_in_order(&code, &["    a: 2,", "    b: 1,"]);
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, GenerationError, OptionsError, StructOptions};
+
+const SOURCE: &str = r#"
+name = "x"
+secret = "hunter2"
+
+[server]
+host = "a"
+port = 80
+
+[ui]
+theme = "dark"
+scale = 1.5
+"#;
+
+#[test]
+fn test_excluded_keys_are_not_generated() {
+    let options = StructOptions {
+        exclude_paths: vec!["secret".to_owned(), "ui.*".to_owned()],
+        ..StructOptions::default()
+    };
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(!code.contains("secret"));
+    assert!(!code.contains("hunter2"));
+    assert!(!code.contains("theme"));
+    assert!(code.contains("pub port: i64,"));
+}
+
+#[test]
+fn test_only_included_keys_are_generated() {
+    let options = StructOptions {
+        include_paths: vec!["name".to_owned(), "server.port".to_owned()],
+        ..StructOptions::default()
+    };
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(code.contains("pub name: Cow<'static, str>,"));
+    assert!(code.contains("pub port: i64,"));
+    assert!(!code.contains("host"));
+    assert!(!code.contains("secret"));
+    assert!(!code.contains("pub ui:"));
+}
+
+#[test]
+fn test_conflicting_filters_are_an_error() {
+    let options = StructOptions {
+        include_paths: vec!["server.port".to_owned()],
+        exclude_paths: vec!["server".to_owned()],
+        ..StructOptions::default()
+    };
+    let result = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options);
+
+    match result {
+        Err(GenerationError::StructOptions(OptionsError::ConflictingPathFilters(path))) => {
+            assert_eq!(path, "server.port")
+        }
+        other => panic!("Expected options error, found {:?}", other),
+    }
+}
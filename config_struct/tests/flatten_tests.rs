@@ -0,0 +1,73 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{
+    DynamicLoading, FlattenStyle, Format, GenerationError, OptionsError, StructOptions,
+};
+
+const SOURCE: &str = "name = \"app\"\n\n[limits]\nmax = 10\nmin = 1\n";
+
+fn generate(options: &StructOptions) -> Result<String, GenerationError> {
+    config_struct::generate_config_from_source(Format::Toml, SOURCE, options)
+}
+
+fn flatten_options() -> StructOptions {
+    StructOptions {
+        flatten_paths: vec!["limits".to_owned()],
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_fields_use_the_joiner() {
+    let options = StructOptions {
+        flatten_joiner: "__".to_owned(),
+        ..flatten_options()
+    };
+    let code = generate(&options).unwrap();
+
+    assert!(code.contains("pub limits__max: i64,"));
+    assert!(code.contains("limits__min: 1,"));
+    assert!(!code.contains("_Config__limits"));
+}
+
+#[test]
+fn test_serde_flatten_keeps_the_struct() {
+    let options = StructOptions {
+        flatten_paths: vec!["limits".to_owned()],
+        flatten_style: FlattenStyle::SerdeFlatten,
+        dynamic_loading: DynamicLoading::Never,
+        ..StructOptions::serde_default()
+    };
+    let code = generate(&options).unwrap();
+
+    assert!(code.contains("    #[serde(flatten)]\n    pub limits: _Config__limits,"));
+}
+
+#[test]
+fn test_option_errors() {
+    let cases = vec![
+        (
+            StructOptions {
+                flatten_joiner: "-".to_owned(),
+                ..flatten_options()
+            },
+            OptionsError::InvalidFlattenJoiner("-".to_owned()),
+        ),
+        (
+            StructOptions {
+                flatten_style: FlattenStyle::SerdeFlatten,
+                ..flatten_options()
+            },
+            OptionsError::SerdeFlattenWithoutSerde,
+        ),
+    ];
+
+    for (options, expected) in cases {
+        match generate(&options) {
+            Err(GenerationError::StructOptions(error)) => {
+                assert_eq!(error.to_string(), expected.to_string())
+            }
+            other => panic!("Expected {:?}, found {:?}", expected, other),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use config_struct::{FloatSize, Format, StructOptions};
+
+fn generate(format: Format, source: &str) -> String {
+    config_struct::generate_config_from_source(format, source, &StructOptions::default()).unwrap()
+}
+
+fn assert_round_trip_values(code: &str) {
+    assert!(code.contains("    tiny: 1e-7,\n"));
+    assert!(code.contains("    negative_zero: -0.0,\n"));
+    assert!(code.contains("    huge: 1e300,\n"));
+    assert!(code.contains("    subnormal: 5e-324,\n"));
+    assert!(code.contains("    tenth: 0.1,\n"));
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_json_floats() {
+    let code = generate(
+        Format::Json,
+        r#"{ "tiny": 1e-7, "negative_zero": -0.0, "huge": 1e300, "subnormal": 5e-324, "tenth": 0.1 }"#,
+    );
+
+    assert_round_trip_values(&code);
+}
+
+#[cfg(feature = "ron-parsing")]
+#[test]
+fn test_ron_floats() {
+    let code = generate(
+        Format::Ron,
+        "(tiny: 1e-7, negative_zero: -0.0, huge: 1e300, subnormal: 5e-324, tenth: 0.1)",
+    );
+
+    assert_round_trip_values(&code);
+    assert!(code.contains("pub huge: f64,"));
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_toml_floats() {
+    let code = generate(
+        Format::Toml,
+        "
+tiny = 1e-7
+negative_zero = -0.0
+huge = 1e300
+subnormal = 5e-324
+tenth = 0.1
+not_a_number = nan
+positive_infinity = inf
+negative_infinity = -inf
+",
+    );
+
+    assert_round_trip_values(&code);
+    assert!(code.contains("    not_a_number: f64::NAN,\n"));
+    assert!(code.contains("    positive_infinity: f64::INFINITY,\n"));
+    assert!(code.contains("    negative_infinity: f64::NEG_INFINITY,\n"));
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_yaml_floats() {
+    let code = generate(
+        Format::Yaml,
+        "
+tiny: 1e-7
+negative_zero: -0.0
+huge: 1e300
+subnormal: 5e-324
+tenth: 0.1
+not_a_number: .nan
+positive_infinity: .inf
+negative_infinity: -.inf
+",
+    );
+
+    assert_round_trip_values(&code);
+    assert!(code.contains("    not_a_number: f64::NAN,\n"));
+    assert!(code.contains("    positive_infinity: f64::INFINITY,\n"));
+    assert!(code.contains("    negative_infinity: f64::NEG_INFINITY,\n"));
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_f32_floats() {
+    let options = StructOptions {
+        default_float_size: FloatSize::F32,
+        ..StructOptions::default()
+    };
+    let code = config_struct::generate_config_from_source(
+        Format::Yaml,
+        "tenth: 0.1\ntiny: 1e-40\npositive_infinity: .inf\n",
+        &options,
+    )
+    .unwrap();
+
+    assert!(code.contains("    tenth: 0.1,\n"));
+    assert!(code.contains("    tiny: 1e-40,\n"));
+    assert!(code.contains("    positive_infinity: f32::INFINITY,\n"));
+}
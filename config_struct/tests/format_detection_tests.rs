@@ -0,0 +1,33 @@
+#![cfg(all(feature = "json-parsing", feature = "yaml-parsing"))]
+
+use config_struct::{GenerationError, StructOptions};
+
+#[test]
+fn test_detected_formats() {
+    let json = config_struct::generate_config_auto(
+        r#"{ "name": "json", "list": [1, 2] }"#,
+        &StructOptions::default(),
+    )
+    .unwrap();
+    assert!(json.contains(r#"name: Cow::Borrowed("json"),"#));
+
+    let yaml = config_struct::generate_config_auto(
+        "# comment\nname: yaml\nlist:\n  - 1\n",
+        &StructOptions::default(),
+    )
+    .unwrap();
+    assert!(yaml.contains(r#"name: Cow::Borrowed("yaml"),"#));
+}
+
+#[test]
+fn test_undetected_format() {
+    let result = config_struct::generate_config_auto("{ not valid", &StructOptions::default());
+
+    match result {
+        Err(GenerationError::UndetectedFormat(reason)) => {
+            assert!(reason.contains("json: "));
+            assert!(reason.contains("yaml: "));
+        }
+        other => panic!("Expected undetected format error, found {:?}", other),
+    }
+}
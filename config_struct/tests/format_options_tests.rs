@@ -0,0 +1,124 @@
+use config_struct::{DocumentSelector, Format, GenerationError, StructOptions};
+
+#[cfg(feature = "yaml-parsing")]
+const DOCUMENTS: &str = "\
+name: base
+server:
+  port: 80
+---
+server:
+  host: localhost
+---
+# The last document.
+server:
+  port: 8080
+";
+
+#[cfg(feature = "yaml-parsing")]
+fn generate_document(source: &str, document: DocumentSelector) -> Result<String, GenerationError> {
+    let mut options = StructOptions::default();
+    options.format_options.yaml.document = document;
+    config_struct::generate_config_from_source(Format::Yaml, source, &options)
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_first_yaml_document_by_default() {
+    let code = generate_document(DOCUMENTS, DocumentSelector::First).unwrap();
+
+    assert!(code.contains("name: Cow::Borrowed(\"base\"),"));
+    assert!(code.contains("port: 80,"));
+    assert!(!code.contains("host"));
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_yaml_document_by_index() {
+    let code = generate_document(DOCUMENTS, DocumentSelector::Index(2)).unwrap();
+
+    assert!(code.contains("port: 8080,"));
+    assert!(!code.contains("name"));
+
+    match generate_document(DOCUMENTS, DocumentSelector::Index(3)) {
+        Err(GenerationError::MissingYamlDocument(3, 3)) => (),
+        other => panic!("Expected a missing document, found {:?}", other),
+    }
+    match generate_document("name: only\n", DocumentSelector::Index(1)) {
+        Err(GenerationError::MissingYamlDocument(1, 1)) => (),
+        other => panic!("Expected a missing document, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_yaml_documents_merged_in_order() {
+    let code = generate_document(DOCUMENTS, DocumentSelector::MergeAll).unwrap();
+
+    assert!(code.contains("name: Cow::Borrowed(\"base\"),"));
+    assert!(code.contains("host: Cow::Borrowed(\"localhost\"),"));
+    assert!(code.contains("port: 8080,"));
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_yaml_merge_conflicts_name_the_document() {
+    let source = "server:\n  port: 80\n---\nname: x\n---\nserver:\n  port: [80]\n";
+
+    match generate_document(source, DocumentSelector::MergeAll) {
+        Err(error @ GenerationError::YamlDocumentConflict(..)) => {
+            assert_eq!(error.path(), Some("server.port"));
+            assert!(error.to_string().contains("YAML document 2"));
+        }
+        other => panic!("Expected a merge conflict, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_yaml_errors_keep_their_lines() {
+    let source = "name: base\n---\nport: 80\nport: 81\n";
+
+    match generate_document(source, DocumentSelector::Index(1)) {
+        Err(GenerationError::DuplicateKeyAt(key, first, second)) => {
+            assert_eq!((key.as_str(), first, second), ("port", 3, 4))
+        }
+        other => panic!("Expected a duplicate key, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "json-parsing")]
+fn generate_json(source: &str, allow_non_finite: bool, allow_trailing_characters: bool) -> String {
+    let mut options = StructOptions::default();
+    options.format_options.json.allow_non_finite = allow_non_finite;
+    options.format_options.json.allow_trailing_characters = allow_trailing_characters;
+    config_struct::generate_config_from_source(Format::Json, source, &options).unwrap()
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_json_non_finite_numbers() {
+    let source = r#"{ "low": -Infinity, "high": Infinity, "none": NaN, "text": "NaN" }"#;
+
+    let code = generate_json(source, true, false);
+
+    assert!(code.contains("low: f64::NEG_INFINITY,"));
+    assert!(code.contains("high: f64::INFINITY,"));
+    assert!(code.contains("none: f64::NAN,"));
+    assert!(code.contains("text: Cow::Borrowed(\"NaN\"),"));
+
+    let options = StructOptions::default();
+    assert!(config_struct::generate_config_from_source(Format::Json, source, &options).is_err());
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_json_trailing_characters() {
+    let source = "{ \"port\": 80 }\n{ \"port\": 81 }\n";
+
+    let code = generate_json(source, false, true);
+
+    assert!(code.contains("port: 80,"));
+
+    let options = StructOptions::default();
+    assert!(config_struct::generate_config_from_source(Format::Json, source, &options).is_err());
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{FieldAccess, Format, GenerationError, StructOptions};
+
+fn getters() -> StructOptions {
+    StructOptions {
+        field_access: FieldAccess::Getters,
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_fields_are_private() {
+    let source = r#"
+name = "Example"
+ports = [80, 443, 8080]
+tags = []
+
+[server]
+host = "localhost"
+"#;
+
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, source, &getters()).unwrap();
+
+    assert!(code.contains("    name: Cow<'static, str>,"));
+    assert!(code.contains("    host: Cow<'static, str>,"));
+    assert!(!code.contains("pub name:"));
+
+    assert!(code.contains("pub fn name(&self) -> &str {"));
+    assert!(code.contains("pub fn ports(&self) -> &[i64] {"));
+    assert!(code.contains("pub fn tags(&self) -> &[()] {"));
+    assert!(code.contains("pub fn server(&self) -> &_Config__server {"));
+    assert!(code.contains("pub fn host(&self) -> &str {"));
+}
+
+#[test]
+fn test_public_fields_have_no_getters() {
+    let code = config_struct::generate_config_from_source(
+        Format::Toml,
+        "name = \"Example\"",
+        &StructOptions::default(),
+    )
+    .unwrap();
+
+    assert!(code.contains("pub name: Cow<'static, str>,"));
+    assert!(!code.contains("pub fn name("));
+}
+
+#[test]
+fn test_getter_conflicting_with_load_fn() {
+    let options = StructOptions {
+        generate_load_fns: true,
+        ..getters()
+    };
+    let result = config_struct::generate_config_from_source(Format::Toml, "load = true", &options);
+
+    match result {
+        Err(GenerationError::ReservedGetterName(name)) => assert_eq!(name, "load"),
+        other => panic!("Expected reserved name error, found {:?}", other),
+    }
+}
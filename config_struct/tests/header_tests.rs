@@ -0,0 +1,62 @@
+use std::path::PathBuf;
+
+use config_struct::{Format, HeaderStyle, StructOptions};
+
+fn generate(options: &StructOptions) -> String {
+    config_struct::generate_config_from_source(Format::Toml, "name = \"app\"\n", options).unwrap()
+}
+
+#[test]
+fn test_inner_attributes() {
+    let code = generate(&StructOptions::default());
+
+    assert!(code.starts_with("#![cfg_attr(rustfmt, rustfmt_skip)]\n#![allow(dead_code)]\n\n"));
+}
+
+#[test]
+fn test_outer_comment() {
+    let code = generate(&StructOptions {
+        header: HeaderStyle::OuterComment,
+        ..StructOptions::default()
+    });
+
+    assert!(code.starts_with("// Generated by config_struct.\n\nuse std::borrow::Cow;\n"));
+    assert!(!code.contains("#!["));
+}
+
+#[test]
+fn test_custom_header() {
+    let code = generate(&StructOptions {
+        header: HeaderStyle::Custom("#![allow(clippy::all)]".to_owned()),
+        ..StructOptions::default()
+    });
+
+    assert!(code.starts_with("#![allow(clippy::all)]\n\nuse std::borrow::Cow;\n"));
+}
+
+#[test]
+fn test_generated_by_comment() {
+    let options = StructOptions {
+        generated_by_comment: true,
+        ..StructOptions::default()
+    };
+    let stamp = format!(
+        "// Generated by config_struct {}",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let code = generate(&options);
+    assert!(code.starts_with(&format!("{}.\n\n#![cfg_attr", stamp)));
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("generated_by_comment");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("settings.toml");
+    std::fs::write(&path, "name = \"app\"\n").unwrap();
+
+    let options = StructOptions {
+        header: HeaderStyle::OuterComment,
+        ..options
+    };
+    let code = config_struct::generate_config(&path, &options).unwrap();
+    assert!(code.starts_with(&format!("{} from `settings.toml`.\n\nuse ", stamp)));
+}
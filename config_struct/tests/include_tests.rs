@@ -0,0 +1,127 @@
+#![cfg(all(feature = "toml-parsing", feature = "json-parsing"))]
+
+use std::path::{Path, PathBuf};
+
+use config_struct::{Error, GenerationError, IncludeOptions, StructOptions};
+
+mod common;
+
+fn include_dir(name: &str) -> PathBuf {
+    let dir = common::temp_dir(name);
+    std::fs::create_dir_all(dir.join("parts")).unwrap();
+    dir
+}
+
+fn include_options() -> StructOptions {
+    StructOptions {
+        resolve_includes: Some(IncludeOptions::default()),
+        ..StructOptions::default()
+    }
+}
+
+fn generate(path: &Path, options: &StructOptions) -> Result<String, Error> {
+    config_struct::generate_config(path, options)
+}
+
+#[test]
+fn test_includes_are_replaced() {
+    let dir = include_dir("include_replaced");
+    std::fs::write(
+        dir.join("config.toml"),
+        "name = \"app\"\ndatabase = { include = \"parts/db.toml\" }\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("parts/db.toml"),
+        "url = \"postgres://\"\npool = { include = \"pool.json\" }\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("parts/pool.json"), r#"{ "size": 4 }"#).unwrap();
+
+    let code = generate(&dir.join("config.toml"), &include_options()).unwrap();
+
+    assert!(code.contains("pub database: _Config__database,"));
+    assert!(code.contains("pub pool: _Config__database__pool,"));
+    assert!(code.contains("url: Cow::Borrowed(\"postgres://\"),"));
+    assert!(code.contains("size: 4,"));
+}
+
+#[test]
+fn test_includes_are_ignored_by_default() {
+    let dir = include_dir("include_ignored");
+    std::fs::write(
+        dir.join("config.toml"),
+        "database = { include = \"parts/db.toml\" }\n",
+    )
+    .unwrap();
+
+    let code = generate(&dir.join("config.toml"), &StructOptions::default()).unwrap();
+
+    assert!(code.contains("include: Cow::Borrowed(\"parts/db.toml\"),"));
+}
+
+#[test]
+fn test_include_key() {
+    let dir = include_dir("include_key");
+    std::fs::write(
+        dir.join("config.toml"),
+        "servers = { import = \"parts/servers.json\" }\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("parts/servers.json"),
+        r#"[{ "host": "a" }, { "host": "b" }]"#,
+    )
+    .unwrap();
+
+    let options = StructOptions {
+        resolve_includes: Some(IncludeOptions {
+            key: "import".to_owned(),
+        }),
+        ..StructOptions::default()
+    };
+    let code = generate(&dir.join("config.toml"), &options).unwrap();
+
+    assert!(code.contains("pub servers: Cow<'static, [_Config__servers]>,"));
+    assert!(code.contains("host: Cow::Borrowed(\"b\"),"));
+}
+
+#[test]
+fn test_include_cycles() {
+    let dir = include_dir("include_cycle");
+    std::fs::write(
+        dir.join("config.toml"),
+        "a = { include = \"parts/a.toml\" }\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("parts/a.toml"), "b = { include = \"b.toml\" }\n").unwrap();
+    std::fs::write(dir.join("parts/b.toml"), "a = { include = \"a.toml\" }\n").unwrap();
+
+    match generate(&dir.join("config.toml"), &include_options()) {
+        Err(Error::Generation(GenerationError::IncludeCycle(chain))) => {
+            let files = chain
+                .split(" -> ")
+                .map(|path| Path::new(path).file_name().unwrap().to_str().unwrap())
+                .collect::<Vec<&str>>();
+            assert_eq!(files, vec!["a.toml", "b.toml", "a.toml"]);
+        }
+        other => panic!("Expected include cycle, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_missing_includes() {
+    let dir = include_dir("include_missing");
+    std::fs::write(
+        dir.join("config.toml"),
+        "a = { include = \"parts/missing.toml\" }\n",
+    )
+    .unwrap();
+
+    match generate(&dir.join("config.toml"), &include_options()) {
+        Err(Error::Generation(GenerationError::IncludeFailed(path, _))) => {
+            assert!(path.ends_with("missing.toml"))
+        }
+        other => panic!("Expected include error, found {:?}", other),
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "json-parsing")]
+
+use config_struct::{Format, GenerationError, IndexGapBehavior, StructOptions};
+
+fn generate(source: &str, options: &StructOptions) -> Result<String, GenerationError> {
+    config_struct::generate_config_from_source(Format::Json, source, options)
+}
+
+fn index_map_options() -> StructOptions {
+    StructOptions {
+        index_map_paths: vec!["levels".to_owned()],
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_index_map_becomes_array() {
+    let source = r#"{
+        "levels": {
+            "2": { "name": "second", "boss": true },
+            "1": { "name": "first" }
+        }
+    }"#;
+    let code = generate(source, &index_map_options()).unwrap();
+
+    assert!(code.contains("pub levels: Cow<'static, [_Config__levels]>,"));
+    assert!(code.contains("pub boss: Option<bool>,"));
+    let first = code.find(r#"name: Cow::Borrowed("first")"#).unwrap();
+    let second = code.find(r#"name: Cow::Borrowed("second")"#).unwrap();
+    assert!(first < second);
+}
+
+#[test]
+fn test_index_map_gaps() {
+    let source = r#"{ "levels": { "0": 10, "1": 20, "3": 40 } }"#;
+
+    match generate(source, &index_map_options()) {
+        Err(GenerationError::MissingIndex(path, 2)) => assert_eq!(path, "levels"),
+        other => panic!("Expected a missing index error, found {:?}", other),
+    }
+
+    let options = StructOptions {
+        index_map_gaps: IndexGapBehavior::Compact,
+        ..index_map_options()
+    };
+    let code = generate(source, &options).unwrap();
+    assert!(code.contains("levels: Cow::Borrowed(&[10, 20, 40]),"));
+}
+
+#[test]
+fn test_index_map_keys_must_be_indices() {
+    let source = r#"{ "levels": { "1": 10, "two": 20 } }"#;
+
+    match generate(source, &index_map_options()) {
+        Err(GenerationError::InvalidIndexKey(path, key)) => {
+            assert_eq!(path, "levels");
+            assert_eq!(key, "two");
+        }
+        other => panic!("Expected an invalid index error, found {:?}", other),
+    }
+
+    match generate(r#"{ "levels": 3 }"#, &index_map_options()) {
+        Err(GenerationError::InvalidIndexMapPath(path)) => assert_eq!(path, "levels"),
+        other => panic!("Expected an invalid path error, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_yaml_index_maps_with_integer_keys() {
+    let source = "levels:\n  2: {x: 2}\n  1: {x: 1}\n";
+    let code =
+        config_struct::generate_config_from_source(Format::Yaml, source, &index_map_options())
+            .unwrap();
+
+    assert!(code.contains("pub levels: Cow<'static, [_Config__levels]>,"));
+    let first = code.find("x: 1,").unwrap();
+    let second = code.find("x: 2,").unwrap();
+    assert!(first < second);
+
+    let source = "levels:\n  1: 10\n  1.5: 15\n";
+    match config_struct::generate_config_from_source(Format::Yaml, source, &index_map_options()) {
+        Err(GenerationError::InvalidIndexKey(path, key)) => {
+            assert_eq!(path, "levels");
+            assert_eq!(key, "1.5");
+        }
+        other => panic!("Expected an invalid index error, found {:?}", other),
+    }
+}
@@ -0,0 +1,70 @@
+#![cfg(feature = "ini-parsing")]
+
+use config_struct::{DuplicateKeyBehavior, DynamicLoading, Format, GenerationError, StructOptions};
+
+const SOURCE: &str = "
+name = Service
+version = 3
+
+[server]
+host = localhost
+port = 8080
+timeout: 2.5
+
+[features]
+enabled = true
+";
+
+#[test]
+fn test_ini_generation() {
+    let code =
+        config_struct::generate_config_from_source(Format::Ini, SOURCE, &StructOptions::default())
+            .unwrap();
+
+    assert!(code.contains("pub name: Cow<'static, str>,"));
+    assert!(code.contains("pub version: i64,"));
+    assert!(code.contains("pub server: _Config__server,"));
+    assert!(code.contains("pub port: i64,"));
+    assert!(code.contains("pub timeout: f64,"));
+    assert!(code.contains("pub enabled: bool,"));
+    assert!(code.contains(r#"host: Cow::Borrowed("localhost"),"#));
+}
+
+#[test]
+fn test_duplicate_keys_as_arrays() {
+    let source = "[mirrors]\nurl = a.example.com\nurl = b.example.com\n";
+    let options = StructOptions {
+        duplicate_keys: DuplicateKeyBehavior::Array,
+        ..StructOptions::default()
+    };
+
+    let code = config_struct::generate_config_from_source(Format::Ini, source, &options).unwrap();
+
+    assert!(code.contains("pub url: Cow<'static, [Cow<'static, str>]>,"));
+}
+
+#[test]
+fn test_dynamic_loading_is_unsupported() {
+    let options = StructOptions {
+        dynamic_loading: DynamicLoading::Always,
+        ..StructOptions::serde_default()
+    };
+    let dir = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("ini_loading");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.ini");
+    std::fs::write(&path, SOURCE).unwrap();
+
+    match config_struct::generate_config(&path, &options) {
+        Err(config_struct::Error::Generation(GenerationError::UnsupportedDynamicLoading(
+            format,
+        ))) => assert_eq!(format, "ini"),
+        other => panic!("Expected unsupported dynamic loading, found {:?}", other),
+    }
+
+    let options = StructOptions {
+        dynamic_loading: DynamicLoading::Never,
+        ..StructOptions::serde_default()
+    };
+    let code = config_struct::generate_config(&path, &options).unwrap();
+    assert!(code.contains("pub fn load() -> Cow<'static, Self>"));
+}
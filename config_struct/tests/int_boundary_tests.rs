@@ -0,0 +1,203 @@
+use config_struct::{Format, GenerationError, IntSize, StructOptions};
+
+fn generate(format: Format, source: &str, int_size: IntSize) -> Result<String, GenerationError> {
+    let options = StructOptions {
+        default_int_size: int_size,
+        ..StructOptions::default()
+    };
+    config_struct::generate_config_from_source(format, source, &options)
+}
+
+fn assert_i64_limits(code: &str) {
+    assert!(code.contains("pub max: i64,"));
+    assert!(code.contains("    max: 9223372036854775807,\n"));
+    assert!(code.contains("pub min: i64,"));
+    assert!(code.contains("    min: -9223372036854775808,\n"));
+}
+
+fn assert_u64_only(result: Result<String, GenerationError>) {
+    match result {
+        Err(GenerationError::IntegerOutOfRange(path, value, int_type)) => {
+            assert_eq!(path, "id");
+            assert_eq!(value, "18446744073709551615");
+            assert_eq!(int_type, "i64");
+        }
+        other => panic!("Expected range error, found {:?}", other),
+    }
+}
+
+fn assert_too_large(result: Result<String, GenerationError>, value: &str) {
+    match result {
+        Err(GenerationError::IntegerTooLarge(path, found)) => {
+            assert_eq!(path, "id");
+            assert_eq!(found, value);
+        }
+        other => panic!("Expected size error, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_json_boundaries() {
+    let limits = r#"{ "max": 9223372036854775807, "min": -9223372036854775808 }"#;
+    assert_i64_limits(&generate(Format::Json, limits, IntSize::I64).unwrap());
+
+    let u64_max = r#"{ "id": 18446744073709551615 }"#;
+    assert_u64_only(generate(Format::Json, u64_max, IntSize::I64));
+    let code = generate(Format::Json, u64_max, IntSize::Auto).unwrap();
+    assert!(code.contains("pub id: u64,"));
+    assert!(code.contains("    id: 18446744073709551615,\n"));
+
+    let i128_max = r#"{ "id": 170141183460469231731687303715884105727 }"#;
+    let code = generate(Format::Json, i128_max, IntSize::Auto).unwrap();
+    assert!(code.contains("pub id: i128,"));
+    assert!(code.contains("    id: 170141183460469231731687303715884105727,\n"));
+    let code = generate(Format::Json, i128_max, IntSize::U128).unwrap();
+    assert!(code.contains("pub id: u128,"));
+
+    let u128_max = r#"{ "id": 340282366920938463463374607431768211455 }"#;
+    let code = generate(Format::Json, u128_max, IntSize::Auto).unwrap();
+    assert!(code.contains("pub id: u128,"));
+    assert!(code.contains("    id: 340282366920938463463374607431768211455,\n"));
+
+    let too_large = r#"{ "id": 340282366920938463463374607431768211456 }"#;
+    assert_too_large(
+        generate(Format::Json, too_large, IntSize::Auto),
+        "340282366920938463463374607431768211456",
+    );
+    let too_small = r#"{ "id": -170141183460469231731687303715884105729 }"#;
+    assert_too_large(
+        generate(Format::Json, too_small, IntSize::Auto),
+        "-170141183460469231731687303715884105729",
+    );
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_auto_arrays() {
+    let code = generate(
+        Format::Json,
+        r#"{ "ids": [1, 18446744073709551615] }"#,
+        IntSize::Auto,
+    )
+    .unwrap();
+    assert!(code.contains("pub ids: Cow<'static, [u64]>,"));
+
+    let code = generate(Format::Json, r#"{ "ids": [-1, 200] }"#, IntSize::Auto).unwrap();
+    assert!(code.contains("pub ids: Cow<'static, [i16]>,"));
+
+    let mixed = r#"{ "ids": [-1, 18446744073709551615] }"#;
+    match generate(Format::Json, mixed, IntSize::Auto) {
+        Err(GenerationError::MixedSignIntegers(path, min, max)) => {
+            assert_eq!(path, "ids");
+            assert_eq!(min, "-1");
+            assert_eq!(max, "18446744073709551615");
+        }
+        other => panic!("Expected mixed sign error, found {:?}", other),
+    }
+
+    let mixed = r#"{ "ids": [-1, 340282366920938463463374607431768211455] }"#;
+    assert!(generate(Format::Json, mixed, IntSize::Auto).is_err());
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_yaml_boundaries() {
+    let limits = "max: 9223372036854775807\nmin: -9223372036854775808\n";
+    assert_i64_limits(&generate(Format::Yaml, limits, IntSize::I64).unwrap());
+
+    let u64_max = "id: 18446744073709551615\n";
+    assert_u64_only(generate(Format::Yaml, u64_max, IntSize::I64));
+    let code = generate(Format::Yaml, u64_max, IntSize::Auto).unwrap();
+    assert!(code.contains("pub id: u64,"));
+
+    let large = "id: 18446744073709551616\nids: [-170141183460469231731687303715884105728]\n";
+    let code = generate(Format::Yaml, large, IntSize::Auto).unwrap();
+    assert!(code.contains("pub id: i128,"));
+    assert!(code.contains("    id: 18446744073709551616,\n"));
+    assert!(code.contains("-170141183460469231731687303715884105728"));
+
+    let merged =
+        "base: &base\n  id: 340282366920938463463374607431768211455\nother:\n  <<: *base\n";
+    let code = generate(Format::Yaml, merged, IntSize::Auto).unwrap();
+    assert_eq!(
+        code.matches("id: 340282366920938463463374607431768211455,")
+            .count(),
+        2
+    );
+
+    assert_too_large(
+        generate(
+            Format::Yaml,
+            "id: 340282366920938463463374607431768211456\n",
+            IntSize::Auto,
+        ),
+        "340282366920938463463374607431768211456",
+    );
+}
+
+#[cfg(feature = "ini-parsing")]
+#[test]
+fn test_ini_boundaries() {
+    let limits = "max = 9223372036854775807\nmin = -9223372036854775808\n";
+    assert_i64_limits(&generate(Format::Ini, limits, IntSize::I64).unwrap());
+
+    let u64_max = "id = 18446744073709551615\n";
+    assert_u64_only(generate(Format::Ini, u64_max, IntSize::I64));
+    let code = generate(Format::Ini, u64_max, IntSize::Auto).unwrap();
+    assert!(code.contains("pub id: u64,"));
+
+    let code = generate(Format::Ini, "id = 18446744073709551616\n", IntSize::Auto).unwrap();
+    assert!(code.contains("pub id: i128,"));
+
+    assert_too_large(
+        generate(
+            Format::Ini,
+            "id = 340282366920938463463374607431768211456\n",
+            IntSize::Auto,
+        ),
+        "340282366920938463463374607431768211456",
+    );
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_toml_boundaries() {
+    // TOML integers are always 64-bit signed, so larger values don't parse.
+    let limits = "max = 9223372036854775807\nmin = -9223372036854775808\n";
+    assert_i64_limits(&generate(Format::Toml, limits, IntSize::I64).unwrap());
+
+    match generate(Format::Toml, "id = 9223372036854775808\n", IntSize::Auto) {
+        Err(GenerationError::ParseFailed(_)) => (),
+        other => panic!("Expected parse error, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "ron-parsing")]
+#[test]
+fn test_ron_boundaries() {
+    // RON numbers are parsed as `f64`, so only integers up to 2^53 are
+    // exact, and those beyond the `i64` range stay floats.
+    let code = generate(
+        Format::Ron,
+        "(exact: 9007199254740992, large: 18446744073709551615)",
+        IntSize::I64,
+    )
+    .unwrap();
+    assert!(code.contains("pub exact: i64,"));
+    assert!(code.contains("    exact: 9007199254740992,\n"));
+    assert!(code.contains("pub large: f64,"));
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_narrow_default_size() {
+    match generate(Format::Toml, "id = 300", IntSize::I8) {
+        Err(GenerationError::IntegerOutOfRange(path, value, int_type)) => {
+            assert_eq!(path, "id");
+            assert_eq!(value, "300");
+            assert_eq!(int_type, "i8");
+        }
+        other => panic!("Expected range error, found {:?}", other),
+    }
+}
@@ -0,0 +1,144 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, GenerationError, StructOptions};
+
+fn generate(source: &str, options: &StructOptions) -> Result<String, GenerationError> {
+    config_struct::generate_config_from_source(Format::Toml, source, options)
+}
+
+fn options_deriving(name: &str) -> StructOptions {
+    StructOptions {
+        derived_traits: vec!["Debug".to_owned(), "Clone".to_owned(), name.to_owned()],
+        string_type: config_struct::StringType::String,
+        generate_const: false,
+        ..StructOptions::default()
+    }
+}
+
+#[cfg(feature = "clap-support")]
+mod clap_support {
+    use super::*;
+
+    use config_struct::{OptionsError, StringType};
+
+    const SOURCE: &str = r#"
+verbose = false
+logLevel = "debug"
+ports = [80, 443]
+
+[server]
+host = "localhost"
+port = 8080
+"#;
+
+    #[test]
+    fn test_clap_attributes() {
+        let mut options = options_deriving("clap::Parser");
+        options.enum_fields.insert(
+            "logLevel".to_owned(),
+            vec!["debug".to_owned(), "info".to_owned()],
+        );
+
+        let code = generate(SOURCE, &options).unwrap();
+
+        assert!(code.contains("#[derive(Debug, Clone, clap::Parser)]\npub struct Config {"));
+        assert!(code.contains("#[derive(Debug, Clone, clap::Args)]\n#[allow(non_camel_case_types)]\npub struct _Config__server {"));
+        assert!(code.contains("    #[command(flatten)]\n    pub server: _Config__server,"));
+        assert!(code.contains(
+            "    #[arg(id = \"server-port\", long = \"server-port\", default_value = \"8080\")]\n    pub port: i64,"
+        ));
+        assert!(code.contains(
+            "    #[arg(id = \"verbose\", long = \"verbose\", action = clap::ArgAction::Set, default_value = \"false\")]\n"
+        ));
+        assert!(code.contains(
+            "    #[arg(id = \"ports\", long = \"ports\", value_delimiter = ',', default_values = [\"80\", \"443\"])]\n    pub ports: Vec<i64>,"
+        ));
+        assert!(code.contains(
+            "    #[arg(id = \"log-level\", long = \"log-level\", default_value = \"debug\")]\n"
+        ));
+        assert!(code.contains(
+            "clap::ValueEnum)]\npub enum LogLevel {\n    #[value(name = \"debug\")]\n    Debug,"
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_fields_are_listed() {
+        let source = r#"
+tags = ["a"]
+
+[env]
+HOME = "/root"
+
+[[servers]]
+port = 80
+
+[client]
+tags = ["c"]
+"#;
+        let mut options = options_deriving("clap::Parser");
+        options.string_type = StringType::Cow;
+        options.map_paths = vec!["env".to_owned()];
+
+        match generate(source, &options) {
+            Err(GenerationError::UnsupportedCliFields(paths)) => {
+                assert_eq!(paths, ["client.tags", "env", "servers", "tags"]);
+            }
+            other => panic!("Expected unsupported fields, found {:?}", other),
+        }
+
+        options.string_type = StringType::String;
+        options.exclude_paths = vec!["servers".to_owned(), "env".to_owned()];
+        assert!(generate(source, &options).is_ok());
+    }
+
+    #[test]
+    fn test_flag_collisions_are_listed() {
+        let source = "server_port = 1\n\n[server]\nport = 2\n";
+
+        match generate(source, &options_deriving("clap::Parser")) {
+            Err(GenerationError::UnsupportedCliFields(paths)) => {
+                assert_eq!(paths, ["server_port"]);
+            }
+            other => panic!("Expected unsupported fields, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_static_str_is_invalid() {
+        let options = StructOptions {
+            string_type: StringType::StaticStr,
+            ..options_deriving("clap::Parser")
+        };
+
+        match generate("name = \"app\"", &options) {
+            Err(GenerationError::StructOptions(OptionsError::ClapWithStaticStr)) => (),
+            other => panic!("Expected an options error, found {:?}", other),
+        }
+    }
+}
+
+#[cfg(feature = "schemars-support")]
+#[test]
+fn test_schemars_validations() {
+    use config_struct::Rule;
+
+    let source = "name = \"app\"\nports = [80, 443]\nratio = 0.5\n\n[[servers]]\nport = 80\n";
+    let mut options = options_deriving("schemars::JsonSchema");
+    options.validations = vec![
+        ("name".to_owned(), Rule::NonEmptyString),
+        ("ports".to_owned(), Rule::IntRange(1, 65535)),
+        ("ports".to_owned(), Rule::NonEmptyArray),
+        ("ratio".to_owned(), Rule::FloatRange(0.0, 1.0)),
+        ("servers.*.port".to_owned(), Rule::IntRange(1, 65535)),
+    ];
+
+    let code = generate(source, &options).unwrap();
+
+    assert!(code.contains("#[derive(Debug, Clone, schemars::JsonSchema)]"));
+    assert!(code.contains("    #[schemars(length(min = 1))]\n    pub name: String,"));
+    assert!(code.contains(
+        "    #[schemars(inner(range(min = 1, max = 65535)), length(min = 1))]\n    pub ports: Vec<i64>,"
+    ));
+    assert!(code.contains("    #[schemars(range(min = 0.0, max = 1.0))]\n    pub ratio: f64,"));
+    assert!(code.contains("    #[schemars(range(min = 1, max = 65535))]\n    pub port: i64,"));
+}
@@ -0,0 +1,37 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, GenerationError, OptionsError, StructOptions};
+
+const SOURCE: &str = r#"
+name = "App"
+port = 8080
+"#;
+
+#[test]
+fn test_into_impl() {
+    let options = StructOptions {
+        impl_into: Some("crate::settings::AppSettings".to_owned()),
+        ..StructOptions::default()
+    };
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(code.contains("impl From<Config> for crate::settings::AppSettings {"));
+    assert!(code.contains("name: value.name.into(),"));
+    assert!(code.contains("port: value.port.into(),"));
+}
+
+#[test]
+fn test_invalid_into_target() {
+    let options = StructOptions {
+        impl_into: Some("crate::AppSettings<T>".to_owned()),
+        ..StructOptions::default()
+    };
+    let result = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options);
+
+    match result {
+        Err(GenerationError::StructOptions(OptionsError::InvalidIntoTarget(target))) => {
+            assert_eq!(target, "crate::AppSettings<T>")
+        }
+        other => panic!("Expected invalid target error, found {:?}", other),
+    }
+}
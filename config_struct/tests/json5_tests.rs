@@ -0,0 +1,85 @@
+#![cfg(feature = "json5-parsing")]
+
+use config_struct::{Format, GenerationError, StructOptions};
+
+fn generate(source: &str) -> Result<String, GenerationError> {
+    config_struct::generate_config_from_source(Format::Json5, source, &StructOptions::default())
+}
+
+#[test]
+fn test_comments_and_trailing_commas() {
+    let code = generate(
+        r#"// Line comment
+        {
+            /* Block comment */
+            "name": "app",
+            "ports": [80, 443,],
+        }"#,
+    )
+    .unwrap();
+
+    assert!(code.contains("pub name: Cow<'static, str>,"));
+    assert!(code.contains("pub ports: Cow<'static, [i64]>,"));
+    assert!(code.contains("    ports: Cow::Borrowed(&[80, 443]),\n"));
+}
+
+#[test]
+fn test_json5_syntax() {
+    let code = generate("{ name: 'app', hex: 0x10, ratio: .5 }").unwrap();
+
+    assert!(code.contains("    name: Cow::Borrowed(\"app\"),\n"));
+    assert!(code.contains("    hex: 16,\n"));
+    assert!(code.contains("    ratio: 0.5,\n"));
+}
+
+#[test]
+fn test_parse_error_location() {
+    match generate("{\n  name: 'app',\n  port: ]\n}") {
+        Err(GenerationError::ParseFailed(error)) => {
+            assert_eq!(error.line, Some(3));
+            assert_eq!(error.source_line.as_deref(), Some("  port: ]"));
+        }
+        other => panic!("Expected parse error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_load_fns() {
+    let options = StructOptions::serde_default();
+    let dir = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("json5_load_fns");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.jsonc");
+    std::fs::write(&path, "{ \"name\": \"app\", }").unwrap();
+
+    let code = config_struct::generate_config(&path, &options).unwrap();
+    assert!(code.contains("::json5::from_str(&file_contents)"));
+}
+
+#[test]
+fn test_non_finite_numbers() {
+    let code = generate("{ high: Infinity, low: -Infinity, unknown: NaN }").unwrap();
+
+    assert!(code.contains("pub high: f64,"), "{}", code);
+    assert!(code.contains("    high: f64::INFINITY,\n"), "{}", code);
+    assert!(code.contains("    low: f64::NEG_INFINITY,\n"), "{}", code);
+    assert!(code.contains("    unknown: f64::NAN,\n"), "{}", code);
+}
+
+#[test]
+fn test_large_integers_are_floats() {
+    let code = generate(
+        "{\n  big: 9223372036854775808,\n  small: -1,\n  bigger: -99999999999999999999,\n}",
+    )
+    .unwrap();
+
+    assert!(code.contains("pub big: f64,"), "{}", code);
+    assert!(code.contains("pub small: i64,"), "{}", code);
+    assert!(code.contains("pub bigger: f64,"), "{}", code);
+
+    let code = generate("{ big: +18446744073709551616 }").unwrap();
+    assert!(
+        code.contains("    big: 1.8446744073709552e19,\n"),
+        "{}",
+        code
+    );
+}
@@ -0,0 +1,147 @@
+#![cfg(all(feature = "toml-parsing", feature = "json-parsing"))]
+
+use config_struct::{Format, IntSize, KeySanitization, MissingFields, SerdeSupport, StructOptions};
+use serde_json::{json, Value};
+
+const SOURCE: &str = r#"
+# The name of the app.
+name = "app"
+level = "info"
+ports = [80, 443]
+ratio = 0.5
+
+[server]
+host = "localhost"
+max-connections = 30
+
+[[plugins]]
+name = "fmt"
+
+[[plugins]]
+name = "lint"
+args = ["--strict"]
+"#;
+
+/// Options which allow the `max-connections` key.
+fn sanitizing_options() -> StructOptions {
+    StructOptions {
+        key_sanitization: KeySanitization::Sanitize,
+        ..StructOptions::default()
+    }
+}
+
+fn schema(source: &str, options: &StructOptions) -> Value {
+    let schema = config_struct::generate_schema(Format::Toml, source, options).unwrap();
+    serde_json::from_str(&schema).unwrap()
+}
+
+#[test]
+fn test_schema() {
+    let options = StructOptions {
+        enum_fields: vec![(
+            "level".to_owned(),
+            vec!["debug".to_owned(), "info".to_owned(), "warn".to_owned()],
+        )]
+        .into_iter()
+        .collect(),
+        field_int_types: vec![("server.max-connections".to_owned(), IntSize::U16)]
+            .into_iter()
+            .collect(),
+        preserve_comments: true,
+        ..sanitizing_options()
+    };
+    let schema = schema(SOURCE, &options);
+
+    assert_eq!(
+        schema["$schema"],
+        "https://json-schema.org/draft/2020-12/schema"
+    );
+    assert_eq!(schema["title"], "Config");
+    assert_eq!(schema["type"], "object");
+    assert_eq!(
+        schema["required"],
+        json!(["level", "name", "plugins", "ports", "ratio", "server"])
+    );
+
+    let properties = &schema["properties"];
+    assert_eq!(
+        properties["name"],
+        json!({"type": "string", "description": "The name of the app."})
+    );
+    assert_eq!(
+        properties["level"],
+        json!({"type": "string", "enum": ["debug", "info", "warn"]})
+    );
+    assert_eq!(
+        properties["ports"],
+        json!({"type": "array", "items": {
+            "type": "integer",
+            "minimum": i64::MIN,
+            "maximum": i64::MAX,
+        }})
+    );
+    assert_eq!(properties["ratio"], json!({"type": "number"}));
+    assert_eq!(
+        properties["server"]["properties"]["max-connections"],
+        json!({"type": "integer", "minimum": 0, "maximum": 65535})
+    );
+}
+
+#[test]
+fn test_array_of_structs() {
+    let schema = schema(SOURCE, &sanitizing_options());
+    let plugins = &schema["properties"]["plugins"];
+
+    assert_eq!(plugins["type"], "array");
+    assert_eq!(plugins["items"]["required"], json!(["name"]));
+    assert_eq!(
+        plugins["items"]["properties"]["args"],
+        json!({"anyOf": [
+            {"type": "array", "items": {"type": "string"}},
+            {"type": "null"},
+        ]})
+    );
+}
+
+#[test]
+fn test_serde_defaults() {
+    let options = StructOptions {
+        serde_support: SerdeSupport::Yes,
+        serde_missing_fields: MissingFields::UseTypeDefault,
+        impl_default: true,
+        ..StructOptions::default()
+    };
+    let schema = schema("a = 1\n", &options);
+
+    assert!(schema.get("required").is_none());
+}
+
+#[test]
+fn test_root_array() {
+    let schema = config_struct::generate_schema(
+        Format::Json,
+        r#"[{"name": "a"}, {"name": "b"}]"#,
+        &StructOptions::default(),
+    )
+    .unwrap();
+    let schema: Value = serde_json::from_str(&schema).unwrap();
+
+    assert_eq!(schema["type"], "array");
+    assert_eq!(schema["items"]["properties"]["name"]["type"], "string");
+}
+
+#[test]
+fn test_create_schema() {
+    let dir = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("create_schema");
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("config.toml");
+    let destination = dir.join("config.schema.json");
+    std::fs::write(&source, SOURCE).unwrap();
+
+    let options = sanitizing_options();
+    config_struct::create_schema(&source, &destination, &options).unwrap();
+
+    let written = std::fs::read_to_string(&destination).unwrap();
+    let generated = config_struct::generate_schema(Format::Toml, SOURCE, &options).unwrap();
+    assert_eq!(written, generated);
+}
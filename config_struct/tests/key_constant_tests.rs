@@ -0,0 +1,75 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, StructOptions, WarningKind};
+
+const SOURCE: &str = r#"
+server_port = 8080
+tags = ["a", "b"]
+
+[server]
+port = 80
+
+[[plugins]]
+name = "x"
+"#;
+
+fn key_options() -> StructOptions {
+    StructOptions {
+        generate_key_constants: true,
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_keys_module_is_generated() {
+    let output = config_struct::generate_config_from_source_with_diagnostics(
+        Format::Toml,
+        SOURCE,
+        &key_options(),
+    )
+    .unwrap();
+
+    assert!(output.code.contains("pub mod keys {"));
+    assert!(output
+        .code
+        .contains(r#"pub const SERVER_PORT: &str = "server.port";"#));
+    assert!(output
+        .code
+        .contains(r#"pub const SERVER_PORT_2: &str = "server_port";"#));
+    assert!(output.code.contains(r#"pub const TAGS: &str = "tags";"#));
+    assert!(output
+        .code
+        .contains(r#"pub const PLUGINS: &str = "plugins";"#));
+    assert!(!output.code.contains("PLUGINS_NAME"));
+
+    assert_eq!(output.warnings.len(), 1);
+    assert_eq!(output.warnings[0].kind, WarningKind::KeyConstantCollision);
+    assert_eq!(
+        output.warnings[0].message,
+        "The key constant for `server_port` would be `SERVER_PORT`, which was taken by `server.port`, so it was named `SERVER_PORT_2`."
+    );
+}
+
+#[test]
+fn test_keys_use_original_keys() {
+    let options = StructOptions {
+        field_renames: vec![("Max Retries".to_owned(), "retries".to_owned())]
+            .into_iter()
+            .collect(),
+        ..key_options()
+    };
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, "\"Max Retries\" = 3", &options)
+            .unwrap();
+
+    assert!(code.contains(r#"pub const MAX_RETRIES: &str = "Max Retries";"#));
+}
+
+#[test]
+fn test_keys_are_off_by_default() {
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, SOURCE, &StructOptions::default())
+            .unwrap();
+
+    assert!(!code.contains("pub mod keys"));
+}
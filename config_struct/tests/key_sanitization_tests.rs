@@ -0,0 +1,96 @@
+use config_struct::{
+    Format, GenerationError, KeySanitization, SerdeSupport, StructOptions, WarningKind,
+};
+
+const SOURCE: &str = r#"
+"404-page" = "missing.html"
+2fa_enabled = true
+self = 1
+
+["my server"]
+port = 80
+"#;
+
+fn options(key_sanitization: KeySanitization) -> StructOptions {
+    StructOptions {
+        key_sanitization,
+        serde_support: SerdeSupport::Yes,
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_invalid_keys_are_an_error_by_default() {
+    match config_struct::generate_config_from_source(
+        Format::Toml,
+        SOURCE,
+        &StructOptions::default(),
+    ) {
+        Err(GenerationError::InvalidFieldName(name)) => assert_eq!(name, "2fa_enabled"),
+        other => panic!("Expected invalid field error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_sanitize() {
+    let code = config_struct::generate_config_from_source(
+        Format::Toml,
+        SOURCE,
+        &options(KeySanitization::Sanitize),
+    )
+    .unwrap();
+
+    assert!(code.contains("    pub _404_page: Cow<'static, str>,\n"));
+    assert!(code.contains("    pub _2fa_enabled: bool,\n"));
+    assert!(code.contains("    pub self_: i64,\n"));
+    assert!(code.contains("    pub my_server: _Config__my_server,\n"));
+    assert!(code.contains("pub struct _Config__my_server {"));
+    assert!(!code.contains("rename"));
+}
+
+#[test]
+fn test_sanitize_with_serde_rename() {
+    let code = config_struct::generate_config_from_source(
+        Format::Toml,
+        SOURCE,
+        &options(KeySanitization::SanitizeWithSerdeRename),
+    )
+    .unwrap();
+
+    assert!(code.contains("    #[serde(rename = \"404-page\")]\n    pub _404_page:"));
+    assert!(code.contains("    #[serde(rename = \"2fa_enabled\")]\n    pub _2fa_enabled:"));
+    assert!(code.contains("    #[serde(rename = \"self\")]\n    pub self_:"));
+    assert!(code.contains("    #[serde(rename = \"my server\")]\n    pub my_server:"));
+}
+
+#[test]
+fn test_collisions_are_numbered() {
+    let output = config_struct::generate_config_from_source_with_diagnostics(
+        Format::Toml,
+        "a-b = 1\na_b = 2\n\"a.b\" = 3\n",
+        &options(KeySanitization::SanitizeWithSerdeRename),
+    )
+    .unwrap();
+
+    assert!(output.code.contains("    pub a_b: i64,\n"));
+    assert!(output
+        .code
+        .contains("    #[serde(rename = \"a-b\")]\n    pub a_b_2: i64,\n"));
+    assert!(output
+        .code
+        .contains("    #[serde(rename = \"a.b\")]\n    pub a_b_3: i64,\n"));
+    assert!(output.code.contains("    a_b: 2,\n"));
+
+    let kinds = output
+        .warnings
+        .iter()
+        .map(|warning| (warning.kind, warning.path.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            (WarningKind::SanitizedKeyCollision, "a-b"),
+            (WarningKind::SanitizedKeyCollision, "a.b"),
+        ]
+    );
+}
@@ -0,0 +1,145 @@
+use config_struct::{Error, ExceededLimit, Format, GenerationError, ResourceLimits, StructOptions};
+
+fn generate(
+    format: Format,
+    source: &str,
+    resource_limits: ResourceLimits,
+) -> Result<String, GenerationError> {
+    let options = StructOptions {
+        resource_limits,
+        ..StructOptions::default()
+    };
+    config_struct::generate_config_from_source(format, source, &options)
+}
+
+fn assert_exceeded(
+    result: Result<String, GenerationError>,
+    expected_limit: &str,
+    expected_path: &str,
+) {
+    match result {
+        Err(GenerationError::LimitExceeded(ExceededLimit { limit, path, .. })) => {
+            assert_eq!((limit, path.as_str()), (expected_limit, expected_path))
+        }
+        other => panic!("Expected an exceeded limit, found {:?}", other),
+    }
+}
+
+/// A YAML config whose last alias stands for `10^levels` strings.
+#[cfg(feature = "yaml-parsing")]
+fn billion_laughs(levels: usize) -> String {
+    let mut yaml = "a0: &a0 lol\n".to_owned();
+    for level in 1..=levels {
+        let aliases = vec![format!("*a{}", level - 1); 10].join(", ");
+        yaml.push_str(&format!("a{}: &a{} [{}]\n", level, level, aliases));
+    }
+    yaml
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_yaml_aliases_count_as_expanded() {
+    // Expanded, this would be around a billion values.
+    let result = generate(Format::Yaml, &billion_laughs(9), ResourceLimits::default());
+    assert_exceeded(result, "max_nodes", "a6");
+
+    let limits = ResourceLimits {
+        max_nodes: 1_000,
+        ..ResourceLimits::default()
+    };
+    assert!(generate(Format::Yaml, &billion_laughs(2), limits).is_ok());
+    assert_exceeded(
+        generate(Format::Yaml, &billion_laughs(3), limits),
+        "max_nodes",
+        "a3",
+    );
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_yaml_aliases_count_their_depth() {
+    // The anchored value is 3 deep, but 4 deep where it's used.
+    let yaml = "deep: &deep [[[1]]]\nholder:\n  inner: *deep\n";
+    let generate = |max_depth| {
+        let options = StructOptions {
+            max_depth,
+            ..StructOptions::default()
+        };
+        config_struct::generate_config_from_source(Format::Yaml, yaml, &options)
+    };
+    assert!(generate(4).is_ok());
+
+    match generate(3) {
+        Err(GenerationError::MaxDepthExceeded { path, depth }) => {
+            assert_eq!((path.as_str(), depth), ("holder.inner", 4))
+        }
+        other => panic!("Expected too deep a value, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_json_limits() {
+    let source = r#"{ "name": "a long name", "ports": [80, 443, 8080] }"#;
+
+    let limits = ResourceLimits {
+        max_nodes: 5,
+        ..ResourceLimits::default()
+    };
+    assert_exceeded(generate(Format::Json, source, limits), "max_nodes", "name");
+
+    let limits = ResourceLimits {
+        max_string_len: 5,
+        ..ResourceLimits::default()
+    };
+    assert_exceeded(
+        generate(Format::Json, source, limits),
+        "max_string_len",
+        "name",
+    );
+
+    assert!(generate(Format::Json, source, ResourceLimits::default()).is_ok());
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_source_length_is_checked_before_parsing() {
+    let source = "name = \"app\"\n";
+    let limits = ResourceLimits {
+        max_source_bytes: source.len() - 1,
+        ..ResourceLimits::default()
+    };
+
+    let error = generate(Format::Toml, source, limits).unwrap_err();
+    assert_eq!(error.path(), None);
+    assert!(error
+        .to_string()
+        .starts_with("The config passed resource_limits.max_source_bytes, which is 12."));
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_readers_are_not_read_past_the_limit() {
+    let options = StructOptions {
+        resource_limits: ResourceLimits {
+            max_source_bytes: 1024,
+            ..ResourceLimits::default()
+        },
+        ..StructOptions::default()
+    };
+
+    // An endless reader.
+    let reader = std::io::repeat(b' ');
+    match config_struct::generate_config_from_reader(Format::Toml, reader, &options) {
+        Err(Error::Generation(GenerationError::LimitExceeded(exceeded))) => {
+            assert_eq!(exceeded.limit, "max_source_bytes")
+        }
+        other => panic!("Expected an exceeded limit, found {:?}", other),
+    }
+
+    let source = "name = \"app\"\n";
+    assert!(
+        config_struct::generate_config_from_reader(Format::Toml, source.as_bytes(), &options)
+            .is_ok()
+    );
+}
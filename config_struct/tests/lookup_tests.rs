@@ -0,0 +1,62 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, GenerationError, OptionsError, StructOptions};
+
+const SOURCE: &str = "name = \"app\"\n\n[[servers]]\nport = 80\n";
+
+#[test]
+fn test_get_is_generated_on_the_root() {
+    let options = StructOptions {
+        generate_get_by_path: true,
+        ..StructOptions::default()
+    };
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(code.contains("pub fn get(&self, path: &str) -> Option<ConfigValueRef<'_>> {"));
+    assert!(code.contains("pub enum ConfigValueRef<'a> {"));
+    assert!(code.contains("impl ConfigFields for _Config__servers {"));
+    assert!(code.contains(r#"const FIELD_NAMES: &[&str] = &["name", "servers"];"#));
+}
+
+#[test]
+fn test_get_uses_original_keys() {
+    let options = StructOptions {
+        generate_get_by_path: true,
+        field_renames: vec![("Max Retries".to_owned(), "max_retries".to_owned())]
+            .into_iter()
+            .collect(),
+        ..StructOptions::default()
+    };
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, "\"Max Retries\" = 3", &options)
+            .unwrap();
+
+    assert!(code.contains(r#""Max Retries" => Some(self.max_retries.value_ref()),"#));
+}
+
+#[test]
+fn test_get_is_off_by_default() {
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, SOURCE, &StructOptions::default())
+            .unwrap();
+
+    assert!(!code.contains("ConfigValueRef"));
+}
+
+#[test]
+fn test_get_rejects_cfg_sections() {
+    let options = StructOptions {
+        generate_get_by_path: true,
+        cfg_sections: vec![("name".to_owned(), "unix".to_owned())]
+            .into_iter()
+            .collect(),
+        ..StructOptions::default()
+    };
+
+    match config_struct::generate_config_from_source(Format::Toml, SOURCE, &options) {
+        Err(GenerationError::StructOptions(OptionsError::CfgSectionsWith(option))) => {
+            assert_eq!(option, "generate_get_by_path")
+        }
+        other => panic!("Expected options error, found {:?}", other),
+    }
+}
@@ -0,0 +1,173 @@
+#![cfg(feature = "ron-parsing")]
+
+use config_struct::{ConstFallback, Format, GenerationError, MapType, OptionsError, StructOptions};
+
+const SOURCE: &str = r#"(
+    scores: {
+        "alice": 10,
+        "bob-the-builder": 7,
+    },
+    servers: {
+        "primary": (host: "a", port: 80),
+        "backup": (host: "b"),
+    },
+)"#;
+
+fn map_options(map_type: MapType) -> StructOptions {
+    StructOptions {
+        map_type,
+        map_paths: vec!["scores".to_owned(), "servers".to_owned()],
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_maps_are_structs_by_default() {
+    let result =
+        config_struct::generate_config_from_source(Format::Ron, SOURCE, &StructOptions::default());
+
+    match result {
+        Err(GenerationError::InvalidFieldName(name)) => assert_eq!(name, "bob-the-builder"),
+        other => panic!("Expected invalid field name error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_maps_as_slices() {
+    let code = config_struct::generate_config_from_source(
+        Format::Ron,
+        SOURCE,
+        &map_options(MapType::Slice),
+    )
+    .unwrap();
+
+    assert!(code.contains("pub scores: Cow<'static, [(Cow<'static, str>, i64)]>,"));
+    assert!(code.contains(
+        r#"scores: Cow::Borrowed(&[(Cow::Borrowed("alice"), 10), (Cow::Borrowed("bob-the-builder"), 7)]),"#
+    ));
+    assert!(code.contains("pub const CONFIG: Config"));
+}
+
+#[test]
+fn test_map_structs_are_unified() {
+    let code = config_struct::generate_config_from_source(
+        Format::Ron,
+        SOURCE,
+        &map_options(MapType::Slice),
+    )
+    .unwrap();
+
+    assert!(code.contains("pub servers: Cow<'static, [(Cow<'static, str>, _Config__servers)]>,"));
+    assert!(code.contains("pub port: Option<i64>,"));
+    assert_eq!(code.matches("pub struct _Config__servers").count(), 1);
+}
+
+#[test]
+fn test_maps_as_hash_maps() {
+    let code = config_struct::generate_config_from_source(
+        Format::Ron,
+        SOURCE,
+        &map_options(MapType::HashMap),
+    )
+    .unwrap();
+
+    assert!(code.contains("pub scores: ::std::collections::HashMap<Cow<'static, str>, i64>,"));
+    assert!(code.contains("pub static CONFIG: ::std::sync::LazyLock<Config>"));
+}
+
+#[test]
+fn test_hash_maps_with_runtime_fn() {
+    let options = StructOptions {
+        const_fallback: ConstFallback::RuntimeFn,
+        ..map_options(MapType::HashMap)
+    };
+    let code = config_struct::generate_config_from_source(Format::Ron, SOURCE, &options).unwrap();
+
+    assert!(code.contains("pub fn config() -> &'static Config {"));
+    assert!(!code.contains("pub const CONFIG"));
+}
+
+#[test]
+fn test_hash_maps_without_const_fallback() {
+    let options = StructOptions {
+        const_fallback: ConstFallback::Error,
+        ..map_options(MapType::HashMap)
+    };
+    let result = config_struct::generate_config_from_source(Format::Ron, SOURCE, &options);
+
+    match result {
+        Err(GenerationError::NonConstField(path)) => assert_eq!(path, "scores"),
+        other => panic!("Expected non-const field error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_const_fallback_is_only_used_when_needed() {
+    let options = StructOptions {
+        const_fallback: ConstFallback::Error,
+        ..map_options(MapType::Slice)
+    };
+    let code = config_struct::generate_config_from_source(Format::Ron, SOURCE, &options).unwrap();
+
+    assert!(code.contains("pub const CONFIG: Config"));
+}
+
+#[test]
+fn test_heterogenous_maps_are_an_error() {
+    let options = StructOptions {
+        map_paths: vec!["values".to_owned()],
+        ..StructOptions::default()
+    };
+    let result = config_struct::generate_config_from_source(
+        Format::Ron,
+        r#"(values: { "a": 1, "b": "two" })"#,
+        &options,
+    );
+
+    match result {
+        Err(GenerationError::HeterogenousMap(path)) => assert_eq!(path, "values"),
+        other => panic!("Expected heterogenous map error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_slice_maps_cannot_be_loaded_dynamically() {
+    let options = StructOptions {
+        generate_load_fns: true,
+        ..map_options(MapType::Slice)
+    };
+    let result = config_struct::generate_config_from_source(Format::Ron, SOURCE, &options);
+
+    match result {
+        Err(GenerationError::StructOptions(OptionsError::SliceMapsWithDynamicLoading)) => (),
+        other => panic!("Expected options error, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_yaml_maps_with_scalar_keys() {
+    let options = StructOptions {
+        map_type: MapType::Slice,
+        map_paths: vec!["codes".to_owned()],
+        ..StructOptions::default()
+    };
+    let source = "codes:\n  404: missing\n  500: broken\n  true: yes\n";
+    let code = config_struct::generate_config_from_source(Format::Yaml, source, &options).unwrap();
+
+    for entry in &[
+        r#"(Cow::Borrowed("404"), Cow::Borrowed("missing")),"#,
+        r#"(Cow::Borrowed("500"), Cow::Borrowed("broken")),"#,
+        r#"(Cow::Borrowed("true"), Cow::Borrowed("yes")),"#,
+    ] {
+        assert!(code.contains(entry), "{}", code);
+    }
+
+    let source = "codes:\n  [1, 2]: pair\n";
+    match config_struct::generate_config_from_source(Format::Yaml, source, &options) {
+        Err(error @ GenerationError::UnsupportedYamlKey(_)) => {
+            assert_eq!(error.path(), Some("codes"))
+        }
+        other => panic!("Expected unsupported key error, found {:?}", other),
+    }
+}
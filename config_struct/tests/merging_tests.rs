@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+
+use config_struct::{DynamicLoading, Error, GenerationError, StructOptions};
+
+fn write_temp_file(name: &str, contents: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_merging_files() {
+    let base = write_temp_file(
+        "merge_base.toml",
+        r#"
+name = "Base"
+list = [1, 2, 3]
+
+[server]
+host = "localhost"
+port = 80
+"#,
+    );
+    let overrides = write_temp_file(
+        "merge_overrides.json",
+        r#"{ "list": [4], "server": { "port": 8080 }, "extra": true }"#,
+    );
+
+    let code =
+        config_struct::generate_config_from_files(&[base, overrides], &StructOptions::default())
+            .unwrap();
+
+    assert!(code.contains(r#"name: Cow::Borrowed("Base")"#));
+    assert!(code.contains("list: Cow::Borrowed(&[4])"));
+    assert!(code.contains(r#"host: Cow::Borrowed("localhost")"#));
+    assert!(code.contains("port: 8080"));
+    assert!(code.contains("extra: true"));
+}
+
+#[test]
+fn test_merging_incompatible_files() {
+    let base = write_temp_file("merge_incompatible_base.toml", "[server]\nport = 80\n");
+    let overrides = write_temp_file(
+        "merge_incompatible_overrides.json",
+        r#"{ "server": { "port": "80" } }"#,
+    );
+
+    let result =
+        config_struct::generate_config_from_files(&[base, overrides], &StructOptions::default());
+
+    match result {
+        Err(Error::Generation(GenerationError::IncompatibleMerge(path))) => {
+            assert_eq!(path, "server.port")
+        }
+        other => panic!("Expected merge error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_merging_with_dynamic_loading() {
+    let base = write_temp_file("merge_dynamic_base.toml", "a = 1\n");
+    let overrides = write_temp_file("merge_dynamic_overrides.toml", "a = 2\n");
+
+    let options = StructOptions {
+        generate_load_fns: true,
+        dynamic_loading: DynamicLoading::DebugOnly,
+        ..StructOptions::default()
+    };
+
+    assert!(config_struct::generate_config_from_files(&[&base, &overrides], &options).is_err());
+
+    let options = StructOptions {
+        dynamic_loading: DynamicLoading::Never,
+        ..options
+    };
+
+    assert!(config_struct::generate_config_from_files(&[&base, &overrides], &options).is_ok());
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{
+    Format, GenerationError, MissingFields, OptionsError, SerdeSupport, StructOptions,
+};
+
+const SOURCE: &str = r#"
+name = "Example"
+type = "kind"
+
+[server]
+port = 80
+"#;
+
+fn options(serde_missing_fields: MissingFields) -> StructOptions {
+    StructOptions {
+        serde_missing_fields,
+        serde_support: SerdeSupport::Yes,
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_use_const() {
+    let code = config_struct::generate_config_from_source(
+        Format::Toml,
+        SOURCE,
+        &options(MissingFields::UseConst),
+    )
+    .unwrap();
+
+    assert!(code.contains("    #[serde(default = \"Config::default_name\")]\n    pub name:"));
+    assert!(code.contains("    #[serde(default = \"Config::default_type\")]\n    pub r#type:"));
+    assert!(
+        code.contains("    #[serde(default = \"_Config__server::default_port\")]\n    pub port:")
+    );
+    assert!(code.contains("    fn default_port() -> i64 {\n        80\n    }"));
+}
+
+#[test]
+fn test_use_type_default() {
+    let code = config_struct::generate_config_from_source(
+        Format::Toml,
+        SOURCE,
+        &options(MissingFields::UseTypeDefault),
+    )
+    .unwrap();
+
+    assert!(code.contains("    #[serde(default)]\n    pub name:"));
+    assert!(code.contains("    #[serde(default)]\n    pub r#type:"));
+    assert!(!code.contains("fn default_name()"));
+
+    // The missing `server` table needs a default of its own.
+    assert!(code.contains("#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]\n#[allow(non_camel_case_types)]\npub struct _Config__server {"));
+    assert!(code.contains(
+        "#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]\npub struct Config {"
+    ));
+}
+
+#[test]
+fn test_error_by_default() {
+    let code = config_struct::generate_config_from_source(
+        Format::Toml,
+        SOURCE,
+        &options(MissingFields::Error),
+    )
+    .unwrap();
+
+    assert!(!code.contains("default"));
+}
+
+#[test]
+fn test_requires_deserialize() {
+    let options = StructOptions {
+        serde_missing_fields: MissingFields::UseConst,
+        ..StructOptions::default()
+    };
+
+    match config_struct::generate_config_from_source(Format::Toml, SOURCE, &options) {
+        Err(GenerationError::StructOptions(OptionsError::MissingFieldsWithoutDeserialize)) => (),
+        other => panic!("Expected options error, found {:?}", other),
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "json-parsing")]
+
+use config_struct::{Format, GenerationError, MixedArrayBehavior, StructOptions};
+
+const SOURCE: &str = r#"{
+    "table": {
+        "point": [1, "x", true]
+    }
+}"#;
+
+#[test]
+fn test_mixed_arrays_are_an_error_by_default() {
+    let result =
+        config_struct::generate_config_from_source(Format::Json, SOURCE, &StructOptions::default());
+
+    match result {
+        Err(GenerationError::HeterogenousArray(path, indices)) => {
+            assert_eq!(path, "table.point");
+            assert_eq!(indices, vec![1, 2]);
+        }
+        other => panic!("Expected heterogenous array error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_mixed_arrays_as_tuples() {
+    let options = StructOptions {
+        mixed_arrays: MixedArrayBehavior::Tuple,
+        ..StructOptions::default()
+    };
+
+    let code = config_struct::generate_config_from_source(Format::Json, SOURCE, &options).unwrap();
+
+    assert!(code.contains("pub point: (i64, Cow<'static, str>, bool),"));
+    assert!(code.contains(r#"point: (1, Cow::Borrowed("x"), true),"#));
+}
+
+// TOML itself doesn't allow mixed arrays, so the parser rejects them
+// before `mixed_arrays` applies.
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_mixed_toml_arrays_fail_to_parse() {
+    let options = StructOptions {
+        mixed_arrays: MixedArrayBehavior::Tuple,
+        ..StructOptions::default()
+    };
+
+    let source = "[table]\npoint = [1, \"x\", true]\n";
+    match config_struct::generate_config_from_source(Format::Toml, source, &options) {
+        Err(GenerationError::ParseFailed(ref parse_error)) => {
+            assert!(
+                parse_error.message.contains("mixed types in an array"),
+                "{}",
+                parse_error.message
+            );
+            assert_eq!(parse_error.line, Some(2));
+        }
+        other => panic!("Expected a parse error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_mixed_numbers_are_widened() {
+    let source = r#"{ "ints": [1, 2.5, 3], "nested": [[1], [0.5, 2]] }"#;
+
+    let code =
+        config_struct::generate_config_from_source(Format::Json, source, &StructOptions::default())
+            .unwrap();
+
+    assert!(code.contains("pub ints: Cow<'static, [f64]>,"));
+    assert!(code.contains("ints: Cow::Borrowed(&[1.0, 2.5, 3.0]),"));
+    assert!(code.contains("pub nested: Cow<'static, [Cow<'static, [f64]>]>,"));
+}
+
+#[test]
+fn test_numbers_and_strings_are_not_widened() {
+    let source = r#"{ "values": [1, 2.5, "three"] }"#;
+
+    let result =
+        config_struct::generate_config_from_source(Format::Json, source, &StructOptions::default());
+
+    match result {
+        Err(GenerationError::HeterogenousArray(path, indices)) => {
+            assert_eq!(path, "values");
+            assert_eq!(indices, vec![1, 2]);
+        }
+        other => panic!("Expected heterogenous array error, found {:?}", other),
+    }
+}
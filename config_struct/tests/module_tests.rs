@@ -0,0 +1,55 @@
+#![cfg(all(feature = "json-parsing", feature = "toml-parsing"))]
+
+use config_struct::{Error, GenerationError, ModuleInput, StructOptions};
+
+mod common;
+
+use common::temp_dir;
+
+#[test]
+fn test_configs_are_generated_into_one_module() {
+    let dir = temp_dir("module_generated");
+    std::fs::write(
+        dir.join("graphics.toml"),
+        "width = 1920\ntitle = \"Game\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("audio.json"),
+        r#"{ "volume": 0.5, "device": "default" }"#,
+    )
+    .unwrap();
+
+    let inputs = vec![
+        ModuleInput::new(dir.join("graphics.toml"), "Graphics"),
+        ModuleInput {
+            const_name: Some("AUDIO_SETTINGS".to_owned()),
+            ..ModuleInput::new(dir.join("audio.json"), "Audio")
+        },
+    ];
+    let destination = dir.join("config.rs");
+    config_struct::create_config_module(&inputs, &destination, &StructOptions::default()).unwrap();
+
+    let code = std::fs::read_to_string(&destination).unwrap();
+    assert_eq!(code.matches("#![allow(dead_code)]").count(), 1);
+    assert_eq!(code.matches("use std::borrow::Cow;").count(), 1);
+    assert!(code.contains("pub struct Graphics"));
+    assert!(code.contains("pub const GRAPHICS: Graphics"));
+    assert!(code.contains("pub struct Audio"));
+    assert!(code.contains("pub const AUDIO_SETTINGS: Audio"));
+}
+
+#[test]
+fn test_name_collisions_are_detected_before_reading() {
+    let inputs = vec![
+        ModuleInput::new("missing.toml", "Settings"),
+        ModuleInput::new("missing.json", "Settings"),
+    ];
+
+    match config_struct::generate_config_module(&inputs, &StructOptions::default()) {
+        Err(Error::Generation(GenerationError::DuplicateModuleName(name))) => {
+            assert_eq!(name, "Settings")
+        }
+        other => panic!("Expected duplicate name error, found {:?}", other),
+    }
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "json-parsing")]
+
+use config_struct::{Format, StringType, StructOptions};
+
+fn generate(source: &str) -> String {
+    let options = StructOptions {
+        string_type: StringType::StaticStr,
+        ..StructOptions::default()
+    };
+    config_struct::generate_config_from_source(Format::Json, source, &options).unwrap()
+}
+
+#[test]
+fn test_lines_are_raw_strings() {
+    let code = generate(r###"{ "text": "first\nsays \"#\" and \"##\"\nlast" }"###);
+
+    assert!(code.contains("    text: r###\"first\nsays \"#\" and \"##\"\nlast\"###,\n"));
+}
+
+#[test]
+fn test_single_lines_are_escaped() {
+    let code = generate(r#"{ "text": "say \"hi\"\ttab" }"#);
+
+    assert!(code.contains(r#"    text: "say \"hi\"\ttab","#));
+}
+
+#[test]
+fn test_carriage_returns_are_concatenated() {
+    let code = generate(r#"{ "text": "first\r\nsecond" }"#);
+
+    assert!(code
+        .contains("    text: concat!(\n        \"first\\r\\n\",\n        \"second\",\n    ),\n"));
+}
+
+#[test]
+fn test_trailing_whitespace_is_concatenated() {
+    let code = generate(r#"{ "text": "first  \nsecond  " }"#);
+
+    assert!(code
+        .contains("    text: concat!(\n        \"first  \\n\",\n        \"second  \",\n    ),\n"));
+}
+
+#[test]
+fn test_direction_overrides_are_escaped() {
+    let code = generate(r#"{ "text": "a\u202eb\nc" }"#);
+
+    assert!(code.contains("\"a\\u{202e}b\\n\","));
+}
+
+#[test]
+fn test_arrays_of_lines_are_wrapped() {
+    let code = generate(r#"{ "texts": ["a\nb", "c"] }"#);
+
+    assert!(code.contains("    texts: &[\n        r#\"a\nb\"#,\n        \"c\",\n    ],\n"));
+}
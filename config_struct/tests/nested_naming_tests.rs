@@ -0,0 +1,142 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{ArrayElementNaming, Format, GenerationError, NestedNaming, StructOptions};
+
+const SOURCE: &str = r#"
+[server]
+host = "localhost"
+
+[server.tls]
+cert = "cert.pem"
+
+[[clients]]
+name = "a"
+"#;
+
+fn generate(nested_naming: NestedNaming) -> Result<String, GenerationError> {
+    let options = StructOptions {
+        nested_naming,
+        ..StructOptions::default()
+    };
+    config_struct::generate_config_from_source(Format::Toml, SOURCE, &options)
+}
+
+#[test]
+fn test_concatenated_names() {
+    let code = generate(NestedNaming::Concatenated).unwrap();
+
+    assert!(code.contains("pub struct _Config__server__tls {"));
+    assert!(code.contains("pub clients: Cow<'static, [_Config__clients]>,"));
+}
+
+#[test]
+fn test_pascal_case_names() {
+    let code = generate(NestedNaming::PascalCase).unwrap();
+
+    assert!(code.contains("pub struct Server {"));
+    assert!(code.contains("pub tls: ServerTls,"));
+    assert!(code.contains("pub struct ServerTls {"));
+    assert!(code.contains("pub clients: Cow<'static, [Clients]>,"));
+    assert!(!code.contains("_Config__"));
+}
+
+#[test]
+fn test_explicit_names() {
+    let names = vec![("server.tls".to_owned(), "TlsOptions".to_owned())]
+        .into_iter()
+        .collect();
+    let code = generate(NestedNaming::Explicit(names)).unwrap();
+
+    assert!(code.contains("pub tls: TlsOptions,"));
+    assert!(code.contains("pub struct _Config__server {"));
+}
+
+#[test]
+fn test_pascal_case_collisions() {
+    let source = "[server.tls]\ncert = 1\n\n[server_tls]\ncert = 2\n";
+    let options = StructOptions {
+        nested_naming: NestedNaming::PascalCase,
+        ..StructOptions::default()
+    };
+    let result = config_struct::generate_config_from_source(Format::Toml, source, &options);
+
+    match result {
+        Err(GenerationError::ConflictingStructNames(name, first, second)) => {
+            assert_eq!(name, "ServerTls");
+            assert_eq!(first, "server.tls");
+            assert_eq!(second, "server_tls");
+        }
+        other => panic!("Expected conflicting names error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_invalid_explicit_names() {
+    let names = vec![("server".to_owned(), "type".to_owned())]
+        .into_iter()
+        .collect();
+
+    match generate(NestedNaming::Explicit(names)) {
+        Err(GenerationError::InvalidNestedStructName(path, name)) => {
+            assert_eq!(path, "server");
+            assert_eq!(name, "type");
+        }
+        other => panic!("Expected invalid name error, found {:?}", other),
+    }
+}
+
+fn singular_options(singulars: Vec<(&str, &str)>) -> StructOptions {
+    let singulars = singulars
+        .into_iter()
+        .map(|(key, singular)| (key.to_owned(), singular.to_owned()))
+        .collect();
+    StructOptions {
+        array_element_naming: ArrayElementNaming::Singular(singulars),
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_singular_element_names() {
+    let source = r#"
+[[items]]
+name = "a"
+meta = { size = 1 }
+
+[[items]]
+name = "b"
+meta = { size = 2 }
+
+[server]
+ports = [{ number = 80 }, { number = 443 }]
+people = [{ name = "c" }]
+grid = [[{ x = 1 }], [{ x = 2 }, { x = 3 }]]
+"#;
+    let options = singular_options(vec![("people", "person")]);
+    let code = config_struct::generate_config_from_source(Format::Toml, source, &options).unwrap();
+
+    assert!(code.contains("pub items: Cow<'static, [ConfigItem]>,"));
+    assert!(code.contains("pub ports: Cow<'static, [ConfigServerPort]>,"));
+    assert!(code.contains("pub people: Cow<'static, [ConfigServerPerson]>,"));
+    assert!(code.contains("pub grid: Cow<'static, [Cow<'static, [ConfigServerGrid]>]>,"));
+    assert!(code.contains("pub meta: _Config__items__meta,"));
+    assert_eq!(code.matches("pub struct ConfigItem {").count(), 1);
+    assert_eq!(code.matches("pub struct ConfigServerGrid {").count(), 1);
+    assert_eq!(code.matches("ConfigServerGrid {").count(), 4);
+}
+
+#[test]
+fn test_singular_name_collisions() {
+    let source = "items = [{ a = 1 }]\nitem = [{ b = 2 }]\n";
+    let result =
+        config_struct::generate_config_from_source(Format::Toml, source, &singular_options(vec![]));
+
+    match result {
+        Err(GenerationError::ConflictingStructNames(name, first, second)) => {
+            assert_eq!(name, "ConfigItem");
+            assert_eq!(first, "item");
+            assert_eq!(second, "items");
+        }
+        other => panic!("Expected conflicting names error, found {:?}", other),
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use config_struct::{Format, GenerationError, SerdeSupport, StructOptions};
+
+fn options(paths: &[(&str, &str)]) -> StructOptions {
+    let newtype_paths = paths
+        .iter()
+        .map(|&(path, name)| (path.to_owned(), name.to_owned()))
+        .collect::<HashMap<_, _>>();
+    StructOptions {
+        newtype_paths,
+        ..StructOptions::default()
+    }
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_newtype_fields() {
+    let source = r#"
+timeout_ms = 500
+ratio = 0.5
+
+[cache]
+size_mb = 64
+"#;
+    let options = options(&[
+        ("timeout_ms", "TimeoutMs"),
+        ("ratio", "Ratio"),
+        ("cache.size_mb", "Megabytes"),
+    ]);
+
+    let code = config_struct::generate_config_from_source(Format::Toml, source, &options).unwrap();
+
+    assert!(code.contains("pub struct TimeoutMs(pub i64);"));
+    assert!(code.contains("pub struct Ratio(pub f64);"));
+    assert!(code.contains("    pub timeout_ms: TimeoutMs,"));
+    assert!(code.contains("    pub size_mb: Megabytes,"));
+    assert!(code.contains("timeout_ms: TimeoutMs(500),"));
+    assert!(code.contains("ratio: Ratio(0.5),"));
+    assert!(code.contains("size_mb: Megabytes(64),"));
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_shared_newtypes_are_declared_once() {
+    let source = "read_timeout_ms = 100\nwrite_timeout_ms = 200\nretries = [1, 2]\n";
+    let options = StructOptions {
+        serde_support: SerdeSupport::Yes,
+        ..options(&[
+            ("read_timeout_ms", "Millis"),
+            ("write_timeout_ms", "Millis"),
+            ("retries", "Retries"),
+        ])
+    };
+
+    let code = config_struct::generate_config_from_source(Format::Toml, source, &options).unwrap();
+
+    assert_eq!(code.matches("pub struct Millis(pub i64);").count(), 1);
+    assert!(code.contains("#[serde(transparent)]\npub struct Millis(pub i64);"));
+    assert!(code.contains("    pub retries: Cow<'static, [Retries]>,"));
+    assert!(code.contains("retries: Cow::Borrowed(&[Retries(1), Retries(2)]),"));
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_newtype_errors() {
+    let generate = |source: &str, options: &StructOptions| {
+        config_struct::generate_config_from_source(Format::Toml, source, options)
+    };
+
+    let source = "read_ms = 100\nwrite_ms = 2.5\n";
+    match generate(
+        source,
+        &options(&[("read_ms", "Millis"), ("write_ms", "Millis")]),
+    ) {
+        Err(GenerationError::ConflictingNewtypes(conflict)) => {
+            assert_eq!(
+                (
+                    conflict.name.as_str(),
+                    conflict.first_type.as_str(),
+                    conflict.second_type.as_str(),
+                    conflict.second_path.as_str()
+                ),
+                ("Millis", "i64", "f64", "write_ms")
+            );
+        }
+        other => panic!("Expected conflicting newtypes, found {:?}", other),
+    }
+
+    match generate("name = \"app\"", &options(&[("name", "Name")])) {
+        Err(error @ GenerationError::InvalidNewtypeField(..)) => {
+            assert_eq!(error.path(), Some("name"))
+        }
+        other => panic!("Expected an invalid newtype field, found {:?}", other),
+    }
+
+    match generate("timeout = 5", &options(&[("timeout", "2Fast")])) {
+        Err(GenerationError::InvalidNewtypeName(path, name)) => {
+            assert_eq!((path.as_str(), name.as_str()), ("timeout", "2Fast"))
+        }
+        other => panic!("Expected an invalid newtype name, found {:?}", other),
+    }
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{
+    DynamicLoading, EnvOptions, Format, GenerationError, OptionsError, StringType, StructOptions,
+};
+
+const SOURCE: &str = r#"
+name = "x"
+words = ["one", "two"]
+"#;
+
+fn no_std_options() -> StructOptions {
+    StructOptions {
+        no_std: true,
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_cow_is_imported_from_alloc() {
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &no_std_options())
+        .unwrap();
+
+    assert!(code.contains("use alloc::borrow::Cow;"));
+    assert!(!code.contains("std::"));
+}
+
+#[test]
+fn test_static_strs_need_no_alloc() {
+    let options = StructOptions {
+        string_type: StringType::StaticStr,
+        ..no_std_options()
+    };
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(!code.contains("alloc"));
+    assert!(!code.contains("Cow"));
+    assert!(code.contains("pub words: &'static [&'static str],"));
+
+    let source = "note = \"Cow::Borrowed\"\n";
+    let code = config_struct::generate_config_from_source(Format::Toml, source, &options).unwrap();
+    assert!(!code.contains("alloc"), "{}", code);
+}
+
+#[test]
+fn test_no_std_rejects_dynamic_loading() {
+    let options = StructOptions {
+        generate_load_fns: true,
+        dynamic_loading: DynamicLoading::Always,
+        ..no_std_options()
+    };
+    let result = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options);
+
+    match result {
+        Err(GenerationError::StructOptions(OptionsError::NoStdWithDynamicLoading)) => (),
+        other => panic!("Expected options error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_no_std_rejects_env_overrides() {
+    let options = StructOptions {
+        env_overrides: Some(EnvOptions::default()),
+        ..no_std_options()
+    };
+    let result = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options);
+
+    match result {
+        Err(GenerationError::StructOptions(OptionsError::NoStdWithEnvOverrides)) => (),
+        other => panic!("Expected options error, found {:?}", other),
+    }
+}
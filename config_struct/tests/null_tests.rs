@@ -0,0 +1,68 @@
+#![cfg(all(feature = "json-parsing", feature = "yaml-parsing"))]
+
+use config_struct::{Format, GenerationError, NullBehavior, StructOptions};
+
+const JSON: &str = r#"{
+    "ports": [80, null],
+    "proxy": null,
+    "servers": [{ "host": "a", "port": 80 }, { "host": "b", "port": null }]
+}"#;
+
+const YAML: &str = "
+ports: [80, ~]
+proxy: null
+servers:
+  - host: a
+    port: 80
+  - host: b
+    port: ~
+";
+
+fn options(null_values: NullBehavior) -> StructOptions {
+    StructOptions {
+        null_values,
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_nulls_are_options_by_default() {
+    for &(format, source) in &[(Format::Json, JSON), (Format::Yaml, YAML)] {
+        let code =
+            config_struct::generate_config_from_source(format, source, &StructOptions::default())
+                .unwrap();
+
+        assert!(code.contains("pub ports: Cow<'static, [Option<i64>]>,"));
+        assert!(code.contains("ports: Cow::Borrowed(&[Some(80), None]),"));
+        assert!(code.contains("pub proxy: Option<()>,"));
+        assert!(code.contains("pub port: Option<i64>,"));
+    }
+}
+
+#[test]
+fn test_nulls_as_unit() {
+    let source = r#"{ "proxy": null }"#;
+    let code = config_struct::generate_config_from_source(
+        Format::Json,
+        source,
+        &options(NullBehavior::Unit),
+    )
+    .unwrap();
+
+    assert!(code.contains("pub proxy: (),"));
+    assert!(code.contains("proxy: (),"));
+}
+
+#[test]
+fn test_nulls_can_be_errors() {
+    for &(format, source) in &[(Format::Json, JSON), (Format::Yaml, YAML)] {
+        match config_struct::generate_config_from_source(
+            format,
+            source,
+            &options(NullBehavior::Error),
+        ) {
+            Err(GenerationError::NullValue(path)) => assert_eq!(path, "ports"),
+            other => panic!("Expected a null value error, found {:?}", other),
+        }
+    }
+}
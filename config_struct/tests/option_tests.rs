@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use config_struct::{
+    Format, GenerationError, SerdeSupport, StructOptions, UntypedOptionBehavior, WarningKind,
+};
+
+fn generate(format: Format, source: &str, options: &StructOptions) -> String {
+    config_struct::generate_config_from_source(format, source, options).unwrap()
+}
+
+fn serde_options() -> StructOptions {
+    StructOptions {
+        serde_support: SerdeSupport::Yes,
+        ..StructOptions::default()
+    }
+}
+
+/// Check the code for a config with a server missing its optional
+/// `port` and `limits`.
+fn assert_absent_fields(code: &str) {
+    assert!(code.contains(
+        "    #[serde(skip_serializing_if = \"Option::is_none\", default)]\n    pub port: Option<i64>,"
+    ));
+    assert!(code.contains("    pub limits: Option<_Config__servers__limits>,"));
+    assert!(code.contains("port: Some(80),"));
+    assert!(code.contains("limits: Some(_Config__servers__limits {"));
+    assert!(code.contains("port: None,"));
+    assert!(code.contains("limits: None,"));
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_json_options() {
+    let source = r#"{
+        "nothing": null,
+        "ports": [80, null],
+        "servers": [
+            { "name": "a", "port": 80, "limits": { "max": 1 } },
+            { "name": "b" }
+        ]
+    }"#;
+
+    let code = generate(Format::Json, source, &serde_options());
+
+    assert_absent_fields(&code);
+    assert!(code.contains("    pub nothing: Option<()>,"));
+    assert!(code.contains("    pub ports: Cow<'static, [Option<i64>]>,"));
+    assert!(code.contains("ports: Cow::Borrowed(&[Some(80), None]),"));
+    // A null is kept when serialized.
+    assert!(!code
+        .contains("#[serde(skip_serializing_if = \"Option::is_none\", default)]\n    pub nothing"));
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_yaml_options() {
+    let source = "
+nothing: ~
+ports: [80, null]
+servers:
+  - name: a
+    port: 80
+    limits:
+      max: 1
+  - name: b
+";
+
+    let code = generate(Format::Yaml, source, &serde_options());
+
+    assert_absent_fields(&code);
+    assert!(code.contains("    pub nothing: Option<()>,"));
+    assert!(code.contains("ports: Cow::Borrowed(&[Some(80), None]),"));
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_toml_options() {
+    let source = r#"
+[[servers]]
+name = "a"
+port = 80
+limits = { max = 1 }
+
+[[servers]]
+name = "b"
+"#;
+
+    let code = generate(Format::Toml, source, &serde_options());
+
+    assert_absent_fields(&code);
+}
+
+#[cfg(feature = "ron-parsing")]
+#[test]
+fn test_ron_options() {
+    let source = r#"(
+        nothing: None,
+        ports: [Some(80), None],
+        servers: [
+            (name: "a", port: Some(80), limits: Some((max: 1))),
+            (name: "b", port: None, limits: None),
+        ],
+    )"#;
+
+    let code = generate(Format::Ron, source, &serde_options());
+
+    // RON's `None`s are written out, so they are serialized too.
+    assert!(code.contains("    pub port: Option<i64>,"));
+    assert!(!code.contains("skip_serializing_if"));
+    assert!(code.contains("limits: Some(_Config__servers__limits {"));
+    assert!(code.contains("limits: None,"));
+    assert!(code.contains("ports: Cow::Borrowed(&[Some(80), None]),"));
+    assert!(code.contains("    pub nothing: Option<()>,"));
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_options_always_none_take_types_from_options() {
+    let source = r#"{ "nothing": null, "servers": [{ "backup": null }, { "backup": null }] }"#;
+
+    let output = config_struct::generate_config_from_source_with_diagnostics(
+        Format::Json,
+        source,
+        &StructOptions::default(),
+    )
+    .unwrap();
+    let warnings = output
+        .warnings
+        .iter()
+        .map(|warning| (warning.kind, warning.path.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        [
+            (WarningKind::UntypedOption, "nothing"),
+            (WarningKind::UntypedOption, "servers.backup"),
+        ]
+    );
+
+    let mut option_types = HashMap::new();
+    option_types.insert("servers.backup".to_owned(), "u16".to_owned());
+    let options = StructOptions {
+        option_types,
+        default_option_type: Some("bool".to_owned()),
+        ..StructOptions::default()
+    };
+    let code = generate(Format::Json, source, &options);
+
+    assert!(code.contains("    pub nothing: Option<bool>,"));
+    assert!(code.contains("    pub backup: Option<u16>,"));
+    assert!(code.contains("nothing: None,"));
+
+    let options = StructOptions {
+        untyped_options: UntypedOptionBehavior::Error,
+        ..StructOptions::default()
+    };
+    match config_struct::generate_config_from_source(Format::Json, source, &options) {
+        Err(GenerationError::UntypedOption(path)) => assert_eq!(path, "nothing"),
+        other => panic!("Expected an untyped option error, found {:?}", other),
+    }
+}
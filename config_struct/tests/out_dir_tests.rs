@@ -0,0 +1,100 @@
+#![cfg(feature = "toml-parsing")]
+
+use std::{fs, path::PathBuf, process::Command};
+
+use config_struct::{Error, GenerationError, StructOptions};
+
+#[test]
+fn test_out_dir_is_required() {
+    // Cargo only sets `OUT_DIR` for the tests of packages with a build script.
+    if std::env::var_os("OUT_DIR").is_some() {
+        return;
+    }
+
+    match config_struct::create_config_in_out_dir(
+        "config.toml",
+        "config",
+        &StructOptions::default(),
+    ) {
+        Err(Error::Generation(GenerationError::MissingOutDir)) => (),
+        other => panic!("Expected missing OUT_DIR error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_generated_code_is_included() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("out_dir_crate");
+    fs::create_dir_all(dir.join("src")).unwrap();
+
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            r#"[package]
+name = "out_dir_crate"
+version = "0.1.0"
+edition = "2018"
+
+[workspace]
+
+[build-dependencies]
+config_struct = {{ path = {:?} }}
+"#,
+            manifest_dir.display().to_string()
+        ),
+    )
+    .unwrap();
+    fs::write(dir.join("config.toml"), "name = \"out dir\"\nport = 8080\n").unwrap();
+    fs::write(
+        dir.join("build.rs"),
+        r#"fn main() {
+    let path = config_struct::create_config_in_out_dir(
+        "config.toml",
+        "config",
+        &config_struct::StructOptions::default(),
+    )
+    .unwrap();
+    assert!(path.ends_with("config.rs"));
+}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src/main.rs"),
+        r#"#[allow(dead_code)]
+mod config {
+    include!(concat!(env!("OUT_DIR"), "/config.rs"));
+}
+
+fn main() {
+    assert_eq!(config::CONFIG.name, "out dir");
+    assert_eq!(config::CONFIG.port, 8080);
+}
+"#,
+    )
+    .unwrap();
+
+    // Reuse the locked dependency versions, so the crate builds offline.
+    if let Ok(lockfile) = fs::read(manifest_dir.join("Cargo.lock")) {
+        fs::write(dir.join("Cargo.lock"), lockfile).unwrap();
+    }
+
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned());
+    let output = Command::new(cargo)
+        .args(["run", "--quiet", "--offline"])
+        .current_dir(&dir)
+        .env("CARGO_TARGET_DIR", dir.join("target"))
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "Building the crate failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let build_output = fs::read_dir(dir.join("target/debug/build"))
+        .unwrap()
+        .filter_map(|entry| fs::read_to_string(entry.unwrap().path().join("output")).ok())
+        .collect::<String>();
+    assert!(build_output.contains("cargo:rerun-if-changed=config.toml"));
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, GenerationError, OptionsError, StringType, StructOptions};
+
+const SOURCE: &str = r#"
+name = "app"
+tags = ["a", "b"]
+
+[server]
+port = 80
+
+[[plugins]]
+name = "x"
+"#;
+
+fn owned_options() -> StructOptions {
+    StructOptions {
+        generate_owned_variant: true,
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_owned_structs_are_generated() {
+    let options = StructOptions {
+        impl_default: true,
+        ..owned_options()
+    };
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(code.contains("pub struct ConfigOwned {"));
+    assert!(code.contains("pub name: String,"));
+    assert!(code.contains("pub tags: Vec<String>,"));
+    assert!(code.contains("pub server: _Config__serverOwned,"));
+    assert!(code.contains("pub plugins: Vec<_Config__pluginsOwned>,"));
+    assert!(code.contains("impl From<Config> for ConfigOwned {"));
+    assert!(code.contains("impl From<&ConfigOwned> for Config {"));
+    assert!(code.contains("impl From<_Config__server> for _Config__serverOwned {"));
+    assert!(code.contains("server: _Config__server::from(&value.server),"));
+    assert!(code.contains("impl Default for ConfigOwned {"));
+}
+
+#[test]
+fn test_owned_structs_have_public_fields() {
+    let options = StructOptions {
+        field_access: config_struct::FieldAccess::Getters,
+        ..owned_options()
+    };
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(code.contains("    name: Cow<'static, str>,"));
+    assert!(code.contains("    pub name: String,"));
+}
+
+#[test]
+fn test_owned_variant_requires_owned_strings() {
+    let options = StructOptions {
+        string_type: StringType::StaticStr,
+        ..owned_options()
+    };
+
+    match config_struct::generate_config_from_source(Format::Toml, SOURCE, &options) {
+        Err(GenerationError::StructOptions(OptionsError::OwnedVariantWithStaticStr)) => (),
+        other => panic!("Expected an options error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_owned_variant_requires_clone() {
+    let options = StructOptions {
+        derived_traits: vec!["Debug".to_owned()],
+        ..owned_options()
+    };
+
+    match config_struct::generate_config_from_source(Format::Toml, SOURCE, &options) {
+        Err(GenerationError::StructOptions(OptionsError::OwnedVariantWithoutClone)) => (),
+        other => panic!("Expected an options error, found {:?}", other),
+    }
+}
@@ -0,0 +1,158 @@
+#![cfg(feature = "toml-parsing")]
+
+use std::{path::Path, time::Instant};
+
+use config_struct::{Error, GenerationError, GenerationJob, StructOptions};
+
+mod common;
+
+use common::temp_dir;
+
+/// Write `count` small configs to `dir`, and a job generating each into
+/// a module of its own.
+fn small_configs(dir: &Path, count: usize, options: &StructOptions) -> Vec<GenerationJob> {
+    (0..count)
+        .map(|i| {
+            let source = dir.join(format!("config_{}.toml", i));
+            std::fs::write(
+                &source,
+                format!(
+                    "name = \"service {}\"\nreplicas = {}\nratio = {}.5\n\n[server]\nhost = \"localhost\"\nport = {}\n\n[[routes]]\npath = \"/{}\"\nweight = 1\n",
+                    i, i, i, 8000 + i, i
+                ),
+            )
+            .unwrap();
+
+            let options = StructOptions {
+                struct_name: format!("Config{}", i),
+                ..options.clone()
+            };
+            GenerationJob::new(source, dir.join(format!("config_{}.rs", i)), options)
+        })
+        .collect()
+}
+
+fn cached() -> StructOptions {
+    StructOptions {
+        cache_source_hash: true,
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_results_are_in_job_order() {
+    let dir = temp_dir("parallel_order");
+    let jobs = small_configs(&dir, 24, &StructOptions::default());
+
+    let reports = config_struct::create_configs_parallel(&jobs);
+
+    assert_eq!(reports.len(), jobs.len());
+    for (i, (job, report)) in jobs.iter().zip(reports).enumerate() {
+        let report = report.unwrap();
+        assert_eq!(report.destination, job.destination);
+        assert!(report.written);
+        assert!(report.code.contains(&format!("pub struct Config{} {{", i)));
+
+        let sequential = config_struct::generate_config(&job.source, &job.options).unwrap();
+        assert_eq!(report.code, sequential);
+        assert_eq!(
+            std::fs::read_to_string(&job.destination).unwrap(),
+            sequential
+        );
+    }
+}
+
+#[test]
+fn test_failed_jobs_dont_stop_others() {
+    let dir = temp_dir("parallel_failures");
+    let mut jobs = small_configs(&dir, 4, &StructOptions::default());
+    std::fs::write(&jobs[1].source, "broken = [1, \"two\"]\n").unwrap();
+    jobs[3].destination = jobs[0].destination.clone();
+
+    let reports = config_struct::create_configs_parallel(&jobs);
+
+    assert!(reports[0].is_ok());
+    assert!(matches!(reports[1], Err(Error::Generation(_))));
+    assert!(reports[2].is_ok());
+    match reports[3] {
+        Err(Error::Generation(GenerationError::DuplicateDestination(ref path))) => {
+            assert_eq!(Path::new(path), jobs[0].destination)
+        }
+        ref other => panic!("Expected a duplicate destination error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_unchanged_jobs_are_skipped() {
+    let dir = temp_dir("parallel_skipped");
+    let jobs = small_configs(&dir, 16, &cached());
+
+    let first = config_struct::create_configs_parallel(&jobs);
+    assert!(first.iter().all(|report| report.as_ref().unwrap().written));
+    assert!(dir.join("config_0.rs.hash").exists());
+
+    // Only the job whose config changed is generated again.
+    std::fs::write(&jobs[5].source, "name = \"changed\"\n").unwrap();
+    let second = config_struct::create_configs_parallel(&jobs);
+
+    for (i, (first, second)) in first.iter().zip(&second).enumerate() {
+        let (first, second) = (first.as_ref().unwrap(), second.as_ref().unwrap());
+        if i == 5 {
+            assert!(second.written);
+            assert!(second.code.contains("pub name: Cow<'static, str>,"));
+            assert!(!second.code.contains("replicas"));
+        } else {
+            assert!(!second.written, "Job {} was generated again", i);
+            assert_eq!(second.code, first.code);
+        }
+    }
+}
+
+/// Run with `cargo test --release --test parallel_tests -- --ignored
+/// --nocapture` to compare generating many small configs one at a time
+/// with generating them in parallel, and then again with nothing changed.
+#[test]
+#[ignore]
+fn bench_many_small_configs() {
+    let dir = temp_dir("parallel_bench");
+    let jobs = small_configs(&dir, 64, &cached());
+
+    let start = Instant::now();
+    for job in &jobs {
+        config_struct::create_config_ext(&job.source, &job.destination, &job.options).unwrap();
+    }
+    let sequential = start.elapsed();
+
+    // Without their destinations, the jobs can't be skipped.
+    for job in &jobs {
+        std::fs::remove_file(&job.destination).unwrap();
+    }
+    let start = Instant::now();
+    let reports = config_struct::create_configs_parallel(&jobs);
+    let parallel = start.elapsed();
+    assert!(reports
+        .iter()
+        .all(|report| report.as_ref().unwrap().written));
+
+    let start = Instant::now();
+    let reports = config_struct::create_configs_parallel(&jobs);
+    let unchanged = start.elapsed();
+    assert!(reports
+        .iter()
+        .all(|report| !report.as_ref().unwrap().written));
+
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    println!(
+        "{} configs on {} threads: {:?} sequential, {:?} parallel ({:.1}x), {:?} unchanged",
+        jobs.len(),
+        threads,
+        sequential,
+        parallel,
+        sequential.as_secs_f64() / parallel.as_secs_f64(),
+        unchanged
+    );
+    assert!(unchanged < parallel);
+    if threads > 1 {
+        assert!(parallel < sequential);
+    }
+}
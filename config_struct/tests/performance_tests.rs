@@ -0,0 +1,46 @@
+#![cfg(feature = "json-parsing")]
+
+use std::time::{Duration, Instant};
+
+use config_struct::{Format, StructOptions};
+
+/// A config with 10,000 array elements, each a small struct.
+fn large_config() -> String {
+    let items = (0..10_000)
+        .map(|i| {
+            format!(
+                r#"{{"id": {}, "name": "item {}", "weight": {}.5, "tags": [1, 2, 3]}}"#,
+                i, i, i
+            )
+        })
+        .collect::<Vec<_>>();
+    format!(r#"{{"items": [{}]}}"#, items.join(", "))
+}
+
+#[test]
+fn test_large_config() {
+    let source = large_config();
+
+    let start = Instant::now();
+    let code = config_struct::generate_config_from_source(
+        Format::Json,
+        &source,
+        &StructOptions::default(),
+    )
+    .unwrap();
+    let elapsed = start.elapsed();
+
+    assert!(code.contains("name: Cow::Borrowed(\"item 9999\"),"));
+    assert_eq!(
+        code.matches("tags: Cow::Borrowed(&[1, 2, 3]),").count(),
+        10_000
+    );
+
+    // Well under a second in release builds; the bound is generous so
+    // that unoptimized builds on slow machines pass too.
+    assert!(
+        elapsed < Duration::from_secs(10),
+        "Generation took {:?}",
+        elapsed
+    );
+}
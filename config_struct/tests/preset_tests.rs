@@ -0,0 +1,113 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{
+    DisplayStyle, DynamicLoading, Format, OptionsError, SerdeSupport, StructOptions,
+};
+
+const SOURCE: &str = r#"
+name = "app"
+tags = ["a", "b"]
+
+[server]
+host = "localhost"
+port = 8080
+"#;
+
+fn generate(options: &StructOptions) -> String {
+    config_struct::generate_config_from_source(Format::Toml, SOURCE, options).unwrap()
+}
+
+#[test]
+fn test_minimal_generates_only_structs_and_const() {
+    let code = generate(&StructOptions::minimal());
+
+    assert!(code.contains("#[derive(Clone)]"));
+    assert!(!code.contains("Debug"));
+    assert!(!code.contains("serde"));
+    assert!(!code.contains("fn load"));
+    assert!(!code.contains("pub mod keys"));
+    assert!(code.contains("pub const CONFIG: Config = Config {"));
+}
+
+#[test]
+fn test_serde_default_derives_serde_and_loads_in_debug() {
+    let dir = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("preset_serde");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    std::fs::write(&path, SOURCE).unwrap();
+
+    let code = config_struct::generate_config(&path, &StructOptions::serde_default()).unwrap();
+
+    assert!(code.contains("#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]"));
+    assert!(code.contains("#[cfg(debug_assertions)]"));
+    assert!(code.contains("pub fn load() -> Cow<'static, Self>"));
+    assert!(code.contains("::toml::from_str(&file_contents)"));
+}
+
+#[test]
+fn test_embedded_needs_no_cow_or_alloc() {
+    let code = generate(&StructOptions::embedded());
+
+    assert!(!code.contains("Cow"));
+    assert!(!code.contains("alloc"));
+    assert!(!code.contains("std::"));
+    assert!(!code.contains("fn load"));
+    assert!(!code.contains("serde"));
+    assert!(code.contains("pub name: &'static str,"));
+    assert!(code.contains("pub tags: &'static [&'static str],"));
+}
+
+#[test]
+fn test_presets_are_valid_for_every_format() {
+    for &format in Format::ENABLED {
+        StructOptions::minimal().validate_for(format).unwrap();
+        StructOptions::embedded().validate_for(format).unwrap();
+    }
+}
+
+#[test]
+fn test_validate_for_checks_the_options() {
+    let options = StructOptions {
+        struct_name: "not a name".to_owned(),
+        ..StructOptions::minimal()
+    };
+
+    match options.validate_for(Format::Toml) {
+        Err(OptionsError::InvalidStructName(name)) => assert_eq!(name, "not a name"),
+        other => panic!("Expected an invalid struct name, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "ini-parsing")]
+#[test]
+fn test_validate_for_rejects_runtime_serde_for_ini() {
+    let dynamic = StructOptions {
+        dynamic_loading: DynamicLoading::Always,
+        ..StructOptions::serde_default()
+    };
+    let display = StructOptions {
+        serde_support: SerdeSupport::Yes,
+        impl_display: DisplayStyle::SourceFormat,
+        ..StructOptions::default()
+    };
+    let tests = StructOptions {
+        generate_tests: true,
+        dynamic_loading: DynamicLoading::Never,
+        ..StructOptions::serde_default()
+    };
+
+    for (options, expected) in &[
+        (dynamic, "dynamic_loading"),
+        (display, "impl_display"),
+        (tests, "generate_tests"),
+    ] {
+        options.validate_for(Format::Toml).unwrap();
+        match options.validate_for(Format::Ini) {
+            Err(error @ OptionsError::FormatWith(..)) => {
+                assert_eq!(error.option_names()[0], *expected);
+                assert!(error.to_string().contains("ini files"));
+            }
+            other => panic!("Expected a format error, found {:?}", other),
+        }
+    }
+}
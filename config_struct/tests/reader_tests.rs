@@ -0,0 +1,64 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Error, Format, StructOptions};
+
+mod common;
+
+use common::temp_dir;
+
+#[test]
+fn test_reader_matches_source() {
+    let source = "name = \"app\"\nports = [80, 443]\n";
+    let options = StructOptions::default();
+
+    let from_reader =
+        config_struct::generate_config_from_reader(Format::Toml, source.as_bytes(), &options)
+            .unwrap();
+    let from_source =
+        config_struct::generate_config_from_source(Format::Toml, source, &options).unwrap();
+
+    assert_eq!(from_reader, from_source);
+}
+
+#[test]
+fn test_create_from_reader() {
+    let dir = temp_dir("create_from_reader");
+    let destination = dir.join("config.rs");
+
+    config_struct::create_config_from_reader(
+        Format::Toml,
+        &b"number = 100\n"[..],
+        &destination,
+        &StructOptions::default(),
+    )
+    .unwrap();
+
+    let code = std::fs::read_to_string(&destination).unwrap();
+    assert!(code.contains("pub number: i64,"));
+}
+
+#[test]
+fn test_reader_errors() {
+    let options = StructOptions::default();
+
+    match config_struct::generate_config_from_reader(Format::Toml, &[0xff, 0xfe][..], &options) {
+        Err(Error::IO(error)) => assert_eq!(error.kind(), std::io::ErrorKind::InvalidData),
+        other => panic!("Expected an IO error, found {:?}", other),
+    }
+
+    match config_struct::generate_config_from_reader(Format::Toml, &b"number = "[..], &options) {
+        Err(Error::Generation(_)) => (),
+        other => panic!("Expected a generation error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_file_errors_name_the_file() {
+    let error = config_struct::generate_config("missing/config.toml", &StructOptions::default())
+        .unwrap_err();
+
+    assert!(matches!(error, Error::FileIO(ref path, _) if path == "missing/config.toml"));
+    assert!(error
+        .to_string()
+        .starts_with("IO error in `missing/config.toml`: "));
+}
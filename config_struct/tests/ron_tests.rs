@@ -0,0 +1,107 @@
+#![cfg(feature = "ron-parsing")]
+
+use config_struct::{Format, GenerationError, MixedArrayBehavior, StructOptions, WarningKind};
+
+fn tuple_options() -> StructOptions {
+    StructOptions {
+        mixed_arrays: MixedArrayBehavior::Tuple,
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_tuples_are_generated_as_tuples() {
+    let source = "(pair: (1, \"one\"), points: [(0, 0), (1, 2)], nested: ((1, 2), [3]))";
+    let code =
+        config_struct::generate_config_from_source(Format::Ron, source, &tuple_options()).unwrap();
+
+    assert!(code.contains("pub pair: (i64, Cow<'static, str>),"));
+    assert!(code.contains("pub points: Cow<'static, [(i64, i64)]>,"));
+    assert!(code.contains("pub nested: ((i64, i64), Cow<'static, [i64]>),"));
+}
+
+#[test]
+fn test_tuples_need_mixed_arrays() {
+    let source = "(\n    server: (\n        bind: (\"localhost\", 80),\n    ),\n)";
+
+    match config_struct::generate_config_from_source(Format::Ron, source, &StructOptions::default())
+    {
+        Err(GenerationError::RonTupleWithoutTuples { path, line, column }) => {
+            assert_eq!((path.as_str(), line, column), ("server.bind", 3, 15))
+        }
+        other => panic!("Expected tuple error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_names_are_ignored_with_a_warning() {
+    let source = "Config(\n    window: Window(size: Size(800, 600)),\n)";
+    let output = config_struct::generate_config_from_source_with_diagnostics(
+        Format::Ron,
+        source,
+        &tuple_options(),
+    )
+    .unwrap();
+
+    assert!(output.code.contains("pub size: (i64, i64),"));
+    let warnings = output
+        .warnings
+        .iter()
+        .map(|warning| (warning.kind, warning.path.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        [
+            (WarningKind::IgnoredRonName, ""),
+            (WarningKind::IgnoredRonName, "window"),
+            (WarningKind::IgnoredRonName, "window.size"),
+        ]
+    );
+    assert_eq!(
+        output.warnings[2].message,
+        "The name `Size` of the RON tuple under key `window.size` was ignored."
+    );
+}
+
+#[test]
+fn test_enum_variants_are_unsupported() {
+    let source = "(\n    logging: (level: Warn),\n)";
+
+    match config_struct::generate_config_from_source(Format::Ron, source, &StructOptions::default())
+    {
+        Err(GenerationError::UnsupportedRon {
+            construct,
+            path,
+            line,
+            column,
+        }) => {
+            assert_eq!(construct, "enum variant `Warn`");
+            assert_eq!((path.as_str(), line, column), ("logging.level", 2, 22));
+        }
+        other => panic!("Expected unsupported construct error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_map_keys_must_be_strings() {
+    let source = "(\n    scores: { \"alice\": 10, 2: 7 },\n)";
+
+    let error =
+        config_struct::generate_config_from_source(Format::Ron, source, &StructOptions::default())
+            .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Unsupported RON map with a key which isn't a string under key `scores` at line 2 column 28."
+    );
+    assert_eq!(error.path(), Some("scores"));
+}
+
+#[test]
+fn test_syntax_errors_are_left_to_the_parser() {
+    let source = "(name: \"app\", tuple: (1, 2)";
+
+    match config_struct::generate_config_from_source(Format::Ron, source, &tuple_options()) {
+        Err(GenerationError::ParseFailed(_)) => (),
+        other => panic!("Expected parse error, found {:?}", other),
+    }
+}
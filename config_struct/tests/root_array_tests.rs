@@ -0,0 +1,129 @@
+use config_struct::{Format, GenerationError, StructOptions};
+
+#[test]
+#[cfg(feature = "json-parsing")]
+fn test_json_root_array() {
+    let source = r#"[{ "name": "a", "port": 80 }, { "name": "b" }]"#;
+    let options = StructOptions {
+        struct_name: "Server".to_owned(),
+        ..StructOptions::default()
+    };
+
+    let code = config_struct::generate_config_from_source(Format::Json, source, &options).unwrap();
+
+    assert!(code.contains("pub struct Server {"));
+    assert!(code.contains("pub port: Option<i64>,"));
+    assert!(code.contains("pub const SERVER: &[Server] = &[Server {"));
+    assert!(!code.contains("pub struct Config"));
+}
+
+#[test]
+#[cfg(feature = "json-parsing")]
+fn test_empty_root_array() {
+    let code =
+        config_struct::generate_config_from_source(Format::Json, "[]", &StructOptions::default())
+            .unwrap();
+
+    assert!(code.contains("pub struct Config;"));
+    assert!(code.contains("pub const CONFIG: &[Config] = &[];"));
+}
+
+#[test]
+#[cfg(feature = "yaml-parsing")]
+fn test_yaml_root_array() {
+    let source = "
+- name: a
+  server:
+    port: 80
+- name: b
+  server:
+    port: 81
+";
+
+    let code =
+        config_struct::generate_config_from_source(Format::Yaml, source, &StructOptions::default())
+            .unwrap();
+
+    assert!(code.contains("pub server: _Config__server,"));
+    assert!(code.contains("pub const CONFIG: &[Config] = &["));
+}
+
+#[test]
+#[cfg(feature = "ron-parsing")]
+fn test_ron_root_array() {
+    let source = r#"[{ "name": "a" }, { "name": "b" }]"#;
+
+    let code =
+        config_struct::generate_config_from_source(Format::Ron, source, &StructOptions::default())
+            .unwrap();
+
+    assert!(code.contains("pub const CONFIG: &[Config] = &["));
+}
+
+#[test]
+#[cfg(feature = "json-parsing")]
+fn test_root_array_options_use_element_paths() {
+    let source = r#"[{ "level": "info" }, { "level": "warn" }]"#;
+    let options = StructOptions {
+        enum_fields: vec![(
+            "level".to_owned(),
+            vec!["info".to_owned(), "warn".to_owned()],
+        )]
+        .into_iter()
+        .collect(),
+        ..StructOptions::default()
+    };
+
+    let code = config_struct::generate_config_from_source(Format::Json, source, &options).unwrap();
+
+    assert!(code.contains("pub level: Level,"));
+}
+
+#[test]
+#[cfg(feature = "json-parsing")]
+fn test_mixed_root_array() {
+    let source = r#"[{ "name": "a" }, 1, "b"]"#;
+
+    match config_struct::generate_config_from_source(
+        Format::Json,
+        source,
+        &StructOptions::default(),
+    ) {
+        Err(GenerationError::NonTableRootElements(indices)) => assert_eq!(indices, vec![1, 2]),
+        other => panic!("Expected non-table elements error, found {:?}", other),
+    }
+}
+
+#[test]
+#[cfg(feature = "json-parsing")]
+fn test_root_array_unsupported_options() {
+    let options = StructOptions {
+        impl_default: true,
+        ..StructOptions::default()
+    };
+
+    match config_struct::generate_config_from_source(Format::Json, "[{}]", &options) {
+        Err(GenerationError::UnsupportedRootArray(option)) => assert_eq!(option, "impl_default"),
+        other => panic!("Expected unsupported root array error, found {:?}", other),
+    }
+}
+
+#[test]
+#[cfg(feature = "json-parsing")]
+fn test_root_array_const_fallback() {
+    use config_struct::{ConstFallback, MapType};
+
+    let source = r#"[{ "scores": { "a": 1 } }]"#;
+    let options = StructOptions {
+        map_paths: vec!["scores".to_owned()],
+        map_type: MapType::HashMap,
+        const_fallback: ConstFallback::RuntimeFn,
+        ..StructOptions::default()
+    };
+
+    let code = config_struct::generate_config_from_source(Format::Json, source, &options).unwrap();
+
+    assert!(code.contains("pub fn config() -> &'static [Config] {"));
+    assert!(code.contains("static CONFIG: ::std::sync::OnceLock<Vec<Config>>"));
+    assert!(code.contains(".get_or_init(|| vec![Config {"));
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, GenerationError, OptionsError, RootMode, StructOptions};
+
+fn generate(source: &str, options: &StructOptions) -> Result<String, GenerationError> {
+    config_struct::generate_config_from_source(Format::Toml, source, options)
+}
+
+fn per_table_options() -> StructOptions {
+    StructOptions {
+        root_mode: RootMode::ConstPerTopLevelTable,
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_const_per_table() {
+    let source = "
+[prod]
+port = 443
+server = { host = \"example.com\" }
+
+[dev]
+port = 8080
+server = { host = \"localhost\" }
+
+[stagingEu]
+port = 8443
+server = { host = \"eu.example.com\" }
+";
+    let code = generate(source, &per_table_options()).unwrap();
+
+    assert!(code.contains("pub struct Config {"));
+    assert!(code.contains("pub server: _Config__server,"));
+    assert!(!code.contains("pub dev:"));
+    assert!(code.contains("pub const PROD_CONFIG: Config = Config {"));
+    assert!(code.contains("pub const DEV_CONFIG: Config = Config {"));
+    assert!(code.contains("pub const STAGING_EU_CONFIG: Config = Config {"));
+    assert!(code.contains("port: 8080,"));
+    assert_eq!(code.matches("pub struct").count(), 2);
+}
+
+#[test]
+fn test_const_name_is_a_suffix() {
+    let options = StructOptions {
+        struct_name: "Settings".to_owned(),
+        const_name: Some("ENV".to_owned()),
+        ..per_table_options()
+    };
+    let code = generate("[dev]\nport = 1\n\n[prod]\nport = 2\n", &options).unwrap();
+
+    assert!(code.contains("pub const DEV_ENV: Settings = Settings {"));
+    assert!(code.contains("pub const PROD_ENV: Settings = Settings {"));
+}
+
+#[test]
+fn test_top_level_scalars_are_errors() {
+    match generate("name = \"a\"\n\n[dev]\nport = 1\n", &per_table_options()) {
+        Err(GenerationError::NonTableTopLevelKey(key)) => assert_eq!(key, "name"),
+        other => panic!("Expected non-table key error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_tables_must_share_a_shape() {
+    let source = "
+[dev]
+server = { host = \"localhost\", port = 8080 }
+
+[prod]
+server = { host = \"example.com\" }
+";
+    match generate(source, &per_table_options()) {
+        Err(GenerationError::MismatchedTopLevelTables(path, first, other)) => {
+            assert_eq!(path, "server.port");
+            assert_eq!(first, "dev");
+            assert_eq!(other, "prod");
+        }
+        other => panic!("Expected mismatched tables error, found {:?}", other),
+    }
+
+    let source = "[dev]\nport = 8080\n\n[prod]\nport = \"443\"\n";
+    match generate(source, &per_table_options()) {
+        Err(GenerationError::MismatchedTopLevelTables(path, ..)) => assert_eq!(path, "port"),
+        other => panic!("Expected mismatched tables error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_single_root_options_are_rejected() {
+    let options = StructOptions {
+        impl_default: true,
+        ..per_table_options()
+    };
+
+    match generate("[dev]\nport = 1\n", &options) {
+        Err(GenerationError::StructOptions(OptionsError::RootModeWith(option))) => {
+            assert_eq!(option, "impl_default")
+        }
+        other => panic!("Expected an options error, found {:?}", other),
+    }
+}
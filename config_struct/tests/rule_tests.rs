@@ -0,0 +1,118 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, GenerationError, OptionsError, Rule, StructOptions};
+
+const SOURCE: &str = r#"
+name = ""
+log_level = "loud"
+ratio = 1.5
+
+[[servers]]
+host = "a"
+port = 80
+
+[[servers]]
+host = "b"
+port = 0
+"#;
+
+fn rule_options() -> StructOptions {
+    StructOptions {
+        validations: vec![
+            ("name".to_owned(), Rule::NonEmptyString),
+            (
+                "log_level".to_owned(),
+                Rule::OneOf(vec!["info".to_owned(), "warn".to_owned()]),
+            ),
+            ("ratio".to_owned(), Rule::FloatRange(0.0, 1.0)),
+            ("servers".to_owned(), Rule::NonEmptyArray),
+            ("servers.*.port".to_owned(), Rule::IntRange(1, 65535)),
+        ],
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_every_violation_is_reported() {
+    let error = config_struct::generate_config_from_source(Format::Toml, SOURCE, &rule_options())
+        .unwrap_err();
+
+    match error {
+        GenerationError::ValidationFailed(ref violations) => {
+            let paths = violations
+                .iter()
+                .map(|violation| violation.path.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(paths, ["log_level", "name", "ratio", "servers.1.port"]);
+            assert_eq!(violations[3].rule, Rule::IntRange(1, 65535));
+            assert_eq!(violations[3].value, "0");
+        }
+        ref other => panic!("Expected a validation error, found {:?}", other),
+    }
+
+    let diagnostic = error.to_diagnostic_string();
+    assert!(
+        diagnostic.contains("\n  - `servers.1.port` is 0, which is not an integer from 1 to 65535")
+    );
+    assert!(diagnostic.contains("\n  - `ratio` is 1.5, which is not a number from 0.0 to 1.0"));
+}
+
+#[test]
+fn test_valid_configs_are_generated() {
+    let source = "name = \"app\"\nlog_level = \"info\"\nratio = 1\nservers = []\n";
+    let options = StructOptions {
+        validations: rule_options()
+            .validations
+            .into_iter()
+            .filter(|(path, _)| path != "servers")
+            .collect(),
+        ..StructOptions::default()
+    };
+
+    config_struct::generate_config_from_source(Format::Toml, source, &options).unwrap();
+}
+
+#[test]
+fn test_impossible_rules_are_rejected() {
+    let options = StructOptions {
+        validations: vec![("port".to_owned(), Rule::IntRange(10, 1))],
+        ..StructOptions::default()
+    };
+
+    match config_struct::generate_config_from_source(Format::Toml, "port = 5", &options) {
+        Err(GenerationError::StructOptions(OptionsError::InvalidRule(path, _))) => {
+            assert_eq!(path, "port")
+        }
+        other => panic!("Expected an options error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_rules_with_nan_bounds_equal_themselves() {
+    let rule = Rule::FloatRange(f64::NAN, 1.0);
+
+    assert_eq!(rule, rule.clone());
+    assert_ne!(rule, Rule::FloatRange(0.0, 1.0));
+    assert_ne!(Rule::FloatRange(0.0, 1.0), Rule::FloatRange(-0.0, 1.0));
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_strings_are_matched_against_regexes() {
+    let options = StructOptions {
+        validations: vec![(
+            "hosts".to_owned(),
+            Rule::MatchesRegex("^[a-z]+\\.example\\.com$".to_owned()),
+        )],
+        ..StructOptions::default()
+    };
+    let source = "hosts = [\"a.example.com\", \"example.org\"]";
+
+    match config_struct::generate_config_from_source(Format::Toml, source, &options) {
+        Err(GenerationError::ValidationFailed(violations)) => {
+            assert_eq!(violations.len(), 1);
+            assert_eq!(violations[0].path, "hosts.1");
+        }
+        other => panic!("Expected a validation error, found {:?}", other),
+    }
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{DirOptions, Format, ModuleInput, StructOptions, WarningKind};
+
+mod common;
+
+use common::temp_dir;
+
+const SOURCE: &str = r#"
+name = "app"
+ports = [80, 443]
+
+[server]
+host = "localhost"
+"#;
+
+fn options() -> StructOptions {
+    StructOptions {
+        rustfmt_output: true,
+        ..StructOptions::default()
+    }
+}
+
+fn generate() -> config_struct::GenerationOutput {
+    config_struct::generate_config_from_source_with_diagnostics(Format::Toml, SOURCE, &options())
+        .unwrap()
+}
+
+/// Generate a module and a directory config from `SOURCE`, returning the
+/// kinds of their warnings.
+fn generate_from_files() -> Vec<WarningKind> {
+    let dir = temp_dir("rustfmt_files");
+    let source = dir.join("app.toml");
+    std::fs::write(&source, SOURCE).unwrap();
+
+    let module = config_struct::generate_config_module_with_diagnostics(
+        &[ModuleInput::new(&source, "App")],
+        &options(),
+    )
+    .unwrap();
+    let dir_options = DirOptions {
+        struct_options: options(),
+        ..DirOptions::default()
+    };
+    let dir_config =
+        config_struct::generate_config_from_dir_with_diagnostics(&dir, &dir_options).unwrap();
+
+    module
+        .warnings
+        .into_iter()
+        .chain(dir_config.warnings)
+        .map(|warning| warning.kind)
+        .collect()
+}
+
+// Both cases are in one test, since the second sets `RUSTFMT` for the
+// whole process.
+#[test]
+fn test_rustfmt_output() {
+    let formatted = generate();
+    assert!(formatted.warnings.is_empty(), "{:?}", formatted.warnings);
+    assert!(!formatted.code.contains("rustfmt_skip"));
+    assert!(formatted.code.contains("#![allow(dead_code)]"));
+    assert!(formatted.code.contains("pub struct Config {\n"));
+    assert!(generate_from_files().is_empty());
+
+    std::env::set_var("RUSTFMT", "config-struct-missing-rustfmt");
+    let unformatted = generate();
+    let file_warnings = generate_from_files();
+    std::env::remove_var("RUSTFMT");
+
+    let warnings = unformatted
+        .warnings
+        .iter()
+        .map(|warning| (warning.kind, warning.path.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(warnings, vec![(WarningKind::RustfmtFailed, "")]);
+    assert!(!unformatted.code.contains("rustfmt_skip"));
+    assert!(unformatted.code.contains("pub struct Config"));
+    assert_eq!(
+        file_warnings,
+        vec![WarningKind::RustfmtFailed, WarningKind::RustfmtFailed]
+    );
+}
@@ -0,0 +1,197 @@
+#![cfg(feature = "yaml-parsing")]
+
+use std::path::PathBuf;
+
+use config_struct::{
+    Error, Format, GenerationError, Mismatch, Schema, SchemaField, SchemaMismatch, StructOptions,
+};
+
+mod common;
+
+use common::temp_dir;
+
+const REFERENCE: &str = "
+name: Service
+port: 8080
+servers:
+  - host: a
+    weight: 1.0
+";
+
+fn write_configs(name: &str, candidate: &str) -> (PathBuf, PathBuf) {
+    let dir = temp_dir(&format!("schema_{}", name));
+
+    let candidate_path = dir.join("candidate.yaml");
+    let reference_path = dir.join("reference.yaml");
+    std::fs::write(&candidate_path, candidate).unwrap();
+    std::fs::write(&reference_path, REFERENCE).unwrap();
+
+    (candidate_path, reference_path)
+}
+
+fn validate(name: &str, candidate: &str) -> Result<(), SchemaMismatch> {
+    let (candidate, reference) = write_configs(name, candidate);
+    config_struct::validate_file_against(
+        Format::Yaml,
+        &candidate,
+        &reference,
+        &StructOptions::default(),
+    )
+}
+
+#[test]
+fn test_matching_config() {
+    let candidate = "
+name: Other service
+port: 9000
+servers:
+  - host: b
+    weight: 0.5
+  - host: c
+    weight: 2.0
+";
+
+    validate("matching", candidate).unwrap();
+}
+
+#[test]
+fn test_mismatched_config() {
+    let candidate = "
+name: Other service
+port: '9000'
+servers:
+  - host: b
+    weight: 1
+debug: true
+";
+
+    match validate("mismatched", candidate) {
+        Err(SchemaMismatch::Mismatches(mismatches)) => assert_eq!(
+            mismatches,
+            vec![
+                Mismatch::TypeConflict {
+                    path: "port".to_owned(),
+                    expected: "i64".to_owned(),
+                    found: "Cow<'static, str>".to_owned(),
+                },
+                Mismatch::TypeConflict {
+                    path: "servers.weight".to_owned(),
+                    expected: "f64".to_owned(),
+                    found: "i64".to_owned(),
+                },
+                Mismatch::ExtraKey("debug".to_owned()),
+            ]
+        ),
+        other => panic!("Expected mismatches, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_missing_keys() {
+    match validate("missing", "name: Other service\n") {
+        Err(SchemaMismatch::Mismatches(mismatches)) => assert_eq!(
+            mismatches,
+            vec![
+                Mismatch::MissingKey("port".to_owned()),
+                Mismatch::MissingKey("servers".to_owned()),
+            ]
+        ),
+        other => panic!("Expected mismatches, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_unreadable_config() {
+    let (_, reference) = write_configs("unreadable", "");
+    let result = config_struct::validate_file_against(
+        Format::Yaml,
+        "does/not/exist.yaml".as_ref(),
+        &reference,
+        &StructOptions::default(),
+    );
+
+    match result {
+        Err(SchemaMismatch::Error(Error::FileIO(path, _))) => {
+            assert_eq!(path, "does/not/exist.yaml")
+        }
+        other => panic!("Expected IO error, found {:?}", other),
+    }
+}
+
+fn infer(source: &str) -> Schema {
+    Schema::infer(Format::Yaml, source, &StructOptions::default()).unwrap()
+}
+
+fn generate_code(schema: &Schema, source: &str) -> Result<String, GenerationError> {
+    let options = StructOptions::default();
+    let values = config_struct::parse_config(Format::Yaml, source, &options).unwrap();
+    schema.generate_code(&values, &options)
+}
+
+#[test]
+fn test_schema_fields() {
+    let field = |path: &str, type_name: &str| SchemaField {
+        path: path.to_owned(),
+        type_name: type_name.to_owned(),
+    };
+
+    assert_eq!(
+        infer(REFERENCE).fields(),
+        vec![
+            field("name", "Cow<'static, str>"),
+            field("port", "i64"),
+            field("servers", "Cow<'static, [_Config__servers]>"),
+            field("servers.host", "Cow<'static, str>"),
+            field("servers.weight", "f64"),
+        ]
+    );
+}
+
+#[test]
+fn test_generate_code_matches_one_shot() {
+    let options = StructOptions::default();
+    let one_shot =
+        config_struct::generate_config_from_source(Format::Yaml, REFERENCE, &options).unwrap();
+
+    assert_eq!(
+        generate_code(&infer(REFERENCE), REFERENCE).unwrap(),
+        one_shot
+    );
+}
+
+#[test]
+fn test_merged_schemas() {
+    let schema = infer(REFERENCE)
+        .merge(&infer(
+            "name: Other\nport: 1\nservers:\n  - host: b\n    weight: 2.0\ndebug: true\n",
+        ))
+        .unwrap();
+
+    let code = generate_code(&schema, REFERENCE).unwrap();
+    assert!(code.contains("pub debug: Option<bool>,"));
+    assert!(code.contains("    debug: None,\n"));
+    assert!(code.contains("pub weight: f64,"));
+
+    let with_debug = format!("{}debug: false\n", REFERENCE);
+    let code = generate_code(&schema, &with_debug).unwrap();
+    assert!(code.contains("    debug: Some(false),\n"));
+}
+
+#[test]
+fn test_conflicting_schemas() {
+    match infer(REFERENCE).merge(&infer("port: '8080'\n")) {
+        Err(GenerationError::ConflictingSchemas(path)) => assert_eq!(path, "port"),
+        other => panic!("Expected a schema conflict, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_values_must_fit_schema() {
+    match generate_code(&infer(REFERENCE), "name: Other\nport: '80'\n") {
+        Err(GenerationError::NonConformingConfig(details)) => {
+            assert!(details.contains("Key `port` has type `Cow<'static, str>`"));
+            assert!(details.contains("Missing key `servers`."));
+        }
+        other => panic!("Expected a conformance error, found {:?}", other),
+    }
+}
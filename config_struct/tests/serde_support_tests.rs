@@ -0,0 +1,74 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{
+    FlattenStyle, Format, GenerationError, OptionsError, SerdeAttributes, SerdeSupport,
+    StructOptions,
+};
+
+const SOURCE: &str = r#"
+name = "app"
+
+[server]
+port = 8080
+"#;
+
+fn generate(serde_support: SerdeSupport) -> String {
+    let options = StructOptions {
+        serde_support,
+        ..StructOptions::default()
+    };
+    config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap()
+}
+
+#[test]
+fn test_one_direction_is_derived() {
+    let code = generate(SerdeSupport::SerializeOnly);
+    assert!(code.contains("#[derive(Debug, Clone, serde::Serialize)]"));
+    assert!(!code.contains("Deserialize"));
+
+    let code = generate(SerdeSupport::DeserializeOnly);
+    assert!(code.contains("#[derive(Debug, Clone, serde::Deserialize)]"));
+    assert!(!code.contains("Serialize"));
+}
+
+#[test]
+fn test_manual_impls_replace_derives() {
+    let code = generate(SerdeSupport::ManualImpl);
+
+    assert!(code.contains("#[derive(Debug, Clone)]"));
+    assert!(!code.contains("serde::Serialize)]"));
+    assert!(code.contains("impl ::serde::Serialize for Config {"));
+    assert!(code.contains("impl<'de> ::serde::Deserialize<'de> for Config {"));
+    assert!(code.contains("impl<'de> ::serde::Deserialize<'de> for _Config__server {"));
+    assert!(code.contains("\"server\" => field_server = Some(map.next_value()?),"));
+}
+
+#[test]
+fn test_manual_impls_reject_derive_attributes() {
+    let attributes = StructOptions {
+        serde_support: SerdeSupport::ManualImpl,
+        serde_attributes: SerdeAttributes {
+            all_structs: vec!["deny_unknown_fields".to_owned()],
+            ..SerdeAttributes::default()
+        },
+        ..StructOptions::default()
+    };
+    let flattened = StructOptions {
+        serde_support: SerdeSupport::ManualImpl,
+        flatten_paths: vec!["server".to_owned()],
+        flatten_style: FlattenStyle::SerdeFlatten,
+        ..StructOptions::default()
+    };
+
+    for (options, option) in &[
+        (attributes, "serde_attributes"),
+        (flattened, "flatten_style"),
+    ] {
+        match config_struct::generate_config_from_source(Format::Toml, SOURCE, options) {
+            Err(GenerationError::StructOptions(error @ OptionsError::ManualImplWith(_))) => {
+                assert_eq!(error.option_names(), ["serde_support", *option])
+            }
+            other => panic!("Expected an options error, found {:?}", other),
+        }
+    }
+}
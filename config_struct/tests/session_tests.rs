@@ -0,0 +1,97 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{ConfigSession, FloatSize, Format, StructOptions};
+
+mod common;
+
+use common::temp_dir;
+
+fn named(struct_name: &str) -> StructOptions {
+    StructOptions {
+        struct_name: struct_name.to_owned(),
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_generate_repeatedly() {
+    let source = "ratio = 0.5\nplugins = []\n";
+    let mut session = ConfigSession::with_source(Format::Toml, source);
+
+    for options in &[named("ServerConfig"), named("ClientConfig")] {
+        assert_eq!(
+            session.generate(options).unwrap(),
+            config_struct::generate_config_from_source(Format::Toml, source, options).unwrap()
+        );
+    }
+
+    let options = StructOptions {
+        default_float_size: FloatSize::F32,
+        ..StructOptions::default()
+    };
+    assert!(session
+        .generate(&options)
+        .unwrap()
+        .contains("pub ratio: f32,"));
+    assert!(session
+        .generate(&StructOptions::default())
+        .unwrap()
+        .contains("pub ratio: f64,"));
+}
+
+#[test]
+fn test_open_file() {
+    let dir = temp_dir("session_open");
+    let source = dir.join("config.toml");
+    std::fs::write(&source, "number = 100\n").unwrap();
+
+    let mut session = ConfigSession::open(&source).unwrap();
+    let report = session
+        .create(dir.join("config.rs"), &named("Config"))
+        .unwrap();
+
+    assert!(report.written);
+    assert!(report.code.contains("pub number: i64,"));
+    assert!(!dir.join("config.rs.hash").exists());
+}
+
+#[test]
+fn test_cached_source_hash() {
+    let dir = temp_dir("session_hash");
+    let destination = dir.join("config.rs");
+    let options = StructOptions {
+        cache_source_hash: true,
+        ..StructOptions::default()
+    };
+    let mut session = ConfigSession::with_source(Format::Toml, "plugins = []\n");
+
+    let report = session.create(&destination, &options).unwrap();
+    assert!(report.written);
+    assert_eq!(report.warnings.len(), 1);
+    assert!(dir.join("config.rs.hash").exists());
+
+    // Nothing is generated, so there's no warning about the empty array.
+    let report = ConfigSession::with_source(Format::Toml, "plugins = []\n")
+        .create(&destination, &options)
+        .unwrap();
+    assert!(!report.written);
+    assert!(report.warnings.is_empty());
+    assert_eq!(report.code, std::fs::read_to_string(&destination).unwrap());
+
+    // Changed options, configs and destinations are all regenerated.
+    let report = session.create(&destination, &named("Other")).unwrap();
+    assert!(report.written);
+    let report = session.create(&destination, &options).unwrap();
+    assert!(report.written);
+
+    let report = ConfigSession::with_source(Format::Toml, "plugins = [1]\n")
+        .create(&destination, &options)
+        .unwrap();
+    assert!(report.written);
+
+    std::fs::write(&destination, "// Edited.\n").unwrap();
+    let report = ConfigSession::with_source(Format::Toml, "plugins = [1]\n")
+        .create(&destination, &options)
+        .unwrap();
+    assert!(report.written);
+}
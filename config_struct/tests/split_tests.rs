@@ -0,0 +1,91 @@
+#![cfg(feature = "toml-parsing")]
+
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use config_struct::StructOptions;
+
+fn modified(path: &PathBuf) -> SystemTime {
+    fs::metadata(path).unwrap().modified().unwrap()
+}
+
+fn set_modified(path: &PathBuf, time: SystemTime) {
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(time)
+        .unwrap();
+}
+
+#[test]
+fn test_types_and_values_are_written_separately() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("split");
+    fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("config.toml");
+    let types = dir.join("types.rs");
+    let values = dir.join("values.rs");
+    let options = StructOptions {
+        generated_by_comment: true,
+        ..StructOptions::default()
+    };
+
+    fs::write(&config, "name = \"a\"\n[server]\nport = 80\n").unwrap();
+    config_struct::create_config_split(&config, &types, &values, &options).unwrap();
+
+    let types_code = fs::read_to_string(&types).unwrap();
+    let values_code = fs::read_to_string(&values).unwrap();
+    assert!(types_code.contains("pub struct Config {"));
+    assert!(types_code.contains("pub struct _Config__server {"));
+    assert!(!types_code.contains("pub const CONFIG"));
+    assert!(!values_code.contains("pub struct"));
+    assert!(values_code.contains("use super::*;"));
+    assert!(values_code.contains("pub const CONFIG: Config = Config {"));
+    assert!(values_code.contains("port: 80,"));
+
+    let header = "// Generated by config_struct";
+    assert!(types_code.starts_with(header));
+    assert!(values_code.starts_with(header));
+
+    // Only the values change with a new port, so the types are left alone.
+    let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+    set_modified(&types, old);
+    set_modified(&values, old);
+    fs::write(&config, "name = \"a\"\n[server]\nport = 8080\n").unwrap();
+    config_struct::create_config_split(&config, &types, &values, &options).unwrap();
+
+    assert_eq!(modified(&types), old);
+    assert_ne!(modified(&values), old);
+    assert!(fs::read_to_string(&values).unwrap().contains("port: 8080,"));
+}
+
+#[test]
+fn test_values_import_is_configurable() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("split_import");
+    fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("config.toml");
+    let values = dir.join("values.rs");
+    fs::write(&config, "name = \"a\"\n").unwrap();
+
+    let options = StructOptions {
+        values_import: "use crate::config::types::*;".to_owned(),
+        ..StructOptions::default()
+    };
+    config_struct::create_config_split(&config, dir.join("types.rs"), &values, &options).unwrap();
+    let values_code = fs::read_to_string(&values).unwrap();
+    assert!(values_code.contains("use crate::config::types::*;"));
+    assert!(!values_code.contains("use super::*;"));
+
+    // Values included alongside the types share their imports.
+    let options = StructOptions {
+        values_import: String::new(),
+        ..StructOptions::default()
+    };
+    config_struct::create_config_split(&config, dir.join("types.rs"), &values, &options).unwrap();
+    let values_code = fs::read_to_string(&values).unwrap();
+    assert!(!values_code.contains("use "));
+    assert!(values_code.contains("pub const CONFIG: Config = Config {"));
+}
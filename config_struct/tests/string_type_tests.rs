@@ -0,0 +1,91 @@
+use config_struct::{DynamicLoading, Format, SerdeSupport, StringType, StructOptions};
+
+const SOURCE: &str = r#"
+name = "Config name"
+words = ["one", "two"]
+"#;
+
+#[test]
+fn test_cow_strings() {
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, SOURCE, &StructOptions::default())
+            .unwrap();
+
+    assert!(code.contains("use std::borrow::Cow;"));
+    assert!(code.contains("pub name: Cow<'static, str>,"));
+    assert!(code.contains("pub words: Cow<'static, [Cow<'static, str>]>,"));
+    assert!(code.contains(r#"name: Cow::Borrowed("Config name"),"#));
+}
+
+#[test]
+fn test_static_strs() {
+    let options = StructOptions {
+        string_type: StringType::StaticStr,
+        ..StructOptions::default()
+    };
+
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(!code.contains("Cow"));
+    assert!(code.contains("pub name: &'static str,"));
+    assert!(code.contains("pub words: &'static [&'static str],"));
+    assert!(code.contains(r#"name: "Config name","#));
+    assert!(code.contains(r#"words: &["one", "two"],"#));
+}
+
+#[test]
+fn test_static_strs_mentioning_cow() {
+    let options = StructOptions {
+        string_type: StringType::StaticStr,
+        ..StructOptions::default()
+    };
+    let source = "note = \"use Cow::Borrowed or Cow<'static, str>\"\n";
+
+    let code = config_struct::generate_config_from_source(Format::Toml, source, &options).unwrap();
+
+    assert!(!code.contains("use std::borrow::Cow;"), "{}", code);
+    assert!(code.contains("pub note: &'static str,"));
+}
+
+#[test]
+fn test_owned_strings() {
+    let options = StructOptions {
+        string_type: StringType::String,
+        generate_const: false,
+        impl_default: true,
+        ..StructOptions::default()
+    };
+
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(!code.contains("Cow"));
+    assert!(code.contains("pub name: String,"));
+    assert!(code.contains("pub words: Vec<String>,"));
+    assert!(code.contains(r#"name: String::from("Config name"),"#));
+    assert!(code.contains(r#"words: vec![String::from("one"), String::from("two")],"#));
+}
+
+#[test]
+fn test_invalid_string_type_combinations() {
+    let static_dynamic = StructOptions {
+        string_type: StringType::StaticStr,
+        generate_load_fns: true,
+        dynamic_loading: DynamicLoading::Always,
+        ..StructOptions::default()
+    };
+
+    let static_deserialize = StructOptions {
+        string_type: StringType::StaticStr,
+        serde_support: SerdeSupport::Yes,
+        ..StructOptions::default()
+    };
+
+    let owned_const = StructOptions {
+        string_type: StringType::String,
+        ..StructOptions::default()
+    };
+
+    for options in &[static_dynamic, static_deserialize, owned_const] {
+        assert!(config_struct::generate_config_from_source(Format::Toml, SOURCE, options).is_err());
+    }
+}
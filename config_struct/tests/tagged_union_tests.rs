@@ -0,0 +1,192 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{
+    Format, GenerationError, OptionsError, SerdeSupport, StructOptions, TaggedUnionSpec,
+    WarningKind,
+};
+
+fn options(path: &str) -> StructOptions {
+    StructOptions {
+        tagged_unions: vec![TaggedUnionSpec::new(path, "kind")
+            .variant("s3", "S3")
+            .variant("local", "Local")],
+        ..StructOptions::default()
+    }
+}
+
+fn generate(source: &str, options: &StructOptions) -> String {
+    config_struct::generate_config_from_source(Format::Toml, source, options)
+        .unwrap_or_else(|error| panic!("Failed to generate config: {}", error))
+}
+
+#[test]
+fn test_tagged_union_field() {
+    let source = r#"
+[storage.backend]
+kind = "s3"
+bucket = "logs"
+
+[storage.backend.credentials]
+key = "abc"
+"#;
+    let output = config_struct::generate_config_from_source_with_diagnostics(
+        Format::Toml,
+        source,
+        &options("storage.backend"),
+    )
+    .unwrap();
+    let code = output.code;
+
+    assert!(code.contains("    pub backend: Backend,\n"), "{}", code);
+    assert!(
+        code.contains(
+            "pub enum Backend {
+    S3 {
+        bucket: Cow<'static, str>,
+        credentials: _Config__storage__backend__credentials,
+    },
+    Local {},
+}"
+        ),
+        "{}",
+        code
+    );
+    assert!(
+        code.contains("pub struct _Config__storage__backend__credentials {"),
+        "{}",
+        code
+    );
+    assert!(!code.contains("pub struct _Config__storage__backend {"));
+    assert!(
+        code.contains(
+            "        backend: Backend::S3 {
+            bucket: Cow::Borrowed(\"logs\"),
+            credentials: _Config__storage__backend__credentials {"
+        ),
+        "{}",
+        code
+    );
+    assert!(!code.contains("kind"), "{}", code);
+
+    let warnings = output
+        .warnings
+        .iter()
+        .map(|warning| (warning.kind, warning.path.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        vec![(WarningKind::EmptyVariant, "storage.backend")]
+    );
+}
+
+#[test]
+fn test_arrays_of_variants() {
+    let source = r#"
+[[backends]]
+kind = "s3"
+bucket = "logs"
+region = "eu-west-1"
+
+[[backends]]
+kind = "local"
+path = "/var/log"
+
+[[backends]]
+kind = "s3"
+bucket = "archive"
+"#;
+    let code = generate(source, &options("backends"));
+
+    assert!(
+        code.contains("    pub backends: Cow<'static, [Backends]>,\n"),
+        "{}",
+        code
+    );
+    assert!(
+        code.contains(
+            "    S3 {
+        bucket: Cow<'static, str>,
+        region: Option<Cow<'static, str>>,
+    },
+    Local {
+        path: Cow<'static, str>,
+    },"
+        ),
+        "{}",
+        code
+    );
+    assert!(code.contains("Backends::Local {"), "{}", code);
+    assert!(code.contains("region: None,"), "{}", code);
+}
+
+#[test]
+fn test_serde_attributes() {
+    let source = "[backend]\nkind = \"gcs\"\nbucket = \"logs\"\n";
+    let options = StructOptions {
+        serde_support: SerdeSupport::Yes,
+        tagged_unions: vec![TaggedUnionSpec::new("backend", "kind")
+            .variant("s3", "S3")
+            .variant("gcs", "GoogleCloud")],
+        ..StructOptions::default()
+    };
+
+    let code = generate(source, &options);
+
+    assert!(
+        code.contains(
+            "#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = \"kind\", rename_all = \"lowercase\")]
+pub enum Backend {
+    S3 {},
+    #[serde(rename = \"gcs\")]
+    GoogleCloud {"
+        ),
+        "{}",
+        code
+    );
+}
+
+#[test]
+fn test_unknown_tags() {
+    let source = "[backend]\nkind = \"ftp\"\nhost = \"example.com\"\n";
+
+    match config_struct::generate_config_from_source(Format::Toml, source, &options("backend")) {
+        Err(error @ GenerationError::UnknownTag(..)) => {
+            assert_eq!(error.path(), Some("backend"));
+            assert_eq!(
+                error.to_string(),
+                "Table `backend` has the tag `ftp`, which is not one of the known tags: [\"s3\", \"local\"]."
+            );
+        }
+        other => panic!("Expected an unknown tag error, found {:?}", other),
+    }
+
+    let source = "[backend]\nbucket = \"logs\"\n";
+    match config_struct::generate_config_from_source(Format::Toml, source, &options("backend")) {
+        Err(GenerationError::MissingTag(path, tag)) => {
+            assert_eq!((&*path, &*tag), ("backend", "kind"))
+        }
+        other => panic!("Expected a missing tag error, found {:?}", other),
+    }
+
+    let source = "backend = \"s3\"\n";
+    match config_struct::generate_config_from_source(Format::Toml, source, &options("backend")) {
+        Err(GenerationError::InvalidTaggedUnionField(path)) => assert_eq!(path, "backend"),
+        other => panic!("Expected an invalid field error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_tagged_unions_need_derived_serde() {
+    let options = StructOptions {
+        serde_support: SerdeSupport::ManualImpl,
+        ..options("backend")
+    };
+
+    match config_struct::generate_config_from_source(Format::Toml, "", &options) {
+        Err(GenerationError::StructOptions(OptionsError::ManualImplWith(option))) => {
+            assert_eq!(option, "tagged_unions")
+        }
+        other => panic!("Expected an options error, found {:?}", other),
+    }
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "toml-parsing")]
+
+use std::path::PathBuf;
+
+use config_struct::{
+    DynamicLoading, Error, Format, GenerationError, OptionsError, SerdeSupport, StructOptions,
+};
+
+const SOURCE: &str = "name = \"app\"\nport = 80\n";
+
+fn generate(name: &str, options: &StructOptions) -> Result<String, Error> {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("generated_tests");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.toml", name));
+    std::fs::write(&path, SOURCE).unwrap();
+
+    config_struct::generate_config(&path, options)
+}
+
+fn options() -> StructOptions {
+    StructOptions {
+        generate_tests: true,
+        ..StructOptions::serde_default()
+    }
+}
+
+#[test]
+fn test_tests_are_generated() {
+    let code = generate("tested", &options()).unwrap();
+
+    assert!(code.contains("#[cfg(test)]\nmod generated_tests {"));
+    assert!(
+        code.contains("#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]")
+    );
+    assert!(code.contains("fn round_trips()"));
+    assert!(code.contains("fn matches_config_file()"));
+    assert!(code.contains("generated_tests/tested.toml\");"));
+}
+
+#[test]
+fn test_static_configs_only_round_trip() {
+    let options = StructOptions {
+        dynamic_loading: DynamicLoading::Never,
+        ..options()
+    };
+    let code = generate("static", &options).unwrap();
+
+    assert!(code.contains("fn round_trips()"));
+    assert!(!code.contains("fn matches_config_file()"));
+    assert!(!code.contains("include_str!"));
+}
+
+#[test]
+fn test_tests_need_serde() {
+    let options = StructOptions {
+        serde_support: SerdeSupport::Mixed {
+            serialize: true,
+            deserialize: false,
+        },
+        generate_load_fns: false,
+        ..options()
+    };
+
+    match generate("no_serde", &options) {
+        Err(Error::Generation(GenerationError::StructOptions(OptionsError::TestsWithoutSerde))) => {
+        }
+        other => panic!("Expected options error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_tests_need_a_file() {
+    let options = StructOptions {
+        generate_load_fns: false,
+        ..options()
+    };
+    let result = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options);
+
+    match result {
+        Err(GenerationError::MissingTestFile) => {}
+        other => panic!("Expected missing file error, found {:?}", other),
+    }
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, GenerationError, OptionsError, StructOptions, ValueTransform};
+
+const SOURCE: &str = r#"
+timeout = "5s"
+retry_delays = ["100ms", "2m"]
+
+[cache]
+size = " 10MB "
+dir = "${CONFIG_STRUCT_TEST_CACHE_DIR}/cache"
+"#;
+
+fn transform_options() -> StructOptions {
+    StructOptions {
+        value_transforms: vec![
+            ("timeout".to_owned(), ValueTransform::ParseDurationMs),
+            ("retry_delays".to_owned(), ValueTransform::ParseDurationMs),
+            ("cache.*".to_owned(), ValueTransform::TrimWhitespace),
+            ("cache.size".to_owned(), ValueTransform::ParseByteSize),
+            ("cache.dir".to_owned(), ValueTransform::EnvSubstitute),
+        ],
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_transformed_values() {
+    std::env::set_var("CONFIG_STRUCT_TEST_CACHE_DIR", "/var/tmp");
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, SOURCE, &transform_options())
+            .unwrap();
+
+    assert!(code.contains("pub timeout: u64,"));
+    assert!(code.contains("timeout: 5000,"));
+    assert!(code.contains("pub retry_delays: Cow<'static, [u64]>,"));
+    assert!(code.contains("retry_delays: Cow::Borrowed(&[100, 120000]),"));
+    assert!(code.contains("size: 10000000,"));
+    assert!(code.contains(r#"dir: Cow::Borrowed("/var/tmp/cache"),"#));
+}
+
+#[test]
+fn test_missing_env_vars_are_errors() {
+    let options = StructOptions {
+        value_transforms: vec![("dir".to_owned(), ValueTransform::EnvSubstitute)],
+        ..StructOptions::default()
+    };
+    let source = "dir = \"${CONFIG_STRUCT_TEST_UNSET_DIR}\"";
+
+    match config_struct::generate_config_from_source(Format::Toml, source, &options) {
+        Err(GenerationError::MissingEnvVar(path, name)) => {
+            assert_eq!(path, "dir");
+            assert_eq!(name, "CONFIG_STRUCT_TEST_UNSET_DIR");
+        }
+        other => panic!("Expected a missing variable error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_invalid_durations_are_errors() {
+    let options = StructOptions {
+        value_transforms: vec![("timeout".to_owned(), ValueTransform::ParseDurationMs)],
+        ..StructOptions::default()
+    };
+
+    match config_struct::generate_config_from_source(Format::Toml, "timeout = \"soon\"", &options) {
+        Err(GenerationError::InvalidDuration(path, value)) => {
+            assert_eq!(path, "timeout");
+            assert_eq!(value, "soon");
+        }
+        other => panic!("Expected an invalid duration error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_transforms_reject_dynamic_loading() {
+    let options = StructOptions {
+        generate_load_fns: true,
+        ..transform_options()
+    };
+
+    match config_struct::generate_config_from_source(Format::Toml, SOURCE, &options) {
+        Err(GenerationError::StructOptions(OptionsError::ValueTransformsWithDynamicLoading)) => (),
+        other => panic!("Expected an options error, found {:?}", other),
+    }
+}
@@ -0,0 +1,98 @@
+use config_struct::{Format, GenerationError, StructOptions};
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_json_value_matches_source() {
+    let source = r#"{ "name": "app", "ports": [80, 443], "server": { "ratio": 0.5 } }"#;
+    let options = StructOptions::default();
+
+    let value: serde_json::Value = serde_json::from_str(source).unwrap();
+    let from_value = config_struct::generate_config_from_json_value(&value, &options).unwrap();
+    let from_source =
+        config_struct::generate_config_from_source(Format::Json, source, &options).unwrap();
+
+    assert_eq!(from_value, from_source);
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_json_value_roots() {
+    let options = StructOptions::default();
+
+    let value = serde_json::json!([{ "name": "a" }, { "name": "b" }]);
+    let code = config_struct::generate_config_from_json_value(&value, &options).unwrap();
+    assert!(code.contains("pub const CONFIG: &[Config]"));
+
+    let from_value =
+        config_struct::generate_config_from_json_value(&serde_json::json!(5), &options);
+    let from_source = config_struct::generate_config_from_source(Format::Json, "5", &options);
+    match (from_value, from_source) {
+        (Err(value_error @ GenerationError::ParseFailed(_)), Err(source_error)) => {
+            assert_eq!(value_error.to_string(), source_error.to_string());
+        }
+        other => panic!("Expected matching parse errors, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "toml-parsing")]
+#[test]
+fn test_toml_value_matches_source() {
+    let source = "name = \"app\"\nreleased = 1979-05-27T07:32:00Z\n\n[server]\nratio = 0.5\n";
+    let options = StructOptions::default();
+
+    let value: toml::Value = toml::from_str(source).unwrap();
+    let from_value = config_struct::generate_config_from_toml_value(&value, &options).unwrap();
+
+    // Spans only exist in the source, so compare with a parsed struct.
+    let config = config_struct::parse_config(Format::Toml, source, &options).unwrap();
+    let from_struct = config_struct::generate_config_from_struct(&config, &options).unwrap();
+
+    assert_eq!(from_value, from_struct);
+
+    match config_struct::generate_config_from_toml_value(&toml::Value::Integer(5), &options) {
+        Err(GenerationError::ParseFailed(error)) => {
+            assert!(error.to_string().contains("expected a map"), "{}", error)
+        }
+        other => panic!("Expected a parse error, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "yaml-parsing")]
+#[test]
+fn test_yaml_value_matches_source() {
+    let source = "defaults: &defaults\n  retries: 3\nserver:\n  <<: *defaults\n  name: app\n";
+    let options = StructOptions::default();
+
+    let value: serde_yaml::Value = serde_yaml::from_str(source).unwrap();
+    let from_value = config_struct::generate_config_from_yaml_value(&value, &options).unwrap();
+    let from_source =
+        config_struct::generate_config_from_source(Format::Yaml, source, &options).unwrap();
+
+    assert_eq!(from_value, from_source);
+    assert!(from_value.contains("retries: 3,"));
+
+    let from_value =
+        config_struct::generate_config_from_yaml_value(&serde_yaml::Value::Bool(true), &options);
+    let from_source = config_struct::generate_config_from_source(Format::Yaml, "true", &options);
+    match (from_value, from_source) {
+        (Err(value_error @ GenerationError::ParseFailed(_)), Err(source_error)) => {
+            assert_eq!(value_error.to_string(), source_error.to_string());
+        }
+        other => panic!("Expected matching parse errors, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "json-parsing")]
+#[test]
+fn test_value_limits() {
+    let options = StructOptions {
+        max_depth: 2,
+        ..StructOptions::default()
+    };
+    let value = serde_json::json!({ "a": { "b": { "c": { "d": 1 } } } });
+
+    match config_struct::generate_config_from_json_value(&value, &options) {
+        Err(GenerationError::MaxDepthExceeded { path, .. }) => assert_eq!(path, "a.b.c"),
+        other => panic!("Expected a depth error, found {:?}", other),
+    }
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "toml-parsing")]
+
+use config_struct::{Format, GenerationError, StructOptions};
+
+const SOURCE: &str = r#"
+min_version = "1.4.0"
+tested = ["1.4.0-rc.1", "1.4.0"]
+"#;
+
+fn semver_options() -> StructOptions {
+    StructOptions {
+        semver_paths: vec!["min_version".to_owned(), "tested".to_owned()],
+        ..StructOptions::default()
+    }
+}
+
+#[test]
+fn test_versions_use_a_generated_struct() {
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &semver_options())
+        .unwrap();
+
+    assert!(code.contains("pub min_version: ConfigVersion,"));
+    assert!(code.contains("pub tested: Cow<'static, [ConfigVersion]>,"));
+    assert!(code.contains("pub struct ConfigVersion {"));
+    assert!(code.contains("impl ::core::str::FromStr for ConfigVersion {"));
+    assert!(code.contains(r#"pre: ::std::borrow::Cow::Borrowed("rc.1")"#));
+}
+
+#[test]
+fn test_version_struct_is_only_generated_when_used() {
+    let code =
+        config_struct::generate_config_from_source(Format::Toml, SOURCE, &StructOptions::default())
+            .unwrap();
+
+    assert!(!code.contains("ConfigVersion"));
+}
+
+#[test]
+fn test_invalid_versions_are_errors() {
+    let source = "min_version = \"1.4\"\ntested = []";
+
+    match config_struct::generate_config_from_source(Format::Toml, source, &semver_options()) {
+        Err(GenerationError::InvalidVersion(path, message)) => {
+            assert_eq!(path, "min_version");
+            assert!(message.contains("three numbers"));
+        }
+        other => panic!("Expected an invalid version error, found {:?}", other),
+    }
+}
+
+#[cfg(feature = "semver")]
+#[test]
+fn test_semver_versions() {
+    use config_struct::{ConstFallback, VersionType};
+
+    let options = StructOptions {
+        version_type: VersionType::Semver,
+        const_fallback: ConstFallback::Error,
+        ..semver_options()
+    };
+    match config_struct::generate_config_from_source(Format::Toml, SOURCE, &options) {
+        Err(GenerationError::NonConstField(path)) => assert_eq!(path, "tested"),
+        other => panic!("Expected a non-const field error, found {:?}", other),
+    }
+
+    let options = StructOptions {
+        const_fallback: ConstFallback::LazyStatic,
+        ..options
+    };
+    let code = config_struct::generate_config_from_source(Format::Toml, SOURCE, &options).unwrap();
+
+    assert!(code.contains("pub min_version: semver::Version,"));
+    assert!(code.contains("min_version: semver::Version::new(1, 4, 0),"));
+    assert!(code.contains(r#"semver::Version::parse("1.4.0-rc.1").unwrap()"#));
+    assert!(!code.contains("ConfigVersion"));
+}
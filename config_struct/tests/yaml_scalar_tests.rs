@@ -0,0 +1,48 @@
+#![cfg(feature = "yaml-parsing")]
+
+use config_struct::{Format, StructOptions, YamlScalarStyle};
+
+fn generate(source: &str, yaml_scalars: YamlScalarStyle) -> String {
+    let options = StructOptions {
+        yaml_scalars,
+        ..StructOptions::default()
+    };
+    config_struct::generate_config_from_source(Format::Yaml, source, &options).unwrap()
+}
+
+#[test]
+fn test_quoted_version_is_a_string() {
+    let source = "version: \"3.0\"\nrelease: 3.0\n";
+
+    for style in [
+        YamlScalarStyle::Yaml11,
+        YamlScalarStyle::CoreSchema,
+        YamlScalarStyle::StringsWhenQuotedAmbiguity,
+    ] {
+        let code = generate(source, style);
+        assert!(code.contains("pub version: Cow<'static, str>,"));
+        assert!(code.contains("version: Cow::Borrowed(\"3.0\"),"));
+        assert!(code.contains("pub release: f64,"));
+    }
+}
+
+#[test]
+fn test_core_schema_words_are_strings() {
+    let source = "threshold: inf\n";
+
+    let code = generate(source, YamlScalarStyle::Yaml11);
+    assert!(code.contains("pub threshold: f64,"));
+
+    let code = generate(source, YamlScalarStyle::CoreSchema);
+    assert!(code.contains("pub threshold: Cow<'static, str>,"));
+}
+
+#[test]
+fn test_unquoted_values_match_quoted_neighbours() {
+    let code = generate(
+        "versions: [\"1.0\", 1.1]\n",
+        YamlScalarStyle::StringsWhenQuotedAmbiguity,
+    );
+
+    assert!(code.contains("Cow::Borrowed(\"1.0\"), Cow::Borrowed(\"1.1\")"));
+}
@@ -0,0 +1,347 @@
+//! Macros which generate config structs in place, for projects where a
+//! build script would be overkill.
+//!
+//! ```toml
+//! [dependencies.config_struct_macros]
+//! version = "~0.3.0"
+//! features = ["toml-parsing"]
+//! ```
+//!
+//! Each macro runs the same pipeline as `config_struct::generate_config`
+//! when the crate is compiled, and expands to the structs and the const:
+//!
+//! ```rust,ignore
+//! mod config {
+//!     config_struct_macros::config_file!("config.toml", struct_name = "Settings");
+//! }
+//!
+//! assert_eq!(config::CONFIG.name, "Application");
+//! ```
+//!
+//! Paths are relative to the crate's `Cargo.toml`, and the config file is
+//! included with `include_str!`, so that changing it recompiles the crate.
+//! Since the expansion imports `Cow`, each macro is best given a module
+//! of its own.
+//!
+//! These macros live in their own crate because a procedural macro crate
+//! can't be exported by the library it depends on. The features enable
+//! the same formats as those of `config_struct`.
+//!
+//! The options which can be given after the config are:
+//!
+//! -   `struct_name = "Settings"`
+//! -   `const_name = "SETTINGS"`
+//! -   `serde = true`, to derive `Serialize` and `Deserialize`
+//!
+//! Any others are taken from `StructOptions::default()`.
+extern crate proc_macro;
+
+use std::path::PathBuf;
+
+use config_struct::{Format, HeaderStyle, SerdeSupport, StructOptions};
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Generate the structs and const for a config file.
+///
+/// The format of the config is detected from its extension.
+///
+/// ```rust,ignore
+/// config_struct_macros::config_file!("config.toml", serde = true);
+/// ```
+#[proc_macro]
+pub fn config_file(input: TokenStream) -> TokenStream {
+    expand_config_file(input).unwrap_or_else(MacroError::into_compile_error)
+}
+
+/// Generate the structs and const for a config given as a string, in the
+/// format named by the first argument.
+///
+/// ```rust,ignore
+/// config_struct_macros::config_source!(toml, "name = \"Application\"");
+/// ```
+#[proc_macro]
+pub fn config_source(input: TokenStream) -> TokenStream {
+    expand_config_source(input).unwrap_or_else(MacroError::into_compile_error)
+}
+
+fn expand_config_file(input: TokenStream) -> Result<TokenStream, MacroError> {
+    let mut arguments = split_arguments(input).into_iter();
+    let path = match arguments.next() {
+        Some(ref argument) => single_string(argument)?,
+        None => {
+            return Err(MacroError::at_call_site(
+                "Expected the path of a config file.",
+            ))
+        }
+    };
+    let options = parse_options(arguments)?;
+
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let path = manifest_dir.join(path);
+
+    let code = config_struct::generate_config(&path, &options)
+        .map_err(|error| MacroError::at_call_site(&error.to_string()))?;
+
+    let mut tokens = parse_code(&code)?;
+    tokens.extend(include_dependency(&path.to_string_lossy()));
+    Ok(tokens)
+}
+
+fn expand_config_source(input: TokenStream) -> Result<TokenStream, MacroError> {
+    let mut arguments = split_arguments(input).into_iter();
+    let format = match arguments.next() {
+        Some(ref argument) => match argument[..] {
+            [TokenTree::Ident(ref ident)] => ident
+                .to_string()
+                .parse::<Format>()
+                .map_err(|error| MacroError::new(&error.to_string(), ident.span()))?,
+            _ => return Err(MacroError::at(argument, "Expected a format, like `toml`.")),
+        },
+        None => return Err(MacroError::at_call_site("Expected a config format.")),
+    };
+    let source = match arguments.next() {
+        Some(ref argument) => single_string(argument)?,
+        None => return Err(MacroError::at_call_site("Expected the config source.")),
+    };
+    let options = parse_options(arguments)?;
+
+    let code = config_struct::generate_config_from_source(format, source, &options)
+        .map_err(|error| MacroError::at_call_site(&error.to_string()))?;
+
+    parse_code(&code)
+}
+
+/// Options for code which is expanded in place, updated by the
+/// `key = value` arguments.
+fn parse_options<I>(arguments: I) -> Result<StructOptions, MacroError>
+where
+    I: Iterator<Item = Vec<TokenTree>>,
+{
+    let mut options = StructOptions {
+        // Inner attributes can't be expanded in place.
+        header: HeaderStyle::Custom(String::new()),
+        emit_rerun_directives: false,
+        ..StructOptions::default()
+    };
+
+    for argument in arguments {
+        let (key, value) = match argument[..] {
+            [TokenTree::Ident(ref key), TokenTree::Punct(ref equals), ref value]
+                if equals.as_char() == '=' =>
+            {
+                (key, value)
+            }
+            _ => {
+                return Err(MacroError::at(
+                    &argument,
+                    "Expected an option like `key = value`.",
+                ))
+            }
+        };
+
+        match key.to_string().as_str() {
+            "struct_name" => options.struct_name = string_value(value)?,
+            "const_name" => options.const_name = Some(string_value(value)?),
+            "serde" => {
+                options.serde_support = if bool_value(value)? {
+                    SerdeSupport::Yes
+                } else {
+                    SerdeSupport::No
+                }
+            }
+            other => {
+                return Err(MacroError::new(
+                    &format!(
+                        "Unknown option `{}`. Expected `struct_name`, `const_name` or `serde`.",
+                        other
+                    ),
+                    key.span(),
+                ))
+            }
+        }
+    }
+
+    Ok(options)
+}
+
+/// The arguments of a macro, split on commas.
+fn split_arguments(input: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut arguments = vec![];
+    let mut argument = vec![];
+    for token in input {
+        match token {
+            TokenTree::Punct(ref punct) if punct.as_char() == ',' => {
+                arguments.push(std::mem::take(&mut argument));
+            }
+            token => argument.push(token),
+        }
+    }
+    if !argument.is_empty() {
+        arguments.push(argument);
+    }
+    arguments
+}
+
+fn single_string(argument: &[TokenTree]) -> Result<String, MacroError> {
+    match *argument {
+        [ref token] => string_value(token),
+        _ => Err(MacroError::at(argument, "Expected a string literal.")),
+    }
+}
+
+fn string_value(token: &TokenTree) -> Result<String, MacroError> {
+    let error = || MacroError::new("Expected a string literal.", token.span());
+    match *token {
+        TokenTree::Literal(ref literal) => unquote(&literal.to_string()).ok_or_else(error),
+        TokenTree::Group(ref group) if group.delimiter() == Delimiter::None => {
+            match group.stream().into_iter().collect::<Vec<_>>()[..] {
+                [ref token] => string_value(token),
+                _ => Err(error()),
+            }
+        }
+        _ => Err(error()),
+    }
+}
+
+fn bool_value(token: &TokenTree) -> Result<bool, MacroError> {
+    match *token {
+        TokenTree::Ident(ref ident) if ident.to_string() == "true" => Ok(true),
+        TokenTree::Ident(ref ident) if ident.to_string() == "false" => Ok(false),
+        _ => Err(MacroError::new("Expected `true` or `false`.", token.span())),
+    }
+}
+
+/// The value of a string literal, as written in source, or `None` if it
+/// isn't a string literal.
+fn unquote(literal: &str) -> Option<String> {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let quoted = raw.get(hashes..raw.len() - hashes)?;
+        return quoted
+            .strip_prefix('"')?
+            .strip_suffix('"')
+            .map(str::to_owned);
+    }
+
+    let quoted = literal.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        match chars.next()? {
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            '0' => value.push('\0'),
+            '\\' => value.push('\\'),
+            '"' => value.push('"'),
+            '\'' => value.push('\''),
+            'x' => {
+                let digits = [chars.next()?, chars.next()?].iter().collect::<String>();
+                value.push(u8::from_str_radix(&digits, 16).ok()? as char);
+            }
+            'u' => {
+                if chars.next()? != '{' {
+                    return None;
+                }
+                let digits = chars
+                    .by_ref()
+                    .take_while(|&c| c != '}')
+                    .filter(|&c| c != '_')
+                    .collect::<String>();
+                value.push(std::char::from_u32(u32::from_str_radix(&digits, 16).ok()?)?);
+            }
+            // An escaped newline skips the whitespace which follows it.
+            '\n' => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(value)
+}
+
+fn parse_code(code: &str) -> Result<TokenStream, MacroError> {
+    code.parse().map_err(|error| {
+        MacroError::at_call_site(&format!("Generated code failed to parse: {:?}", error))
+    })
+}
+
+/// `const _: &str = include_str!("path");`, so that the compiler tracks
+/// the config file.
+fn include_dependency(path: &str) -> TokenStream {
+    format!("const _: &str = include_str!({});", Literal::string(path))
+        .parse()
+        .expect("a string literal is valid in `include_str!`")
+}
+
+/// An error to report as a `compile_error!` at some span.
+struct MacroError {
+    message: String,
+    span: Span,
+}
+
+impl MacroError {
+    fn new(message: &str, span: Span) -> Self {
+        MacroError {
+            message: message.to_owned(),
+            span,
+        }
+    }
+
+    fn at_call_site(message: &str) -> Self {
+        MacroError::new(message, Span::call_site())
+    }
+
+    fn at(tokens: &[TokenTree], message: &str) -> Self {
+        let span = tokens
+            .first()
+            .map_or_else(Span::call_site, |token| token.span());
+        MacroError::new(message, span)
+    }
+
+    fn into_compile_error(self) -> TokenStream {
+        let mut message = Literal::string(&self.message);
+        message.set_span(self.span);
+
+        let mut tokens = vec![
+            TokenTree::Ident(Ident::new("compile_error", self.span)),
+            TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+            TokenTree::Group(Group::new(
+                Delimiter::Parenthesis,
+                TokenStream::from(TokenTree::Literal(message)),
+            )),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ];
+        for token in &mut tokens {
+            token.set_span(self.span);
+        }
+        tokens.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_are_unquoted() {
+        assert_eq!(unquote(r#""config.toml""#).unwrap(), "config.toml");
+        assert_eq!(
+            unquote(r#""a\n\"b\"\x41\u{1F600}""#).unwrap(),
+            "a\n\"b\"A\u{1F600}"
+        );
+        assert_eq!(unquote("\"a \\\n    b\"").unwrap(), "a b");
+        assert_eq!(unquote(r###"r#"say "hi""#"###).unwrap(), r#"say "hi""#);
+        assert!(unquote("12").is_none());
+        assert!(unquote("b\"bytes\"").is_none());
+    }
+}
@@ -1,12 +1,14 @@
 fn main() {
-    use config_struct::{DynamicLoading, StructOptions};
+    use config_struct::{
+        ArrayStyle, ConstFallback, DateTimeType, DirOptions, DisplayStyle, DynamicLoading,
+        DynamicPath, EmptyArrayBehavior, EmptyRootStyle, EnvOptions, FieldAccess, FieldOrder,
+        FlattenStyle, HeaderStyle, IncludeOptions, IntSize, IntoConversion, KeySanitization,
+        MapType, MissingFields, MixedArrayBehavior, ModuleInput, NestedNaming, RootMode,
+        SerdeAttributes, SerdeSupport, StringType, StructOptions, TaggedUnionSpec,
+    };
 
     std::fs::create_dir_all("src/config").expect("Failed to create config dir.");
 
-    println!("cargo:rerun-if-changed=config.json");
-    println!("cargo:rerun-if-changed=config.ron");
-    println!("cargo:rerun-if-changed=config.toml");
-    println!("cargo:rerun-if-changed=config.yaml");
     println!("cargo:rerun-if-changed=tests/atlernate_config.json");
     println!("cargo:rerun-if-changed=tests/example_config.json");
 
@@ -21,11 +23,354 @@ fn main() {
         "tests/temp/example_config.json",
     )
     .expect("Failed to copy example_config.json to temp");
+    std::fs::create_dir_all("tests/temp/spaced dir").expect("Failed to create spaced temp dir.");
+    std::fs::copy(
+        "tests/example_config.json",
+        "tests/temp/spaced dir/café config.json",
+    )
+    .expect("Failed to copy example_config.json to spaced temp dir");
 
     config_struct::create_config(
         "config.json",
         "src/config/json.rs",
-        &StructOptions::serde_default(),
+        &StructOptions {
+            default_int_size: IntSize::Auto,
+            impl_default: true,
+            serde_attributes: SerdeAttributes {
+                all_structs: vec!["deny_unknown_fields".to_owned()],
+                by_struct: vec![("Config".to_owned(), vec!["default".to_owned()])]
+                    .into_iter()
+                    .collect(),
+            },
+            generate_tests: true,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "escapes.json",
+        "src/config/escapes.rs",
+        &StructOptions {
+            struct_name: "EscapesConfig".to_owned(),
+            map_paths: vec!["map".to_owned()],
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "escapes.json",
+        "src/config/included.rs",
+        &StructOptions {
+            struct_name: "IncludedConfig".to_owned(),
+            map_paths: vec!["map".to_owned()],
+            header: HeaderStyle::OuterComment,
+            generated_by_comment: true,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "binary.toml",
+        "src/config/binary.rs",
+        &StructOptions {
+            struct_name: "BinaryConfig".to_owned(),
+            binary_paths: vec!["icon".to_owned(), "sprites.frames".to_owned()],
+            dynamic_loading: DynamicLoading::Never,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "includes.toml",
+        "src/config/includes.rs",
+        &StructOptions {
+            struct_name: "IncludesConfig".to_owned(),
+            resolve_includes: Some(IncludeOptions::default()),
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "diff.toml",
+        "src/config/diff.rs",
+        &StructOptions {
+            struct_name: "DiffConfig".to_owned(),
+            derived_traits: vec![
+                "Debug".to_owned(),
+                "Clone".to_owned(),
+                "PartialEq".to_owned(),
+            ],
+            generate_diff: true,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "platforms.toml",
+        "src/config/platforms.rs",
+        &StructOptions {
+            struct_name: "PlatformsConfig".to_owned(),
+            impl_default: true,
+            serde_missing_fields: MissingFields::UseConst,
+            cfg_sections: vec![
+                ("linux".to_owned(), "target_os = \"linux\"".to_owned()),
+                ("macos".to_owned(), "target_os = \"macos\"".to_owned()),
+                ("windows".to_owned(), "windows".to_owned()),
+            ]
+            .into_iter()
+            .collect(),
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "empty_arrays.toml",
+        "src/config/empty_arrays.rs",
+        &StructOptions {
+            struct_name: "EmptyArraysConfig".to_owned(),
+            empty_array_types: vec![("plugins".to_owned(), "Cow<'static, str>".to_owned())]
+                .into_iter()
+                .collect(),
+            default_empty_array_type: Some("u16".to_owned()),
+            untyped_empty_arrays: EmptyArrayBehavior::Error,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "manual_serde.toml",
+        "src/config/manual_serde.rs",
+        &StructOptions {
+            struct_name: "ManualSerdeConfig".to_owned(),
+            derived_traits: vec![
+                "Debug".to_owned(),
+                "Clone".to_owned(),
+                "PartialEq".to_owned(),
+            ],
+            field_renames: vec![("logLevel".to_owned(), "log_level".to_owned())]
+                .into_iter()
+                .collect(),
+            enum_fields: vec![(
+                "logLevel".to_owned(),
+                vec!["debug".to_owned(), "info".to_owned()],
+            )]
+            .into_iter()
+            .collect(),
+            serde_support: SerdeSupport::ManualImpl,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "field_consts.toml",
+        "src/config/field_consts.rs",
+        &StructOptions {
+            struct_name: "FieldConstsConfig".to_owned(),
+            generate_const: false,
+            generate_load_fns: false,
+            generate_field_consts: true,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "multiline.toml",
+        "src/config/multiline.rs",
+        &StructOptions {
+            struct_name: "MultilineConfig".to_owned(),
+            derived_traits: vec![
+                "Debug".to_owned(),
+                "Clone".to_owned(),
+                "PartialEq".to_owned(),
+            ],
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "sanitized.yaml",
+        "src/config/sanitized.rs",
+        &StructOptions {
+            struct_name: "SanitizedConfig".to_owned(),
+            key_sanitization: KeySanitization::SanitizeWithSerdeRename,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "settings.jsonc",
+        "src/config/settings.rs",
+        &StructOptions {
+            struct_name: "SettingsConfig".to_owned(),
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "plugins.toml",
+        "src/config/plugins.rs",
+        &StructOptions {
+            struct_name: "PluginsConfig".to_owned(),
+            derived_traits: vec![
+                "Debug".to_owned(),
+                "Clone".to_owned(),
+                "PartialEq".to_owned(),
+            ],
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "arrays.toml",
+        "src/config/arrays.rs",
+        &StructOptions {
+            struct_name: "ArraysConfig".to_owned(),
+            array_style: ArrayStyle::FixedUpTo(3),
+            derived_traits: vec![
+                "Debug".to_owned(),
+                "Clone".to_owned(),
+                "PartialEq".to_owned(),
+            ],
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "large_ints.json",
+        "src/config/large_ints.rs",
+        &StructOptions {
+            struct_name: "LargeIntsConfig".to_owned(),
+            default_int_size: IntSize::Auto,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config_from_dir(
+        "environments",
+        "src/config/environments.rs",
+        &DirOptions {
+            struct_options: StructOptions {
+                struct_name: "EnvironmentConfig".to_owned(),
+                ..StructOptions::default()
+            },
+            ..DirOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "into.toml",
+        "src/config/into.rs",
+        &StructOptions {
+            struct_name: "IntoConfig".to_owned(),
+            impl_into: Some("crate::into_tests::AppSettings".to_owned()),
+            into_conversion: IntoConversion::TryInto,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "config.json",
+        "src/config/flattened.rs",
+        &StructOptions {
+            struct_name: "FlattenedConfig".to_owned(),
+            default_int_size: IntSize::Auto,
+            flatten_paths: vec!["nested.values".to_owned()],
+            dynamic_loading: DynamicLoading::Never,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "config.json",
+        "src/config/serde_flattened.rs",
+        &StructOptions {
+            struct_name: "SerdeFlattenedConfig".to_owned(),
+            default_int_size: IntSize::Auto,
+            flatten_paths: vec!["nested.values".to_owned()],
+            flatten_style: FlattenStyle::SerdeFlatten,
+            dynamic_loading: DynamicLoading::Never,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "config.ini",
+        "src/config/ini.rs",
+        &StructOptions {
+            struct_name: "IniConfig".to_owned(),
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "root_array.json",
+        "src/config/embedded.rs",
+        &StructOptions {
+            struct_name: "EmbeddedConfig".to_owned(),
+            generate_const: false,
+            dynamic_loading: DynamicLoading::Embedded,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config_split(
+        "config.toml",
+        "src/config/split.rs",
+        "src/config/split_values.rs",
+        &StructOptions {
+            struct_name: "SplitConfig".to_owned(),
+            const_name: Some("SPLIT_CONFIG".to_owned()),
+            values_import: "use super::split::*;".to_owned(),
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "deployments.toml",
+        "src/config/deployments.rs",
+        &StructOptions {
+            struct_name: "Deployment".to_owned(),
+            root_mode: RootMode::ConstPerTopLevelTable,
+            derived_traits: vec![
+                "Debug".to_owned(),
+                "Clone".to_owned(),
+                "PartialEq".to_owned(),
+            ],
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "root_array.json",
+        "src/config/root_array.rs",
+        &StructOptions {
+            struct_name: "RootArrayConfig".to_owned(),
+            ..StructOptions::serde_default()
+        },
     )
     .unwrap();
 
@@ -34,32 +379,376 @@ fn main() {
         "src/config/ron.rs",
         &StructOptions {
             struct_name: "RonConfig".to_owned(),
+            map_type: MapType::HashMap,
+            map_paths: vec!["scores".to_owned()],
+            mixed_arrays: MixedArrayBehavior::Tuple,
+            generate_tests: true,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "config.ron",
+        "src/config/ron_fn.rs",
+        &StructOptions {
+            struct_name: "RonFnConfig".to_owned(),
+            const_name: Some("RON_FN_CONFIG".to_owned()),
+            const_fallback: ConstFallback::RuntimeFn,
+            map_type: MapType::HashMap,
+            map_paths: vec!["scores".to_owned()],
+            mixed_arrays: MixedArrayBehavior::Tuple,
+            dynamic_loading: DynamicLoading::Never,
+            generate_builder: true,
             ..StructOptions::serde_default()
         },
     )
     .unwrap();
 
+    config_struct::create_config(
+        "named.ron",
+        "src/config/named_ron.rs",
+        &StructOptions {
+            struct_name: "NamedRonConfig".to_owned(),
+            mixed_arrays: MixedArrayBehavior::Tuple,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "versions.toml",
+        "src/config/versions.rs",
+        &StructOptions {
+            struct_name: "VersionsConfig".to_owned(),
+            semver_paths: vec![
+                "min_supported_version".to_owned(),
+                "tested_versions".to_owned(),
+            ],
+            serde_support: SerdeSupport::Yes,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "owned.toml",
+        "src/config/owned.rs",
+        &StructOptions {
+            struct_name: "OwnedConfig".to_owned(),
+            map_paths: vec!["keybindings".to_owned()],
+            generate_owned_variant: true,
+            serde_support: SerdeSupport::Yes,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "optional.json",
+        "src/config/optional.rs",
+        &StructOptions {
+            struct_name: "OptionalConfig".to_owned(),
+            option_types: vec![("servers.backup".to_owned(), "u16".to_owned())]
+                .into_iter()
+                .collect(),
+            default_option_type: Some("bool".to_owned()),
+            serde_support: SerdeSupport::Yes,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "units.toml",
+        "src/config/units.rs",
+        &StructOptions {
+            struct_name: "UnitsConfig".to_owned(),
+            derived_traits: vec![
+                "Debug".to_owned(),
+                "Clone".to_owned(),
+                "PartialEq".to_owned(),
+            ],
+            newtype_paths: vec![
+                ("cache.size_mb".to_owned(), "Megabytes".to_owned()),
+                ("cache.ttl_ms".to_owned(), "Millis".to_owned()),
+                ("ratio".to_owned(), "Ratio".to_owned()),
+                ("timeout_ms".to_owned(), "Millis".to_owned()),
+            ]
+            .into_iter()
+            .collect(),
+            serde_support: SerdeSupport::Yes,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "storage.toml",
+        "src/config/storage.rs",
+        &StructOptions {
+            struct_name: "StorageConfig".to_owned(),
+            derived_traits: vec![
+                "Debug".to_owned(),
+                "Clone".to_owned(),
+                "PartialEq".to_owned(),
+            ],
+            tagged_unions: vec![
+                TaggedUnionSpec::new("backend", "kind")
+                    .variant("s3", "S3")
+                    .variant("local", "Local"),
+                TaggedUnionSpec::new("mirrors", "kind")
+                    .variant("s3", "S3")
+                    .variant("local", "Local"),
+            ],
+            serde_support: SerdeSupport::Yes,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
     config_struct::create_config(
         "config.toml",
         "src/config/toml.rs",
         &StructOptions {
             struct_name: "TomlConfig".to_owned(),
+            enum_fields: vec![(
+                "log_level".to_owned(),
+                vec!["debug".to_owned(), "info".to_owned(), "warn".to_owned()],
+            )]
+            .into_iter()
+            .collect(),
+            env_overrides: Some(EnvOptions {
+                prefix: "TOML_CONFIG".to_owned(),
+                ..EnvOptions::default()
+            }),
+            deduplicate_structs: true,
+            generate_builder: true,
+            preserve_comments: true,
+            field_int_types: vec![
+                ("color".to_owned(), IntSize::U8),
+                ("fallback_server.port".to_owned(), IntSize::U16),
+                ("servers.port".to_owned(), IntSize::U16),
+            ]
+            .into_iter()
+            .collect(),
+            generate_tests: true,
+            impl_display: DisplayStyle::SourceFormat,
+            generate_get_by_path: true,
+            generate_key_constants: true,
             ..StructOptions::serde_default()
         },
     )
     .unwrap();
 
+    config_struct::create_config(
+        "config.toml",
+        "src/config/getters.rs",
+        &StructOptions {
+            struct_name: "GettersConfig".to_owned(),
+            field_access: FieldAccess::Getters,
+            enum_fields: vec![(
+                "log_level".to_owned(),
+                vec!["debug".to_owned(), "info".to_owned(), "warn".to_owned()],
+            )]
+            .into_iter()
+            .collect(),
+            env_overrides: Some(EnvOptions {
+                prefix: "GETTERS_CONFIG".to_owned(),
+                ..EnvOptions::default()
+            }),
+            max_array_size: 2,
+            impl_default: true,
+            generate_builder: true,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "config.toml",
+        "src/config/missing_const.rs",
+        &StructOptions {
+            struct_name: "MissingConstConfig".to_owned(),
+            serde_missing_fields: MissingFields::UseConst,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "config.toml",
+        "src/config/missing_default.rs",
+        &StructOptions {
+            struct_name: "MissingDefaultConfig".to_owned(),
+            serde_missing_fields: MissingFields::UseTypeDefault,
+            impl_default: true,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "config.toml",
+        "src/config/missing_type_default.rs",
+        &StructOptions {
+            struct_name: "MissingTypeDefaultConfig".to_owned(),
+            serde_missing_fields: MissingFields::UseTypeDefault,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "config.toml",
+        "src/config/no_std.rs",
+        &StructOptions {
+            struct_name: "NoStdConfig".to_owned(),
+            no_std: true,
+            generate_load_fns: true,
+            dynamic_loading: DynamicLoading::Never,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
     config_struct::create_config(
         "config.yaml",
         "src/config/yaml.rs",
         &StructOptions {
             struct_name: "YamlConfig".to_owned(),
             const_name: Some("YAML_CONFIG".to_owned()),
+            default_int_size: IntSize::Auto,
+            mixed_arrays: MixedArrayBehavior::Tuple,
+            nested_naming: NestedNaming::PascalCase,
+            preserve_comments: true,
+            field_order: FieldOrder::SourceOrder,
+            field_renames: vec![("max-connections".to_owned(), "max_connections".to_owned())]
+                .into_iter()
+                .collect(),
+            generate_tests: true,
+            impl_display: DisplayStyle::PrettyDebug,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "config.json",
+        "src/config/static_str.rs",
+        &StructOptions {
+            struct_name: "StaticStrConfig".to_owned(),
+            default_int_size: IntSize::Auto,
+            string_type: StringType::StaticStr,
+            map_paths: vec!["nested.values".to_owned()],
+            generate_get_by_path: true,
+            serde_support: SerdeSupport::Mixed {
+                serialize: true,
+                deserialize: false,
+            },
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "anchors.yaml",
+        "src/config/anchors.rs",
+        &StructOptions {
+            struct_name: "AnchorsConfig".to_owned(),
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "dates.toml",
+        "src/config/dates.rs",
+        &StructOptions {
+            struct_name: "DatesConfig".to_owned(),
+            date_time_type: DateTimeType::ChronoDateTime,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config_module(
+        &[
+            ModuleInput::new("anchors.yaml", "ModuleAnchors"),
+            ModuleInput {
+                const_name: Some("MODULE_DATES".to_owned()),
+                ..ModuleInput::new("dates.toml", "ModuleDates")
+            },
+        ],
+        "src/config/module.rs",
+        &StructOptions::default(),
+    )
+    .unwrap();
+
+    std::fs::create_dir_all("src/config/empty").expect("Failed to create empty config dir.");
+    for (file, struct_name) in &[
+        ("blank.json", "BlankJsonConfig"),
+        ("blank.ron", "BlankRonConfig"),
+        ("blank.toml", "BlankTomlConfig"),
+        ("blank.yaml", "BlankYamlConfig"),
+        ("comments.ron", "CommentsRonConfig"),
+        ("comments.toml", "CommentsTomlConfig"),
+        ("comments.yaml", "CommentsYamlConfig"),
+    ] {
+        config_struct::create_config(
+            format!("empty/{}", file),
+            format!("src/config/empty/{}.rs", file.replace('.', "_")),
+            &StructOptions {
+                struct_name: struct_name.to_string(),
+                ..StructOptions::default()
+            },
+        )
+        .unwrap();
+    }
+
+    for (file, struct_name) in &[
+        ("tables.json", "TablesJsonConfig"),
+        ("tables.ron", "TablesRonConfig"),
+        ("tables.toml", "TablesTomlConfig"),
+        ("tables.yaml", "TablesYamlConfig"),
+    ] {
+        config_struct::create_config(
+            format!("empty/{}", file),
+            format!("src/config/empty/{}.rs", file.replace('.', "_")),
+            &StructOptions {
+                struct_name: struct_name.to_string(),
+                impl_display: DisplayStyle::SourceFormat,
+                ..StructOptions::serde_default()
+            },
+        )
+        .unwrap();
+    }
+
+    config_struct::create_config(
+        "empty/blank.toml",
+        "src/config/empty/manual_serde.rs",
+        &StructOptions {
+            struct_name: "ManualSerdeEmptyConfig".to_owned(),
+            serde_support: SerdeSupport::ManualImpl,
+            impl_display: DisplayStyle::SourceFormat,
+            generate_builder: true,
+            generate_owned_variant: true,
             ..StructOptions::serde_default()
         },
     )
     .unwrap();
 
+    config_struct::create_config(
+        "empty/blank.toml",
+        "src/config/empty/braced.rs",
+        &StructOptions {
+            struct_name: "BracedEmptyConfig".to_owned(),
+            empty_root: EmptyRootStyle::Braced,
+            ..StructOptions::default()
+        },
+    )
+    .unwrap();
+
     config_struct::create_config(
         "tests/temp/example_config.json",
         "tests/config/dynamic.rs",
@@ -67,6 +756,8 @@ fn main() {
             struct_name: "DynamicConfig".to_owned(),
             const_name: Some("DYNAMIC_CONFIG".to_owned()),
             dynamic_loading: DynamicLoading::Always,
+            // This copy is written by this script, so shouldn't trigger reruns.
+            emit_rerun_directives: false,
             ..StructOptions::serde_default()
         },
     )
@@ -79,6 +770,7 @@ fn main() {
             struct_name: "DependentConfig".to_owned(),
             const_name: Some("DEPENDENT_CONFIG".to_owned()),
             dynamic_loading: DynamicLoading::DebugOnly,
+            emit_rerun_directives: false,
             ..StructOptions::serde_default()
         },
     )
@@ -91,6 +783,48 @@ fn main() {
             struct_name: "StaticConfig".to_owned(),
             const_name: Some("STATIC_CONFIG".to_owned()),
             dynamic_loading: DynamicLoading::Never,
+            emit_rerun_directives: false,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "tests/temp/example_config.json",
+        "tests/config/env_path.rs",
+        &StructOptions {
+            struct_name: "EnvPathConfig".to_owned(),
+            const_name: Some("ENV_PATH_CONFIG".to_owned()),
+            dynamic_loading: DynamicLoading::Always,
+            dynamic_path: DynamicPath::EnvVar("TEST_CONFIG_PATH".to_owned()),
+            emit_rerun_directives: false,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "tests/temp/example_config.json",
+        "tests/config/argument_path.rs",
+        &StructOptions {
+            struct_name: "ArgumentPathConfig".to_owned(),
+            const_name: Some("ARGUMENT_PATH_CONFIG".to_owned()),
+            dynamic_loading: DynamicLoading::DebugOnly,
+            dynamic_path: DynamicPath::Argument,
+            emit_rerun_directives: false,
+            ..StructOptions::serde_default()
+        },
+    )
+    .unwrap();
+
+    config_struct::create_config(
+        "tests/temp/spaced dir/café config.json",
+        "tests/config/manifest_relative.rs",
+        &StructOptions {
+            struct_name: "ManifestRelativeConfig".to_owned(),
+            dynamic_loading: DynamicLoading::Always,
+            dynamic_path: DynamicPath::ManifestRelative,
+            emit_rerun_directives: false,
             ..StructOptions::serde_default()
         },
     )
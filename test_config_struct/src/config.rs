@@ -1,4 +1,63 @@
+pub mod anchors;
+pub mod arrays;
+pub mod binary;
+pub mod dates;
+pub mod deployments;
+pub mod diff;
+pub mod embedded;
+pub mod empty {
+    pub mod blank_json;
+    pub mod blank_ron;
+    pub mod blank_toml;
+    pub mod blank_yaml;
+    pub mod braced;
+    pub mod comments_ron;
+    pub mod comments_toml;
+    pub mod comments_yaml;
+    pub mod manual_serde;
+    pub mod tables_json;
+    pub mod tables_ron;
+    pub mod tables_toml;
+    pub mod tables_yaml;
+}
+pub mod empty_arrays;
+pub mod environments;
+pub mod escapes;
+pub mod field_consts;
+pub mod flattened;
+pub mod getters;
+pub mod includes;
+#[allow(dead_code)]
+pub mod included {
+    include!("config/included.rs");
+}
+pub mod ini;
+pub mod into;
 pub mod json;
+pub mod large_ints;
+pub mod manual_serde;
+pub mod missing_const;
+pub mod missing_default;
+pub mod missing_type_default;
+pub mod module;
+pub mod multiline;
+pub mod named_ron;
+pub mod no_std;
+pub mod optional;
+pub mod owned;
+pub mod platforms;
+pub mod plugins;
 pub mod ron;
+pub mod ron_fn;
+pub mod root_array;
+pub mod sanitized;
+pub mod serde_flattened;
+pub mod settings;
+pub mod split;
+pub mod split_values;
+pub mod static_str;
+pub mod storage;
 pub mod toml;
+pub mod units;
+pub mod versions;
 pub mod yaml;
@@ -1,7 +1,254 @@
 #![cfg(test)]
 
+// The `no_std` config imports from `alloc`.
+extern crate alloc;
+
 mod config;
 
+mod arrays_tests {
+    use crate::config::arrays::{ArraysConfig, ARRAYSCONFIG};
+
+    // Fixed-size arrays can be indexed in a const.
+    const END_X: f64 = ARRAYSCONFIG.points[1].position[0];
+
+    #[test]
+    fn test_fixed_arrays() {
+        let origin: [i64; 3] = ARRAYSCONFIG.origin;
+        assert_eq!(origin, [0, 0, 0]);
+        assert_eq!(ARRAYSCONFIG.points[0].position, [1.5, 2.0]);
+        assert_eq!(END_X, -4.0);
+    }
+
+    #[test]
+    fn test_long_arrays_are_slices() {
+        assert_eq!(ARRAYSCONFIG.palette.len(), 5);
+        assert_eq!(ARRAYSCONFIG.palette[4], "magenta");
+    }
+
+    #[test]
+    fn test_runtime_values() {
+        let toml_source = std::fs::read_to_string("arrays.toml").unwrap();
+        let conf: ArraysConfig = toml::from_str(&toml_source).unwrap();
+        assert_eq!(conf, ARRAYSCONFIG);
+    }
+}
+
+mod binary_tests {
+    use crate::config::binary::{BinaryConfig, BINARYCONFIG};
+
+    #[test]
+    fn test_decoded_bytes() {
+        assert_eq!(
+            &*BINARYCONFIG.icon,
+            &[0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]
+        );
+        assert_eq!(BINARYCONFIG.sprites.frames.len(), 2);
+        assert_eq!(&*BINARYCONFIG.sprites.frames[0], &[0x00, 0x01, 0x02]);
+        assert_eq!(&*BINARYCONFIG.sprites.frames[1], &[0xff]);
+    }
+
+    #[test]
+    fn test_load_function() {
+        let config = BinaryConfig::load();
+        assert_eq!(config.name, "Binary config");
+        assert_eq!(config.icon, BINARYCONFIG.icon);
+    }
+}
+
+mod escapes_tests {
+    use crate::config::escapes::ESCAPESCONFIG;
+
+    #[test]
+    fn test_escaped_strings() {
+        assert_eq!(ESCAPESCONFIG.quote, "say \"hi\"");
+        assert_eq!(ESCAPESCONFIG.backslash, "C:\\path\\to");
+        assert_eq!(ESCAPESCONFIG.control, "line\nnext\ttab\0end");
+        assert_eq!(ESCAPESCONFIG.emoji, "😇 ok");
+        assert_eq!(ESCAPESCONFIG.code, "\"); panic!(\"boom\"); (\"");
+    }
+
+    #[test]
+    fn test_nested_escaped_strings() {
+        assert_eq!(&*ESCAPESCONFIG.nested.list, &["\"", "\\", "\n"]);
+        assert_eq!(ESCAPESCONFIG.map[0].0, "key \"quoted\"");
+    }
+}
+
+mod included_tests {
+    use crate::config::included::INCLUDEDCONFIG;
+
+    #[test]
+    fn test_included_header() {
+        let code = std::fs::read_to_string("src/config/included.rs").unwrap();
+        let first_line = code.lines().next().unwrap();
+        assert!(first_line.starts_with("// Generated by config_struct "));
+        assert!(first_line.ends_with(" from `escapes.json`."));
+        assert!(!code.contains("#!["));
+        assert_eq!(INCLUDEDCONFIG.emoji, "😇 ok");
+    }
+}
+
+mod getters_tests {
+    use crate::config::getters::{GettersConfig, GettersConfigBuilder, LogLevel, GETTERSCONFIG};
+
+    #[test]
+    fn test_getters() {
+        assert_eq!(GETTERSCONFIG.name(), "Config name");
+        assert_eq!(GETTERSCONFIG.number(), 100);
+        assert_eq!(GETTERSCONFIG.log_level(), LogLevel::Warn);
+        assert_eq!(GETTERSCONFIG.coord(), &[-5.0, 5.0]);
+        assert_eq!(GETTERSCONFIG.words(), ["one", "two", "three"].as_ref());
+        assert_eq!(
+            GETTERSCONFIG.table().table_again().name(),
+            "OK this is just getting ridiculous"
+        );
+        assert_eq!(GETTERSCONFIG.servers()[1].port(), 2);
+    }
+
+    #[test]
+    fn test_deserialization() {
+        let toml_source = include_str!("../config.toml");
+        let conf: GettersConfig = toml::from_str(toml_source).unwrap();
+        assert_eq!(conf.name(), "Config name");
+        assert_eq!(GettersConfig::load().name(), conf.name());
+    }
+
+    #[test]
+    fn test_builder() {
+        let config = GettersConfigBuilder::from_const().with_number(5).build();
+        assert_eq!(config.number(), 5);
+        assert_eq!(GettersConfig::default().number(), 100);
+    }
+}
+
+mod ini_tests {
+    use crate::config::ini::INICONFIG;
+
+    #[test]
+    fn test_values() {
+        assert_eq!(INICONFIG.name, "Legacy service");
+        assert_eq!(INICONFIG.retries, 3);
+        assert_eq!(INICONFIG.server.host, "localhost");
+        assert_eq!(INICONFIG.server.port, 8080);
+        assert_eq!(INICONFIG.server.timeout, 2.5);
+        assert_eq!(INICONFIG.server.tls.enabled, false);
+    }
+}
+
+mod includes_tests {
+    use crate::config::includes::INCLUDESCONFIG;
+
+    #[test]
+    fn test_included_values() {
+        assert_eq!(INCLUDESCONFIG.name, "Includes config");
+        assert_eq!(INCLUDESCONFIG.database.url, "postgres://localhost");
+        assert_eq!(INCLUDESCONFIG.database.replicas.len(), 2);
+        assert_eq!(INCLUDESCONFIG.database.replicas[1].host, "b");
+        assert_eq!(INCLUDESCONFIG.database.replicas[1].port, 5433);
+    }
+}
+
+mod diff_tests {
+    use std::borrow::Cow;
+
+    use crate::config::diff::{DiffConfig, DIFFCONFIG};
+
+    #[test]
+    fn test_diff_of_equal_values_is_empty() {
+        assert!(DIFFCONFIG.diff(&DIFFCONFIG).is_empty());
+    }
+
+    #[test]
+    fn test_diff_and_apply() {
+        let mut changed: DiffConfig = DIFFCONFIG.clone();
+        changed.server.port = 9090;
+        changed.tags = Cow::Owned(vec![Cow::Borrowed("c")]);
+
+        let diff = DIFFCONFIG.diff(&changed);
+        assert_eq!(diff.name, None);
+        assert_eq!(diff.tags.as_ref().map(|tags| tags.len()), Some(1));
+        assert_eq!(
+            diff.server.as_ref().and_then(|server| server.port),
+            Some(9090)
+        );
+        assert_eq!(
+            diff.server.as_ref().and_then(|server| server.host.clone()),
+            None
+        );
+
+        let mut patched = DIFFCONFIG.clone();
+        patched.apply(&diff);
+        assert_eq!(patched, changed);
+    }
+}
+
+#[cfg(test)]
+mod field_consts_tests {
+    use crate::config::field_consts::{_Config__server, FieldConstsConfig};
+
+    #[test]
+    fn test_consts_match_the_config() {
+        let mode = match String::from("fast").as_str() {
+            FieldConstsConfig::MODE => "matched",
+            _ => "other",
+        };
+        assert_eq!(mode, "matched");
+
+        const VERSIONS: [u8; FieldConstsConfig::VERSION as usize] = [0; 5];
+        assert_eq!(VERSIONS.len(), 5);
+
+        assert_eq!(_Config__server::HOST, "localhost");
+        assert_eq!(_Config__server::PORT, 8080);
+    }
+}
+
+mod flatten_tests {
+    use crate::config::flattened::FLATTENEDCONFIG;
+    use crate::config::serde_flattened::SERDEFLATTENEDCONFIG;
+
+    #[test]
+    fn test_flattened_fields() {
+        let nested = &FLATTENEDCONFIG.nested;
+        assert_eq!(nested.name, "nested2");
+        assert_eq!(
+            (nested.values_x, nested.values_y, nested.values_z),
+            (0, 1, 2)
+        );
+    }
+
+    #[test]
+    fn test_serde_flattened_fields() {
+        let values = &SERDEFLATTENEDCONFIG.nested.values;
+        assert_eq!((values.x, values.y, values.z), (0, 1, 2));
+
+        let json = serde_json::to_value(&SERDEFLATTENEDCONFIG.nested).unwrap();
+        assert_eq!(json["x"], 0);
+        assert!(json.get("values").is_none());
+    }
+}
+
+#[cfg(test)]
+mod into_tests {
+    use std::borrow::Cow;
+
+    use crate::config::into::INTOCONFIG;
+
+    #[derive(Debug)]
+    pub struct AppSettings {
+        pub name: String,
+        pub port: u16,
+        pub tags: Vec<Cow<'static, str>>,
+    }
+
+    #[test]
+    fn test_into_target() {
+        let settings = AppSettings::from(INTOCONFIG.clone());
+        assert_eq!(settings.name, "Into config");
+        assert_eq!(settings.port, 8080);
+        assert_eq!(settings.tags, vec!["a", "b"]);
+    }
+}
+
 mod json_tests {
     use crate::config::json::{Config, CONFIG};
 
@@ -53,9 +300,485 @@ mod json_tests {
         let empty: &[()] = &[];
         assert_eq!(CONFIG.empty, empty);
     }
+
+    #[test]
+    fn test_default_impls() {
+        use crate::config::json::{_Config__array_of_structs, _Config__nested};
+
+        let config = Config::default();
+        assert_eq!(config.name, CONFIG.name);
+        assert_eq!(config.nested.values.z, 2);
+
+        assert_eq!(_Config__nested::default().name, "nested2");
+        assert_eq!(_Config__array_of_structs::default().name, "first");
+    }
+
+    #[test]
+    fn test_unified_array_elements() {
+        assert_eq!(CONFIG.servers[0].host, "a");
+        assert_eq!(CONFIG.servers[0].port, Some(1));
+        assert!(CONFIG.servers[0].tls.is_none());
+        assert_eq!(CONFIG.servers[1].port, None);
+        assert_eq!(CONFIG.servers[1].tls.as_ref().unwrap().cert, "cert.pem");
+
+        let config = Config::load();
+        assert_eq!(config.servers[1].port, None);
+    }
+
+    #[test]
+    fn test_serde_attributes() {
+        let conf: Config = serde_json::from_str(r#"{ "name": "Partial" }"#).unwrap();
+        assert_eq!(conf.name, "Partial");
+        assert_eq!(conf.number, CONFIG.number);
+
+        assert!(serde_json::from_str::<Config>(r#"{ "unknown": 1 }"#).is_err());
+    }
+}
+
+mod empty_arrays_tests {
+    use crate::config::empty_arrays::{EmptyArraysConfig, EMPTYARRAYSCONFIG};
+
+    #[test]
+    fn test_typed_empty_arrays() {
+        assert!(EMPTYARRAYSCONFIG.plugins.is_empty());
+        let ports: &[u16] = &EMPTYARRAYSCONFIG.ports;
+        assert!(ports.is_empty());
+    }
+
+    #[test]
+    fn test_runtime_elements() {
+        let toml_source = r#"
+            name = "Plugins"
+            plugins = ["lint", "format"]
+            ports = [8080]
+        "#;
+        let conf: EmptyArraysConfig = toml::from_str(toml_source).unwrap();
+        assert_eq!(conf.plugins, ["lint", "format"].as_ref());
+        assert_eq!(conf.ports, [8080].as_ref());
+    }
+}
+
+mod empty_tests {
+    use crate::config::empty::{
+        blank_json::BLANKJSONCONFIG,
+        blank_ron::BLANKRONCONFIG,
+        blank_toml::BLANKTOMLCONFIG,
+        blank_yaml::BLANKYAMLCONFIG,
+        braced::BRACEDEMPTYCONFIG,
+        comments_ron::COMMENTSRONCONFIG,
+        comments_toml::COMMENTSTOMLCONFIG,
+        comments_yaml::COMMENTSYAMLCONFIG,
+        manual_serde::{
+            ManualSerdeEmptyConfig, ManualSerdeEmptyConfigBuilder, MANUALSERDEEMPTYCONFIG,
+        },
+        tables_json::{TablesJsonConfig, TABLESJSONCONFIG},
+        tables_ron::TABLESRONCONFIG,
+        tables_toml::{TablesTomlConfig, TABLESTOMLCONFIG},
+        tables_yaml::{TablesYamlConfig, TABLESYAMLCONFIG},
+    };
+
+    #[test]
+    fn test_empty_roots() {
+        let _ = (
+            BLANKJSONCONFIG,
+            BLANKRONCONFIG,
+            BLANKTOMLCONFIG,
+            BLANKYAMLCONFIG,
+        );
+        let _ = (COMMENTSRONCONFIG, COMMENTSTOMLCONFIG, COMMENTSYAMLCONFIG);
+        let _ = BRACEDEMPTYCONFIG;
+    }
+
+    #[test]
+    fn test_manual_serde_unit_root() {
+        let conf: ManualSerdeEmptyConfig = toml::from_str("").unwrap();
+        let _ = ManualSerdeEmptyConfigBuilder::new(conf).build();
+        assert_eq!(MANUALSERDEEMPTYCONFIG.to_string(), "");
+        assert_eq!(
+            serde_json::to_string(&MANUALSERDEEMPTYCONFIG).unwrap(),
+            "{}"
+        );
+    }
+
+    #[test]
+    fn test_nested_empty_tables() {
+        assert_eq!(TABLESJSONCONFIG.server.name, "app");
+        assert_eq!(TABLESRONCONFIG.server.name, "app");
+        assert_eq!(TABLESTOMLCONFIG.server.name, "app");
+        assert_eq!(TABLESYAMLCONFIG.server.name, "app");
+
+        let json_source = std::fs::read_to_string("empty/tables.json").unwrap();
+        let conf: TablesJsonConfig = serde_json::from_str(&json_source).unwrap();
+        assert_eq!(conf.server.name, "app");
+
+        let toml_source = std::fs::read_to_string("empty/tables.toml").unwrap();
+        let conf: TablesTomlConfig = toml::from_str(&toml_source).unwrap();
+        assert_eq!(conf.server.name, "app");
+
+        let yaml_source = std::fs::read_to_string("empty/tables.yaml").unwrap();
+        let conf: TablesYamlConfig = serde_yaml::from_str(&yaml_source).unwrap();
+        assert_eq!(conf.server.name, "app");
+    }
+
+    #[test]
+    fn test_nested_empty_tables_display() {
+        let text = TABLESTOMLCONFIG.to_string();
+        assert!(text.contains("[telemetry]"), "{}", text);
+        let conf: TablesTomlConfig = toml::from_str(&text).unwrap();
+        assert_eq!(conf.server.name, "app");
+
+        let text = TABLESJSONCONFIG.to_string();
+        let conf: TablesJsonConfig = serde_json::from_str(&text).unwrap();
+        assert_eq!(conf.server.name, "app");
+
+        let text = TABLESYAMLCONFIG.to_string();
+        let conf: TablesYamlConfig = serde_yaml::from_str(&text).unwrap();
+        assert_eq!(conf.server.name, "app");
+    }
+}
+
+mod environments_tests {
+    use crate::config::environments::{for_env, DEV, PROD};
+
+    #[test]
+    fn test_consts() {
+        assert_eq!(DEV.url, "localhost");
+        assert_eq!(PROD.retries, 5);
+        assert!(DEV.tags.is_empty());
+        assert_eq!(PROD.tags, ["primary"].as_ref());
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(for_env("dev").unwrap().retries, 1);
+        assert_eq!(for_env("prod").unwrap().url, "example.com");
+        assert!(for_env("staging").is_none());
+    }
+}
+
+mod large_ints_tests {
+    use crate::config::large_ints::{LargeIntsConfig, LARGEINTSCONFIG};
+
+    #[test]
+    fn test_consts() {
+        assert_eq!(LARGEINTSCONFIG.account_id, i128::MAX);
+        assert_eq!(
+            LARGEINTSCONFIG.balance_micros,
+            -99_999_999_999_999_999_999_999
+        );
+        assert_eq!(LARGEINTSCONFIG.ledger_ids, [1, u128::MAX].as_ref());
+    }
+
+    #[test]
+    fn test_runtime_values() {
+        let json_source = std::fs::read_to_string("large_ints.json").unwrap();
+        let conf: LargeIntsConfig = serde_json::from_str(&json_source).unwrap();
+        assert_eq!(conf.account_id, LARGEINTSCONFIG.account_id);
+        assert_eq!(conf.balance_micros, LARGEINTSCONFIG.balance_micros);
+        assert_eq!(conf.ledger_ids, LARGEINTSCONFIG.ledger_ids);
+    }
+}
+
+mod platforms_tests {
+    use crate::config::platforms::{PlatformsConfig, PLATFORMSCONFIG};
+
+    #[test]
+    fn test_shared_fields() {
+        assert_eq!(PLATFORMSCONFIG.name, "Platforms");
+        assert_eq!(PlatformsConfig::default().name, "Platforms");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_linux_section() {
+        assert_eq!(PLATFORMSCONFIG.linux.shell, "sh");
+        assert_eq!(PLATFORMSCONFIG.linux.separator, ":");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_windows_section() {
+        assert_eq!(PLATFORMSCONFIG.windows.drive, "C:");
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_loading_gated_fields() {
+        let config = PlatformsConfig::load();
+        assert_eq!(config.name, "Platforms");
+    }
+}
+
+mod manual_serde_tests {
+    use crate::config::manual_serde::{LogLevel, ManualSerdeConfig, MANUALSERDECONFIG};
+
+    #[test]
+    fn test_deserialization() {
+        let toml_source = std::fs::read_to_string("manual_serde.toml").unwrap();
+        let conf: ManualSerdeConfig = toml::from_str(&toml_source).unwrap();
+        assert_eq!(conf, MANUALSERDECONFIG);
+        assert_eq!(conf.log_level, LogLevel::Info);
+        assert_eq!(conf.r#type, "service");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let json = serde_json::to_string(&MANUALSERDECONFIG).unwrap();
+        assert!(json.contains("\"logLevel\":\"info\""));
+
+        let conf: ManualSerdeConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(conf, MANUALSERDECONFIG);
+    }
+
+    #[test]
+    fn test_missing_and_unknown_keys() {
+        let json = r#"{
+            "name": "x", "logLevel": "debug", "type": "t", "tags": [], "extra": 1,
+            "server": {"host": "h", "port": 1},
+            "plugins": [{"name": "p"}]
+        }"#;
+        let conf: ManualSerdeConfig = serde_json::from_str(json).unwrap();
+        assert!(conf.plugins[0].args.is_none());
+        assert!(conf.plugins[0].target.is_none());
+
+        let error = serde_json::from_str::<ManualSerdeConfig>(r#"{"name": "x"}"#).unwrap_err();
+        assert!(error.to_string().contains("missing field"));
+    }
+}
+
+mod multiline_tests {
+    use crate::config::multiline::{MultilineConfig, MULTILINECONFIG};
+
+    #[test]
+    fn test_values() {
+        assert_eq!(
+            MULTILINECONFIG.description,
+            "A long description,\nwhich says \"#hashtag\" and \"##double\"."
+        );
+        assert_eq!(MULTILINECONFIG.windows, "first\r\nsecond\r\n");
+        assert_eq!(MULTILINECONFIG.padded, "trailing   \ntab\t\nend");
+        assert_eq!(MULTILINECONFIG.nested.lines[0], "a\nb");
+    }
+
+    #[test]
+    fn test_runtime_values() {
+        let toml_source = std::fs::read_to_string("multiline.toml").unwrap();
+        let conf: MultilineConfig = toml::from_str(&toml_source).unwrap();
+        assert_eq!(conf, MULTILINECONFIG);
+    }
+}
+
+mod plugins_tests {
+    use crate::config::plugins::{PluginsConfig, PLUGINSCONFIG};
+
+    #[test]
+    fn test_optional_fields() {
+        let plugins = &PLUGINSCONFIG.plugins;
+        assert_eq!(plugins.len(), 3);
+        assert!(plugins[0].args.is_none());
+        assert_eq!(plugins[1].args.as_deref(), Some(&["--strict".into()][..]));
+        assert!(plugins[1].target.is_none());
+
+        let target = plugins[2].target.as_ref().unwrap();
+        assert_eq!(target.host, "example.com");
+        assert_eq!(target.retry.attempts, 3);
+    }
+
+    #[test]
+    fn test_runtime_values() {
+        let toml_source = std::fs::read_to_string("plugins.toml").unwrap();
+        let conf: PluginsConfig = toml::from_str(&toml_source).unwrap();
+        assert_eq!(conf, PLUGINSCONFIG);
+    }
+}
+
+mod embedded_tests {
+    use std::borrow::Cow;
+
+    use crate::config::embedded::EmbeddedConfig;
+
+    #[test]
+    fn test_embedded_file_is_parsed_once() {
+        let configs = EmbeddedConfig::load();
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].name, "alpha");
+        assert_eq!(configs[1].port, 8081);
+
+        match (configs, EmbeddedConfig::load()) {
+            (Cow::Borrowed(first), Cow::Borrowed(second)) => assert!(std::ptr::eq(first, second)),
+            _ => panic!("Expected the embedded config to be borrowed"),
+        }
+    }
+
+    #[test]
+    fn test_try_load() {
+        let configs: Vec<EmbeddedConfig> = EmbeddedConfig::try_load().unwrap();
+        assert_eq!(configs[1].name, "beta");
+    }
+}
+
+mod deployments_tests {
+    use crate::config::deployments::{Deployment, DEV_DEPLOYMENT, PROD_DEPLOYMENT};
+
+    #[test]
+    fn test_const_per_table() {
+        let deployments: [&Deployment; 2] = [&DEV_DEPLOYMENT, &PROD_DEPLOYMENT];
+        assert_eq!(deployments[0].replicas, 1);
+        assert_eq!(deployments[1].log_level, "warn");
+        assert_eq!(DEV_DEPLOYMENT.database.host, "localhost");
+        assert_eq!(PROD_DEPLOYMENT.database.port, 6432);
+        assert_ne!(DEV_DEPLOYMENT, PROD_DEPLOYMENT);
+    }
+}
+
+mod split_tests {
+    use crate::config::{split::SplitConfig, split_values::SPLIT_CONFIG};
+
+    #[test]
+    fn test_values_are_separate() {
+        let config: &SplitConfig = &SPLIT_CONFIG;
+        assert_eq!(config.name, "Config name");
+        assert_eq!(config.number, 100);
+
+        let types = include_str!("config/split.rs");
+        assert!(!types.contains("SPLIT_CONFIG"));
+    }
+}
+
+mod macros_tests {
+    mod file {
+        config_struct_macros::config_file!(
+            "config.toml",
+            struct_name = "MacroConfig",
+            const_name = "MACRO_CONFIG",
+            serde = true,
+        );
+    }
+
+    mod source {
+        config_struct_macros::config_source!(json, r#"{ "name": "inline", "ports": [80, 443] }"#);
+    }
+
+    #[test]
+    fn test_config_file() {
+        let config: &file::MacroConfig = &file::MACRO_CONFIG;
+        assert_eq!(config.name, "Config name");
+
+        let toml_source = include_str!("../config.toml");
+        let parsed: file::MacroConfig = toml::from_str(toml_source).unwrap();
+        assert_eq!(parsed.number, file::MACRO_CONFIG.number);
+    }
+
+    #[test]
+    fn test_config_source() {
+        assert_eq!(source::CONFIG.name, "inline");
+        assert_eq!(source::CONFIG.ports[..], [80, 443]);
+    }
+}
+
+mod root_array_tests {
+    use crate::config::root_array::{RootArrayConfig, ROOTARRAYCONFIG};
+
+    #[test]
+    fn test_elements() {
+        assert_eq!(ROOTARRAYCONFIG.len(), 2);
+        assert_eq!(ROOTARRAYCONFIG[0].name, "alpha");
+        assert_eq!(ROOTARRAYCONFIG[1].port, 8081);
+        assert_eq!(ROOTARRAYCONFIG[0].tags, ["a", "b"].as_ref());
+        assert_eq!(ROOTARRAYCONFIG[1].tags, ["c"].as_ref());
+    }
+
+    #[test]
+    fn test_load_function() {
+        let configs = RootArrayConfig::load();
+        assert_eq!(configs.len(), ROOTARRAYCONFIG.len());
+        assert_eq!(configs[1].name, ROOTARRAYCONFIG[1].name);
+
+        let configs: Vec<RootArrayConfig> = RootArrayConfig::try_load().unwrap();
+        assert_eq!(configs[0].name, "alpha");
+    }
+}
+
+mod sanitized_tests {
+    use crate::config::sanitized::{SanitizedConfig, SANITIZEDCONFIG};
+
+    #[test]
+    fn test_sanitized_names() {
+        assert_eq!(SANITIZEDCONFIG._404_page, "missing.html");
+        assert_eq!(SANITIZEDCONFIG._2fa_enabled, true);
+        assert_eq!(SANITIZEDCONFIG.r#type, "admin");
+        assert_eq!(SANITIZEDCONFIG.error_pages._500_page, "error.html");
+    }
+
+    #[test]
+    fn test_load_function() {
+        let config = SanitizedConfig::load();
+        assert_eq!(config._404_page, SANITIZEDCONFIG._404_page);
+        assert_eq!(config.error_pages._500_page, "error.html");
+    }
+}
+
+mod settings_tests {
+    use crate::config::settings::{SettingsConfig, SETTINGSCONFIG};
+
+    #[test]
+    fn test_values() {
+        assert_eq!(SETTINGSCONFIG.name, "editor");
+        assert_eq!(SETTINGSCONFIG.font_size, 14);
+        assert_eq!(SETTINGSCONFIG.rulers, [80, 100].as_ref());
+        assert_eq!(SETTINGSCONFIG.theme.dark, true);
+    }
+
+    #[test]
+    fn test_load_function() {
+        let config = SettingsConfig::load();
+        assert_eq!(config.name, SETTINGSCONFIG.name);
+        assert_eq!(config.rulers, SETTINGSCONFIG.rulers);
+    }
+}
+
+mod static_str_tests {
+    use crate::config::static_str::{StaticStrConfig, StaticStrConfigValueRef, STATICSTRCONFIG};
+
+    #[test]
+    fn test_static_values() {
+        let name: &'static str = STATICSTRCONFIG.name;
+        let coord: &'static [f64] = STATICSTRCONFIG.coord;
+        assert_eq!(name, "Config name");
+        assert_eq!(coord, &[-5.0, 5.0]);
+        assert_eq!(STATICSTRCONFIG.array_of_structs[1].name, "second");
+    }
+
+    #[test]
+    fn test_get_static_values() {
+        match STATICSTRCONFIG.get("array_of_structs.1.name") {
+            Some(StaticStrConfigValueRef::Str(name)) => assert_eq!(name, "second"),
+            _ => panic!("Expected array_of_structs.1.name to be a string."),
+        }
+        match STATICSTRCONFIG.get("coord.0") {
+            Some(StaticStrConfigValueRef::F64(x)) => assert_eq!(x, -5.0),
+            _ => panic!("Expected coord.0 to be a number."),
+        }
+        assert!(STATICSTRCONFIG.get("nested.values").is_none());
+    }
+
+    #[test]
+    fn test_map_values() {
+        let values: &'static [(&'static str, i8)] = STATICSTRCONFIG.nested.values;
+        assert_eq!(values, &[("x", 0), ("y", 1), ("z", 2)]);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let conf: &StaticStrConfig = &STATICSTRCONFIG;
+        let json = serde_json::to_string(conf).unwrap();
+        assert!(json.contains(r#""name":"Config name""#));
+    }
 }
 
 mod ron_tests {
+    use std::borrow::Cow;
+
     use ron;
 
     use crate::config::ron::{RonConfig, RONCONFIG};
@@ -108,12 +831,211 @@ mod ron_tests {
         assert_eq!(RONCONFIG.objects[1].name, "Thing 2");
         assert_eq!(RONCONFIG.objects[1].index, 1);
     }
+
+    #[test]
+    fn test_tuple_values() {
+        assert_eq!(RONCONFIG.tuple, (1, Cow::Borrowed("two"), 3.5));
+        assert_eq!(RONCONFIG.points, &[(0, 0), (1, 1)][..]);
+        assert_eq!(RonConfig::load().tuple.1, "two");
+    }
+
+    #[test]
+    fn test_map_values() {
+        assert_eq!(RONCONFIG.scores.len(), 2);
+        assert_eq!(RONCONFIG.scores["alice"], 10);
+        assert_eq!(RONCONFIG.scores["bob-the-builder"], 7);
+        assert_eq!(RonConfig::load().scores["alice"], 10);
+    }
+}
+
+#[cfg(test)]
+mod named_ron_tests {
+    use crate::config::named_ron::NAMEDRONCONFIG;
+
+    #[test]
+    fn test_names_are_ignored() {
+        assert_eq!(NAMEDRONCONFIG.window.title, "Main");
+        assert_eq!(NAMEDRONCONFIG.window.size, (800, 600));
+        assert_eq!(NAMEDRONCONFIG.origin, (0.5, -0.5));
+        assert_eq!(NAMEDRONCONFIG.layers[1].name, "foreground");
+        assert_eq!(NAMEDRONCONFIG.layers[1].offset, (4, 2));
+    }
+}
+
+mod units_tests {
+    use crate::config::units::{Megabytes, Millis, Ratio, UnitsConfig, UNITSCONFIG};
+
+    #[test]
+    fn test_newtypes_hold_the_values() {
+        assert_eq!(UNITSCONFIG.timeout_ms, Millis(500));
+        assert_eq!(UNITSCONFIG.cache.ttl_ms, Millis(60000));
+        assert_eq!(UNITSCONFIG.cache.size_mb, Megabytes(64));
+        assert_eq!(UNITSCONFIG.ratio, Ratio(0.5));
+        assert!(UNITSCONFIG.timeout_ms < UNITSCONFIG.cache.ttl_ms);
+    }
+
+    #[test]
+    fn test_newtypes_are_read_as_numbers() {
+        let conf: UnitsConfig = toml::from_str(include_str!("../units.toml")).unwrap();
+        assert_eq!(conf, UNITSCONFIG);
+
+        let json = serde_json::to_value(&UNITSCONFIG).unwrap();
+        assert_eq!(json["timeout_ms"], 500);
+        assert_eq!(json["cache"]["size_mb"], 64);
+    }
+}
+
+mod storage_tests {
+    use crate::config::storage::{Backend, Mirrors, StorageConfig, STORAGECONFIG};
+
+    #[test]
+    fn test_variants_hold_the_values() {
+        match STORAGECONFIG.backend {
+            Backend::S3 {
+                ref bucket,
+                ref region,
+            } => assert_eq!((&**bucket, &**region), ("logs", "eu-west-1")),
+            Backend::Local {} => panic!("Expected the S3 backend"),
+        }
+        assert_eq!(
+            STORAGECONFIG.mirrors[0],
+            Mirrors::Local {
+                path: "/var/backups".into()
+            }
+        );
+        assert_eq!(
+            STORAGECONFIG.mirrors[1],
+            Mirrors::S3 {
+                bucket: "archive".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_variants_are_read_by_tag() {
+        let conf: StorageConfig = toml::from_str(include_str!("../storage.toml")).unwrap();
+        assert_eq!(conf, STORAGECONFIG);
+
+        let json = serde_json::to_value(&STORAGECONFIG).unwrap();
+        assert_eq!(json["backend"]["kind"], "s3");
+        assert_eq!(json["mirrors"][0]["kind"], "local");
+        assert_eq!(json["mirrors"][0]["path"], "/var/backups");
+    }
+}
+
+mod versions_tests {
+    use crate::config::versions::{VersionsConfig, VersionsConfigVersion, VERSIONSCONFIG};
+
+    #[test]
+    fn test_versions_compare_by_precedence() {
+        let tested = &VERSIONSCONFIG.tested_versions;
+        assert!(tested[0] < VERSIONSCONFIG.min_supported_version);
+        assert!(tested[1] == VERSIONSCONFIG.min_supported_version);
+        assert!(tested[2] > tested[1]);
+        assert_eq!(tested[2].minor, 10);
+        assert_eq!(tested[2].to_string(), "1.10.0+build.5");
+
+        let runtime: VersionsConfigVersion = "1.5.0-alpha.2".parse().unwrap();
+        assert!(runtime > VERSIONSCONFIG.min_supported_version);
+        assert!(runtime < "1.5.0-alpha.10".parse().unwrap());
+        assert!("1.5".parse::<VersionsConfigVersion>().is_err());
+    }
+
+    #[test]
+    fn test_versions_are_serialized_as_strings() {
+        let source = toml::to_string(&VERSIONSCONFIG).unwrap();
+        assert!(source.contains(r#"min_supported_version = "1.4.0""#));
+
+        let config: VersionsConfig = toml::from_str(&source).unwrap();
+        assert_eq!(config.tested_versions, VERSIONSCONFIG.tested_versions);
+    }
+}
+
+mod optional_tests {
+    use crate::config::optional::{OptionalConfig, OPTIONALCONFIG};
+
+    #[test]
+    fn test_options_round_trip_through_json() {
+        let main = &OPTIONALCONFIG.servers[0];
+        let fallback = &OPTIONALCONFIG.servers[1];
+        assert_eq!(main.port, Some(8080));
+        assert_eq!(main.limits.as_ref().map(|l| l.connections), Some(100));
+        assert_eq!((fallback.port, fallback.backup), (None, None));
+        assert_eq!(OPTIONALCONFIG.ports[..], [Some(80), None]);
+        assert_eq!(OPTIONALCONFIG.nothing, None::<bool>);
+
+        // Missing keys stay missing, and nulls stay null.
+        let json = serde_json::to_value(&OPTIONALCONFIG).unwrap();
+        let original: serde_json::Value =
+            serde_json::from_str(include_str!("../optional.json")).unwrap();
+        assert_eq!(json, original);
+
+        let parsed: OptionalConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.servers[1].name, "fallback");
+        assert!(parsed.servers[1].limits.is_none());
+    }
+}
+
+mod owned_tests {
+    use crate::config::owned::{OwnedConfig, OwnedConfigOwned, OWNEDCONFIG};
+
+    #[test]
+    fn test_owned_config_can_be_changed_and_converted_back() {
+        let mut owned = OwnedConfigOwned::from(OWNEDCONFIG);
+        owned.name.push_str("-dev");
+        owned.recent_files.push("c.txt".to_owned());
+        owned.plugins[1].enabled = true;
+        owned.theme.font_size = 14;
+
+        let config = OwnedConfig::from(&owned);
+        assert_eq!(config.name, "editor-dev");
+        assert_eq!(config.recent_files.len(), 3);
+        assert_eq!(config.recent_files[2], "c.txt");
+        assert!(config.plugins[1].enabled);
+        assert_eq!(config.theme.font_size, 14);
+        assert_eq!(config.keybindings, OWNEDCONFIG.keybindings);
+    }
+
+    #[test]
+    fn test_owned_config_round_trips_through_json() {
+        let owned = OwnedConfigOwned::from(OWNEDCONFIG);
+        let source = serde_json::to_string(&owned).unwrap();
+        let parsed: OwnedConfigOwned = serde_json::from_str(&source).unwrap();
+
+        assert_eq!(parsed.plugins[0].name, "spellcheck");
+        assert_eq!(parsed.window_size, [800, 600]);
+    }
+}
+
+mod ron_fn_tests {
+    use crate::config::ron_fn::{ron_fn_config, RonFnConfig, RonFnConfigBuilder};
+
+    #[test]
+    fn test_runtime_fn() {
+        let config: &'static RonFnConfig = ron_fn_config();
+        assert_eq!(config.name, "Config name");
+        assert_eq!(config.scores["alice"], 10);
+        assert!(std::ptr::eq(config, ron_fn_config()));
+    }
+
+    #[test]
+    fn test_load_functions() {
+        assert_eq!(RonFnConfig::load().scores["bob-the-builder"], 7);
+        assert_eq!(RonFnConfig::try_load().unwrap().name, "Config name");
+    }
+
+    #[test]
+    fn test_builder_from_runtime_fn() {
+        let config = RonFnConfigBuilder::from_const().with_integer(5).build();
+        assert_eq!(config.integer, 5);
+        assert_eq!(config.name, "Config name");
+    }
 }
 
 mod toml_tests {
     use toml;
 
-    use crate::config::toml::{TomlConfig, TOMLCONFIG};
+    use crate::config::toml::{LogLevel, TomlConfig, TomlConfigValueRef, TOMLCONFIG};
 
     #[test]
     fn test_declarations() {
@@ -125,6 +1047,7 @@ mod toml_tests {
         let toml_source = include_str!("../config.toml");
         let conf: TomlConfig = toml::from_str(toml_source).unwrap();
         assert_eq!(conf.name, "Config name");
+        assert_eq!(conf.log_level, LogLevel::Warn);
     }
 
     #[test]
@@ -133,6 +1056,55 @@ mod toml_tests {
         assert_eq!(config.name, TOMLCONFIG.name);
     }
 
+    #[test]
+    fn test_key_constants() {
+        use crate::config::toml::keys;
+
+        assert_eq!(keys::LOG_LEVEL, "log_level");
+        assert_eq!(keys::TABLE_TABLE_AGAIN_NAME, "table.table_again.name");
+        assert_eq!(keys::SERVERS, "servers");
+        assert!(TOMLCONFIG.get(keys::TABLE_TABLE_AGAIN_NAME).is_some());
+    }
+
+    #[test]
+    fn test_get_by_path() {
+        match TOMLCONFIG.get("servers.1.port") {
+            Some(TomlConfigValueRef::U64(port)) => assert_eq!(port, 2),
+            _ => panic!("Expected servers.1.port to be a number."),
+        }
+        match TOMLCONFIG.get("table.table_again.name") {
+            Some(TomlConfigValueRef::Str(name)) => {
+                assert_eq!(name, "OK this is just getting ridiculous")
+            }
+            _ => panic!("Expected table.table_again.name to be a string."),
+        }
+        match TOMLCONFIG.get("log_level") {
+            Some(TomlConfigValueRef::Str(level)) => assert_eq!(level, "warn"),
+            _ => panic!("Expected log_level to be a string."),
+        }
+        match TOMLCONFIG.get("table") {
+            Some(TomlConfigValueRef::Struct(table)) => {
+                let names = table.field_names().copied().collect::<Vec<_>>();
+                assert_eq!(names, ["magnitude", "name", "table_again"]);
+            }
+            _ => panic!("Expected table to be a struct."),
+        }
+        match TOMLCONFIG.get("words") {
+            Some(TomlConfigValueRef::Array(words)) => assert_eq!(words.len(), 3),
+            _ => panic!("Expected words to be an array."),
+        }
+
+        assert!(TOMLCONFIG.get("servers.2.port").is_none());
+        assert!(TOMLCONFIG.get("name.first").is_none());
+        assert!(TOMLCONFIG.get("missing").is_none());
+    }
+
+    #[test]
+    fn test_display_as_toml() {
+        let conf: TomlConfig = toml::from_str(&TOMLCONFIG.to_string()).unwrap();
+        assert_eq!(conf.name, TOMLCONFIG.name);
+    }
+
     #[test]
     fn test_simple_values() {
         assert_eq!(TOMLCONFIG.name, "Config name");
@@ -144,6 +1116,12 @@ mod toml_tests {
         assert_eq!(TOMLCONFIG.floaty, 123.456789);
     }
 
+    #[test]
+    fn test_enum_values() {
+        assert_eq!(TOMLCONFIG.log_level, LogLevel::Warn);
+        assert_ne!(TOMLCONFIG.log_level, LogLevel::Debug);
+    }
+
     #[test]
     fn test_simple_array_values() {
         assert_eq!(TOMLCONFIG.coord, [-5.0, 5.0].as_ref());
@@ -184,19 +1162,86 @@ mod toml_tests {
         let empty: &[()] = &[];
         assert_eq!(TOMLCONFIG.empty, empty);
     }
+
+    #[test]
+    fn test_deduplicated_structs() {
+        use crate::config::toml::_Config__fallback_server as Server;
+
+        let servers: [&Server; 3] = [
+            &TOMLCONFIG.fallback_server,
+            &TOMLCONFIG.servers[0],
+            &TOMLCONFIG.servers[1],
+        ];
+        assert_eq!(servers[0].host, "fallback");
+        assert_eq!(servers[2].port, 2);
+    }
+
+    #[test]
+    fn test_field_int_types() {
+        let port: u16 = TOMLCONFIG.fallback_server.port;
+        let color: &[u8] = &TOMLCONFIG.color;
+        assert_eq!(port, 8080);
+        assert_eq!(color, &[0, 64, 128, 255]);
+    }
+
+    #[test]
+    fn test_builder() {
+        use crate::config::toml::{_Config__tableBuilder, TomlConfigBuilder};
+
+        let config = TomlConfigBuilder::from_const()
+            .with_name("Built")
+            .with_number(5)
+            .with_log_level(LogLevel::Debug)
+            .with_table(_Config__tableBuilder::new(TOMLCONFIG.table.clone()).with_magnitude(10))
+            .build();
+
+        assert_eq!(config.name, "Built");
+        assert_eq!(config.number, 5);
+        assert_eq!(config.log_level, LogLevel::Debug);
+        assert_eq!(config.table.magnitude, 10);
+        assert_eq!(config.table.name, TOMLCONFIG.table.name);
+        assert_eq!(config.words, TOMLCONFIG.words);
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        std::env::set_var("TOML_CONFIG_NAME", "Overridden");
+        std::env::set_var("TOML_CONFIG_TABLE__MAGNITUDE", "5");
+        let config = TomlConfig::load_with_env_overrides().unwrap();
+        assert_eq!(config.name, "Overridden");
+        assert_eq!(config.number, TOMLCONFIG.number);
+        assert_eq!(config.table.magnitude, 5);
+
+        std::env::set_var("TOML_CONFIG_TABLE__MAGNITUDE", "not a number");
+        assert!(TomlConfig::load_with_env_overrides().is_err());
+    }
 }
 
 mod yaml_tests {
     use serde_yaml;
     use std;
 
-    use crate::config::yaml::{YamlConfig, YAML_CONFIG};
+    use crate::config::yaml::{Nested, NestedValues, YamlConfig, YAML_CONFIG};
 
     #[test]
     fn test_declarations() {
         let _conf: &YamlConfig = &YAML_CONFIG;
     }
 
+    #[test]
+    fn test_pretty_display() {
+        let text = YAML_CONFIG.to_string();
+        assert!(text.starts_with("YamlConfig {\n"));
+        assert_eq!(text, format!("{:#?}", YAML_CONFIG));
+    }
+
+    #[test]
+    fn test_nested_names() {
+        let nested: &Nested = &YAML_CONFIG.nested;
+        let values: &NestedValues = &nested.values;
+        assert_eq!(values.x, 0);
+    }
+
     #[test]
     fn test_deserialization() {
         let yaml_source = include_str!("../config.yaml");
@@ -235,9 +1280,142 @@ mod yaml_tests {
         assert_eq!(YAML_CONFIG.array_of_structs[1].n, 1);
     }
 
+    #[test]
+    fn test_renamed_fields() {
+        assert_eq!(YAML_CONFIG.max_connections, 10);
+        assert_eq!(YAML_CONFIG.r#type, "main");
+
+        let config = YamlConfig::load();
+        assert_eq!(config.max_connections, 10);
+        assert_eq!(config.r#type, "main");
+    }
+
+    #[test]
+    fn test_mixed_array_is_tuple() {
+        let (number, ref word, ref table) = YAML_CONFIG.mixed;
+        assert_eq!(number, 1);
+        assert_eq!(word, "mixed");
+        assert_eq!(table.name, "inner");
+    }
+
     #[test]
     fn test_empty_array_is_array_of_unit() {
         let empty: &[()] = &[];
         assert_eq!(YAML_CONFIG.empty, empty);
     }
 }
+
+mod anchors_tests {
+    use crate::config::anchors::ANCHORSCONFIG;
+
+    #[test]
+    fn test_merged_fields() {
+        assert_eq!(ANCHORSCONFIG.production.host, "example.com");
+        assert_eq!(ANCHORSCONFIG.production.port, 80);
+        assert_eq!(ANCHORSCONFIG.staging.host, "localhost");
+    }
+
+    #[test]
+    fn test_aliased_sequences() {
+        assert_eq!(ANCHORSCONFIG.staging.tags, ANCHORSCONFIG.defaults.tags);
+        assert_eq!(ANCHORSCONFIG.production.tags[1], "public");
+    }
+}
+
+mod missing_fields_tests {
+    use crate::config::{
+        missing_const::MissingConstConfig, missing_default::MissingDefaultConfig,
+        missing_type_default::MissingTypeDefaultConfig,
+    };
+
+    const PARTIAL_CONFIG: &str = r#"
+name = "Partial"
+
+[table]
+name = "Partial table"
+"#;
+
+    #[test]
+    fn test_missing_fields_use_const() {
+        let config: MissingConstConfig = toml::from_str(PARTIAL_CONFIG).unwrap();
+        assert_eq!(config.name, "Partial");
+        assert_eq!(config.number, 100);
+        assert_eq!(config.words, ["one", "two", "three"].as_ref());
+        assert_eq!(config.table.name, "Partial table");
+        assert_eq!(config.table.magnitude, 1000000000);
+        assert_eq!(
+            config.table.table_again.name,
+            "OK this is just getting ridiculous"
+        );
+        assert_eq!(config.servers[1].port, 2);
+    }
+
+    #[test]
+    fn test_missing_fields_use_type_default() {
+        let config: MissingDefaultConfig = toml::from_str(PARTIAL_CONFIG).unwrap();
+        assert_eq!(config.name, "Partial");
+        assert_eq!(config.number, 0);
+        assert!(config.words.is_empty());
+        assert_eq!(config.table.name, "Partial table");
+        assert_eq!(config.table.magnitude, 0);
+        assert_eq!(config.fallback_server.port, 8080);
+    }
+
+    #[test]
+    fn test_missing_tables_use_type_default() {
+        let config: MissingTypeDefaultConfig = toml::from_str(PARTIAL_CONFIG).unwrap();
+        assert_eq!(config.table.name, "Partial table");
+        assert_eq!(config.table.table_again.name, "");
+        assert_eq!(config.fallback_server.host, "");
+        assert_eq!(config.fallback_server.port, 0);
+    }
+}
+
+mod module_tests {
+    use crate::config::module::{MODULEANCHORS, MODULE_DATES};
+
+    #[test]
+    fn test_configs_share_a_module() {
+        assert_eq!(MODULEANCHORS.production.host, "example.com");
+        assert_eq!(MODULE_DATES.name, "Release dates");
+    }
+}
+
+mod no_std_tests {
+    use crate::config::no_std::{NoStdConfig, NOSTDCONFIG};
+
+    #[test]
+    fn test_load_function() {
+        let config = NoStdConfig::load();
+        assert_eq!(config.name, "Config name");
+        assert_eq!(config.table.name, NOSTDCONFIG.table.name);
+    }
+}
+
+mod dates_tests {
+    use chrono::{Datelike, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
+
+    use crate::config::dates::DATESCONFIG;
+
+    #[test]
+    fn test_offset_date_times_are_utc() {
+        let released = Utc.with_ymd_and_hms(2023, 5, 1, 10, 0, 0).unwrap();
+        let deadline = Utc.with_ymd_and_hms(2023, 5, 1, 10, 30, 0).unwrap();
+        assert_eq!(DATESCONFIG.released, released);
+        assert_eq!(DATESCONFIG.deadline, deadline);
+    }
+
+    #[test]
+    fn test_local_values() {
+        assert_eq!(DATESCONFIG.meeting.date().day(), 1);
+        assert_eq!(DATESCONFIG.meeting.time().nanosecond(), 500_000_000);
+        assert_eq!(
+            DATESCONFIG.birthday,
+            NaiveDate::from_ymd_opt(1985, 11, 27).unwrap()
+        );
+        assert_eq!(
+            DATESCONFIG.alarm,
+            NaiveTime::from_hms_opt(7, 30, 0).unwrap()
+        );
+    }
+}
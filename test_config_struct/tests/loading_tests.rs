@@ -7,6 +7,15 @@ mod dependent;
 #[path = "config/static_config.rs"]
 mod static_config;
 
+#[path = "config/env_path.rs"]
+mod env_path;
+
+#[path = "config/argument_path.rs"]
+mod argument_path;
+
+#[path = "config/manifest_relative.rs"]
+mod manifest_relative;
+
 struct Cleanup;
 
 impl Cleanup {
@@ -42,6 +51,46 @@ fn test_in_release_mode() {
     run_loading_tests("Example Config", "Example Config");
 }
 
+#[test]
+fn test_env_var_path() {
+    // Only this test reads the variable, so setting it doesn't race.
+    std::env::remove_var("TEST_CONFIG_PATH");
+    assert_eq!(env_path::EnvPathConfig::load().name, "Example Config");
+
+    std::env::set_var("TEST_CONFIG_PATH", "tests/alternate_config.json");
+    assert_eq!(env_path::EnvPathConfig::load().name, "Alternate Config");
+
+    std::env::set_var("TEST_CONFIG_PATH", "tests/missing_config.json");
+    let error = env_path::EnvPathConfig::try_load().unwrap_err();
+    assert!(matches!(error, env_path::EnvPathConfigLoadError::Io(..)));
+    assert!(error
+        .to_string()
+        .contains("from the `TEST_CONFIG_PATH` environment variable"));
+
+    std::env::remove_var("TEST_CONFIG_PATH");
+}
+
+#[test]
+fn test_manifest_relative_path() {
+    let config = manifest_relative::ManifestRelativeConfig::try_load().unwrap();
+    assert_eq!(config.name, "Example Config");
+}
+
+#[test]
+fn test_argument_path() {
+    let config =
+        argument_path::ArgumentPathConfig::load_from("tests/alternate_config.json").unwrap();
+    let expected = if cfg!(debug_assertions) {
+        "Alternate Config"
+    } else {
+        "Example Config"
+    };
+    assert_eq!(config.name, expected);
+
+    let result = argument_path::ArgumentPathConfig::try_load_from("tests/missing_config.json");
+    assert_eq!(result.is_ok(), !cfg!(debug_assertions));
+}
+
 fn run_loading_tests(dependent_renamed: &str, dependent_alternate: &str) {
     let _cleanup = Cleanup::new();
 
@@ -81,4 +130,19 @@ fn run_loading_tests(dependent_renamed: &str, dependent_alternate: &str) {
     assert_eq!(dynamic_conf.name, "Alternate Config");
     assert_eq!(dependent_conf.name, dependent_alternate);
     assert_eq!(static_conf.name, "Example Config");
+
+    // Test loading an invalid file
+    std::fs::write("tests/temp/example_config.json", b"{ not json").unwrap();
+
+    let dynamic_error = dynamic::DynamicConfig::try_load().unwrap_err();
+    let dependent_result = dependent::DependentConfig::try_load();
+    let static_conf = static_config::StaticConfig::try_load().unwrap();
+
+    assert!(matches!(
+        dynamic_error,
+        dynamic::DynamicConfigLoadError::Deserialize(..)
+    ));
+    assert!(dynamic_error.to_string().contains("example_config.json"));
+    assert_eq!(dependent_result.is_ok(), !cfg!(debug_assertions));
+    assert_eq!(static_conf.name, "Example Config");
 }